@@ -0,0 +1,162 @@
+//! Helpers for small fixed-size message bodies (IDs, status codes, indices and combinations
+//! of these), written in network byte order with bounds checking.
+//!
+//! `put_*` functions return [`Error::BufferLength`] where the output buffer is too short,
+//! `get_*` functions return [`Error::InvalidPageLength`] for truncated bodies and return
+//! the remainder of the body alongside the decoded value.
+
+use core::convert::TryFrom;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::error::Error;
+use crate::types::{Id, ID_LEN};
+
+/// Fixed-size body field
+pub trait Field: Sized {
+    /// Encoded field length
+    const LEN: usize;
+
+    /// Write the field to a buffer of at least [`Field::LEN`] bytes
+    fn write(&self, buff: &mut [u8]);
+
+    /// Read the field from a buffer of at least [`Field::LEN`] bytes
+    fn read(buff: &[u8]) -> Self;
+}
+
+impl Field for u16 {
+    const LEN: usize = 2;
+
+    fn write(&self, buff: &mut [u8]) {
+        NetworkEndian::write_u16(buff, *self)
+    }
+
+    fn read(buff: &[u8]) -> Self {
+        NetworkEndian::read_u16(buff)
+    }
+}
+
+impl Field for u32 {
+    const LEN: usize = 4;
+
+    fn write(&self, buff: &mut [u8]) {
+        NetworkEndian::write_u32(buff, *self)
+    }
+
+    fn read(buff: &[u8]) -> Self {
+        NetworkEndian::read_u32(buff)
+    }
+}
+
+impl Field for Id {
+    const LEN: usize = ID_LEN;
+
+    fn write(&self, buff: &mut [u8]) {
+        buff[..ID_LEN].copy_from_slice(self)
+    }
+
+    fn read(buff: &[u8]) -> Self {
+        Id::try_from(&buff[..ID_LEN]).unwrap()
+    }
+}
+
+macro_rules! impl_field_tuple {
+    ($($n:ident: $i:tt),+) => {
+        impl <$($n: Field),+> Field for ($($n),+) {
+            const LEN: usize = 0 $(+ $n::LEN)+;
+
+            fn write(&self, buff: &mut [u8]) {
+                let mut _n = 0;
+                $( self.$i.write(&mut buff[_n..]); _n += $n::LEN; )+
+            }
+
+            fn read(buff: &[u8]) -> Self {
+                let mut _n = 0;
+                ($( { let v = $n::read(&buff[_n..]); _n += $n::LEN; v } ),+)
+            }
+        }
+    };
+}
+
+impl_field_tuple!(A: 0, B: 1);
+impl_field_tuple!(A: 0, B: 1, C: 2);
+
+/// Write a field to the start of the buffer, returning the encoded length
+pub fn put<F: Field>(buff: &mut [u8], v: &F) -> Result<usize, Error> {
+    if buff.len() < F::LEN {
+        return Err(Error::BufferLength);
+    }
+
+    v.write(&mut buff[..F::LEN]);
+
+    Ok(F::LEN)
+}
+
+/// Read a field from the start of the body, returning the field and the remaining body
+pub fn get<F: Field>(body: &[u8]) -> Result<(F, &[u8]), Error> {
+    if body.len() < F::LEN {
+        debug!("Body length {} shorter than field length {}", body.len(), F::LEN);
+        return Err(Error::InvalidPageLength);
+    }
+
+    Ok((F::read(&body[..F::LEN]), &body[F::LEN..]))
+}
+
+/// Write a u16 to the start of the buffer
+pub fn put_u16(buff: &mut [u8], v: u16) -> Result<usize, Error> {
+    put(buff, &v)
+}
+
+/// Read a u16 from the start of the body
+pub fn get_u16(body: &[u8]) -> Result<(u16, &[u8]), Error> {
+    get(body)
+}
+
+/// Write a u32 to the start of the buffer
+pub fn put_u32(buff: &mut [u8], v: u32) -> Result<usize, Error> {
+    put(buff, &v)
+}
+
+/// Read a u32 from the start of the body
+pub fn get_u32(body: &[u8]) -> Result<(u32, &[u8]), Error> {
+    get(body)
+}
+
+/// Write an ID to the start of the buffer
+pub fn put_id(buff: &mut [u8], id: &Id) -> Result<usize, Error> {
+    put(buff, id)
+}
+
+/// Read an ID from the start of the body
+pub fn get_id(body: &[u8]) -> Result<(Id, &[u8]), Error> {
+    get(body)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn body_fields() {
+        let id = Id::from([0xab; ID_LEN]);
+        let mut buff = [0u8; 64];
+
+        // Fields round-trip in network byte order
+        assert_eq!(put_u32(&mut buff, 0x01020304), Ok(4));
+        assert_eq!(&buff[..4], &[1, 2, 3, 4]);
+        assert_eq!(get_u32(&buff[..6]), Ok((0x01020304, &buff[4..6])));
+
+        assert_eq!(put_id(&mut buff, &id), Ok(ID_LEN));
+        assert_eq!(get_id(&buff[..ID_LEN]), Ok((id.clone(), &[][..])));
+
+        // Tuples are encoded in order
+        let v = (id.clone(), 7u16, 9u32);
+        assert_eq!(put(&mut buff, &v), Ok(ID_LEN + 6));
+        assert_eq!(get::<(Id, u16, u32)>(&buff[..ID_LEN + 6]), Ok((v, &[][..])));
+
+        // Short buffers and bodies are rejected
+        assert_eq!(put_id(&mut buff[..ID_LEN - 1], &id), Err(Error::BufferLength));
+        assert_eq!(get_id(&buff[..ID_LEN - 1]), Err(Error::InvalidPageLength));
+        assert_eq!(get_u16(&buff[..1]), Err(Error::InvalidPageLength));
+    }
+}
@@ -0,0 +1,129 @@
+//! CBOR body codec, allowing `serde` types to be used as typed page and data bodies.
+//!
+//! Wrap a `Serialize`/`Deserialize` type in [`Cbor`] to use it as a [`PageBody`] or [`DataBody`],
+//! for example `Service<Cbor<Info>>` or `DataOptions<Cbor<Reading>>`, with received bodies decoded
+//! via [`Container::body`](crate::wire::Container::body).
+
+use core::fmt::Debug;
+use core::ops::{Deref, DerefMut};
+
+use alloc::vec::Vec;
+
+use encdec::{Encode, Decode};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::error::Error;
+
+use super::{PageBody, DataBody};
+
+/// Typed body with CBOR wire representation
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Cbor<T>(pub T);
+
+impl<T> Cbor<T> {
+    /// Fetch the inner body
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Cbor<T> {
+    fn from(v: T) -> Self {
+        Self(v)
+    }
+}
+
+impl<T> Deref for Cbor<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Cbor<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<T: Serialize + Debug> Encode for Cbor<T> {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        let mut buff = Vec::new();
+        ciborium::ser::into_writer(&self.0, &mut buff).map_err(|e| {
+            error!("Failed to encode CBOR body: {:?}", e);
+            Error::EncodeFailed
+        })?;
+
+        Ok(buff.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buff.len();
+        let mut w = buff;
+
+        ciborium::ser::into_writer(&self.0, &mut w).map_err(|e| match e {
+            ciborium::ser::Error::Io(_) => Error::BufferLength,
+            _ => {
+                error!("Failed to encode CBOR body: {:?}", e);
+                Error::EncodeFailed
+            },
+        })?;
+
+        Ok(len - w.len())
+    }
+}
+
+impl<'a, T: DeserializeOwned + Debug> Decode<'a> for Cbor<T> {
+    type Output = Cbor<T>;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let mut r = buff;
+
+        let v = ciborium::de::from_reader(&mut r).map_err(|e| {
+            debug!("Failed to decode CBOR body: {:?}", e);
+            Error::EncodeFailed
+        })?;
+
+        Ok((Cbor(v), buff.len() - r.len()))
+    }
+}
+
+impl<T: Serialize + Debug> PageBody for Cbor<T> {}
+
+impl<T: Serialize + Debug> DataBody for Cbor<T> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::prelude::*;
+    use crate::service::{DataOptions, Publisher};
+
+    #[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+    struct Reading {
+        sensor: String,
+        value: i32,
+    }
+
+    #[test]
+    fn typed_cbor_bodies() {
+        let r = Cbor(Reading{ sensor: "temp".into(), value: 21 });
+
+        let mut buff = [0u8; 64];
+        let n = r.encode(&mut buff).unwrap();
+        assert_eq!(n, r.encode_len().unwrap());
+        assert_eq!(Cbor::<Reading>::decode(&buff[..n]).unwrap(), (r.clone(), n));
+        assert_eq!(r.encode(&mut buff[..4]), Err(Error::BufferLength));
+
+        // Typed bodies decode from published objects
+        let mut svc = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let opts = DataOptions{ body: Some(r.clone()), ..Default::default() };
+        let (_n, d) = svc.publish_data(opts, vec![0u8; 1024]).unwrap();
+
+        assert_eq!(d.body::<Cbor<Reading>>(), Ok(r));
+        assert_eq!(d.body::<Cbor<u64>>(), Err(Error::EncodeFailed));
+    }
+}
@@ -10,6 +10,13 @@ mod header;
 use encdec::EncDec;
 pub use header::*;
 
+pub mod body;
+
+#[cfg(feature = "cbor")]
+pub mod cbor;
+#[cfg(feature = "cbor")]
+pub use cbor::Cbor;
+
 use crate::options::Options;
 use crate::types::{ImmutableData, Id, ID_LEN};
 use crate::error::Error;
@@ -24,7 +31,8 @@ pub trait Message<'a>: Decode<'a> + Encode {
     const KIND: u16;
 }
 
-/// Marker trait for page body types
+/// Marker trait for page body types, implement this for `encdec` types to use them as typed
+/// page bodies, or see [`Cbor`] (with the `cbor` feature) for `serde` types
 pub trait PageBody: Encode {}
 
 impl PageBody for &[u8] {}
@@ -36,7 +44,7 @@ impl PageBody for Id {}
 
 impl PageBody for Empty {}
 
-/// Marker trait for data body types
+/// Marker trait for data body types, see [`PageBody`]
 pub trait DataBody: Encode {}
 
 impl DataBody for &[u8] {}
@@ -0,0 +1,63 @@
+//! Delegation helpers, for creating and verifying [`Delegation`] certificates authorising a
+//! delegate key to publish objects on behalf of a service.
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::error::Error;
+use crate::options::{Delegation, DelegationScope};
+use crate::types::*;
+
+use super::{Crypto, PubKey as _};
+
+/// Context prefix for delegation signatures, separating these from object signatures
+const DELEGATION_CTX: [u8; 8] = *b"dsf-dlgt";
+
+/// Signed delegation data length (context, issuer, delegate, scope and expiry)
+const DELEGATION_DATA_LEN: usize = DELEGATION_CTX.len() + ID_LEN + PUBLIC_KEY_LEN + 4 + 8;
+
+/// Create a delegation from the issuing service to the provided delegate key, signed with the service private key
+pub fn sign_delegation(
+    issuer: &Id,
+    private_key: &PrivateKey,
+    delegate: PublicKey,
+    scope: DelegationScope,
+    expiry: DateTime,
+) -> Result<Delegation, Error> {
+    let data = delegation_data(issuer, &delegate, scope, expiry);
+    let signature = Crypto::pk_sign(private_key, &data).map_err(|_| Error::CryptoError)?;
+
+    Ok(Delegation { issuer: issuer.clone(), delegate, scope, expiry, signature })
+}
+
+/// Verify a delegation signature using the issuing service public key.
+///
+/// Returns [`Error::KeyIdMismatch`] where the key does not match the issuer ID, and
+/// [`Error::InvalidDelegation`] for invalid signatures. Note this does not check delegation expiry.
+pub fn verify_delegation(delegation: &Delegation, issuer_key: &PublicKey) -> Result<(), Error> {
+    if Id::from_public_key(issuer_key) != delegation.issuer {
+        return Err(Error::KeyIdMismatch);
+    }
+
+    let data = delegation_data(&delegation.issuer, &delegation.delegate, delegation.scope, delegation.expiry);
+
+    match Crypto::pk_verify(issuer_key, &delegation.signature, &data) {
+        Ok(true) => Ok(()),
+        _ => Err(Error::InvalidDelegation),
+    }
+}
+
+/// Build signed delegation data
+fn delegation_data(issuer: &Id, delegate: &PublicKey, scope: DelegationScope, expiry: DateTime) -> [u8; DELEGATION_DATA_LEN] {
+    let mut d = [0u8; DELEGATION_DATA_LEN];
+    let mut n = 0;
+
+    for p in [&DELEGATION_CTX[..], issuer.as_ref(), delegate.as_ref()] {
+        d[n..][..p.len()].copy_from_slice(p);
+        n += p.len();
+    }
+
+    NetworkEndian::write_u32(&mut d[n..], scope.bits());
+    NetworkEndian::write_u64(&mut d[n + 4..], expiry.as_secs());
+
+    d
+}
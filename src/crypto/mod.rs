@@ -8,21 +8,56 @@ use core::convert::TryFrom;
 use blake2::{Blake2b512};
 use sha2::{Sha512Trunc256, digest::FixedOutput};
 
+use crate::error::Error;
 use crate::prelude::Keys;
 use crate::types::*;
 
 pub mod native;
 
+pub mod rng;
+pub use rng::{set_rng, reset_rng, fill_random, RngSource};
+#[cfg(any(test, feature = "test_utils"))]
+pub use rng::set_deterministic_rng;
+#[cfg(all(feature = "std", any(test, feature = "test_utils")))]
+pub use rng::with_deterministic_rng;
+
+pub mod delegation;
+pub use delegation::{sign_delegation, verify_delegation};
+
 
 pub type Crypto = native::RustCrypto;
 
-/// Signer trait, used for generating page signatures
+pub use native::PreparedKey;
+
+/// Signer trait, used for generating page signatures.
+///
+/// This allows signing keys to be held externally (for example, in an HSM, secure element,
+/// or remote signer), see [`Builder::sign_with`](crate::wire::Builder::sign_with).
 pub trait Signer {
-    type Error;
+    type Error: Debug;
 
     fn sign(&mut self, id: &Id, data: &[u8]) -> Result<Signature, Self::Error>;
 }
 
+/// Sign using a private key held in memory
+impl Signer for PrivateKey {
+    type Error = Error;
+
+    fn sign(&mut self, _id: &Id, data: &[u8]) -> Result<Signature, Self::Error> {
+        Crypto::pk_sign(self, data).map_err(|_| Error::CryptoError)
+    }
+}
+
+/// Async signer trait, used for generating page signatures with async signing backends
+/// (for example, remote signers), see [`Builder::sign_with_async`](crate::wire::Builder::sign_with_async).
+#[cfg(feature = "async")]
+#[async_trait::async_trait]
+pub trait AsyncSigner {
+    type Error: Debug;
+
+    async fn sign(&mut self, id: &Id, data: &[u8]) -> Result<Signature, Self::Error>;
+}
+
 /// Validator trait, used for checking page signatures.
 /// This should return Ok(true) for success, Ok(false) for an unknown ID:Key pair
 /// or an error on a signature mismatch
@@ -94,9 +129,26 @@ pub trait Hash {
         Ok(CryptoHash::try_from(h.deref()).unwrap())
     }
 
+    /// Hash a sequence of data segments via [Sha512Trunc256]
+    fn hash_iter<'a>(parts: impl IntoIterator<Item=&'a [u8]>) -> Result<CryptoHash, ()> {
+        use sha2::Digest;
+
+        let mut h = Sha512Trunc256::new();
+        for p in parts {
+            h.input(p);
+        }
+
+        let d = h.fixed_result();
+        Ok(CryptoHash::try_from(d.deref()).unwrap())
+    }
+
     /// Derive hash via [Blake2b512]
     fn kdf(seed: &[u8]) -> Result<CryptoHash, ()>;
 
+    /// Derive an indexed subkey from a secret key, used for per-option encryption
+    /// (see [`Keys::option_key`](crate::keys::Keys::option_key))
+    fn kdf_subkey(key: &[u8], index: u64) -> Result<CryptoHash, ()>;
+
     /// Hasher to generate TIDs for a given ID and keyset using [Hash::kdf]
     fn hash_tid(id: Id, keys: &Keys, o: impl Queryable) -> Result<CryptoHash, ()> {
         use sha2::Digest;
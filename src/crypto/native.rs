@@ -1,63 +1,103 @@
 
 
-use core::convert::{TryFrom, TryInto};
 use core::ops::Deref;
 
 use ed25519_dalek::{Keypair, Signer, Verifier};
-use chacha20poly1305::ChaCha20Poly1305;
 use chacha20poly1305::aead::{NewAead, AeadInPlace};
 
-use rand_core_0_6::{OsRng, RngCore as _};
 use sha2::Digest;
 
 use crate::types::{*};
-use super::{PubKey, SecKey, Hash};
+use super::{PubKey, SecKey, Hash, rng::fill_random};
 
 pub struct RustCrypto;
 
-/// Hacks to run two versions of rand_core because ed25519_dalek expects 0.5.x
-struct RandHelper(OsRng);
+/// Pre-parsed public key for repeated signature verification,
+/// avoiding public key decompression on each verify
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreparedKey(ed25519_dalek::PublicKey);
 
-impl rand_core_0_5::RngCore for RandHelper {
-    fn next_u32(&mut self) -> u32 {
-        self.0.next_u32()
+impl RustCrypto {
+    /// Set the RNG source used for key and nonce generation (see [`set_rng`](super::set_rng))
+    pub fn set_rng(source: &'static (dyn super::RngSource + Sync)) {
+        super::set_rng(source)
     }
 
-    fn next_u64(&mut self) -> u64 {
-        self.0.next_u64()
+    /// Reset to the default (OS) RNG (see [`reset_rng`](super::reset_rng))
+    pub fn reset_rng() {
+        super::reset_rng()
     }
 
-    fn fill_bytes(&mut self, dest: &mut [u8]) {
-        self.0.fill_bytes(dest)
+    /// Parse a public key for use with [`RustCrypto::pk_verify_prepared`]
+    pub fn pk_prepare(public_key: &PublicKey) -> Result<PreparedKey, ()> {
+        ed25519_dalek::PublicKey::from_bytes(public_key)
+            .map(PreparedKey)
+            .map_err(|_e| () )
     }
 
-    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core_0_5::Error> {
-        match self.0.try_fill_bytes(dest) {
-            Ok(()) => Ok(()),
-            Err(e) => {
-                let code = e.code().unwrap_or(rand_core_0_5::Error::CUSTOM_START.try_into().unwrap());
-                Err(rand_core_0_5::Error::from(code))
-            }
+    /// Verify a signature using a pre-parsed public key
+    pub fn pk_verify_prepared(public_key: &PreparedKey, signature: &Signature, data: &[u8]) -> Result<bool, ()> {
+        let signature = ed25519_dalek::Signature::from_bytes(signature).map_err(|_e| () )?;
+
+        match public_key.0.verify_strict(data, &signature) {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
         }
     }
-}
 
-impl rand_core_0_5::CryptoRng for RandHelper {}
+    /// Verify a batch of `(public key, signature, data)` signatures, returning true only where all are valid.
+    ///
+    /// Batch verification does not identify invalid signatures, on failure signatures should be
+    /// verified individually with [`PubKey::pk_verify`]. As with `pk_verify` small-order keys and
+    /// signature R values are rejected, however batch verification uses the cofactored equation
+    /// so may accept signatures (with crafted torsion components) rejected by individual verification.
+    #[cfg(feature = "alloc")]
+    pub fn pk_verify_batch(items: &[(&PublicKey, &Signature, &[u8])]) -> Result<bool, ()> {
+        use curve25519_dalek::edwards::CompressedEdwardsY;
+
+        let mut public_keys = alloc::vec::Vec::with_capacity(items.len());
+        let mut signatures = alloc::vec::Vec::with_capacity(items.len());
+        let mut messages = alloc::vec::Vec::with_capacity(items.len());
+
+        for (public_key, signature, data) in items {
+            // Reject small-order points, matching `verify_strict`
+            for p in [&public_key[..], &signature[..32]] {
+                match CompressedEdwardsY::from_slice(p).decompress() {
+                    Some(p) if !p.is_small_order() => (),
+                    _ => return Ok(false),
+                }
+            }
 
+            public_keys.push(ed25519_dalek::PublicKey::from_bytes(public_key).map_err(|_e| () )?);
+            signatures.push(ed25519_dalek::Signature::from_bytes(signature).map_err(|_e| () )?);
+            messages.push(*data);
+        }
+
+        match ed25519_dalek::verify_batch(&messages, &signatures, &public_keys) {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+}
 
 impl PubKey for RustCrypto {
     type Error = ();
 
     fn new_pk() -> Result<(PublicKey, PrivateKey), Self::Error> {
 
-        let keys = ed25519_dalek::Keypair::generate(&mut RandHelper(OsRng));
+        // Generate secret key from the configured RNG, returning errors rather than panicking
+        let mut seed = [0u8; ed25519_dalek::SECRET_KEY_LENGTH];
+        fill_random(&mut seed)?;
+
+        let secret = ed25519_dalek::SecretKey::from_bytes(&seed).map_err(|_| () )?;
+        let public = ed25519_dalek::PublicKey::from(&secret);
 
-        let public_key = PublicKey::from(keys.public.to_bytes());
+        let public_key = PublicKey::from(public.to_bytes());
 
         // Our private keys contain both the public and private components
         let mut private_key = PrivateKey::default();
-        private_key[..32].copy_from_slice(&keys.secret.to_bytes());
-        private_key[32..].copy_from_slice(&keys.public.to_bytes());
+        private_key[..32].copy_from_slice(&secret.to_bytes());
+        private_key[32..].copy_from_slice(&public.to_bytes());
 
         Ok((public_key, private_key))
     }
@@ -115,10 +155,8 @@ impl SecKey for RustCrypto {
     type Error = ();
 
     fn new_sk() -> Result<SecretKey, Self::Error> {
-        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
-
-        let secret_key = SecretKey::try_from(key.deref())
-            .map_err(|_| () )?;
+        let mut secret_key = SecretKey::default();
+        fill_random(&mut secret_key)?;
 
         Ok(secret_key)
     }
@@ -132,7 +170,7 @@ impl SecKey for RustCrypto {
         let cipher = XChaCha20Poly1305::new(secret_key);
         
         let mut nonce = XNonce::default();
-        OsRng.fill_bytes(&mut nonce);
+        fill_random(&mut nonce)?;
 
         let tag = cipher.encrypt_in_place_detached(&nonce, assoc.unwrap_or(&[]), message)
             .map_err(|e| {
@@ -209,6 +247,19 @@ impl Hash for RustCrypto {
     
         Ok(CryptoHash::from(derived.as_ref()))
     }
+
+    fn kdf_subkey(key: &[u8], index: u64) -> Result<CryptoHash, ()> {
+        use blake2::digest::{FixedOutput, consts::U32};
+
+        let salt = index.to_le_bytes();
+
+        let inst = blake2::Blake2bMac::<U32>::new_with_salt_and_personal(key, &salt, &DSF_SUBKEY_KDF_CTX)
+            .map_err(|_| () )?;
+
+        let derived = inst.finalize_fixed();
+
+        Ok(CryptoHash::from(derived.as_ref()))
+    }
 }
 
 /// Creates a curve25519 key from an ed25519 public key.
@@ -256,6 +307,8 @@ fn pri_ed26619_to_x25519(sk: &ed25519_dalek::SecretKey) -> Result<crypto_kx::Sec
 const DSF_NS_KDF_IDX: u64 = 1;
 /// Blake2b KDF context, randomly generated
 const DSF_NS_KDF_CTX: [u8; 8] = [208, 217, 2, 27, 15, 253, 70, 121];
+/// Blake2b subkey KDF context, randomly generated
+const DSF_SUBKEY_KDF_CTX: [u8; 8] = [94, 29, 156, 71, 162, 3, 248, 107];
 
 
 #[cfg(test)]
@@ -281,6 +334,20 @@ mod test {
         assert_eq!(false, valid);
     }
 
+    #[test]
+    fn test_pk_verify_prepared() {
+        let (public, private) = RustCrypto::new_pk().expect("Error generating public/private keypair");
+        let mut data = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        let signature = RustCrypto::pk_sign(&private, &data).expect("Error generating signature");
+        let prepared = RustCrypto::pk_prepare(&public).expect("Error parsing public key");
+
+        assert_eq!(RustCrypto::pk_verify_prepared(&prepared, &signature, &data), Ok(true));
+
+        data[3] = 100;
+        assert_eq!(RustCrypto::pk_verify_prepared(&prepared, &signature, &data), Ok(false));
+    }
+
     #[test]
     fn test_sk_encrypt_decrypt() {
         let secret = RustCrypto::new_sk().expect("Error generating secret key");
@@ -318,6 +385,21 @@ mod test {
         })
     }
 
+    #[bench]
+    fn bench_pk_verify_prepared(b: &mut Bencher) {
+        let (public, private) = RustCrypto::new_pk().expect("Error generating public/private keypair");
+        let data = [0xabu8; 256];
+
+        let signature = RustCrypto::pk_sign(&private, &data).expect("Error generating signature");
+        let prepared = RustCrypto::pk_prepare(&public).expect("Error parsing public key");
+
+        b.iter(|| {
+            let valid =
+                RustCrypto::pk_verify_prepared(&prepared, &signature, &data).expect("Error validating signature");
+            assert_eq!(true, valid);
+        })
+    }
+
     #[bench]
     fn bench_pk_sk_convert(b: &mut Bencher) {
         let (pub_key_a, pri_key_a) = RustCrypto::new_pk().expect("Error generating public/private keypair");
@@ -0,0 +1,288 @@
+//! Random number source used for key and nonce generation.
+//!
+//! This defaults to the operating system RNG ([`rand_core_0_6::OsRng`]), platforms without
+//! one (or with a hardware TRNG) can provide their own [`RngSource`] using [`set_rng`].
+//!
+//! For reproducible tests, [`with_deterministic_rng`] overrides the RNG for the current thread only.
+
+#[cfg(any(test, feature = "test_utils"))]
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use core::num::NonZeroU32;
+
+use rand_core_0_6::{OsRng, RngCore, CryptoRng};
+
+/// RNG source, implemented by applications providing their own generator.
+///
+/// This is implemented for functions (and closures) filling the provided buffer,
+/// stateful sources (for example, a seeded CSPRNG) may use interior mutability.
+pub trait RngSource {
+    /// Fill the provided buffer with cryptographically secure random data
+    fn fill(&self, buff: &mut [u8]) -> Result<(), ()>;
+}
+
+impl <F: Fn(&mut [u8]) -> Result<(), ()>> RngSource for F {
+    fn fill(&self, buff: &mut [u8]) -> Result<(), ()> {
+        (self)(buff)
+    }
+}
+
+/// Injected RNG source, `None` when using the default
+static SOURCE: spin::RwLock<Option<&'static (dyn RngSource + Sync)>> = spin::RwLock::new(None);
+
+/// Set the RNG source used for key and nonce generation.
+///
+/// The provided source MUST be cryptographically secure, and should be set prior to use
+/// (sources are replaced under a lock, so this must not be called from within [`RngSource::fill`]).
+pub fn set_rng(source: &'static (dyn RngSource + Sync)) {
+    *SOURCE.write() = Some(source);
+}
+
+/// Reset to the default (OS) RNG
+pub fn reset_rng() {
+    *SOURCE.write() = None;
+}
+
+/// Deterministic RNG state, see [`set_deterministic_rng`]
+#[cfg(any(test, feature = "test_utils"))]
+static SEED: AtomicU64 = AtomicU64::new(0);
+
+/// Use a deterministic (seeded) RNG for key and nonce generation, for reproducible test vectors.
+///
+/// This is NOT secure and MUST NOT be used outside of testing, call [`reset_rng`] to restore the default.
+#[cfg(any(test, feature = "test_utils"))]
+pub fn set_deterministic_rng(seed: u64) {
+    SEED.store(seed, Ordering::SeqCst);
+    set_rng(&deterministic_rng);
+}
+
+/// SplitMix64 state increment
+#[cfg(any(test, feature = "test_utils"))]
+const SPLITMIX_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// SplitMix64 output function over the (incremented) generator state
+#[cfg(any(test, feature = "test_utils"))]
+fn splitmix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// SplitMix64 generator over the deterministic RNG state
+#[cfg(any(test, feature = "test_utils"))]
+fn deterministic_rng(buff: &mut [u8]) -> Result<(), ()> {
+    for c in buff.chunks_mut(8) {
+        let z = splitmix64(SEED.fetch_add(SPLITMIX_GAMMA, Ordering::SeqCst).wrapping_add(SPLITMIX_GAMMA));
+        c.copy_from_slice(&z.to_le_bytes()[..c.len()]);
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "std", any(test, feature = "test_utils")))]
+std::thread_local! {
+    /// Scoped deterministic RNG state for the current thread, see [`with_deterministic_rng`]
+    static SCOPED_SEED: core::cell::Cell<Option<u64>> = core::cell::Cell::new(None);
+}
+
+/// Run the provided closure using a deterministic (seeded) RNG for key and nonce generation on the
+/// current thread only, restoring the previous RNG on return. Other threads continue to use the
+/// configured RNG.
+///
+/// This is NOT secure and MUST NOT be used outside of testing.
+#[cfg(all(feature = "std", any(test, feature = "test_utils")))]
+pub fn with_deterministic_rng<R, F: FnOnce() -> R>(seed: u64, f: F) -> R {
+    // Restore the previous state on return (or unwind)
+    struct Restore(Option<u64>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED_SEED.with(|s| s.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCOPED_SEED.with(|s| s.replace(Some(seed))));
+    f()
+}
+
+/// Fill the provided buffer from the scoped deterministic RNG, where set for the current thread
+#[cfg(all(feature = "std", any(test, feature = "test_utils")))]
+fn scoped_rng(buff: &mut [u8]) -> bool {
+    SCOPED_SEED.with(|s| {
+        let mut seed = match s.get() {
+            Some(v) => v,
+            None => return false,
+        };
+
+        for c in buff.chunks_mut(8) {
+            seed = seed.wrapping_add(SPLITMIX_GAMMA);
+            c.copy_from_slice(&splitmix64(seed).to_le_bytes()[..c.len()]);
+        }
+
+        s.set(Some(seed));
+        true
+    })
+}
+
+/// Fill the provided buffer using the configured RNG
+pub fn fill_random(buff: &mut [u8]) -> Result<(), ()> {
+    #[cfg(all(feature = "std", any(test, feature = "test_utils")))]
+    if scoped_rng(buff) {
+        return Ok(());
+    }
+
+    match *SOURCE.read() {
+        Some(source) => source.fill(buff),
+        None => OsRng.try_fill_bytes(buff).map_err(|_| ()),
+    }
+}
+
+/// Error code returned by [`Rng::try_fill_bytes`] where the configured RNG fails
+const RNG_ERROR_CODE: NonZeroU32 = match NonZeroU32::new(rand_core_0_6::Error::CUSTOM_START) {
+    Some(c) => c,
+    None => panic!("invalid RNG error code"),
+};
+
+/// [`RngCore`] implementation using the configured RNG,
+/// for use with crypto libraries expecting an RNG instance.
+///
+/// Failures of the configured RNG are returned by [`RngCore::try_fill_bytes`], while the
+/// infallible [`RngCore`] methods retry until the RNG succeeds (for example, where a hardware
+/// TRNG is not yet ready) rather than panicking. Callers able to handle RNG failures should
+/// use `try_fill_bytes` or [`fill_random`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Rng;
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        let mut b = [0u8; 4];
+        self.fill_bytes(&mut b);
+        u32::from_le_bytes(b)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut b = [0u8; 8];
+        self.fill_bytes(&mut b);
+        u64::from_le_bytes(b)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        while self.try_fill_bytes(dest).is_err() {
+            core::hint::spin_loop();
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core_0_6::Error> {
+        fill_random(dest).map_err(|_| rand_core_0_6::Error::from(RNG_ERROR_CODE))
+    }
+}
+
+impl CryptoRng for Rng {}
+
+/// Lock serialising tests which replace the global RNG
+#[cfg(test)]
+pub(crate) static TEST_RNG_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    // Counting wrapper, still backed by OsRng as tests run concurrently
+    fn counting_rng(buff: &mut [u8]) -> Result<(), ()> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        OsRng.try_fill_bytes(buff).map_err(|_| ())
+    }
+
+    #[test]
+    fn injected_rng() {
+        let _l = TEST_RNG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut a = [0u8; 16];
+
+        set_rng(&counting_rng);
+        fill_random(&mut a).unwrap();
+        reset_rng();
+
+        assert!(CALLS.load(Ordering::SeqCst) > 0);
+    }
+
+    // Stateful source, still backed by OsRng as tests run concurrently
+    struct CountingSource(AtomicUsize);
+
+    impl RngSource for CountingSource {
+        fn fill(&self, buff: &mut [u8]) -> Result<(), ()> {
+            self.0.fetch_add(buff.len(), Ordering::SeqCst);
+            OsRng.try_fill_bytes(buff).map_err(|_| ())
+        }
+    }
+
+    static COUNTING: CountingSource = CountingSource(AtomicUsize::new(0));
+
+    #[test]
+    fn injected_rng_source() {
+        use crate::crypto::{Crypto, PubKey as _, SecKey as _};
+
+        let _l = TEST_RNG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        set_rng(&COUNTING);
+        let _ = Crypto::new_pk().unwrap();
+        let _ = Crypto::new_sk().unwrap();
+        reset_rng();
+
+        assert!(COUNTING.0.load(Ordering::SeqCst) >= 64);
+    }
+
+    std::thread_local! {
+        static FAIL: core::cell::Cell<bool> = core::cell::Cell::new(false);
+    }
+
+    // Fails on the test thread only, other threads are backed by OsRng as tests run concurrently
+    fn failing_rng(buff: &mut [u8]) -> Result<(), ()> {
+        match FAIL.with(|f| f.get()) {
+            true => Err(()),
+            false => OsRng.try_fill_bytes(buff).map_err(|_| ()),
+        }
+    }
+
+    #[test]
+    fn failing_rng_errors() {
+        use crate::crypto::{Crypto, PubKey as _, SecKey as _};
+
+        let _l = TEST_RNG_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut a = [0u8; 16];
+
+        set_rng(&failing_rng);
+        FAIL.with(|f| f.set(true));
+        let (r, pk, sk) = (Rng.try_fill_bytes(&mut a), Crypto::new_pk(), Crypto::new_sk());
+        FAIL.with(|f| f.set(false));
+        reset_rng();
+
+        // Failures are returned rather than panicking
+        assert_eq!(r.err().and_then(|e| e.code()), Some(RNG_ERROR_CODE));
+        assert!(pk.is_err());
+        assert!(sk.is_err());
+    }
+
+    #[test]
+    fn scoped_deterministic_rng() {
+        let (mut a, mut b) = ([0u8; 24], [0u8; 24]);
+
+        with_deterministic_rng(7, || fill_random(&mut a)).unwrap();
+        with_deterministic_rng(7, || fill_random(&mut b)).unwrap();
+        assert_eq!(a, b);
+
+        // Other threads are unaffected by the scoped RNG
+        let c = with_deterministic_rng(7, || std::thread::spawn(|| {
+            let mut c = [0u8; 24];
+            fill_random(&mut c).unwrap();
+            c
+        }).join().unwrap());
+        assert_ne!(a, c);
+
+        // And the scoped RNG is removed on return
+        fill_random(&mut b).unwrap();
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,231 @@
+//! Decentralised identifier (DID) shims for referencing DSF services from external identity systems.
+//!
+//! Service public keys are represented using the [`did:key`](https://w3c-ccg.github.io/did-method-key/)
+//! method (ed25519 multicodec, base58btc multibase), which are interoperable with other did:key
+//! implementations, for example: `did:key:z6Mk...`.
+//!
+//! Where only a service ID is available this is represented as `did:dsf:z...`, with the raw ID
+//! base58btc encoded. Both forms resolve to a service [`Id`] with [`Did::id`].
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::error::Error;
+use crate::types::*;
+
+/// DID prefix for public keys
+pub const DID_KEY_PREFIX: &str = "did:key:";
+
+/// DID prefix for service IDs
+pub const DID_DSF_PREFIX: &str = "did:dsf:";
+
+/// Multibase prefix for base58btc encoding
+const MULTIBASE_BASE58BTC: char = 'z';
+
+/// Multicodec (varint encoded) prefix for ed25519 public keys
+const MULTICODEC_ED25519_PUB: [u8; 2] = [0xed, 0x01];
+
+/// Maximum decoded DID identifier length
+const MAX_DECODED_LEN: usize = 48;
+
+/// Maximum base58 encoded DID identifier length
+const MAX_ENCODED_LEN: usize = 68;
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// DID referencing a DSF service by public key or ID
+#[derive(Clone, Debug, PartialEq)]
+pub enum Did {
+    /// Service public key (`did:key:`)
+    Key(PublicKey),
+    /// Service ID (`did:dsf:`)
+    Dsf(Id),
+}
+
+impl Did {
+    /// Fetch the service ID for a DID, deriving this from the public key where required
+    pub fn id(&self) -> Id {
+        match self {
+            Did::Key(pk) => Id::from_public_key(pk),
+            Did::Dsf(id) => id.clone(),
+        }
+    }
+
+    /// Fetch the service public key for a DID (if available)
+    pub fn public_key(&self) -> Option<&PublicKey> {
+        match self {
+            Did::Key(pk) => Some(pk),
+            Did::Dsf(_) => None,
+        }
+    }
+}
+
+impl From<PublicKey> for Did {
+    fn from(pk: PublicKey) -> Self {
+        Did::Key(pk)
+    }
+}
+
+impl From<Id> for Did {
+    fn from(id: Id) -> Self {
+        Did::Dsf(id)
+    }
+}
+
+impl fmt::Display for Did {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut raw = [0u8; MAX_DECODED_LEN];
+
+        let (prefix, n) = match self {
+            Did::Key(pk) => {
+                raw[..2].copy_from_slice(&MULTICODEC_ED25519_PUB);
+                raw[2..][..PUBLIC_KEY_LEN].copy_from_slice(pk);
+                (DID_KEY_PREFIX, 2 + PUBLIC_KEY_LEN)
+            },
+            Did::Dsf(id) => {
+                raw[..ID_LEN].copy_from_slice(id);
+                (DID_DSF_PREFIX, ID_LEN)
+            },
+        };
+
+        let mut encoded = [0u8; MAX_ENCODED_LEN];
+        let n = base58_encode(&raw[..n], &mut encoded).ok_or(fmt::Error)?;
+
+        // Base58 alphabet is ASCII, so always valid UTF8
+        let encoded = core::str::from_utf8(&encoded[..n]).map_err(|_| fmt::Error)?;
+
+        write!(f, "{}{}{}", prefix, MULTIBASE_BASE58BTC, encoded)
+    }
+}
+
+impl FromStr for Did {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (is_key, s) = if let Some(s) = s.strip_prefix(DID_KEY_PREFIX) {
+            (true, s)
+        } else if let Some(s) = s.strip_prefix(DID_DSF_PREFIX) {
+            (false, s)
+        } else {
+            return Err(Error::InvalidDid);
+        };
+
+        let s = s.strip_prefix(MULTIBASE_BASE58BTC).ok_or(Error::InvalidDid)?;
+
+        let mut raw = [0u8; MAX_DECODED_LEN];
+        let n = base58_decode(s.as_bytes(), &mut raw).ok_or(Error::InvalidDid)?;
+        let raw = &raw[..n];
+
+        match is_key {
+            true if raw.len() == 2 + PUBLIC_KEY_LEN && raw[..2] == MULTICODEC_ED25519_PUB => {
+                Ok(Did::Key(PublicKey::try_from(&raw[2..])?))
+            },
+            false if raw.len() == ID_LEN => Ok(Did::Dsf(Id::try_from(raw)?)),
+            _ => Err(Error::InvalidDid),
+        }
+    }
+}
+
+/// Base58 encode data into the provided buffer, returning the encoded length
+fn base58_encode(data: &[u8], out: &mut [u8]) -> Option<usize> {
+    let zeros = data.iter().take_while(|b| **b == 0).count();
+
+    // Convert to little-endian base58 digits
+    let mut digits = [0u8; MAX_ENCODED_LEN];
+    let mut len = 0;
+
+    for b in &data[zeros..] {
+        let mut carry = *b as u32;
+        for d in digits[..len].iter_mut() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            *digits.get_mut(len)? = (carry % 58) as u8;
+            len += 1;
+            carry /= 58;
+        }
+    }
+
+    // Leading zeros are encoded as leading '1's
+    let n = zeros + len;
+    if out.len() < n {
+        return None;
+    }
+
+    out[..zeros].iter_mut().for_each(|o| *o = BASE58_ALPHABET[0]);
+    for (o, d) in out[zeros..n].iter_mut().zip(digits[..len].iter().rev()) {
+        *o = BASE58_ALPHABET[*d as usize];
+    }
+
+    Some(n)
+}
+
+/// Base58 decode data into the provided buffer, returning the decoded length
+fn base58_decode(data: &[u8], out: &mut [u8]) -> Option<usize> {
+    let zeros = data.iter().take_while(|c| **c == BASE58_ALPHABET[0]).count();
+
+    // Convert to little-endian bytes
+    let mut bytes = [0u8; MAX_DECODED_LEN];
+    let mut len = 0;
+
+    for c in &data[zeros..] {
+        let mut carry = BASE58_ALPHABET.iter().position(|a| a == c)? as u32;
+        for b in bytes[..len].iter_mut() {
+            carry += (*b as u32) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            *bytes.get_mut(len)? = carry as u8;
+            len += 1;
+            carry >>= 8;
+        }
+    }
+
+    let n = zeros + len;
+    if out.len() < n {
+        return None;
+    }
+
+    out[..zeros].iter_mut().for_each(|o| *o = 0);
+    for (o, b) in out[zeros..n].iter_mut().zip(bytes[..len].iter().rev()) {
+        *o = *b;
+    }
+
+    Some(n)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn did_round_trip() {
+        let keys = test_utils::keys(test_utils::SERVICE_SEED);
+        let pk = keys.pub_key.clone().unwrap();
+
+        // Public keys encode as interoperable ed25519 did:keys
+        let did = Did::from(pk.clone());
+        let k = did.to_string();
+        assert!(k.starts_with("did:key:z6Mk"));
+        assert_eq!(Did::from_str(&k), Ok(did.clone()));
+        assert_eq!(did.id(), keys.id().unwrap());
+
+        // IDs (including leading zeros) encode as did:dsf
+        let mut raw = [0xabu8; ID_LEN];
+        raw[..2].copy_from_slice(&[0, 0]);
+        let did = Did::from(Id::from(raw));
+        let s = did.to_string();
+        assert!(s.starts_with("did:dsf:z11"));
+        assert_eq!(Did::from_str(&s), Ok(did));
+
+        // Malformed DIDs are rejected
+        for s in &["did:web:example.com", "did:key:6Mk", "did:key:z0OIl", "did:dsf:z6Mk", &k[..k.len() - 4]] {
+            assert_eq!(Did::from_str(s), Err(Error::InvalidDid), "{}", s);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! One-call helpers for common workflows (create service → publish → encode → parse → subscribe),
+//! using allocated buffers sized to fit each object.
+//!
+//! These wrap the [`ServiceBuilder`], [`Publisher`], [`Net`], and [`Subscriber`] APIs with the
+//! required ordering handled internally, for applications not needing fixed buffers or
+//! fine-grained control over encoding.
+//!
+//! ```
+//! use dsf_core::easy;
+//! use dsf_core::prelude::*;
+//! use dsf_core::keys::NullKeySource;
+//!
+//! // Publisher creates a service and publishes a primary page
+//! let mut svc = easy::create_service(vec![Options::name("sensor")]).unwrap();
+//! let page = easy::publish_signed_page(&mut svc, None, Default::default()).unwrap();
+//!
+//! // Subscriber loads the service from the primary page
+//! let mut replica: Service = easy::subscribe(&page, &NullKeySource).unwrap();
+//!
+//! // Then verifies data objects published by the service
+//! let data = easy::publish_signed_data(&mut svc, DataOptions{ body: Some(&[0xab; 4][..]), ..Default::default() }).unwrap();
+//! let c = easy::receive(&mut replica, &data).unwrap();
+//! assert_eq!(c.body_raw(), &[0xab; 4]);
+//! ```
+
+use alloc::vec::Vec;
+
+use encdec::DecodeOwned;
+
+use crate::base::{DataBody, MaybeEncrypted, PageBody};
+use crate::error::Error;
+use crate::keys::{KeySource, Keys};
+use crate::net::{Message, Request, Response};
+use crate::options::Options;
+use crate::service::{DataOptions, Net, PrimaryOptions, Publisher, Service, ServiceBuilder, Subscriber};
+use crate::types::*;
+use crate::wire::Container;
+
+/// Create a generic service with the provided public options and freshly generated keys
+pub fn create_service(public_options: Vec<Options>) -> Result<Service, Error> {
+    ServiceBuilder::generic().public_options(public_options).build()
+}
+
+/// Publish a signed primary page, returning the encoded page.
+///
+/// Where a body is provided the service is first updated (incrementing the service version).
+pub fn publish_signed_page<B: PageBody + Clone>(s: &mut Service<B>, body: Option<B>, options: PrimaryOptions) -> Result<Vec<u8>, Error> {
+    if let Some(body) = body {
+        s.update(|b, _public, _private| *b = MaybeEncrypted::Cleartext(body.clone()))?;
+    }
+
+    let (_n, c) = s.publish_primary_alloc(options)?;
+    Ok(c.raw().to_vec())
+}
+
+/// Publish a signed data object, returning the encoded object
+pub fn publish_signed_data<B: PageBody, D: DataBody + Clone>(s: &mut Service<B>, options: DataOptions<D>) -> Result<Vec<u8>, Error> {
+    let (_n, c) = s.publish_data_alloc(options)?;
+    Ok(c.raw().to_vec())
+}
+
+/// Encode (and sign) a request to a peer, returning the encoded message
+pub fn encode_request<B: PageBody>(s: &Service<B>, req: &Request, peer_keys: &Keys) -> Result<Vec<u8>, Error> {
+    s.encode_request_alloc(req, peer_keys).map(|c| c.raw().to_vec())
+}
+
+/// Encode (and sign) a response to a peer, returning the encoded message
+pub fn encode_response<B: PageBody>(s: &Service<B>, resp: &Response, peer_keys: &Keys) -> Result<Vec<u8>, Error> {
+    s.encode_response_alloc(resp, peer_keys).map(|c| c.raw().to_vec())
+}
+
+/// Parse an encoded object, verifying the signature (and decrypting where keys are available)
+pub fn verify_and_decode<K: KeySource>(buff: &[u8], key_source: &K) -> Result<Container, Error> {
+    Container::parse(buff.to_vec(), key_source)
+}
+
+/// Parse and verify an encoded request or response message
+pub fn decode_message<K: KeySource>(buff: &[u8], key_source: &K) -> Result<Message, Error> {
+    Message::parse(buff.to_vec(), key_source).map(|(m, _n)| m)
+}
+
+/// Load a service replica from an encoded primary page, decrypting private services
+/// using the secret key from the provided key source
+pub fn subscribe<B: PageBody + DecodeOwned<Output=B>, K: KeySource>(buff: &[u8], key_source: &K) -> Result<Service<B>, Error> {
+    let c = verify_and_decode(buff, key_source)?;
+
+    match c.encrypted() {
+        true => Service::load_private(&c, key_source),
+        false => Service::load(&c),
+    }
+}
+
+/// Parse and validate an object published by a subscribed service, applying primary page updates
+pub fn receive<B: PageBody + DecodeOwned<Output=B>>(s: &mut Service<B>, buff: &[u8]) -> Result<Container, Error> {
+    let c = verify_and_decode(buff, &s.keys())?;
+    let header = c.header();
+
+    if header.kind().is_page() && !header.flags().intersects(Flags::SECONDARY | Flags::TERTIARY) {
+        s.apply_primary(&c)?;
+    } else {
+        s.validate_page(&c)?;
+    }
+
+    Ok(c)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keys::NullKeySource;
+    use crate::net::RequestBody;
+    use crate::test_utils;
+
+    #[test]
+    fn easy_workflow() {
+        let mut svc = create_service(vec![Options::name("easy")]).unwrap();
+        let p1 = publish_signed_page(&mut svc, None, Default::default()).unwrap();
+
+        let mut replica: Service = subscribe(&p1, &NullKeySource).unwrap();
+        assert_eq!(replica.id(), svc.id());
+
+        // Body updates publish a new version
+        let p2 = publish_signed_page(&mut svc, Some(vec![0x01, 0x02]), Default::default()).unwrap();
+        let c = receive(&mut replica, &p2).unwrap();
+        assert!(c.header().index() > verify_and_decode(&p1, &NullKeySource).unwrap().header().index());
+        assert_eq!(replica.body(), &MaybeEncrypted::Cleartext(vec![0x01, 0x02]));
+
+        let opts = DataOptions{ body: Some(&[0xcd; 8][..]), ..Default::default() };
+        let d = publish_signed_data(&mut svc, opts).unwrap();
+        assert_eq!(receive(&mut replica, &d).unwrap().body_raw(), &[0xcd; 8]);
+
+        // Modified objects are rejected
+        let mut forged = d.clone();
+        let n = forged.len();
+        forged[n - 1] ^= 0xff;
+        assert!(receive(&mut replica, &forged).is_err());
+
+        // Messages roundtrip via encode / decode
+        let (a, b) = test_utils::service_pair();
+        let req = Request::new(a.id(), 1, RequestBody::Ping, Flags::empty());
+        let m = encode_request(&a, &req, &b.keys()).unwrap();
+        assert_eq!(decode_message(&m, &a.keys()), Ok(Message::Request(req)));
+    }
+}
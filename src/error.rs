@@ -1,5 +1,7 @@
 //! Error types for DSF Core
 
+use crate::options::OptionError;
+
 /// Error enum represents possible core errors
 /// 
 /// For serialisation add `serde`, `thiserror`, `strum`, and/or `defmt` features
@@ -53,6 +55,40 @@ pub enum Error {
     EncodeFailed,
     BufferLength,
     InvalidUtf8,
+    ForkDetected,
+    TooManyOptions,
+    OptionsTooLong,
+    InvalidEnvelope,
+    EnvelopeMismatch,
+    UnauthorizedPublisher,
+    InvalidFlags,
+    ObjectTooLarge,
+    TooManyPages,
+    InsufficientSignatures,
+    ReEncryptionMismatch,
+    TenantMismatch,
+    InvalidDid,
+    DigestMismatch,
+    Incomplete,
+    InvalidQuery,
+    InvalidResumeToken,
+    UnsupportedVersion,
+    InvalidNonce,
+    InvalidDelegation,
+    DelegationExpired,
+    CompressionFailed,
+    InvalidFragment,
+    NotDecrypted,
+    PageExpired,
+    ChainBroken,
+    ReplayedObject,
+    IndexRollback,
+    DowngradeDetected,
+    RateLimited,
+    BlockHashMismatch,
+    NoCommonTransport,
+    InvalidOptions(OptionError),
+    NotEncrypted,
 }
 
 #[cfg(feature = "std")]
@@ -0,0 +1,235 @@
+//! Key grinding for vanity service IDs, enabled via the `grind` feature.
+//!
+//! Candidate keys are derived deterministically from a seed and attempt index (see [`derive_keys`]),
+//! so a result may be reproduced from the seed and index alone. Grinding is bounded by
+//! [`GrindOptions::max_attempts`] and optionally rate limited, with a progress callback
+//! allowing tooling to report on (or cancel) long-running searches.
+//!
+//! Note that each additional base64 prefix character multiplies the expected attempts by 64.
+
+use std::time::{Duration, Instant};
+
+use crate::crypto::{Crypto, Hash as _};
+use crate::error::Error;
+use crate::keys::Keys;
+use crate::types::*;
+
+/// Base64 (URL safe) characters, as used when displaying IDs
+const ID_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Options for key grinding
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrindOptions {
+    /// Maximum number of candidate keys to generate
+    pub max_attempts: u64,
+    /// Maximum candidate generation rate (attempts per second)
+    pub max_rate: Option<u32>,
+    /// Number of attempts between progress callbacks
+    pub progress_interval: u64,
+}
+
+impl Default for GrindOptions {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1_000_000,
+            max_rate: None,
+            progress_interval: 10_000,
+        }
+    }
+}
+
+/// Grinding progress, passed to progress callbacks
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrindProgress {
+    /// Number of candidate keys generated
+    pub attempts: u64,
+    /// Time elapsed since grinding started
+    pub elapsed: Duration,
+}
+
+/// Successful grinding result
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grind {
+    /// Matching service ID
+    pub id: Id,
+    /// Keys for the matching service ID
+    pub keys: Keys,
+    /// Attempt index, for reproducing keys with [`derive_keys`]
+    pub index: u64,
+}
+
+/// Time source for rate limiting, allowing callers (and tests) to provide their own timer
+pub trait GrindTimer {
+    /// Fetch the time elapsed since grinding started
+    fn elapsed(&self) -> Duration;
+
+    /// Wait for the provided duration
+    fn sleep(&mut self, d: Duration);
+}
+
+/// [`GrindTimer`] using the system clock
+pub struct SystemTimer(Instant);
+
+impl SystemTimer {
+    /// Create a new timer, starting from now
+    pub fn new() -> Self {
+        Self(Instant::now())
+    }
+}
+
+impl Default for SystemTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GrindTimer for SystemTimer {
+    fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+
+    fn sleep(&mut self, d: Duration) {
+        std::thread::sleep(d)
+    }
+}
+
+/// Deterministically derive a candidate key set from a seed and attempt index
+pub fn derive_keys(seed: &[u8; 32], index: u64) -> Keys {
+    let h = Crypto::hash_iter([&seed[..], &index.to_be_bytes()[..]]).unwrap();
+
+    let secret = ed25519_dalek::SecretKey::from_bytes(&h).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+
+    // Our private keys contain both the public and private components
+    let mut pri_key = PrivateKey::default();
+    pri_key[..32].copy_from_slice(secret.as_bytes());
+    pri_key[32..].copy_from_slice(public.as_bytes());
+
+    Keys {
+        pub_key: Some(PublicKey::from(public.to_bytes())),
+        pri_key: Some(pri_key),
+        sec_key: None,
+        sym_keys: None,
+    }
+}
+
+/// Create a predicate matching IDs with the provided (base64) display prefix,
+/// returning [`Error::InvalidOption`] for prefixes that can never match
+pub fn id_prefix(prefix: &str) -> Result<impl Fn(&Id) -> bool + '_, Error> {
+    // Base64 IDs are 44 characters, including one padding character
+    if prefix.len() > 43 || !prefix.chars().all(|c| ID_CHARS.contains(c)) {
+        return Err(Error::InvalidOption);
+    }
+
+    Ok(move |id: &Id| id.to_string().starts_with(prefix))
+}
+
+/// Grind candidate keys derived from the provided seed until the service ID matches the predicate.
+///
+/// The progress callback is called every [`GrindOptions::progress_interval`] attempts, and may
+/// return false to cancel grinding. Returns [`Error::NotFound`] where no match is found within
+/// [`GrindOptions::max_attempts`] or grinding is cancelled.
+pub fn grind<F, P>(seed: &[u8; 32], opts: &GrindOptions, predicate: F, progress: P) -> Result<Grind, Error>
+where
+    F: Fn(&Id) -> bool,
+    P: FnMut(&GrindProgress) -> bool,
+{
+    grind_with(seed, opts, predicate, progress, SystemTimer::new())
+}
+
+/// Grind candidate keys as with [`grind`], using the provided timer for progress and rate limiting
+pub fn grind_with<F, P, T>(seed: &[u8; 32], opts: &GrindOptions, predicate: F, mut progress: P, mut timer: T) -> Result<Grind, Error>
+where
+    F: Fn(&Id) -> bool,
+    P: FnMut(&GrindProgress) -> bool,
+    T: GrindTimer,
+{
+    for index in 0..opts.max_attempts {
+        let keys = derive_keys(seed, index);
+        let id = keys.id().unwrap();
+
+        if predicate(&id) {
+            debug!("Found matching ID {} after {} attempts", id, index + 1);
+            return Ok(Grind { id, keys, index });
+        }
+
+        let attempts = index + 1;
+
+        // Report progress, stopping if cancelled
+        if opts.progress_interval > 0 && attempts % opts.progress_interval == 0 {
+            let p = GrindProgress { attempts, elapsed: timer.elapsed() };
+            if !progress(&p) {
+                debug!("Grinding cancelled after {} attempts", attempts);
+                return Err(Error::NotFound);
+            }
+        }
+
+        // Delay where generation is ahead of the rate limit
+        if let Some(rate) = opts.max_rate.filter(|r| *r > 0) {
+            let target = Duration::from_secs_f64(attempts as f64 / rate as f64);
+            if let Some(d) = target.checked_sub(timer.elapsed()) {
+                timer.sleep(d);
+            }
+        }
+    }
+
+    debug!("No matching ID found in {} attempts", opts.max_attempts);
+
+    Err(Error::NotFound)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Simulated timer, advancing only on sleep
+    #[derive(Default)]
+    struct TestTimer {
+        elapsed: Duration,
+        sleeps: usize,
+    }
+
+    impl GrindTimer for &mut TestTimer {
+        fn elapsed(&self) -> Duration {
+            self.elapsed
+        }
+
+        fn sleep(&mut self, d: Duration) {
+            self.elapsed += d;
+            self.sleeps += 1;
+        }
+    }
+
+    #[test]
+    fn grind_vanity_ids() {
+        let seed = [0x11; 32];
+        let opts = GrindOptions{ max_attempts: 10_000, progress_interval: 16, ..Default::default() };
+
+        // Matching keys are found and reproducible from the seed and index
+        let predicate = id_prefix("A").unwrap();
+        let g = grind(&seed, &opts, &predicate, |_p| true).unwrap();
+        assert!(g.id.to_string().starts_with('A'));
+        assert_eq!(derive_keys(&seed, g.index), g.keys);
+        assert_eq!(g.keys.id(), Some(g.id.clone()));
+        assert_eq!(grind(&seed, &opts, &predicate, |_p| true), Ok(g));
+
+        // Grinding stops at the attempt cap or on cancellation
+        let capped = GrindOptions{ max_attempts: 4, ..opts.clone() };
+        assert_eq!(grind(&seed, &capped, |_id| false, |_p| true), Err(Error::NotFound));
+
+        let mut reports = vec![];
+        let r = grind(&seed, &opts, |_id| false, |p| { reports.push(p.attempts); p.attempts < 32 });
+        assert_eq!(r, Err(Error::NotFound));
+        assert_eq!(reports, vec![16, 32]);
+
+        // Generation is rate limited, sleeping until each attempt is within the rate
+        let limited = GrindOptions{ max_attempts: 10, max_rate: Some(200), ..opts };
+        let mut timer = TestTimer::default();
+        assert_eq!(grind_with(&seed, &limited, |_id| false, |_p| true, &mut timer), Err(Error::NotFound));
+        assert_eq!(timer.sleeps, 10);
+        assert!(timer.elapsed > Duration::from_millis(49) && timer.elapsed <= Duration::from_millis(50));
+
+        // Prefixes that can not match are rejected
+        assert!(id_prefix("not+base64").is_err());
+    }
+}
@@ -0,0 +1,172 @@
+//! Instrumentation hooks for object encoding and parsing, enabled with the `instrument` feature.
+//!
+//! This allows downstream crates to time encode / parse phases (for example using `tracing`
+//! spans or `defmt` timestamps) by registering an [`Instrument`] with [`set_instrument`].
+//! With the feature disabled instrumentation points compile to nothing.
+
+/// Enter an instrumentation span for the provided [`Phase`], exiting when the enclosing scope ends
+#[cfg(feature = "instrument")]
+macro_rules! span {
+    ($phase:ident) => {
+        let _span = $crate::instrument::Span::enter($crate::instrument::Phase::$phase);
+    };
+}
+
+#[cfg(not(feature = "instrument"))]
+macro_rules! span {
+    ($phase:ident) => {};
+}
+
+#[cfg(feature = "instrument")]
+pub use self::hooks::*;
+
+#[cfg(feature = "instrument")]
+mod hooks {
+    /// Object encode and parse phases
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub enum Phase {
+        /// Header encoding
+        Header,
+        /// Body encoding
+        Body,
+        /// Private options encoding
+        PrivateOptions,
+        /// Body and private options encryption
+        Encrypt,
+        /// Public options encoding
+        PublicOptions,
+        /// Object signing (or AEAD encryption for symmetric mode objects)
+        Sign,
+
+        /// Header parsing and options limit checks
+        ParseHeader,
+        /// Extraction of signing options
+        ParseOptions,
+        /// Signature verification
+        Verify,
+        /// Body and private options decryption
+        Decrypt,
+    }
+
+    /// Instrumentation hook, called on entry to and exit from each [`Phase`]
+    pub trait Instrument: Sync {
+        /// Called on entry to a phase
+        fn enter(&self, phase: Phase);
+
+        /// Called on exit from a phase (including on errors)
+        fn exit(&self, phase: Phase);
+    }
+
+    struct NopInstrument;
+
+    impl Instrument for NopInstrument {
+        fn enter(&self, _phase: Phase) {}
+        fn exit(&self, _phase: Phase) {}
+    }
+
+    /// Global instrumentation hook, set once with [`set_instrument`]
+    static INSTRUMENT: spin::Once<&'static dyn Instrument> = spin::Once::new();
+
+    /// Register the global instrumentation hook.
+    ///
+    /// This may be called once, returning false if a hook is already registered.
+    pub fn set_instrument(instrument: &'static dyn Instrument) -> bool {
+        let mut set = false;
+        INSTRUMENT.call_once(|| {
+            set = true;
+            instrument
+        });
+        set
+    }
+
+    /// Fetch the global instrumentation hook
+    pub fn instrument() -> &'static dyn Instrument {
+        match INSTRUMENT.get() {
+            Some(i) => *i,
+            None => &NopInstrument,
+        }
+    }
+
+    /// Span guard, calling [`Instrument::exit`] on drop
+    pub struct Span(Phase);
+
+    impl Span {
+        /// Enter a phase, exiting when the returned guard is dropped
+        pub fn enter(phase: Phase) -> Self {
+            instrument().enter(phase);
+            Self(phase)
+        }
+    }
+
+    impl Drop for Span {
+        fn drop(&mut self) {
+            instrument().exit(self.0);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "instrument"))]
+mod test {
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::service::{DataOptions, Publisher};
+    use crate::test_utils::{self, TestKeySource};
+    use crate::wire::Container;
+
+    std::thread_local! {
+        static EVENTS: RefCell<Vec<(bool, Phase)>> = RefCell::new(Vec::new());
+    }
+
+    struct Recorder;
+
+    impl Instrument for Recorder {
+        fn enter(&self, phase: Phase) {
+            EVENTS.with(|e| e.borrow_mut().push((true, phase)));
+        }
+
+        fn exit(&self, phase: Phase) {
+            EVENTS.with(|e| e.borrow_mut().push((false, phase)));
+        }
+    }
+
+    fn take() -> Vec<(bool, Phase)> {
+        EVENTS.with(|e| e.borrow_mut().drain(..).collect())
+    }
+
+    #[test]
+    fn instrument_encode_parse() {
+        static RECORDER: Recorder = Recorder;
+        assert!(set_instrument(&RECORDER));
+        assert!(!set_instrument(&RECORDER));
+
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let p = test_utils::primary_page(&mut s);
+        take();
+
+        let body = [0xaau8; 16];
+        let (_n, d) = s.publish_data(DataOptions{ body: Some(&body[..]), ..Default::default() }, vec![0u8; 1024]).unwrap();
+        let encoded = take();
+
+        // Phases are entered and exited in order, public options may be written in multiple passes
+        assert!(encoded.chunks(2).all(|c| c[0] == (true, c[1].1) && !c[1].0));
+
+        let mut entered: Vec<_> = encoded.iter().filter(|e| e.0).map(|e| e.1).collect();
+        entered.dedup();
+        assert_eq!(entered, vec![Phase::Header, Phase::Body, Phase::PrivateOptions, Phase::PublicOptions, Phase::Sign]);
+
+        // Unknown self-signed objects extract signing options prior to verification
+        let ks = TestKeySource::new();
+        let _c = Container::parse(p.raw().to_vec(), &ks).unwrap();
+        let parsed: Vec<_> = take().into_iter().filter(|e| e.0).map(|e| e.1).collect();
+        assert_eq!(parsed, vec![Phase::ParseHeader, Phase::ParseOptions, Phase::Verify]);
+
+        // Objects with known keys are verified directly
+        let ks = ks.with(s.id(), s.keys());
+        let _c = Container::parse(d.raw().to_vec(), &ks).unwrap();
+        let parsed: Vec<_> = take().into_iter().filter(|e| e.0).map(|e| e.1).collect();
+        assert_eq!(parsed, vec![Phase::ParseHeader, Phase::Verify]);
+    }
+}
@@ -0,0 +1,234 @@
+//! Interceptors provide hooks for application middleware at defined points in object
+//! encoding and decoding, allowing applications to attach or strip custom options
+//! and enforce policy centrally.
+//!
+//! Interceptors are registered in an [`Interceptors`] chain, and are called in order of
+//! registration, with the first error aborting the operation.
+//!
+//! Interceptors must be `Send + Sync` so services holding interceptor chains may be shared across threads.
+//!
+//! Without `alloc`, up to [`MAX_STATIC_INTERCEPTORS`] `'static` interceptors may be registered
+//! via [`Interceptors::push_static`], and the option-editing hooks (`pre_sign` and `pre_convert`)
+//! are unavailable.
+
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::base::Header;
+use crate::error::Error;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::Container;
+
+/// Interceptor trait, implement the required hooks to intercept object encoding / decoding
+pub trait Interceptor: Send + Sync {
+    /// Called prior to signing outgoing objects, options added to `public_options`
+    /// are attached to the object
+    #[cfg(feature = "alloc")]
+    fn pre_sign(&self, _id: &Id, _header: &Header, _public_options: &mut Vec<Options>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called prior to signature verification of incoming messages, following header checks.
+    ///
+    /// Message contents are unverified, so this should only be used for admission control
+    /// (for example, rate limiting by sender ID) to reject messages before verification and decoding
+    fn pre_verify(&self, _container: &Container<&[u8]>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called following parsing and verification of incoming messages
+    fn post_parse(&self, _container: &Container<&[u8]>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called prior to conversion of parsed messages, options removed from `public_options`
+    /// are ignored by conversion
+    #[cfg(feature = "alloc")]
+    fn pre_convert(&self, _header: &Header, _public_options: &mut Vec<Options>) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Maximum number of interceptors in a chain without `alloc`
+#[cfg(not(feature = "alloc"))]
+pub const MAX_STATIC_INTERCEPTORS: usize = 4;
+
+/// Ordered chain of interceptors
+#[derive(Clone, Default)]
+pub struct Interceptors {
+    #[cfg(feature = "alloc")]
+    chain: Vec<Arc<dyn Interceptor>>,
+    #[cfg(not(feature = "alloc"))]
+    chain: heapless::Vec<&'static dyn Interceptor, MAX_STATIC_INTERCEPTORS>,
+}
+
+impl Interceptors {
+    /// Create a new (empty) interceptor chain
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append an interceptor to the chain
+    #[cfg(feature = "alloc")]
+    pub fn with<I: Interceptor + 'static>(mut self, interceptor: I) -> Self {
+        self.push(interceptor);
+        self
+    }
+
+    /// Append an interceptor to the chain
+    #[cfg(feature = "alloc")]
+    pub fn push<I: Interceptor + 'static>(&mut self, interceptor: I) {
+        self.chain.push(Arc::new(interceptor));
+    }
+
+    /// Append a static interceptor to the chain, returning [`Error::BufferLength`]
+    /// where [`MAX_STATIC_INTERCEPTORS`] is exceeded
+    #[cfg(not(feature = "alloc"))]
+    pub fn push_static(&mut self, interceptor: &'static dyn Interceptor) -> Result<(), Error> {
+        self.chain.push(interceptor).map_err(|_| Error::BufferLength)
+    }
+
+    /// Fetch the number of registered interceptors
+    pub fn len(&self) -> usize {
+        self.chain.len()
+    }
+
+    /// Check whether the chain is empty
+    pub fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    /// Run pre-sign hooks
+    #[cfg(feature = "alloc")]
+    pub fn pre_sign(&self, id: &Id, header: &Header, public_options: &mut Vec<Options>) -> Result<(), Error> {
+        self.chain.iter().try_for_each(|i| i.pre_sign(id, header, public_options))
+    }
+
+    /// Run pre-verify hooks
+    pub fn pre_verify(&self, container: &Container<&[u8]>) -> Result<(), Error> {
+        self.chain.iter().try_for_each(|i| i.pre_verify(container))
+    }
+
+    /// Run post-parse hooks
+    pub fn post_parse(&self, container: &Container<&[u8]>) -> Result<(), Error> {
+        self.chain.iter().try_for_each(|i| i.post_parse(container))
+    }
+
+    /// Run pre-convert hooks
+    #[cfg(feature = "alloc")]
+    pub fn pre_convert(&self, header: &Header, public_options: &mut Vec<Options>) -> Result<(), Error> {
+        self.chain.iter().try_for_each(|i| i.pre_convert(header, public_options))
+    }
+}
+
+impl core::fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Interceptors").field("len", &self.chain.len()).finish()
+    }
+}
+
+impl PartialEq for Interceptors {
+    /// Interceptor chains are equal where they contain the same interceptor instances
+    fn eq(&self, other: &Self) -> bool {
+        self.chain.len() == other.chain.len()
+            && self.chain.iter().zip(other.chain.iter()).all(|(a, b)| core::ptr::eq(&**a as *const dyn Interceptor as *const u8, &**b as *const dyn Interceptor as *const u8))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::net::{Message, Request, RequestBody};
+    use crate::service::{Net, Publisher};
+    use crate::test_utils;
+
+    const TAG: &str = "tagged";
+
+    /// Attaches a name option to outgoing objects
+    struct Tagger;
+
+    impl Interceptor for Tagger {
+        fn pre_sign(&self, _id: &Id, _header: &Header, public_options: &mut Vec<Options>) -> Result<(), Error> {
+            public_options.push(Options::name(TAG));
+            Ok(())
+        }
+    }
+
+    /// Rejects incoming objects without a name option, strips limits prior to conversion
+    struct Policy;
+
+    impl Interceptor for Policy {
+        fn post_parse(&self, container: &Container<&[u8]>) -> Result<(), Error> {
+            match container.public_options_iter().any(|o| o == Options::name(TAG)) {
+                true => Ok(()),
+                false => Err(Error::InvalidOption),
+            }
+        }
+
+        fn pre_convert(&self, _header: &Header, public_options: &mut Vec<Options>) -> Result<(), Error> {
+            public_options.retain(|o| !matches!(o, Options::Limit(_)));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn intercept_encode_decode() {
+        let (mut source, target) = test_utils::service_pair();
+        let interceptors = Interceptors::new().with(Policy);
+
+        let req = Request::new(source.id(), test_utils::FIXTURE_REQUEST_ID, RequestBody::Hello, Flags::ADDRESS_REQUEST)
+            .with_limit(10);
+
+        // Untagged messages are rejected by policy
+        let enc = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        assert_eq!(Message::parse_with(enc.raw().to_vec(), &source.keys(), &interceptors).map(|(m, _n)| m),
+            Err(Error::InvalidOption));
+
+        // Tagged messages are accepted, with limits stripped
+        source.add_interceptor(Tagger);
+        assert_eq!(source.interceptors().len(), 1);
+
+        let enc = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        let (m, _n) = Message::parse_with(enc.raw().to_vec(), &source.keys(), &interceptors).unwrap();
+        assert!(matches!(m, Message::Request(r) if r.limit.is_none()));
+
+        // Which are otherwise retained
+        let (m, _n) = Message::parse(enc.raw().to_vec(), &source.keys()).unwrap();
+        assert!(matches!(m, Message::Request(r) if r.limit == Some(10)));
+
+        // Published objects are also tagged
+        let (_n, p) = source.publish_primary_buff(Default::default()).unwrap();
+        assert!(p.public_options_iter().any(|o| o == Options::name(TAG)));
+    }
+
+    /// Admits up to a fixed number of messages prior to verification
+    struct RateLimit(AtomicUsize);
+
+    impl Interceptor for RateLimit {
+        fn pre_verify(&self, _container: &Container<&[u8]>) -> Result<(), Error> {
+            self.0.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .map(|_| ())
+                .map_err(|_| Error::RateLimited)
+        }
+    }
+
+    #[test]
+    fn intercept_admission() {
+        let (source, target) = test_utils::service_pair();
+        let interceptors = Interceptors::new().with(RateLimit(AtomicUsize::new(1)));
+
+        let req = Request::new(source.id(), test_utils::FIXTURE_REQUEST_ID, RequestBody::Ping, Flags::empty());
+        let mut enc = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap().raw().to_vec();
+
+        assert!(Message::parse_with(enc.clone(), &source.keys(), &interceptors).is_ok());
+        assert_eq!(Message::parse_with(enc.clone(), &source.keys(), &interceptors).map(|(m, _n)| m),
+            Err(Error::RateLimited));
+        assert_eq!(Message::parse_ref_with_limits(&mut enc, &source.keys(), &interceptors, &Default::default()).map(|(m, _n)| m),
+            Err(Error::RateLimited));
+    }
+}
@@ -1,7 +1,9 @@
 
 
-use crate::types::{Id, PrivateKey, PublicKey, SecretKey};
-use crate::crypto::{Crypto, PubKey as _};
+use crate::types::{Clock, DateTime, Id, PrivateKey, PublicKey, SecretKey};
+use crate::crypto::{Crypto, PreparedKey, PubKey as _, Hash as _};
+use crate::error::Error;
+use crate::options::OptionKind;
 
 use core::str::FromStr;
 
@@ -73,6 +75,16 @@ impl Keys {
         }
     }
 
+    /// Fetch the service / peer ID for these keys, derived from the public key
+    /// (or private key where no public key is available)
+    pub fn id(&self) -> Option<Id> {
+        match (&self.pub_key, &self.pri_key) {
+            (Some(pub_key), _) => Some(Id::from_public_key(pub_key)),
+            (_, Some(pri_key)) => Some(Id::from_public_key(&Crypto::get_public(pri_key))),
+            _ => None,
+        }
+    }
+
     /// Derive encryption keys for the specified peer
     pub fn derive_peer(&self, peer_pub_key: PublicKey) -> Result<Keys, ()> {
         // Derivation requires our public key
@@ -93,6 +105,74 @@ impl Keys {
             sym_keys: Some(sym_keys),
         })
     }
+
+    /// Derive the subkey for sealed options of the provided kind from the secret key,
+    /// see [`SealedOption`](crate::options::SealedOption)
+    pub fn option_key(&self, kind: OptionKind) -> Option<SecretKey> {
+        let sec_key = self.sec_key.as_ref()?;
+        let k = Crypto::kdf_subkey(sec_key, kind as u64).ok()?;
+        Some(SecretKey::from(*k.as_bytes()))
+    }
+
+    /// Derive subkeys for the provided option kinds, for disclosure of only these options to a consumer
+    pub fn option_keys(&self, kinds: &[OptionKind]) -> Result<OptionKeys, Error> {
+        let mut keys = OptionKeys::default();
+        for k in kinds {
+            let key = self.option_key(*k).ok_or(Error::NoSecretKey)?;
+            keys.insert(*k, key)?;
+        }
+        Ok(keys)
+    }
+}
+
+/// Maximum number of keys held in [`OptionKeys`]
+pub const MAX_OPTION_KEYS: usize = 8;
+
+/// Disclosed subkeys for opening sealed options of specific kinds
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct OptionKeys {
+    keys: heapless::Vec<(OptionKind, SecretKey), MAX_OPTION_KEYS>,
+}
+
+impl OptionKeys {
+    /// Add (or replace) the subkey for an option kind
+    pub fn insert(&mut self, kind: OptionKind, key: SecretKey) -> Result<(), Error> {
+        match self.keys.iter_mut().find(|(k, _)| *k == kind) {
+            Some(e) => e.1 = key,
+            None => self.keys.push((kind, key)).map_err(|_| Error::TooManyOptions)?,
+        }
+        Ok(())
+    }
+
+    /// Fetch the subkey for an option kind
+    pub fn get(&self, kind: OptionKind) -> Option<&SecretKey> {
+        self.keys.iter().find(|(k, _)| *k == kind).map(|(_, v)| v)
+    }
+
+    /// Iterate over the option kinds with available subkeys
+    pub fn kinds(&self) -> impl Iterator<Item=OptionKind> + '_ {
+        self.keys.iter().map(|(k, _)| *k)
+    }
+}
+
+/// Source of subkeys for opening sealed options
+pub trait OptionKeySource {
+    /// Fetch the subkey for an option kind
+    fn option_key(&self, kind: OptionKind) -> Option<SecretKey>;
+}
+
+/// Full service keys derive subkeys for any option kind
+impl OptionKeySource for Keys {
+    fn option_key(&self, kind: OptionKind) -> Option<SecretKey> {
+        Keys::option_key(self, kind)
+    }
+}
+
+impl OptionKeySource for OptionKeys {
+    fn option_key(&self, kind: OptionKind) -> Option<SecretKey> {
+        self.get(kind).cloned()
+    }
 }
 
 pub trait KeySource: Sized {
@@ -114,19 +194,57 @@ pub trait KeySource: Sized {
         self.keys(id).map(|k| k.sec_key ).flatten()
     }
 
+    /// Fetch a pre-parsed verification key (optional).
+    ///
+    /// Implementations MUST only return keys matching the provided ID,
+    /// as the ID / public key check is skipped when this is available.
+    fn verifier(&self, _id: &Id) -> Option<PreparedKey> {
+        None
+    }
+
     /// Update keys for the specified ID (optional)
     fn update<F: FnMut(&mut Keys)>(&mut self, _id: &Id, _f: F) -> bool {
         false
     }
 
-    /// Build cached keystore wrapper
-    fn cached(&self, existing: Option<(Id, Keys)>) -> CachedKeySource<Self> {
-        CachedKeySource {
+    /// Fetch the current time for time-dependent validation (for example, delegation expiry).
+    ///
+    /// This uses the system clock where available, without `std` a clock may be provided
+    /// with [`KeySource::with_clock`]. Time-dependent checks fail where no time is available.
+    fn now(&self) -> Option<DateTime> {
+        #[cfg(feature="std")]
+        return Some(DateTime::now());
+
+        #[cfg(not(feature="std"))]
+        return None;
+    }
+
+    /// Build a key source wrapper falling back to the provided keys where the inner
+    /// key source has no keys for an ID (for example, keys from a previously decoded page)
+    fn with_fallback(&self, existing: Option<(Id, Keys)>) -> FallbackKeySource<Self> {
+        FallbackKeySource {
             key_source: self,
             cached: existing,
         }
     }
 
+    /// Build a key source wrapper returning keys only for IDs accepted by the provided filter
+    /// (for example, services belonging to a tenant)
+    fn scoped<F: Fn(&Id) -> bool>(&self, filter: F) -> ScopedKeySource<'_, Self, F> {
+        ScopedKeySource {
+            key_source: self,
+            filter,
+        }
+    }
+
+    /// Build a key source wrapper providing the current time from the provided clock
+    fn with_clock<C: Clock>(&self, clock: C) -> ClockedKeySource<'_, Self, C> {
+        ClockedKeySource {
+            key_source: self,
+            clock,
+        }
+    }
+
     /// Build null keystore implementation
     fn null() -> NullKeySource {
         NullKeySource
@@ -162,13 +280,13 @@ impl KeySource for Option<SecretKey> {
 }
 
 
-/// Wrapper to cache a KeySource with a value for immediate lookup
-pub struct CachedKeySource<'a, K: KeySource + Sized> {
+/// Wrapper extending a KeySource with a fallback value for immediate lookup
+pub struct FallbackKeySource<'a, K: KeySource + Sized> {
     key_source: &'a K,
     cached: Option<(Id, Keys)>,
 }
 
-impl<'a, K: KeySource + Sized> KeySource for CachedKeySource<'a, K> {
+impl<'a, K: KeySource + Sized> KeySource for FallbackKeySource<'a, K> {
     fn keys(&self, id: &Id) -> Option<Keys> {
         if let Some(k) = self.key_source.keys(id) {
             return Some(k);
@@ -179,6 +297,61 @@ impl<'a, K: KeySource + Sized> KeySource for CachedKeySource<'a, K> {
             _ => None,
         }
     }
+
+    fn verifier(&self, id: &Id) -> Option<PreparedKey> {
+        self.key_source.verifier(id)
+    }
+
+    fn now(&self) -> Option<DateTime> {
+        self.key_source.now()
+    }
+}
+
+/// Wrapper to scope a KeySource to a subset of IDs, for isolating key lookups
+/// between tenants sharing a key store
+pub struct ScopedKeySource<'a, K: KeySource + Sized, F: Fn(&Id) -> bool> {
+    key_source: &'a K,
+    filter: F,
+}
+
+impl<'a, K: KeySource + Sized, F: Fn(&Id) -> bool> KeySource for ScopedKeySource<'a, K, F> {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        match (self.filter)(id) {
+            true => self.key_source.keys(id),
+            false => None,
+        }
+    }
+
+    fn verifier(&self, id: &Id) -> Option<PreparedKey> {
+        match (self.filter)(id) {
+            true => self.key_source.verifier(id),
+            false => None,
+        }
+    }
+
+    fn now(&self) -> Option<DateTime> {
+        self.key_source.now()
+    }
+}
+
+/// Wrapper providing the current time for a KeySource from a [`Clock`]
+pub struct ClockedKeySource<'a, K: KeySource + Sized, C: Clock> {
+    key_source: &'a K,
+    clock: C,
+}
+
+impl<'a, K: KeySource + Sized, C: Clock> KeySource for ClockedKeySource<'a, K, C> {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        self.key_source.keys(id)
+    }
+
+    fn verifier(&self, id: &Id) -> Option<PreparedKey> {
+        self.key_source.verifier(id)
+    }
+
+    fn now(&self) -> Option<DateTime> {
+        Some(self.clock.now())
+    }
 }
 
 /// Null key source implementation contains no keys
@@ -195,4 +368,455 @@ impl KeySource for std::collections::HashMap<Id, Keys> {
     fn keys(&self, id: &Id) -> Option<Keys> {
         self.get(id).cloned()
     }
-}
\ No newline at end of file
+}
+
+/// Key source caching pre-parsed verification keys (and derived symmetric keys) by ID,
+/// for high-throughput paths where the same peers are verified repeatedly
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct VerifierCache {
+    entries: std::collections::HashMap<Id, (Keys, Option<PreparedKey>)>,
+}
+
+#[cfg(feature = "std")]
+impl VerifierCache {
+    /// Create an empty verifier cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert keys for the provided ID, checking the ID matches the public key
+    /// and pre-parsing this for verification
+    pub fn insert(&mut self, id: Id, keys: Keys) -> Result<(), Error> {
+        let prepared = Self::prepare(&id, &keys)?;
+        self.entries.insert(id, (keys, prepared));
+        Ok(())
+    }
+
+    /// Remove keys for the provided ID
+    pub fn remove(&mut self, id: &Id) -> Option<Keys> {
+        self.entries.remove(id).map(|(k, _)| k)
+    }
+
+    /// Fetch the number of cached entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn prepare(id: &Id, keys: &Keys) -> Result<Option<PreparedKey>, Error> {
+        let pub_key = match &keys.pub_key {
+            Some(k) => k,
+            None => return Ok(None),
+        };
+
+        // Check ID matches public key
+        if id != &Id::from_public_key(pub_key) {
+            return Err(Error::KeyIdMismatch);
+        }
+
+        Crypto::pk_prepare(pub_key).map(Some).map_err(|_e| Error::CryptoError)
+    }
+}
+
+#[cfg(feature = "std")]
+impl KeySource for VerifierCache {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        self.entries.get(id).map(|(k, _)| k.clone())
+    }
+
+    fn verifier(&self, id: &Id) -> Option<PreparedKey> {
+        self.entries.get(id).and_then(|(_, v)| v.clone())
+    }
+
+    fn update<F: FnMut(&mut Keys)>(&mut self, id: &Id, mut f: F) -> bool {
+        let (keys, prepared) = match self.entries.get_mut(id) {
+            Some(e) => e,
+            None => return false,
+        };
+
+        f(keys);
+
+        // Re-parse on public key changes, dropping the entry if this is no longer valid
+        match Self::prepare(id, keys) {
+            Ok(p) => *prepared = p,
+            Err(_) => {
+                self.entries.remove(id);
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(feature = "std")]
+type KeyMap = std::collections::HashMap<Id, Keys>;
+
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+type KeyMap = alloc::collections::BTreeMap<Id, Keys>;
+
+/// In-memory key store, backed by a `HashMap` (or `BTreeMap` where `std` is not available)
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Default)]
+pub struct MemoryKeyStore {
+    entries: KeyMap,
+}
+
+#[cfg(feature = "alloc")]
+impl MemoryKeyStore {
+    /// Create an empty key store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert keys for the provided ID, returning any existing keys
+    pub fn insert(&mut self, id: Id, keys: Keys) -> Option<Keys> {
+        self.entries.insert(id, keys)
+    }
+
+    /// Remove keys for the provided ID
+    pub fn remove(&mut self, id: &Id) -> Option<Keys> {
+        self.entries.remove(id)
+    }
+
+    /// Fetch the number of stored entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl KeySource for MemoryKeyStore {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        self.entries.get(id).cloned()
+    }
+
+    fn update<F: FnMut(&mut Keys)>(&mut self, id: &Id, mut f: F) -> bool {
+        match self.entries.get_mut(id) {
+            Some(k) => {
+                f(k);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// Fixed-capacity key store for `no_std` use, holding up to `N` entries
+#[derive(Clone, Debug, Default)]
+pub struct HeaplessKeyStore<const N: usize> {
+    entries: heapless::Vec<(Id, Keys), N>,
+}
+
+impl<const N: usize> HeaplessKeyStore<N> {
+    /// Create an empty key store
+    pub fn new() -> Self {
+        Self { entries: heapless::Vec::new() }
+    }
+
+    /// Insert keys for the provided ID, returning any existing keys
+    /// or [`Error::BufferLength`] where the store is full
+    pub fn insert(&mut self, id: Id, keys: Keys) -> Result<Option<Keys>, Error> {
+        if let Some((_, k)) = self.entries.iter_mut().find(|(i, _)| i == &id) {
+            return Ok(Some(core::mem::replace(k, keys)));
+        }
+
+        self.entries.push((id, keys)).map_err(|_| Error::BufferLength)?;
+
+        Ok(None)
+    }
+
+    /// Remove keys for the provided ID
+    pub fn remove(&mut self, id: &Id) -> Option<Keys> {
+        let i = self.entries.iter().position(|(i, _)| i == id)?;
+        Some(self.entries.swap_remove(i).1)
+    }
+
+    /// Fetch the number of stored entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<const N: usize> KeySource for HeaplessKeyStore<N> {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        self.entries.iter().find(|(i, _)| i == id).map(|(_, k)| k.clone())
+    }
+
+    fn update<F: FnMut(&mut Keys)>(&mut self, id: &Id, mut f: F) -> bool {
+        match self.entries.iter_mut().find(|(i, _)| i == id) {
+            Some((_, k)) => {
+                f(k);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+/// Cached keys and verifier for an ID, see [`CachedKeySource`]
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    id: Id,
+    keys: Option<Keys>,
+    verifier: Option<PreparedKey>,
+}
+
+/// Wrapper caching keys and verifiers for up to `N` recently used IDs from the inner key source,
+/// for key sources with expensive lookups (databases, remote stores, etc.).
+///
+/// Least recently used entries are evicted once the cache is full. The cache is held behind a
+/// spin lock (released during inner lookups) so wrappers may be shared between threads.
+pub struct CachedKeySource<K: KeySource, const N: usize> {
+    key_source: K,
+    cache: spin::Mutex<heapless::Vec<CacheEntry, N>>,
+}
+
+impl<K: KeySource, const N: usize> CachedKeySource<K, N> {
+    /// Create a new caching wrapper over the provided key source
+    pub fn new(key_source: K) -> Self {
+        Self {
+            key_source,
+            cache: spin::Mutex::new(heapless::Vec::new()),
+        }
+    }
+
+    /// Fetch the inner key source
+    pub fn inner(&self) -> &K {
+        &self.key_source
+    }
+
+    /// Consume the wrapper, returning the inner key source
+    pub fn into_inner(self) -> K {
+        self.key_source
+    }
+
+    /// Drop all cached entries
+    pub fn clear(&self) {
+        self.cache.lock().clear();
+    }
+
+    /// Fetch the number of cached entries
+    pub fn cached_len(&self) -> usize {
+        self.cache.lock().len()
+    }
+
+    /// Fetch a cached value, moving hits to the back of the cache (most recently used)
+    fn get<R>(&self, id: &Id, f: impl Fn(&CacheEntry) -> Option<R>) -> Option<R> {
+        let mut cache = self.cache.lock();
+
+        let i = cache.iter().position(|e| &e.id == id)?;
+        let e = cache.remove(i);
+        let r = f(&e);
+        let _ = cache.push(e);
+
+        r
+    }
+
+    /// Store a value fetched from the inner key source, evicting the least recently used entry when full
+    fn set(&self, id: &Id, f: impl Fn(&mut CacheEntry)) {
+        let mut cache = self.cache.lock();
+
+        if let Some(e) = cache.iter_mut().find(|e| &e.id == id) {
+            f(e);
+            return;
+        }
+
+        if N > 0 && cache.is_full() {
+            cache.remove(0);
+        }
+
+        let mut e = CacheEntry{ id: id.clone(), keys: None, verifier: None };
+        f(&mut e);
+        let _ = cache.push(e);
+    }
+}
+
+impl<K: KeySource, const N: usize> KeySource for CachedKeySource<K, N> {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        if let Some(k) = self.get(id, |e| e.keys.clone()) {
+            return Some(k);
+        }
+
+        let k = self.key_source.keys(id)?;
+        self.set(id, |e| e.keys = Some(k.clone()));
+
+        Some(k)
+    }
+
+    fn verifier(&self, id: &Id) -> Option<PreparedKey> {
+        if let Some(v) = self.get(id, |e| e.verifier.clone()) {
+            return Some(v);
+        }
+
+        let v = self.key_source.verifier(id)?;
+        self.set(id, |e| e.verifier = Some(v.clone()));
+
+        Some(v)
+    }
+
+    fn now(&self) -> Option<DateTime> {
+        self.key_source.now()
+    }
+
+    fn update<F: FnMut(&mut Keys)>(&mut self, id: &Id, f: F) -> bool {
+        // Invalidate cached entry so updated keys are re-fetched
+        let cache = self.cache.get_mut();
+        if let Some(i) = cache.iter().position(|e| &e.id == id) {
+            cache.remove(i);
+        }
+
+        self.key_source.update(id, f)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::crypto::SecKey as _;
+    use crate::test_utils;
+    use crate::types::AlgorithmSuite;
+    use crate::wire::Container;
+
+    #[test]
+    fn verifier_cache_parse() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let page = test_utils::primary_page(&mut s);
+
+        // Mismatched IDs are rejected
+        let mut cache = VerifierCache::new();
+        assert_eq!(
+            cache.insert(test_utils::id(test_utils::PEER_SEED), test_utils::keys(test_utils::SERVICE_SEED)),
+            Err(Error::KeyIdMismatch)
+        );
+
+        cache.insert(s.id(), test_utils::keys(test_utils::SERVICE_SEED)).unwrap();
+        assert!(cache.verifier(&s.id()).is_some());
+
+        let c = Container::parse(page.raw().to_vec(), &cache).expect("Error parsing with verifier cache");
+        assert!(c.verified);
+        assert_eq!(c.raw(), page.raw());
+
+        // Corrupted pages still fail verification
+        let mut d = page.raw().to_vec();
+        let n = d.len();
+        d[n - 1] ^= 0xFF;
+        assert!(Container::parse(d, &cache).is_err());
+    }
+
+    #[test]
+    fn key_stores() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let keys = test_utils::keys(test_utils::SERVICE_SEED);
+        let peer = test_utils::id(test_utils::PEER_SEED);
+
+        let mut memory = MemoryKeyStore::new();
+        assert_eq!(memory.insert(s.id(), keys.clone()), None);
+
+        let mut heapless = HeaplessKeyStore::<1>::new();
+        assert_eq!(heapless.insert(s.id(), keys.clone()), Ok(None));
+        assert_eq!(heapless.insert(peer.clone(), keys.clone()), Err(Error::BufferLength));
+
+        assert_eq!(memory.keys(&s.id()), Some(keys.clone()));
+        assert_eq!(heapless.keys(&s.id()), Some(keys.clone()));
+        assert_eq!(memory.keys(&peer), None);
+        assert_eq!(heapless.keys(&peer), None);
+
+        // Updates are applied to stored keys
+        let sec_key = Crypto::new_sk().unwrap();
+        assert!(heapless.update(&s.id(), |k| k.sec_key = Some(sec_key.clone())));
+        assert_eq!(heapless.sec_key(&s.id()), Some(sec_key.clone()));
+        assert!(!memory.update(&peer, |_k| ()));
+
+        // Cached lookups evict least recently used entries and are invalidated on update
+        memory.insert(peer.clone(), keys.clone());
+        let mut cached = CachedKeySource::<_, 1>::new(memory);
+        assert_eq!(cached.keys(&s.id()), Some(keys.clone()));
+        assert_eq!(cached.keys(&peer), Some(keys.clone()));
+        assert_eq!(cached.cached_len(), 1);
+
+        assert!(cached.update(&peer, |k| k.sec_key = Some(sec_key.clone())));
+        assert_eq!(cached.cached_len(), 0);
+        assert_eq!(cached.sec_key(&peer), Some(sec_key));
+
+        // Stores can be used directly for parsing
+        let page = test_utils::primary_page(&mut s);
+        Container::parse(page.raw().to_vec(), &cached.into_inner()).expect("Error parsing with memory key store");
+        Container::parse(page.raw().to_vec(), &heapless).expect("Error parsing with heapless key store");
+    }
+
+    #[test]
+    fn derive_ids() {
+        let s = test_utils::service(test_utils::SERVICE_SEED);
+        let keys = test_utils::keys(test_utils::SERVICE_SEED);
+
+        // IDs derive from public keys, or private keys where no public key is available
+        assert_eq!(Id::from_public_key(&s.public_key()), s.id());
+        assert_eq!(keys.id(), Some(s.id()));
+
+        let pri_only = Keys{ pub_key: None, ..keys };
+        assert_eq!(pri_only.id(), Some(s.id()));
+        assert_eq!(Keys::default().id(), None);
+
+        // Suites without supported hash algorithms are rejected
+        assert_eq!(Id::from_public_key_with(AlgorithmSuite::BASE, &s.public_key()), Ok(s.id()));
+        assert_eq!(Id::from_public_key_with(AlgorithmSuite::SIGN_ED25519, &s.public_key()), Err(Error::UnsupportedSignatureMode));
+    }
+
+    // Verifier cache counting verifier lookups
+    struct CountingVerifiers(VerifierCache, core::sync::atomic::AtomicUsize);
+
+    impl KeySource for CountingVerifiers {
+        fn keys(&self, id: &Id) -> Option<Keys> {
+            self.0.keys(id)
+        }
+
+        fn verifier(&self, id: &Id) -> Option<PreparedKey> {
+            self.1.fetch_add(1, core::sync::atomic::Ordering::SeqCst);
+            self.0.verifier(id)
+        }
+    }
+
+    #[test]
+    fn cached_key_source_verifiers() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let page = test_utils::primary_page(&mut s);
+
+        let mut verifiers = VerifierCache::new();
+        verifiers.insert(s.id(), test_utils::keys(test_utils::SERVICE_SEED)).unwrap();
+
+        let cached = CachedKeySource::<_, 2>::new(CountingVerifiers(verifiers, Default::default()));
+
+        // Cached sources may be shared between threads
+        fn is_sync<T: Sync>(_t: &T) {}
+        is_sync(&cached);
+
+        // Verifiers are fetched from the inner source once, then served from the cache
+        let c = Container::parse(page.raw().to_vec(), &cached).expect("Error parsing with cached key source");
+        assert!(c.verified);
+        assert!(cached.verifier(&s.id()).is_some());
+        assert_eq!(cached.inner().1.load(core::sync::atomic::Ordering::SeqCst), 1);
+
+        // Missing verifiers are not cached
+        assert!(cached.verifier(&test_utils::id(test_utils::PEER_SEED)).is_none());
+        assert_eq!(cached.cached_len(), 1);
+
+        cached.clear();
+        assert_eq!(cached.cached_len(), 0);
+    }
+}
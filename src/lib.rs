@@ -34,6 +34,9 @@ extern crate bitflags;
 #[cfg(feature = "std")]
 extern crate chrono;
 
+#[macro_use]
+pub mod instrument;
+
 pub mod types;
 
 pub mod crypto;
@@ -56,8 +59,28 @@ pub mod api;
 
 pub mod prelude;
 
+#[cfg(feature = "alloc")]
+pub mod easy;
+
 pub mod error;
 
+pub mod interceptor;
+
+#[cfg(feature = "alloc")]
+pub mod tenant;
+
+#[cfg(feature = "did")]
+pub mod did;
+
+#[cfg(feature = "grind")]
+pub mod grind;
+
+#[cfg(all(feature = "alloc", any(test, feature = "test_utils")))]
+pub mod test_utils;
+
+#[cfg(test)]
+mod smoke;
+
 #[cfg(feature = "defmt")] 
 pub trait Debug = core::fmt::Debug + defmt::Format;
 
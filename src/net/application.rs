@@ -0,0 +1,102 @@
+//! Application-specific messages, allowing applications to build protocols atop the DSF
+//! message parse / verify path.
+//!
+//! Message objects with a non-zero application ID are verified as for DSF messages and returned
+//! from [`Message::convert`](super::Message::convert) as [`Message::Application`](super::Message::Application),
+//! with the raw body and options for decoding by the application.
+
+use core::ops::Deref;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::interceptor::Interceptors;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::Container;
+
+use super::Common;
+
+/// Application-specific message, containing the raw body and options alongside the verified envelope
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ApplicationMessage {
+    pub common: Common,
+    /// Application ID
+    pub application_id: u16,
+    /// Application message kind
+    pub kind: Kind,
+    /// Raw message body
+    pub body: Vec<u8>,
+    /// Public options (following pre-convert interceptors)
+    pub public_options: Vec<Options>,
+    /// Private options
+    pub private_options: Vec<Options>,
+    /// Verified message envelope
+    pub envelope: Container,
+}
+
+impl Deref for ApplicationMessage {
+    type Target = Common;
+
+    fn deref(&self) -> &Common {
+        &self.common
+    }
+}
+
+impl PartialEq for ApplicationMessage {
+    fn eq(&self, b: &Self) -> bool {
+        self.from == b.from && self.flags == b.flags && self.application_id == b.application_id
+            && self.kind == b.kind && self.body == b.body && self.envelope == b.envelope
+    }
+}
+
+impl ApplicationMessage {
+    /// Convert a verified container into an application message
+    pub fn convert<T: ImmutableData>(base: Container<T>) -> Result<Self, Error> {
+        Self::convert_with(base, &Interceptors::default())
+    }
+
+    /// Convert a verified container into an application message, applying the provided pre-convert interceptors.
+    ///
+    /// Returns [`Error::InvalidMessageType`] for DSF (application ID 0) or non-message objects,
+    /// [`Error::InvalidSignature`] for unverified containers, and [`Error::CryptoError`] for encrypted containers.
+    pub fn convert_with<T: ImmutableData>(base: Container<T>, interceptors: &Interceptors) -> Result<Self, Error> {
+        let header = base.header();
+        let application_id = header.application_id();
+        let kind = header.kind();
+
+        if application_id == 0 || !kind.is_message() {
+            debug!("Error converting object of kind {:?} (application: {}) to application message", kind, application_id);
+            return Err(Error::InvalidMessageType);
+        }
+
+        if !base.verified {
+            error!("Attempted to convert unverified container to application message");
+            return Err(Error::InvalidSignature);
+        }
+
+        if base.encrypted() {
+            error!("Attempted to convert encrypted container to application message");
+            return Err(Error::CryptoError);
+        }
+
+        let (common, public_options) = Common::parse(&base, interceptors)?;
+
+        Ok(Self {
+            common,
+            application_id,
+            kind,
+            body: base.try_body_raw()?.to_vec(),
+            public_options,
+            private_options: base.private_options_iter().collect(),
+            envelope: base.to_owned(),
+        })
+    }
+
+    /// Resolve the application message kind, checking the application ID and base kind
+    pub fn kind_as<K: KindConvert>(&self) -> Result<K, KindError> {
+        K::try_from_kind(self.application_id, self.kind)
+    }
+}
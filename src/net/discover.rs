@@ -0,0 +1,363 @@
+//! Discovery filters and replay-protected discovery.
+//!
+//! Discover requests carry a [`DiscoverFilter`] as public options, matching services by application ID,
+//! page kind, and option predicates (such as name prefix or metadata equality). Responders evaluate
+//! filters against candidate pages with [`DiscoverFilter::matches`] so matching semantics are shared
+//! between implementations.
+//!
+//! Captured Discover requests may be replayed to map networks, and captured responses replayed
+//! to claim a peer is present. Requesters attach a random challenge nonce with
+//! [`Request::with_new_nonce`], responders bind this in their (signed) response with
+//! [`Response::bind_nonce`], and requesters check responses with [`Response::check_nonce`]
+//! so responses captured from earlier exchanges are rejected.
+//!
+//! Nonces are carried in [`Options::Nonce`](crate::options::Options::Nonce) public options,
+//! covered by the message signature and so verified at parse.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use encdec::{Encode, Decode, DecodeExt};
+
+use crate::crypto::fill_random;
+use crate::error::Error;
+use crate::options::{Options, OptionString};
+use crate::types::*;
+use crate::wire::Container;
+
+use super::{Request, RequestBody, Response};
+
+/// Filter for Discover requests
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DiscoverFilter {
+    /// Application ID to match, `None` for all applications
+    pub application_id: Option<u16>,
+    /// Page kind to match, `None` for all page kinds
+    pub page_kind: Option<u16>,
+    /// Option predicates, all of which must match
+    pub predicates: Vec<DiscoverPredicate>,
+}
+
+/// Option predicate for discovery filters, evaluated against page public options
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DiscoverPredicate {
+    /// Service name starts with the provided prefix
+    NamePrefix(OptionString),
+    /// Page carries an option equal to the provided option (for example, a name, kind or metadata key:value)
+    Equals(Options),
+}
+
+impl DiscoverPredicate {
+    /// Evaluate the predicate against page options
+    pub fn matches(&self, mut options: impl Iterator<Item=Options>) -> bool {
+        match self {
+            DiscoverPredicate::NamePrefix(p) => options.any(|o| matches!(o, Options::Name(n) if n.as_ref().starts_with(p.as_ref()))),
+            DiscoverPredicate::Equals(e) => options.any(|o| &o == e),
+        }
+    }
+
+    fn option(&self) -> Options {
+        match self {
+            DiscoverPredicate::NamePrefix(p) => Options::NamePrefix(p.clone()),
+            DiscoverPredicate::Equals(o) => o.clone(),
+        }
+    }
+}
+
+impl DiscoverFilter {
+    /// Set the application ID
+    pub fn with_application_id(mut self, id: u16) -> Self {
+        self.application_id = Some(id);
+        self
+    }
+
+    /// Set the page kind
+    pub fn with_page_kind(mut self, kind: u16) -> Self {
+        self.page_kind = Some(kind);
+        self
+    }
+
+    /// Match services with the provided name
+    pub fn with_name(self, name: &str) -> Self {
+        self.with_option(Options::name(name))
+    }
+
+    /// Match services with names starting with the provided prefix
+    pub fn with_name_prefix(mut self, prefix: &str) -> Self {
+        self.predicates.push(DiscoverPredicate::NamePrefix(prefix.into()));
+        self
+    }
+
+    /// Match services with the provided kind
+    pub fn with_kind(self, kind: &str) -> Self {
+        self.with_option(Options::kind(kind))
+    }
+
+    /// Match services with the provided metadata key:value
+    pub fn with_metadata(self, key: &str, value: &str) -> Self {
+        self.with_option(Options::meta(key, value))
+    }
+
+    /// Match services carrying an option equal to that provided
+    pub fn with_option(mut self, option: Options) -> Self {
+        self.predicates.push(DiscoverPredicate::Equals(option));
+        self
+    }
+
+    /// Check whether a filter is empty (and thus matches all pages)
+    pub fn is_empty(&self) -> bool {
+        self.application_id.is_none() && self.page_kind.is_none() && self.predicates.is_empty()
+    }
+
+    /// Evaluate the filter against a page, returning true if this should be included in discovery results.
+    ///
+    /// Predicates are evaluated against public options only, other objects never match.
+    pub fn matches<T: ImmutableData>(&self, page: &Container<T>) -> bool {
+        let header = page.header();
+        let kind = header.kind();
+
+        // Filters only apply to pages
+        if !kind.is_page() {
+            return false;
+        }
+
+        // Check application ID
+        if let Some(id) = self.application_id {
+            if header.application_id() != id {
+                return false;
+            }
+        }
+
+        // Check page kind
+        if let Some(k) = self.page_kind {
+            if kind != Kind::page(k) {
+                return false;
+            }
+        }
+
+        // Check option predicates
+        self.predicates.iter().all(|p| p.matches(page.public_options_iter()))
+    }
+
+    /// Parse a filter from Discover request options, options not used by filters
+    /// are included as equality predicates
+    pub fn from_options<'a>(options: impl IntoIterator<Item=&'a Options>) -> Self {
+        let mut f = DiscoverFilter::default();
+
+        for o in options {
+            match o {
+                Options::ApplicationId(v) => f.application_id = Some(*v),
+                Options::PageKind(v) => f.page_kind = Some(*v),
+                Options::NamePrefix(v) => f.predicates.push(DiscoverPredicate::NamePrefix(v.clone())),
+                o => f.predicates.push(DiscoverPredicate::Equals(o.clone())),
+            }
+        }
+
+        f
+    }
+
+    /// Fetch the options encoding this filter
+    pub fn options(&self) -> impl Iterator<Item=Options> + '_ {
+        self.application_id.map(Options::application_id).into_iter()
+            .chain(self.page_kind.map(Options::page_kind))
+            .chain(self.predicates.iter().map(|p| p.option()))
+    }
+}
+
+impl Encode for DiscoverFilter {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        self.options().map(|o| o.encode_len()).sum()
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut n = 0;
+        for o in self.options() {
+            n += o.encode(&mut buff[n..])?;
+        }
+        Ok(n)
+    }
+}
+
+impl <'a> Decode<'a> for DiscoverFilter {
+    type Output = DiscoverFilter;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let options = Options::decode_iter(buff).collect::<Result<Vec<_>, _>>()?;
+        Ok((DiscoverFilter::from_options(&options), buff.len()))
+    }
+}
+
+impl RequestBody {
+    /// Create a Discover request body for the provided filter
+    pub fn discover(filter: &DiscoverFilter) -> Self {
+        RequestBody::Discover(vec![], filter.options().collect())
+    }
+
+    /// Fetch the filter for Discover requests
+    pub fn discover_filter(&self) -> Option<DiscoverFilter> {
+        match self {
+            RequestBody::Discover(_body, opts) => Some(DiscoverFilter::from_options(opts)),
+            _ => None,
+        }
+    }
+}
+
+/// Generate a new random challenge nonce
+pub fn new_nonce() -> Result<u64, Error> {
+    let mut b = [0u8; 8];
+    fill_random(&mut b).map_err(|_| Error::CryptoError)?;
+    Ok(u64::from_be_bytes(b))
+}
+
+impl <D> Request<D> {
+    /// Attach a new random challenge nonce to be bound in responses
+    pub fn with_new_nonce(self) -> Result<Self, Error> {
+        Ok(self.with_nonce(new_nonce()?))
+    }
+}
+
+impl Response {
+    /// Bind the challenge nonce (if any) from the provided request,
+    /// responders MUST bind nonces in responses to Discover requests
+    pub fn bind_nonce<D>(mut self, req: &Request<D>) -> Self {
+        self.common.nonce = req.common.nonce;
+        self
+    }
+
+    /// Check the response binds the challenge nonce issued in the provided request,
+    /// returning [`Error::InvalidNonce`] where the request carries no nonce or the nonces do not match
+    pub fn check_nonce<D>(&self, req: &Request<D>) -> Result<(), Error> {
+        match (req.common.nonce, self.nonce) {
+            (Some(a), Some(b)) if a == b => Ok(()),
+            (None, _) => {
+                debug!("Request {} carries no nonce", req.common.id);
+                Err(Error::InvalidNonce)
+            },
+            _ => {
+                debug!("Response nonce {:?} does not match request {}", self.nonce, req.common.id);
+                Err(Error::InvalidNonce)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::{Message, RequestBody, ResponseBody};
+    use crate::options::Options;
+    use crate::service::{Net, Publisher, ServiceBuilder};
+    use crate::test_utils;
+
+    #[test]
+    fn discover_nonce_challenge() {
+        let (source, target) = test_utils::service_pair();
+
+        // Requesters attach a nonce alongside discovery filters
+        let req = Request::new(source.id(), 1, RequestBody::Discover(vec![], vec![Options::kind("sensor")]), Flags::empty())
+            .with_new_nonce().unwrap();
+        let c = source.encode_request_alloc(&req, &target.keys()).unwrap();
+
+        let r = match Message::parse(c.raw().to_vec(), &source.keys()).unwrap() {
+            (Message::Request(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        assert_eq!(r.nonce, req.nonce);
+        assert_eq!(r.data, req.data);
+
+        // Responders bind the nonce in the signed response
+        let resp = Response::new(target.id(), r.id, ResponseBody::NoResult, Flags::empty()).bind_nonce(&r);
+        let c = target.encode_response_alloc(&resp, &source.keys()).unwrap();
+
+        let resp = match Message::parse(c.raw().to_vec(), &target.keys()).unwrap() {
+            (Message::Response(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        assert_eq!(resp.check_nonce(&req), Ok(()));
+
+        // Responses do not validate against later requests
+        let next = Request::new(source.id(), 2, req.data.clone(), Flags::empty()).with_new_nonce().unwrap();
+        assert_eq!(resp.check_nonce(&next), Err(Error::InvalidNonce));
+
+        // Unbound responses and requests without nonces are rejected
+        let unbound = Response::new(target.id(), r.id, ResponseBody::NoResult, Flags::empty());
+        assert_eq!(unbound.check_nonce(&req), Err(Error::InvalidNonce));
+        let plain = Request::new(source.id(), 3, req.data.clone(), Flags::empty());
+        assert_eq!(resp.check_nonce(&plain), Err(Error::InvalidNonce));
+
+        // Nonces are covered by the response signature
+        let nonce = req.nonce.unwrap().to_be_bytes();
+        let mut b = c.raw().to_vec();
+        let i = b.windows(nonce.len()).position(|w| w == nonce).unwrap();
+        b[i] ^= 0xff;
+        assert!(Message::parse(b, &target.keys()).is_err());
+    }
+
+    #[test]
+    fn discover_rejects_common_field_filters() {
+        let (source, target) = test_utils::service_pair();
+
+        // Options decoded as common message fields can not be used as filters
+        for o in [Options::pub_key(source.public_key()), Options::limit(4)] {
+            let req = Request::new(source.id(), 1, RequestBody::Discover(vec![], vec![Options::kind("sensor"), o]), Flags::empty());
+            assert_eq!(source.encode_request_alloc(&req, &target.keys()).err(), Some(Error::InvalidOption));
+        }
+    }
+
+    #[test]
+    fn discover_filter_matching() {
+        let publish = |app: u16, kind: PageKind, opts: Vec<Options>| {
+            let mut s = ServiceBuilder::<Vec<u8>>::default().application_id(app).kind(kind).public_options(opts)
+                .keys(test_utils::keys(test_utils::SERVICE_SEED)).build().unwrap();
+            let (_n, c) = s.publish_primary_buff(Default::default()).unwrap();
+            c.to_owned()
+        };
+
+        let p1 = publish(1, PageKind::Generic, vec![Options::name("sensor-kitchen"), Options::meta("floor", "1")]);
+        let p2 = publish(1, PageKind::Generic, vec![Options::name("sensor-garage"), Options::meta("floor", "0")]);
+        let p3 = publish(2, PageKind::Peer, vec![Options::name("light-kitchen"), Options::kind("light")]);
+
+        let f = DiscoverFilter::default();
+        assert!(f.is_empty());
+        assert!(f.matches(&p1) && f.matches(&p2) && f.matches(&p3));
+
+        let f = DiscoverFilter::default().with_application_id(1);
+        assert!(f.matches(&p1) && f.matches(&p2) && !f.matches(&p3));
+
+        let f = DiscoverFilter::default().with_page_kind(PageKind::Peer as u16);
+        assert!(!f.matches(&p1) && !f.matches(&p2) && f.matches(&p3));
+
+        let f = DiscoverFilter::default().with_name_prefix("sensor-");
+        assert!(f.matches(&p1) && f.matches(&p2) && !f.matches(&p3));
+
+        let f = DiscoverFilter::default().with_name_prefix("sensor-").with_metadata("floor", "0");
+        assert!(!f.matches(&p1) && f.matches(&p2) && !f.matches(&p3));
+
+        let f = DiscoverFilter::default().with_kind("light").with_name("light-kitchen");
+        assert!(!f.matches(&p1) && !f.matches(&p2) && f.matches(&p3));
+
+        // Filters roundtrip via encode / decode
+        let f = DiscoverFilter::default().with_application_id(1).with_page_kind(PageKind::Generic as u16)
+            .with_name_prefix("sensor-").with_metadata("floor", "0");
+        let mut buff = [0u8; 256];
+        let n = f.encode(&mut buff).unwrap();
+        assert_eq!(DiscoverFilter::decode(&buff[..n]), Ok((f.clone(), n)));
+
+        // And via Discover requests
+        let (source, target) = test_utils::service_pair();
+        let req = Request::new(source.id(), 1, RequestBody::discover(&f), Flags::empty()).with_new_nonce().unwrap();
+        let c = source.encode_request_alloc(&req, &target.keys()).unwrap();
+
+        let r = match Message::parse(c.raw().to_vec(), &source.keys()).unwrap() {
+            (Message::Request(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        assert_eq!(r.data.discover_filter(), Some(f));
+        assert_eq!(RequestBody::Ping.discover_filter(), None);
+    }
+}
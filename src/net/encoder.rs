@@ -0,0 +1,425 @@
+//! Standalone message encoding, allowing requests and responses to be constructed and signed
+//! without a [`Service`](crate::service::Service) instance, for example on DHT relay nodes
+//! holding only their own and peer keys.
+//!
+//! Services encode messages using [`Net`](crate::service::Net), which wraps a [`MessageEncoder`]
+//! with the service ID, private key and interceptors.
+
+use encdec::Encode;
+
+use crate::base::{Empty, Header, body::put_u32};
+use crate::error::Error;
+use crate::interceptor::Interceptors;
+use crate::keys::Keys;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::{Builder, Container, builder::{Init, SetPrivateOptions, SetPublicOptions}};
+
+#[cfg(feature = "alloc")]
+use alloc::vec;
+
+use super::{Request, RequestBody, RequestBodyRef, RequestBodyFixed, Response, ResponseBody, ResponseBodyFixed, Common, PeerEntry, padding::padding_for, request::is_discover_filter};
+
+impl Request {
+    /// Encode and sign the request as the sender (`from`), using the sender private key
+    /// (or symmetric keys in symmetric mode) from the provided keys
+    pub fn encode<B: MutableData>(&self, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        MessageEncoder::from_keys(&self.common.from, keys).encode_request(self, keys, buff)
+    }
+}
+
+impl Response {
+    /// Encode and sign the response as the sender (`from`), using the sender private key
+    /// (or symmetric keys in symmetric mode) from the provided keys
+    pub fn encode<B: MutableData>(&self, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        MessageEncoder::from_keys(&self.common.from, keys).encode_response(self, keys, buff)
+    }
+}
+
+/// Message encoder, signing messages with the provided sender ID and private key
+#[derive(Clone)]
+pub struct MessageEncoder<'a> {
+    pub(crate) id: &'a Id,
+    pub(crate) private_key: Option<&'a PrivateKey>,
+    pub(crate) interceptors: Option<&'a Interceptors>,
+}
+
+impl <'a> MessageEncoder<'a> {
+    /// Create a new encoder for the provided sender ID and private key
+    pub fn new(id: &'a Id, private_key: &'a PrivateKey) -> Self {
+        Self { id, private_key: Some(private_key), interceptors: None }
+    }
+
+    /// Create a new encoder for the provided sender ID using the private key from the provided keys (if available),
+    /// messages may only be encoded in symmetric mode where no private key is available
+    pub fn from_keys(id: &'a Id, keys: &'a Keys) -> Self {
+        Self { id, private_key: keys.pri_key.as_ref(), interceptors: None }
+    }
+
+    /// Apply the provided pre-sign interceptors to encoded messages
+    pub fn with_interceptors(mut self, interceptors: &'a Interceptors) -> Self {
+        self.interceptors = Some(interceptors);
+        self
+    }
+
+    /// Encode and sign a request using the provided peer keys and buffer
+    pub fn encode_request<B: MutableData>(&self, req: &Request, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        // Attach discovery filter options
+        let options: &[Options] = match &req.data {
+            RequestBody::Discover(_body, opts) => {
+                check_discover_filters(opts)?;
+                &opts[..]
+            },
+            _ => &[],
+        };
+
+        let kind = Kind::from(RequestKind::from(&req.data));
+
+        self.encode_message(kind, &req.common, options, keys, buff, |b| Ok(match &req.data {
+            RequestBody::Hello | RequestBody::Ping => b.body(Empty)?,
+            RequestBody::FindNode(id) | RequestBody::FindValue(id) | RequestBody::Subscribe(id) | RequestBody::Unsubscribe(id) | RequestBody::Query(id) | RequestBody::Locate(id) | RequestBody::Unregister(id) => b.body(id.as_ref())?,
+            RequestBody::Store(id, pages) | RequestBody::PushData(id, pages) | RequestBody::Register(id, pages) => {
+                b.with_body(|buff| put_pages(id, pages, buff))?
+            },
+            RequestBody::Discover(body, _opts) => {
+                b.body(body.as_slice())?
+            },
+            RequestBody::SubscribeFiltered(id, filter) => {
+                b.with_body(|buff| {
+                    let mut n = id.encode(buff)?;
+                    n += filter.encode(&mut buff[n..])?;
+                    Ok(n)
+                })?
+            },
+            RequestBody::PeerExchange(peers) => b.with_body(|buff| PeerEntry::encode_list(peers, buff))?,
+        }))
+    }
+
+    /// Encode and sign a page-carrying request by reference, writing pages directly from the provided containers
+    pub fn encode_request_ref<T: ImmutableData, B: MutableData>(&self, req: &Request<RequestBodyRef<T>>, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        let kind = Kind::from(RequestKind::from(&req.data));
+
+        // Encode ID and pages directly from the referenced containers
+        self.encode_message(kind, &req.common, &[], keys, buff, |b| {
+            b.with_body(|buff| put_pages(req.data.id(), req.data.pages(), buff))
+        })
+    }
+
+    /// Encode and sign a request with a fixed-capacity body
+    pub fn encode_request_fixed<B: MutableData, const N: usize>(&self, req: &Request<RequestBodyFixed<'_, N>>, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        // Attach discovery filter options
+        let options: &[Options] = match &req.data {
+            RequestBodyFixed::Discover(_body, opts) => {
+                check_discover_filters(opts)?;
+                &opts[..]
+            },
+            _ => &[],
+        };
+
+        let kind = Kind::from(RequestKind::from(&req.data));
+
+        self.encode_message(kind, &req.common, options, keys, buff, |b| Ok(match &req.data {
+            RequestBodyFixed::Hello | RequestBodyFixed::Ping => b.body(Empty)?,
+            RequestBodyFixed::FindNode(id) | RequestBodyFixed::FindValue(id) | RequestBodyFixed::Subscribe(id) | RequestBodyFixed::Unsubscribe(id) | RequestBodyFixed::Query(id) | RequestBodyFixed::Locate(id) | RequestBodyFixed::Unregister(id) => b.body(id.as_ref())?,
+            RequestBodyFixed::Store(id, pages) | RequestBodyFixed::PushData(id, pages) | RequestBodyFixed::Register(id, pages) => {
+                b.with_body(|buff| put_pages(id, pages, buff))?
+            },
+            RequestBodyFixed::SubscribeFiltered(id, filter) => b.with_body(|buff| {
+                let n = id.encode(buff)?;
+                Ok(n + put_raw(filter, &mut buff[n..])?)
+            })?,
+            RequestBodyFixed::Discover(body, _opts) => b.body(*body)?,
+            RequestBodyFixed::PeerExchange(peers) => b.with_body(|buff| put_raw(peers, buff))?,
+        }))
+    }
+
+    /// Encode and sign a response using the provided peer keys and buffer
+    pub fn encode_response<B: MutableData>(&self, resp: &Response, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        let kind = Kind::from(ResponseKind::from(&resp.data));
+
+        self.encode_message(kind, &resp.common, &[], keys, buff, |b| Ok(match &resp.data {
+            ResponseBody::Status(status) => b.with_body(|buff| put_u32(buff, status.into()))?,
+            ResponseBody::StatusDetail(status, detail) => b.with_body(|buff| {
+                let n = put_u32(buff, status.into())?;
+                Ok(n + detail.encode(&mut buff[n..])?)
+            })?,
+            ResponseBody::NodesFound(id, nodes) => b.with_body(|buff| put_nodes(id, nodes, buff))?,
+            ResponseBody::ValuesFound(id, pages) | ResponseBody::PullData(id, pages) | ResponseBody::Located(id, pages) => {
+                b.with_body(|buff| put_pages(id, pages, buff))?
+            },
+            ResponseBody::NoResult => b.body(Empty)?,
+            ResponseBody::PeerExchange(peers) => b.with_body(|buff| PeerEntry::encode_list(peers, buff))?,
+            ResponseBody::Registered(id, sigs) => b.with_body(|buff| put_signatures(id, sigs, buff))?,
+            ResponseBody::Discovered(matches) => b.with_body(|buff| {
+                put_discovered(matches.iter().map(|(id, opts)| (id, &opts[..])), buff)
+            })?,
+        }))
+    }
+
+    /// Encode and sign a response with a fixed-capacity body
+    pub fn encode_response_fixed<B: MutableData, const N: usize>(&self, resp: &Response<ResponseBodyFixed<'_, N>>, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        let kind = Kind::from(ResponseKind::from(&resp.data));
+
+        self.encode_message(kind, &resp.common, &[], keys, buff, |b| Ok(match &resp.data {
+            ResponseBodyFixed::Status(status) => b.with_body(|buff| put_u32(buff, status.into()))?,
+            ResponseBodyFixed::StatusDetail(status, detail) => b.with_body(|buff| {
+                let n = put_u32(buff, status.into())?;
+                Ok(n + put_raw(detail, &mut buff[n..])?)
+            })?,
+            ResponseBodyFixed::NodesFound(id, nodes) => b.with_body(|buff| put_nodes(id, nodes, buff))?,
+            ResponseBodyFixed::ValuesFound(id, pages) | ResponseBodyFixed::PullData(id, pages) | ResponseBodyFixed::Located(id, pages) => {
+                b.with_body(|buff| put_pages(id, pages, buff))?
+            },
+            ResponseBodyFixed::NoResult => b.body(Empty)?,
+            ResponseBodyFixed::PeerExchange(peers) => b.with_body(|buff| put_raw(peers, buff))?,
+            ResponseBodyFixed::Registered(id, sigs) => b.with_body(|buff| put_signatures(id, sigs, buff))?,
+            ResponseBodyFixed::Discovered(matches) => b.with_body(|buff| {
+                put_discovered(matches.iter().map(|(id, opts)| (id, &opts[..])), buff)
+            })?,
+        }))
+    }
+
+    /// Encode and sign a message of the provided kind, writing the body with the provided function
+    /// and attaching the provided public options ahead of common message options
+    fn encode_message<B: MutableData>(
+        &self,
+        kind: Kind,
+        common: &Common,
+        options: &[Options],
+        keys: &Keys,
+        buff: B,
+        body: impl FnOnce(Builder<Init, B>) -> Result<Builder<SetPrivateOptions, B>, Error>,
+    ) -> Result<Container<B>, Error> {
+        // Create generic header
+        let header = Header {
+            kind,
+            flags: common.flags,
+            index: common.id,
+            ..Default::default()
+        };
+
+        // Setup builder
+        let b = Builder::new(buff)
+            .id(self.id)
+            .header(&header)?;
+
+        // Encode body
+        let b = body(b)?;
+
+        // Attach options
+        let b = b.private_options(&[])?
+            .public()
+            .public_options(options.iter())?;
+
+        // Sign/encrypt object using provided keying
+        self.finalise(common.flags, common, keys, b)
+    }
+
+    /// Attach common message options, then sign (or encrypt in symmetric mode) the message
+    pub fn finalise<T: MutableData>(&self, flags: Flags, common: &Common, keys: &Keys, mut b: Builder<SetPublicOptions, T> ) -> Result<Container<T>, Error> {
+
+        // Append public key if required
+        if let Some(pk) = &common.public_key {
+            b.public_option(&Options::pub_key(pk.clone()))?;
+        }
+
+        // Append local and observed remote addresses if provided
+        if let Some(addr) = &common.local_address {
+            b.public_option(&Options::address_request(*addr))?;
+        }
+        if let Some(addr) = &common.remote_address {
+            b.public_option(&Options::address_response(*addr))?;
+        }
+
+        // Append paging options if provided
+        if let Some(limit) = common.limit {
+            b.public_option(&Options::limit(limit))?;
+        }
+        if let Some(token) = &common.continuation {
+            b.public_option(&Options::continuation(token.clone()))?;
+        }
+
+        // Append algorithm suite if provided
+        if let Some(suite) = common.algorithms {
+            b.public_option(&Options::algorithms(suite))?;
+        }
+
+        // Append challenge nonce if provided
+        if let Some(nonce) = common.nonce {
+            b.public_option(&Options::nonce(nonce))?;
+        }
+
+        // Append flow-control options if provided
+        if let Some(pacing) = &common.pacing {
+            for o in pacing.options() {
+                b.public_option(&o)?;
+            }
+        }
+
+        // Append transport hints if provided
+        if let Some(hints) = &common.transport {
+            for o in hints.options() {
+                b.public_option(&o)?;
+            }
+        }
+
+        // Apply interceptors
+        self.intercept(&mut b)?;
+
+        // Pad to the requested length (following all other options) if enabled
+        if let Some(o) = common.padding.and_then(|len| padding_for(b.as_ref().len(), len)) {
+            b.public_option(&o)?;
+        }
+
+        // Sign/encrypt object using provided keying
+        let c = if !flags.contains(Flags::SYMMETRIC_MODE) {
+            // Public key mode, no KX required
+
+            // Check we have a private key to sign with 
+            let private_key = match self.private_key {
+                Some(k) => k,
+                None => return Err(Error::NoPrivateKey),
+            };
+
+            // Perform signing
+            b.sign_pk(private_key)?
+
+        } else {
+            // Secret key mode, available following KX
+
+            // Derive key by direction
+            let sec_key = match &keys.sym_keys {
+                Some(k) if flags.contains(Flags::SYMMETRIC_DIR) => &k.1,
+                Some(k) => &k.0,
+                _ => {
+                    error!("Attempted to sign object with no secret key");
+                    return Err(Error::NoSecretKey);
+                },
+            };
+
+            // Sign/Encrypt (AEAD) using secret key
+            b.encrypt_sk(sec_key)?
+        };
+
+        Ok(c)
+    }
+
+    /// Apply pre-sign interceptors, attaching any generated public options to the builder
+    fn intercept<T: MutableData>(&self, b: &mut Builder<SetPublicOptions, T>) -> Result<(), Error> {
+        let interceptors = match self.interceptors {
+            Some(i) if !i.is_empty() => i,
+            _ => return Ok(()),
+        };
+
+        let header = Header::from(&b.header_ref());
+
+        let mut options = vec![];
+        interceptors.pre_sign(self.id, &header, &mut options)?;
+
+        for o in &options {
+            b.public_option(o)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write a target ID followed by encoded pages
+fn put_pages<T: ImmutableData>(id: &Id, pages: &[Container<T>], buff: &mut [u8]) -> Result<usize, Error> {
+    let n = id.encode(buff)?;
+    Ok(n + Container::encode_pages(pages, &mut buff[n..])?)
+}
+
+/// Write a target ID followed by node entries, each encoded as peer ID, address and public key options
+fn put_nodes(id: &Id, nodes: &[(Id, Address, PublicKey)], buff: &mut [u8]) -> Result<usize, Error> {
+    let mut i = id.encode(buff)?;
+    for n in nodes {
+        i += [
+            Options::peer_id(n.0.clone()),
+            Options::address(n.1),
+            Options::pub_key(n.2.clone())
+        ].encode(&mut buff[i..])?;
+    }
+    Ok(i)
+}
+
+/// Write a target ID followed by signatures
+fn put_signatures(id: &Id, sigs: &[Signature], buff: &mut [u8]) -> Result<usize, Error> {
+    let mut i = id.encode(buff)?;
+    for s in sigs {
+        i += s.encode(&mut buff[i..])?;
+    }
+    Ok(i)
+}
+
+/// Check discovery filter options, rejecting options that would be decoded as common message fields
+/// (and so dropped from the filter on receipt)
+fn check_discover_filters(opts: &[Options]) -> Result<(), Error> {
+    match opts.iter().find(|o| !is_discover_filter(o)) {
+        Some(o) => {
+            debug!("Unsupported discovery filter option: {:?}", o);
+            Err(Error::InvalidOption)
+        },
+        None => Ok(()),
+    }
+}
+
+/// Write discovery matches, each delimited by a peer ID option containing the service ID
+fn put_discovered<'b>(matches: impl Iterator<Item=(&'b Id, &'b [Options])>, buff: &mut [u8]) -> Result<usize, Error> {
+    let mut i = 0;
+    for (id, opts) in matches {
+        i += Options::peer_id(id.clone()).encode(&mut buff[i..])?;
+        i += opts.encode(&mut buff[i..])?;
+    }
+    Ok(i)
+}
+
+/// Write a pre-encoded body
+fn put_raw(d: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+    if buff.len() < d.len() {
+        return Err(Error::BufferLength);
+    }
+    buff[..d.len()].copy_from_slice(d);
+    Ok(d.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{Crypto, PubKey as _};
+    use crate::net::Message;
+    use crate::service::Net;
+    use crate::test_utils;
+
+    #[test]
+    fn encode_without_service() {
+        // Relay holding only its own keys
+        let (pub_key, pri_key) = Crypto::new_pk().unwrap();
+        let id = Id::from_public_key(&pub_key);
+        let keys = Keys { pri_key: Some(pri_key.clone()), ..Keys::new(pub_key.clone()) };
+
+        let target = test_utils::service(test_utils::SERVICE_SEED);
+
+        // Requests and responses are signed using the relay keys and parse as normal
+        let req = Request::new(id.clone(), 1, RequestBody::FindNode(target.id()), Flags::PUB_KEY_REQUEST)
+            .with_public_key(pub_key.clone());
+        let c = req.encode(&keys, vec![0u8; 1024]).unwrap();
+        let (m, _) = Message::parse(c.raw().to_vec(), &keys).unwrap();
+        assert_eq!(m, Message::request(req));
+
+        let resp = Response::new(id.clone(), 1, ResponseBody::NoResult, Flags::empty());
+        let c = MessageEncoder::new(&id, &pri_key).encode_response(&resp, &target.keys(), vec![0u8; 1024]).unwrap();
+        let (m, _) = Message::parse(c.raw().to_vec(), &keys).unwrap();
+        assert_eq!(m, Message::response(resp));
+
+        // Encoding without a private key fails
+        let req = Request::new(id.clone(), 2, RequestBody::Hello, Flags::empty());
+        assert_eq!(req.encode(&Keys::new(pub_key), vec![0u8; 1024]).err(), Some(Error::NoPrivateKey));
+
+        // And service encoding matches the standalone encoder
+        let req = Request::new(target.id(), 3, RequestBody::Hello, Flags::empty());
+        let a = target.encode_request(&req, &keys, vec![0u8; 1024]).unwrap();
+        let b = req.encode(&target.keys(), vec![0u8; 1024]).unwrap();
+        assert_eq!(a.raw(), b.raw());
+    }
+}
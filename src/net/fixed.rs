@@ -0,0 +1,383 @@
+//! Fixed-capacity request and response bodies, bounding the size of decoded message bodies.
+//!
+//! Note that common fields are still parsed from public options via `Common::parse`,
+//! so these types require `alloc`.
+//!
+//! Lists are held in `heapless::Vec`s of up to `N` entries (default [`FIXED_CAPACITY`]), with pages
+//! verified in place and borrowed from the message buffer as with [`Message::parse_ref`].
+//! Entries containing nested lists (subscription filters, peer exchange entries and status detail)
+//! are carried as borrowed encoded bodies, for decoding by the application.
+//!
+//! Messages are encoded with [`MessageEncoder::encode_request_fixed`](super::MessageEncoder::encode_request_fixed)
+//! and [`MessageEncoder::encode_response_fixed`](super::MessageEncoder::encode_response_fixed),
+//! and parsed with [`Message::parse_fixed`].
+
+use core::convert::TryFrom;
+
+use encdec::DecodeExt;
+
+use crate::base::body::{get_id, get_u32};
+use crate::error::Error;
+use crate::interceptor::Interceptors;
+use crate::keys::KeySource;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::{Container, Limits, PageList, offsets};
+
+use super::{Common, Message, Request, Response, Status, request::is_discover_filter};
+
+/// Default fixed body list capacity
+pub const FIXED_CAPACITY: usize = 8;
+
+/// Pages borrowed from a message buffer
+pub type FixedPages<'a, const N: usize = FIXED_CAPACITY> = heapless::Vec<Container<&'a [u8]>, N>;
+
+/// Fixed-capacity request bodies
+#[derive(Clone, PartialEq, Debug)]
+pub enum RequestBodyFixed<'a, const N: usize = FIXED_CAPACITY> {
+    Hello,
+    Ping,
+    FindNode(Id),
+    FindValue(Id),
+    Store(Id, FixedPages<'a, N>),
+
+    Locate(Id),
+    Subscribe(Id),
+    /// Subscribe with an encoded [`SubscribeFilter`](super::SubscribeFilter)
+    SubscribeFiltered(Id, &'a [u8]),
+    Unsubscribe(Id),
+    Query(Id),
+    PushData(Id, FixedPages<'a, N>),
+
+    Register(Id, FixedPages<'a, N>),
+    Unregister(Id),
+    Discover(&'a [u8], heapless::Vec<Options, N>),
+    /// Encoded [`PeerEntry`](super::PeerEntry) list
+    PeerExchange(&'a [u8]),
+}
+
+/// Fixed-capacity response bodies
+#[derive(Clone, PartialEq, Debug)]
+pub enum ResponseBodyFixed<'a, const N: usize = FIXED_CAPACITY> {
+    Status(Status),
+    /// Status with encoded [`StatusDetail`](super::StatusDetail)
+    StatusDetail(Status, &'a [u8]),
+    NodesFound(Id, heapless::Vec<(Id, Address, PublicKey), N>),
+    ValuesFound(Id, FixedPages<'a, N>),
+    NoResult,
+    PullData(Id, FixedPages<'a, N>),
+    /// Encoded [`PeerEntry`](super::PeerEntry) list
+    PeerExchange(&'a [u8]),
+    Registered(Id, heapless::Vec<Signature, N>),
+    Discovered(heapless::Vec<(Id, heapless::Vec<Options, N>), N>),
+    Located(Id, FixedPages<'a, N>),
+}
+
+/// Message with fixed-capacity bodies, see [`Message::parse_fixed`]
+#[derive(Clone, PartialEq, Debug)]
+pub enum MessageFixed<'a, const N: usize = FIXED_CAPACITY> {
+    Request(Request<RequestBodyFixed<'a, N>>),
+    Response(Response<ResponseBodyFixed<'a, N>>),
+}
+
+impl <'a, const N: usize> From<&RequestBodyFixed<'a, N>> for RequestKind {
+    fn from(r: &RequestBodyFixed<'a, N>) -> Self {
+        match r {
+            RequestBodyFixed::Hello => RequestKind::Hello,
+            RequestBodyFixed::Ping => RequestKind::Ping,
+            RequestBodyFixed::FindNode(_) => RequestKind::FindNodes,
+            RequestBodyFixed::FindValue(_) => RequestKind::FindValues,
+            RequestBodyFixed::Store(_, _) => RequestKind::Store,
+            RequestBodyFixed::Locate(_) => RequestKind::Locate,
+            RequestBodyFixed::Subscribe(_) | RequestBodyFixed::SubscribeFiltered(_, _) => RequestKind::Subscribe,
+            RequestBodyFixed::Unsubscribe(_) => RequestKind::Unsubscribe,
+            RequestBodyFixed::Query(_) => RequestKind::Query,
+            RequestBodyFixed::PushData(_, _) => RequestKind::PushData,
+            RequestBodyFixed::Register(_, _) => RequestKind::Register,
+            RequestBodyFixed::Unregister(_) => RequestKind::Unregister,
+            RequestBodyFixed::Discover(_, _) => RequestKind::Discover,
+            RequestBodyFixed::PeerExchange(_) => RequestKind::PeerExchange,
+        }
+    }
+}
+
+impl <'a, const N: usize> From<&ResponseBodyFixed<'a, N>> for ResponseKind {
+    fn from(r: &ResponseBodyFixed<'a, N>) -> Self {
+        match r {
+            ResponseBodyFixed::Status(_) | ResponseBodyFixed::StatusDetail(_, _) => ResponseKind::Status,
+            ResponseBodyFixed::NodesFound(_, _) => ResponseKind::NodesFound,
+            ResponseBodyFixed::ValuesFound(_, _) => ResponseKind::ValuesFound,
+            ResponseBodyFixed::NoResult => ResponseKind::NoResult,
+            ResponseBodyFixed::PullData(_, _) => ResponseKind::PullData,
+            ResponseBodyFixed::PeerExchange(_) => ResponseKind::PeerExchange,
+            ResponseBodyFixed::Registered(_, _) => ResponseKind::Registered,
+            ResponseBodyFixed::Discovered(_) => ResponseKind::Discovered,
+            ResponseBodyFixed::Located(_, _) => ResponseKind::Located,
+        }
+    }
+}
+
+impl <'a, const N: usize> PartialEq for Request<RequestBodyFixed<'a, N>> {
+    fn eq(&self, b: &Self) -> bool {
+        self.common.from == b.common.from && self.common.flags == b.common.flags && self.data == b.data
+    }
+}
+
+impl <'a, const N: usize> PartialEq for Response<ResponseBodyFixed<'a, N>> {
+    fn eq(&self, b: &Self) -> bool {
+        self.common.from == b.common.from && self.common.flags == b.common.flags && self.data == b.data
+    }
+}
+
+/// Verify pages in place, returning [`Error::TooManyPages`] where the capacity is exceeded
+fn parse_pages<'a, K: KeySource, const N: usize>(buff: &'a mut [u8], key_source: &K, limits: &Limits) -> Result<FixedPages<'a, N>, Error> {
+    let pages = PageList::parse(buff, key_source, limits)?;
+    if pages.len() > N {
+        return Err(Error::TooManyPages);
+    }
+
+    Ok(pages.iter().collect())
+}
+
+/// Split a container into the common message fields and the container body
+fn split<'a>(base: Container<&'a mut [u8]>, interceptors: &Interceptors) -> Result<(Common, &'a mut [u8]), Error> {
+    if base.encrypted() {
+        error!("Attempted to convert encrypted container to message");
+        return Err(Error::CryptoError);
+    }
+
+    let (common, _public_options) = Common::parse(&base, interceptors)?;
+
+    let data_len = base.header().data_len();
+    let buff: &'a mut [u8] = base.buff;
+
+    Ok((common, &mut buff[offsets::BODY..][..data_len]))
+}
+
+impl <'a, const N: usize> Request<RequestBodyFixed<'a, N>> {
+    /// Convert a request container into a request with a fixed-capacity body,
+    /// with pages verified in place and borrowed from the container buffer
+    pub fn convert_fixed<K: KeySource>(base: Container<&'a mut [u8]>, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<Self, Error> {
+        let kind = RequestKind::try_from(base.header().kind())
+            .map_err(|_| Error::InvalidRequestKind)?;
+
+        // Discovery filters are attached as public options
+        let mut filters = heapless::Vec::new();
+        if kind == RequestKind::Discover {
+            for o in base.public_options_iter().filter(is_discover_filter) {
+                filters.push(o).map_err(|_| Error::TooManyOptions)?;
+            }
+        }
+
+        let (common, body) = split(base, interceptors)?;
+
+        let data = match kind {
+            RequestKind::Store | RequestKind::PushData | RequestKind::Register => {
+                let (id, _) = get_id(body)?;
+                let pages = parse_pages(&mut body[ID_LEN..], key_source, limits)?;
+
+                match kind {
+                    RequestKind::Store => RequestBodyFixed::Store(id, pages),
+                    RequestKind::PushData => RequestBodyFixed::PushData(id, pages),
+                    _ => RequestBodyFixed::Register(id, pages),
+                }
+            },
+            RequestKind::Hello => RequestBodyFixed::Hello,
+            RequestKind::Ping => RequestBodyFixed::Ping,
+            RequestKind::FindNodes => RequestBodyFixed::FindNode(get_id(body)?.0),
+            RequestKind::FindValues => RequestBodyFixed::FindValue(get_id(body)?.0),
+            RequestKind::Locate => RequestBodyFixed::Locate(get_id(body)?.0),
+            RequestKind::Subscribe => match get_id(body)? {
+                (id, []) => RequestBodyFixed::Subscribe(id),
+                (id, _) => RequestBodyFixed::SubscribeFiltered(id, &body[ID_LEN..]),
+            },
+            RequestKind::Unsubscribe => RequestBodyFixed::Unsubscribe(get_id(body)?.0),
+            RequestKind::Query => RequestBodyFixed::Query(get_id(body)?.0),
+            RequestKind::Unregister => RequestBodyFixed::Unregister(get_id(body)?.0),
+            RequestKind::Discover => RequestBodyFixed::Discover(body, filters),
+            RequestKind::PeerExchange => RequestBodyFixed::PeerExchange(body),
+        };
+
+        Ok(Request { common, data })
+    }
+}
+
+impl <'a, const N: usize> Response<ResponseBodyFixed<'a, N>> {
+    /// Convert a response container into a response with a fixed-capacity body,
+    /// with pages verified in place and borrowed from the container buffer
+    pub fn convert_fixed<K: KeySource>(base: Container<&'a mut [u8]>, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<Self, Error> {
+        let kind = ResponseKind::try_from(base.header().kind())
+            .map_err(|_| Error::InvalidResponseKind)?;
+
+        let (common, body) = split(base, interceptors)?;
+
+        let data = match kind {
+            ResponseKind::ValuesFound | ResponseKind::PullData | ResponseKind::Located => {
+                let (id, _) = get_id(body)?;
+                let pages = parse_pages(&mut body[ID_LEN..], key_source, limits)?;
+
+                match kind {
+                    ResponseKind::ValuesFound => ResponseBodyFixed::ValuesFound(id, pages),
+                    ResponseKind::PullData => ResponseBodyFixed::PullData(id, pages),
+                    _ => ResponseBodyFixed::Located(id, pages),
+                }
+            },
+            ResponseKind::Status => match get_u32(body)? {
+                (status, []) => ResponseBodyFixed::Status(status.into()),
+                (status, _) => ResponseBodyFixed::StatusDetail(status.into(), &body[4..]),
+            },
+            ResponseKind::NoResult => ResponseBodyFixed::NoResult,
+            ResponseKind::NodesFound => {
+                let (id, rest) = get_id(body)?;
+                ResponseBodyFixed::NodesFound(id, decode_nodes(rest)?)
+            },
+            ResponseKind::PeerExchange => ResponseBodyFixed::PeerExchange(body),
+            ResponseKind::Registered => {
+                let (id, rest) = get_id(body)?;
+
+                if rest.len() % SIGNATURE_LEN != 0 {
+                    return Err(Error::BufferLength);
+                }
+                if rest.len() / SIGNATURE_LEN > N {
+                    return Err(Error::TooManyPages);
+                }
+
+                let mut sigs = heapless::Vec::new();
+                for s in rest.chunks(SIGNATURE_LEN) {
+                    let _ = sigs.push(Signature::try_from(s)?);
+                }
+
+                ResponseBodyFixed::Registered(id, sigs)
+            },
+            ResponseKind::Discovered => ResponseBodyFixed::Discovered(decode_discovered(body)?),
+        };
+
+        Ok(Response { common, data })
+    }
+}
+
+/// Decode node entries delimited by peer ID options, skipping incomplete entries
+fn decode_nodes<const N: usize>(buff: &[u8]) -> Result<heapless::Vec<(Id, Address, PublicKey), N>, Error> {
+    let mut nodes = heapless::Vec::new();
+    let mut entry: (Option<Id>, Option<Address>, Option<PublicKey>) = (None, None, None);
+
+    let mut push = |e: &mut (Option<Id>, Option<Address>, Option<PublicKey>)| match core::mem::take(e) {
+        (Some(id), Some(addr), Some(key)) => nodes.push((id, addr, key)).map_err(|_| Error::TooManyOptions),
+        _ => Ok(()),
+    };
+
+    for o in Options::decode_iter(buff) {
+        match o? {
+            Options::PeerId(id) => {
+                push(&mut entry)?;
+                entry.0 = Some(id);
+            },
+            Options::IPv4(addr) if entry.1.is_none() => entry.1 = Some(addr.into()),
+            Options::IPv6(addr) if entry.1.is_none() => entry.1 = Some(addr.into()),
+            Options::PubKey(key) if entry.2.is_none() => entry.2 = Some(key),
+            _ => (),
+        }
+    }
+    push(&mut entry)?;
+
+    Ok(nodes)
+}
+
+/// Decode discovery matches delimited by peer ID options
+fn decode_discovered<const N: usize>(buff: &[u8]) -> Result<heapless::Vec<(Id, heapless::Vec<Options, N>), N>, Error> {
+    let mut matches: heapless::Vec<(Id, heapless::Vec<Options, N>), N> = heapless::Vec::new();
+
+    for o in Options::decode_iter(buff) {
+        match (o?, matches.last_mut()) {
+            (Options::PeerId(id), _) => matches.push((id, heapless::Vec::new())).map_err(|_| Error::TooManyOptions)?,
+            (o, Some((_id, opts))) => opts.push(o).map_err(|_| Error::TooManyOptions)?,
+            // Options preceding the first match are ignored
+            (_, None) => (),
+        }
+    }
+
+    Ok(matches)
+}
+
+impl Message {
+    /// Parse a request or response message over a borrowed buffer into fixed-capacity bodies,
+    /// enforcing the provided object, page, and options limits.
+    ///
+    /// Application-specific messages are not supported and return [`Error::InvalidMessageType`].
+    pub fn parse_fixed<'a, K: KeySource, const N: usize>(data: &'a mut [u8], key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<(MessageFixed<'a, N>, usize), Error> {
+        interceptors.pre_verify(&Container::checked(&data[..])?.0)?;
+
+        let c = Container::parse_with_limits(data, key_source, limits)?;
+        let n = c.len();
+
+        interceptors.post_parse(&c.borrowed())?;
+
+        let header = c.header();
+        let kind = header.kind();
+
+        let m = match header.application_id() {
+            0 if kind.is_request() => MessageFixed::Request(Request::convert_fixed(c, key_source, interceptors, limits)?),
+            0 if kind.is_response() => MessageFixed::Response(Response::convert_fixed(c, key_source, interceptors, limits)?),
+            _ => {
+                debug!("Error converting base object of kind {:?} to fixed message", kind);
+                return Err(Error::InvalidMessageType);
+            }
+        };
+
+        Ok((m, n))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use encdec::Decode;
+    use crate::net::{MessageEncoder, RequestBody, ResponseBody};
+    use crate::service::Publisher;
+    use crate::test_utils;
+
+    #[test]
+    fn fixed_roundtrip() {
+        let (mut a, b) = test_utils::service_pair();
+        let (keys, source) = (b.keys(), test_utils::TestKeySource::default());
+        let pri_key = a.private_key().unwrap();
+        let id = a.id();
+        let e = MessageEncoder::new(&id, &pri_key);
+
+        let (_n, p) = a.publish_primary_buff(Default::default()).unwrap();
+        let (_n, d) = a.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+        let raw = [p.raw().to_vec(), d.raw().to_vec()];
+        let pages: FixedPages<4> = raw.iter().map(|r| {
+            let (mut c, _n) = Container::decode(&r[..]).unwrap();
+            c.verified = true;
+            c
+        }).collect();
+
+        // Fixed requests encode as owned requests
+        let req = Request::new(a.id(), 1, RequestBodyFixed::Store(a.id(), pages.clone()), Flags::empty());
+        let mut buff = [0u8; 2048];
+        let c = e.encode_request_fixed(&req, &keys, &mut buff[..]).unwrap();
+        let n = c.len();
+        let mut over = buff;
+
+        let owned = Request::new(a.id(), 1, RequestBody::Store(a.id(), vec![p.to_owned(), d.to_owned()]), Flags::empty());
+        assert_eq!(&buff[..n], e.encode_request(&owned, &keys, vec![0u8; 2048]).unwrap().raw());
+
+        // And parse with pages borrowed from the buffer
+        let (m, _n) = Message::parse_fixed::<_, 4>(&mut buff[..n], &source, &Default::default(), &Default::default()).unwrap();
+        assert_eq!(m, MessageFixed::Request(req.clone()));
+
+        // Exceeding the capacity is rejected
+        let r = Message::parse_fixed::<_, 1>(&mut over[..n], &source, &Default::default(), &Default::default());
+        assert_eq!(r.err(), Some(Error::TooManyPages));
+
+        // Responses roundtrip via the owned encoder
+        let nodes = vec![(b.id(), Address::from(AddressV4::from(([127, 0, 0, 1], 8080))), b.public_key())];
+        let resp = Response::new(a.id(), 2, ResponseBody::NodesFound(a.id(), nodes.clone()), Flags::empty());
+        let mut buff = e.encode_response(&resp, &keys, vec![0u8; 1024]).unwrap().raw().to_vec();
+
+        let (m, _n) = Message::parse_fixed::<_, 4>(&mut buff, &source, &Default::default(), &Default::default()).unwrap();
+        let fixed = ResponseBodyFixed::NodesFound(a.id(), nodes.into_iter().collect());
+        assert_eq!(m, MessageFixed::Response(Response{ common: resp.common.clone(), data: fixed }));
+    }
+}
@@ -0,0 +1,234 @@
+//! Symmetric mode handshake.
+//!
+//! Symmetric mode messages ([`Flags::SYMMETRIC_MODE`]) require both peers to derive shared keys
+//! via [`Keys::derive_peer`]. [`Handshake`] drives the exchange: the initiator sends a `Hello`
+//! request carrying its public key, algorithm suite, and a challenge nonce, the responder checks
+//! this and replies with its own public key and suite (binding the nonce), and both sides derive
+//! symmetric keys from the exchanged public keys.
+//!
+//! Peers not advertising [`AlgorithmSuite::SYMMETRIC_MESSAGES`], and asymmetric messages received
+//! once the handshake is complete, return [`Error::DowngradeDetected`].
+
+use crate::error::Error;
+use crate::keys::Keys;
+use crate::types::*;
+
+use super::{Request, RequestBody, Response, ResponseBody, Status};
+
+/// Handshake state
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HandshakeState {
+    /// No exchange started
+    Init,
+    /// Hello request sent, awaiting response
+    HelloSent(RequestId),
+    /// Symmetric keys derived
+    Complete,
+}
+
+/// Handshake state machine for establishing symmetric mode with a peer
+#[derive(Clone, PartialEq, Debug)]
+pub struct Handshake {
+    id: Id,
+    keys: Keys,
+    suite: AlgorithmSuite,
+    state: HandshakeState,
+    request: Option<Request>,
+    peer: Option<Id>,
+}
+
+impl Handshake {
+    /// Create a new handshake using the local ID and keys (which must include the private key)
+    pub fn new(id: Id, keys: Keys) -> Self {
+        Self {
+            id,
+            keys,
+            suite: AlgorithmSuite::local(),
+            state: HandshakeState::Init,
+            request: None,
+            peer: None,
+        }
+    }
+
+    /// Fetch the handshake state
+    pub fn state(&self) -> HandshakeState {
+        self.state
+    }
+
+    /// Fetch the peer ID, available once the handshake is complete
+    pub fn peer(&self) -> Option<&Id> {
+        self.peer.as_ref()
+    }
+
+    /// Build the `Hello` request to initiate a handshake
+    pub fn hello(&mut self, request_id: RequestId) -> Result<Request, Error> {
+        let pub_key = self.keys.pub_key.clone().ok_or(Error::NoPublicKey)?;
+
+        let req = Request::new(self.id.clone(), request_id, RequestBody::Hello, Flags::empty())
+            .with_public_key(pub_key)
+            .with_algorithms(self.suite)
+            .with_new_nonce()?;
+
+        self.request = Some(req.clone());
+        self.state = HandshakeState::HelloSent(request_id);
+
+        Ok(req)
+    }
+
+    /// Accept a `Hello` request from a peer, returning the response to send
+    /// and the derived keys for the peer.
+    ///
+    /// Requests are only accepted prior to starting an exchange, returning
+    /// [`Error::InvalidMessageType`] once a hello is sent or the handshake is complete.
+    pub fn accept(&mut self, req: &Request) -> Result<(Response, Keys), Error> {
+        if req.data != RequestBody::Hello {
+            return Err(Error::InvalidMessageType);
+        }
+
+        if self.state != HandshakeState::Init {
+            debug!("Unexpected hello from {:?} in state {:?}", req.from, self.state);
+            return Err(Error::InvalidMessageType);
+        }
+
+        let keys = self.derive(&req.from, req.public_key.as_ref(), req.algorithms)?;
+        let pub_key = self.keys.pub_key.clone().ok_or(Error::NoPublicKey)?;
+
+        let resp = Response::new(self.id.clone(), req.id, ResponseBody::Status(Status::Ok), Flags::empty())
+            .with_public_key(pub_key)
+            .with_algorithms(self.suite)
+            .bind_nonce(req);
+
+        self.state = HandshakeState::Complete;
+        self.peer = Some(req.from.clone());
+
+        Ok((resp, keys))
+    }
+
+    /// Complete an initiated handshake using the peer's response to the `Hello` request,
+    /// returning the derived keys for the peer
+    pub fn complete(&mut self, resp: &Response) -> Result<Keys, Error> {
+        let req = match (&self.state, &self.request) {
+            (HandshakeState::HelloSent(id), Some(req)) if *id == resp.id => req,
+            _ => return Err(Error::InvalidResponse),
+        };
+
+        resp.check_nonce(req)?;
+        let keys = self.derive(&resp.from, resp.public_key.as_ref(), resp.algorithms)?;
+
+        self.state = HandshakeState::Complete;
+        self.request = None;
+        self.peer = Some(resp.from.clone());
+
+        Ok(keys)
+    }
+
+    /// Check a message received from the peer uses symmetric mode once the handshake is complete
+    pub fn check(&self, flags: Flags) -> Result<(), Error> {
+        if self.state == HandshakeState::Complete && !flags.contains(Flags::SYMMETRIC_MODE) {
+            debug!("Asymmetric message from {:?} following handshake", self.peer);
+            return Err(Error::DowngradeDetected);
+        }
+        Ok(())
+    }
+
+    /// Check the peer key and suite, deriving symmetric keys
+    fn derive(&self, peer: &Id, pub_key: Option<&PublicKey>, suite: Option<AlgorithmSuite>) -> Result<Keys, Error> {
+        let pub_key = pub_key.ok_or(Error::NoPublicKey)?;
+        if &Id::from_public_key(pub_key) != peer {
+            return Err(Error::KeyIdMismatch);
+        }
+
+        if !suite.map(|s| s.contains(AlgorithmSuite::SYMMETRIC_MESSAGES)).unwrap_or(false) {
+            debug!("Peer {:?} does not advertise symmetric mode (suite: {:?})", peer, suite);
+            return Err(Error::DowngradeDetected);
+        }
+
+        self.keys.derive_peer(pub_key.clone()).map_err(|_| Error::NoPrivateKey)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keys::NullKeySource;
+    use crate::net::Message;
+    use crate::service::Net;
+    use crate::test_utils;
+
+    #[test]
+    fn symmetric_handshake() {
+        let (a, b) = test_utils::service_pair();
+        let (mut ha, mut hb) = (Handshake::new(a.id(), a.keys()), Handshake::new(b.id(), b.keys()));
+
+        // Hello exchange in asymmetric mode, with peer keys unknown
+        let req = ha.hello(1).unwrap();
+        assert_eq!(ha.state(), HandshakeState::HelloSent(1));
+
+        let c = a.encode_request_alloc(&req, &b.keys()).unwrap();
+        let req = match Message::parse(c.raw().to_vec(), &NullKeySource).unwrap() {
+            (Message::Request(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        let (resp, b_keys) = hb.accept(&req).unwrap();
+
+        let c = b.encode_response_alloc(&resp, &a.keys()).unwrap();
+        let resp = match Message::parse(c.raw().to_vec(), &NullKeySource).unwrap() {
+            (Message::Response(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        let a_keys = ha.complete(&resp).unwrap();
+        assert_eq!(ha.peer(), Some(&b.id()));
+        assert_eq!(hb.peer(), Some(&a.id()));
+
+        // Derived keys allow symmetric mode messages
+        let req = Request::new(a.id(), 2, RequestBody::Ping, Flags::SYMMETRIC_MODE | Flags::ENCRYPTED);
+        let c = a.encode_request_alloc(&req, &a_keys).unwrap();
+        let (m, _) = Message::parse(c.raw().to_vec(), &b_keys).unwrap();
+        assert_eq!(m, Message::Request(req));
+
+        assert_eq!(hb.check(c.header().flags()), Ok(()));
+        assert_eq!(hb.check(Flags::empty()), Err(Error::DowngradeDetected));
+
+        // Responses must match the outstanding request
+        assert_eq!(ha.complete(&resp), Err(Error::InvalidResponse));
+
+        // Further hellos are rejected once the handshake is complete
+        let hello = Handshake::new(a.id(), a.keys()).hello(3).unwrap();
+        assert_eq!(hb.accept(&hello).err(), Some(Error::InvalidMessageType));
+        assert_eq!(hb.peer(), Some(&a.id()));
+
+        // As are hellos while awaiting a response
+        let mut hc = Handshake::new(a.id(), a.keys());
+        hc.hello(4).unwrap();
+        let hello = Handshake::new(b.id(), b.keys()).hello(5).unwrap();
+        assert_eq!(hc.accept(&hello).err(), Some(Error::InvalidMessageType));
+        assert_eq!(hc.state(), HandshakeState::HelloSent(4));
+    }
+
+    #[test]
+    fn handshake_downgrade() {
+        let (a, b) = test_utils::service_pair();
+        let mut hb = Handshake::new(b.id(), b.keys());
+
+        // Hello requests without symmetric support are rejected
+        let req = Request::new(a.id(), 1, RequestBody::Hello, Flags::empty())
+            .with_public_key(a.public_key())
+            .with_algorithms(AlgorithmSuite::BASE);
+        assert_eq!(hb.accept(&req).err(), Some(Error::DowngradeDetected));
+
+        // As are responses stripping the suite
+        let mut ha = Handshake::new(a.id(), a.keys());
+        let req = ha.hello(3).unwrap();
+        let resp = Response::new(b.id(), 3, ResponseBody::Status(Status::Ok), Flags::empty())
+            .with_public_key(b.public_key())
+            .bind_nonce(&req);
+        assert_eq!(ha.complete(&resp), Err(Error::DowngradeDetected));
+
+        // And keys not matching the peer ID
+        let req = Request::new(a.id(), 1, RequestBody::Hello, Flags::empty())
+            .with_public_key(b.public_key())
+            .with_algorithms(AlgorithmSuite::local());
+        assert_eq!(hb.accept(&req).err(), Some(Error::KeyIdMismatch));
+    }
+}
@@ -3,15 +3,61 @@
 //! These messages are used to maintain the network, publish and subscribe to services, and exchange data,
 //! and can be converted to and from base objects for encoding/decoding.
 
-use crate::wire::Container;
+use core::convert::TryFrom;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::base::Header;
+use crate::wire::{Container, Limits};
+#[cfg(feature = "alloc")]
+use crate::wire::Reassembler;
 use crate::error::Error;
+use crate::interceptor::Interceptors;
 use crate::types::*;
+use crate::options::{ContinuationToken, Filters, Options};
 
 pub mod request;
-pub use request::{Request, RequestBody};
+pub use request::{Request, RequestBody, RequestBodyRef, RequestBodyPages};
 
 pub mod response;
-pub use response::{Response, ResponseBody, Status};
+pub use response::{Response, ResponseBody, Status, StatusDetail};
+
+pub mod fixed;
+pub use fixed::{RequestBodyFixed, ResponseBodyFixed, MessageFixed, FixedPages, FIXED_CAPACITY};
+
+pub mod subscribe;
+pub use subscribe::SubscribeFilter;
+
+pub mod paging;
+pub use paging::paginate;
+
+pub mod planner;
+pub use planner::Planner;
+
+pub mod pex;
+pub use pex::PeerEntry;
+
+pub mod pacing;
+pub use pacing::{Pacing, Pacer};
+
+pub mod transport;
+pub use transport::TransportHints;
+
+pub mod padding;
+pub use padding::CONSTANT_MESSAGE_LEN;
+
+pub mod application;
+pub use application::ApplicationMessage;
+
+pub mod discover;
+pub use discover::{new_nonce, DiscoverFilter, DiscoverPredicate};
+
+pub mod encoder;
+pub use encoder::MessageEncoder;
+
+pub mod handshake;
+pub use handshake::{Handshake, HandshakeState};
 
 pub const BUFF_SIZE: usize = 10 * 1024;
 
@@ -24,6 +70,17 @@ use crate::keys::{KeySource};
 pub enum Message {
     Request(Request),
     Response(Response),
+    /// Application-specific message (non-zero application ID)
+    Application(ApplicationMessage),
+}
+
+/// Message parsed over a borrowed buffer, see [`Message::parse_ref`]
+#[derive(Clone, PartialEq, Debug)]
+pub enum MessageRef<'a> {
+    /// Page-carrying requests, with pages borrowed from the message buffer
+    Pages(Request<RequestBodyPages<'a>>),
+    /// Other messages, parsed as owned messages
+    Owned(Message),
 }
 
 impl From<Request> for Message {
@@ -38,6 +95,12 @@ impl From<Response> for Message {
     }
 }
 
+impl From<ApplicationMessage> for Message {
+    fn from(msg: ApplicationMessage) -> Self {
+        Self::Application(msg)
+    }
+}
+
 impl Message {
     pub fn request(req: Request) -> Self {
         Self::Request(req)
@@ -51,6 +114,7 @@ impl Message {
         match self {
             Message::Request(req) => req.id,
             Message::Response(resp) => resp.id,
+            Message::Application(msg) => msg.id,
         }
     }
 
@@ -58,6 +122,7 @@ impl Message {
         match self {
             Message::Request(req) => req.from.clone(),
             Message::Response(resp) => resp.from.clone(),
+            Message::Application(msg) => msg.from.clone(),
         }
     }
 
@@ -65,6 +130,7 @@ impl Message {
         match self {
             Message::Request(req) => req.common.flags,
             Message::Response(resp) => resp.common.flags,
+            Message::Application(msg) => msg.common.flags,
         }
     }
 
@@ -72,6 +138,7 @@ impl Message {
         match self {
             Message::Request(req) => req.flags(),
             Message::Response(resp) => resp.flags(),
+            Message::Application(msg) => &mut msg.common.flags,
         }
     }
 
@@ -79,6 +146,7 @@ impl Message {
         match self {
             Message::Request(req) => req.public_key.clone(),
             Message::Response(resp) => resp.public_key.clone(),
+            Message::Application(msg) => msg.public_key.clone(),
         }
     }
 
@@ -86,6 +154,7 @@ impl Message {
         match self {
             Message::Request(req) => req.common.public_key = Some(pub_key),
             Message::Response(resp) => resp.common.public_key = Some(pub_key),
+            Message::Application(msg) => msg.common.public_key = Some(pub_key),
         }
     }
 }
@@ -96,8 +165,29 @@ impl Message {
     where
         K: KeySource,
     {
+        Self::parse_with(data, key_source, &Interceptors::default())
+    }
+
+    /// Parses an array containing a page into a page object using the provided key source,
+    /// applying the provided interceptors following parsing and prior to conversion
+    pub fn parse_with<'a, K, T: MutableData>(data: T, key_source: &K, interceptors: &Interceptors) -> Result<(Message, usize), Error>
+    where
+        K: KeySource,
+    {
+        Self::parse_with_limits(data, key_source, interceptors, &Limits::default())
+    }
+
+    /// Parses an array containing a page into a page object using the provided key source,
+    /// applying the provided interceptors and enforcing the provided object, page, and options limits
+    pub fn parse_with_limits<'a, K, T: MutableData>(data: T, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<(Message, usize), Error>
+    where
+        K: KeySource,
+    {
+        // Apply admission control prior to verification
+        interceptors.pre_verify(&Container::checked(data.as_ref())?.0)?;
+
         // Parse container, verifying sigs etc.
-        let mut c = Container::parse(data, key_source)?;
+        let mut c = Container::parse_with_limits(data, key_source, limits)?;
         let n = c.len();
 
         // Decrypt symmetric encrypted objects if enabled
@@ -119,33 +209,81 @@ impl Message {
             }
         }
 
+        // Apply post-parse interceptors
+        interceptors.post_parse(&c.borrowed())?;
+
         // Convert into message object
-        let m = Message::convert(c, key_source)?;
+        let m = Message::convert_with(c, key_source, interceptors, limits)?;
 
         Ok((m, n))
     }
+
+    /// Add a fragment received from `peer` to the provided [`Reassembler`], parsing the message
+    /// once all fragments have been received. See [`Fragmenter`](crate::wire::Fragmenter)
+    /// for fragmenting encoded messages.
+    #[cfg(feature = "alloc")]
+    pub fn parse_fragment<P: Ord + Clone, K: KeySource>(reassembler: &mut Reassembler<P>, peer: P, fragment: &[u8], key_source: &K) -> Result<Option<(Message, usize)>, Error> {
+        match reassembler.push_raw(peer, fragment)? {
+            Some(data) => Self::parse(data, key_source).map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
 impl Message {
+    /// Parse a message over a borrowed buffer using the provided key source.
+    ///
+    /// Page-carrying (Store, PushData and Register) requests are verified in place with pages
+    /// borrowed from the buffer rather than copied, other messages are parsed as owned messages.
+    pub fn parse_ref<'a, K: KeySource>(data: &'a mut [u8], key_source: &K) -> Result<(MessageRef<'a>, usize), Error> {
+        Self::parse_ref_with_limits(data, key_source, &Interceptors::default(), &Limits::default())
+    }
+
+    /// Parse a message over a borrowed buffer, applying the provided interceptors and
+    /// enforcing the provided object, page, and options limits
+    pub fn parse_ref_with_limits<'a, K: KeySource>(data: &'a mut [u8], key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<(MessageRef<'a>, usize), Error> {
+        interceptors.pre_verify(&Container::checked(&data[..])?.0)?;
+
+        let c = Container::parse_with_limits(data, key_source, limits)?;
+        let n = c.len();
+
+        interceptors.post_parse(&c.borrowed())?;
+
+        let header = c.header();
+        let pages = header.application_id() == 0 && matches!(RequestKind::try_from(header.kind()),
+            Ok(RequestKind::Store) | Ok(RequestKind::PushData) | Ok(RequestKind::Register));
+
+        let m = match pages {
+            true => MessageRef::Pages(Request::convert_pages(c, key_source, interceptors, limits)?),
+            false => MessageRef::Owned(Message::convert_with(c, key_source, interceptors, limits)?),
+        };
+
+        Ok((m, n))
+    }
+
     pub fn convert<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K) -> Result<Message, Error> {
+        Self::convert_with(base, key_source, &Interceptors::default(), &Limits::default())
+    }
+
+    /// Convert a container into a message object, applying the provided pre-convert interceptors and page limits.
+    ///
+    /// Objects with a non-zero application ID are returned as [`Message::Application`] messages
+    /// for handling by the application.
+    pub fn convert_with<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<Message, Error> {
         let header = base.header();
         let app_id = header.application_id();
         let kind = header.kind();
 
-        // Check for DSF messages
+        // Pass application-specific messages through to the application
         if app_id != 0 {
-            error!(
-                "Error converting application-specific base object {:?} to message",
-                kind
-            );
-            return Err(Error::InvalidMessageType);
+            return Ok(Message::Application(ApplicationMessage::convert_with(base, interceptors)?));
         }
 
         // Parse request and response types
         if kind.is_request() {
-            Ok(Message::Request(Request::convert(base, key_source)?))
+            Ok(Message::Request(Request::convert_with(base, key_source, interceptors, limits)?))
         } else if kind.is_response() {
-            Ok(Message::Response(Response::convert(base, key_source)?))
+            Ok(Message::Response(Response::convert_with(base, key_source, interceptors, limits)?))
         } else {
             debug!("Error converting base object of kind {:?} to message", kind);
             Err(Error::InvalidMessageType)
@@ -160,6 +298,52 @@ pub struct Common {
     pub id: RequestId,
     pub flags: Flags,
 
+    /// Address of the remote peer as observed by the sender (responses to `ADDRESS_REQUEST`)
     pub remote_address: Option<Address>,
+    /// Local address of the sender, allowing the receiver to detect NAT (requests with `ADDRESS_REQUEST`)
+    pub local_address: Option<Address>,
     pub public_key: Option<PublicKey>,
+
+    /// Maximum number of results to return (requests)
+    pub limit: Option<u32>,
+    /// Token for fetching further results (requests and responses)
+    pub continuation: Option<ContinuationToken>,
+    /// Algorithm / feature suite supported by the sender
+    pub algorithms: Option<AlgorithmSuite>,
+    /// Flow-control parameters requested by the sender (subscriptions and data streams)
+    pub pacing: Option<Pacing>,
+    /// Padded message length (symmetric mode)
+    pub padding: Option<u16>,
+    /// Challenge nonce, issued in discovery requests and bound in responses
+    pub nonce: Option<u64>,
+    /// Transport capabilities and constraints advertised by the sender (Hello exchanges)
+    pub transport: Option<TransportHints>,
+}
+
+impl Common {
+    /// Fetch common message fields from public options, applying the provided pre-convert interceptors
+    pub(crate) fn parse<T: ImmutableData>(base: &Container<T>, interceptors: &Interceptors) -> Result<(Common, Vec<Options>), Error> {
+        let header = base.header();
+
+        let mut public_options: Vec<_> = base.public_options_iter().collect();
+        interceptors.pre_convert(&Header::from(&header), &mut public_options)?;
+
+        let common = Common {
+            from: base.id(),
+            id: header.index(),
+            flags: header.flags(),
+            public_key: Filters::pub_key(&public_options.iter()),
+            remote_address: Filters::address_response(&public_options.iter()),
+            local_address: Filters::address_request(&public_options.iter()),
+            limit: Filters::limit(&public_options.iter()),
+            continuation: Filters::continuation(&public_options.iter()),
+            algorithms: Filters::algorithms(&public_options.iter()),
+            pacing: Pacing::from_options(&public_options.iter()),
+            padding: padding::padded_len(&public_options, base.len())?,
+            nonce: Filters::nonce(&public_options.iter()),
+            transport: TransportHints::from_options(&public_options.iter()),
+        };
+
+        Ok((common, public_options))
+    }
 }
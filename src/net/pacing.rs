@@ -0,0 +1,178 @@
+//! Flow-control (pacing) metadata for `Subscribe` and `PushData` exchanges.
+//!
+//! Subscribers may advertise a receive window and maximum object rate with [`Pacing`]
+//! options, publishers then pace pushed objects using a [`Pacer`] to avoid overwhelming
+//! constrained subscribers.
+
+use core::time::Duration;
+
+use crate::options::{Options, Filters};
+
+/// Flow-control parameters advertised by a peer
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Pacing {
+    /// Maximum number of objects in flight (unacknowledged) at a time
+    pub window: Option<u32>,
+    /// Maximum number of objects per second
+    pub max_rate: Option<u32>,
+}
+
+impl Pacing {
+    /// Create pacing parameters with the provided window and rate
+    pub fn new(window: Option<u32>, max_rate: Option<u32>) -> Self {
+        Self { window, max_rate }
+    }
+
+    /// Fetch pacing parameters from options, returning `None` where none are set
+    pub fn from_options(options: &impl Filters) -> Option<Self> {
+        let p = Self::new(options.window(), options.max_rate());
+        match p.is_empty() {
+            true => None,
+            false => Some(p),
+        }
+    }
+
+    /// Check whether any pacing parameters are set
+    pub fn is_empty(&self) -> bool {
+        self.window.is_none() && self.max_rate.is_none()
+    }
+
+    /// Fetch options encoding these pacing parameters
+    pub fn options(&self) -> impl Iterator<Item=Options> {
+        self.window.map(Options::window).into_iter()
+            .chain(self.max_rate.map(Options::max_rate))
+    }
+
+    /// Combine pacing parameters, applying the most restrictive of each
+    pub fn min(&self, other: &Pacing) -> Pacing {
+        let min = |a: Option<u32>, b: Option<u32>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        Pacing {
+            window: min(self.window, other.window),
+            max_rate: min(self.max_rate, other.max_rate),
+        }
+    }
+}
+
+/// Token units per object, allowing sub-object accumulation at millisecond resolution
+const TOKEN_UNITS: u64 = 1000;
+
+/// Token bucket pacer over a caller-provided (monotonic) clock.
+///
+/// Tokens accumulate at the configured rate up to the burst size, with each paced object
+/// consuming a single token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pacer {
+    rate: u32,
+    burst: u32,
+    tokens: u64,
+    last: Duration,
+}
+
+impl Pacer {
+    /// Create a new pacer with the provided rate (objects per second) and burst size,
+    /// starting full at the provided time
+    pub fn new(rate: u32, burst: u32, now: Duration) -> Self {
+        let burst = burst.max(1);
+
+        Self {
+            rate,
+            burst,
+            tokens: burst as u64 * TOKEN_UNITS,
+            last: now,
+        }
+    }
+
+    /// Create a pacer from advertised pacing parameters, bursting up to the receive window.
+    ///
+    /// Returns `None` where no rate is advertised.
+    pub fn from_pacing(pacing: &Pacing, now: Duration) -> Option<Self> {
+        let rate = pacing.max_rate?;
+        let burst = pacing.window.unwrap_or(rate);
+
+        Some(Self::new(rate, burst, now))
+    }
+
+    /// Accumulate tokens up to the provided time
+    fn refill(&mut self, now: Duration) {
+        let elapsed = now.saturating_sub(self.last);
+        self.last = self.last.max(now);
+
+        // Rate is in objects per second, so accumulates `rate` units per millisecond
+        let units = (elapsed.as_millis() as u64).saturating_mul(self.rate as u64);
+        self.tokens = self.tokens.saturating_add(units).min(self.burst as u64 * TOKEN_UNITS);
+    }
+
+    /// Attempt to send an object at the provided time, returning true and consuming
+    /// a token if permitted by the pacer
+    pub fn try_acquire(&mut self, now: Duration) -> bool {
+        self.refill(now);
+
+        if self.tokens < TOKEN_UNITS {
+            return false;
+        }
+
+        self.tokens -= TOKEN_UNITS;
+        true
+    }
+
+    /// Fetch the delay until an object may be sent
+    pub fn delay(&mut self, now: Duration) -> Duration {
+        self.refill(now);
+
+        if self.tokens >= TOKEN_UNITS {
+            return Duration::from_millis(0);
+        }
+
+        match self.rate {
+            0 => Duration::from_secs(u64::MAX),
+            r => {
+                let units = TOKEN_UNITS - self.tokens;
+                Duration::from_millis((units + r as u64 - 1) / r as u64)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(v: u64) -> Duration {
+        Duration::from_millis(v)
+    }
+
+    #[test]
+    fn pacer_token_bucket() {
+        // 10 objects per second with a burst of 2
+        let mut p = Pacer::new(10, 2, ms(0));
+
+        assert!(p.try_acquire(ms(0)));
+        assert!(p.try_acquire(ms(0)));
+        assert!(!p.try_acquire(ms(0)));
+        assert_eq!(p.delay(ms(0)), ms(100));
+
+        // Tokens accumulate over time
+        assert!(!p.try_acquire(ms(50)));
+        assert_eq!(p.delay(ms(50)), ms(50));
+        assert!(p.try_acquire(ms(100)));
+
+        // Up to the burst limit
+        assert!(p.try_acquire(ms(10_000)));
+        assert!(p.try_acquire(ms(10_000)));
+        assert!(!p.try_acquire(ms(10_000)));
+
+        // Pacers are created from advertised parameters
+        assert_eq!(Pacer::from_pacing(&Pacing::new(Some(4), None), ms(0)), None);
+        assert_eq!(Pacer::from_pacing(&Pacing::new(Some(4), Some(10)), ms(0)), Some(Pacer::new(10, 4, ms(0))));
+
+        // And combined using the most restrictive parameters
+        let a = Pacing::new(Some(4), Some(10));
+        assert_eq!(a.min(&Pacing::new(None, Some(5))), Pacing::new(Some(4), Some(5)));
+    }
+}
@@ -0,0 +1,81 @@
+//! Message padding for symmetric mode exchanges.
+//!
+//! In symmetric mode message bodies and public options are encrypted, however encoded message
+//! lengths (and response timing) may still reveal the kind of request or response exchanged.
+//! Messages may be padded to a fixed length with [`Request::with_padding`](super::Request::with_padding)
+//! or [`Response::with_padding`](super::Response::with_padding), or to [`CONSTANT_MESSAGE_LEN`] for
+//! constant-size `Hello`, `Ping` and `Status` exchanges with `with_constant_size`.
+//!
+//! Padding is appended as a [`Options::Padding`] public option and ignored at parse.
+//! Note that:
+//! - padding only hides message lengths in symmetric mode, public key mode options are not encrypted
+//! - messages exceeding the padded length are sent without padding, so padded lengths should
+//!   exceed the largest message of the kinds to be hidden
+//! - padding does not mitigate timing, where this is a concern responses should be sent after a
+//!   fixed delay from request receipt rather than as soon as they are available
+
+use core::convert::TryFrom;
+
+use crate::error::Error;
+use crate::options::Options;
+use crate::types::SIGNATURE_LEN;
+use crate::wire::consts::OPTION_HEADER_LEN;
+
+/// Padded message length for constant-size `Hello`, `Ping` and `Status` exchanges
+pub const CONSTANT_MESSAGE_LEN: u16 = 256;
+
+/// Fetch the padding option required to pad a message of the provided (unsigned) length
+/// to the target length, returning `None` where the padding option will not fit
+pub fn padding_for(len: usize, target: u16) -> Option<Options> {
+    let gap = (target as usize).checked_sub(len.checked_add(SIGNATURE_LEN)?)?;
+
+    match gap.checked_sub(OPTION_HEADER_LEN).and_then(|n| u16::try_from(n).ok()) {
+        Some(n) => Some(Options::padding(n)),
+        None => {
+            debug!("Message length {} exceeds padded length {}", len + SIGNATURE_LEN, target);
+            None
+        }
+    }
+}
+
+/// Fetch the padded length of a message from its public options and encoded length,
+/// returning `None` where the message is not padded and [`Error::BufferLength`] where
+/// a padded message exceeds the maximum padded length
+pub fn padded_len<'a>(options: impl IntoIterator<Item = &'a Options>, len: usize) -> Result<Option<u16>, Error> {
+    match options.into_iter().any(|o| matches!(o, Options::Padding(_))) {
+        true => u16::try_from(len).map(Some).map_err(|_| Error::BufferLength),
+        false => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use encdec::Encode;
+
+    #[test]
+    fn padding_lengths() {
+        // Padding fills the remainder of the message
+        let o = padding_for(100, CONSTANT_MESSAGE_LEN).unwrap();
+        assert_eq!(100 + o.encode_len().unwrap() + SIGNATURE_LEN, CONSTANT_MESSAGE_LEN as usize);
+
+        // Padding is omitted where it will not fit
+        let full = CONSTANT_MESSAGE_LEN as usize - SIGNATURE_LEN;
+        assert_eq!(padding_for(full - OPTION_HEADER_LEN, CONSTANT_MESSAGE_LEN), Some(Options::padding(0)));
+        assert_eq!(padding_for(full - 1, CONSTANT_MESSAGE_LEN), None);
+        assert_eq!(padding_for(full + 1, CONSTANT_MESSAGE_LEN), None);
+        assert_eq!(padding_for(usize::MAX, CONSTANT_MESSAGE_LEN), None);
+    }
+
+    #[test]
+    fn padded_lengths() {
+        let padded = [Options::padding(0)];
+
+        assert_eq!(padded_len(&[] as &[Options], 100), Ok(None));
+        assert_eq!(padded_len(&padded, 100), Ok(Some(100)));
+        assert_eq!(padded_len(&padded, u16::MAX as usize), Ok(Some(u16::MAX)));
+
+        // Padded lengths exceeding u16 are rejected rather than truncated
+        assert_eq!(padded_len(&padded, u16::MAX as usize + 1), Err(Error::BufferLength));
+    }
+}
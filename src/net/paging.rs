@@ -0,0 +1,63 @@
+//! Paging helpers for splitting large result sets (such as `ValuesFound`) over multiple responses
+//! using the `Limit` and `Continuation` options.
+
+use crate::error::Error;
+use crate::options::ContinuationToken;
+
+/// Select the page of `items` for a request with the provided limit and continuation token,
+/// returning the selected items and a token for fetching the next page if more results are available.
+///
+/// This uses offset-based continuation tokens, returning [`Error::InvalidOption`] for
+/// tokens that are not offset-based or exceed the available items.
+pub fn paginate<'a, T>(items: &'a [T], limit: Option<u32>, token: Option<&ContinuationToken>) -> Result<(&'a [T], Option<ContinuationToken>), Error> {
+    // Resolve starting offset
+    let start = match token {
+        Some(t) => t.offset().ok_or(Error::InvalidOption)? as usize,
+        None => 0,
+    };
+    if start > items.len() {
+        return Err(Error::InvalidOption);
+    }
+
+    // Select items up to the limit
+    let remaining = &items[start..];
+    let n = match limit {
+        Some(l) => remaining.len().min(l as usize),
+        None => remaining.len(),
+    };
+
+    // Generate a token if further items are available
+    let next = match start + n < items.len() {
+        true => Some(ContinuationToken::from_offset((start + n) as u32)),
+        false => None,
+    };
+
+    Ok((&remaining[..n], next))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn paginate_items() {
+        let items = [0u8, 1, 2, 3, 4];
+
+        let (p, t) = paginate(&items, Some(2), None).unwrap();
+        assert_eq!(p, &[0, 1]);
+
+        let (p, t) = paginate(&items, Some(2), t.as_ref()).unwrap();
+        assert_eq!(p, &[2, 3]);
+
+        let (p, t) = paginate(&items, Some(2), t.as_ref()).unwrap();
+        assert_eq!(p, &[4]);
+        assert_eq!(t, None);
+
+        let (p, t) = paginate(&items, None, None).unwrap();
+        assert_eq!(p, &items);
+        assert_eq!(t, None);
+
+        let bad = ContinuationToken::from_offset(6);
+        assert_eq!(paginate(&items, None, Some(&bad)), Err(Error::InvalidOption));
+    }
+}
@@ -0,0 +1,292 @@
+//! Peer exchange (PEX) supports gossip-based peer discovery outside of explicit `FindNode` flows,
+//! exchanging compact summaries of known peers signed by the peers themselves.
+//!
+//! Entries are encoded as options beginning with the peer ID and terminated by a `PeerSig`
+//! option, containing the peer signature over the preceding (encoded) entry options.
+
+use core::iter::once;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use encdec::{Encode, Decode};
+
+use crate::crypto::{Crypto, PubKey as _};
+use crate::error::Error;
+use crate::options::Options;
+use crate::types::*;
+
+/// Signed peer summary for peer exchange
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PeerEntry {
+    /// Peer ID
+    pub id: Id,
+    /// Peer public key
+    pub public_key: PublicKey,
+    /// Peer addresses
+    pub addresses: Vec<Address>,
+    /// Time the peer was last seen (by the peer, when signing)
+    pub last_seen: DateTime,
+    /// Peer algorithm / feature suite
+    pub capabilities: AlgorithmSuite,
+    /// Peer signature over the entry
+    pub signature: Signature,
+}
+
+impl PeerEntry {
+    /// Create a new (unsigned) peer entry, see [`PeerEntry::sign`]
+    pub fn new(public_key: PublicKey, addresses: Vec<Address>, last_seen: DateTime) -> Self {
+        let id = Id::from_public_key(&public_key);
+
+        Self {
+            id,
+            public_key,
+            addresses,
+            last_seen,
+            capabilities: AlgorithmSuite::local(),
+            signature: Signature::default(),
+        }
+    }
+
+    /// Set the advertised algorithm / feature suite
+    pub fn with_capabilities(mut self, capabilities: AlgorithmSuite) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Sign a peer entry using the peer private key
+    pub fn sign(mut self, private_key: &PrivateKey) -> Result<Self, Error> {
+        let data = self.signed_data()?;
+
+        self.signature = Crypto::pk_sign(private_key, &data)
+            .map_err(|_| Error::CryptoError)?;
+
+        Ok(self)
+    }
+
+    /// Verify a peer entry, checking the ID matches the public key and the entry signature
+    pub fn verify(&self) -> Result<(), Error> {
+        if self.id != Id::from_public_key(&self.public_key) {
+            return Err(Error::KeyIdMismatch);
+        }
+
+        let data = self.signed_data()?;
+
+        match Crypto::pk_verify(&self.public_key, &self.signature, &data) {
+            Ok(true) => Ok(()),
+            _ => Err(Error::InvalidSignature),
+        }
+    }
+
+    /// Fetch entry options covered by the peer signature
+    fn options(&self) -> impl Iterator<Item=Options> + '_ {
+        once(Options::peer_id(self.id.clone()))
+            .chain(once(Options::pub_key(self.public_key.clone())))
+            .chain(self.addresses.iter().map(|a| Options::address(*a)))
+            .chain(once(Options::last_seen(self.last_seen)))
+            .chain(once(Options::algorithms(self.capabilities)))
+    }
+
+    /// Encode signed entry data
+    fn signed_data(&self) -> Result<Vec<u8>, Error> {
+        let mut data = vec![0u8; self.options().map(|o| o.encode_len()).sum::<Result<usize, _>>()?];
+
+        let mut n = 0;
+        for o in self.options() {
+            n += o.encode(&mut data[n..])?;
+        }
+
+        Ok(data)
+    }
+
+    /// Encode a list of peer entries
+    pub fn encode_list(entries: &[PeerEntry], buff: &mut [u8]) -> Result<usize, Error> {
+        let mut n = 0;
+        for e in entries {
+            n += e.encode(&mut buff[n..])?;
+        }
+        Ok(n)
+    }
+
+    /// Decode a list of concatenated peer entries
+    pub fn decode_list(buff: &[u8]) -> Result<Vec<PeerEntry>, Error> {
+        let mut entries = vec![];
+        let mut n = 0;
+
+        while n < buff.len() {
+            let (e, l) = PeerEntry::decode(&buff[n..])?;
+            entries.push(e);
+            n += l;
+        }
+
+        Ok(entries)
+    }
+}
+
+impl Encode for PeerEntry {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        let n: usize = self.options().map(|o| o.encode_len()).sum::<Result<_, _>>()?;
+        Ok(n + Options::PeerSig(self.signature.clone()).encode_len()?)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.encode_len()? {
+            return Err(Error::BufferLength);
+        }
+
+        let mut n = 0;
+        for o in self.options() {
+            n += o.encode(&mut buff[n..])?;
+        }
+        n += Options::PeerSig(self.signature.clone()).encode(&mut buff[n..])?;
+
+        Ok(n)
+    }
+}
+
+impl <'a> Decode<'a> for PeerEntry {
+    type Output = PeerEntry;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let (mut id, mut public_key, mut last_seen, mut capabilities) = (None, None, None, None);
+        let mut addresses = vec![];
+        let mut n = 0;
+
+        // Entries must begin with a peer ID
+        if !matches!(Options::decode(buff)?.0, Options::PeerId(_)) {
+            return Err(Error::NoPeerId);
+        }
+
+        // Parse options up to and including the signature
+        let signature = loop {
+            if n >= buff.len() {
+                return Err(Error::NoSignature);
+            }
+
+            let (o, l) = Options::decode(&buff[n..])?;
+            n += l;
+
+            match o {
+                Options::PeerId(v) if id.is_none() => id = Some(v),
+                Options::PubKey(v) => public_key = Some(v),
+                Options::IPv4(v) => addresses.push(v.into()),
+                Options::IPv6(v) => addresses.push(v.into()),
+                Options::LastSeen(v) => last_seen = Some(v),
+                Options::Algorithms(v) => capabilities = Some(v),
+                Options::PeerSig(v) => break v,
+                _ => return Err(Error::InvalidOption),
+            }
+        };
+
+        let entry = match (id, public_key, last_seen) {
+            (Some(id), Some(public_key), Some(last_seen)) => PeerEntry {
+                id,
+                public_key,
+                addresses,
+                last_seen,
+                capabilities: capabilities.unwrap_or(AlgorithmSuite::BASE),
+                signature,
+            },
+            (_, None, _) => return Err(Error::NoPublicKey),
+            _ => return Err(Error::InvalidOption),
+        };
+
+        Ok((entry, n))
+    }
+}
+
+/// De-duplicate peer entries by ID, retaining the most recently seen entry for each peer
+/// (entries are returned ordered by ID)
+pub fn dedup(entries: &mut Vec<PeerEntry>) {
+    // Order by ID then most recently seen, so the first entry for each ID is retained
+    entries.sort_by(|a, b| a.id.cmp(&b.id).then_with(|| b.last_seen.as_secs().cmp(&a.last_seen.as_secs())));
+    entries.dedup_by(|e, retained| e.id == retained.id);
+}
+
+/// Merge received peer entries into a set of known peers, returning the number of entries
+/// added or updated.
+///
+/// Entries failing verification are discarded, and entries for known peers
+/// replace existing entries only where more recently seen.
+pub fn merge<I: IntoIterator<Item=PeerEntry>>(known: &mut Vec<PeerEntry>, received: I) -> usize {
+    let mut updated = 0;
+
+    for e in received {
+        if let Err(err) = e.verify() {
+            debug!("Discarding peer entry for {}: {:?}", e.id, err);
+            continue;
+        }
+
+        match known.iter_mut().find(|k| k.id == e.id) {
+            Some(k) if k.last_seen.as_secs() < e.last_seen.as_secs() => *k = e,
+            Some(_) => continue,
+            None => known.push(e),
+        }
+
+        updated += 1;
+    }
+
+    updated
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils;
+
+    fn entry(seed: u8, port: u16, last_seen: u64) -> PeerEntry {
+        let s = test_utils::service(seed);
+        PeerEntry::new(s.public_key(), vec![Address::new(Ip::V4([10, 0, 0, seed]), port)], DateTime::from_secs(last_seen))
+            .sign(&s.private_key().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn encode_decode_entries() {
+        let entries = vec![entry(1, 8000, 100), entry(2, 8001, 200)];
+
+        let mut buff = vec![0u8; 1024];
+        let mut n = 0;
+        for e in &entries {
+            n += e.encode(&mut buff[n..]).unwrap();
+        }
+
+        let decoded = PeerEntry::decode_list(&buff[..n]).unwrap();
+        assert_eq!(decoded, entries);
+        assert!(decoded.iter().all(|e| e.verify().is_ok()));
+
+        // Modified entries fail verification
+        let mut e = entries[0].clone();
+        e.addresses[0].port = 9000;
+        assert_eq!(e.verify(), Err(Error::InvalidSignature));
+
+        e.public_key = entries[1].public_key.clone();
+        assert_eq!(e.verify(), Err(Error::KeyIdMismatch));
+    }
+
+    #[test]
+    fn merge_dedup_entries() {
+        let mut known = vec![entry(1, 8000, 100)];
+
+        let mut forged = entry(3, 8000, 300);
+        forged.last_seen = DateTime::from_secs(400);
+
+        // Newer and unknown entries are merged, stale and forged entries discarded
+        let n = merge(&mut known, vec![entry(1, 8001, 150), entry(2, 8000, 100), entry(1, 8002, 50), forged]);
+        assert_eq!(n, 2);
+        assert_eq!(known.len(), 2);
+        assert_eq!(known[0].addresses[0].port, 8001);
+
+        let mut entries = vec![entry(1, 8000, 100), entry(2, 8000, 100), entry(1, 8001, 200), entry(1, 8002, 150)];
+        dedup(&mut entries);
+        assert_eq!(entries.len(), 2);
+        assert!(entries.windows(2).all(|w| w[0].id < w[1].id));
+
+        let seen = |seed| entries.iter().find(|e| e.id == test_utils::id(seed)).map(|e| e.last_seen.as_secs());
+        assert_eq!(seen(1), Some(200));
+        assert_eq!(seen(2), Some(100));
+    }
+}
@@ -0,0 +1,255 @@
+//! Message planner for splitting page-carrying requests (`Store`, `PushData`, `Register`)
+//! over multiple messages to fit within a transport MTU.
+
+use core::ops::Range;
+
+use alloc::vec::Vec;
+
+use encdec::Encode;
+
+use crate::error::Error;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::{Container, HEADER_LEN};
+
+use super::{RequestBody, RequestBodyRef};
+
+/// Fixed encoding overhead for page-carrying requests, comprising the object header and ID,
+/// target ID (in the request body) and signature / MAC.
+///
+/// Secret key tags for encrypted modes are accounted for by [`Planner::with_flags`].
+pub const PAGE_REQUEST_OVERHEAD: usize = HEADER_LEN + ID_LEN + ID_LEN + SIGNATURE_LEN;
+
+/// Planner splits page sets across multiple requests given an MTU budget
+#[derive(Clone, PartialEq, Debug)]
+pub struct Planner {
+    mtu: usize,
+    flags: Flags,
+    options_len: usize,
+    private_options_len: usize,
+}
+
+impl Planner {
+    /// Create a new planner for the provided MTU
+    pub fn new(mtu: usize) -> Self {
+        Self { mtu, flags: Flags::empty(), options_len: 0, private_options_len: 0 }
+    }
+
+    /// Set the request flags, reserving space for secret key tag(s) in encrypted modes
+    /// (symmetric mode MACs replace signatures, so do not require additional space)
+    pub fn with_flags(mut self, flags: Flags) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Reserve space for private options attached to each request
+    pub fn with_private_options(mut self, options: &[Options]) -> Result<Self, Error> {
+        for o in options {
+            self.private_options_len += o.encode_len()?;
+        }
+        Ok(self)
+    }
+
+    /// Reserve space for public options attached to each request
+    /// (for example public key, address or paging options)
+    pub fn with_options(mut self, options: &[Options]) -> Result<Self, Error> {
+        for o in options {
+            self.options_len += o.encode_len()?;
+        }
+        Ok(self)
+    }
+
+    /// Reserve a fixed number of bytes for public options attached to each request
+    pub fn with_options_len(mut self, options_len: usize) -> Self {
+        self.options_len += options_len;
+        self
+    }
+
+    /// Fetch the encoding overhead for each request, including tag(s) and options
+    pub fn overhead(&self) -> usize {
+        PAGE_REQUEST_OVERHEAD + self.flags.tag_len() + self.options_len + self.private_options_len
+    }
+
+    /// Fetch the space available for pages in each request
+    pub fn budget(&self) -> Result<usize, Error> {
+        match self.mtu.checked_sub(self.overhead()) {
+            Some(n) if n > 0 => Ok(n),
+            _ => Err(Error::BufferLength),
+        }
+    }
+
+    /// Plan page batches, returning contiguous ranges of pages for each request.
+    ///
+    /// Page order is preserved, returning [`Error::BufferLength`] if any single page
+    /// exceeds the available budget. Empty page sets produce a single empty batch.
+    pub fn plan<T: ImmutableData>(&self, pages: &[Container<T>]) -> Result<Vec<Range<usize>>, Error> {
+        let budget = self.budget()?;
+
+        let mut batches = vec![];
+        let (mut start, mut used) = (0, 0);
+
+        for (i, p) in pages.iter().enumerate() {
+            let n = p.len();
+            if n > budget {
+                return Err(Error::BufferLength);
+            }
+
+            // Start a new batch if this page doesn't fit
+            if used + n > budget {
+                batches.push(start..i);
+                start = i;
+                used = 0;
+            }
+
+            used += n;
+        }
+
+        batches.push(start..pages.len());
+
+        Ok(batches)
+    }
+
+    /// Split a borrowed page-carrying request body into request bodies fitting the MTU
+    pub fn split_ref<'a, T: ImmutableData>(&self, body: &RequestBodyRef<'a, T>) -> Result<Vec<RequestBodyRef<'a, T>>, Error> {
+        let pages = body.pages();
+
+        let bodies = self.plan(pages)?.into_iter().map(|r| {
+            let p = &pages[r];
+            match body {
+                RequestBodyRef::Store(id, _) => RequestBodyRef::Store(id.clone(), p),
+                RequestBodyRef::PushData(id, _) => RequestBodyRef::PushData(id.clone(), p),
+                RequestBodyRef::Register(id, _) => RequestBodyRef::Register(id.clone(), p),
+            }
+        }).collect();
+
+        Ok(bodies)
+    }
+
+    /// Split an owned request body into request bodies fitting the MTU,
+    /// bodies not carrying pages are returned unchanged
+    pub fn split(&self, body: &RequestBody) -> Result<Vec<RequestBody>, Error> {
+        let (id, pages) = match body {
+            RequestBody::Store(id, p) | RequestBody::PushData(id, p) | RequestBody::Register(id, p) => (id, p),
+            _ => return Ok(vec![body.clone()]),
+        };
+
+        let bodies = self.plan(pages)?.into_iter().map(|r| {
+            let p = pages[r].to_vec();
+            match body {
+                RequestBody::Store(..) => RequestBody::Store(id.clone(), p),
+                RequestBody::PushData(..) => RequestBody::PushData(id.clone(), p),
+                _ => RequestBody::Register(id.clone(), p),
+            }
+        }).collect();
+
+        Ok(bodies)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::{Message, Request};
+    use crate::service::{DataOptions, Net, Publisher};
+    use crate::test_utils;
+
+    #[test]
+    fn plan_split_pages() {
+        let (mut source, target) = test_utils::service_pair();
+        let _p = test_utils::primary_page(&mut source);
+
+        let pages: Vec<_> = (0..8).map(|_| {
+            let body = [0xabu8; 100];
+            let (_n, c) = source.publish_data(DataOptions{ body: Some(&body[..]), ..Default::default() }, vec![0u8; 1024]).unwrap();
+            c.to_owned()
+        }).collect();
+        let page_len = pages[0].len();
+
+        // Plan for three pages per request
+        let opts = [Options::pub_key(source.public_key())];
+        let planner = Planner::new(PAGE_REQUEST_OVERHEAD + opts[0].encode_len().unwrap() + page_len * 3 + 1)
+            .with_options(&opts).unwrap();
+        assert_eq!(planner.plan(&pages).unwrap(), vec![0..3, 3..6, 6..8]);
+
+        // Split requests fit the MTU and round-trip
+        let bodies = planner.split_ref(&RequestBodyRef::Store(source.id(), &pages[..])).unwrap();
+        assert_eq!(bodies.len(), 3);
+
+        let mut decoded = vec![];
+        for b in bodies {
+            let mut req = Request::new(source.id(), 1, b, Flags::empty());
+            req.common.public_key = Some(source.public_key());
+
+            let enc = source.encode_request_ref(&req, &target.keys(), vec![0u8; 2048]).unwrap();
+            assert!(enc.len() <= planner.mtu);
+
+            match Message::parse(enc.raw().to_vec(), &source.keys()).unwrap() {
+                (Message::Request(Request{ data: RequestBody::Store(_id, p), .. }), _) => decoded.extend(p),
+                m => panic!("Unexpected message: {:?}", m),
+            }
+        }
+        assert_eq!(decoded, pages);
+
+        // Owned bodies split equivalently
+        let bodies = planner.split(&RequestBody::PushData(source.id(), pages.clone())).unwrap();
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies.iter().all(|b| matches!(b, RequestBody::PushData(..))));
+
+        // Pages exceeding the budget can not be planned
+        let planner = Planner::new(PAGE_REQUEST_OVERHEAD + page_len - 1);
+        assert_eq!(planner.plan(&pages), Err(Error::BufferLength));
+    }
+
+    #[test]
+    fn plan_encrypted_modes() {
+        let (mut source, target) = test_utils::service_pair();
+        let _p = test_utils::primary_page(&mut source);
+
+        let pages: Vec<_> = (0..4).map(|_| {
+            let body = [0xabu8; 100];
+            let (_n, c) = source.publish_data(DataOptions{ body: Some(&body[..]), ..Default::default() }, vec![0u8; 1024]).unwrap();
+            c.to_owned()
+        }).collect();
+        let page_len = pages[0].len();
+        let mtu = PAGE_REQUEST_OVERHEAD + page_len * 2 + 1;
+
+        // Encrypted modes reserve space for secret key tags
+        let planner = Planner::new(mtu);
+        let budget = planner.budget().unwrap();
+        assert_eq!(planner.plan(&pages).unwrap(), vec![0..2, 2..4]);
+
+        let encrypted = Planner::new(mtu).with_flags(Flags::ENCRYPTED);
+        assert_eq!(encrypted.budget().unwrap(), budget - SECRET_KEY_TAG_LEN);
+        assert_eq!(encrypted.plan(&pages).unwrap(), vec![0..1, 1..2, 2..3, 3..4]);
+
+        let split = Planner::new(mtu).with_flags(Flags::ENCRYPTED | Flags::SPLIT_ENCRYPTION);
+        assert_eq!(split.budget().unwrap(), budget - 2 * SECRET_KEY_TAG_LEN);
+
+        // As do private options
+        let opts = [Options::name("private")];
+        let private = Planner::new(mtu).with_private_options(&opts).unwrap();
+        assert_eq!(private.budget().unwrap(), budget - opts[0].encode_len().unwrap());
+
+        // Symmetric mode MACs replace signatures, so split requests fit the MTU as-is
+        let flags = Flags::SYMMETRIC_MODE | Flags::ENCRYPTED;
+        let source_keys = source.keys().derive_peer(target.public_key()).unwrap();
+        let target_keys = target.keys().derive_peer(source.public_key()).unwrap();
+
+        let symmetric = Planner::new(mtu).with_flags(flags);
+        assert_eq!(symmetric.budget().unwrap(), budget);
+
+        let mut decoded = vec![];
+        for b in symmetric.split_ref(&RequestBodyRef::PushData(source.id(), &pages[..])).unwrap() {
+            let req = Request::new(source.id(), 1, b, flags);
+
+            let enc = source.encode_request_ref(&req, &source_keys, vec![0u8; 2048]).unwrap();
+            assert!(enc.len() <= mtu);
+
+            match Message::parse(enc.raw().to_vec(), &target_keys).unwrap() {
+                (Message::Request(Request{ data: RequestBody::PushData(_id, p), .. }), _) => decoded.extend(p),
+                m => panic!("Unexpected message: {:?}", m),
+            }
+        }
+        assert_eq!(decoded, pages);
+    }
+}
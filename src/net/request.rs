@@ -9,14 +9,15 @@ use encdec::{Encode, Decode, EncodeExt, DecodeExt};
 use alloc::vec::Vec;
 
 use crate::{
-    base::Message,
+    base::{Message, body::get_id},
     error::Error,
-    options::{Options, Filters},
+    interceptor::Interceptors,
+    options::{Options, ContinuationToken},
     types::*,
     keys::KeySource,
-    wire::{Container, Builder},
+    wire::{Container, Builder, Limits, PageList, offsets},
 };
-use super::Common;
+use super::{Common, Pacing, PeerEntry, SubscribeFilter, TransportHints, CONSTANT_MESSAGE_LEN};
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -64,6 +65,8 @@ pub enum RequestBody {
 
     Locate(Id),
     Subscribe(Id),
+    /// Subscribe to a filtered subset of service data
+    SubscribeFiltered(Id, SubscribeFilter),
     Unsubscribe(Id),
     Query(Id),
     PushData(Id, Vec<Container>),
@@ -71,6 +74,58 @@ pub enum RequestBody {
     Register(Id, Vec<Container>),
     Unregister(Id),
     Discover(Vec<u8>, Vec<Options>),
+    /// Offer signed peer summaries for gossip-based discovery
+    PeerExchange(Vec<PeerEntry>),
+}
+
+/// Borrowed request bodies for page-carrying requests, allowing encoding
+/// directly from existing containers without copying into a [`RequestBody`]
+#[derive(Clone, Debug)]
+pub enum RequestBodyRef<'a, T: ImmutableData = Vec<u8>> {
+    Store(Id, &'a [Container<T>]),
+    PushData(Id, &'a [Container<T>]),
+    Register(Id, &'a [Container<T>]),
+}
+
+impl <'a, T: ImmutableData> RequestBodyRef<'a, T> {
+    /// Fetch the target ID for the request
+    pub fn id(&self) -> &Id {
+        match self {
+            RequestBodyRef::Store(id, _) | RequestBodyRef::PushData(id, _) | RequestBodyRef::Register(id, _) => id,
+        }
+    }
+
+    /// Fetch the pages attached to the request
+    pub fn pages(&self) -> &'a [Container<T>] {
+        match self {
+            RequestBodyRef::Store(_, p) | RequestBodyRef::PushData(_, p) | RequestBodyRef::Register(_, p) => p,
+        }
+    }
+}
+
+/// Page-carrying request bodies with pages borrowed from a parsed message buffer,
+/// see [`Message::parse_ref`](super::Message::parse_ref)
+#[derive(Clone, PartialEq, Debug)]
+pub enum RequestBodyPages<'a> {
+    Store(Id, PageList<'a>),
+    PushData(Id, PageList<'a>),
+    Register(Id, PageList<'a>),
+}
+
+impl <'a> RequestBodyPages<'a> {
+    /// Fetch the target ID for the request
+    pub fn id(&self) -> &Id {
+        match self {
+            RequestBodyPages::Store(id, _) | RequestBodyPages::PushData(id, _) | RequestBodyPages::Register(id, _) => id,
+        }
+    }
+
+    /// Fetch the pages attached to the request
+    pub fn pages(&self) -> &PageList<'a> {
+        match self {
+            RequestBodyPages::Store(_, p) | RequestBodyPages::PushData(_, p) | RequestBodyPages::Register(_, p) => p,
+        }
+    }
 }
 
 #[derive(Debug, Encode, Decode)]
@@ -288,13 +343,25 @@ impl From<&RequestBody> for RequestKind {
             RequestBody::FindValue(_) => RequestKind::FindValues,
             RequestBody::Store(_, _) => RequestKind::Store,
             RequestBody::Locate(_) => RequestKind::Locate,
-            RequestBody::Subscribe(_) => RequestKind::Subscribe,
+            RequestBody::Subscribe(_) | RequestBody::SubscribeFiltered(_, _) => RequestKind::Subscribe,
             RequestBody::Unsubscribe(_) => RequestKind::Unsubscribe,
             RequestBody::Query(_) => RequestKind::Query,
             RequestBody::PushData(_, _) => RequestKind::PushData,
             RequestBody::Register(_, _) => RequestKind::Register,
             RequestBody::Unregister(_) => RequestKind::Unregister,
             RequestBody::Discover(_, _) => RequestKind::Discover,
+            RequestBody::PeerExchange(_) => RequestKind::PeerExchange,
+        }
+    }
+}
+
+/// Convert borrowed request bodies to protocol message enumerations
+impl <'a, T: ImmutableData> From<&RequestBodyRef<'a, T>> for RequestKind {
+    fn from(r: &RequestBodyRef<'a, T>) -> Self {
+        match r {
+            RequestBodyRef::Store(_, _) => RequestKind::Store,
+            RequestBodyRef::PushData(_, _) => RequestKind::PushData,
+            RequestBodyRef::Register(_, _) => RequestKind::Register,
         }
     }
 }
@@ -307,6 +374,14 @@ impl <D> Request<D> {
             flags: flags | Flags::SYMMETRIC_DIR,
             public_key: None,
             remote_address: None,
+            local_address: None,
+            limit: None,
+            continuation: None,
+            algorithms: None,
+            pacing: None,
+            padding: None,
+            nonce: None,
+            transport: None,
         };
         Request { common, data }
     }
@@ -323,6 +398,60 @@ impl <D> Request<D> {
         self.common.public_key = Some(pk);
         self
     }
+
+    /// Limit the number of results returned
+    pub fn with_limit(mut self, limit: u32) -> Self {
+        self.common.limit = Some(limit);
+        self
+    }
+
+    /// Continue a previous request using the token from a partial response
+    pub fn with_continuation(mut self, token: ContinuationToken) -> Self {
+        self.common.continuation = Some(token);
+        self
+    }
+
+    /// Advertise the supported algorithm / feature suite (for example, in Hello requests)
+    pub fn with_algorithms(mut self, suite: AlgorithmSuite) -> Self {
+        self.common.algorithms = Some(suite);
+        self
+    }
+
+    /// Request flow-control for pushed data (for example, in Subscribe requests)
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.common.pacing = Some(pacing);
+        self
+    }
+
+    /// Pad the encoded message to the provided length, hiding the message kind in symmetric mode
+    pub fn with_padding(mut self, len: u16) -> Self {
+        self.common.padding = Some(len);
+        self
+    }
+
+    /// Pad the encoded message to [`CONSTANT_MESSAGE_LEN`] for constant-size `Hello` / `Ping` exchanges
+    pub fn with_constant_size(self) -> Self {
+        self.with_padding(CONSTANT_MESSAGE_LEN)
+    }
+
+    /// Advertise transport capabilities and constraints (for example, in Hello requests)
+    pub fn with_transport(mut self, hints: TransportHints) -> Self {
+        self.common.transport = Some(hints);
+        self
+    }
+
+    /// Attach a challenge nonce to be bound in responses (for example, in Discover requests)
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.common.nonce = Some(nonce);
+        self
+    }
+
+    /// Attach the local address of the requester, so responders to `ADDRESS_REQUEST`
+    /// may report whether the requester is behind a NAT
+    pub fn with_local_address(mut self, addr: Address) -> Self {
+        self.common.local_address = Some(addr);
+        self
+    }
 }
 
 impl PartialEq for Request {
@@ -331,9 +460,19 @@ impl PartialEq for Request {
     }
 }
 
+impl <'a> PartialEq for Request<RequestBodyPages<'a>> {
+    fn eq(&self, b: &Self) -> bool {
+        self.common.from == b.common.from && self.common.flags == b.common.flags && self.data == b.data
+    }
+}
+
 impl Request {
-    pub fn convert<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K) -> Result<Request, Error>
-    {
+    pub fn convert<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K) -> Result<Request, Error> {
+        Self::convert_with(base, key_source, &Interceptors::default(), &Limits::default())
+    }
+
+    /// Convert a container into a request, applying the provided pre-convert interceptors and page limits
+    pub fn convert_with<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<Request, Error> {
         let header = base.header();
 
         if base.encrypted() {
@@ -341,12 +480,9 @@ impl Request {
             return Err(Error::CryptoError);
         }
 
-        let body = base.body_raw();
-
-        let remote_address = None;
-        let public_options: Vec<_> = base.public_options_iter().collect();
+        let body = base.try_body_raw()?;
 
-        let public_key = Filters::pub_key(&public_options.iter());
+        let (common, public_options) = Common::parse(&base, interceptors)?;
         //let _private_options = base.private_options().to_vec();
 
         let kind = match RequestKind::try_from(header.kind()) {
@@ -358,83 +494,111 @@ impl Request {
             RequestKind::Hello => RequestBody::Hello,
             RequestKind::Ping => RequestBody::Ping,
             RequestKind::FindNodes => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, _) = get_id(body)?;
                 RequestBody::FindNode(id)
             }
             RequestKind::FindValues => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, _) = get_id(body)?;
                 RequestBody::FindValue(id)
             }
             RequestKind::Subscribe => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
-                RequestBody::Subscribe(id)
+                let (id, rest) = get_id(body)?;
+
+                // Parse subscription filter where present
+                match rest {
+                    [] => RequestBody::Subscribe(id),
+                    d => RequestBody::SubscribeFiltered(id, SubscribeFilter::decode(d)?.0),
+                }
             }
             RequestKind::Unsubscribe => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, _) = get_id(body)?;
                 RequestBody::Unsubscribe(id)
             }
             RequestKind::Query => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, _) = get_id(body)?;
                 RequestBody::Query(id)
             }
             RequestKind::Locate => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, _) = get_id(body)?;
                 RequestBody::Locate(id)
             }
             RequestKind::Store => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, rest) = get_id(body)?;
 
                 // Perhaps i should not fetch pages until later..?
                 // And also sign them earlier..?
-                let pages = Container::decode_pages(&body[ID_LEN..], key_source)?;
+                let pages = Container::decode_pages_with_limits(rest, key_source, limits)?;
 
                 RequestBody::Store(id, pages)
             }
             RequestKind::PushData => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, rest) = get_id(body)?;
 
-                let pages = Container::decode_pages(&body[ID_LEN..], key_source)?;
+                let pages = Container::decode_pages_with_limits(rest, key_source, limits)?;
 
                 RequestBody::PushData(id, pages)
             }
             RequestKind::Register => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, rest) = get_id(body)?;
 
-                let pages = Container::decode_pages(&body[ID_LEN..], key_source)?;
+                let pages = Container::decode_pages_with_limits(rest, key_source, limits)?;
 
                 RequestBody::Register(id, pages)
             }
             RequestKind::Unregister => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, _) = get_id(body)?;
 
                 RequestBody::Unregister(id)
             }
             RequestKind::Discover => {
-                // TODO: pass through discover options
-                RequestBody::Discover(body.to_vec(), public_options)
+                // Filter options, excluding those attached by common message fields
+                let opts = public_options.iter().filter(|o| is_discover_filter(o)).cloned().collect();
+
+                RequestBody::Discover(body.to_vec(), opts)
             },
+            RequestKind::PeerExchange => RequestBody::PeerExchange(PeerEntry::decode_list(body)?),
         };
 
-        // TODO: fetch message specific options
-        //let remote_address = Base::filter_address_option(&mut public_options);
+        Ok(Request { common, data })
+    }
+}
+
+/// Check whether a discover request option is a filter option (rather than a common message field)
+pub(crate) fn is_discover_filter(o: &Options) -> bool {
+    !matches!(o,
+        Options::PubKey(_) | Options::IPv4(_) | Options::IPv6(_) | Options::Limit(_) | Options::Continuation(_) | Options::Algorithms(_) | Options::Padding(_) | Options::Nonce(_)
+    )
+}
 
-        let common = Common {
-            from: base.id(),
-            id: header.index(),
-            flags: header.flags(),
-            public_key,
-            remote_address,
+impl <'a> Request<RequestBodyPages<'a>> {
+    /// Convert a page-carrying (Store, PushData or Register) request container into a request
+    /// with pages verified in place and borrowed from the container buffer
+    pub fn convert_pages<K: KeySource>(base: Container<&'a mut [u8]>, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<Self, Error> {
+        if base.encrypted() {
+            error!("Attempted to convert encrypted container to request");
+            return Err(Error::CryptoError);
+        }
+
+        let (common, _public_options) = Common::parse(&base, interceptors)?;
+
+        let header = base.header();
+        let kind = RequestKind::try_from(header.kind())
+            .map_err(|_| Error::InvalidRequestKind)?;
+        let data_len = header.data_len();
+
+        // Split body from the container buffer
+        let buff: &'a mut [u8] = base.buff;
+        let body = &mut buff[offsets::BODY..][..data_len];
+        let (id, _) = get_id(body)?;
+        let pages = PageList::parse(&mut body[ID_LEN..], key_source, limits)?;
+
+        let data = match kind {
+            RequestKind::Store => RequestBodyPages::Store(id, pages),
+            RequestKind::PushData => RequestBodyPages::PushData(id, pages),
+            RequestKind::Register => RequestBodyPages::Register(id, pages),
+            _ => return Err(Error::InvalidRequestKind),
         };
+
         Ok(Request { common, data })
     }
 }
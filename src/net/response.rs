@@ -4,25 +4,25 @@ use core::ops::Deref;
 #[cfg(feature = "alloc")]
 use alloc::vec::{Vec};
 
-use byteorder::{ByteOrder, NetworkEndian};
 use slice_ext::SplitBefore;
 use encdec::{Encode, EncodeExt, Decode, DecodeExt};
 
-use crate::base::Message;
+use crate::base::{Message, body::{get_id, get_u32}};
 use crate::error::Error;
-use crate::options::{Options, Filters};
+use crate::interceptor::Interceptors;
+use crate::options::{Options, Filters, ContinuationToken, OptionString};
 use crate::types::*;
 use crate::keys::KeySource;
-use crate::wire::Container;
+use crate::wire::{Container, Limits};
 
-use super::Common;
+use super::{Common, Pacing, PeerEntry, TransportHints, CONSTANT_MESSAGE_LEN};
 
 /// Generic Response message
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Response {
+pub struct Response<D = ResponseBody> {
     pub common: Common,
-    pub data: ResponseBody,
+    pub data: D,
 }
 
 /// Response message kinds
@@ -30,10 +30,20 @@ pub struct Response {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ResponseBody {
     Status(Status),
+    /// Status with structured detail for backoff and diagnostics
+    StatusDetail(Status, StatusDetail),
     NodesFound(Id, Vec<(Id, Address, PublicKey)>),
     ValuesFound(Id, Vec<Container>),
     NoResult,
     PullData(Id, Vec<Container>),
+    /// Signed peer summaries for gossip-based discovery
+    PeerExchange(Vec<PeerEntry>),
+    /// Registration result, with the signatures of pages registered for the service
+    Registered(Id, Vec<Signature>),
+    /// Discovery results, with the matched service IDs and the options matched for each service
+    Discovered(Vec<(Id, Vec<Options>)>),
+    /// Locate result, with the service primary page and tertiary pages resolving to the service
+    Located(Id, Vec<Container>),
 }
 
 #[derive(Clone, Debug, Encode, Decode)]
@@ -61,11 +71,15 @@ pub struct PullData {
 impl From<&ResponseBody> for ResponseKind {
     fn from(r: &ResponseBody) -> Self {
         match r {
-            ResponseBody::Status(_) => ResponseKind::Status,
+            ResponseBody::Status(_) | ResponseBody::StatusDetail(_, _) => ResponseKind::Status,
             ResponseBody::NodesFound(_, _) => ResponseKind::NodesFound,
             ResponseBody::ValuesFound(_, _) => ResponseKind::ValuesFound,
             ResponseBody::NoResult => ResponseKind::NoResult,
             ResponseBody::PullData(_, _) => ResponseKind::PullData,
+            ResponseBody::PeerExchange(_) => ResponseKind::PeerExchange,
+            ResponseBody::Registered(_, _) => ResponseKind::Registered,
+            ResponseBody::Discovered(_) => ResponseKind::Discovered,
+            ResponseBody::Located(_, _) => ResponseKind::Located,
         }
     }
 }
@@ -74,6 +88,12 @@ mod status {
     pub const OK: u32 = 0x00;
     pub const INVALID_REQUEST: u32 = 0x01;
     pub const FAILED: u32 = 0x02;
+    pub const PROTOCOL_ERROR: u32 = 0x03;
+    pub const AUTH_FAILED: u32 = 0x04;
+    pub const NOT_FOUND: u32 = 0x05;
+    pub const RATE_LIMITED: u32 = 0x06;
+    pub const STORAGE_FULL: u32 = 0x07;
+    pub const UNSUPPORTED_VERSION: u32 = 0x08;
 }
 
 /// Status response codes
@@ -83,6 +103,18 @@ pub enum Status {
     Ok,
     InvalidRequest,
     Failed,
+    /// Malformed or unexpected message
+    ProtocolError,
+    /// Signature, key, or authorisation failure
+    AuthFailed,
+    /// Requested service or object not found
+    NotFound,
+    /// Request rate exceeded, retry after the time provided in the [`StatusDetail`]
+    RateLimited,
+    /// Storage capacity exceeded
+    StorageFull,
+    /// Protocol version not supported
+    UnsupportedVersion,
     Unknown(u32),
 }
 
@@ -92,17 +124,174 @@ impl From<u32> for Status {
             status::OK => Status::Ok,
             status::INVALID_REQUEST => Status::InvalidRequest,
             status::FAILED => Status::Failed,
+            status::PROTOCOL_ERROR => Status::ProtocolError,
+            status::AUTH_FAILED => Status::AuthFailed,
+            status::NOT_FOUND => Status::NotFound,
+            status::RATE_LIMITED => Status::RateLimited,
+            status::STORAGE_FULL => Status::StorageFull,
+            status::UNSUPPORTED_VERSION => Status::UnsupportedVersion,
             _ => Status::Unknown(v),
         }
     }
 }
 
+impl Status {
+    /// Check whether the status indicates success
+    pub fn is_ok(&self) -> bool {
+        *self == Status::Ok
+    }
+
+    /// Check whether the request may succeed if retried later (or against another peer)
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Status::Failed | Status::RateLimited | Status::StorageFull)
+    }
+}
+
+/// Map request handling errors to status codes for responses
+impl From<&Error> for Status {
+    fn from(e: &Error) -> Self {
+        use Error::*;
+
+        match e {
+            InvalidOption | InvalidOptionLength | InvalidPageLength | InvalidPageKind | InvalidRequestKind
+            | InvalidMessageType | UnexpectedPageType | UnexpectedPageKind | EncodeFailed | BufferLength
+            | InvalidUtf8 | TooManyOptions | OptionsTooLong | InvalidOptions(_) | InvalidFlags | InvalidQuery | InvalidFragment => Status::ProtocolError,
+            InvalidSignature | NoSignature | KeyIdMismatch | PublicKeyChanged | UnauthorizedPublisher
+            | InsufficientSignatures | NoSecretKey | SecretKeyMismatch | NoSymmetricKeys | InvalidDelegation
+            | DelegationExpired | InvalidNonce | TenantMismatch => Status::AuthFailed,
+            NotFound | UnknownService | UnknownPeer | NoPeersFound | NoReplicasFound => Status::NotFound,
+            TooManyPages | ObjectTooLarge => Status::StorageFull,
+            RateLimited => Status::RateLimited,
+            UnsupportedVersion => Status::UnsupportedVersion,
+            _ => Status::Failed,
+        }
+    }
+}
+
+/// Structured detail attached to status responses, encoded as options following the status code
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StatusDetail {
+    /// Time in seconds before the request should be retried
+    pub retry_after: Option<u32>,
+    /// Maximum supported object size in bytes
+    pub max_size: Option<u32>,
+    /// Short diagnostic message
+    pub diagnostic: Option<OptionString>,
+    /// Additional options (for example, supported versions or algorithms)
+    pub options: Vec<Options>,
+}
+
+impl StatusDetail {
+    /// Set the retry-after time in seconds
+    pub fn with_retry_after(mut self, seconds: u32) -> Self {
+        self.retry_after = Some(seconds);
+        self
+    }
+
+    /// Set the maximum supported object size in bytes
+    pub fn with_max_size(mut self, bytes: u32) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Set the diagnostic message
+    pub fn with_diagnostic(mut self, diagnostic: &str) -> Self {
+        self.diagnostic = Some(diagnostic.into());
+        self
+    }
+
+    /// Attach an additional option
+    pub fn with_option(mut self, o: Options) -> Self {
+        self.options.push(o);
+        self
+    }
+
+    fn options(&self) -> impl Iterator<Item=Options> + '_ {
+        self.retry_after.map(Options::retry_after).into_iter()
+            .chain(self.max_size.map(Options::max_size))
+            .chain(self.diagnostic.clone().map(Options::Diagnostic))
+            .chain(self.options.iter().cloned())
+    }
+}
+
+impl Encode for StatusDetail {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        self.options().map(|o| o.encode_len()).sum()
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut n = 0;
+        for o in self.options() {
+            n += o.encode(&mut buff[n..])?;
+        }
+        Ok(n)
+    }
+}
+
+impl <'a> Decode<'a> for StatusDetail {
+    type Output = StatusDetail;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let mut d = StatusDetail::default();
+
+        for o in Options::decode_iter(buff) {
+            match o? {
+                Options::RetryAfter(v) => d.retry_after = Some(v),
+                Options::MaxSize(v) => d.max_size = Some(v),
+                Options::Diagnostic(v) => d.diagnostic = Some(v),
+                o => d.options.push(o),
+            }
+        }
+
+        Ok((d, buff.len()))
+    }
+}
+
+impl ResponseBody {
+    /// Create a status response with structured detail
+    pub fn status_detail(status: Status, detail: StatusDetail) -> Self {
+        ResponseBody::StatusDetail(status, detail)
+    }
+
+    /// Create a status response for the provided request handling error,
+    /// with the error as the diagnostic message
+    pub fn error(e: &Error) -> Self {
+        ResponseBody::StatusDetail(Status::from(e), StatusDetail::default().with_diagnostic(&alloc::format!("{}", e)))
+    }
+
+    /// Fetch the status for status responses
+    pub fn status(&self) -> Option<Status> {
+        match self {
+            ResponseBody::Status(s) | ResponseBody::StatusDetail(s, _) => Some(*s),
+            _ => None,
+        }
+    }
+
+    /// Fetch structured status detail, if provided
+    pub fn detail(&self) -> Option<&StatusDetail> {
+        match self {
+            ResponseBody::StatusDetail(_, d) => Some(d),
+            _ => None,
+        }
+    }
+}
+
 impl From<&Status> for u32 {
     fn from(s: &Status) -> u32 {
         match s {
             Status::Ok => status::OK,
             Status::InvalidRequest => status::INVALID_REQUEST,
             Status::Failed => status::FAILED,
+            Status::ProtocolError => status::PROTOCOL_ERROR,
+            Status::AuthFailed => status::AUTH_FAILED,
+            Status::NotFound => status::NOT_FOUND,
+            Status::RateLimited => status::RATE_LIMITED,
+            Status::StorageFull => status::STORAGE_FULL,
+            Status::UnsupportedVersion => status::UNSUPPORTED_VERSION,
             Status::Unknown(v) => *v,
         }
     }
@@ -125,6 +314,14 @@ impl Response {
             flags,
             public_key: None,
             remote_address: None,
+            local_address: None,
+            limit: None,
+            continuation: None,
+            algorithms: None,
+            pacing: None,
+            padding: None,
+            nonce: None,
+            transport: None,
         };
         Response { common, data }
     }
@@ -146,6 +343,47 @@ impl Response {
         self.common.public_key = Some(pk);
         self
     }
+
+    /// Signal further results are available using the provided token
+    pub fn with_continuation(mut self, token: ContinuationToken) -> Self {
+        self.common.continuation = Some(token);
+        self
+    }
+
+    /// Advertise the supported algorithm / feature suite
+    pub fn with_algorithms(mut self, suite: AlgorithmSuite) -> Self {
+        self.common.algorithms = Some(suite);
+        self
+    }
+
+    /// Apply flow-control to pushed data (for example, in PushData responses)
+    pub fn with_pacing(mut self, pacing: Pacing) -> Self {
+        self.common.pacing = Some(pacing);
+        self
+    }
+
+    /// Pad the encoded message to the provided length, hiding the message kind in symmetric mode
+    pub fn with_padding(mut self, len: u16) -> Self {
+        self.common.padding = Some(len);
+        self
+    }
+
+    /// Pad the encoded message to [`CONSTANT_MESSAGE_LEN`] for constant-size `Status` exchanges
+    pub fn with_constant_size(self) -> Self {
+        self.with_padding(CONSTANT_MESSAGE_LEN)
+    }
+
+    /// Advertise transport capabilities and constraints (for example, in responses to Hello requests)
+    pub fn with_transport(mut self, hints: TransportHints) -> Self {
+        self.common.transport = Some(hints);
+        self
+    }
+
+    /// Bind a challenge nonce from a request (for example, in responses to Discover requests)
+    pub fn with_nonce(mut self, nonce: u64) -> Self {
+        self.common.nonce = Some(nonce);
+        self
+    }
 }
 
 impl PartialEq for Response {
@@ -155,7 +393,12 @@ impl PartialEq for Response {
 }
 
 impl Response {
-    pub fn convert<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K) -> Result<Response, Error>{
+    pub fn convert<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K) -> Result<Response, Error> {
+        Self::convert_with(base, key_source, &Interceptors::default(), &Limits::default())
+    }
+
+    /// Convert a container into a response, applying the provided pre-convert interceptors and page limits
+    pub fn convert_with<T: ImmutableData, K: KeySource>(base: Container<T>, key_source: &K, interceptors: &Interceptors, limits: &Limits) -> Result<Response, Error> {
         let header = base.header();
 
         if base.encrypted() {
@@ -163,13 +406,9 @@ impl Response {
             return Err(Error::CryptoError);
         }
 
-        let body = base.body_raw();
+        let body = base.try_body_raw()?;
 
-        let remote_address = None;
-
-        let public_options: Vec<_> = base.public_options_iter().collect();
-        //let _private_options = base.private_options().to_vec();
-        let public_key = Filters::pub_key(&public_options.iter());
+        let (common, _public_options) = Common::parse(&base, interceptors)?;
 
         let kind = match ResponseKind::try_from(header.kind()) {
             Ok(k) => k,
@@ -178,17 +417,21 @@ impl Response {
 
         let data = match kind {
             ResponseKind::Status => {
-                let status = NetworkEndian::read_u32(body);
-                ResponseBody::Status(status.into())
+                let (status, rest) = get_u32(body)?;
+
+                // Parse structured detail where present
+                match rest {
+                    [] => ResponseBody::Status(status.into()),
+                    d => ResponseBody::StatusDetail(status.into(), StatusDetail::decode(d)?.0),
+                }
             }
             ResponseKind::NoResult => ResponseBody::NoResult,
             ResponseKind::NodesFound => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[..ID_LEN]);
+                let (id, rest) = get_id(body)?;
 
                 // Build options array from body
-                let options = Options::decode_iter(&body[ID_LEN..])
-                        .collect::<Result<Vec<Options>, Error>>().unwrap();
+                let options = Options::decode_iter(rest)
+                        .collect::<Result<Vec<Options>, Error>>()?;
 
                 let nodes: Vec<_> = (&options[..])
                     .split_before(|o| match o {
@@ -211,31 +454,86 @@ impl Response {
                 ResponseBody::NodesFound(id, nodes)
             }
             ResponseKind::ValuesFound => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, rest) = get_id(body)?;
 
-                let pages = Container::decode_pages(&body[ID_LEN..], key_source)?;
+                let pages = Container::decode_pages_with_limits(rest, key_source, limits)?;
 
                 ResponseBody::ValuesFound(id, pages)
             }
             ResponseKind::PullData => {
-                let mut id = Id::default();
-                id.copy_from_slice(&body[0..ID_LEN]);
+                let (id, rest) = get_id(body)?;
 
-                let pages = Container::decode_pages(&body[ID_LEN..], key_source)?;
+                let pages = Container::decode_pages_with_limits(rest, key_source, limits)?;
 
                 ResponseBody::PullData(id, pages)
             }
-        };
+            ResponseKind::PeerExchange => ResponseBody::PeerExchange(PeerEntry::decode_list(body)?),
+            ResponseKind::Registered => {
+                let (id, rest) = get_id(body)?;
 
-        // Fetch other message specific options
-        let common = Common {
-            from: base.id(),
-            id: header.index(),
-            flags: header.flags(),
-            public_key,
-            remote_address,
+                if rest.len() % SIGNATURE_LEN != 0 {
+                    return Err(Error::BufferLength);
+                }
+
+                let sigs = rest.chunks(SIGNATURE_LEN)
+                    .map(Signature::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                ResponseBody::Registered(id, sigs)
+            }
+            ResponseKind::Discovered => {
+                let options = Options::decode_iter(body)
+                        .collect::<Result<Vec<Options>, Error>>()?;
+
+                // Matches are delimited by PeerId options containing the service ID
+                let matches = (&options[..])
+                    .split_before(|o| matches!(o, Options::PeerId(_)))
+                    .filter_map(|opts| match opts.split_first() {
+                        Some((Options::PeerId(id), rest)) => Some((id.clone(), rest.to_vec())),
+                        _ => None,
+                    })
+                    .collect();
+
+                ResponseBody::Discovered(matches)
+            }
+            ResponseKind::Located => {
+                let (id, rest) = get_id(body)?;
+
+                let pages = Container::decode_pages_with_limits(rest, key_source, limits)?;
+
+                ResponseBody::Located(id, pages)
+            }
         };
+
         Ok(Response { common, data })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn status_codes() {
+        for s in [Status::Ok, Status::ProtocolError, Status::AuthFailed, Status::NotFound,
+                Status::RateLimited, Status::StorageFull, Status::UnsupportedVersion, Status::Unknown(0xff)] {
+            assert_eq!(Status::from(u32::from(&s)), s);
+        }
+
+        assert_eq!(Status::from(&Error::InvalidSignature), Status::AuthFailed);
+        assert_eq!(Status::from(&Error::UnknownService), Status::NotFound);
+        assert_eq!(Status::from(&Error::UnsupportedVersion), Status::UnsupportedVersion);
+        assert_eq!(Status::from(&Error::Timeout), Status::Failed);
+        assert!(Status::RateLimited.is_retryable() && !Status::AuthFailed.is_retryable());
+
+        // Errors are reported with a diagnostic, preserving additional options
+        let body = ResponseBody::error(&Error::NotFound);
+        assert_eq!(body.status(), Some(Status::NotFound));
+        assert_eq!(body.detail().and_then(|d| d.diagnostic.as_ref()).map(|d| d.as_ref()), Some("NotFound"));
+
+        let d = StatusDetail::default().with_retry_after(10).with_option(Options::name("v1"));
+        let mut buff = [0u8; 64];
+        let n = d.encode(&mut buff).unwrap();
+        assert_eq!(StatusDetail::decode(&buff[..n]).unwrap().0, d);
+    }
+}
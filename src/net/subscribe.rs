@@ -0,0 +1,182 @@
+//! Subscription filters, allowing subscribers to receive a subset of the data published by a service.
+//!
+//! Filters are encoded as options following the service ID in `Subscribe` requests,
+//! and evaluated by publishers (see [`SubscribeFilter::matches`]) prior to pushing data.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use encdec::{Encode, Decode, DecodeExt};
+
+use crate::error::Error;
+use crate::options::{Options, OptionKind, OptionString, ResumeToken};
+use crate::types::*;
+use crate::wire::Container;
+
+/// Filter for partial subscriptions
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SubscribeFilter {
+    /// Data object kinds to receive, empty for all kinds
+    pub data_kinds: Vec<u16>,
+    /// Minimum data object index to receive
+    pub min_index: Option<u16>,
+    /// Content type to receive, matched against object `ContentType` options
+    pub content_type: Option<OptionString>,
+    /// Resumption token, limiting data objects to those newer than the last object applied by the subscriber
+    pub resume: Option<ResumeToken>,
+}
+
+impl SubscribeFilter {
+    /// Create a filter for service status objects (see [`ServiceStatus`](crate::service::ServiceStatus))
+    pub fn status() -> Self {
+        Self::default().with_data_kind(DataKind::Status as u16)
+    }
+
+    /// Add a data kind to the filter
+    pub fn with_data_kind(mut self, kind: u16) -> Self {
+        self.data_kinds.push(kind);
+        self
+    }
+
+    /// Set the minimum object index
+    pub fn with_min_index(mut self, index: u16) -> Self {
+        self.min_index = Some(index);
+        self
+    }
+
+    /// Set the content type
+    pub fn with_content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Set the resumption token (see [`Service::resume_token`](crate::service::Service::resume_token))
+    pub fn with_resume(mut self, token: ResumeToken) -> Self {
+        self.resume = Some(token);
+        self
+    }
+
+    /// Check whether a filter is empty (and thus matches all data)
+    pub fn is_empty(&self) -> bool {
+        self.data_kinds.is_empty() && self.min_index.is_none() && self.content_type.is_none() && self.resume.is_none()
+    }
+
+    /// Evaluate the filter against a data object, returning true if this should be pushed to the subscriber
+    pub fn matches<T: ImmutableData>(&self, object: &Container<T>) -> bool {
+        let header = object.header();
+        let kind = header.kind();
+
+        // Filters only apply to data objects
+        if !kind.is_data() {
+            return true;
+        }
+
+        // Check data kind
+        if !self.data_kinds.is_empty() && !self.data_kinds.contains(&kind.index()) {
+            return false;
+        }
+
+        // Check object index
+        if let Some(min) = self.min_index {
+            if header.index() < min {
+                return false;
+            }
+        }
+
+        // Check resumption token, publishers should validate tokens with
+        // [`Service::resume_filter`](crate::service::Service::resume_filter) prior to matching
+        if let Some(r) = &self.resume {
+            if header.index() <= r.index {
+                return false;
+            }
+        }
+
+        // Check content type
+        if let Some(ct) = &self.content_type {
+            if !object.public_options_iter().refs().contains_str(OptionKind::ContentType, ct.as_ref()) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn options(&self) -> impl Iterator<Item=Options> + '_ {
+        self.data_kinds.iter().map(|k| Options::data_kind(*k))
+            .chain(self.min_index.map(Options::min_index))
+            .chain(self.content_type.clone().map(Options::ContentType))
+            .chain(self.resume.clone().map(Options::Resume))
+    }
+}
+
+impl Encode for SubscribeFilter {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        self.options().map(|o| o.encode_len()).sum()
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut n = 0;
+        for o in self.options() {
+            n += o.encode(&mut buff[n..])?;
+        }
+        Ok(n)
+    }
+}
+
+impl <'a> Decode<'a> for SubscribeFilter {
+    type Output = SubscribeFilter;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let mut f = SubscribeFilter::default();
+
+        for o in Options::decode_iter(buff) {
+            match o? {
+                Options::DataKind(v) => f.data_kinds.push(v),
+                Options::MinIndex(v) => f.min_index = Some(v),
+                Options::ContentType(v) => f.content_type = Some(v),
+                Options::Resume(v) => f.resume = Some(v),
+                _ => (),
+            }
+        }
+
+        Ok((f, buff.len()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{DataOptions, Publisher};
+    use crate::test_utils;
+
+    #[test]
+    fn filter_data_objects() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+
+        let mut publish = |kind: u16, opts: &[Options]| {
+            let (_n, c) = s.publish_data(DataOptions::<&[u8]>{ data_kind: kind, public_options: opts, ..Default::default() }, vec![0u8; 1024]).unwrap();
+            c
+        };
+
+        let d1 = publish(1, &[]);
+        let d2 = publish(2, &[Options::content_type("text/plain")]);
+        let d3 = publish(1, &[Options::content_type("application/cbor")]);
+
+        let f = SubscribeFilter::default();
+        assert!(f.is_empty());
+        assert!(f.matches(&d1) && f.matches(&d2) && f.matches(&d3));
+
+        let f = SubscribeFilter::default().with_data_kind(1);
+        assert!(f.matches(&d1) && !f.matches(&d2) && f.matches(&d3));
+
+        let f = SubscribeFilter::default().with_min_index(2);
+        assert!(!f.matches(&d1) && f.matches(&d2) && f.matches(&d3));
+
+        let f = SubscribeFilter::default().with_content_type("application/cbor");
+        assert!(!f.matches(&d1) && !f.matches(&d2) && f.matches(&d3));
+    }
+}
@@ -0,0 +1,129 @@
+//! Transport hints for connection setup, advertising the transports supported by a peer along with
+//! MTU and message size constraints in `Hello` exchanges.
+//!
+//! Peers attach [`TransportHints`] to `Hello` requests and responses, then select a common transport
+//! and message size with [`TransportHints::negotiate`]. Supported crypto modes are negotiated
+//! separately via the advertised [`AlgorithmSuite`](crate::types::AlgorithmSuite).
+
+use crate::error::Error;
+use crate::options::{Options, Filters};
+use crate::types::Transports;
+
+/// Transport capabilities and constraints advertised by a peer
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct TransportHints {
+    /// Supported transports
+    pub transports: Option<Transports>,
+    /// Transport MTU in bytes
+    pub mtu: Option<u16>,
+    /// Maximum accepted message size in bytes
+    pub max_message_size: Option<u32>,
+}
+
+impl TransportHints {
+    /// Create transport hints for the provided transports
+    pub fn new(transports: Transports) -> Self {
+        Self { transports: Some(transports), ..Default::default() }
+    }
+
+    /// Set the transport MTU
+    pub fn with_mtu(mut self, mtu: u16) -> Self {
+        self.mtu = Some(mtu);
+        self
+    }
+
+    /// Set the maximum accepted message size
+    pub fn with_max_message_size(mut self, bytes: u32) -> Self {
+        self.max_message_size = Some(bytes);
+        self
+    }
+
+    /// Fetch transport hints from options, returning `None` where none are set
+    pub fn from_options(options: &impl Filters) -> Option<Self> {
+        let h = Self {
+            transports: options.transports(),
+            mtu: options.mtu(),
+            max_message_size: options.max_message_size(),
+        };
+        match h.is_empty() {
+            true => None,
+            false => Some(h),
+        }
+    }
+
+    /// Check whether any transport hints are set
+    pub fn is_empty(&self) -> bool {
+        self.transports.is_none() && self.mtu.is_none() && self.max_message_size.is_none()
+    }
+
+    /// Fetch options encoding these transport hints
+    pub fn options(&self) -> impl Iterator<Item=Options> {
+        self.transports.map(Options::transports).into_iter()
+            .chain(self.mtu.map(Options::mtu))
+            .chain(self.max_message_size.map(Options::max_message_size))
+    }
+
+    /// Compute the transports and constraints for communication with a peer advertising `remote`,
+    /// applying the most restrictive MTU and message size.
+    ///
+    /// Returns [`Error::NoCommonTransport`] where the peers share no transports.
+    pub fn negotiate(&self, remote: Option<&TransportHints>) -> Result<TransportHints, Error> {
+        let min = |a: Option<u32>, b: Option<u32>| match (a, b) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        let local = self.transports.unwrap_or(Transports::UDP);
+        let transports = local.common(remote.and_then(|r| r.transports));
+        if transports.is_empty() {
+            return Err(Error::NoCommonTransport);
+        }
+
+        let remote = remote.cloned().unwrap_or_default();
+        Ok(TransportHints {
+            transports: Some(transports),
+            mtu: min(self.mtu.map(u32::from), remote.mtu.map(u32::from)).map(|v| v as u16),
+            max_message_size: min(self.max_message_size, remote.max_message_size),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::{Message, Request, RequestBody, Response, ResponseBody};
+    use crate::test_utils;
+    use crate::types::Flags;
+
+    #[test]
+    fn negotiate_transports() {
+        let (a, b) = test_utils::service_pair();
+
+        let local = TransportHints::new(Transports::UDP | Transports::BLE).with_mtu(1280).with_max_message_size(4096);
+        let remote = TransportHints::new(Transports::BLE | Transports::LORA | Transports::RELAY_ONLY).with_mtu(244);
+
+        // Hints are carried in Hello exchanges
+        let req = Request::new(a.id(), 1, RequestBody::Hello, Flags::empty()).with_transport(local);
+        let c = req.encode(&a.keys(), vec![0u8; 1024]).unwrap();
+        let (m, _n) = Message::parse(c.raw().to_vec(), &test_utils::TestKeySource::default()).unwrap();
+        assert!(matches!(m, Message::Request(r) if r.common.transport == Some(local)));
+
+        let resp = Response::new(b.id(), 1, ResponseBody::Status(crate::net::Status::Ok), Flags::empty()).with_transport(remote);
+        let c = resp.encode(&b.keys(), vec![0u8; 1024]).unwrap();
+        let (m, _n) = Message::parse(c.raw().to_vec(), &test_utils::TestKeySource::default()).unwrap();
+        assert!(matches!(m, Message::Response(r) if r.common.transport == Some(remote)));
+
+        // Peers select common transports and the most restrictive constraints
+        let n = local.negotiate(Some(&remote)).unwrap();
+        assert_eq!(n.transports, Some(Transports::BLE));
+        assert_eq!(n.mtu, Some(244));
+        assert_eq!(n.max_message_size, Some(4096));
+
+        // Peers without hints are assumed to support UDP
+        assert_eq!(local.negotiate(None).unwrap().transports, Some(Transports::UDP));
+        assert_eq!(remote.negotiate(None), Err(Error::NoCommonTransport));
+        assert_eq!(TransportHints::new(Transports::LORA).negotiate(Some(&local)), Err(Error::NoCommonTransport));
+    }
+}
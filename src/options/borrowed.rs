@@ -0,0 +1,198 @@
+//! Borrowed (zero-copy) views over encoded options, allowing option values
+//! to be matched without decoding into owned [`Options`] objects.
+
+use core::convert::TryFrom;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use encdec::Decode;
+
+use crate::error::Error;
+use super::{Options, OptionKind, OptionLimits, OPTION_HEADER_LEN};
+
+/// Borrowed view of an encoded option
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OptionRef<'a> {
+    kind: u16,
+    raw: &'a [u8],
+}
+
+impl <'a> OptionRef<'a> {
+    /// Parse an option header from the provided buffer, returning the option view and encoded length
+    pub fn parse(buff: &'a [u8]) -> Result<(Self, usize), Error> {
+        if buff.len() < OPTION_HEADER_LEN {
+            return Err(Error::InvalidOptionLength);
+        }
+
+        let kind = NetworkEndian::read_u16(&buff[0..2]);
+        let len = NetworkEndian::read_u16(&buff[2..4]) as usize;
+
+        if OPTION_HEADER_LEN + len > buff.len() {
+            return Err(Error::InvalidOptionLength);
+        }
+
+        let raw = &buff[..OPTION_HEADER_LEN + len];
+
+        Ok((Self{ kind, raw }, raw.len()))
+    }
+
+    /// Fetch the option kind, if recognised
+    pub fn kind(&self) -> Option<OptionKind> {
+        OptionKind::try_from(self.kind).ok()
+    }
+
+    /// Fetch the raw option kind
+    pub fn raw_kind(&self) -> u16 {
+        self.kind
+    }
+
+    /// Check whether the option is of the provided kind
+    pub fn is(&self, kind: OptionKind) -> bool {
+        self.kind == kind as u16
+    }
+
+    /// Fetch the raw option value
+    pub fn data(&self) -> &'a [u8] {
+        &self.raw[OPTION_HEADER_LEN..]
+    }
+
+    /// Fetch the raw encoded option (including header)
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Fetch the value of string options (kind, name, etc.) without copying
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.kind()? {
+            OptionKind::Kind | OptionKind::Name | OptionKind::Building | OptionKind::Room | OptionKind::Manufacturer
+                | OptionKind::Serial | OptionKind::Diagnostic | OptionKind::ContentType => core::str::from_utf8(self.data()).ok(),
+            _ => None,
+        }
+    }
+
+    /// Fetch the key and value of metadata options without copying
+    pub fn as_meta(&self) -> Option<(&'a str, &'a str)> {
+        if !self.is(OptionKind::Meta) {
+            return None;
+        }
+
+        let s = core::str::from_utf8(self.data()).ok()?;
+        let mut sp = s.splitn(2, '|');
+
+        match (sp.next(), sp.next()) {
+            (Some(k), Some(v)) => Some((k, v)),
+            _ => None,
+        }
+    }
+
+    /// Decode into an owned [`Options`] object
+    pub fn to_options(&self) -> Result<Options, Error> {
+        Options::decode(self.raw).map(|(o, _n)| o)
+    }
+}
+
+/// Iterator over borrowed options in an encoded options region
+#[derive(Clone, Debug)]
+pub struct OptionRefIter<'a> {
+    buff: &'a [u8],
+    index: usize,
+    count: usize,
+    max_count: usize,
+}
+
+impl <'a> OptionRefIter<'a> {
+    /// Create a borrowed options iterator over the provided buffer
+    pub fn new(buff: &'a [u8]) -> Self {
+        Self::with_limits(buff, &OptionLimits::default())
+    }
+
+    /// Create a borrowed options iterator, stopping once the configured maximum option count is reached
+    pub fn with_limits(buff: &'a [u8], limits: &OptionLimits) -> Self {
+        Self::with_max_count(buff, limits.max_count)
+    }
+
+    pub(crate) fn with_max_count(buff: &'a [u8], max_count: usize) -> Self {
+        Self{ buff, index: 0, count: 0, max_count }
+    }
+
+    /// Find the first option of the provided kind
+    pub fn find_kind(&self, kind: OptionKind) -> Option<OptionRef<'a>> {
+        self.clone().find(|o| o.is(kind))
+    }
+
+    /// Find the first string option of the provided kind
+    pub fn find_str(&self, kind: OptionKind) -> Option<&'a str> {
+        self.find_kind(kind).and_then(|o| o.as_str())
+    }
+
+    /// Check whether a string option of the provided kind matches the provided value
+    pub fn contains_str(&self, kind: OptionKind, value: &str) -> bool {
+        self.clone().any(|o| o.is(kind) && o.as_str() == Some(value))
+    }
+}
+
+impl <'a> Iterator for OptionRefIter<'a> {
+    type Item = OptionRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rem = &self.buff[self.index..];
+
+        if rem.len() < OPTION_HEADER_LEN || self.count >= self.max_count {
+            return None;
+        }
+
+        let (o, n) = match OptionRef::parse(rem) {
+            Ok(v) => v,
+            Err(e) => {
+                error!("Option parsing error: {:?}", e);
+                return None;
+            }
+        };
+
+        self.index += n;
+        self.count += 1;
+
+        Some(o)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{Id, ID_LEN};
+    use encdec::EncodeExt;
+    use super::super::{Filters, OptionsIter};
+
+    #[test]
+    fn borrowed_options() {
+        let opts = [
+            Options::name("test-name"),
+            Options::meta("key", "value"),
+            Options::peer_id(Id::from([0xaa; ID_LEN])),
+            Options::content_type("text/plain"),
+        ];
+
+        let mut buff = [0u8; 256];
+        let n = Options::encode_iter(opts.iter(), &mut buff).unwrap();
+
+        let refs = OptionRefIter::new(&buff[..n]);
+        assert_eq!(refs.clone().count(), opts.len());
+
+        // Values are borrowed from the encoded buffer
+        assert_eq!(refs.find_str(OptionKind::Name), Some("test-name"));
+        assert_eq!(refs.find_kind(OptionKind::Meta).and_then(|o| o.as_meta()), Some(("key", "value")));
+        assert_eq!(refs.find_kind(OptionKind::PeerId).map(|o| o.data()), Some(&[0xaa; ID_LEN][..]));
+        assert_eq!(refs.find_kind(OptionKind::PeerId).and_then(|o| o.as_str()), None);
+        assert!(refs.contains_str(OptionKind::ContentType, "text/plain"));
+        assert!(!refs.contains_str(OptionKind::Kind, "text/plain"));
+
+        // And can be converted to owned options
+        let owned: Vec<_> = refs.clone().map(|o| o.to_options().unwrap()).collect();
+        assert_eq!(&owned[..], &opts[..]);
+
+        // Filters match owned decoding
+        let iter = OptionsIter::new(&buff[..n]);
+        assert_eq!(iter.peer_id(), Some(Id::from([0xaa; ID_LEN])));
+        assert_eq!(iter.name(), Some("test-name".into()));
+        assert_eq!(iter.pub_key(), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Geohash bucketing for [`Coordinates`], supporting location-based queries via name services.
+//!
+//! Coordinates are quantised into geohash cells of a configurable precision (in base32
+//! characters, each adding 5 bits), with cells hashed as [`OptionKind::Coord`] queries.
+//! Services are registered against the cell containing their location, and lookups over an
+//! area query each of the cells returned by [`GeoHash::covering`].
+//!
+//! Cells are indexed by floor division over the coordinate range, so coordinates on cell
+//! boundaries fall into the north / east cell (and the maximum latitude / longitude into the last).
+
+use core::fmt;
+
+use crate::error::Error;
+use crate::types::{CryptoHasher, Queryable};
+
+use super::{Coordinates, OptionKind};
+
+/// Default geohash precision (~4.9km x 4.9km cells)
+pub const DEFAULT_GEOHASH_PRECISION: u8 = 5;
+
+/// Maximum geohash precision (60 bits)
+pub const MAX_GEOHASH_PRECISION: u8 = 12;
+
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Geohash cell, with bits interleaved longitude first
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GeoHash {
+    bits: u64,
+    precision: u8,
+}
+
+/// Bit lengths of the longitude and latitude components for a precision
+fn component_bits(precision: u8) -> (u32, u32) {
+    let n = precision as u32 * 5;
+    ((n + 1) / 2, n / 2)
+}
+
+/// Compute the cell index of a value in `[min, max]` with `bits` bits of resolution
+fn cell_index(v: f64, min: f64, max: f64, bits: u32) -> u64 {
+    let cells = 1u64 << bits;
+    let i = ((v - min) / (max - min) * cells as f64) as u64;
+    i.min(cells - 1)
+}
+
+fn check_precision(precision: u8) -> Result<(), Error> {
+    match precision {
+        1..=MAX_GEOHASH_PRECISION => Ok(()),
+        _ => Err(Error::InvalidQuery),
+    }
+}
+
+fn check_coordinates(lat: f64, lng: f64) -> Result<(), Error> {
+    match (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) {
+        true => Ok(()),
+        false => Err(Error::InvalidOption),
+    }
+}
+
+impl GeoHash {
+    /// Compute the geohash cell containing the provided latitude and longitude
+    pub fn encode(lat: f32, lng: f32, precision: u8) -> Result<Self, Error> {
+        let (lat, lng) = (lat as f64, lng as f64);
+        check_precision(precision)?;
+        check_coordinates(lat, lng)?;
+
+        let (x_bits, y_bits) = component_bits(precision);
+        let x = cell_index(lng, -180.0, 180.0, x_bits);
+        let y = cell_index(lat, -90.0, 90.0, y_bits);
+
+        Ok(Self::from_cell(x, y, precision))
+    }
+
+    /// Build a geohash from longitude and latitude cell indices
+    fn from_cell(x: u64, y: u64, precision: u8) -> Self {
+        let (x_bits, y_bits) = component_bits(precision);
+        let mut bits = 0;
+
+        for i in 0..(x_bits + y_bits) {
+            let b = match i % 2 == 0 {
+                true => x >> (x_bits - 1 - i / 2),
+                false => y >> (y_bits - 1 - i / 2),
+            };
+            bits = bits << 1 | (b & 1);
+        }
+
+        Self { bits, precision }
+    }
+
+    /// Split a geohash into longitude and latitude cell indices
+    fn cell(&self) -> (u64, u64) {
+        let n = self.precision as u32 * 5;
+        let (mut x, mut y) = (0, 0);
+
+        for i in 0..n {
+            let b = self.bits >> (n - 1 - i) & 1;
+            match i % 2 == 0 {
+                true => x = x << 1 | b,
+                false => y = y << 1 | b,
+            }
+        }
+
+        (x, y)
+    }
+
+    /// Fetch the geohash precision (in base32 characters)
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Fetch the interleaved geohash bits
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Fetch the cell bounds as south-west and north-east corners
+    pub fn bounds(&self) -> (Coordinates, Coordinates) {
+        let (x_bits, y_bits) = component_bits(self.precision);
+        let (x, y) = self.cell();
+
+        let (w, h) = (360.0 / (1u64 << x_bits) as f64, 180.0 / (1u64 << y_bits) as f64);
+        let (lng, lat) = (-180.0 + x as f64 * w, -90.0 + y as f64 * h);
+
+        (
+            Coordinates{ lat: lat as f32, lng: lng as f32, alt: 0.0 },
+            Coordinates{ lat: (lat + h) as f32, lng: (lng + w) as f32, alt: 0.0 },
+        )
+    }
+
+    /// Compute the geohash cells covering the bounding box between the south-west
+    /// and north-east corners, returning [`Error::InvalidQuery`] where more than `max` cells
+    /// would be required.
+    ///
+    /// Boxes with a western longitude greater than the eastern longitude wrap the antimeridian.
+    #[cfg(feature = "alloc")]
+    pub fn covering(sw: &Coordinates, ne: &Coordinates, precision: u8, max: usize) -> Result<alloc::vec::Vec<Self>, Error> {
+        check_precision(precision)?;
+        check_coordinates(sw.lat as f64, sw.lng as f64)?;
+        check_coordinates(ne.lat as f64, ne.lng as f64)?;
+        if sw.lat > ne.lat {
+            return Err(Error::InvalidQuery);
+        }
+
+        let (x_bits, y_bits) = component_bits(precision);
+        let (x0, x1) = (cell_index(sw.lng as f64, -180.0, 180.0, x_bits), cell_index(ne.lng as f64, -180.0, 180.0, x_bits));
+        let (y0, y1) = (cell_index(sw.lat as f64, -90.0, 90.0, y_bits), cell_index(ne.lat as f64, -90.0, 90.0, y_bits));
+
+        let xs: alloc::vec::Vec<u64> = match x0 <= x1 && sw.lng <= ne.lng {
+            true => (x0..=x1).collect(),
+            false => (x0..(1 << x_bits)).chain(0..=x1).collect(),
+        };
+
+        let n = (xs.len() as u64).saturating_mul(y1 - y0 + 1);
+        if n > max as u64 {
+            debug!("Bounding box requires {} cells at precision {} (max {})", n, precision, max);
+            return Err(Error::InvalidQuery);
+        }
+
+        let mut cells = alloc::vec::Vec::with_capacity(n as usize);
+        for y in y0..=y1 {
+            cells.extend(xs.iter().map(|x| Self::from_cell(*x, y, precision)));
+        }
+
+        Ok(cells)
+    }
+}
+
+/// Display as a base32 geohash string
+impl fmt::Display for GeoHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for i in (0..self.precision).rev() {
+            let c = GEOHASH_ALPHABET[(self.bits >> (i as u32 * 5) & 0x1f) as usize];
+            write!(f, "{}", c as char)?;
+        }
+        Ok(())
+    }
+}
+
+/// Geohash cells are queryable as coordinate options
+impl Queryable for GeoHash {
+    fn hash<H: CryptoHasher>(&self, h: &mut H) -> bool {
+        h.update(&(OptionKind::Coord as u16).to_le_bytes());
+        self.hash_cell(h);
+        true
+    }
+}
+
+impl GeoHash {
+    /// Hash cell data, following the option kind
+    pub(crate) fn hash_cell<H: CryptoHasher>(&self, h: &mut H) {
+        h.update(&[self.precision]);
+        h.update(&self.bits.to_le_bytes());
+    }
+}
+
+impl Coordinates {
+    /// Compute the geohash cell containing these coordinates
+    pub fn geohash(&self, precision: u8) -> Result<GeoHash, Error> {
+        GeoHash::encode(self.lat, self.lng, precision)
+    }
+}
+
+/// Coordinates are queryable by the containing cell at [`DEFAULT_GEOHASH_PRECISION`]
+impl Queryable for Coordinates {
+    fn hash<H: CryptoHasher>(&self, h: &mut H) -> bool {
+        match self.geohash(DEFAULT_GEOHASH_PRECISION) {
+            Ok(g) => g.hash(h),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{Crypto, Hash as _};
+    use crate::options::Options;
+    use crate::types::CryptoHash;
+    use crate::test_utils;
+
+    fn coord(lat: f32, lng: f32) -> Coordinates {
+        Coordinates{ lat, lng, alt: 0.0 }
+    }
+
+    fn tid(q: impl Queryable) -> Option<CryptoHash> {
+        let s = test_utils::service(test_utils::SERVICE_SEED);
+        Crypto::hash_tid(s.id(), &s.keys(), q).ok()
+    }
+
+    #[test]
+    fn geohash_encode() {
+        // Reference geohashes (limited by f32 coordinate precision)
+        let g = GeoHash::encode(57.64911, 10.40744, 9).unwrap();
+        assert_eq!(g.to_string(), "u4pruydqq");
+        assert_eq!(GeoHash::encode(-25.382708, -49.265506, 5).unwrap().to_string(), "6gkzw");
+
+        let (sw, ne) = g.bounds();
+        assert!(sw.lat <= 57.64911 && ne.lat >= 57.64911);
+        assert!(sw.lng <= 10.40744 && ne.lng >= 10.40744);
+
+        assert_eq!(GeoHash::encode(90.0, 180.0, 1).unwrap().to_string(), "z");
+        assert_eq!(GeoHash::encode(91.0, 0.0, 5), Err(Error::InvalidOption));
+        assert_eq!(GeoHash::encode(0.0, 0.0, 13), Err(Error::InvalidQuery));
+        assert_eq!(GeoHash::encode(f32::NAN, 0.0, 5), Err(Error::InvalidOption));
+
+        // Coordinates and coordinate options hash to the same cell
+        let c = coord(57.64911, 10.40744);
+        assert!(tid(c.clone()).is_some());
+        assert_eq!(tid(c.clone()), tid(c.geohash(DEFAULT_GEOHASH_PRECISION).unwrap()));
+        assert_eq!(tid(&Options::Coord(c.clone())), tid(c.clone()));
+        assert_ne!(tid(c), tid(coord(-25.382708, -49.265506)));
+        assert_eq!(tid(coord(100.0, 0.0)), None);
+    }
+
+    #[test]
+    fn geohash_covering() {
+        let (sw, ne) = GeoHash::encode(57.64911, 10.40744, 5).unwrap().bounds();
+
+        // Cells cover the box and neighbouring cells
+        let cells = GeoHash::covering(&sw, &ne, 5, 9).unwrap();
+        let c = coord(57.64911, 10.40744).geohash(5).unwrap();
+        assert!(cells.contains(&c));
+
+        let inner = coord(57.65, 10.41);
+        let cells = GeoHash::covering(&inner, &inner, 5, 1).unwrap();
+        assert_eq!(cells, vec![c]);
+
+        // Large areas are limited
+        assert_eq!(GeoHash::covering(&coord(-10.0, -10.0), &coord(10.0, 10.0), 5, 16), Err(Error::InvalidQuery));
+        assert_eq!(GeoHash::covering(&coord(10.0, 0.0), &coord(-10.0, 0.0), 5, 16), Err(Error::InvalidQuery));
+
+        // Boxes may wrap the antimeridian
+        let cells = GeoHash::covering(&coord(0.0, 179.9), &coord(0.1, -179.9), 3, 16).unwrap();
+        assert_eq!(cells.len(), 2);
+        assert!(cells.contains(&GeoHash::encode(0.05, 180.0, 3).unwrap()));
+        assert!(cells.contains(&GeoHash::encode(0.05, -180.0, 3).unwrap()));
+    }
+}
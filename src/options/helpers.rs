@@ -4,26 +4,70 @@ use core::fmt::Display;
 
 use encdec::{Encode, Decode};
 
-use crate::types::{PublicKey, ImmutableData, Address, Signature, DateTime, Id};
-use super::{String, Options, OPTION_HEADER_LEN, MAX_OPTION_LEN, OptionString};
+use crate::error::Error;
+use crate::types::{PublicKey, ImmutableData, Address, AlgorithmSuite, CryptoHash, Signature, DateTime, Id, Transports};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use super::{String, Options, OptionKind, Coordinates, OptionRefIter, OptionValidation, OPTION_HEADER_LEN, MAX_OPTION_LEN, OptionString, ContinuationToken};
+
+
+/// Default maximum number of options in an options section
+pub const DEFAULT_MAX_OPTIONS: usize = 64;
+
+/// Default maximum total length of an options section
+pub const DEFAULT_MAX_OPTIONS_LEN: usize = 2048;
+
+/// Limits applied when parsing options, guarding against objects containing
+/// excessive numbers of options that are repeatedly re-walked by iterators
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OptionLimits {
+    /// Maximum number of options per section
+    pub max_count: usize,
+    /// Maximum total length (in bytes) of an options section
+    pub max_len: usize,
+    /// Validation strictness (duplicate and ordering checks)
+    pub validation: OptionValidation,
+}
+
+impl Default for OptionLimits {
+    fn default() -> Self {
+        Self {
+            max_count: DEFAULT_MAX_OPTIONS,
+            max_len: DEFAULT_MAX_OPTIONS_LEN,
+            validation: OptionValidation::default(),
+        }
+    }
+}
 
+impl OptionLimits {
+    /// Check an encoded options section against the configured limits,
+    /// returning the number of options on success.
+    ///
+    /// See [`OptionLimits::validate`] for structured errors identifying the offending option.
+    pub fn check(&self, buff: &[u8]) -> Result<usize, Error> {
+        self.validate(buff).map_err(Error::from)
+    }
+}
 
 /// Iterator for decoding options from the provided buffer
 pub struct OptionsIter<T> {
     index: usize,
+    count: usize,
+    max_count: usize,
     buff: T,
 }
 
 impl <T: ImmutableData> core::fmt::Debug for OptionsIter<T> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        let i = OptionsIter::new(&self.buff);
-        f.debug_list().entries(i).finish()
+        f.debug_list().entries(self.restart()).finish()
     }
 }
 
 impl <T: ImmutableData + Clone> Clone for OptionsIter<T> {
     fn clone(&self) -> Self {
-        Self { index: 0, buff: self.buff.clone() }
+        Self { index: 0, count: 0, max_count: self.max_count, buff: self.buff.clone() }
     }
 }
 
@@ -32,7 +76,30 @@ where
     T: AsRef<[u8]>,
 {
     pub(crate) fn new(buff: T) -> Self {
-        Self { index: 0, buff }
+        Self::with_limits(buff, &OptionLimits::default())
+    }
+
+    /// Create an options iterator over the provided buffer,
+    /// stopping once the configured maximum option count is reached
+    pub fn with_limits(buff: T, limits: &OptionLimits) -> Self {
+        Self { index: 0, count: 0, max_count: limits.max_count, buff }
+    }
+
+    /// Restart iteration from the start of the buffer
+    fn restart(&self) -> OptionsIter<&[u8]> {
+        OptionsIter { index: 0, count: 0, max_count: self.max_count, buff: self.buff.as_ref() }
+    }
+
+    /// Iterate over borrowed options (from the start of the buffer) without decoding option values
+    pub fn refs(&self) -> OptionRefIter<'_> {
+        OptionRefIter::with_max_count(self.buff.as_ref(), self.max_count)
+    }
+
+    /// Decode the first option matching one of the provided kinds
+    fn find_decode<R, F: Fn(Options) -> Option<R>>(&self, kinds: &[OptionKind], f: F) -> Option<R> {
+        self.refs()
+            .filter(|o| kinds.iter().any(|k| o.is(*k)))
+            .find_map(|o| o.to_options().ok().and_then(&f))
     }
 }
 
@@ -51,6 +118,12 @@ where
             return None;
         }
 
+        // Stop once we've hit the option limit
+        if self.count >= self.max_count {
+            warn!("Option limit ({}) reached, ignoring remaining options", self.max_count);
+            return None;
+        }
+
         let (o, n) = match Options::decode(rem) {
             Ok(v) => v,
             Err(e) => {
@@ -60,6 +133,7 @@ where
         };
 
         self.index += n;
+        self.count += 1;
 
         Some(o)
     }
@@ -75,61 +149,289 @@ pub trait Filters {
     fn prev_sig(&self) -> Option<Signature>;
     fn address(&self) -> Option<Address>;
     fn name(&self) -> Option<OptionString>;
+    fn kind(&self) -> Option<OptionString>;
+    fn limit(&self) -> Option<u32>;
+    fn continuation(&self) -> Option<ContinuationToken>;
+    fn algorithms(&self) -> Option<AlgorithmSuite>;
+    fn window(&self) -> Option<u32>;
+    fn max_rate(&self) -> Option<u32>;
+    fn tenant(&self) -> Option<Id>;
+    fn nonce(&self) -> Option<u64>;
+
+    /// Find the first option of the provided kind
+    fn find_map_kind(&self, kind: OptionKind) -> Option<Options>;
+
+    /// Visit each option matching one of the provided kinds, in order
+    fn for_each_kind<F: FnMut(Options)>(&self, kinds: &[OptionKind], f: F);
+
+    /// Fetch all address options, in order of preference
+    #[cfg(feature = "alloc")]
+    fn addresses(&self) -> Vec<Address> {
+        let mut addresses = Vec::new();
+        self.for_each_kind(&[OptionKind::IpAddrV4, OptionKind::IpAddrV6], |o| match o {
+            Options::IPv4(addr) => addresses.push(addr.into()),
+            Options::IPv6(addr) => addresses.push(addr.into()),
+            _ => (),
+        });
+        addresses
+    }
+
+    /// Fetch the first block hash option
+    fn block_hash(&self) -> Option<CryptoHash> {
+        match self.find_map_kind(OptionKind::BlockHash) {
+            Some(Options::BlockHash(h)) => Some(h),
+            _ => None,
+        }
+    }
+
+    /// Fetch all block hash options, in order (for example, the chunks of a large object)
+    #[cfg(feature = "alloc")]
+    fn block_hashes(&self) -> Vec<CryptoHash> {
+        let mut hashes = Vec::new();
+        self.for_each_kind(&[OptionKind::BlockHash], |o| if let Options::BlockHash(h) = o {
+            hashes.push(h);
+        });
+        hashes
+    }
+
+    /// Fetch the transports advertised by a peer
+    fn transports(&self) -> Option<Transports> {
+        match self.find_map_kind(OptionKind::Transports) {
+            Some(Options::Transports(t)) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Fetch the transport MTU advertised by a peer
+    fn mtu(&self) -> Option<u16> {
+        match self.find_map_kind(OptionKind::Mtu) {
+            Some(Options::Mtu(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Fetch the maximum message size advertised by a peer
+    fn max_message_size(&self) -> Option<u32> {
+        match self.find_map_kind(OptionKind::MaxMessageSize) {
+            Some(Options::MaxMessageSize(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Fetch the requester local address from an address request option
+    fn address_request(&self) -> Option<Address> {
+        match self.find_map_kind(OptionKind::AddressRequest) {
+            Some(Options::AddressRequest(a)) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Fetch the observed requester address from an address response option
+    fn address_response(&self) -> Option<Address> {
+        match self.find_map_kind(OptionKind::AddressResponse) {
+            Some(Options::AddressResponse(a)) => Some(a),
+            _ => None,
+        }
+    }
+
+    /// Fetch the value of the first metadata option with the provided key
+    fn metadata(&self, key: &str) -> Option<heapless::String<48>> {
+        let mut value = None;
+        self.for_each_kind(&[OptionKind::Meta], |o| match o {
+            Options::Metadata(m) if value.is_none() && m.key == key => value = Some(m.value),
+            _ => (),
+        });
+        value
+    }
+
+    fn coordinates(&self) -> Option<Coordinates> {
+        match self.find_map_kind(OptionKind::Coord) {
+            Some(Options::Coord(c)) => Some(c),
+            _ => None,
+        }
+    }
+
+    fn manufacturer(&self) -> Option<OptionString> {
+        match self.find_map_kind(OptionKind::Manufacturer) {
+            Some(Options::Manufacturer(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn serial(&self) -> Option<OptionString> {
+        match self.find_map_kind(OptionKind::Serial) {
+            Some(Options::Serial(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn building(&self) -> Option<OptionString> {
+        match self.find_map_kind(OptionKind::Building) {
+            Some(Options::Building(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn room(&self) -> Option<OptionString> {
+        match self.find_map_kind(OptionKind::Room) {
+            Some(Options::Room(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn content_type(&self) -> Option<OptionString> {
+        match self.find_map_kind(OptionKind::ContentType) {
+            Some(Options::ContentType(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn data_kind(&self) -> Option<u16> {
+        match self.find_map_kind(OptionKind::DataKind) {
+            Some(Options::DataKind(k)) => Some(k),
+            _ => None,
+        }
+    }
+
+    fn retry_after(&self) -> Option<u32> {
+        match self.find_map_kind(OptionKind::RetryAfter) {
+            Some(Options::RetryAfter(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    fn max_size(&self) -> Option<u32> {
+        match self.find_map_kind(OptionKind::MaxSize) {
+            Some(Options::MaxSize(n)) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Check the name option matches the provided name following normalisation
+    fn matches_name(&self, name: &str) -> bool {
+        self.name().map(|n| n.matches(name)).unwrap_or(false)
+    }
+
+    /// Check the kind option matches the provided kind following normalisation
+    fn matches_kind(&self, kind: &str) -> bool {
+        self.kind().map(|k| k.matches(kind)).unwrap_or(false)
+    }
 }
 
-/// Filter implementation for [`OptionsIter`]
+/// Filter implementation for [`OptionsIter`], matching option kinds via borrowed
+/// [`OptionRef`]s and decoding only the selected option
 impl <T: AsRef<[u8]>> Filters for OptionsIter<T> {
+    fn find_map_kind(&self, kind: OptionKind) -> Option<Options> {
+        self.find_decode(&[kind], Some)
+    }
+
+    fn for_each_kind<F: FnMut(Options)>(&self, kinds: &[OptionKind], mut f: F) {
+        self.refs()
+            .filter(|o| kinds.iter().any(|k| o.is(*k)))
+            .filter_map(|o| o.to_options().ok())
+            .for_each(|o| f(o))
+    }
+
     fn pub_key(&self) -> Option<PublicKey> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
-            Options::PubKey(pk) => Some(pk.clone()),
+        self.find_decode(&[OptionKind::PubKey], |o| match o {
+            Options::PubKey(pk) => Some(pk),
             _ => None,
         })
     }
 
     fn peer_id(&self) -> Option<Id> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
-            Options::PeerId(peer_id) => Some(peer_id.clone()),
+        self.find_decode(&[OptionKind::PeerId], |o| match o {
+            Options::PeerId(peer_id) => Some(peer_id),
             _ => None,
         })
     }
 
     fn issued(&self) -> Option<DateTime> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
+        self.find_decode(&[OptionKind::Issued], |o| match o {
             Options::Issued(t) => Some(t),
             _ => None,
         })
     }
 
     fn expiry(&self) -> Option<DateTime> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
+        self.find_decode(&[OptionKind::Expiry], |o| match o {
             Options::Expiry(t) => Some(t),
             _ => None,
         })
     }
 
     fn prev_sig(&self) -> Option<Signature> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
-            Options::PrevSig(s) => Some(s.clone()),
+        self.find_decode(&[OptionKind::PrevSig], |o| match o {
+            Options::PrevSig(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    fn limit(&self) -> Option<u32> {
+        self.find_decode(&[OptionKind::Limit], |o| match o {
+            Options::Limit(n) => Some(n),
+            _ => None,
+        })
+    }
+
+    fn continuation(&self) -> Option<ContinuationToken> {
+        self.find_decode(&[OptionKind::Continuation], |o| match o {
+            Options::Continuation(t) => Some(t),
+            _ => None,
+        })
+    }
+
+    fn algorithms(&self) -> Option<AlgorithmSuite> {
+        self.find_decode(&[OptionKind::Algorithms], |o| match o {
+            Options::Algorithms(a) => Some(a),
+            _ => None,
+        })
+    }
+
+    fn window(&self) -> Option<u32> {
+        self.find_decode(&[OptionKind::Window], |o| match o {
+            Options::Window(n) => Some(n),
+            _ => None,
+        })
+    }
+
+    fn max_rate(&self) -> Option<u32> {
+        self.find_decode(&[OptionKind::MaxRate], |o| match o {
+            Options::MaxRate(n) => Some(n),
+            _ => None,
+        })
+    }
+
+    fn tenant(&self) -> Option<Id> {
+        self.find_decode(&[OptionKind::Tenant], |o| match o {
+            Options::Tenant(id) => Some(id),
+            _ => None,
+        })
+    }
+
+    fn nonce(&self) -> Option<u64> {
+        self.find_decode(&[OptionKind::Nonce], |o| match o {
+            Options::Nonce(n) => Some(n),
             _ => None,
         })
     }
 
     fn name(&self) -> Option<OptionString> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
-            Options::Name(name) => Some(name.clone()),
+        self.find_decode(&[OptionKind::Name], |o| match o {
+            Options::Name(name) => Some(name),
+            _ => None,
+        })
+    }
+
+    fn kind(&self) -> Option<OptionString> {
+        self.find_decode(&[OptionKind::Kind], |o| match o {
+            Options::Kind(kind) => Some(kind),
             _ => None,
         })
     }
 
     fn address(&self) -> Option<Address> {
-        let mut s = OptionsIter{ index: 0, buff: self.buff.as_ref() };
-        s.find_map(|o| match o {
+        self.find_decode(&[OptionKind::IpAddrV4, OptionKind::IpAddrV6], |o| match o {
             Options::IPv4(addr) => Some((addr).into()),
             Options::IPv6(addr) => Some((addr).into()),
             _ => None,
@@ -139,6 +441,16 @@ impl <T: AsRef<[u8]>> Filters for OptionsIter<T> {
 
 /// [`Filters`] implementation for types implementing Iterator over Options
 impl <'a, T: Iterator<Item=&'a Options> + Clone> Filters for T {
+    fn find_map_kind(&self, kind: OptionKind) -> Option<Options> {
+        self.clone().find(|o| OptionKind::from(*o) == kind).cloned()
+    }
+
+    fn for_each_kind<F: FnMut(Options)>(&self, kinds: &[OptionKind], mut f: F) {
+        self.clone()
+            .filter(|o| kinds.contains(&OptionKind::from(*o)))
+            .for_each(|o| f(o.clone()))
+    }
+
     fn pub_key(&self) -> Option<PublicKey> {
         self.clone().find_map(|o| match o {
             Options::PubKey(pk) => Some(pk.clone()),
@@ -174,6 +486,55 @@ impl <'a, T: Iterator<Item=&'a Options> + Clone> Filters for T {
         })
     }
 
+    fn limit(&self) -> Option<u32> {
+        self.clone().find_map(|o| match o {
+            Options::Limit(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    fn continuation(&self) -> Option<ContinuationToken> {
+        self.clone().find_map(|o| match o {
+            Options::Continuation(t) => Some(t.clone()),
+            _ => None,
+        })
+    }
+
+    fn algorithms(&self) -> Option<AlgorithmSuite> {
+        self.clone().find_map(|o| match o {
+            Options::Algorithms(a) => Some(*a),
+            _ => None,
+        })
+    }
+
+    fn window(&self) -> Option<u32> {
+        self.clone().find_map(|o| match o {
+            Options::Window(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    fn max_rate(&self) -> Option<u32> {
+        self.clone().find_map(|o| match o {
+            Options::MaxRate(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    fn tenant(&self) -> Option<Id> {
+        self.clone().find_map(|o| match o {
+            Options::Tenant(id) => Some(id.clone()),
+            _ => None,
+        })
+    }
+
+    fn nonce(&self) -> Option<u64> {
+        self.clone().find_map(|o| match o {
+            Options::Nonce(n) => Some(*n),
+            _ => None,
+        })
+    }
+
     fn name(&self) -> Option<OptionString> {
         self.clone().find_map(|o| match o {
             Options::Name(name) => Some(name.clone()),
@@ -181,6 +542,13 @@ impl <'a, T: Iterator<Item=&'a Options> + Clone> Filters for T {
         })
     }
 
+    fn kind(&self) -> Option<OptionString> {
+        self.clone().find_map(|o| match o {
+            Options::Kind(kind) => Some(kind.clone()),
+            _ => None,
+        })
+    }
+
     fn address(&self) -> Option<Address> {
         self.clone().find_map(|o| match o {
             Options::IPv4(addr) => Some((*addr).into()),
@@ -190,6 +558,24 @@ impl <'a, T: Iterator<Item=&'a Options> + Clone> Filters for T {
     }
 }
 
+/// Compare two sets of options independent of ordering,
+/// returning true if each option occurs the same number of times in both sets
+pub fn unordered_eq<A, B>(a: A, b: B) -> bool
+where
+    A: Iterator<Item = Options> + Clone,
+    B: Iterator<Item = Options> + Clone,
+{
+    // Check option counts match
+    if a.clone().count() != b.clone().count() {
+        return false;
+    }
+
+    // Check occurrences of each option match
+    a.clone().all(|o| {
+        a.clone().filter(|v| v == &o).count() == b.clone().filter(|v| v == &o).count()
+    })
+}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature="thiserror", derive(thiserror::Error))]
 pub enum OptionsParseError {
@@ -241,10 +627,80 @@ impl Display for Options {
 
 #[cfg(test)]
 mod test {
+    use super::*;
+
     #[test]
     fn test_options_parsing() {
 
 
     }
 
+    #[test]
+    fn test_unordered_eq() {
+        let a = [Options::name("a"), Options::kind("b"), Options::name("a")];
+        let b = [Options::name("a"), Options::name("a"), Options::kind("b")];
+        let c = [Options::name("a"), Options::kind("b"), Options::kind("b")];
+
+        assert!(unordered_eq(a.iter().cloned(), b.iter().cloned()));
+        assert!(!unordered_eq(a.iter().cloned(), c.iter().cloned()));
+        assert!(!unordered_eq(a.iter().cloned(), a[..2].iter().cloned()));
+    }
+
+    #[test]
+    fn test_normalized_filters() {
+        use encdec::EncodeExt;
+
+        let opts = [Options::name("Front-Door"), Options::kind("Sensor")];
+
+        assert!(opts.iter().matches_name("front-door"));
+        assert!(opts.iter().matches_kind("SENSOR"));
+        assert!(!opts.iter().matches_name("back-door"));
+
+        // Encoded options match via borrowed filters
+        let mut buff = [0u8; 64];
+        let n = Options::encode_iter(opts.iter(), &mut buff).unwrap();
+        let iter = OptionsIter::new(&buff[..n]);
+        assert!(iter.matches_name("FRONT-DOOR"));
+        assert!(iter.matches_kind("sensor"));
+    }
+
+    #[test]
+    fn test_typed_filters() {
+        use encdec::EncodeExt;
+        use crate::types::{AddressV4, AddressV6};
+
+        let v4 = AddressV4::new([192, 168, 1, 10], 10100);
+        let v6 = AddressV6::new([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1], 10100);
+        let coord = Coordinates{ lat: 1.0, lng: 2.0, alt: 3.0 };
+
+        let opts = [
+            Options::address_v4(v4.clone()),
+            Options::meta("owner", "alice"),
+            Options::Coord(coord.clone()),
+            Options::Building("b1".into()),
+            Options::Room("r2".into()),
+            Options::address_v6(v6.clone()),
+            Options::meta("floor", "3"),
+        ];
+
+        let mut buff = [0u8; 256];
+        let n = Options::encode_iter(opts.iter(), &mut buff).unwrap();
+        let encoded = OptionsIter::new(&buff[..n]);
+
+        // Typed accessors match for both iterator kinds
+        fn check(f: impl Filters, v4: &AddressV4, v6: &AddressV6, coord: &Coordinates) {
+            assert_eq!(f.addresses(), vec![Address::from(v4.clone()), Address::from(v6.clone())]);
+            assert_eq!(f.metadata("floor").as_deref(), Some("3"));
+            assert_eq!(f.metadata("missing"), None);
+            assert_eq!(f.coordinates().as_ref(), Some(coord));
+            assert_eq!(f.building(), Some("b1".into()));
+            assert_eq!(f.room(), Some("r2".into()));
+            assert_eq!(f.find_map_kind(OptionKind::Room), Some(Options::Room("r2".into())));
+            assert_eq!(f.find_map_kind(OptionKind::Serial), None);
+        }
+
+        check(opts.iter(), &v4, &v6, &coord);
+        check(encoded, &v4, &v6, &coord);
+    }
+
 }
\ No newline at end of file
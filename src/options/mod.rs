@@ -10,16 +10,30 @@ use heapless::String;
 use encdec::{Encode, Decode, EncodeExt, DecodeExt};
 
 use crate::error::Error;
-use crate::types::{Address, AddressV4, AddressV6, DateTime, ID_LEN, Id, Ip, PUBLIC_KEY_LEN, PublicKey, Queryable, SIGNATURE_LEN, Signature};
+use crate::types::{AlgorithmSuite, Transports, Address, AddressV4, AddressV6, CryptoHash, DateTime, HASH_LEN, ID_LEN, Id, Ip, PUBLIC_KEY_LEN, PublicKey, Queryable, SECRET_KEY_LEN, SECRET_KEY_TAG_LEN, SecretMeta, SIGNATURE_LEN, Signature};
 
 mod helpers;
-pub use helpers::{OptionsIter, OptionsParseError, Filters};
-
-/// Option header length
-const OPTION_HEADER_LEN: usize = 4;
+mod borrowed;
+pub use borrowed::{OptionRef, OptionRefIter};
+mod standard;
+pub use standard::StandardOptions;
+mod normalize;
+pub use normalize::{normalize, normalized_eq};
+mod sealed;
+pub use sealed::{SealedOption, MAX_SEALED_LEN};
+mod validate;
+pub use validate::{OptionValidation, OptionViolation, OptionError};
+mod geo;
+pub use geo::{GeoHash, DEFAULT_GEOHASH_PRECISION, MAX_GEOHASH_PRECISION};
+pub use helpers::{OptionsIter, OptionsParseError, Filters, unordered_eq, OptionLimits, DEFAULT_MAX_OPTIONS, DEFAULT_MAX_OPTIONS_LEN};
+
+use crate::wire::consts::OPTION_HEADER_LEN;
 
 pub const MAX_OPTION_LEN: usize = 64;
 
+/// Maximum length of an opaque continuation token
+pub const MAX_CONTINUATION_LEN: usize = 32;
+
 
 
 /// DSF defined option fields
@@ -47,6 +61,61 @@ pub enum Options {
     Serial(OptionString),
     Building(OptionString),
     Room(OptionString),
+
+    Continuation(ContinuationToken),
+
+    RetryAfter(u32),
+    MaxSize(u32),
+    Diagnostic(OptionString),
+
+    DataKind(u16),
+    MinIndex(u16),
+    ContentType(OptionString),
+
+    Delegate(Id),
+
+    Algorithms(AlgorithmSuite),
+
+    LastSeen(DateTime),
+    PeerSig(Signature),
+
+    Writer(PublicKey),
+    WriterQuorum(u16),
+    CounterSig(CounterSignature),
+
+    Window(u32),
+    MaxRate(u32),
+
+    ReEncrypted(Signature),
+
+    Tenant(Id),
+
+    Padding(u16),
+
+    NsPolicy(NsPolicy),
+
+    Resume(ResumeToken),
+
+    Nonce(u64),
+
+    KeyTransition(KeyTransition),
+
+    Delegation(Delegation),
+
+    AddressRequest(Address),
+    AddressResponse(Address),
+
+    BlockHash(CryptoHash),
+
+    Sealed(SealedOption),
+
+    Transports(Transports),
+    Mtu(u16),
+    MaxMessageSize(u32),
+
+    ApplicationId(u16),
+    PageKind(u16),
+    NamePrefix(OptionString),
 }
 
 
@@ -75,6 +144,40 @@ pub enum OptionKind {
     Coord       = 0x000e,   // Coordinates (lat, lng, alt)
     Manufacturer = 0x000f,  // Manufacturer name (string)
     Serial      = 0x0010,   // Device serial (string)
+    Continuation = 0x0011,  // CONTINUATION option provides an opaque token for fetching further results
+    RetryAfter  = 0x0012,   // RETRY_AFTER option defines the time in seconds before a request should be retried
+    MaxSize     = 0x0013,   // MAX_SIZE option defines the maximum supported object size in bytes
+    Diagnostic  = 0x0014,   // DIAGNOSTIC option provides a short diagnostic message (string)
+    DataKind    = 0x0015,   // DATA_KIND option selects a data object kind (subscription filters)
+    MinIndex    = 0x0016,   // MIN_INDEX option defines a minimum object index (subscription filters)
+    ContentType = 0x0017,   // CONTENT_TYPE option describes object content type (string)
+    Delegate    = 0x0018,   // DELEGATE option defines a peer ID authorised to publish secondary pages for a service
+    Algorithms  = 0x0019,   // ALGORITHMS option advertises the algorithm / feature suite supported by a peer
+    LastSeen    = 0x001a,   // LAST_SEEN option defines the time a peer was last seen (peer exchange)
+    PeerSig     = 0x001b,   // PEER_SIG option carries a peer signature over a peer summary (peer exchange)
+    Writer      = 0x001c,   // WRITER option declares a public key authorised to counter-sign service updates
+    WriterQuorum = 0x001d,  // WRITER_QUORUM option defines the number of writer counter-signatures required for service updates
+    CounterSig  = 0x001e,   // COUNTER_SIG option carries a writer counter-signature over an object
+    Window      = 0x001f,   // WINDOW option defines the maximum number of objects in flight (flow control)
+    MaxRate     = 0x0020,   // MAX_RATE option defines the maximum number of objects per second (flow control)
+    ReEncrypted = 0x0021,   // RE_ENCRYPTED option references the signature of an object re-encrypted under a rotated secret key
+    Tenant      = 0x0022,   // TENANT option defines the tenant (namespace) an object belongs to for multi-tenant deployments
+    Padding     = 0x0023,   // PADDING option pads messages to a fixed size, contents are ignored
+    NsPolicy    = 0x0024,   // NS_POLICY option defines the namespace policy for a name service
+    Resume      = 0x0025,   // RESUME option carries a subscription resumption token (subscription filters)
+    Nonce       = 0x0026,   // NONCE option carries a random challenge to be bound in responses (discovery)
+    KeyTransition = 0x0027, // KEY_TRANSITION option carries a rotated service secret key, sealed with the previous key
+    Delegation  = 0x0028,   // DELEGATION option carries a signed delegation authorising a delegate key to publish for a service
+    AddressRequest = 0x0029, // ADDRESS_REQUEST option carries the local address of a requester (NAT detection)
+    AddressResponse = 0x002a, // ADDRESS_RESPONSE option carries the address of a requester as observed by the responder
+    BlockHash   = 0x002b,   // BLOCK_HASH option carries the hash of a content-addressed block (block objects and links to blocks)
+    Sealed      = 0x002c,   // SEALED option carries an option encrypted under a per-kind subkey (selective disclosure)
+    Transports  = 0x002d,   // TRANSPORTS option advertises the transports supported by a peer
+    Mtu         = 0x002e,   // MTU option defines the maximum transmission unit of a peer transport in bytes
+    MaxMessageSize = 0x002f, // MAX_MESSAGE_SIZE option defines the maximum message size accepted by a peer in bytes
+    ApplicationId = 0x0030, // APPLICATION_ID option selects an application ID (discovery filters)
+    PageKind    = 0x0031,   // PAGE_KIND option selects a page kind (discovery filters)
+    NamePrefix  = 0x0032,   // NAME_PREFIX option matches service names by prefix (discovery filters)
 }
 
 impl From<&Options> for OptionKind {
@@ -98,6 +201,40 @@ impl From<&Options> for OptionKind {
             Options::Room(_) => OptionKind::Room,
             Options::Manufacturer(_) => OptionKind::Manufacturer,
             Options::Serial(_) => OptionKind::Serial,
+            Options::Continuation(_) => OptionKind::Continuation,
+            Options::RetryAfter(_) => OptionKind::RetryAfter,
+            Options::MaxSize(_) => OptionKind::MaxSize,
+            Options::Diagnostic(_) => OptionKind::Diagnostic,
+            Options::DataKind(_) => OptionKind::DataKind,
+            Options::MinIndex(_) => OptionKind::MinIndex,
+            Options::ContentType(_) => OptionKind::ContentType,
+            Options::Delegate(_) => OptionKind::Delegate,
+            Options::Algorithms(_) => OptionKind::Algorithms,
+            Options::LastSeen(_) => OptionKind::LastSeen,
+            Options::PeerSig(_) => OptionKind::PeerSig,
+            Options::Writer(_) => OptionKind::Writer,
+            Options::WriterQuorum(_) => OptionKind::WriterQuorum,
+            Options::CounterSig(_) => OptionKind::CounterSig,
+            Options::Window(_) => OptionKind::Window,
+            Options::MaxRate(_) => OptionKind::MaxRate,
+            Options::ReEncrypted(_) => OptionKind::ReEncrypted,
+            Options::Tenant(_) => OptionKind::Tenant,
+            Options::Padding(_) => OptionKind::Padding,
+            Options::NsPolicy(_) => OptionKind::NsPolicy,
+            Options::Resume(_) => OptionKind::Resume,
+            Options::Nonce(_) => OptionKind::Nonce,
+            Options::KeyTransition(_) => OptionKind::KeyTransition,
+            Options::Delegation(_) => OptionKind::Delegation,
+            Options::AddressRequest(_) => OptionKind::AddressRequest,
+            Options::AddressResponse(_) => OptionKind::AddressResponse,
+            Options::BlockHash(_) => OptionKind::BlockHash,
+            Options::Sealed(_) => OptionKind::Sealed,
+            Options::Transports(_) => OptionKind::Transports,
+            Options::Mtu(_) => OptionKind::Mtu,
+            Options::MaxMessageSize(_) => OptionKind::MaxMessageSize,
+            Options::ApplicationId(_) => OptionKind::ApplicationId,
+            Options::PageKind(_) => OptionKind::PageKind,
+            Options::NamePrefix(_) => OptionKind::NamePrefix,
         }
     }
 }
@@ -149,6 +286,14 @@ impl Options {
         }
     }
 
+    /// Create address options for a list of candidate addresses.
+    ///
+    /// Addresses are listed in order of preference, with the first address
+    /// (as returned by [`Filters::address`]) being the preferred address.
+    pub fn addresses(addresses: &[Address]) -> impl Iterator<Item=Options> + '_ {
+        addresses.iter().map(|a| Options::address(*a))
+    }
+
     pub fn address_v4<T: Into<AddressV4>>(address: T) -> Options {
         Options::IPv4(address.into())
     }
@@ -161,7 +306,170 @@ impl Options {
         Options::PubKey(public_key)
     }
 
+    pub fn limit(n: u32) -> Options {
+        Options::Limit(n)
+    }
+
+    pub fn continuation(token: ContinuationToken) -> Options {
+        Options::Continuation(token)
+    }
+
+    pub fn retry_after(seconds: u32) -> Options {
+        Options::RetryAfter(seconds)
+    }
+
+    pub fn max_size(bytes: u32) -> Options {
+        Options::MaxSize(bytes)
+    }
+
+    pub fn diagnostic(value: &str) -> Options {
+        Options::Diagnostic(value.into())
+    }
+
+    pub fn data_kind(kind: u16) -> Options {
+        Options::DataKind(kind)
+    }
+
+    pub fn min_index(index: u16) -> Options {
+        Options::MinIndex(index)
+    }
+
+    pub fn content_type(value: &str) -> Options {
+        Options::ContentType(value.into())
+    }
+
+    pub fn delegate(id: Id) -> Options {
+        Options::Delegate(id)
+    }
+
+    pub fn algorithms(suite: AlgorithmSuite) -> Options {
+        Options::Algorithms(suite)
+    }
+
+    pub fn last_seen(when: impl Into<DateTime>) -> Options {
+        Options::LastSeen(when.into())
+    }
+
+    pub fn peer_sig(sig: Signature) -> Options {
+        Options::PeerSig(sig)
+    }
+
+    pub fn writer(public_key: PublicKey) -> Options {
+        Options::Writer(public_key)
+    }
+
+    pub fn writer_quorum(n: u16) -> Options {
+        Options::WriterQuorum(n)
+    }
+
+    pub fn counter_sig(id: Id, signature: Signature) -> Options {
+        Options::CounterSig(CounterSignature{ id, signature })
+    }
+
+    pub fn window(n: u32) -> Options {
+        Options::Window(n)
+    }
+
+    pub fn max_rate(n: u32) -> Options {
+        Options::MaxRate(n)
+    }
+
+    pub fn re_encrypted(sig: Signature) -> Options {
+        Options::ReEncrypted(sig)
+    }
+
+    pub fn tenant(id: Id) -> Options {
+        Options::Tenant(id)
+    }
+
+    pub fn padding(len: u16) -> Options {
+        Options::Padding(len)
+    }
+
+    pub fn ns_policy(policy: NsPolicy) -> Options {
+        Options::NsPolicy(policy)
+    }
+
+    pub fn resume(token: ResumeToken) -> Options {
+        Options::Resume(token)
+    }
+
+    pub fn nonce(nonce: u64) -> Options {
+        Options::Nonce(nonce)
+    }
+
+    pub fn key_transition(transition: KeyTransition) -> Options {
+        Options::KeyTransition(transition)
+    }
+
+    pub fn delegation(delegation: Delegation) -> Options {
+        Options::Delegation(delegation)
+    }
+
+    pub fn address_request<T: Into<Address>>(local: T) -> Options {
+        Options::AddressRequest(local.into())
+    }
+
+    pub fn address_response<T: Into<Address>>(observed: T) -> Options {
+        Options::AddressResponse(observed.into())
+    }
+
+    pub fn block_hash(hash: CryptoHash) -> Options {
+        Options::BlockHash(hash)
+    }
+
+    pub fn sealed(sealed: SealedOption) -> Options {
+        Options::Sealed(sealed)
+    }
+
+    pub fn transports(transports: Transports) -> Options {
+        Options::Transports(transports)
+    }
+
+    pub fn mtu(bytes: u16) -> Options {
+        Options::Mtu(bytes)
+    }
+
+    pub fn max_message_size(bytes: u32) -> Options {
+        Options::MaxMessageSize(bytes)
+    }
+
+    pub fn application_id(id: u16) -> Options {
+        Options::ApplicationId(id)
+    }
+
+    pub fn page_kind(kind: u16) -> Options {
+        Options::PageKind(kind)
+    }
+
+    pub fn name_prefix(prefix: &str) -> Options {
+        Options::NamePrefix(prefix.into())
+    }
+
+    /// Decode an address from its IP and port, with the IP version selected by length
+    fn parse_address(d: &[u8]) -> Result<Address, Error> {
+        match d.len() {
+            6 => Ok(Address::new(Ip::V4(<[u8; 4]>::try_from(&d[..4]).unwrap()), NetworkEndian::read_u16(&d[4..]))),
+            18 => Ok(Address::new(Ip::V6(<[u8; 16]>::try_from(&d[..16]).unwrap()), NetworkEndian::read_u16(&d[16..]))),
+            _ => Err(Error::InvalidOptionLength),
+        }
+    }
+
+    /// Encode an address as its IP and port
+    fn write_address(a: &Address, d: &mut [u8]) -> usize {
+        let n = match &a.ip {
+            Ip::V4(ip) => { d[..4].copy_from_slice(ip); 4 },
+            Ip::V6(ip) => { d[..16].copy_from_slice(ip); 16 },
+        };
+        NetworkEndian::write_u16(&mut d[n..], a.port);
+        n + 2
+    }
+
     fn parse_string(d: &[u8]) -> Result<String<MAX_OPTION_LEN>, Error> {
+        if d.len() > MAX_OPTION_LEN {
+            return Err(Error::InvalidOptionLength);
+        }
+
         let s = core::str::from_utf8(d).map_err(|_| Error::InvalidOption )?;
         Ok(String::from(s))
     }
@@ -207,7 +515,7 @@ impl <'a> Decode<'a> for Options {
             OptionKind::Kind => OptionString::decode(d).map(|(v, _)| Options::Kind(v) ),
             OptionKind::Name => OptionString::decode(d).map(|(v, _)| Options::Name(v) ),
 
-            OptionKind::IpAddrV4 => {
+            OptionKind::IpAddrV4 if d.len() >= 6 => {
                 let mut ip = [0u8; 4];
 
                 ip.copy_from_slice(&d[0..4]);
@@ -215,7 +523,7 @@ impl <'a> Decode<'a> for Options {
 
                 Ok(Options::address_v4(AddressV4::new(ip, port)))
             },
-            OptionKind::IpAddrV6 => {
+            OptionKind::IpAddrV6 if d.len() >= 18 => {
                 let mut ip = [0u8; 16];
 
                 ip.copy_from_slice(&d[0..16]);
@@ -223,31 +531,107 @@ impl <'a> Decode<'a> for Options {
         
                 Ok(Options::address_v6(AddressV6::new(ip, port)))
             },
+            OptionKind::IpAddrV4 | OptionKind::IpAddrV6 => Err(Error::InvalidOptionLength),
 
             OptionKind::Meta => {
                 let s = core::str::from_utf8(d).map_err(|_| Error::InvalidOption )?;
                 let mut sp = s.split('|');
 
                 match (sp.next(), sp.next()) {
+                    (Some(key), Some(value)) if key.len() > 16 || value.len() > 48 => Err(Error::InvalidOptionLength),
                     (Some(key), Some(value)) => Ok(Options::meta(key, value)),
                     _ => Err(Error::InvalidOption)
                 }
             },
             
-            OptionKind::Issued => Ok(Options::Issued(DateTime::from_secs(NetworkEndian::read_u64(d)))),
-            OptionKind::Expiry => Ok(Options::Expiry(DateTime::from_secs(NetworkEndian::read_u64(d)))),
-            OptionKind::Limit => Ok(Options::Limit(NetworkEndian::read_u32(d))),
+            OptionKind::Issued if d.len() >= 8 => Ok(Options::Issued(DateTime::from_secs(NetworkEndian::read_u64(d)))),
+            OptionKind::Expiry if d.len() >= 8 => Ok(Options::Expiry(DateTime::from_secs(NetworkEndian::read_u64(d)))),
+            OptionKind::Limit if d.len() >= 4 => Ok(Options::Limit(NetworkEndian::read_u32(d))),
+            OptionKind::Issued | OptionKind::Expiry | OptionKind::Limit => Err(Error::InvalidOptionLength),
 
-            OptionKind::Coord => Ok(Options::Coord(Coordinates{
+            OptionKind::Coord if d.len() >= 12 => Ok(Options::Coord(Coordinates{
                 lat: NetworkEndian::read_f32(&d[0..]),
                 lng: NetworkEndian::read_f32(&d[4..]),
                 alt: NetworkEndian::read_f32(&d[8..]),
             })),
+            OptionKind::Coord => Err(Error::InvalidOptionLength),
 
             OptionKind::Building => OptionString::decode(d).map(|(v, _)| Options::Building(v) ),
             OptionKind::Room => OptionString::decode(d).map(|(v, _)| Options::Room(v) ),
             OptionKind::Manufacturer => OptionString::decode(d).map(|(v, _)| Options::Manufacturer(v) ),
             OptionKind::Serial => OptionString::decode(d).map(|(v, _)| Options::Serial(v) ),
+            OptionKind::Continuation => ContinuationToken::try_from(d).map(|v| Options::Continuation(v) ),
+            OptionKind::RetryAfter if d.len() >= 4 => Ok(Options::RetryAfter(NetworkEndian::read_u32(d))),
+            OptionKind::MaxSize if d.len() >= 4 => Ok(Options::MaxSize(NetworkEndian::read_u32(d))),
+            OptionKind::RetryAfter | OptionKind::MaxSize => Err(Error::InvalidOptionLength),
+            OptionKind::Diagnostic => OptionString::decode(d).map(|(v, _)| Options::Diagnostic(v) ),
+            OptionKind::DataKind if d.len() >= 2 => Ok(Options::DataKind(NetworkEndian::read_u16(d))),
+            OptionKind::MinIndex if d.len() >= 2 => Ok(Options::MinIndex(NetworkEndian::read_u16(d))),
+            OptionKind::DataKind | OptionKind::MinIndex => Err(Error::InvalidOptionLength),
+            OptionKind::ContentType => OptionString::decode(d).map(|(v, _)| Options::ContentType(v) ),
+            OptionKind::Delegate => Id::try_from(d).map(|v| Options::Delegate(v) ),
+            OptionKind::Tenant => Id::try_from(d).map(|v| Options::Tenant(v) ),
+            OptionKind::Padding => Ok(Options::Padding(d.len() as u16)),
+            OptionKind::NsPolicy if d.len() >= NS_POLICY_LEN => Ok(Options::NsPolicy(NsPolicy{
+                private: d[0] & NS_POLICY_PRIVATE != 0,
+                ttl: NetworkEndian::read_u32(&d[1..]),
+                query_kinds: NetworkEndian::read_u64(&d[5..]),
+            })),
+            OptionKind::NsPolicy => Err(Error::InvalidOptionLength),
+            OptionKind::Resume if d.len() >= RESUME_LEN => Ok(Options::Resume(ResumeToken{
+                index: NetworkEndian::read_u16(d),
+                signature: Signature::try_from(&d[2..][..SIGNATURE_LEN])?,
+                mac: match d.len() >= RESUME_LEN + HASH_LEN {
+                    true => Some(CryptoHash::try_from(&d[RESUME_LEN..][..HASH_LEN])?),
+                    false => None,
+                },
+            })),
+            OptionKind::Resume => Err(Error::InvalidOptionLength),
+            OptionKind::Nonce if d.len() >= 8 => Ok(Options::Nonce(NetworkEndian::read_u64(d))),
+            OptionKind::Nonce => Err(Error::InvalidOptionLength),
+            OptionKind::KeyTransition if d.len() >= KEY_TRANSITION_LEN => Ok(Options::KeyTransition(KeyTransition{
+                sealed_key: <[u8; SECRET_KEY_LEN]>::try_from(&d[..SECRET_KEY_LEN]).unwrap(),
+                meta: SecretMeta::try_from(&d[SECRET_KEY_LEN..][..SECRET_KEY_TAG_LEN])?,
+            })),
+            OptionKind::KeyTransition => Err(Error::InvalidOptionLength),
+            OptionKind::Delegation if d.len() >= DELEGATION_LEN => Ok(Options::Delegation(Delegation{
+                issuer: Id::try_from(&d[..ID_LEN])?,
+                delegate: PublicKey::try_from(&d[ID_LEN..][..PUBLIC_KEY_LEN])?,
+                scope: DelegationScope::from_bits_truncate(NetworkEndian::read_u32(&d[ID_LEN + PUBLIC_KEY_LEN..])),
+                expiry: DateTime::from_secs(NetworkEndian::read_u64(&d[ID_LEN + PUBLIC_KEY_LEN + 4..])),
+                signature: Signature::try_from(&d[ID_LEN + PUBLIC_KEY_LEN + 12..][..SIGNATURE_LEN])?,
+            })),
+            OptionKind::Delegation => Err(Error::InvalidOptionLength),
+            OptionKind::AddressRequest => Options::parse_address(d).map(Options::AddressRequest),
+            OptionKind::AddressResponse => Options::parse_address(d).map(Options::AddressResponse),
+            OptionKind::BlockHash => CryptoHash::try_from(d).map(|v| Options::BlockHash(v) ),
+            OptionKind::Sealed => SealedOption::decode(d).map(Options::Sealed),
+            OptionKind::Algorithms if d.len() >= 4 => Ok(Options::Algorithms(AlgorithmSuite::from_bits_truncate(NetworkEndian::read_u32(d)))),
+            OptionKind::Algorithms => Err(Error::InvalidOptionLength),
+            OptionKind::LastSeen if d.len() >= 8 => Ok(Options::LastSeen(DateTime::from_secs(NetworkEndian::read_u64(d)))),
+            OptionKind::LastSeen => Err(Error::InvalidOptionLength),
+            OptionKind::PeerSig => Signature::try_from(d).map(|v| Options::PeerSig(v) ),
+            OptionKind::ReEncrypted => Signature::try_from(d).map(|v| Options::ReEncrypted(v) ),
+            OptionKind::Writer => PublicKey::try_from(d).map(|v| Options::Writer(v) ),
+            OptionKind::WriterQuorum if d.len() >= 2 => Ok(Options::WriterQuorum(NetworkEndian::read_u16(d))),
+            OptionKind::WriterQuorum => Err(Error::InvalidOptionLength),
+            OptionKind::CounterSig if d.len() >= ID_LEN + SIGNATURE_LEN => Ok(Options::CounterSig(CounterSignature{
+                id: Id::try_from(&d[..ID_LEN])?,
+                signature: Signature::try_from(&d[ID_LEN..][..SIGNATURE_LEN])?,
+            })),
+            OptionKind::CounterSig => Err(Error::InvalidOptionLength),
+            OptionKind::Window if d.len() >= 4 => Ok(Options::Window(NetworkEndian::read_u32(d))),
+            OptionKind::MaxRate if d.len() >= 4 => Ok(Options::MaxRate(NetworkEndian::read_u32(d))),
+            OptionKind::Window | OptionKind::MaxRate => Err(Error::InvalidOptionLength),
+            OptionKind::Transports if d.len() >= 2 => Ok(Options::Transports(Transports::from_bits_truncate(NetworkEndian::read_u16(d)))),
+            OptionKind::Mtu if d.len() >= 2 => Ok(Options::Mtu(NetworkEndian::read_u16(d))),
+            OptionKind::Transports | OptionKind::Mtu => Err(Error::InvalidOptionLength),
+            OptionKind::MaxMessageSize if d.len() >= 4 => Ok(Options::MaxMessageSize(NetworkEndian::read_u32(d))),
+            OptionKind::MaxMessageSize => Err(Error::InvalidOptionLength),
+            OptionKind::ApplicationId if d.len() >= 2 => Ok(Options::ApplicationId(NetworkEndian::read_u16(d))),
+            OptionKind::PageKind if d.len() >= 2 => Ok(Options::PageKind(NetworkEndian::read_u16(d))),
+            OptionKind::ApplicationId | OptionKind::PageKind => Err(Error::InvalidOptionLength),
+            OptionKind::NamePrefix => OptionString::decode(d).map(|(v, _)| Options::NamePrefix(v) ),
         };
 
         let o = match r {
@@ -269,49 +653,85 @@ impl Encode for Options {
     fn encode_len(&self) -> Result<usize, Self::Error> {
         let n = match self {
             Options::None => 0,
-            Options::PubKey(_) => PUBLIC_KEY_LEN,
-            Options::PeerId(_) => ID_LEN,
-            Options::PrevSig(_) => SIGNATURE_LEN,
-            Options::Kind(s) | Options::Name(s) | Options::Building(s) | Options::Room(s) | Options::Manufacturer(s) | Options::Serial(s) => {
+            Options::PubKey(_) | Options::Writer(_) => PUBLIC_KEY_LEN,
+            Options::PeerId(_) | Options::Delegate(_) | Options::Tenant(_) => ID_LEN,
+            Options::PrevSig(_) | Options::PeerSig(_) | Options::ReEncrypted(_) => SIGNATURE_LEN,
+            Options::Kind(s) | Options::Name(s) | Options::Building(s) | Options::Room(s) | Options::Manufacturer(s) | Options::Serial(s) | Options::Diagnostic(s) | Options::ContentType(s) | Options::NamePrefix(s) => {
                 s.as_bytes().len()
             },
             Options::IPv4(_) => 6,
             Options::IPv6(_) => 18,
-            Options::Issued(_) | Options::Expiry(_) => 8,
-            Options::Limit(_) => 4,
+            Options::Issued(_) | Options::Expiry(_) | Options::LastSeen(_) | Options::Nonce(_) => 8,
+            Options::Limit(_) | Options::RetryAfter(_) | Options::MaxSize(_) | Options::Algorithms(_) | Options::Window(_) | Options::MaxRate(_) | Options::MaxMessageSize(_) => 4,
+            Options::DataKind(_) | Options::MinIndex(_) | Options::WriterQuorum(_) | Options::Transports(_) | Options::Mtu(_) | Options::ApplicationId(_) | Options::PageKind(_) => 2,
+            Options::CounterSig(_) => ID_LEN + SIGNATURE_LEN,
+            Options::BlockHash(_) => HASH_LEN,
+            Options::Sealed(s) => s.encode_len(),
             Options::Metadata(m) => m.key.len() + m.value.len() + 1,
             Options::Coord(_) => 3 * 4,
+            Options::Continuation(t) => t.len(),
+            Options::Padding(n) => *n as usize,
+            Options::NsPolicy(_) => NS_POLICY_LEN,
+            Options::Resume(t) => RESUME_LEN + t.mac.as_ref().map(|_| HASH_LEN).unwrap_or(0),
+            Options::KeyTransition(_) => KEY_TRANSITION_LEN,
+            Options::Delegation(_) => DELEGATION_LEN,
+            Options::AddressRequest(a) | Options::AddressResponse(a) => match a.ip {
+                Ip::V4(_) => 6,
+                Ip::V6(_) => 18,
+            },
         };
 
         Ok(OPTION_HEADER_LEN + n)
     }
 
     fn encode(&self, data: &mut [u8]) -> Result<usize, Self::Error> {
+        // Check the option will fit in the buffer
+        if data.len() < self.encode_len()? {
+            return Err(Error::BufferLength);
+        }
+
         // Set kind
         let kind = OptionKind::from(self);
         NetworkEndian::write_u16(&mut data[0..], kind as u16);
 
         // Encode data
         let n = match self {
-            Options::PubKey(pub_key) => {
+            Options::PubKey(pub_key) | Options::Writer(pub_key) => {
                 data[OPTION_HEADER_LEN..][..PUBLIC_KEY_LEN].copy_from_slice(pub_key);
                 PUBLIC_KEY_LEN
             },
-            Options::PeerId(peer_id) => {
+            Options::PeerId(peer_id) | Options::Delegate(peer_id) | Options::Tenant(peer_id) => {
                 data[OPTION_HEADER_LEN..][..ID_LEN].copy_from_slice(peer_id);
                 ID_LEN
             },
-            Options::PrevSig(sig) => {
+            Options::PrevSig(sig) | Options::PeerSig(sig) | Options::ReEncrypted(sig) => {
                 data[OPTION_HEADER_LEN..][..SIGNATURE_LEN].copy_from_slice(sig);
                 SIGNATURE_LEN
             },
-            Options::Kind(s) | Options::Name(s) | Options::Building(s) | Options::Room(s) | Options::Manufacturer(s) | Options::Serial(s) => {
+            Options::Kind(s) | Options::Name(s) | Options::Building(s) | Options::Room(s) | Options::Manufacturer(s) | Options::Serial(s) | Options::Diagnostic(s) | Options::ContentType(s) | Options::NamePrefix(s) => {
                 let len = s.as_bytes().len();
                 data[OPTION_HEADER_LEN..][..len].copy_from_slice(s.as_bytes());
                 len
             },
-            Options::Limit(n) => {
-                NetworkEndian::write_u32(&mut data[4..], *n);
+            Options::Limit(n) | Options::RetryAfter(n) | Options::MaxSize(n) | Options::Window(n) | Options::MaxRate(n) | Options::MaxMessageSize(n) => {
+                NetworkEndian::write_u32(&mut data[OPTION_HEADER_LEN..], *n);
+                4
+            },
+            Options::DataKind(n) | Options::MinIndex(n) | Options::WriterQuorum(n) | Options::Mtu(n) | Options::ApplicationId(n) | Options::PageKind(n) => {
+                NetworkEndian::write_u16(&mut data[OPTION_HEADER_LEN..], *n);
+                2
+            },
+            Options::Transports(t) => {
+                NetworkEndian::write_u16(&mut data[OPTION_HEADER_LEN..], t.bits());
+                2
+            },
+            Options::CounterSig(c) => {
+                data[OPTION_HEADER_LEN..][..ID_LEN].copy_from_slice(&c.id);
+                data[OPTION_HEADER_LEN + ID_LEN..][..SIGNATURE_LEN].copy_from_slice(&c.signature);
+                ID_LEN + SIGNATURE_LEN
+            },
+            Options::Algorithms(s) => {
+                NetworkEndian::write_u32(&mut data[OPTION_HEADER_LEN..], s.bits());
                 4
             },
             Options::IPv4(v) => {
@@ -325,8 +745,8 @@ impl Encode for Options {
 
                 18
             },
-            Options::Issued(v) | Options::Expiry(v) => {
-                NetworkEndian::write_u64(&mut data[4..], v.as_secs());
+            Options::Issued(v) | Options::Expiry(v) | Options::LastSeen(v) => {
+                NetworkEndian::write_u64(&mut data[OPTION_HEADER_LEN..], v.as_secs());
                 8
             },
             Options::Metadata(Metadata{key, value}) => {
@@ -352,6 +772,58 @@ impl Encode for Options {
 
                 3 * 4
             },
+            Options::Continuation(t) => {
+                data[OPTION_HEADER_LEN..][..t.len()].copy_from_slice(t);
+                t.len()
+            },
+            Options::Padding(n) => {
+                let n = *n as usize;
+                data[OPTION_HEADER_LEN..][..n].fill(0);
+                n
+            },
+            Options::NsPolicy(p) => {
+                data[OPTION_HEADER_LEN] = if p.private { NS_POLICY_PRIVATE } else { 0 };
+                NetworkEndian::write_u32(&mut data[OPTION_HEADER_LEN + 1..], p.ttl);
+                NetworkEndian::write_u64(&mut data[OPTION_HEADER_LEN + 5..], p.query_kinds);
+                NS_POLICY_LEN
+            },
+            Options::Resume(t) => {
+                NetworkEndian::write_u16(&mut data[OPTION_HEADER_LEN..], t.index);
+                data[OPTION_HEADER_LEN + 2..][..SIGNATURE_LEN].copy_from_slice(&t.signature);
+                match &t.mac {
+                    Some(m) => {
+                        data[OPTION_HEADER_LEN + RESUME_LEN..][..HASH_LEN].copy_from_slice(m);
+                        RESUME_LEN + HASH_LEN
+                    },
+                    None => RESUME_LEN,
+                }
+            },
+            Options::Nonce(v) => {
+                NetworkEndian::write_u64(&mut data[OPTION_HEADER_LEN..], *v);
+                8
+            },
+            Options::KeyTransition(t) => {
+                data[OPTION_HEADER_LEN..][..SECRET_KEY_LEN].copy_from_slice(&t.sealed_key);
+                data[OPTION_HEADER_LEN + SECRET_KEY_LEN..][..SECRET_KEY_TAG_LEN].copy_from_slice(&t.meta);
+                KEY_TRANSITION_LEN
+            },
+            Options::Delegation(v) => {
+                let d = &mut data[OPTION_HEADER_LEN..];
+                d[..ID_LEN].copy_from_slice(&v.issuer);
+                d[ID_LEN..][..PUBLIC_KEY_LEN].copy_from_slice(&v.delegate);
+                NetworkEndian::write_u32(&mut d[ID_LEN + PUBLIC_KEY_LEN..], v.scope.bits());
+                NetworkEndian::write_u64(&mut d[ID_LEN + PUBLIC_KEY_LEN + 4..], v.expiry.as_secs());
+                d[ID_LEN + PUBLIC_KEY_LEN + 12..][..SIGNATURE_LEN].copy_from_slice(&v.signature);
+                DELEGATION_LEN
+            },
+            Options::AddressRequest(a) | Options::AddressResponse(a) => {
+                Options::write_address(a, &mut data[OPTION_HEADER_LEN..])
+            },
+            Options::BlockHash(h) => {
+                data[OPTION_HEADER_LEN..][..HASH_LEN].copy_from_slice(h);
+                HASH_LEN
+            },
+            Options::Sealed(s) => s.encode(&mut data[OPTION_HEADER_LEN..]),
             _ => todo!()
         };
 
@@ -373,12 +845,25 @@ impl Queryable for &Options {
 
         // Then by option data
         match self {
-            // String based options
-            Options::Name(v) | Options::Kind(v) | Options::Manufacturer(v) | Options::Serial(v) | Options::Building(v) | Options::Room(v) => {
+            // Name and kind options are normalised so lookups are case insensitive
+            Options::Name(v) | Options::Kind(v) => {
+                normalize::hash_normalized(v.as_ref(), h);
+                true
+            }
+            // Other string based options
+            Options::Manufacturer(v) | Options::Serial(v) | Options::Building(v) | Options::Room(v) => {
                 h.update(&(v.as_bytes().len() as u16).to_le_bytes());
                 h.update(v.as_bytes());
                 true
             }
+            // Coordinates are bucketed by geohash cell
+            Options::Coord(c) => match c.geohash(DEFAULT_GEOHASH_PRECISION) {
+                Ok(g) => {
+                    g.hash_cell(h);
+                    true
+                }
+                Err(_) => false,
+            },
             _ => false,
         }
 
@@ -395,6 +880,104 @@ pub struct Coordinates {
     pub alt: f32,
 }
 
+/// Writer counter-signature over an object, see [`Container::counter_sign_digest`](crate::wire::Container::counter_sign_digest)
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CounterSignature {
+    /// Writer ID (hash of the writer public key)
+    pub id: Id,
+    /// Writer signature over the object counter-signing digest
+    pub signature: Signature,
+}
+
+/// Encoded name service policy length (flags, TTL and query kinds)
+const NS_POLICY_LEN: usize = 1 + 4 + 8;
+
+const NS_POLICY_PRIVATE: u8 = 1 << 0;
+
+/// Name service policy, see [`NsConfig`](crate::service::NsConfig)
+#[derive(PartialEq, Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NsPolicy {
+    /// Private namespace, tertiary pages are encrypted with the name service secret key
+    pub private: bool,
+    /// Default tertiary page lifetime in seconds
+    pub ttl: u32,
+    /// Option kinds permitted in queries, with bit `n` set for option kind `n`
+    pub query_kinds: u64,
+}
+
+/// Encoded resumption token length (index and signature), excluding the optional MAC
+const RESUME_LEN: usize = 2 + SIGNATURE_LEN;
+
+/// Subscription resumption token, identifying the last data object applied by a subscriber
+/// so a publisher may push only newer objects on re-subscription.
+/// See [`Service::resume_token`](crate::service::Service::resume_token).
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResumeToken {
+    /// Index of the last applied data object
+    pub index: u16,
+    /// Signature of the last applied data object
+    pub signature: Signature,
+    /// Optional MAC over the token, keyed with the service secret key
+    pub mac: Option<CryptoHash>,
+}
+
+/// Encoded key transition length (sealed key and encryption metadata)
+const KEY_TRANSITION_LEN: usize = SECRET_KEY_LEN + SECRET_KEY_TAG_LEN;
+
+/// Secret key transition, carrying a rotated service secret key sealed with the previous secret key
+/// so subscribers holding the previous key may follow the rotation.
+/// See [`Service::rotate_secret_key`](crate::service::Service::rotate_secret_key).
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct KeyTransition {
+    /// New secret key, encrypted with the previous secret key
+    pub sealed_key: [u8; SECRET_KEY_LEN],
+    /// Encryption metadata (tag and nonce) for the sealed key
+    pub meta: SecretMeta,
+}
+
+/// Encoded delegation length (issuer, delegate, scope, expiry and signature)
+const DELEGATION_LEN: usize = ID_LEN + PUBLIC_KEY_LEN + 4 + 8 + SIGNATURE_LEN;
+
+bitflags! {
+    /// Object kinds a delegate is authorised to publish
+    #[derive(Default)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+    pub struct DelegationScope: u32 {
+        /// Primary page updates
+        const PAGES = (1 << 0);
+        /// Data objects
+        const DATA  = (1 << 1);
+    }
+}
+
+/// Signed delegation, authorising a delegate key to publish objects on behalf of a service
+/// within a limited scope and lifetime.
+/// See [`sign_delegation`](crate::crypto::sign_delegation) and [`verify_delegation`](crate::crypto::verify_delegation).
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Delegation {
+    /// Issuing service ID
+    pub issuer: Id,
+    /// Delegate public key
+    pub delegate: PublicKey,
+    /// Object kinds the delegate may publish
+    pub scope: DelegationScope,
+    /// Delegation expiry time
+    pub expiry: DateTime,
+    /// Issuer signature over the delegation
+    pub signature: Signature,
+}
+
 #[derive(PartialEq, Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Metadata {
@@ -410,6 +993,62 @@ impl defmt::Format for Metadata {
     }
 }
 
+/// Opaque continuation token, returned with partial results to allow a subsequent
+/// request to fetch the remainder.
+///
+/// Tokens are opaque to requesters, [`ContinuationToken::from_offset`] and
+/// [`ContinuationToken::offset`] provide a common offset-based encoding for implementations.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ContinuationToken(heapless::Vec<u8, MAX_CONTINUATION_LEN>);
+
+/// Tag for offset-based continuation tokens
+const CONTINUATION_OFFSET_TAG: u8 = 0x01;
+
+impl ContinuationToken {
+    /// Create an offset-based continuation token
+    pub fn from_offset(offset: u32) -> Self {
+        let mut b = [0u8; 5];
+        b[0] = CONTINUATION_OFFSET_TAG;
+        NetworkEndian::write_u32(&mut b[1..], offset);
+        Self(heapless::Vec::from_slice(&b).unwrap())
+    }
+
+    /// Fetch the offset from an offset-based continuation token
+    pub fn offset(&self) -> Option<u32> {
+        match self.0.as_slice() {
+            [CONTINUATION_OFFSET_TAG, o @ ..] if o.len() == 4 => Some(NetworkEndian::read_u32(o)),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<&[u8]> for ContinuationToken {
+    type Error = Error;
+
+    fn try_from(d: &[u8]) -> Result<Self, Self::Error> {
+        heapless::Vec::from_slice(d)
+            .map(Self)
+            .map_err(|_| Error::InvalidOptionLength)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ContinuationToken {
+    fn format(&self, fmt: defmt::Formatter) {
+        let d: &[u8] = &self.0;
+        defmt::write!(fmt, "{=[u8]:x}", d)
+    }
+}
+
+impl core::ops::Deref for ContinuationToken {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct OptionString(heapless::String<MAX_OPTION_LEN>);
@@ -418,6 +1057,11 @@ impl OptionString {
     pub fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
     }
+
+    /// Check whether this string matches another following normalisation (see [`normalize`])
+    pub fn matches(&self, other: &str) -> bool {
+        normalized_eq(&self.0, other)
+    }
 }
 
 impl From<&str> for OptionString {
@@ -463,6 +1107,10 @@ impl <'a> Decode<'a> for OptionString {
     type Error = Error;
 
     fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.len() > MAX_OPTION_LEN {
+            return Err(Error::InvalidOptionLength);
+        }
+
         let s = core::str::from_utf8(buff)
             .map_err(|_| Error::InvalidOption )?;
         Ok((Self(s.into()), s.as_bytes().len()))
@@ -509,6 +1157,48 @@ mod tests {
             Options::issued(SystemTime::now()),
             Options::expiry(SystemTime::now()),
             Options::Limit(13),
+            Options::continuation(ContinuationToken::from_offset(20)),
+            Options::continuation(ContinuationToken::try_from(&[0xaa; MAX_CONTINUATION_LEN][..]).unwrap()),
+            Options::retry_after(30),
+            Options::max_size(1024),
+            Options::diagnostic("rate limited"),
+            Options::data_kind(0x0102),
+            Options::min_index(12),
+            Options::content_type("application/cbor"),
+            Options::delegate(Id::from([0xbb; ID_LEN])),
+            Options::algorithms(AlgorithmSuite::local()),
+            Options::last_seen(DateTime::from_secs(1_000)),
+            Options::peer_sig(Signature::from([0xcc; SIGNATURE_LEN])),
+            Options::writer([3u8; PUBLIC_KEY_LEN].into()),
+            Options::writer_quorum(2),
+            Options::counter_sig(Id::from([0xdd; ID_LEN]), Signature::from([0xee; SIGNATURE_LEN])),
+            Options::window(16),
+            Options::max_rate(100),
+            Options::re_encrypted(Signature::from([0xab; SIGNATURE_LEN])),
+            Options::tenant(Id::from([0xcd; ID_LEN])),
+            Options::padding(12),
+            Options::ns_policy(NsPolicy{ private: true, ttl: 3600, query_kinds: 1 << OptionKind::Name as u16 }),
+            Options::resume(ResumeToken{ index: 3, signature: Signature::from([0xef; SIGNATURE_LEN]), mac: None }),
+            Options::resume(ResumeToken{ index: 4, signature: Signature::from([0xef; SIGNATURE_LEN]), mac: Some(CryptoHash::from([0x12; HASH_LEN])) }),
+            Options::nonce(0x0102_0304_0506_0708),
+            Options::block_hash(CryptoHash::from([0x34; HASH_LEN])),
+            Options::transports(Transports::UDP | Transports::LORA),
+            Options::mtu(222),
+            Options::max_message_size(4096),
+            Options::application_id(0x0a0b),
+            Options::page_kind(0x0003),
+            Options::name_prefix("sensor-"),
+            Options::sealed(SealedOption{ kind: OptionKind::Coord, meta: SecretMeta::from([0x11; SECRET_KEY_TAG_LEN]), data: heapless::Vec::from_slice(&[0x22; 16]).unwrap() }),
+            Options::key_transition(KeyTransition{ sealed_key: [0x34; SECRET_KEY_LEN], meta: SecretMeta::from([0x56; SECRET_KEY_TAG_LEN]) }),
+            Options::delegation(Delegation{
+                issuer: Id::from([0x78; ID_LEN]),
+                delegate: [4u8; PUBLIC_KEY_LEN].into(),
+                scope: DelegationScope::DATA,
+                expiry: DateTime::from_secs(2_000),
+                signature: Signature::from([0x9a; SIGNATURE_LEN]),
+            }),
+            Options::address_request(AddressV4::from(SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 4), 10100))),
+            Options::address_response(Address::new(Ip::V6([0x20; 16]), 10100)),
         ];
 
         for o in tests.iter() {
@@ -530,6 +1220,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_truncated_options() {
+        // Every option kind (and some unknown kinds) with every payload length up to the largest
+        // fixed-length option must decode or fail without panicking
+        for kind in 0..=(OptionKind::NamePrefix as u16 + 2) {
+            for fill in [0x00, 0x41, 0xff] {
+                for len in 0..=(DELEGATION_LEN + 8) {
+                    let mut data = vec![fill; OPTION_HEADER_LEN + len];
+                    NetworkEndian::write_u16(&mut data[0..2], kind);
+                    NetworkEndian::write_u16(&mut data[2..4], len as u16);
+
+                    if let Ok((_o, n)) = Options::decode(&data) {
+                        assert_eq!(n, OPTION_HEADER_LEN + len);
+                    }
+
+                    // Option lengths exceeding the buffer are always rejected
+                    assert_eq!(Options::decode(&data[..data.len() - 1]).err(), Some(Error::InvalidOptionLength));
+                }
+            }
+        }
+
+        // Truncated fixed-length options are rejected
+        for kind in [OptionKind::IpAddrV4, OptionKind::IpAddrV6, OptionKind::Issued, OptionKind::Expiry, OptionKind::Limit, OptionKind::Coord] {
+            let data = [(kind as u16 >> 8) as u8, kind as u16 as u8, 0, 0];
+            assert_eq!(Options::decode(&data).err(), Some(Error::InvalidOptionLength), "kind: {}", kind);
+        }
+    }
+
+    #[test]
+    fn continuation_offsets() {
+        let t = ContinuationToken::from_offset(0x0102_0304);
+        assert_eq!(t.offset(), Some(0x0102_0304));
+
+        let t = ContinuationToken::try_from(&b"opaque"[..]).unwrap();
+        assert_eq!(t.offset(), None);
+
+        assert!(ContinuationToken::try_from(&[0u8; MAX_CONTINUATION_LEN + 1][..]).is_err());
+    }
+
     #[test]
     fn encode_decode_option_list() {
         #[cfg(feature="simplelog")]
@@ -552,6 +1281,8 @@ mod tests {
             Options::meta("test-key", "test-value"),
             Options::issued(SystemTime::now()),
             Options::expiry(SystemTime::now()),
+            Options::limit(10),
+            Options::continuation(ContinuationToken::from_offset(10)),
         ];
 
         let mut data = vec![0u8; 1024];
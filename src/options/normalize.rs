@@ -0,0 +1,96 @@
+//! Normalisation for name and kind options, applied when hashing queries and matching options
+//! so publishers and resolvers agree on lookups regardless of case or Unicode composition.
+//!
+//! For matching, with the `unicode` feature strings are normalised to NFC and lowercased (with a
+//! final NFC pass, as lowercasing may decompose characters). Without the feature (for example, on
+//! constrained `no_std` targets) only ASCII characters are lowercased.
+//!
+//! Hashing always uses ASCII-only folding (non-ASCII characters are passed through unchanged) so
+//! query hashes are identical in every build, non-ASCII names should be published in NFC lowercase
+//! to be found by hashed lookups.
+
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+
+use crate::types::CryptoHasher;
+
+/// Normalise a string for matching and hashing
+#[cfg(feature = "unicode")]
+pub fn normalize(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.nfc().flat_map(char::to_lowercase).nfc()
+}
+
+/// Normalise a string for matching and hashing (ASCII only)
+#[cfg(not(feature = "unicode"))]
+pub fn normalize(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().map(|c| c.to_ascii_lowercase())
+}
+
+/// Fold a string for hashing, ASCII only and independent of enabled features
+fn fold(s: &str) -> impl Iterator<Item = char> + '_ {
+    s.chars().map(|c| c.to_ascii_lowercase())
+}
+
+/// Compare strings following normalisation
+pub fn normalized_eq(a: &str, b: &str) -> bool {
+    normalize(a).eq(normalize(b))
+}
+
+/// Write a length-prefixed (little-endian u16) folded string to the provided hasher
+pub(crate) fn hash_normalized<H: CryptoHasher>(s: &str, h: &mut H) {
+    let len: usize = fold(s).map(char::len_utf8).sum();
+    h.update(&(len as u16).to_le_bytes());
+
+    let mut buff = [0u8; 4];
+    for c in fold(s) {
+        h.update(c.encode_utf8(&mut buff).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_case() {
+        assert!(normalized_eq("Living-Room", "living-room"));
+        assert!(normalized_eq("SENSOR_01", "sensor_01"));
+        assert!(!normalized_eq("sensor_01", "sensor_02"));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalize_unicode() {
+        // Composed and decomposed forms match
+        assert!(normalized_eq("Caf\u{e9}", "cafe\u{301}"));
+        // Non-ASCII characters are lowercased
+        assert!(normalized_eq("\u{c9}COLE", "\u{e9}cole"));
+        assert!(normalized_eq("\u{394}elta", "\u{3b4}ELTA"));
+    }
+
+    struct Collector(Vec<u8>);
+
+    impl CryptoHasher for Collector {
+        fn update(&mut self, buff: &[u8]) {
+            self.0.extend_from_slice(buff);
+        }
+    }
+
+    fn hashed(s: &str) -> Vec<u8> {
+        let mut h = Collector(vec![]);
+        hash_normalized(s, &mut h);
+        h.0
+    }
+
+    #[test]
+    fn hash_folding_is_feature_independent() {
+        // ASCII is folded
+        assert_eq!(hashed("Living-Room"), hashed("living-room"));
+        assert_eq!(hashed("Living-Room"), [&[11, 0][..], b"living-room"].concat());
+
+        // Non-ASCII characters are hashed as-is, with or without the `unicode` feature
+        assert_eq!(hashed("Caf\u{e9}"), [&[5, 0][..], "caf\u{e9}".as_bytes()].concat());
+        assert_ne!(hashed("caf\u{e9}"), hashed("cafe\u{301}"));
+        assert_ne!(hashed("\u{c9}cole"), hashed("\u{e9}cole"));
+    }
+}
@@ -0,0 +1,172 @@
+//! Sealed options support selective disclosure of option values, with each option encrypted
+//! under a subkey derived from the service secret key for the option kind.
+//!
+//! Publishers attach sealed options to objects via [`Builder::sealed_option`](crate::wire::Builder::sealed_option),
+//! and may disclose the subkeys for only some option kinds (for example, location options to one
+//! consumer and telemetry to another) via [`Keys::option_keys`](crate::keys::Keys::option_keys).
+//! Consumers open the sealed options for which they hold keys with
+//! [`Container::open_sealed`](crate::wire::Container::open_sealed).
+//!
+//! The option kind is left in the clear (and bound as associated data) so recipients can select keys.
+
+use core::convert::TryFrom;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use encdec::{Encode, Decode};
+
+use crate::crypto::{Crypto, SecKey as _};
+use crate::error::Error;
+use crate::types::{SecretKey, SecretMeta, SECRET_KEY_TAG_LEN};
+use crate::wire::consts::OPTION_HEADER_LEN;
+
+use super::{Options, OptionKind, MAX_OPTION_LEN};
+
+/// Maximum sealed (encoded inner option) length
+pub const MAX_SEALED_LEN: usize = OPTION_HEADER_LEN + MAX_OPTION_LEN;
+
+/// Fixed sealed option length (kind and encryption metadata)
+pub(crate) const SEALED_HEADER_LEN: usize = 2 + SECRET_KEY_TAG_LEN;
+
+/// Option encrypted under a per-kind subkey
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SealedOption {
+    /// Kind of the sealed option
+    pub kind: OptionKind,
+    /// Encryption metadata (tag and nonce)
+    pub meta: SecretMeta,
+    /// Encrypted inner option
+    pub data: heapless::Vec<u8, MAX_SEALED_LEN>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for SealedOption {
+    fn format(&self, fmt: defmt::Formatter) {
+        let d: &[u8] = &self.data;
+        defmt::write!(fmt, "SealedOption {{ kind: {}, meta: {}, data: {=[u8]:x} }}", self.kind, self.meta, d)
+    }
+}
+
+impl SealedOption {
+    /// Seal an option using the subkey for the option kind
+    pub fn seal(option: &Options, key: &SecretKey) -> Result<Self, Error> {
+        let kind = OptionKind::from(option);
+        if matches!(option, Options::Sealed(_)) {
+            return Err(Error::InvalidOption);
+        }
+
+        let mut buff = [0u8; MAX_SEALED_LEN];
+        let n = match option.encode(&mut buff) {
+            Ok(n) => n,
+            Err(Error::BufferLength) => return Err(Error::InvalidOptionLength),
+            Err(e) => return Err(e),
+        };
+
+        let assoc = (kind as u16).to_be_bytes();
+        let meta = Crypto::sk_encrypt(key, Some(&assoc), &mut buff[..n]).map_err(|_| Error::CryptoError)?;
+
+        Ok(Self {
+            kind,
+            meta,
+            data: heapless::Vec::from_slice(&buff[..n]).map_err(|_| Error::InvalidOptionLength)?,
+        })
+    }
+
+    /// Open a sealed option using the subkey for the option kind
+    pub fn open(&self, key: &SecretKey) -> Result<Options, Error> {
+        let mut buff = self.data.clone();
+
+        let assoc = (self.kind as u16).to_be_bytes();
+        Crypto::sk_decrypt(key, &self.meta, Some(&assoc), &mut buff).map_err(|_| Error::CryptoError)?;
+
+        // Inner options must match the sealed kind
+        let (o, _n) = Options::decode(&buff)?;
+        if OptionKind::from(&o) != self.kind {
+            return Err(Error::InvalidOption);
+        }
+
+        Ok(o)
+    }
+
+    pub(crate) fn encode_len(&self) -> usize {
+        SEALED_HEADER_LEN + self.data.len()
+    }
+
+    pub(crate) fn encode(&self, buff: &mut [u8]) -> usize {
+        NetworkEndian::write_u16(buff, self.kind as u16);
+        buff[2..][..SECRET_KEY_TAG_LEN].copy_from_slice(&self.meta);
+        buff[SEALED_HEADER_LEN..][..self.data.len()].copy_from_slice(&self.data);
+
+        self.encode_len()
+    }
+
+    pub(crate) fn decode(d: &[u8]) -> Result<Self, Error> {
+        if d.len() < SEALED_HEADER_LEN {
+            return Err(Error::InvalidOptionLength);
+        }
+
+        let kind = OptionKind::try_from(NetworkEndian::read_u16(d)).map_err(|_| Error::InvalidOption)?;
+
+        Ok(Self {
+            kind,
+            meta: SecretMeta::try_from(&d[2..][..SECRET_KEY_TAG_LEN])?,
+            data: heapless::Vec::from_slice(&d[SEALED_HEADER_LEN..]).map_err(|_| Error::InvalidOptionLength)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::Header;
+    use crate::keys::Keys;
+    use crate::options::Coordinates;
+    use crate::test_utils;
+    use crate::types::*;
+    use crate::wire::{Builder, Container};
+
+    #[test]
+    fn selective_disclosure() {
+        let keys = test_utils::keys(test_utils::SERVICE_SEED);
+        let id = keys.id().unwrap();
+
+        let location = Options::Coord(Coordinates{ lat: 1.0, lng: 2.0, alt: 3.0 });
+        let telemetry = Options::meta("temp", "21.5");
+
+        let header = Header{ kind: PageKind::Generic.into(), ..Default::default() };
+        let mut b = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .no_body()
+            .private_options(&[]).unwrap()
+            .public();
+        b.sealed_option(&location, &keys).unwrap();
+        b.sealed_option(&telemetry, &keys).unwrap();
+        let c = b.sign_pk(keys.pri_key.as_ref().unwrap()).unwrap();
+
+        let c = Container::parse(c.raw().to_vec(), &Keys::new(keys.pub_key.clone().unwrap())).unwrap();
+
+        // Consumers only open options for disclosed kinds
+        let location_keys = keys.option_keys(&[OptionKind::Coord]).unwrap();
+        assert_eq!(c.open_sealed(&location_keys), Ok(vec![location.clone()]));
+
+        let telemetry_keys = keys.option_keys(&[OptionKind::Meta]).unwrap();
+        assert_eq!(c.open_sealed(&telemetry_keys), Ok(vec![telemetry.clone()]));
+
+        assert_eq!(c.open_sealed(&keys), Ok(vec![location.clone(), telemetry]));
+
+        // Subkeys are distinct per kind, so cannot open other kinds
+        let sealed = SealedOption::seal(&location, location_keys.get(OptionKind::Coord).unwrap()).unwrap();
+        let wrong = keys.option_key(OptionKind::Meta).unwrap();
+        assert_eq!(sealed.open(&wrong), Err(Error::CryptoError));
+
+        // Nor be relabelled as other kinds
+        let mut relabelled = sealed.clone();
+        relabelled.kind = OptionKind::Meta;
+        assert_eq!(relabelled.open(&wrong), Err(Error::CryptoError));
+
+        // Subkeys require the secret key
+        let public = Keys::new(keys.pub_key.clone().unwrap());
+        assert_eq!(public.option_keys(&[OptionKind::Coord]), Err(Error::NoSecretKey));
+    }
+}
@@ -0,0 +1,44 @@
+//! Standard public options attached to published objects, assembled from service state
+//! and applied to objects in a single validated call with [`Builder::standard_options`](crate::wire::Builder::standard_options).
+
+use crate::error::Error;
+use crate::types::{DateTime, Id, PublicKey, Signature};
+
+use super::Options;
+
+/// Standard public options for published objects
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct StandardOptions {
+    /// Publisher public key (primary pages)
+    pub pub_key: Option<PublicKey>,
+    /// Publisher ID (secondary pages)
+    pub peer_id: Option<Id>,
+    /// Signature of the previously published object
+    pub prev_sig: Option<Signature>,
+    /// Object issued time
+    pub issued: Option<DateTime>,
+    /// Object expiry time
+    pub expiry: Option<DateTime>,
+}
+
+impl StandardOptions {
+    /// Validate standard options, checking that expiry (where set) follows issued time
+    pub fn validate(&self) -> Result<(), Error> {
+        match (&self.issued, &self.expiry) {
+            (Some(i), Some(e)) if e.as_secs() <= i.as_secs() => {
+                error!("Object expiry ({}) must follow issued time ({})", e, i);
+                Err(Error::InvalidOption)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Fetch the options to be encoded
+    pub fn options(&self) -> impl Iterator<Item=Options> + '_ {
+        self.pub_key.clone().map(Options::PubKey).into_iter()
+            .chain(self.peer_id.clone().map(Options::PeerId))
+            .chain(self.prev_sig.clone().map(Options::PrevSig))
+            .chain(self.issued.map(Options::Issued))
+            .chain(self.expiry.map(Options::Expiry))
+    }
+}
@@ -0,0 +1,300 @@
+//! Options validation, applied to encoded options sections prior to decoding.
+//!
+//! [`OptionLimits::validate`] walks option headers checking section length, option count and option
+//! lengths (including the minimum length for each known option kind, see [`OptionKind::min_len`]),
+//! and (depending on the configured [`OptionValidation`] strictness) rejects trailing bytes,
+//! duplicates of single-valued option kinds (see [`OptionKind::allows_duplicates`]), and options not
+//! in canonical (ascending kind) order. Failures are returned as an [`OptionError`] identifying the
+//! offending option kind and offset.
+//!
+//! Validation is applied by [`Container::parse`](crate::wire::Container::parse) using the options limits
+//! configured in [`Limits`](crate::wire::Limits).
+
+use core::convert::TryFrom;
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::error::Error;
+use crate::types::{HASH_LEN, ID_LEN, PUBLIC_KEY_LEN, SIGNATURE_LEN};
+
+use super::{OptionKind, OptionLimits, OPTION_HEADER_LEN, NS_POLICY_LEN, RESUME_LEN, KEY_TRANSITION_LEN, DELEGATION_LEN};
+use super::sealed::SEALED_HEADER_LEN;
+
+/// Strictness of options validation
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OptionValidation {
+    /// Check section length, option count, and option lengths only
+    Lenient,
+    /// Additionally reject trailing bytes and duplicates of single-valued option kinds
+    NoDuplicates,
+    /// Additionally require options to be ordered by kind
+    Canonical,
+}
+
+impl Default for OptionValidation {
+    fn default() -> Self {
+        OptionValidation::Lenient
+    }
+}
+
+/// Reason for options validation failure
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OptionViolation {
+    /// Options section exceeds the maximum length
+    SectionTooLong,
+    /// Options section exceeds the maximum option count
+    TooManyOptions,
+    /// Option length exceeds the options section
+    Truncated,
+    /// Option length is shorter than the minimum for the option kind
+    InvalidLength,
+    /// Options section contains trailing bytes shorter than an option header
+    TrailingBytes,
+    /// Single-valued option kind is repeated
+    Duplicate,
+    /// Option is not in canonical (ascending kind) order
+    OutOfOrder,
+}
+
+/// Options validation error, identifying the offending option kind (where available)
+/// and offset within the options section
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OptionError {
+    /// Reason for validation failure
+    pub violation: OptionViolation,
+    /// Raw kind of the offending option
+    pub kind: Option<u16>,
+    /// Offset of the offending option within the options section
+    pub offset: usize,
+}
+
+impl Default for OptionError {
+    fn default() -> Self {
+        Self::new(OptionViolation::Truncated, None, 0)
+    }
+}
+
+impl OptionError {
+    fn new(violation: OptionViolation, kind: Option<u16>, offset: usize) -> Self {
+        Self { violation, kind, offset }
+    }
+
+    /// Fetch the kind of the offending option, where this is a known option kind
+    pub fn option_kind(&self) -> Option<OptionKind> {
+        self.kind.and_then(|k| OptionKind::try_from(k).ok())
+    }
+}
+
+impl From<OptionError> for Error {
+    fn from(e: OptionError) -> Self {
+        match e.violation {
+            OptionViolation::SectionTooLong => Error::OptionsTooLong,
+            OptionViolation::TooManyOptions => Error::TooManyOptions,
+            OptionViolation::Truncated => Error::InvalidOptionLength,
+            OptionViolation::InvalidLength | OptionViolation::TrailingBytes
+                | OptionViolation::Duplicate | OptionViolation::OutOfOrder => Error::InvalidOptions(e),
+        }
+    }
+}
+
+impl OptionKind {
+    /// Check whether an option kind may be repeated within an options section
+    pub fn allows_duplicates(&self) -> bool {
+        matches!(self,
+            OptionKind::IpAddrV4 | OptionKind::IpAddrV6 | OptionKind::Meta | OptionKind::DataKind | OptionKind::Delegate
+            | OptionKind::Writer | OptionKind::CounterSig | OptionKind::BlockHash | OptionKind::Sealed
+        )
+    }
+
+    /// Fetch the minimum encoded value length for an option kind
+    pub fn min_len(&self) -> usize {
+        use OptionKind::*;
+
+        match self {
+            None | Kind | Name | Meta | Building | Room | Manufacturer | Serial | Continuation
+                | Diagnostic | ContentType | Padding | NamePrefix => 0,
+            DataKind | MinIndex | WriterQuorum | Transports | Mtu | ApplicationId | PageKind => 2,
+            Limit | RetryAfter | MaxSize | Algorithms | Window | MaxRate | MaxMessageSize => 4,
+            IpAddrV4 | AddressRequest | AddressResponse => 6,
+            Issued | Expiry | LastSeen | Nonce => 8,
+            Coord => 12,
+            IpAddrV6 => 18,
+            PubKey | Writer => PUBLIC_KEY_LEN,
+            PeerId | Delegate | Tenant => ID_LEN,
+            PrevSig | PeerSig | ReEncrypted => SIGNATURE_LEN,
+            BlockHash => HASH_LEN,
+            CounterSig => ID_LEN + SIGNATURE_LEN,
+            NsPolicy => NS_POLICY_LEN,
+            Resume => RESUME_LEN,
+            KeyTransition => KEY_TRANSITION_LEN,
+            Delegation => DELEGATION_LEN,
+            Sealed => SEALED_HEADER_LEN,
+        }
+    }
+}
+
+/// Number of words in [`KindSet`], covering all known option kinds
+const KIND_SET_WORDS: usize = 4;
+
+// Known option kinds must fit within the kind set
+const _: () = assert!((OptionKind::NamePrefix as usize) < KIND_SET_WORDS * 64);
+
+/// Set of known option kinds seen within an options section
+#[derive(Default)]
+struct KindSet([u64; KIND_SET_WORDS]);
+
+impl KindSet {
+    /// Insert a known option kind, returning false where the kind is already present
+    fn insert(&mut self, kind: OptionKind) -> bool {
+        let (word, bit) = (kind as usize / 64, 1 << (kind as usize % 64));
+        match self.0.get_mut(word) {
+            Some(w) if *w & bit != 0 => false,
+            Some(w) => { *w |= bit; true },
+            None => true,
+        }
+    }
+}
+
+impl OptionLimits {
+    /// Validate an encoded options section against the configured limits and strictness,
+    /// returning the number of options on success.
+    ///
+    /// This walks option headers only and does not decode option values.
+    pub fn validate(&self, buff: &[u8]) -> Result<usize, OptionError> {
+        if buff.len() > self.max_len {
+            return Err(OptionError::new(OptionViolation::SectionTooLong, None, 0));
+        }
+
+        let strict = self.validation != OptionValidation::Lenient;
+        let canonical = self.validation == OptionValidation::Canonical;
+
+        let mut index = 0;
+        let mut count = 0;
+        let mut last: Option<u16> = None;
+        let mut seen = KindSet::default();
+
+        while buff.len() - index >= OPTION_HEADER_LEN {
+            let kind = NetworkEndian::read_u16(&buff[index..]);
+            let len = NetworkEndian::read_u16(&buff[index + 2..]) as usize;
+
+            if index + OPTION_HEADER_LEN + len > buff.len() {
+                return Err(OptionError::new(OptionViolation::Truncated, Some(kind), index));
+            }
+
+            count += 1;
+            if count > self.max_count {
+                return Err(OptionError::new(OptionViolation::TooManyOptions, Some(kind), index));
+            }
+
+            if canonical && last.map(|l| kind < l).unwrap_or(false) {
+                return Err(OptionError::new(OptionViolation::OutOfOrder, Some(kind), index));
+            }
+
+            if let Ok(k) = OptionKind::try_from(kind) {
+                if len < k.min_len() {
+                    return Err(OptionError::new(OptionViolation::InvalidLength, Some(kind), index));
+                }
+
+                if strict && !k.allows_duplicates() && !seen.insert(k) {
+                    return Err(OptionError::new(OptionViolation::Duplicate, Some(kind), index));
+                }
+            }
+
+            last = Some(kind);
+            index += OPTION_HEADER_LEN + len;
+        }
+
+        if strict && index != buff.len() {
+            return Err(OptionError::new(OptionViolation::TrailingBytes, None, index));
+        }
+
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use encdec::Encode;
+
+    use super::*;
+    use crate::options::Options;
+
+    fn encode(opts: &[Options]) -> Vec<u8> {
+        let mut buff = vec![0u8; 256];
+        let mut n = 0;
+        for o in opts {
+            n += o.encode(&mut buff[n..]).unwrap();
+        }
+        buff.truncate(n);
+        buff
+    }
+
+    #[test]
+    fn kind_set_covers_known_kinds() {
+        for k in 0..=u16::MAX {
+            if let Ok(kind) = OptionKind::try_from(k) {
+                assert!((k as usize) < KIND_SET_WORDS * 64, "kind {} outside kind set", kind);
+
+                let mut s = KindSet::default();
+                assert!(s.insert(kind));
+                assert!(!s.insert(kind));
+            }
+        }
+    }
+
+    #[test]
+    fn validate_options() {
+        let lenient = OptionLimits::default();
+        let strict = OptionLimits{ validation: OptionValidation::NoDuplicates, ..Default::default() };
+        let canonical = OptionLimits{ validation: OptionValidation::Canonical, ..Default::default() };
+
+        // Repeatable kinds are accepted at all levels
+        let b = encode(&[Options::name("a"), Options::meta("k", "1"), Options::meta("k", "2")]);
+        assert_eq!(lenient.validate(&b), Ok(3));
+        assert_eq!(strict.validate(&b), Ok(3));
+        assert_eq!(canonical.validate(&b), Ok(3));
+
+        // Duplicate single-valued kinds are rejected with the offending kind and offset
+        let b = encode(&[Options::name("a"), Options::kind("b"), Options::name("c")]);
+        assert_eq!(lenient.validate(&b), Ok(3));
+        let e = strict.validate(&b).unwrap_err();
+        assert_eq!(e, OptionError::new(OptionViolation::Duplicate, Some(OptionKind::Name as u16), 2 * OPTION_HEADER_LEN + 2));
+        assert_eq!(e.option_kind(), Some(OptionKind::Name));
+        assert_eq!(Error::from(e), Error::InvalidOptions(e));
+
+        // As are duplicates of later option kinds
+        let b = encode(&[Options::mtu(100), Options::name_prefix("a"), Options::mtu(200)]);
+        assert_eq!(strict.validate(&b).map_err(|e| e.option_kind()), Err(Some(OptionKind::Mtu)));
+
+        // Canonical validation requires ascending kinds
+        let b = encode(&[Options::name("a"), Options::kind("b")]);
+        assert_eq!(strict.validate(&b), Ok(2));
+        assert_eq!(canonical.validate(&b), Err(OptionError::new(OptionViolation::OutOfOrder, Some(OptionKind::Kind as u16), OPTION_HEADER_LEN + 1)));
+
+        // Trailing bytes are rejected when strict
+        let mut b = encode(&[Options::name("a")]);
+        b.extend_from_slice(&[0xaa, 0xbb]);
+        assert_eq!(lenient.validate(&b), Ok(1));
+        assert_eq!(strict.validate(&b), Err(OptionError::new(OptionViolation::TrailingBytes, None, OPTION_HEADER_LEN + 1)));
+
+        // Lengths exceeding the section are always rejected
+        let mut b = encode(&[Options::name("a"), Options::name("b")]);
+        let n = b.len();
+        b[n - 3] = 0x10;
+        assert_eq!(lenient.validate(&b), Err(OptionError::new(OptionViolation::Truncated, Some(OptionKind::Name as u16), OPTION_HEADER_LEN + 1)));
+        assert_eq!(lenient.check(&b), Err(Error::InvalidOptionLength));
+
+        // Options shorter than the minimum length for their kind are always rejected
+        let mut b = encode(&[Options::name("a"), Options::issued(crate::types::DateTime::from_secs(10))]);
+        b.truncate(b.len() - 8);
+        b[OPTION_HEADER_LEN + 1 + 3] = 0;
+        let e = lenient.validate(&b).unwrap_err();
+        assert_eq!(e, OptionError::new(OptionViolation::InvalidLength, Some(OptionKind::Issued as u16), OPTION_HEADER_LEN + 1));
+        assert_eq!(Error::from(e), Error::InvalidOptions(e));
+    }
+}
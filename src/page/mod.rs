@@ -1,18 +1,24 @@
-use crate::types::{Id, PublicKey, Signature};
+use crate::base::body;
+use crate::error::Error;
+use crate::options::Filters;
+use crate::types::{DateTime, Flags, Id, ImmutableData, PageKind, PublicKey, Signature};
+use crate::wire::Container;
 
 /// Information about a type of page
 #[derive(Debug, PartialEq, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub enum PageInfo {
+pub enum PageInfo<'a> {
     Primary(Primary),
     Secondary(Secondary),
     Data(()),
     ServiceLink(ServiceLink),
     BlockLink(BlockLink),
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    Private(Private<'a>),
 }
 
-impl PageInfo {
+impl <'a> PageInfo<'a> {
     pub fn primary(pub_key: PublicKey) -> Self {
         PageInfo::Primary(Primary { pub_key })
     }
@@ -29,6 +35,10 @@ impl PageInfo {
         PageInfo::BlockLink(BlockLink{ block_sig, peer_id })
     }
 
+    pub fn private(body: &'a [u8]) -> Self {
+        PageInfo::Private(Private{ body })
+    }
+
     pub fn is_primary(&self) -> bool {
         match self {
             PageInfo::Primary(_) => true,
@@ -43,6 +53,13 @@ impl PageInfo {
         }
     }
 
+    pub fn is_private(&self) -> bool {
+        match self {
+            PageInfo::Private(_) => true,
+            _ => false,
+        }
+    }
+
     pub fn pub_key(&self) -> Option<PublicKey> {
         match self {
             PageInfo::Primary(p) => Some(p.pub_key.clone()),
@@ -87,3 +104,92 @@ pub struct BlockLink {
     pub block_sig: Signature,
     pub peer_id: Id,
 }
+
+/// Private (application defined) page, the body is opaque and passed through untouched.
+///
+/// Primary pages for private services carry a public key and are parsed as [`Primary`] pages,
+/// with the body only accessible to holders of the service secret key.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Private<'a> {
+    /// Raw (and possibly encrypted) page body
+    pub body: &'a [u8],
+}
+
+/// Encoded length of [`ReplicaQos`] replica page bodies
+pub const REPLICA_QOS_LEN: usize = 10;
+
+/// Replica quality of service information, encoded as the body of [`PageKind::Replica`] pages
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReplicaQos {
+    /// Replicating peer uptime (seconds)
+    pub uptime: u32,
+    /// Storage capacity offered for the replica (kB)
+    pub capacity: u32,
+    /// Expected request latency (ms)
+    pub latency: u16,
+}
+
+impl ReplicaQos {
+    /// Encode replica QoS information into the provided buffer
+    pub fn encode(&self, buff: &mut [u8]) -> Result<usize, Error> {
+        body::put(buff, &(self.uptime, self.capacity, self.latency))
+    }
+
+    /// Decode replica QoS information from a replica page body
+    pub fn decode(buff: &[u8]) -> Result<Self, Error> {
+        let ((uptime, capacity, latency), _) = body::get::<(u32, u32, u16)>(buff)?;
+        Ok(Self { uptime, capacity, latency })
+    }
+}
+
+/// Replica page, a secondary page linking a service to a replicating peer
+/// with the QoS information for the replication agreement
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Replica {
+    /// Replicated service ID
+    pub service_id: Id,
+    /// Replicating peer ID
+    pub peer_id: Id,
+    /// Replica QoS information
+    pub qos: ReplicaQos,
+    /// Replication agreement expiry (from the page expiry)
+    pub expiry: Option<DateTime>,
+}
+
+impl Replica {
+    /// Parse replica information from a (decrypted) replica page.
+    ///
+    /// Note this does not validate the page publisher, see
+    /// [`Subscriber::validate_replica`](crate::service::Subscriber::validate_replica).
+    pub fn parse<T: ImmutableData>(page: &Container<T>) -> Result<Self, Error> {
+        let header = page.header();
+
+        if !header.flags().contains(Flags::SECONDARY) {
+            return Err(Error::ExpectedSecondaryPage);
+        }
+        if !header.kind().is_page() || header.kind().index() != PageKind::Replica as u16 {
+            return Err(Error::UnexpectedPageKind);
+        }
+        if page.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
+
+        let peer_id = match page.info()?.peer_id() {
+            Some(p) => p,
+            None => return Err(Error::NoPeerId),
+        };
+
+        Ok(Self {
+            service_id: page.id(),
+            peer_id,
+            qos: ReplicaQos::decode(page.try_body_raw()?)?,
+            expiry: page.public_options_iter().expiry(),
+        })
+    }
+}
@@ -3,8 +3,8 @@ pub use crate::service::{Service, ServiceBuilder};
 pub use crate::wire::{Container, Builder as ContainerBuilder};
 
 pub use crate::service::Net as _;
-pub use crate::service::{DataOptions, Publisher as _, SecondaryOptions};
-pub use crate::service::Subscriber as _;
+pub use crate::service::{DataOptions, PrimaryOptions, Publisher as _, SecondaryOptions, TertiaryOptions};
+pub use crate::service::{Registry as _, Subscriber as _};
 
 pub use crate::types::{
     Address, Data, DataKind, Flags, Id, Kind, PageKind, RequestId, MutableData, ImmutableData
@@ -25,4 +25,4 @@ pub use crate::net::{
     Response as NetResponse, ResponseBody as NetResponseBody,
 };
 
-pub use crate::keys::{KeySource, Keys};
+pub use crate::keys::{KeySource, Keys, NullKeySource};
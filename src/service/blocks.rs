@@ -0,0 +1,142 @@
+//! Content-addressed blocks for distributing large data, published as [`DataKind::Block`] data
+//! objects addressed by the hash of their (cleartext) body with [`Service::publish_block`].
+//!
+//! Blocks carry an [`Options::BlockHash`] public option with the block hash, and other objects
+//! link to blocks by including block hash options (for example, a manifest listing the chunks
+//! of a large file, see [`Service::publish_blocks`]). Blocks may also be linked from name services
+//! via block link tertiary pages ([`Registry::publish_block_link`](super::Registry::publish_block_link)).
+//!
+//! Received blocks are checked against the expected hash using [`verify_block`], or against
+//! the attached hash with [`verify_block_object`].
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::base::PageBody;
+use crate::crypto::{Crypto, Hash as _};
+use crate::error::Error;
+use crate::options::{Filters, Options};
+use crate::types::*;
+use crate::wire::Container;
+
+use super::{DataOptions, Publisher, Service};
+
+/// Compute the content address (hash) of a block
+pub fn block_hash(data: &[u8]) -> Result<CryptoHash, Error> {
+    Crypto::hash(data).map_err(|_| Error::CryptoError)
+}
+
+/// Verify block data matches the expected hash
+pub fn verify_block(hash: &CryptoHash, data: &[u8]) -> Result<(), Error> {
+    match &block_hash(data)? == hash {
+        true => Ok(()),
+        false => Err(Error::BlockHashMismatch),
+    }
+}
+
+/// Verify a block object body matches the attached block hash, returning the block hash.
+///
+/// Encrypted blocks must be decrypted prior to verification.
+pub fn verify_block_object<T: ImmutableData>(object: &Container<T>) -> Result<CryptoHash, Error> {
+    if object.header().kind() != Kind::from(DataKind::Block) {
+        return Err(Error::UnexpectedPageKind);
+    }
+    if object.encrypted() {
+        return Err(Error::NotDecrypted);
+    }
+
+    let hash = object.public_options_iter().block_hash().ok_or(Error::InvalidOption)?;
+    verify_block(&hash, object.body_raw())?;
+
+    Ok(hash)
+}
+
+impl <B: PageBody> Service<B> {
+    /// Publish a content-addressed block as a [`DataKind::Block`] data object,
+    /// returning the block hash with the encoded object
+    pub fn publish_block<T: MutableData>(&mut self, data: &[u8], buff: T) -> Result<(CryptoHash, usize, Container<T>), Error> {
+        let hash = block_hash(data)?;
+        let public_options = [Options::block_hash(hash.clone())];
+
+        let opts = DataOptions {
+            data_kind: DataKind::Block as u16,
+            body: Some(data),
+            public_options: &public_options,
+            ..Default::default()
+        };
+
+        let (n, c) = self.publish_data(opts, buff)?;
+        Ok((hash, n, c))
+    }
+
+    /// Split data into blocks of up to `block_len` bytes and publish each block,
+    /// returning the block hashes (in order) with the encoded objects.
+    ///
+    /// Block hashes may be attached to other objects as [`Options::BlockHash`] options to
+    /// link the blocks, for example as a manifest for reassembly.
+    #[cfg(feature = "alloc")]
+    pub fn publish_blocks(&mut self, data: &[u8], block_len: usize) -> Result<Vec<(CryptoHash, Container<Vec<u8>>)>, Error> {
+        if block_len == 0 {
+            return Err(Error::InvalidPageLength);
+        }
+
+        data.chunks(block_len).map(|chunk| {
+            let hash = block_hash(chunk)?;
+            let public_options = [Options::block_hash(hash.clone())];
+
+            let opts = DataOptions {
+                data_kind: DataKind::Block as u16,
+                body: Some(chunk),
+                public_options: &public_options,
+                ..Default::default()
+            };
+
+            let (_n, c) = self.publish_data_alloc(opts)?;
+            Ok((hash, c))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::Subscriber;
+    use crate::test_utils;
+
+    #[test]
+    fn publish_verify_blocks() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let p = test_utils::primary_page(&mut s).to_owned();
+        let mut replica = Service::<Vec<u8>>::load(&p).unwrap();
+
+        // Large data is split into blocks addressed by hash
+        let data: Vec<u8> = (0..2500u32).map(|i| i as u8).collect();
+        let blocks = s.publish_blocks(&data, 1024).unwrap();
+        assert_eq!(blocks.len(), 3);
+
+        for (hash, c) in blocks.iter() {
+            let c = Container::parse(c.raw().to_vec(), &s.keys()).unwrap();
+            assert_eq!(verify_block_object(&c), Ok(hash.clone()));
+            replica.validate_block(&c).unwrap();
+        }
+
+        // Manifests link blocks for reassembly
+        let links: Vec<_> = blocks.iter().map(|(h, _)| Options::block_hash(h.clone())).collect();
+        let opts = DataOptions{ public_options: &links, ..Default::default() };
+        let (_n, m) = s.publish_data_alloc::<&[u8]>(opts).unwrap();
+        let hashes = m.public_options_iter().block_hashes();
+        let joined: Vec<u8> = blocks.iter().zip(hashes.iter()).flat_map(|((h, c), e)| {
+            assert_eq!(h, e);
+            c.body_raw().to_vec()
+        }).collect();
+        assert_eq!(joined, data);
+
+        // Modified blocks and other objects are rejected
+        assert_eq!(verify_block(&blocks[0].0, &data[1..1025]), Err(Error::BlockHashMismatch));
+        assert_eq!(verify_block_object(&m), Err(Error::UnexpectedPageKind));
+
+        let (hash, _n, c) = s.publish_block(&[0xab; 16], vec![0u8; 1024]).unwrap();
+        assert_eq!(verify_block(&hash, &[0xab; 16]), Ok(()));
+        assert_eq!(c.header().kind(), Kind::from(DataKind::Block));
+    }
+}
@@ -2,13 +2,59 @@
 use alloc::vec::{Vec};
 
 use crate::base::{MaybeEncrypted, PageBody};
-use crate::crypto::{Crypto, PubKey as _, SecKey as _, Hash as _};
+use crate::crypto::{Crypto, PubKey as _, SecKey as _};
 use crate::error::Error;
 use crate::options::Options;
 use crate::types::*;
 use crate::keys::Keys;
+use crate::wire::limits::DEFAULT_MAX_BODY_LEN;
 
-use super::Service;
+use super::{Namespace, NsConfig, Service};
+
+/// Errors returned when validating or building a service
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature="thiserror", derive(thiserror::Error))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ServiceBuildError {
+    #[cfg_attr(feature="thiserror", error("Service ID provided without public key (or public key without ID)"))]
+    MissingKeys,
+
+    #[cfg_attr(feature="thiserror", error("Service ID does not match public key"))]
+    IdMismatch,
+
+    #[cfg_attr(feature="thiserror", error("Private key does not match public key"))]
+    KeyMismatch,
+
+    #[cfg_attr(feature="thiserror", error("Key generation failed"))]
+    KeyGeneration,
+
+    #[cfg_attr(feature="thiserror", error("Body length {0} exceeds maximum {1}"))]
+    BodyTooLarge(usize, usize),
+
+    #[cfg_attr(feature="thiserror", error("Body encoding failed"))]
+    InvalidBody,
+
+    #[cfg_attr(feature="thiserror", error("Encrypted service without secret key"))]
+    EncryptedWithoutSecretKey,
+
+    #[cfg_attr(feature="thiserror", error("Private service must be encrypted"))]
+    PrivateNotEncrypted,
+}
+
+impl From<ServiceBuildError> for Error {
+    fn from(e: ServiceBuildError) -> Self {
+        use ServiceBuildError::*;
+
+        match e {
+            MissingKeys => Error::NoPublicKey,
+            IdMismatch | KeyMismatch => Error::KeyIdMismatch,
+            KeyGeneration => Error::CryptoError,
+            BodyTooLarge(..) => Error::ObjectTooLarge,
+            InvalidBody => Error::EncodeFailed,
+            EncryptedWithoutSecretKey | PrivateNotEncrypted => Error::NoSecretKey,
+        }
+    }
+}
 
 /// Service builder to assist in the construction of service instances
 pub struct ServiceBuilder<B: PageBody = Vec<u8>> {
@@ -20,10 +66,13 @@ pub struct ServiceBuilder<B: PageBody = Vec<u8>> {
     last_page: u16,
     last_data: u16,
     body: Option<B>,
+    max_body_len: usize,
 
     private_key: Option<PrivateKey>,
     secret_key: Option<SecretKey>,
     encrypted: bool,
+    /// Secret key generation failed in [`ServiceBuilder::encrypt`], reported on build
+    secret_key_failed: bool,
 
     public_options: Vec<Options>,
     private_options: Vec<Options>,
@@ -44,10 +93,12 @@ impl <B: PageBody> Default for ServiceBuilder<B> {
             last_data: 0,
             kind: PageKind::Generic,
             body: None,
+            max_body_len: DEFAULT_MAX_BODY_LEN,
 
             private_key: None,
             secret_key: None,
             encrypted: false,
+            secret_key_failed: false,
 
             public_options: vec![],
             private_options: vec![],
@@ -60,9 +111,11 @@ impl <B: PageBody> Default for ServiceBuilder<B> {
 impl <B: PageBody> ServiceBuilder<B> {
     /// Setup a peer service.
     /// This is equivalent to .kind(Kind::Peer)
+    /// Peer pages advertise the algorithm suite supported by this build
     pub fn peer() -> Self {
         Self {
             kind: PageKind::Peer,
+            public_options: vec![Options::algorithms(AlgorithmSuite::local())],
             ..Default::default()
         }
     }
@@ -77,12 +130,16 @@ impl <B: PageBody> ServiceBuilder<B> {
     }
 
     /// Setup a private service.
-    /// This is equivalent to .kind(Kind::Private)
+    /// This is equivalent to .kind(Kind::Private).encrypt(), private services
+    /// are only meaningful to holders of the service secret key.
+    ///
+    /// Note this enables encryption, generating a new secret key (use [`ServiceBuilder::secret_key`]
+    /// to provide an existing key), with key generation failures reported on build.
     pub fn private() -> Self {
         Self {
             kind: PageKind::Private,
             ..Default::default()
-        }
+        }.encrypt()
     }
 
     /// Setup a name service with the specified prefix
@@ -98,6 +155,26 @@ impl <B: PageBody> ServiceBuilder<B> {
 
         s
     }
+
+    /// Setup a name service with the provided configuration, encoding the namespace
+    /// policy into the service options. Private namespaces are encrypted.
+    pub fn name_service(config: &NsConfig) -> Result<Self, Error> {
+        config.validate()?;
+
+        let (public_options, private_options) = config.options();
+
+        let s = Self {
+            kind: PageKind::Name,
+            public_options,
+            private_options,
+            ..Default::default()
+        };
+
+        match config.namespace {
+            Namespace::Public => Ok(s),
+            Namespace::Private => Ok(s.encrypt()),
+        }
+    }
 }
 
 /// ServiceBuilder provides helpers for constructing service instances
@@ -129,6 +206,12 @@ impl <B: PageBody> ServiceBuilder<B> {
         self
     }
 
+    /// Set the maximum encoded body length (defaults to [`DEFAULT_MAX_BODY_LEN`])
+    pub fn max_body_len(mut self, max_body_len: usize) -> Self {
+        self.max_body_len = max_body_len;
+        self
+    }
+
     pub fn private_key(mut self, private_key: PrivateKey) -> Self {
         self.private_key = Some(private_key);
         self
@@ -159,10 +242,15 @@ impl <B: PageBody> ServiceBuilder<B> {
     }
 
     /// Enable service encryption
-    /// this is equivalent to .secret_key(crypto::new_sk().unwrap()).encrypted(true);
+    /// this is equivalent to .secret_key(crypto::new_sk()?).encrypted(true),
+    /// with key generation failures reported on build.
     pub fn encrypt(mut self) -> Self {
-        let secret_key = Crypto::new_sk().unwrap();
-        self.secret_key = Some(secret_key);
+        if self.secret_key.is_none() {
+            match Crypto::new_sk() {
+                Ok(sk) => self.secret_key = Some(sk),
+                Err(_) => self.secret_key_failed = true,
+            }
+        }
         self.encrypted = true;
         self
     }
@@ -182,25 +270,68 @@ impl <B: PageBody> ServiceBuilder<B> {
         self
     }
 
-    pub fn build(self) -> Result<Service<B>, Error> {
-        // TODO: perform any validation (no private options without secret key etc.)
+    /// Validate the builder configuration prior to building
+    pub fn validate(&self) -> Result<(), ServiceBuildError> {
+        // Report deferred secret key generation failures
+        if self.secret_key_failed && self.secret_key.is_none() {
+            return Err(ServiceBuildError::KeyGeneration);
+        }
+
+        // Encrypted services require a secret key
+        if self.encrypted && self.secret_key.is_none() {
+            return Err(ServiceBuildError::EncryptedWithoutSecretKey);
+        }
+
+        // Private services must be encrypted
+        if self.kind == PageKind::Private && !self.encrypted {
+            return Err(ServiceBuildError::PrivateNotEncrypted);
+        }
+
+        // Provided IDs and keys must be consistent
+        match (&self.id, &self.public_key, &self.private_key) {
+            (Some(id), Some(public_key), private_key) => {
+                if id != &Id::from_public_key(public_key) {
+                    return Err(ServiceBuildError::IdMismatch);
+                }
+                if let Some(pk) = private_key {
+                    if &Crypto::get_public(pk) != public_key {
+                        return Err(ServiceBuildError::KeyMismatch);
+                    }
+                }
+            },
+            (None, None, _) => (),
+            _ => return Err(ServiceBuildError::MissingKeys),
+        }
+
+        // Bodies must fit within a single page
+        if let Some(b) = &self.body {
+            let n = b.encode_len().map_err(|_| ServiceBuildError::InvalidBody)?;
+            if n > self.max_body_len {
+                return Err(ServiceBuildError::BodyTooLarge(n, self.max_body_len));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build a service, returning a [`ServiceBuildError`] on invalid configurations
+    pub fn try_build(self) -> Result<Service<B>, ServiceBuildError> {
+        self.validate()?;
 
         // Generate new keys if required
         let (id, public_key, private_key) = match (self.id, self.public_key, self.private_key) {
             (Some(id), Some(public_key), private_key) => (id, public_key, private_key),
-            (_, _, Some(private_key)) => {
+            (None, None, Some(private_key)) => {
                 // Regenerate public key and ID from private key
                 let public_key = Crypto::get_public(&private_key);
-                let id = Crypto::hash(&public_key).unwrap();
-                (Id::from(id.as_bytes()), public_key, Some(private_key))
+                (Id::from_public_key(&public_key), public_key, Some(private_key))
             }
             (None, None, None) => {
                 // Generate new keypair
-                let (public_key, private_key) = Crypto::new_pk().unwrap();
-                let id = Crypto::hash(&public_key).unwrap();
-                (Id::from(id.as_bytes()), public_key, Some(private_key))
+                let (public_key, private_key) = Crypto::new_pk().map_err(|_| ServiceBuildError::KeyGeneration)?;
+                (Id::from_public_key(&public_key), public_key, Some(private_key))
             }
-            _ => panic!("Invalid service builder configuration"),
+            _ => return Err(ServiceBuildError::MissingKeys),
         };
 
         let body = match self.body {
@@ -222,7 +353,58 @@ impl <B: PageBody> ServiceBuilder<B> {
             private_key,
             encrypted: self.encrypted,
             secret_key: self.secret_key,
-            last_sig: None,
+            last_sig: self.last_sig,
+            interceptors: Default::default(),
         })
     }
+
+    /// Build a service, see [`ServiceBuilder::try_build`] for typed errors
+    pub fn build(self) -> Result<Service<B>, Error> {
+        self.try_build().map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils;
+
+    #[test]
+    fn validate_builder() {
+        let a = test_utils::keys(test_utils::SERVICE_SEED);
+        let b = test_utils::keys(test_utils::PEER_SEED);
+        let (a_id, a_pub) = (a.id().unwrap(), a.pub_key.clone().unwrap());
+
+        // Consistent keys build
+        let s = ServiceBuilder::<Vec<u8>>::generic().id(a_id.clone(), a_pub.clone()).keys(a.clone()).build().unwrap();
+        assert_eq!(s.id(), a_id);
+
+        // Mismatched IDs and keys are rejected
+        let r = ServiceBuilder::<Vec<u8>>::generic().id(b.id().unwrap(), a_pub.clone()).validate();
+        assert_eq!(r, Err(ServiceBuildError::IdMismatch));
+
+        let r = ServiceBuilder::<Vec<u8>>::generic().id(a_id, a_pub).keys(b).try_build();
+        assert_eq!(r.err(), Some(ServiceBuildError::KeyMismatch));
+
+        // Oversized bodies are rejected
+        let r = ServiceBuilder::generic().body(vec![0u8; 64]).max_body_len(32).build();
+        assert_eq!(r.err(), Some(Error::ObjectTooLarge));
+
+        // Private services must be encrypted, and encrypted services require a secret key
+        let mut p = ServiceBuilder::<Vec<u8>>::private();
+        p.encrypted = false;
+        assert_eq!(p.validate(), Err(ServiceBuildError::PrivateNotEncrypted));
+
+        let mut e = ServiceBuilder::<Vec<u8>>::generic().encrypt();
+        e.secret_key = None;
+        assert_eq!(e.validate(), Err(ServiceBuildError::EncryptedWithoutSecretKey));
+        assert_eq!(e.build().err(), Some(Error::NoSecretKey));
+
+        // Secret key generation failures are reported on build
+        let mut e = ServiceBuilder::<Vec<u8>>::private();
+        e.secret_key = None;
+        e.secret_key_failed = true;
+        assert_eq!(e.validate(), Err(ServiceBuildError::KeyGeneration));
+        assert_eq!(e.build().err(), Some(Error::CryptoError));
+    }
 }
@@ -0,0 +1,280 @@
+//! Chain helpers for detecting divergent (forked) object histories,
+//! for example where a publisher is restored from an old backup and re-publishes existing indices,
+//! and for validating the continuity of object sequences with [`ChainValidator`].
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::crypto::{Crypto, PubKey as _};
+use crate::error::Error;
+use crate::options::Filters;
+use crate::types::*;
+use crate::wire::Container;
+
+/// Evidence of a publisher fork, comprising two objects from the same service
+/// with the same kind, index and previous signature, but differing signatures.
+///
+/// As both objects must be signed by the publisher this is sufficient proof
+/// that the publisher has forked its chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForkEvidence<T: ImmutableData = Vec<u8>> {
+    /// Service ID
+    pub id: Id,
+    /// Object kind
+    pub kind: Kind,
+    /// Object index at which the fork occurred
+    pub index: u16,
+    /// Common previous signature (if linked)
+    pub prev_sig: Option<Signature>,
+    /// First observed object
+    pub a: Container<T>,
+    /// Second (conflicting) observed object
+    pub b: Container<T>,
+}
+
+impl ForkEvidence {
+    /// Check a pair of objects for a fork, returning evidence if one is found.
+    ///
+    /// Note this does not validate object signatures, objects should be parsed
+    /// (and thus verified) prior to checking, or [`ForkEvidence::verify`] used.
+    pub fn check<A: ImmutableData, B: ImmutableData>(a: &Container<A>, b: &Container<B>) -> Option<ForkEvidence> {
+        let (ha, hb) = (a.header(), b.header());
+
+        // Objects must be for the same service, kind, and index
+        if a.id() != b.id() || ha.kind() != hb.kind() || ha.index() != hb.index() {
+            return None;
+        }
+
+        // And follow on from the same previous object
+        let prev_sig = a.public_options_iter().prev_sig();
+        if prev_sig != b.public_options_iter().prev_sig() {
+            return None;
+        }
+
+        // Matching signatures are the same object
+        if a.signature() == b.signature() {
+            return None;
+        }
+
+        Some(ForkEvidence {
+            id: a.id(),
+            kind: ha.kind(),
+            index: ha.index(),
+            prev_sig,
+            a: a.to_owned(),
+            b: b.to_owned(),
+        })
+    }
+}
+
+impl <T: ImmutableData> ForkEvidence<T> {
+    /// Verify fork evidence using the publisher's public key,
+    /// returning true if both objects are validly signed by the publisher
+    pub fn verify(&self, pub_key: &PublicKey) -> Result<bool, Error> {
+        // Check public key matches the service ID
+        if self.id != Id::from_public_key(pub_key) {
+            return Err(Error::KeyIdMismatch);
+        }
+
+        for c in [&self.a, &self.b] {
+            let valid = Crypto::pk_verify(pub_key, &c.signature(), c.signed())
+                .map_err(|_e| Error::CryptoError)?;
+            if !valid {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Location and cause of a break in an object chain
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainBreak {
+    /// Position of the offending object in the validated sequence
+    pub position: usize,
+    /// Header index of the offending object
+    pub index: u16,
+    /// Cause of the break
+    pub error: Error,
+}
+
+/// Validator for sequences of primary pages and data objects published by a single service,
+/// checking objects are validly signed, link to the previous object via `PrevSig` options,
+/// and have monotonically increasing indices.
+///
+/// Data indices reset when a service is updated, so a data object following a primary page
+/// may use any index.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainValidator {
+    id: Id,
+    pub_key: PublicKey,
+    last_sig: Option<Signature>,
+    last_version: Option<u16>,
+    last_index: Option<u16>,
+    position: usize,
+}
+
+impl ChainValidator {
+    /// Create a validator for objects published by the service with the provided public key
+    pub fn new(pub_key: PublicKey) -> Self {
+        Self {
+            id: Id::from_public_key(&pub_key),
+            pub_key,
+            last_sig: None,
+            last_version: None,
+            last_index: None,
+            position: 0,
+        }
+    }
+
+    /// Continue validation from a known object signature,
+    /// requiring the first validated object to link to this
+    pub fn with_last_sig(mut self, sig: Signature) -> Self {
+        self.last_sig = Some(sig);
+        self
+    }
+
+    /// Fetch the signature of the last validated object
+    pub fn last_sig(&self) -> Option<&Signature> {
+        self.last_sig.as_ref()
+    }
+
+    /// Validate the next object in the chain
+    pub fn push<T: ImmutableData>(&mut self, object: &Container<T>) -> Result<(), ChainBreak> {
+        let header = object.header();
+
+        let r = self.check(object);
+        if let Err(error) = r {
+            debug!("Chain break at position {} (index {}): {:?}", self.position, header.index(), error);
+            return Err(ChainBreak{ position: self.position, index: header.index(), error });
+        }
+
+        if header.kind().is_page() {
+            self.last_version = Some(header.index());
+            self.last_index = None;
+        } else {
+            self.last_index = Some(header.index());
+        }
+        self.last_sig = Some(object.signature());
+        self.position += 1;
+
+        Ok(())
+    }
+
+    /// Validate a sequence of objects, returning the number of objects validated
+    /// or the location of the first break in the chain
+    pub fn validate<'a, T: ImmutableData + 'a>(&mut self, objects: impl IntoIterator<Item = &'a Container<T>>) -> Result<usize, ChainBreak> {
+        let start = self.position;
+        for o in objects {
+            self.push(o)?;
+        }
+        Ok(self.position - start)
+    }
+
+    fn check<T: ImmutableData>(&self, object: &Container<T>) -> Result<(), Error> {
+        let header = object.header();
+        let flags = header.flags();
+
+        if object.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        // Secondary and tertiary pages are published by other peers so are not part of the chain
+        if !(header.kind().is_page() || header.kind().is_data()) || flags.intersects(Flags::SECONDARY | Flags::TERTIARY) {
+            return Err(Error::UnexpectedPageKind);
+        }
+
+        let valid = Crypto::pk_verify(&self.pub_key, &object.signature(), object.signed())
+            .map_err(|_e| Error::CryptoError)?;
+        if !valid {
+            return Err(Error::InvalidSignature);
+        }
+
+        // Objects must link to the previous object
+        if self.last_sig.is_some() && object.public_options_iter().prev_sig() != self.last_sig {
+            return Err(Error::ChainBroken);
+        }
+
+        // With increasing indices
+        let last = match header.kind().is_page() {
+            true => self.last_version,
+            false => self.last_index,
+        };
+        if matches!(last, Some(l) if header.index() <= l) {
+            return Err(Error::ChainBroken);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{DataOptions, Publisher, Subscriber, Service};
+    use crate::test_utils;
+
+    #[test]
+    fn validate_object_chain() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let p = test_utils::primary_page(&mut s).to_owned();
+
+        let mut objects = vec![p];
+        for i in 0..3u8 {
+            let body = [i; 4];
+            let opts = DataOptions{ body: Some(&body[..]), ..Default::default() };
+            let (_n, d) = s.publish_data(opts, vec![0u8; 1024]).unwrap();
+            objects.push(d.to_owned());
+        }
+
+        // Complete chains validate
+        let mut v = ChainValidator::new(s.public_key());
+        assert_eq!(v.validate(&objects), Ok(4));
+        assert_eq!(v.last_sig(), Some(&objects[3].signature()));
+
+        // Dropped objects break the chain
+        let dropped = [&objects[0], &objects[1], &objects[3]];
+        let r = ChainValidator::new(s.public_key()).validate(dropped.iter().cloned());
+        assert_eq!(r, Err(ChainBreak{ position: 2, index: 3, error: Error::ChainBroken }));
+
+        // As do forged objects
+        let mut forged = objects[2].raw().to_vec();
+        let n = objects[2].signed().len();
+        forged[n - 1] ^= 0xff;
+        let (forged, _n) = Container::from(forged);
+        let r = ChainValidator::new(s.public_key()).validate([&objects[0], &objects[1], &forged]);
+        assert_eq!(r.map_err(|e| (e.position, e.error)), Err((2, Error::InvalidSignature)));
+
+        // Validation may continue from a known object
+        let mut v = ChainValidator::new(s.public_key()).with_last_sig(objects[1].signature());
+        assert_eq!(v.validate(&objects[2..]), Ok(2));
+
+        let mut v = ChainValidator::new(s.public_key()).with_last_sig(objects[0].signature());
+        assert_eq!(v.push(&objects[2]).map_err(|e| e.error), Err(Error::ChainBroken));
+    }
+
+    #[test]
+    fn detect_primary_fork() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let p1 = test_utils::primary_page(&mut s);
+
+        // Restore publisher from "backup" and re-publish the same index with differing content
+        let mut r = test_utils::service(test_utils::SERVICE_SEED);
+        r.update(|_b, o, _p| o.push(crate::options::Options::name("forked")) ).unwrap();
+        r.version = 0;
+        let p2 = test_utils::primary_page(&mut r);
+
+        // Identical objects are not a fork
+        assert_eq!(ForkEvidence::check(&p1, &p1), None);
+
+        // Differing objects at the same index are
+        let e = ForkEvidence::check(&p1, &p2).expect("fork not detected");
+        assert_eq!(e.index, 1);
+        assert_eq!(e.verify(&s.public_key()), Ok(true));
+
+        // Subscribers reject the conflicting page
+        let mut replica = Service::<Vec<u8>>::load(&p1).unwrap();
+        assert_eq!(replica.apply_primary(&p2), Err(Error::ForkDetected));
+    }
+}
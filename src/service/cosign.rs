@@ -0,0 +1,206 @@
+//! Counter-signing helpers for multi-writer services.
+//!
+//! Services may declare a set of authorised writers ([`Options::Writer`]) and a quorum
+//! ([`Options::WriterQuorum`]) in primary page public options. Data objects published by the
+//! service must then carry at least `quorum` valid counter-signatures ([`Options::CounterSig`])
+//! from declared writers, each over the object [`Container::counter_sign_digest`].
+//!
+//! Publishing is two-phase: the service publishes an object as usual, writers counter-sign
+//! the object with [`Service::counter_sign`], then the service attaches the gathered
+//! counter-signatures with [`Service::attach_counter_signatures`] (re-signing the object).
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use encdec::Encode;
+
+use crate::base::PageBody;
+use crate::crypto::{Crypto, PubKey as _};
+use crate::error::Error;
+use crate::options::{Options, CounterSignature};
+use crate::types::*;
+use crate::wire::{Container, header::WireHeader, HEADER_LEN};
+
+use super::Service;
+
+impl <B: PageBody> Service<B> {
+    /// Counter-sign an object published by a multi-writer service, using this service's private key
+    pub fn counter_sign<T: ImmutableData>(&self, object: &Container<T>) -> Result<CounterSignature, Error> {
+        let private_key = match &self.private_key {
+            Some(k) => k,
+            None => return Err(Error::NoPrivateKey),
+        };
+
+        let digest = object.counter_sign_digest()?;
+        let signature = Crypto::pk_sign(private_key, &digest)
+            .map_err(|_| Error::CryptoError)?;
+
+        Ok(CounterSignature{ id: self.id.clone(), signature })
+    }
+
+    /// Attach counter-signatures to an object published by this service,
+    /// re-signing the object into the provided buffer
+    pub fn attach_counter_signatures<T: ImmutableData, U: MutableData>(
+        &mut self,
+        object: &Container<T>,
+        counter_sigs: &[CounterSignature],
+        mut buff: U,
+    ) -> Result<(usize, Container<U>), Error> {
+        let private_key = match &self.private_key {
+            Some(k) => k,
+            None => return Err(Error::NoPrivateKey),
+        };
+
+        if object.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        // Copy existing object (excluding signature)
+        let n = object.len() - SIGNATURE_LEN;
+        let b = buff.as_mut();
+        if b.len() < n {
+            return Err(Error::BufferLength);
+        }
+        b[..n].copy_from_slice(&object.raw()[..n]);
+
+        // Append counter-signatures to public options
+        let mut i = n;
+        for c in counter_sigs {
+            i += Options::CounterSig(c.clone()).encode(&mut b[i..])?;
+        }
+
+        let public_options_len = object.header().public_options_len() + (i - n);
+        WireHeader::new(&mut b[..HEADER_LEN]).set_public_options_len(public_options_len);
+
+        // Re-sign object
+        if b.len() < i + SIGNATURE_LEN {
+            return Err(Error::BufferLength);
+        }
+        let sig = Crypto::pk_sign(private_key, &b[..i])
+            .map_err(|_| Error::CryptoError)?;
+        b[i..][..SIGNATURE_LEN].copy_from_slice(&sig);
+        i += SIGNATURE_LEN;
+
+        // Update last signature where this replaces the most recent object
+        if self.last_sig.as_ref() == Some(&object.signature()) {
+            self.last_sig = Some(sig);
+        }
+
+        Ok((i, Container {
+            buff,
+            len: i,
+            verified: true,
+            decrypted: false,
+        }))
+    }
+
+    /// Validate counter-signatures attached to an object against declared writers,
+    /// returning the number of distinct writers with valid counter-signatures.
+    ///
+    /// Counter-signatures from undeclared writers are ignored, invalid counter-signatures
+    /// from declared writers return [`Error::InvalidSignature`], and objects with fewer
+    /// than [`Service::writer_quorum`] counter-signatures return [`Error::InsufficientSignatures`].
+    pub fn validate_counter_signatures<T: ImmutableData>(&self, object: &Container<T>) -> Result<usize, Error> {
+        let digest = object.counter_sign_digest()?;
+        let mut signers: Vec<Id> = vec![];
+
+        for c in object.public_options_iter().filter_map(|o| match o {
+            Options::CounterSig(c) => Some(c),
+            _ => None,
+        }) {
+            let key = match self.writers().find(|k| Id::from_public_key(k) == c.id) {
+                Some(k) => k,
+                None => {
+                    debug!("Ignoring counter-signature from undeclared writer {}", c.id);
+                    continue;
+                }
+            };
+
+            match Crypto::pk_verify(&key, &c.signature, &digest) {
+                Ok(true) => (),
+                _ => return Err(Error::InvalidSignature),
+            }
+
+            if !signers.contains(&c.id) {
+                signers.push(c.id);
+            }
+        }
+
+        if signers.len() < self.writer_quorum() as usize {
+            return Err(Error::InsufficientSignatures);
+        }
+
+        Ok(signers.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{Publisher, Subscriber, DataOptions};
+    use crate::test_utils::{self, TestKeySource};
+
+    #[test]
+    fn counter_signed_data() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let (a, b, c) = (test_utils::service(0x03), test_utils::service(0x04), test_utils::service(0x05));
+
+        // Declare writers and quorum
+        assert_eq!(s.set_writer_quorum(1), Err(Error::InsufficientSignatures));
+        s.add_writer(&a.public_key()).unwrap();
+        s.add_writer(&b.public_key()).unwrap();
+        s.set_writer_quorum(2).unwrap();
+
+        let p = test_utils::primary_page(&mut s);
+        let mut replica = Service::<Vec<u8>>::load(&p).unwrap();
+        assert_eq!(replica.writer_quorum(), 2);
+
+        let body = [0xaau8; 16];
+        let (_n, d) = s.publish_data(DataOptions{ body: Some(&body[..]), ..Default::default() }, vec![0u8; 1024]).unwrap();
+
+        // Objects without counter-signatures are rejected
+        assert_eq!(replica.validate_page(&d), Err(Error::InsufficientSignatures));
+
+        // As are objects with insufficient (or undeclared) counter-signatures
+        let (sa, sb, sc) = (a.counter_sign(&d).unwrap(), b.counter_sign(&d).unwrap(), c.counter_sign(&d).unwrap());
+        let (_n, d1) = s.attach_counter_signatures(&d, &[sa.clone(), sa.clone(), sc.clone()], vec![0u8; 1024]).unwrap();
+        assert_eq!(replica.validate_page(&d1), Err(Error::InsufficientSignatures));
+
+        // Objects with a quorum of counter-signatures are accepted, and parse as usual
+        let (_n, d2) = s.attach_counter_signatures(&d, &[sa.clone(), sc, sb.clone()], vec![0u8; 1024]).unwrap();
+        let d2 = Container::parse(d2.raw().to_vec(), &TestKeySource::default()).unwrap();
+        assert_eq!(replica.validate_counter_signatures(&d2), Ok(2));
+        assert_eq!(replica.validate_page(&d2), Ok(()));
+        assert_eq!(d2.counter_sign_digest(), d.counter_sign_digest());
+
+        // Truncated objects return an error rather than panicking
+        let (truncated, _n) = Container::from(&d.raw()[..HEADER_LEN + 1]);
+        assert_eq!(truncated.counter_sign_digest(), Err(Error::BufferLength));
+
+        // Counter-signatures over other objects are rejected
+        let (_n, other) = s.publish_data(DataOptions{ body: Some(&body[..]), ..Default::default() }, vec![0u8; 1024]).unwrap();
+        let (_n, d3) = s.attach_counter_signatures(&other, &[sa, sb], vec![0u8; 1024]).unwrap();
+        assert_eq!(replica.validate_page(&d3), Err(Error::InvalidSignature));
+    }
+
+    #[test]
+    fn remove_writer_quorum() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let (a, b, c) = (test_utils::service(0x03), test_utils::service(0x04), test_utils::service(0x05));
+
+        s.add_writer(&a.public_key()).unwrap();
+        s.add_writer(&b.public_key()).unwrap();
+        s.set_writer_quorum(2).unwrap();
+
+        // Writers may not be removed below the quorum
+        assert_eq!(s.remove_writer(&c.public_key()), Err(Error::NotFound));
+        assert_eq!(s.remove_writer(&a.public_key()), Err(Error::InsufficientSignatures));
+        assert_eq!(s.writers().count(), 2);
+
+        // Until the quorum is lowered
+        s.set_writer_quorum(1).unwrap();
+        assert_eq!(s.remove_writer(&a.public_key()), Ok(()));
+        assert_eq!(s.writers().collect::<Vec<_>>(), vec![b.public_key()]);
+        assert_eq!(s.remove_writer(&b.public_key()), Err(Error::InsufficientSignatures));
+    }
+}
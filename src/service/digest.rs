@@ -0,0 +1,257 @@
+//! Chain digests summarise the data object history of a service, allowing replicas to check
+//! they hold the complete history without exchanging every object.
+//!
+//! A [`ChainDigest`] is a hash over the ordered signatures of data objects up to (and including)
+//! an index, computed incrementally with [`ChainDigest::apply`] as objects are published or applied.
+//! Digests may be published as [`DataKind::Digest`] data objects with [`Service::publish_digest`]
+//! and compared against locally computed digests.
+
+use core::convert::TryFrom;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use encdec::Encode;
+
+use crate::base::{DataBody, PageBody};
+use crate::crypto::{Crypto, Hash as _};
+use crate::error::Error;
+use crate::types::*;
+use crate::wire::Container;
+
+use super::{DataOptions, Publisher, Service};
+
+/// Encoded chain digest length (index and hash)
+pub const CHAIN_DIGEST_LEN: usize = 2 + HASH_LEN;
+
+/// Digest over the signature chain of a service's data objects
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ChainDigest {
+    /// Service ID
+    pub id: Id,
+    /// Index of the last data object included in the digest (0 where empty)
+    pub index: u16,
+    /// Chain hash
+    pub hash: CryptoHash,
+}
+
+impl ChainDigest {
+    /// Create an empty digest for the provided service
+    pub fn new(id: Id) -> Result<Self, Error> {
+        let hash = Crypto::hash(&id).map_err(|_| Error::CryptoError)?;
+        Ok(Self { id, index: 0, hash })
+    }
+
+    /// Compute a digest over the provided data objects, which must be ordered by index from 1
+    pub fn compute<'a, T: ImmutableData + 'a>(id: Id, objects: impl IntoIterator<Item = &'a Container<T>>) -> Result<Self, Error> {
+        let mut d = Self::new(id)?;
+        for o in objects {
+            d.apply(o)?;
+        }
+        Ok(d)
+    }
+
+    /// Apply the next data object to the digest, objects must be applied in index order
+    pub fn apply<T: ImmutableData>(&mut self, object: &Container<T>) -> Result<(), Error> {
+        let header = object.header();
+
+        if object.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+        if !header.kind().is_data() {
+            return Err(Error::ExpectedDataObject);
+        }
+        if header.index() != self.index.wrapping_add(1) {
+            debug!("Digest index mismatch (expected: {} actual: {})", self.index.wrapping_add(1), header.index());
+            return Err(Error::DigestMismatch);
+        }
+
+        let signature = object.signature();
+        self.hash = Crypto::hash_iter([self.hash.as_ref(), signature.as_ref()])
+            .map_err(|_| Error::CryptoError)?;
+        self.index = header.index();
+
+        Ok(())
+    }
+
+    /// Verify the digest against the provided data objects (ordered by index from 1),
+    /// objects following the digest index are ignored
+    pub fn verify<'a, T: ImmutableData + 'a>(&self, objects: impl IntoIterator<Item = &'a Container<T>>) -> Result<(), Error> {
+        let mut d = Self::new(self.id.clone())?;
+        for o in objects.into_iter().take(self.index as usize) {
+            d.apply(o)?;
+        }
+        self.check(&d)
+    }
+
+    /// Check a digest matches another (for example, locally computed) digest
+    pub fn check(&self, other: &ChainDigest) -> Result<(), Error> {
+        match self == other {
+            true => Ok(()),
+            false => Err(Error::DigestMismatch),
+        }
+    }
+
+    /// Load a digest from a published digest data object,
+    /// encrypted objects must be decrypted prior to loading ([`Error::NotDecrypted`])
+    pub fn from_object<T: ImmutableData>(object: &Container<T>) -> Result<Self, Error> {
+        if object.header().kind() != Kind::from(DataKind::Digest) {
+            return Err(Error::UnexpectedPageKind);
+        }
+        if object.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
+
+        let body = object.body_raw();
+        if body.len() != CHAIN_DIGEST_LEN {
+            return Err(Error::InvalidPageLength);
+        }
+
+        Ok(Self {
+            id: object.id(),
+            index: NetworkEndian::read_u16(&body[..2]),
+            hash: CryptoHash::try_from(&body[2..])?,
+        })
+    }
+}
+
+/// Encode digest index and hash as a data object body
+impl Encode for ChainDigest {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(CHAIN_DIGEST_LEN)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < CHAIN_DIGEST_LEN {
+            return Err(Error::BufferLength);
+        }
+
+        NetworkEndian::write_u16(&mut buff[..2], self.index);
+        buff[2..CHAIN_DIGEST_LEN].copy_from_slice(&self.hash);
+
+        Ok(CHAIN_DIGEST_LEN)
+    }
+}
+
+impl DataBody for ChainDigest {}
+
+impl <B: PageBody> Service<B> {
+    /// Publish a digest of this service's data objects as a [`DataKind::Digest`] data object
+    pub fn publish_digest<T: MutableData>(&mut self, digest: &ChainDigest, buff: T) -> Result<(usize, Container<T>), Error> {
+        if digest.id != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        let opts = DataOptions {
+            data_kind: DataKind::Digest as u16,
+            body: Some(digest.clone()),
+            ..Default::default()
+        };
+
+        self.publish_data(opts, buff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::ServiceBuilder;
+
+    fn init_service() -> Service {
+        ServiceBuilder::generic()
+            .build()
+            .expect("Failed to create service")
+    }
+
+    /// Publish data objects with distinct bodies
+    fn publish_objects(s: &mut Service, n: u8) -> Vec<Container> {
+        (0..n).map(|i| {
+            let body = [i; 4];
+            let opts = DataOptions{ body: Some(&body[..]), ..Default::default() };
+            s.publish_data(opts, vec![0u8; 1024]).unwrap().1
+        }).collect()
+    }
+
+    #[test]
+    fn chain_digest_incremental() {
+        let mut s = init_service();
+        let objects = publish_objects(&mut s, 3);
+
+        // Empty digests match across services only where IDs match
+        let empty = ChainDigest::new(s.id()).unwrap();
+        assert_eq!(empty.index, 0);
+        assert_eq!(ChainDigest::compute(s.id(), &objects[..0]), Ok(empty.clone()));
+        assert_ne!(ChainDigest::new(init_service().id()).unwrap().hash, empty.hash);
+
+        // Incremental digests match digests over the full history
+        let mut d = ChainDigest::new(s.id()).unwrap();
+        for o in &objects {
+            d.apply(o).unwrap();
+        }
+        assert_eq!(d.index, 3);
+        assert_eq!(ChainDigest::compute(s.id(), &objects), Ok(d.clone()));
+        assert_eq!(d.verify(&objects), Ok(()));
+
+        // Partial digests verify against longer histories
+        let partial = ChainDigest::compute(s.id(), &objects[..2]).unwrap();
+        assert_eq!(partial.verify(&objects), Ok(()));
+        assert_eq!(partial.check(&d), Err(Error::DigestMismatch));
+
+        // Missing or reordered objects are rejected
+        assert_eq!(ChainDigest::compute(s.id(), [&objects[0], &objects[2]]), Err(Error::DigestMismatch));
+        assert_eq!(ChainDigest::compute(s.id(), [&objects[1], &objects[0]]), Err(Error::DigestMismatch));
+        assert_eq!(d.verify(&objects[1..]), Err(Error::DigestMismatch));
+
+        // Published digests load and compare with local digests
+        let (_n, p) = s.publish_digest(&d, vec![0u8; 1024]).unwrap();
+        assert_eq!(p.header().index(), 4);
+
+        let p = Container::parse(p.raw().to_vec(), &s.keys()).unwrap();
+        assert_eq!(ChainDigest::from_object(&p), Ok(d));
+        assert_eq!(ChainDigest::from_object(&objects[0]), Err(Error::UnexpectedPageKind));
+    }
+
+    #[test]
+    fn chain_digest_invalid_objects() {
+        let mut s = init_service();
+        let objects = publish_objects(&mut s, 1);
+
+        // Objects from other services are rejected
+        let mut other = init_service();
+        let foreign = publish_objects(&mut other, 1);
+        let mut d = ChainDigest::new(s.id()).unwrap();
+        assert_eq!(d.apply(&foreign[0]), Err(Error::UnexpectedServiceId));
+
+        // As are pages
+        let (_n, p) = s.publish_primary(Default::default(), vec![0u8; 1024]).unwrap();
+        assert_eq!(d.apply(&p), Err(Error::ExpectedDataObject));
+
+        // Failed applications leave the digest unchanged
+        assert_eq!(d, ChainDigest::new(s.id()).unwrap());
+        assert_eq!(d.apply(&objects[0]), Ok(()));
+
+        // Services only publish their own digests
+        let digest = ChainDigest::compute(other.id(), &foreign).unwrap();
+        assert_eq!(s.publish_digest(&digest, vec![0u8; 1024]).err(), Some(Error::UnexpectedServiceId));
+
+        // Digest objects with invalid bodies are rejected
+        let opts = DataOptions{ data_kind: DataKind::Digest as u16, body: Some(&[0u8; 4][..]), ..Default::default() };
+        let (_n, c) = s.publish_data(opts, vec![0u8; 1024]).unwrap();
+        assert_eq!(ChainDigest::from_object(&c), Err(Error::InvalidPageLength));
+    }
+
+    #[test]
+    fn chain_digest_encrypted() {
+        let mut s: Service = ServiceBuilder::generic().encrypt().build().unwrap();
+        let objects = publish_objects(&mut s, 2);
+
+        // Encrypted digest objects must be decrypted before loading
+        let d = ChainDigest::compute(s.id(), &objects).unwrap();
+        let (_n, mut c) = s.publish_digest(&d, vec![0u8; 1024]).unwrap();
+        assert_eq!(ChainDigest::from_object(&c), Err(Error::NotDecrypted));
+
+        c.decrypt(&s.secret_key().unwrap()).unwrap();
+        assert_eq!(ChainDigest::from_object(&c), Ok(d));
+    }
+}
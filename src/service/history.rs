@@ -0,0 +1,206 @@
+//! Bounded history of published or applied objects for a service,
+//! retaining recent (index, signature, issued) entries with a pluggable retention policy.
+
+use crate::error::Error;
+use crate::options::Filters;
+use crate::types::*;
+use crate::wire::Container;
+
+/// History entry for a published or applied object
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HistoryEntry {
+    /// Object index
+    pub index: u16,
+    /// Object signature
+    pub signature: Signature,
+    /// Object issued time (if available)
+    pub issued: Option<DateTime>,
+}
+
+impl HistoryEntry {
+    /// Create a history entry from an object container
+    pub fn from_container<T: ImmutableData>(c: &Container<T>) -> Self {
+        Self {
+            index: c.header().index(),
+            signature: c.signature(),
+            issued: c.public_options_iter().issued(),
+        }
+    }
+}
+
+/// Retention policy for history entries, applied on insertion
+pub trait Retention {
+    /// Check whether `entry` should be retained given the `latest` entry
+    fn retain(&self, entry: &HistoryEntry, latest: &HistoryEntry) -> bool;
+}
+
+/// Retain entries up to the history capacity
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct KeepAll;
+
+impl Retention for KeepAll {
+    fn retain(&self, _entry: &HistoryEntry, _latest: &HistoryEntry) -> bool {
+        true
+    }
+}
+
+/// Retain entries issued within the provided number of seconds of the latest entry,
+/// entries without an issued time are retained up to the history capacity
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct MaxAge(pub u64);
+
+impl Retention for MaxAge {
+    fn retain(&self, entry: &HistoryEntry, latest: &HistoryEntry) -> bool {
+        match (entry.issued, latest.issued) {
+            (Some(e), Some(l)) => l.as_secs().saturating_sub(e.as_secs()) <= self.0,
+            _ => true,
+        }
+    }
+}
+
+/// Bounded history of up to `N` entries, ordered by index
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct History<const N: usize, R: Retention = KeepAll> {
+    entries: heapless::Vec<HistoryEntry, N>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    retention: R,
+}
+
+impl <const N: usize, R: Retention + Default> Default for History<N, R> {
+    fn default() -> Self {
+        Self::new(R::default())
+    }
+}
+
+impl <const N: usize, R: Retention> History<N, R> {
+    /// Create a new history with the provided retention policy
+    pub fn new(retention: R) -> Self {
+        Self { entries: heapless::Vec::new(), retention }
+    }
+
+    /// Record a published or applied object
+    pub fn record<T: ImmutableData>(&mut self, c: &Container<T>) -> Result<bool, Error> {
+        self.insert(HistoryEntry::from_container(c))
+    }
+
+    /// Insert an entry, returning true if the history was updated.
+    ///
+    /// Entries matching an existing index with a differing signature
+    /// return [`Error::ForkDetected`], entries older than a full history are ignored.
+    pub fn insert(&mut self, entry: HistoryEntry) -> Result<bool, Error> {
+        if N == 0 {
+            return Ok(false);
+        }
+
+        // Find insertion point, checking for existing entries
+        let i = match self.entries.binary_search_by_key(&entry.index, |e| e.index) {
+            Ok(i) if self.entries[i].signature == entry.signature => return Ok(false),
+            Ok(_) => return Err(Error::ForkDetected),
+            Err(i) => i,
+        };
+
+        // Evict the oldest entry when full
+        if self.entries.is_full() {
+            if i == 0 {
+                return Ok(false);
+            }
+            self.entries.remove(0);
+            let _ = self.entries.insert(i - 1, entry);
+        } else {
+            let _ = self.entries.insert(i, entry);
+        }
+
+        // Apply retention policy
+        if let Some(latest) = self.entries.last().cloned() {
+            let retention = &self.retention;
+            self.entries.retain(|e| retention.retain(e, &latest));
+        }
+
+        Ok(true)
+    }
+
+    /// Fetch the latest entry
+    pub fn latest(&self) -> Option<&HistoryEntry> {
+        self.entries.last()
+    }
+
+    /// Fetch an entry by index
+    pub fn get(&self, index: u16) -> Option<&HistoryEntry> {
+        self.entries.binary_search_by_key(&index, |e| e.index)
+            .ok()
+            .map(|i| &self.entries[i])
+    }
+
+    /// Iterate over entries, oldest first
+    pub fn iter(&self) -> impl Iterator<Item=&HistoryEntry> {
+        self.entries.iter()
+    }
+
+    /// Fetch the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the history is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{DataOptions, Publisher};
+    use crate::test_utils;
+
+    fn entry(index: u16, sig: u8, issued: u64) -> HistoryEntry {
+        HistoryEntry{ index, signature: Signature::from([sig; SIGNATURE_LEN]), issued: Some(DateTime::from_secs(issued)) }
+    }
+
+    #[test]
+    fn history_insert_query() {
+        let mut h = History::<3>::default();
+
+        for i in 1..=4 {
+            assert_eq!(h.insert(entry(i, i as u8, 100 + i as u64)), Ok(true));
+        }
+
+        // Oldest entries are evicted
+        assert_eq!(h.len(), 3);
+        assert_eq!(h.get(1), None);
+        assert_eq!(h.get(2), Some(&entry(2, 2, 102)));
+        assert_eq!(h.latest(), Some(&entry(4, 4, 104)));
+
+        // Duplicates are ignored, conflicts are reported
+        assert_eq!(h.insert(entry(3, 3, 103)), Ok(false));
+        assert_eq!(h.insert(entry(3, 0xff, 103)), Err(Error::ForkDetected));
+
+        // As are entries older than a full history
+        assert_eq!(h.insert(entry(1, 1, 101)), Ok(false));
+
+        // Age based retention
+        let mut h = History::<8, MaxAge>::new(MaxAge(10));
+        h.insert(entry(1, 1, 100)).unwrap();
+        h.insert(entry(2, 2, 105)).unwrap();
+        h.insert(entry(3, 3, 112)).unwrap();
+        assert_eq!(h.iter().map(|e| e.index).collect::<std::vec::Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn history_record_containers() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let _p = test_utils::primary_page(&mut s);
+
+        let mut h = History::<4>::default();
+        for _i in 0..3 {
+            let (_n, d) = s.publish_data(DataOptions::<&[u8]>::default(), vec![0u8; 1024]).unwrap();
+            assert_eq!(h.record(&d), Ok(true));
+            assert_eq!(h.latest().map(|e| e.signature.clone()), Some(d.signature()));
+            assert!(h.latest().unwrap().issued.is_some());
+        }
+        assert_eq!(h.len(), 3);
+    }
+}
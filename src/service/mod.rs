@@ -3,11 +3,13 @@
 //! `Publisher`, `Subscriber`, and `Net` traits provide functionality for publishing services,
 //! subscribing to services, and sending messages respectively.
 
-use crate::base::{MaybeEncrypted, PageBody};
+use crate::base::{Header, MaybeEncrypted, PageBody};
 use crate::crypto::{Crypto, PubKey as _, SecKey as _, Hash as _};
 use crate::error::Error;
+use crate::interceptor::{Interceptor, Interceptors};
 use crate::options::Options;
 use crate::types::*;
+use crate::wire::builder::{Builder, SetPublicOptions};
 
 #[cfg(feature = "alloc")]
 use alloc::vec::{Vec};
@@ -16,19 +18,54 @@ mod kinds;
 pub use kinds::*;
 // Service extensions
 mod publisher;
-pub use publisher::{Publisher, DataOptions, SecondaryOptions};
+pub use publisher::{Publisher, PrimaryOptions, DataOptions, SecondaryOptions, DEFAULT_PAGE_EXPIRY};
 
 mod subscriber;
 pub use subscriber::Subscriber;
 
 mod registry;
-pub use registry::{Registry, TertiaryOptions};
+pub use registry::{Registry, Resolver, TertiaryLink, TertiaryOptions};
+
+mod ns;
+pub use ns::{NsConfig, Namespace, NS_QUERY_KINDS, NS_DEFAULT_TTL};
 
 mod net;
 pub use net::Net;
 
 mod builder;
-pub use builder::ServiceBuilder;
+pub use builder::{ServiceBuilder, ServiceBuildError};
+
+mod chain;
+pub use chain::{ForkEvidence, ChainValidator, ChainBreak};
+
+mod cosign;
+
+mod rekey;
+
+mod rotate;
+
+mod digest;
+pub use digest::{ChainDigest, CHAIN_DIGEST_LEN};
+
+mod status;
+pub use status::{ServiceStatus, SERVICE_STATUS_MAX_LEN};
+
+mod peer;
+pub use peer::{PeerInfo, MAX_PEER_ADDRESSES};
+
+mod blocks;
+pub use blocks::{block_hash, verify_block, verify_block_object};
+
+mod history;
+pub use history::{History, HistoryEntry, Retention, KeepAll, MaxAge};
+
+mod resume;
+
+mod replay;
+pub use replay::ReplayState;
+
+mod state;
+pub use state::SigningState;
 
 use crate::keys::Keys;
 
@@ -60,6 +97,9 @@ pub struct Service<B: PageBody = Vec<u8>> {
     secret_key: Option<SecretKey>,
 
     last_sig: Option<Signature>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    interceptors: Interceptors,
 }
 
 impl <B: PageBody> Default for Service<B> {
@@ -69,11 +109,11 @@ impl <B: PageBody> Default for Service<B> {
         let (public_key, private_key) = Crypto::new_pk().unwrap();
 
         // Generate service ID from public key
-        let id = Crypto::hash(&public_key).unwrap();
+        let id = Id::from_public_key(&public_key);
 
         // Create service object
         Service {
-            id: Id::from(id.as_bytes()),
+            id,
             application_id: 0,
             kind: PageKind::Generic,
             version: 0,
@@ -86,6 +126,7 @@ impl <B: PageBody> Default for Service<B> {
             encrypted: false,
             secret_key: None,
             last_sig: None,
+            interceptors: Default::default(),
         }
     }
 }
@@ -149,6 +190,94 @@ impl <B: PageBody> Service<B> {
         self.encrypted
     }
 
+    /// Fetch peers delegated to publish secondary pages for this service
+    pub fn delegates(&self) -> impl Iterator<Item=Id> + '_ {
+        self.public_options.iter().filter_map(|o| match o {
+            Options::Delegate(id) => Some(id.clone()),
+            _ => None,
+        })
+    }
+
+    /// Check whether a peer may publish secondary pages for this service,
+    /// services without a delegation list accept secondary pages from no other peers
+    pub fn is_delegate(&self, id: &Id) -> bool {
+        self.delegates().any(|d| &d == id)
+    }
+
+    /// Add a delegated publisher, updating the service version
+    pub fn add_delegate(&mut self, id: &Id) -> Result<(), Error> {
+        if self.delegates().any(|d| &d == id) {
+            return Ok(());
+        }
+
+        let o = Options::delegate(id.clone());
+        self.update(|_b, public, _p| public.push(o.clone()) )
+    }
+
+    /// Remove a delegated publisher, updating the service version
+    pub fn remove_delegate(&mut self, id: &Id) -> Result<(), Error> {
+        if !self.delegates().any(|d| &d == id) {
+            return Err(Error::NotFound);
+        }
+
+        self.update(|_b, public, _p| public.retain(|o| o != &Options::Delegate(id.clone())) )
+    }
+
+    /// Fetch public keys of writers authorised to counter-sign updates for this service
+    pub fn writers(&self) -> impl Iterator<Item=PublicKey> + '_ {
+        self.public_options.iter().filter_map(|o| match o {
+            Options::Writer(k) => Some(k.clone()),
+            _ => None,
+        })
+    }
+
+    /// Fetch the number of writer counter-signatures required for data objects,
+    /// services without a writer quorum do not require counter-signatures
+    pub fn writer_quorum(&self) -> u16 {
+        self.public_options.iter().find_map(|o| match o {
+            Options::WriterQuorum(n) => Some(*n),
+            _ => None,
+        }).unwrap_or(0)
+    }
+
+    /// Add an authorised writer, updating the service version
+    pub fn add_writer(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        if self.writers().any(|k| &k == public_key) {
+            return Ok(());
+        }
+
+        let o = Options::writer(public_key.clone());
+        self.update(|_b, public, _p| public.push(o.clone()) )
+    }
+
+    /// Remove an authorised writer, updating the service version.
+    /// Returns [`Error::InsufficientSignatures`] where this would leave fewer writers than the writer quorum.
+    pub fn remove_writer(&mut self, public_key: &PublicKey) -> Result<(), Error> {
+        if !self.writers().any(|k| &k == public_key) {
+            return Err(Error::NotFound);
+        }
+
+        if self.writers().count() <= self.writer_quorum() as usize {
+            return Err(Error::InsufficientSignatures);
+        }
+
+        self.update(|_b, public, _p| public.retain(|o| o != &Options::Writer(public_key.clone())) )
+    }
+
+    /// Set the number of writer counter-signatures required for data objects, updating the service version
+    pub fn set_writer_quorum(&mut self, n: u16) -> Result<(), Error> {
+        if n as usize > self.writers().count() {
+            return Err(Error::InsufficientSignatures);
+        }
+
+        self.update(|_b, public, _p| {
+            public.retain(|o| !matches!(o, Options::WriterQuorum(_)));
+            if n > 0 {
+                public.push(Options::writer_quorum(n));
+            }
+        })
+    }
+
     pub fn public_key(&self) -> PublicKey {
         self.public_key.clone()
     }
@@ -177,6 +306,34 @@ impl <B: PageBody> Service<B> {
             sym_keys: None,
         }
     }
+
+    /// Register an interceptor, called on object encoding in order of registration
+    pub fn add_interceptor<I: Interceptor + 'static>(&mut self, interceptor: I) {
+        self.interceptors.push(interceptor);
+    }
+
+    /// Fetch registered interceptors, for use when parsing messages
+    pub fn interceptors(&self) -> &Interceptors {
+        &self.interceptors
+    }
+
+    /// Apply pre-sign interceptors, attaching any generated public options to the builder
+    pub(crate) fn intercept<T: MutableData>(&self, b: &mut Builder<SetPublicOptions, T>) -> Result<(), Error> {
+        if self.interceptors.is_empty() {
+            return Ok(());
+        }
+
+        let header = Header::from(&b.header_ref());
+
+        let mut options = vec![];
+        self.interceptors.pre_sign(&self.id, &header, &mut options)?;
+
+        for o in &options {
+            b.public_option(o)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -193,6 +350,14 @@ mod test {
 
     use super::*;
 
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn service_is_send_sync() {
+        assert_send_sync::<Service>();
+        assert_send_sync::<Interceptors>();
+    }
+
     #[test]
     fn test_service() {
         //let _ = simplelog::SimpleLogger::init(simplelog::LevelFilter::Debug, simplelog::Config::default());
@@ -281,4 +446,103 @@ mod test {
             .validate_data(&b)
             .expect("Error validating data against replica");
     }
+
+    #[test]
+    fn test_delegated_publishers() {
+        use crate::test_utils::{self, TestKeySource};
+
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let mut a = test_utils::service(test_utils::PEER_SEED);
+        let mut b = test_utils::service(0x03);
+        let ks = TestKeySource::default().with(b.id(), b.keys());
+
+        let secondary = |p: &mut Service| {
+            let (_n, c) = p.publish_secondary(&s.id(), SecondaryOptions::default(), vec![0u8; 1024]).unwrap();
+            Container::parse(c.raw().to_vec(), &ks).unwrap()
+        };
+        let (sa, sb) = (secondary(&mut a), secondary(&mut b));
+
+        // Services without delegates accept secondary pages from no other peers
+        let p = test_utils::primary_page(&mut s);
+        let mut replica = Service::<Vec<u8>>::load(&p).unwrap();
+        assert_eq!(replica.validate_page(&sa), Err(Error::UnauthorizedPublisher));
+        assert_eq!(replica.validate_page(&sb), Err(Error::UnauthorizedPublisher));
+
+        // Delegation lists are carried by primary page updates
+        s.add_delegate(&a.id()).unwrap();
+        assert_eq!(s.delegates().collect::<Vec<_>>(), vec![a.id()]);
+
+        let p = test_utils::primary_page(&mut s);
+        replica.apply_primary(&p).unwrap();
+        assert_eq!(replica.validate_page(&sa), Ok(()));
+        assert_eq!(replica.validate_page(&sb), Err(Error::UnauthorizedPublisher));
+
+        // And removed on later versions
+        s.remove_delegate(&a.id()).unwrap();
+        assert_eq!(s.remove_delegate(&a.id()), Err(Error::NotFound));
+        s.add_delegate(&b.id()).unwrap();
+
+        let p = test_utils::primary_page(&mut s);
+        replica.apply_primary(&p).unwrap();
+        assert_eq!(replica.validate_page(&sa), Err(Error::UnauthorizedPublisher));
+        assert_eq!(replica.validate_page(&sb), Ok(()));
+
+        // Secondary pages must match the service application ID
+        let mut other = ServiceBuilder::<Vec<u8>>::default().application_id(7).build().unwrap();
+        let ks = ks.with(other.id(), other.keys());
+        let (_n, c) = other.publish_secondary(&s.id(), SecondaryOptions::default(), vec![0u8; 1024]).unwrap();
+        let so = Container::parse(c.raw().to_vec(), &ks).unwrap();
+        assert_eq!(replica.validate_page(&so), Err(Error::UnexpectedApplicationId));
+
+        // And be verified against the publisher key
+        let mut unverified = sb.clone();
+        unverified.verified = false;
+        assert_eq!(replica.validate_page(&unverified), Err(Error::InvalidSignature));
+
+        // Expired pages are rejected under the provided policy and clock
+        use crate::wire::ExpiryPolicy;
+        let policy = ExpiryPolicy::default();
+        assert_eq!(replica.validate_page_with(&sb, &policy, &SystemClock), Ok(()));
+        assert_eq!(replica.validate_page_with(&sb, &policy, &DateTime::from_secs(u32::MAX as u64)), Err(Error::PageExpired));
+    }
+
+    #[test]
+    fn test_private_service() {
+        use crate::page::PageInfo;
+        use crate::test_utils::TestKeySource;
+
+        // Private services require a secret key
+        assert_eq!(ServiceBuilder::<Vec<u8>>::default().kind(PageKind::Private).build(), Err(Error::NoSecretKey));
+
+        let mut s = ServiceBuilder::<Vec<u8>>::private()
+            .body(vec![0xaa, 0xbb, 0xcc])
+            .build()
+            .unwrap();
+
+        let (_n, p) = s.publish_primary_buff(Default::default()).unwrap();
+
+        // Private primary pages verify without the secret key, but the body is opaque
+        let p = Container::parse(p.raw().to_vec(), &TestKeySource::default()).unwrap();
+        assert!(p.verified && p.encrypted());
+        assert_eq!(p.info(), Ok(PageInfo::primary(s.public_key())));
+
+        let replica = Service::<Vec<u8>>::load(&p).unwrap();
+        assert!(matches!(replica.body(), MaybeEncrypted::Encrypted(_)));
+
+        // Secret key holders can resolve the service
+        assert_eq!(Service::<Vec<u8>>::load_private(&p, &TestKeySource::default()).map(|_| ()), Err(Error::NoSecretKey));
+
+        let ks = TestKeySource::default().with(s.id(), s.keys());
+        let mut replica = Service::<Vec<u8>>::load_private(&p, &ks).unwrap();
+        assert_eq!(replica.body(), &MaybeEncrypted::Cleartext(vec![0xaa, 0xbb, 0xcc]));
+        assert_eq!(replica.secret_key(), s.secret_key());
+
+        // And apply updates
+        s.update(|b, _public, _private| *b = MaybeEncrypted::Cleartext(vec![0xdd])).unwrap();
+        let (_n, p) = s.publish_primary_buff(Default::default()).unwrap();
+
+        assert_eq!(replica.apply_primary(&p), Ok(true));
+        assert_eq!(replica.body(), &MaybeEncrypted::Cleartext(vec![0xdd]));
+        assert_eq!(replica.version(), s.version());
+    }
 }
@@ -1,21 +1,24 @@
-use byteorder::{NetworkEndian, ByteOrder};
-
-use encdec::{Encode, Decode, EncodeExt, DecodeExt};
+use encdec::{Decode, EncodeExt, DecodeExt};
 
 use crate::{
-    base::{PageBody, Empty},
+    base::PageBody,
     error::Error,
-    net::{Request, RequestBody, Response, ResponseBody, Common},
-    options::Options,
-    prelude::{Header, Keys},
+    net::{Request, RequestBodyRef, Response, Common, PeerEntry, MessageEncoder},
+    prelude::Keys,
     service::Service,
-    types::{MutableData, RequestKind, ResponseKind, Address, Flags, Kind},
+    types::{ImmutableData, MutableData, Address, DateTime, Flags},
     wire::{
         Container, Builder,
         builder::{SetPublicOptions, Encrypt}
     },
 };
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::wire::encode_with_alloc;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct MessageOptions {
     pub append_public_key: bool,
@@ -40,6 +43,9 @@ pub trait Net{
     /// Encode a request using the provided peer keys and buffer
     fn encode_request<B: MutableData>(&self, req: &Request, peer_keys: &Keys, buff: B) -> Result<Container<B>, Error>;
 
+    /// Encode a page-carrying request by reference, writing pages directly from the provided containers
+    fn encode_request_ref<T: ImmutableData, B: MutableData>(&self, req: &Request<RequestBodyRef<T>>, peer_keys: &Keys, buff: B) -> Result<Container<B>, Error>;
+
     /// Encode a response using the provided peer keys and buffer
     fn encode_response<B: MutableData>(&self, resp: &Response, peer_keys: &Keys, buff: B) -> Result<Container<B>, Error>;
 
@@ -60,115 +66,49 @@ pub trait Net{
     ) -> Result<Container<[u8; N]>, Error> {
         self.encode_response(resp, peer_keys, [0u8; N])
     }
+
+    /// Helper to encode and sign a request using an allocated buffer sized to fit the encoded request
+    #[cfg(feature = "alloc")]
+    fn encode_request_alloc(&self, req: &Request, peer_keys: &Keys) -> Result<Container<Vec<u8>>, Error> {
+        encode_with_alloc(0, |b| self.encode_request(req, peer_keys, b))
+    }
+
+    /// Helper to encode and sign a response using an allocated buffer sized to fit the encoded response
+    #[cfg(feature = "alloc")]
+    fn encode_response_alloc(&self, resp: &Response, peer_keys: &Keys) -> Result<Container<Vec<u8>>, Error> {
+        encode_with_alloc(0, |b| self.encode_response(resp, peer_keys, b))
+    }
 }
 
 
 impl <D: PageBody> Net for Service<D> {
     fn encode_request<B: MutableData>(&self, req: &Request, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        self.encoder().encode_request(req, keys, buff)
+    }
 
-        // Create generic header
-          let header = Header {
-            kind: Kind::from(RequestKind::from(&req.data)),
-            flags: req.flags,
-            index: req.id,
-            ..Default::default()
-        };
-
-        // Setup builder
-        let b = Builder::new(buff)
-            .id(&self.id)
-            .header(&header);
-
-        // Encode body
-        let b = match &req.data {
-            RequestBody::Hello | RequestBody::Ping => b.body(Empty)?,
-            RequestBody::FindNode(id) | RequestBody::FindValue(id) | RequestBody::Subscribe(id) | RequestBody::Unsubscribe(id) | RequestBody::Query(id) | RequestBody::Locate(id) | RequestBody::Unregister(id) => b.body(id.as_ref())?,
-            RequestBody::Store(id, pages) | RequestBody::PushData(id, pages) | RequestBody::Register(id, pages) => {
-                b.with_body(|buff| {
-                    let mut n = id.encode(buff)?;
-                    n += Container::encode_pages(pages, &mut buff[n..])?;
-                    Ok(n)
-                })?
-            },
-            // TODO: filter on options here too
-            RequestBody::Discover(body, _opts) => {
-                b.body(body.as_slice())?
-            },
-        };
-
-        // Attach options
-        let b = b.private_options(&[])?
-            .public();
-
-        // Encrypt if running symmetric mode
-        //let b = self.encrypt_message(req.flags, keys, b)?;
-
-        // Sign/encrypt object using provided keying
-        let c = self.finalise_message(req.flags, &req.common, keys, b)?;
-
-        // Return new container
-        Ok(c)
+    fn encode_request_ref<T: ImmutableData, B: MutableData>(&self, req: &Request<RequestBodyRef<T>>, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
+        self.encoder().encode_request_ref(req, keys, buff)
     }
 
     fn encode_response<B: MutableData>(&self, resp: &Response, keys: &Keys, buff: B) -> Result<Container<B>, Error> {
-        // Create generic header
-        let header = Header {
-            kind: Kind::from(ResponseKind::from(&resp.data)),
-            flags: resp.flags,
-            index: resp.id,
-            ..Default::default()
-        };
-
-        // Setup builder
-        let b = Builder::new(buff)
-            .id(&self.id)
-            .header(&header);
-
-        // Encode body
-        let b = match &resp.data {
-            ResponseBody::Status(status) => b.with_body(|buff| {
-                NetworkEndian::write_u32(buff, status.into());
-                Ok(4)
-            })?,
-            ResponseBody::NodesFound(id, nodes) => b.with_body(|buff| {
-                    let mut i = id.encode(buff)?;
-                    for n in nodes {
-                        i += [
-                            Options::peer_id(n.0.clone()),
-                            Options::address(n.1),
-                            Options::pub_key(n.2.clone())
-                        ].encode(&mut buff[i..])?;
-                    }
-                    Ok(i)
-            })?,
-            ResponseBody::ValuesFound(id, pages) | ResponseBody::PullData(id, pages) => {
-                b.with_body(|buff| {
-                    let mut i = id.encode(buff)?;
-                    i += Container::encode_pages(pages, &mut buff[i..])?;
-                    Ok(i)
-                })?
-            },
-            ResponseBody::NoResult => b.body(Empty)?,
-        };
-
-        // Attach options
-        let b = b.private_options(&[])?
-            .public();
-
-        // Encrypt if running symmetric mode
-        //let b = self.encrypt_message(resp.flags, keys, b)?;
-        
-        // Sign/encrypt object using provided keying
-        let c = self.finalise_message(resp.flags, &resp.common, keys, b)?;
-
-        // Return new container
-        Ok(c)
+        self.encoder().encode_response(resp, keys, buff)
     }
 }
 
 
 impl <D: PageBody> Service<D> {
 
+    /// Generate a signed peer exchange entry for this peer
+    pub fn peer_entry(&self, addresses: Vec<Address>, last_seen: DateTime) -> Result<PeerEntry, Error> {
+        let private_key = match &self.private_key {
+            Some(k) => k,
+            None => return Err(Error::NoPrivateKey),
+        };
+
+        PeerEntry::new(self.public_key(), addresses, last_seen)
+            .sign(private_key)
+    }
+
     pub fn encrypt_message<T: MutableData>(&self, flags: Flags, keys: &Keys, b: Builder<Encrypt, T>) -> Result<Builder<SetPublicOptions, T>, Error> {
 
         // Apply symmetric encryption if enabled
@@ -186,61 +126,31 @@ impl <D: PageBody> Service<D> {
         }
     }
 
-    pub fn finalise_message<T: MutableData>(&self, flags: Flags, common: &Common, keys: &Keys, mut b: Builder<SetPublicOptions, T> ) -> Result<Container<T>, Error> {
-
-        // Append public key if required
-        if let Some(pk) = &common.public_key {
-            b.public_option(&Options::pub_key(pk.clone()))?;
+    /// Fetch a message encoder using the service ID, private key and interceptors
+    pub fn encoder(&self) -> MessageEncoder<'_> {
+        MessageEncoder {
+            id: &self.id,
+            private_key: self.private_key.as_ref(),
+            interceptors: Some(&self.interceptors),
         }
+    }
 
-        // Append remote address if provided
-        if let Some(addr) = &common.remote_address {
-            b.public_option(&Options::address(*addr))?;
-        }
-
-        // TODO: messages should be encrypted not just signed..?
-        //let mut b = b.encrypt(opts.sk)?;
-
-        // Sign/encrypt object using provided keying
-        let c = if !flags.contains(Flags::SYMMETRIC_MODE) {
-            // Public key mode, no KX required
-
-            // TODO: attempt encryption against peer pub key if available
-
-            // Check we have a private key to sign with 
-            let private_key = match &self.private_key {
-                Some(k) => k,
-                None => return Err(Error::NoPrivateKey),
-            };
-
-            // Perform signing
-            b.sign_pk(private_key)?
-
-        } else {
-            // Secret key mode, available following KX
-
-            // Derive key by direction
-            let sec_key = match &keys.sym_keys {
-                Some(k) if flags.contains(Flags::SYMMETRIC_DIR) => &k.1,
-                Some(k) => &k.0,
-                _ => panic!("Attempted to sign object with no secret key"),
-            };
-
-            // Sign/Encrypt (AEAD) using secret key
-            b.encrypt_sk(sec_key)?
-        };
-
-        Ok(c)
+    /// Attach common message options, then sign (or encrypt in symmetric mode) the message
+    pub fn finalise_message<T: MutableData>(&self, flags: Flags, common: &Common, keys: &Keys, b: Builder<SetPublicOptions, T> ) -> Result<Container<T>, Error> {
+        self.encoder().finalise(flags, common, keys, b)
     }
 }
 
 #[cfg(test)]
 mod test {
+    use core::convert::TryFrom;
     use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
     use pretty_assertions::assert_eq;
 
-    use crate::{prelude::*, net::{Status, Message}};
+    use crate::types::{BaseKind, RequestKind, ResponseKind};
+
+    use crate::{prelude::*, net::{RequestBody, ResponseBody, Status, StatusDetail, SubscribeFilter, Message, MessageRef, RequestBodyPages}};
     use super::*;
 
     fn setup() -> (Service, Service) {
@@ -252,6 +162,12 @@ mod test {
         (s, p)
     }
 
+    fn peer_entry(seed: u8) -> PeerEntry {
+        let p = crate::test_utils::service(seed);
+        let addr = Address::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, seed)), 8080));
+        p.peer_entry(vec![addr], DateTime::from_secs(1_000)).unwrap()
+    }
+
     fn requests(source: Id, target: Id, flags: Flags, page: Container) -> Vec<Request> {
         let request_id = 120;
 
@@ -286,6 +202,17 @@ mod test {
                 RequestBody::Subscribe(target.clone()),
                 flags.clone(),
             ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::SubscribeFiltered(target.clone(), SubscribeFilter::default()
+                    .with_data_kind(1)
+                    .with_min_index(10)
+                    .with_content_type("application/cbor")
+                    .with_resume(crate::options::ResumeToken{ index: 9, signature: Signature::from([0xaa; crate::types::SIGNATURE_LEN]), mac: None })
+                ),
+                flags.clone(),
+            ),
             Request::new(
                 source.clone(),
                 request_id,
@@ -298,6 +225,48 @@ mod test {
                 RequestBody::PushData(source.clone(), vec![page.clone()]),
                 flags.clone(),
             ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::FindValue(target.clone()),
+                flags.clone(),
+            ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::Unsubscribe(target.clone()),
+                flags.clone(),
+            ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::Locate(target.clone()),
+                flags.clone(),
+            ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::Register(source.clone(), vec![page.clone()]),
+                flags.clone(),
+            ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::Unregister(source.clone()),
+                flags.clone(),
+            ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::Discover(vec![0xaa, 0xbb], vec![Options::name("test"), Options::kind("sensor")]),
+                flags.clone(),
+            ),
+            Request::new(
+                source.clone(),
+                request_id,
+                RequestBody::PeerExchange(vec![peer_entry(crate::test_utils::PEER_SEED)]),
+                flags.clone(),
+            ),
         ]
     }
 
@@ -365,6 +334,116 @@ mod test {
     }
 
 
+    #[test]
+    fn encode_request_ref_matches_owned() {
+        let (mut source, target) = setup();
+        let (_n, page) = source.publish_primary_buff(Default::default()).unwrap();
+
+        let pages = [page];
+        let flags = Flags::ADDRESS_REQUEST;
+
+        let req_ref = Request::new(source.id(), 120, RequestBodyRef::Store(source.id(), &pages[..]), flags);
+        let req = Request::new(source.id(), 120, RequestBody::Store(source.id(), vec![pages[0].to_owned()]), flags);
+
+        // Encode borrowed request
+        let enc = source.encode_request_ref(&req_ref, &target.keys(), vec![0u8; 1024])
+            .expect("Error encoding request");
+
+        // Parse back and check this matches the owned request
+        let (r2, _) = Message::parse(enc.raw().to_vec(), &source.keys())
+            .expect("error parsing message");
+
+        assert_eq!(Message::request(req), r2);
+    }
+
+    #[test]
+    fn encode_decode_paging_options() {
+        let (source, target) = setup();
+        let token = crate::options::ContinuationToken::from_offset(10);
+
+        let req = Request::new(source.id(), 120, RequestBody::FindValue(target.id()), Flags::empty())
+            .with_limit(10)
+            .with_continuation(token.clone());
+        let resp = Response::new(source.id(), 120, ResponseBody::ValuesFound(target.id(), vec![]), Flags::empty())
+            .with_continuation(token.clone());
+
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        let (m, _) = Message::parse(c.raw().to_vec(), &source.keys()).unwrap();
+        match m {
+            Message::Request(r) => {
+                assert_eq!(r.limit, Some(10));
+                assert_eq!(r.continuation, Some(token.clone()));
+            },
+            _ => panic!("Expected request"),
+        }
+
+        let c = source.encode_response(&resp, &target.keys(), vec![0u8; 1024]).unwrap();
+        let (m, _) = Message::parse(c.raw().to_vec(), &source.keys()).unwrap();
+        match m {
+            Message::Response(r) => {
+                assert_eq!(r.limit, None);
+                assert_eq!(r.continuation, Some(token));
+            },
+            _ => panic!("Expected response"),
+        }
+    }
+
+    #[test]
+    fn negotiate_algorithms() {
+        use crate::options::Filters;
+        use crate::types::AlgorithmSuite;
+
+        let (source, target) = setup();
+
+        // Hello requests advertise the local suite
+        let req = Request::new(source.id(), 120, RequestBody::Hello, Flags::empty())
+            .with_algorithms(AlgorithmSuite::local());
+
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        let remote = match Message::parse(c.raw().to_vec(), &source.keys()).unwrap() {
+            (Message::Request(r), _) => r.algorithms,
+            _ => panic!("Expected request"),
+        };
+        assert_eq!(remote, Some(AlgorithmSuite::local()));
+        assert_eq!(AlgorithmSuite::local().negotiate(remote), Ok(AlgorithmSuite::local()));
+
+        // As do peer pages
+        let mut peer = ServiceBuilder::<Vec<u8>>::peer().build().unwrap();
+        let (_n, p) = peer.publish_primary_buff(Default::default()).unwrap();
+        assert_eq!(p.public_options_iter().algorithms(), Some(AlgorithmSuite::local()));
+    }
+
+    #[test]
+    fn subscribe_pacing() {
+        use crate::net::{Pacing, Pacer};
+
+        let (source, target) = setup();
+
+        // Subscribers advertise flow-control parameters
+        let pacing = Pacing::new(Some(8), Some(20));
+        let req = Request::new(source.id(), 121, RequestBody::Subscribe(target.id()), Flags::empty())
+            .with_pacing(pacing);
+
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        let remote = match Message::parse(c.raw().to_vec(), &source.keys()).unwrap() {
+            (Message::Request(r), _) => r.pacing,
+            _ => panic!("Expected request"),
+        };
+        assert_eq!(remote, Some(pacing));
+
+        // Which publishers use to pace pushed data
+        let mut p = Pacer::from_pacing(&remote.unwrap(), Default::default()).unwrap();
+        assert_eq!((0..10).filter(|_| p.try_acquire(Default::default())).count(), 8);
+
+        // Messages without pacing options have no pacing parameters
+        let req = Request::new(source.id(), 122, RequestBody::Subscribe(target.id()), Flags::empty());
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        match Message::parse(c.raw().to_vec(), &source.keys()).unwrap() {
+            (Message::Request(r), _) => assert_eq!(r.pacing, None),
+            _ => panic!("Expected request"),
+        };
+    }
+
     fn responses(source: &Service, target: &Service, flags: Flags, page: Container) -> Vec<Response> {
         let request_id = 123;
         
@@ -375,6 +454,24 @@ mod test {
                 ResponseBody::Status(Status::Ok),
                 flags.clone(),
             ),
+            Response::new(
+                source.id(),
+                request_id,
+                ResponseBody::status_detail(Status::Failed, StatusDetail::default()
+                    .with_retry_after(30)
+                    .with_max_size(1024)
+                    .with_diagnostic("rate limited")
+                ),
+                flags.clone(),
+            ),
+            Response::new(
+                source.id(),
+                request_id,
+                ResponseBody::status_detail(Status::UnsupportedVersion, StatusDetail::default()
+                    .with_option(Options::name("v1"))
+                ),
+                flags.clone(),
+            ),
             // TODO: put node information here
             Response::new(
                 source.id(),
@@ -407,6 +504,33 @@ mod test {
                 ResponseBody::PullData(target.id(), vec![page.clone()]),
                 flags.clone(),
             ),
+            Response::new(
+                source.id(),
+                request_id,
+                ResponseBody::PeerExchange(vec![peer_entry(crate::test_utils::SERVICE_SEED), peer_entry(crate::test_utils::PEER_SEED)]),
+                flags.clone(),
+            ),
+            Response::new(
+                source.id(),
+                request_id,
+                ResponseBody::Registered(target.id(), vec![page.signature()]),
+                flags.clone(),
+            ),
+            Response::new(
+                source.id(),
+                request_id,
+                ResponseBody::Discovered(vec![
+                    (target.id(), vec![Options::name("test")]),
+                    (source.id(), vec![]),
+                ]),
+                flags.clone(),
+            ),
+            Response::new(
+                source.id(),
+                request_id,
+                ResponseBody::Located(target.id(), vec![page.clone()]),
+                flags.clone(),
+            ),
         ]
     }
 
@@ -464,4 +588,241 @@ mod test {
             assert_eq!(Message::response(r), r2);
         }
     }
+
+    // Generate a round-trip conformance test over a table of messages, checking every
+    // variant of the provided kind is covered and encodes / decodes via both owned
+    // and fixed-size buffers
+    macro_rules! conformance {
+        ($name:ident, $kind:ident, $base:expr, $table:expr, $encode:ident, $encode_buff:ident, $wrap:path, $flags:expr) => {
+            #[test]
+            fn $name() {
+                let (mut source, target) = setup();
+                let (_n, page) = source.publish_primary_buff(Default::default()).unwrap();
+
+                let flags: Flags = $flags;
+                let (enc_keys, dec_keys) = match flags.contains(Flags::SYMMETRIC_MODE) {
+                    true => (
+                        source.keys().derive_peer(target.public_key()).unwrap(),
+                        target.keys().derive_peer(source.public_key()).unwrap(),
+                    ),
+                    false => (target.keys(), source.keys()),
+                };
+
+                let table = $table(&source, &target, flags, page.to_owned());
+
+                // Check every kind is exercised by the table
+                let covered: Vec<_> = table.iter().map(|m| $kind::from(&m.data)).collect();
+                for i in 0..(1 << 13) {
+                    if let Ok(k) = $kind::try_from(Kind::new().with_base($base).with_index(i)) {
+                        assert!(covered.contains(&k), "No conformance coverage for {:?}", k);
+                    }
+                }
+
+                for m in table {
+                    // Owned buffer
+                    let enc = source.$encode(&m, &enc_keys, vec![0u8; 1024])
+                        .expect("Error encoding message");
+                    let (d, _) = Message::parse(enc.raw().to_vec(), &dec_keys)
+                        .expect("Error parsing message");
+                    assert_eq!($wrap(m.clone()), d);
+
+                    // Fixed size buffer
+                    let enc = source.$encode_buff::<1024>(&m, &enc_keys)
+                        .expect("Error encoding message");
+                    let mut buff = [0u8; 1024];
+                    buff[..enc.len()].copy_from_slice(enc.raw());
+                    let (d, _) = Message::parse(&mut buff[..enc.len()], &dec_keys)
+                        .expect("Error parsing message");
+                    assert_eq!($wrap(m), d);
+                }
+            }
+        };
+    }
+
+    conformance!(conformance_requests_pk, RequestKind, BaseKind::Request,
+        |s: &Service, t: &Service, f, p| requests(s.id(), t.id(), f, p),
+        encode_request, encode_request_buff, Message::request,
+        Flags::ADDRESS_REQUEST);
+
+    conformance!(conformance_requests_sk, RequestKind, BaseKind::Request,
+        |s: &Service, t: &Service, f, p| requests(s.id(), t.id(), f, p),
+        encode_request, encode_request_buff, Message::request,
+        Flags::ADDRESS_REQUEST | Flags::SYMMETRIC_MODE | Flags::ENCRYPTED);
+
+    conformance!(conformance_responses_pk, ResponseKind, BaseKind::Response,
+        responses, encode_response, encode_response_buff, Message::response,
+        Flags::ADDRESS_REQUEST);
+
+    conformance!(conformance_responses_sk, ResponseKind, BaseKind::Response,
+        responses, encode_response, encode_response_buff, Message::response,
+        Flags::ADDRESS_REQUEST | Flags::SYMMETRIC_MODE | Flags::ENCRYPTED);
+
+    #[test]
+    fn parse_ref_borrows_pages() {
+        let (mut source, target) = setup();
+        let (_n, page) = source.publish_primary_buff(Default::default()).unwrap();
+        let (_n, data) = source.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+        let pages = vec![page.to_owned(), data.to_owned()];
+
+        let req = Request::new(source.id(), 1, RequestBody::Store(source.id(), pages.clone()), Flags::ADDRESS_REQUEST)
+            .with_public_key(source.public_key());
+        let enc = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+
+        // Page-carrying requests borrow pages from the message buffer
+        let mut buff = enc.raw().to_vec();
+        let (m, n) = Message::parse_ref(&mut buff, &source.keys()).unwrap();
+        assert_eq!(n, enc.len());
+
+        let r = match m {
+            MessageRef::Pages(r) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        assert_eq!(r.common.from, source.id());
+        assert!(matches!(r.data, RequestBodyPages::Store(..)));
+        assert_eq!(r.data.id(), &source.id());
+
+        let parsed: Vec<_> = r.data.pages().iter().collect();
+        let expected: Vec<_> = pages.iter().map(|p| p.borrowed()).collect();
+        assert_eq!(parsed, expected);
+
+        // Other messages are parsed as owned messages
+        let req = Request::new(source.id(), 2, RequestBody::Ping, Flags::empty()).with_public_key(source.public_key());
+        let enc = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        let mut buff = enc.raw().to_vec();
+        let (m, _n) = Message::parse_ref(&mut buff, &source.keys()).unwrap();
+        assert_eq!(m, MessageRef::Owned(Message::request(req)));
+    }
+
+    #[test]
+    fn encode_decode_constant_size_sk() {
+        let (source, target) = setup();
+
+        let source_keys = source.keys().derive_peer(target.public_key()).unwrap();
+        let target_keys = target.keys().derive_peer(source.public_key()).unwrap();
+
+        let flags = Flags::SYMMETRIC_MODE | Flags::ENCRYPTED;
+
+        let messages = vec![
+            Message::request(Request::new(source.id(), 1, RequestBody::Hello, flags).with_public_key(source.public_key()).with_constant_size()),
+            Message::request(Request::new(source.id(), 2, RequestBody::Ping, flags).with_constant_size()),
+            Message::response(Response::new(source.id(), 3, ResponseBody::Status(Status::Ok), flags).with_constant_size()),
+            Message::response(Response::new(source.id(), 4, ResponseBody::Status(Status::InvalidRequest), flags).with_constant_size()),
+        ];
+
+        for m in messages {
+            let enc = match &m {
+                Message::Request(r) => source.encode_request(r, &source_keys, vec![0u8; 1024]),
+                Message::Response(r) => source.encode_response(r, &source_keys, vec![0u8; 1024]),
+                Message::Application(_) => unreachable!(),
+            }.expect("Error encoding message");
+
+            // Messages are padded to a constant size
+            assert_eq!(enc.len(), crate::net::CONSTANT_MESSAGE_LEN as usize);
+
+            // And padding is stripped at parse
+            let (d, _) = Message::parse(enc.raw().to_vec(), &target_keys)
+                .expect("Error parsing message");
+            let padding = match &d {
+                Message::Request(r) => r.padding,
+                Message::Response(r) => r.padding,
+                Message::Application(a) => a.padding,
+            };
+            assert_eq!(padding, Some(crate::net::CONSTANT_MESSAGE_LEN));
+            assert_eq!(d, m);
+        }
+    }
+
+    #[test]
+    fn convert_application_messages() {
+        use crate::net::ApplicationMessage;
+        use crate::types::KindConvert;
+
+        #[derive(Copy, Clone, Debug, PartialEq, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+        #[repr(u16)]
+        enum AppKind {
+            Query = 0x01,
+        }
+        crate::impl_kind_convert!(AppKind, 0x1234, BaseKind::Request);
+
+        let (source, target) = setup();
+
+        // Encode an application message
+        let header = Header {
+            application_id: 0x1234,
+            kind: AppKind::Query.to_kind().unwrap(),
+            index: 12,
+            ..Default::default()
+        };
+        let b = Builder::new(vec![0u8; 1024])
+            .id(&source.id())
+            .header(&header).unwrap()
+            .body(&[0xaau8, 0xbb][..]).unwrap()
+            .private_options(&[Options::name("query")]).unwrap()
+            .public();
+        let req = Request::new(source.id(), 12, RequestBody::Hello, Flags::empty())
+            .with_public_key(source.public_key());
+        let c = source.finalise_message(Flags::empty(), &req.common, &target.keys(), b).unwrap();
+
+        // Application messages are verified and passed through
+        let (m, _) = Message::parse(c.raw().to_vec(), &source.keys()).unwrap();
+        let a = match &m {
+            Message::Application(a) => a,
+            _ => panic!("Expected application message"),
+        };
+        assert_eq!(m.request_id(), 12);
+        assert_eq!(m.from(), source.id());
+        assert_eq!(m.pub_key(), Some(source.public_key()));
+        assert_eq!(a.kind_as::<AppKind>(), Ok(AppKind::Query));
+        assert_eq!(&a.body[..], &[0xaa, 0xbb]);
+        assert_eq!(a.private_options, vec![Options::name("query")]);
+        assert!(a.envelope.verified);
+
+        // Unverified containers are rejected
+        let (u, _) = Container::from(c.raw().to_vec());
+        assert_eq!(ApplicationMessage::convert(u), Err(Error::InvalidSignature));
+
+        // As are DSF messages
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        assert_eq!(ApplicationMessage::convert(c), Err(Error::InvalidMessageType));
+    }
+
+    #[test]
+    fn address_request_response() {
+        use crate::options::Filters;
+
+        let (source, target) = setup();
+
+        let local = Address::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)), 10100));
+        let observed = Address::from(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)), 40100));
+
+        // Requesters attach their local address
+        let req = Request::new(source.id(), 1, RequestBody::Hello, Flags::ADDRESS_REQUEST)
+            .with_local_address(local);
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+
+        let r = match Message::parse(c.raw().to_vec(), &source.keys()).unwrap() {
+            (Message::Request(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        assert!(r.flags.contains(Flags::ADDRESS_REQUEST));
+        assert_eq!(r.local_address, Some(local));
+
+        // Responders report the observed address
+        let resp = Response::new(target.id(), r.id, ResponseBody::NoResult, Flags::empty())
+            .with_remote_address(observed);
+        let c = target.encode_response(&resp, &source.keys(), vec![0u8; 1024]).unwrap();
+
+        let resp = match Message::parse(c.raw().to_vec(), &target.keys()).unwrap() {
+            (Message::Response(r), _) => r,
+            m => panic!("Unexpected message: {:?}", m),
+        };
+        assert_eq!(resp.remote_address, Some(observed));
+        assert_ne!(resp.remote_address, r.local_address);
+
+        // Candidate addresses are listed in order of preference
+        let candidates = [observed, local];
+        let opts: Vec<_> = Options::addresses(&candidates).collect();
+        assert_eq!(Filters::address(&opts.iter()), Some(observed));
+        assert_eq!(Filters::addresses(&opts.iter()), candidates.to_vec());
+    }
 }
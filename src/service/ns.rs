@@ -0,0 +1,260 @@
+//! Name service configuration, defining the namespace policy for a [`Registry`](super::Registry).
+//!
+//! An [`NsConfig`] describes the namespace prefix, whether the namespace is public or private
+//! (tertiary pages encrypted with the name service secret key), the option kinds permitted in
+//! queries, the default tertiary page lifetime and any peers delegated to publish links.
+//! Configurations are validated and encoded into the name service primary page with
+//! [`ServiceBuilder::name_service`](super::ServiceBuilder::name_service), and read back using
+//! [`Registry::ns_config`](super::Registry::ns_config).
+
+use core::time::Duration;
+
+#[cfg(feature = "alloc")]
+use alloc::{vec::Vec, string::String};
+
+use crate::base::{MaybeEncrypted, PageBody};
+use crate::error::Error;
+use crate::options::{Options, OptionKind, NsPolicy, MAX_OPTION_LEN};
+use crate::types::*;
+
+use super::{Service, TertiaryOptions};
+
+/// Option kinds that may be used in name service queries
+pub const NS_QUERY_KINDS: &[OptionKind] = &[
+    OptionKind::Name, OptionKind::Kind, OptionKind::Manufacturer,
+    OptionKind::Serial, OptionKind::Building, OptionKind::Room,
+    OptionKind::Coord,
+];
+
+/// Default tertiary page lifetime (1 day)
+pub const NS_DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Name service namespace visibility
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Namespace {
+    /// Public namespace, links may be resolved by any peer
+    Public,
+    /// Private namespace, links are encrypted and may only be resolved by holders of the service secret key
+    Private,
+}
+
+/// Name service configuration
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct NsConfig {
+    /// Namespace prefix
+    pub prefix: String,
+    /// Namespace visibility
+    pub namespace: Namespace,
+    /// Option kinds permitted in queries
+    pub query_kinds: Vec<OptionKind>,
+    /// Default tertiary page lifetime
+    pub ttl: Duration,
+    /// Peers delegated to publish links in this namespace
+    pub delegates: Vec<Id>,
+}
+
+impl NsConfig {
+    /// Create a public namespace configuration with the provided prefix, permitting name queries
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.into(),
+            namespace: Namespace::Public,
+            query_kinds: vec![OptionKind::Name],
+            ttl: NS_DEFAULT_TTL,
+            delegates: vec![],
+        }
+    }
+
+    /// Set the namespace to private
+    pub fn private(mut self) -> Self {
+        self.namespace = Namespace::Private;
+        self
+    }
+
+    /// Set the option kinds permitted in queries
+    pub fn with_query_kinds(mut self, kinds: &[OptionKind]) -> Self {
+        self.query_kinds = kinds.to_vec();
+        self
+    }
+
+    /// Set the default tertiary page lifetime
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Delegate link publishing to the provided peer
+    pub fn with_delegate(mut self, id: Id) -> Self {
+        self.delegates.push(id);
+        self
+    }
+
+    /// Validate the configuration, returning [`Error::InvalidOption`] for empty or oversized prefixes,
+    /// TTLs outside 1s..=u32::MAX seconds and empty query kinds, and [`Error::InvalidQuery`]
+    /// where query kinds can not be used in queries
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.prefix.is_empty() || self.prefix.len() > MAX_OPTION_LEN {
+            return Err(Error::InvalidOption);
+        }
+
+        if self.ttl.as_secs() == 0 || self.ttl.as_secs() > u32::MAX as u64 {
+            return Err(Error::InvalidOption);
+        }
+
+        if self.query_kinds.is_empty() {
+            return Err(Error::InvalidOption);
+        }
+        if let Some(k) = self.query_kinds.iter().find(|k| !NS_QUERY_KINDS.contains(k)) {
+            debug!("Option kind {:?} not supported in name service queries", k);
+            return Err(Error::InvalidQuery);
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the encoded policy for this configuration
+    pub fn policy(&self) -> NsPolicy {
+        NsPolicy {
+            private: self.namespace == Namespace::Private,
+            ttl: self.ttl.as_secs().min(u32::MAX as u64) as u32,
+            query_kinds: self.query_kinds.iter().fold(0, |a, k| a | 1 << (*k as u16)),
+        }
+    }
+
+    /// Fetch (public, private) options encoding this configuration,
+    /// the prefix and policy are private options for private namespaces
+    pub fn options(&self) -> (Vec<Options>, Vec<Options>) {
+        let ns = vec![Options::name(&self.prefix), Options::ns_policy(self.policy())];
+        let delegates = self.delegates.iter().cloned().map(Options::delegate).collect();
+
+        match self.namespace {
+            Namespace::Public => ([ns, delegates].concat(), vec![]),
+            Namespace::Private => (delegates, ns),
+        }
+    }
+
+    /// Check a query option is permitted by this configuration
+    pub fn check_query(&self, q: &Options) -> Result<(), Error> {
+        match self.query_kinds.contains(&OptionKind::from(q)) {
+            true => Ok(()),
+            false => Err(Error::InvalidQuery),
+        }
+    }
+
+    /// Create tertiary page options using the default lifetime from the provided issue time
+    pub fn tertiary_options(&self, index: u16, issued: DateTime) -> TertiaryOptions {
+        TertiaryOptions { index, issued, expiry: issued + self.ttl }
+    }
+
+    /// Load a configuration from name service options, services without a policy
+    /// (for example, those created with [`ServiceBuilder::ns`](super::ServiceBuilder::ns))
+    /// use default policy values
+    pub(super) fn from_service<B: PageBody>(s: &Service<B>) -> Result<Self, Error> {
+        if s.kind() != PageKind::Name {
+            return Err(Error::UnexpectedPageKind);
+        }
+
+        // Policy options may be public or private
+        let private: &[Options] = match &s.private_options {
+            MaybeEncrypted::Cleartext(o) => o,
+            _ => &[],
+        };
+        let options = || s.public_options.iter().chain(private.iter());
+
+        let prefix = options().find_map(|o| match o {
+            Options::Name(n) => Some(n.clone()),
+            _ => None,
+        }).ok_or(Error::NotFound)?;
+
+        let policy = options().find_map(|o| match o {
+            Options::NsPolicy(p) => Some(*p),
+            _ => None,
+        });
+
+        let mut c = NsConfig::new(prefix.as_ref());
+        c.delegates = s.delegates().collect();
+
+        match policy {
+            Some(p) => {
+                c.namespace = if p.private { Namespace::Private } else { Namespace::Public };
+                c.ttl = Duration::from_secs(p.ttl as u64);
+                c.query_kinds = NS_QUERY_KINDS.iter()
+                    .filter(|k| p.query_kinds & 1 << (**k as u16) != 0)
+                    .cloned().collect();
+            },
+            None if s.encrypted() => c.namespace = Namespace::Private,
+            None => (),
+        }
+
+        // Policy must match service encryption
+        if (c.namespace == Namespace::Private) != s.encrypted() {
+            return Err(Error::InvalidOption);
+        }
+
+        c.validate()?;
+
+        Ok(c)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::Empty;
+    use crate::options::Filters;
+    use crate::service::{Publisher, Registry, ServiceBuilder, Subscriber, TertiaryLink};
+
+    #[test]
+    fn name_service_config() {
+        let d = crate::test_utils::id(0x42);
+        let c = NsConfig::new("test.com")
+            .with_query_kinds(&[OptionKind::Name, OptionKind::Kind])
+            .with_ttl(Duration::from_secs(3600))
+            .with_delegate(d.clone());
+
+        // Configurations are encoded and read back from the primary page
+        let mut s = ServiceBuilder::<Vec<u8>>::name_service(&c).unwrap().build().unwrap();
+        assert_eq!(s.ns_config(), Ok(c.clone()));
+        assert!(s.is_delegate(&d));
+
+        let (_n, p) = s.publish_primary_buff(Default::default()).unwrap();
+        let r = Service::<Vec<u8>>::load(&p).unwrap();
+        assert_eq!(r.ns_config(), Ok(c.clone()));
+
+        // Queries and tertiary page lifetimes follow the policy
+        assert_eq!(c.check_query(&Options::kind("light")), Ok(()));
+        assert_eq!(c.check_query(&Options::Building("b1".into())), Err(Error::InvalidQuery));
+
+        let o = c.tertiary_options(0, DateTime::from_secs(1_000));
+        assert_eq!(o.expiry, DateTime::from_secs(4_600));
+
+        let t = ServiceBuilder::<Empty>::generic().build().unwrap();
+        let (_n, l) = s.publish_tertiary_buff::<512, _>(t.id().into(), o, &Options::name("a")).unwrap();
+        assert_eq!(l.public_options_iter().expiry(), Some(DateTime::from_secs(4_600)));
+        assert_eq!(s.resolve_link(&l), Ok(TertiaryLink::Service(t.id())));
+
+        // Private namespaces encrypt the prefix and policy
+        let c = c.private();
+        let s = ServiceBuilder::<Vec<u8>>::name_service(&c).unwrap().build().unwrap();
+        assert!(s.encrypted());
+        assert_eq!(s.ns_config(), Ok(c));
+
+        // Legacy name services use default policies
+        let s = ServiceBuilder::<Vec<u8>>::ns("test.com").build().unwrap();
+        assert_eq!(s.ns_config(), Ok(NsConfig::new("test.com")));
+
+        // Invalid configurations are rejected
+        let invalid = [
+            NsConfig::new(""),
+            NsConfig::new("test.com").with_ttl(Duration::from_secs(0)),
+            NsConfig::new("test.com").with_query_kinds(&[]),
+        ];
+        for c in &invalid {
+            assert_eq!(c.validate(), Err(Error::InvalidOption));
+        }
+        let c = NsConfig::new("test.com").with_query_kinds(&[OptionKind::PubKey]);
+        assert_eq!(c.validate(), Err(Error::InvalidQuery));
+    }
+}
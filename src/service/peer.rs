@@ -0,0 +1,237 @@
+//! Peer information describes the addresses, supported protocols, relay capability and uptime of
+//! a peer, published as the body of [`PageKind::Peer`] primary pages.
+//!
+//! Bodies are encoded as a flags byte, the peer [`AlgorithmSuite`] and (where set) uptime in network
+//! byte order, followed by peer addresses encoded as address options, so that peer pages published
+//! by other implementations may be parsed without relying on ad-hoc option collections.
+//!
+//! Peer services are created with [`ServiceBuilder::peer_info`], and peer information is loaded
+//! from received pages with [`Container::peer_info`].
+
+use byteorder::{ByteOrder, NetworkEndian};
+use encdec::{Encode, Decode, DecodeExt};
+
+use crate::base::PageBody;
+use crate::error::Error;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::Container;
+
+use super::ServiceBuilder;
+
+const FLAG_RELAY: u8 = 1 << 0;
+const FLAG_UPTIME: u8 = 1 << 1;
+
+const FLAGS_ALL: u8 = FLAG_RELAY | FLAG_UPTIME;
+
+/// Maximum number of addresses advertised in peer information
+pub const MAX_PEER_ADDRESSES: usize = 4;
+
+/// Peer information, carried as the body of peer pages
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct PeerInfo {
+    /// Peer addresses
+    pub addresses: heapless::Vec<Address, MAX_PEER_ADDRESSES>,
+    /// Supported algorithm / feature suite
+    pub capabilities: AlgorithmSuite,
+    /// Peer is willing to relay messages for other peers
+    pub relay: bool,
+    /// Peer uptime in seconds
+    pub uptime: Option<u32>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for PeerInfo {
+    fn format(&self, fmt: defmt::Formatter) {
+        let a: &[Address] = &self.addresses;
+        defmt::write!(fmt, "PeerInfo {{ addresses: {}, capabilities: {}, relay: {}, uptime: {} }}", a, self.capabilities, self.relay, self.uptime)
+    }
+}
+
+impl Default for PeerInfo {
+    fn default() -> Self {
+        Self {
+            addresses: heapless::Vec::new(),
+            capabilities: AlgorithmSuite::local(),
+            relay: false,
+            uptime: None,
+        }
+    }
+}
+
+impl PeerInfo {
+    /// Add a peer address, returning [`Error::TooManyOptions`] where [`MAX_PEER_ADDRESSES`] is exceeded
+    pub fn with_address(mut self, address: Address) -> Result<Self, Error> {
+        self.addresses.push(address).map_err(|_| Error::TooManyOptions)?;
+        Ok(self)
+    }
+
+    /// Set the advertised algorithm / feature suite
+    pub fn with_capabilities(mut self, capabilities: AlgorithmSuite) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Set whether the peer relays messages for other peers
+    pub fn with_relay(mut self, relay: bool) -> Self {
+        self.relay = relay;
+        self
+    }
+
+    /// Set peer uptime
+    pub fn with_uptime(mut self, uptime: core::time::Duration) -> Self {
+        self.uptime = Some(uptime.as_secs().min(u32::MAX as u64) as u32);
+        self
+    }
+
+    fn flags(&self) -> u8 {
+        let mut f = 0;
+        if self.relay { f |= FLAG_RELAY }
+        if self.uptime.is_some() { f |= FLAG_UPTIME }
+        f
+    }
+
+    fn address_options(&self) -> impl Iterator<Item=Options> + '_ {
+        self.addresses.iter().map(|a| Options::address(*a))
+    }
+}
+
+impl Encode for PeerInfo {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        let n: usize = self.address_options().map(|o| o.encode_len()).sum::<Result<_, _>>()?;
+        Ok(1 + 4 + self.uptime.map(|_| 4).unwrap_or(0) + n)
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.encode_len()? {
+            return Err(Error::BufferLength);
+        }
+
+        buff[0] = self.flags();
+        NetworkEndian::write_u32(&mut buff[1..], self.capabilities.bits());
+        let mut n = 5;
+
+        if let Some(v) = self.uptime {
+            NetworkEndian::write_u32(&mut buff[n..], v);
+            n += 4;
+        }
+
+        for o in self.address_options() {
+            n += o.encode(&mut buff[n..])?;
+        }
+
+        Ok(n)
+    }
+}
+
+impl <'a> Decode<'a> for PeerInfo {
+    type Output = PeerInfo;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.len() < 5 {
+            return Err(Error::InvalidPageLength);
+        }
+
+        let flags = buff[0];
+        if flags & !FLAGS_ALL != 0 {
+            return Err(Error::InvalidFlags);
+        }
+
+        let mut info = PeerInfo {
+            addresses: heapless::Vec::new(),
+            capabilities: AlgorithmSuite::from_bits_truncate(NetworkEndian::read_u32(&buff[1..])),
+            relay: flags & FLAG_RELAY != 0,
+            uptime: None,
+        };
+        let mut n = 5;
+
+        if flags & FLAG_UPTIME != 0 {
+            let d = buff.get(n..n + 4).ok_or(Error::InvalidPageLength)?;
+            info.uptime = Some(NetworkEndian::read_u32(d));
+            n += 4;
+        }
+
+        // Addresses follow as options, other options are ignored
+        for o in Options::decode_iter(&buff[n..]) {
+            let a = match o? {
+                Options::IPv4(a) => Address::from(a),
+                Options::IPv6(a) => Address::from(a),
+                _ => continue,
+            };
+            info.addresses.push(a).map_err(|_| Error::TooManyOptions)?;
+        }
+
+        Ok((info, buff.len()))
+    }
+}
+
+impl PageBody for PeerInfo {}
+
+impl ServiceBuilder<PeerInfo> {
+    /// Setup a peer service publishing the provided peer information.
+    /// This is equivalent to .peer().body(info)
+    pub fn peer_info(info: PeerInfo) -> Self {
+        Self::peer().body(info)
+    }
+}
+
+impl <T: ImmutableData> Container<T> {
+    /// Load peer information from a peer page
+    pub fn peer_info(&self) -> Result<PeerInfo, Error> {
+        if self.header().kind() != Kind::page(PageKind::Peer as u16) {
+            return Err(Error::UnexpectedPageKind);
+        }
+        if self.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
+
+        PeerInfo::decode(self.body_raw()).map(|(i, _n)| i)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keys::NullKeySource;
+    use crate::service::{Publisher, Service, Subscriber};
+    use crate::test_utils;
+
+    #[test]
+    fn peer_info_pages() {
+        let info = PeerInfo::default()
+            .with_address(AddressV4::from(([10, 0, 0, 1], 10100)).into()).unwrap()
+            .with_address(AddressV6::from(([0xfe; 16], 10100)).into()).unwrap()
+            .with_relay(true)
+            .with_uptime(core::time::Duration::from_secs(120));
+
+        let mut s = ServiceBuilder::peer_info(info.clone())
+            .keys(test_utils::keys(test_utils::PEER_SEED))
+            .build().unwrap();
+
+        // Peer pages carry peer information as the page body
+        let (_n, p) = s.publish_primary_buff(Default::default()).unwrap();
+        let p = Container::parse(p.raw().to_vec(), &NullKeySource).unwrap();
+        assert_eq!(p.peer_info(), Ok(info.clone()));
+
+        let replica = Service::<PeerInfo>::load(&p).unwrap();
+        assert_eq!(replica.body(), &crate::base::MaybeEncrypted::Cleartext(info.clone()));
+
+        // Other pages are rejected
+        let mut g = test_utils::service(test_utils::SERVICE_SEED);
+        let (_n, g) = g.publish_primary_buff(Default::default()).unwrap();
+        assert_eq!(g.peer_info(), Err(Error::UnexpectedPageKind));
+
+        // Address capacity and unknown flags are enforced
+        let full = (0..MAX_PEER_ADDRESSES).fold(PeerInfo::default(), |i, n| i.with_address(AddressV4::from(([10, 0, 0, n as u8], 1)).into()).unwrap());
+        assert_eq!(full.with_address(AddressV4::from(([10, 0, 1, 0], 1)).into()), Err(Error::TooManyOptions));
+
+        let mut buff = [0u8; 128];
+        let n = info.encode(&mut buff).unwrap();
+        buff[0] |= 0x80;
+        assert_eq!(PeerInfo::decode(&buff[..n]), Err(Error::InvalidFlags));
+    }
+}
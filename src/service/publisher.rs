@@ -1,19 +1,31 @@
 use core::ops::Add;
+use core::time::Duration;
 
 use encdec::{Encode, Decode};
 
 use crate::{
     base::{Header, MaybeEncrypted, DataBody, PageBody},
+    crypto::Signer,
     error::Error,
-    options::Options,
+    options::{Options, StandardOptions},
+    page::{ReplicaQos, REPLICA_QOS_LEN},
     service::Service,
     types::*,
     wire::{
-        Builder, Container,
-        builder::{Encrypt, SetPublicOptions}
+        Builder, Container, Compressor,
+        builder::{Init, SetPrivateOptions, Encrypt, SetPublicOptions}
     },
 };
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use crate::wire::{encode_with_alloc, MIN_OBJECT_LEN};
+
+#[cfg(feature = "async")]
+use crate::crypto::AsyncSigner;
+
 /// Publisher trait allows services to generate primary, data, and secondary pages
 /// as well as to encode (and sign and optionally encrypt) generated pages
 pub trait Publisher<const N: usize = 512> {
@@ -24,6 +36,15 @@ pub trait Publisher<const N: usize = 512> {
         buff: T,
     ) -> Result<(usize, Container<T>), Error>;
 
+    /// Generates a primary page signed with the provided [`Signer`], for services where the private key
+    /// is held externally (for example, in an HSM, secure element, or remote signer)
+    fn publish_primary_signed<S: Signer, T: MutableData>(
+        &mut self,
+        options: PrimaryOptions,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error>;
+
     // Helper to publish primary page using fixed sized buffer
     fn publish_primary_buff(&mut self, options: PrimaryOptions) -> Result<(usize, Container<[u8; N]>), Error> {
         let buff = [0u8; N];
@@ -38,6 +59,34 @@ pub trait Publisher<const N: usize = 512> {
         buff: T,
     ) -> Result<(usize, Container<T>), Error>;
 
+    /// Create a data object for publishing signed with the provided [`Signer`]
+    fn publish_data_signed<S: Signer, B: DataBody, T: MutableData>(
+        &mut self,
+        options: DataOptions<B>,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error>;
+
+    /// Create a data object for publishing with a `len` byte body written incrementally by the provided producer,
+    /// see [`Builder::with_body_stream`]. Note that `options.body` is ignored.
+    fn publish_data_stream<T: MutableData>(
+        &mut self,
+        options: DataOptions,
+        len: usize,
+        producer: impl FnMut(&mut [u8]) -> Result<usize, Error>,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error>;
+
+    /// Create a data object for publishing with the body compressed using the provided [`Compressor`],
+    /// see [`Builder::compressed_body`]
+    #[cfg(feature = "alloc")]
+    fn publish_data_compressed<C: Compressor, B: DataBody, T: MutableData>(
+        &mut self,
+        options: DataOptions<B>,
+        compressor: &C,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error>;
+
     // Helper to publish data block using fixed size buffer
     fn publish_data_buff<B: DataBody>(&mut self, options: DataOptions<B>) -> Result<(usize, Container<[u8; N]>), Error> {
         let buff = [0u8; N];
@@ -53,12 +102,65 @@ pub trait Publisher<const N: usize = 512> {
         buff: T,
     ) -> Result<(usize, Container<T>), Error>;
 
+    /// Create a secondary page for publishing signed with the provided [`Signer`]
+    fn publish_secondary_signed<S: Signer, T: MutableData>(
+        &mut self,
+        id: &Id,
+        options: SecondaryOptions,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error>;
+
+    /// Create a replica page for the service `id`, publishing the provided QoS information
+    /// for the replication agreement. See [`Replica`](crate::page::Replica) for parsing.
+    fn publish_replica<T: MutableData>(
+        &mut self,
+        id: &Id,
+        qos: &ReplicaQos,
+        options: SecondaryOptions,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let mut body = [0u8; REPLICA_QOS_LEN];
+        qos.encode(&mut body)?;
+
+        let options = SecondaryOptions {
+            page_kind: PageKind::Replica.into(),
+            body: Some(&body[..]),
+            ..options
+        };
+
+        self.publish_secondary(id, options, buff)
+    }
+
     /// Helper to publish secondary page fixed size buffer
     fn publish_secondary_buff(&mut self, id: &Id, options: SecondaryOptions) -> Result<(usize, Container<[u8; N]>), Error> {
         let buff = [0u8; N];
         let (n, c) = self.publish_secondary(id, options, buff)?;
         Ok((n, c))
     }
+
+    /// Helper to publish primary page using an allocated buffer sized to fit the encoded page
+    #[cfg(feature = "alloc")]
+    fn publish_primary_alloc(&mut self, options: PrimaryOptions) -> Result<(usize, Container<Vec<u8>>), Error> {
+        let c = encode_with_alloc(0, |b| self.publish_primary(options.clone(), b).map(|(_n, c)| c))?;
+        Ok((c.len(), c))
+    }
+
+    /// Helper to publish data block using an allocated buffer sized to fit the encoded object
+    #[cfg(feature = "alloc")]
+    fn publish_data_alloc<B: DataBody + Clone>(&mut self, options: DataOptions<B>) -> Result<(usize, Container<Vec<u8>>), Error> {
+        let hint = encode_len_hint(&options.body, options.public_options, options.private_options);
+        let c = encode_with_alloc(hint, |b| self.publish_data(options.clone(), b).map(|(_n, c)| c))?;
+        Ok((c.len(), c))
+    }
+
+    /// Helper to publish secondary page using an allocated buffer sized to fit the encoded page
+    #[cfg(feature = "alloc")]
+    fn publish_secondary_alloc(&mut self, id: &Id, options: SecondaryOptions) -> Result<(usize, Container<Vec<u8>>), Error> {
+        let hint = encode_len_hint(&options.body, options.public_options, options.private_options);
+        let c = encode_with_alloc(hint, |b| self.publish_secondary(id, options.clone(), b).map(|(_n, c)| c))?;
+        Ok((c.len(), c))
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -79,18 +181,32 @@ impl Default for PrimaryOptions {
     }
 }
 
+impl PrimaryOptions {
+    /// Create primary page options with issued and expiry times from the provided clock
+    pub fn from_clock<C: Clock>(clock: &C) -> Self {
+        Self {
+            issued: Some(clock.now()),
+            expiry: Some(clock.now().add(DEFAULT_PAGE_EXPIRY)),
+        }
+    }
+}
+
+/// Default page expiry (relative to issued time)
+pub const DEFAULT_PAGE_EXPIRY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Default issued time, using the system clock where available
 fn default_issued() -> Option<DateTime> {
     #[cfg(feature="std")]
-    return Some(std::time::SystemTime::now().into());
+    return Some(SystemClock.now());
 
     #[cfg(not(feature="std"))]
     return None;
 }
 
+/// Default expiry time, using the system clock where available
 fn default_expiry() -> Option<DateTime> {
     #[cfg(feature="std")]
-    return Some(std::time::SystemTime::now()
-        .add(std::time::Duration::from_secs(24*60*60)).into());
+    return Some(SystemClock.now().add(DEFAULT_PAGE_EXPIRY));
 
     #[cfg(not(feature="std"))]
     return None;
@@ -139,6 +255,29 @@ impl <'a>Default for SecondaryOptions<'a> {
     }
 }
 
+impl <'a> SecondaryOptions<'a> {
+    /// Create secondary page options with issued and expiry times from the provided clock
+    pub fn from_clock<C: Clock>(clock: &C) -> Self {
+        Self {
+            issued: Some(clock.now()),
+            expiry: Some(clock.now().add(DEFAULT_PAGE_EXPIRY)),
+            ..Default::default()
+        }
+    }
+}
+
+
+/// Estimate the encoded length of an object from the body and options,
+/// as a starting point for adaptive buffer sizing
+#[cfg(feature = "alloc")]
+fn encode_len_hint<B: Encode>(body: &Option<B>, public_options: &[Options], private_options: &[Options]) -> usize {
+    let body_len = body.as_ref().map(|b| b.encode_len().unwrap_or(0)).unwrap_or(0);
+    let options_len: usize = public_options.iter().chain(private_options.iter())
+        .map(|o| o.encode_len().unwrap_or(0))
+        .sum();
+
+    MIN_OBJECT_LEN + body_len + options_len
+}
 
 #[derive(Clone, Debug)]
 pub struct DataOptions<'a, Body: DataBody = &'a [u8]> {
@@ -174,6 +313,16 @@ impl<'a, Body: DataBody> Default for DataOptions<'a, Body> {
     }
 }
 
+impl<'a, Body: DataBody> DataOptions<'a, Body> {
+    /// Create data options with the issued time from the provided clock
+    pub fn from_clock<C: Clock>(clock: &C) -> Self {
+        Self {
+            issued: Some(clock.now()),
+            ..Default::default()
+        }
+    }
+}
+
 impl <B> Publisher for Service<B> 
     where
         B: PageBody,
@@ -185,6 +334,213 @@ impl <B> Publisher for Service<B>
         options: PrimaryOptions,
         buff: T,
     ) -> Result<(usize, Container<T>), Error> {
+        let mut signer = self.key_signer()?;
+        self.publish_primary_signed(options, &mut signer, buff)
+    }
+
+    fn publish_primary_signed<S: Signer, T: MutableData>(
+        &mut self,
+        options: PrimaryOptions,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let (b, version) = self.build_primary(options, buff)?;
+
+        // Sign generated object
+        let c = self.sign_with(b, signer)?;
+        self.version = version;
+        
+        // Return container and encode
+        Ok((c.len(), c))
+    }
+
+    /// Secondary generates a secondary page using this service to be attached to / stored at the provided service ID
+    fn publish_secondary<T: MutableData>(
+        &mut self,
+        id: &Id,
+        options: SecondaryOptions,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let mut signer = self.key_signer()?;
+        self.publish_secondary_signed(id, options, &mut signer, buff)
+    }
+
+    fn publish_secondary_signed<S: Signer, T: MutableData>(
+        &mut self,
+        id: &Id,
+        options: SecondaryOptions,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let b = self.build_secondary(id, options, buff)?;
+
+        // Sign generated object
+        let c = self.sign_with(b, signer)?;
+        
+        Ok((c.len(), c))
+    }
+
+    fn publish_data<D: DataBody, T: MutableData>(
+        &mut self,
+        options: DataOptions<D>,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let mut signer = self.key_signer()?;
+        self.publish_data_signed(options, &mut signer, buff)
+    }
+
+    fn publish_data_signed<S: Signer, D: DataBody, T: MutableData>(
+        &mut self,
+        options: DataOptions<D>,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let (options, body) = options.split_body();
+        let (b, data_index) = self.build_data(options, buff, |b| write_data_body(b, body))?;
+
+        // Sign generated object
+        let c = self.sign_with(b, signer)?;
+        self.data_index = data_index;
+        
+        // Return container and encoded length
+        Ok((c.len(), c))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn publish_data_compressed<C: Compressor, D: DataBody, T: MutableData>(
+        &mut self,
+        options: DataOptions<D>,
+        compressor: &C,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let mut signer = self.key_signer()?;
+        let (options, body) = options.split_body();
+
+        // Encode body for compression
+        let mut data = Vec::new();
+        if let Some(body) = body {
+            let encode_failed = |e| {
+                error!("Failed to encode data body: {:?}", e);
+                Error::EncodeFailed
+            };
+
+            data.resize(body.encode_len().map_err(encode_failed)?, 0);
+            let n = body.encode(&mut data).map_err(encode_failed)?;
+            data.truncate(n);
+        }
+
+        let (b, data_index) = self.build_data(options, buff, |b| b.compressed_body(compressor, &data))?;
+
+        let c = self.sign_with(b, &mut signer)?;
+        self.data_index = data_index;
+
+        Ok((c.len(), c))
+    }
+
+    fn publish_data_stream<T: MutableData>(
+        &mut self,
+        options: DataOptions,
+        len: usize,
+        producer: impl FnMut(&mut [u8]) -> Result<usize, Error>,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let mut signer = self.key_signer()?;
+        let (b, data_index) = self.build_data(options, buff, |b| b.with_body_stream(len, producer))?;
+
+        let c = self.sign_with(b, &mut signer)?;
+        self.data_index = data_index;
+
+        Ok((c.len(), c))
+    }
+}
+
+/// Publishing with async signers, for services where the private key is held by a remote signer or HSM
+#[cfg(feature = "async")]
+impl <B> Service<B>
+    where
+        B: PageBody,
+        <B as Encode>::Error: core::fmt::Debug,
+{
+    /// Generate a primary page signed with the provided [`AsyncSigner`]
+    pub async fn publish_primary_async<S: AsyncSigner, T: MutableData>(
+        &mut self,
+        options: PrimaryOptions,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let (b, version) = self.build_primary(options, buff)?;
+
+        let c = self.sign_with_async(b, signer).await?;
+        self.version = version;
+
+        Ok((c.len(), c))
+    }
+
+    /// Generate a secondary page for the provided service ID signed with the provided [`AsyncSigner`]
+    pub async fn publish_secondary_async<S: AsyncSigner, T: MutableData>(
+        &mut self,
+        id: &Id,
+        options: SecondaryOptions<'_>,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let b = self.build_secondary(id, options, buff)?;
+
+        let c = self.sign_with_async(b, signer).await?;
+
+        Ok((c.len(), c))
+    }
+
+    /// Generate a data object signed with the provided [`AsyncSigner`]
+    pub async fn publish_data_async<S: AsyncSigner, D: DataBody, T: MutableData>(
+        &mut self,
+        options: DataOptions<'_, D>,
+        signer: &mut S,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        let (options, body) = options.split_body();
+        let (b, data_index) = self.build_data(options, buff, |b| write_data_body(b, body))?;
+
+        let c = self.sign_with_async(b, signer).await?;
+        self.data_index = data_index;
+
+        Ok((c.len(), c))
+    }
+}
+
+impl <'a, D: DataBody> DataOptions<'a, D> {
+    /// Split the body from data options for writing with [`write_data_body`]
+    fn split_body(self) -> (DataOptions<'a>, Option<D>) {
+        let DataOptions{ data_kind, body, issued, public_options, private_options, no_last_sig } = self;
+        (DataOptions{ data_kind, body: None, issued, public_options, private_options, no_last_sig }, body)
+    }
+}
+
+/// Write an (optional) data object body
+fn write_data_body<D: DataBody, T: MutableData>(b: Builder<Init, T>, body: Option<D>) -> Result<Builder<SetPrivateOptions, T>, Error> {
+    match body {
+        Some(body) if body.encode_len().map(|n| n > b.remaining()).unwrap_or(false) => {
+            Err(Error::BufferLength)
+        },
+        Some(body) => b.body(body).map_err(|e| {
+            error!("Failed to encode data body: {:?}", e);
+            Error::EncodeFailed
+        }),
+        None => b.with_body(|_b| Ok(0) ),
+    }
+}
+
+impl <B> Service<B>
+    where
+        B: PageBody,
+        <B as Encode>::Error: core::fmt::Debug,
+{
+    /// Build an unsigned primary page, returning the builder and new page version
+    fn build_primary<T: MutableData>(
+        &mut self,
+        options: PrimaryOptions,
+        buff: T,
+    ) -> Result<(Builder<SetPublicOptions, T>, u16), Error> {
         let mut flags = Flags::default();
         if self.encrypted {
             flags |= Flags::ENCRYPTED;
@@ -192,13 +548,14 @@ impl <B> Publisher for Service<B>
 
         debug!("Primary options: {:?}", options);
 
-        self.version = self.version.wrapping_add(1);
+        // Version is only updated once the page is successfully encoded
+        let version = self.version.wrapping_add(1);
 
         // Setup header
         let header = Header {
             application_id: self.application_id,
             kind: self.kind.into(),
-            index: self.version,
+            index: version,
             flags,
             ..Default::default()
         };
@@ -211,10 +568,13 @@ impl <B> Publisher for Service<B>
 
         // Build object
         let b = Builder::new(buff)
-           .header(&header)
+           .header(&header)?
            .id(&self.id());
 
         let b = match &self.body {
+            MaybeEncrypted::Cleartext(body) if body.encode_len().map(|n| n > b.remaining()).unwrap_or(false) => {
+                return Err(Error::BufferLength)
+            },
             MaybeEncrypted::Cleartext(body) => b.body(body).map_err(|e| {
                 error!("Failed to encode body: {:?}", e);
                 Error::EncodeFailed
@@ -233,44 +593,26 @@ impl <B> Publisher for Service<B>
         // Apply internal encryption if enabled
         let mut b = self.encrypt(b)?;
 
-        // Generate and append public options
-        b = b.public_options([
-            Options::pub_key(self.public_key.clone()),
-        ].iter())?;
-
-        // Attach last sig if available
-        if let Some(last) = &self.last_sig {
-            b = b.public_options([Options::prev_sig(last)].iter())?;
-        }
-
-        // Generate and append public options
-
-        // Attach issued if provided
-        if let Some(iss) = options.issued {
-            b = b.public_options([Options::expiry(iss)].iter())?;
-        }
-        // Attach expiry if provided
-        if let Some(exp) = options.expiry {
-            b = b.public_options([Options::expiry(exp)].iter())?;
-        }
+        // Attach standard options
+        let standard = StandardOptions {
+            pub_key: Some(self.public_key.clone()),
+            ..self.standard_options(options.issued, options.expiry)
+        };
+        b = b.standard_options(&standard)?;
         
         // Then finally attach public options
         let b = b.public_options(self.public_options.iter())?;
 
-        // Sign generated object
-        let c = self.sign(b)?;
-        
-        // Return container and encode
-        Ok((c.len(), c))
+        Ok((b, version))
     }
 
-    /// Secondary generates a secondary page using this service to be attached to / stored at the provided service ID
-    fn publish_secondary<T: MutableData>(
+    /// Build an unsigned secondary page for the provided service ID
+    fn build_secondary<T: MutableData>(
         &mut self,
         id: &Id,
         options: SecondaryOptions,
         buff: T,
-    ) -> Result<(usize, Container<T>), Error> {
+    ) -> Result<Builder<SetPublicOptions, T>, Error> {
         // Set secondary page flags
         let mut flags = Flags::SECONDARY;
         if self.encrypted {
@@ -291,7 +633,7 @@ impl <B> Publisher for Service<B>
 
         // Build object
         let b = Builder::new(buff)
-            .header(&header)
+            .header(&header)?
             .id(id);
 
         let b = match options.body {
@@ -304,95 +646,82 @@ impl <B> Publisher for Service<B>
         // Apply internal encryption if enabled
         let b = self.encrypt(b)?;
 
-        // Generate and append public options
-        let mut b = b.public_options([
-            Options::peer_id(self.id.clone()),
-        ].iter())?;
+        // Attach standard options
+        let standard = StandardOptions {
+            peer_id: Some(self.id.clone()),
+            ..self.standard_options(options.issued, options.expiry)
+        };
+        let b = b.standard_options(&standard)?;
 
-        // Attach issued if provided
-        if let Some(iss) = options.issued {
-            b = b.public_options([Options::issued(iss)].iter())?;
-        }
-        // Attach expiry if provided
-        if let Some(exp) = options.expiry {
-            b = b.public_options([Options::expiry(exp)].iter())?;
-        }
-        // Attach last sig if available
-        if let Some(last) = &self.last_sig {
-            b = b.public_options([Options::prev_sig(last)].iter())?;
-        }
         // Then finally attach public options
         let b = b.public_options(options.public_options.iter())?;
 
-        // Sign generated object
-        let c = self.sign(b)?;
-        
-        Ok((c.len(), c))
+        Ok(b)
     }
+}
 
-    fn publish_data<D: DataBody, T: MutableData>(
+impl <B: PageBody> Service<B> {
+    /// Build an unsigned data object using the provided body writer, returning the builder and new data index
+    fn build_data<T: MutableData>(
         &mut self,
-        options: DataOptions<D>,
+        options: DataOptions,
         buff: T,
-    ) -> Result<(usize, Container<T>), Error> {
+        body: impl FnOnce(Builder<Init, T>) -> Result<Builder<SetPrivateOptions, T>, Error>,
+    ) -> Result<(Builder<SetPublicOptions, T>, u16), Error> {
         let mut flags = Flags::default();
         if self.encrypted {
             flags |= Flags::ENCRYPTED;
         }
 
-        self.data_index = self.data_index.wrapping_add(1);
+        // Index is only updated once the object is successfully encoded
+        let data_index = self.data_index.wrapping_add(1);
 
         let header = Header {
             application_id: self.application_id,
             kind: Kind::data(options.data_kind),
             flags,
-            index: self.data_index,
+            index: data_index,
             ..Default::default()
         };
 
         // Build object
         let b = Builder::new(buff)
-            .header(&header)
+            .header(&header)?
             .id(&self.id());
 
-        let b = match options.body {
-            Some(body) => b.body(body).map_err(|e| {
-                error!("Failed to encode data body: {:?}", e);
-                Error::EncodeFailed
-            })?,
-            None => b.with_body(|_b| Ok(0) )?,
-        };
+        let b = body(b)?;
     
         let b = b.private_options(options.private_options)?;
 
         // Apply internal encryption if enabled
         let mut b = self.encrypt(b)?;
 
-        // Generate and append public options
-
-        // Attach issued if provided
-        if let Some(iss) = options.issued {
-            b = b.public_options(&[Options::issued(iss)])?;
-        }
-
-        // Attach last sig if available
-        if let Some(last) = &self.last_sig {
-            b = b.public_options(&[Options::prev_sig(last)])?;
+        // Attach standard options, omitting the last signature where requested
+        let mut standard = self.standard_options(options.issued, None);
+        if options.no_last_sig {
+            standard.prev_sig = None;
         }
+        b = b.standard_options(&standard)?;
 
         // Attach public options
         let b = b.public_options(options.public_options)?;
 
-        // Sign generated object
-        let c = self.sign(b)?;
-        
-        // Return container and encoded length
-        Ok((c.len(), c))
+        Ok((b, data_index))
     }
 }
 
 impl <B: PageBody> Service<B> {
 
+    /// Assemble standard public options from service state, with the provided issued and expiry times
+    pub(super) fn standard_options(&self, issued: Option<DateTime>, expiry: Option<DateTime>) -> StandardOptions {
+        StandardOptions {
+            prev_sig: self.last_sig.clone(),
+            issued,
+            expiry,
+            ..Default::default()
+        }
+    }
+
     /// Encrypt the data and private options in the provided container builder
     pub(super) fn encrypt<T: MutableData>(&mut self, b: Builder<Encrypt, T>) -> Result<Builder<SetPublicOptions, T>, Error> {
 
@@ -406,17 +735,32 @@ impl <B: PageBody> Service<B> {
         Ok(b)
     }
 
+    /// Fetch the service private key for signing, see [`Publisher::publish_primary_signed`]
+    /// and related methods for signing with externally held keys
+    fn key_signer(&self) -> Result<PrivateKey, Error> {
+        match &self.private_key {
+            Some(pk) => Ok(pk.clone()),
+            None => {
+                error!("No public key for object signing");
+                Err(Error::NoPrivateKey)
+            }
+        }
+    }
+
     /// Sign and finalise a container builder
     pub(super) fn sign<T: MutableData>(&mut self, b: Builder<SetPublicOptions, T> ) -> Result<Container<T>, Error> {
+        let mut signer = self.key_signer()?;
+        self.sign_with(b, &mut signer)
+    }
+
+    /// Sign and finalise a container builder using the provided signer
+    pub(super) fn sign_with<S: Signer, T: MutableData>(&mut self, mut b: Builder<SetPublicOptions, T>, signer: &mut S) -> Result<Container<T>, Error> {
+
+        // Apply interceptors
+        self.intercept(&mut b)?;
 
         // Sign generated object
-        let c = match &self.private_key {
-            Some(pk) => b.sign_pk(pk)?,
-            None => {
-                error!("No public key for object signing");
-                return Err(Error::NoPrivateKey);
-            }
-        };
+        let c = b.sign_with(signer)?;
 
         // Update last signature
         self.last_sig = Some(c.signature());
@@ -424,6 +768,17 @@ impl <B: PageBody> Service<B> {
         // Return signed container
         Ok(c)
     }
+
+    /// Sign and finalise a container builder using the provided async signer
+    #[cfg(feature = "async")]
+    pub(super) async fn sign_with_async<S: AsyncSigner, T: MutableData>(&mut self, mut b: Builder<SetPublicOptions, T>, signer: &mut S) -> Result<Container<T>, Error> {
+        self.intercept(&mut b)?;
+
+        let c = b.sign_with_async(signer).await?;
+        self.last_sig = Some(c.signature());
+
+        Ok(c)
+    }
 }
 
 #[cfg(test)]
@@ -492,4 +847,182 @@ mod test {
         assert_eq!(d2.public_options_iter().prev_sig(), Some(d1.signature()));
         assert_eq!(svc.last_sig, Some(d2.signature()));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_publish_standard_options() {
+        let mut svc = init_service();
+
+        // Primary pages carry public key, issued and expiry times
+        let opts = PrimaryOptions::default();
+        let (_n, p) = svc.publish_primary_buff(opts.clone()).expect("Failed to publish primary page");
+        let o = p.public_options_iter();
+        assert_eq!(o.pub_key(), Some(svc.public_key()));
+        assert_eq!(o.issued().map(|t| t.as_secs()), opts.issued.map(|t| t.as_secs()));
+        assert_eq!(o.expiry().map(|t| t.as_secs()), opts.expiry.map(|t| t.as_secs()));
+
+        // Data objects omit the previous signature where requested
+        let opts = DataOptions::<&[u8]>{ no_last_sig: true, ..Default::default() };
+        let (_n, d) = svc.publish_data_buff(opts).expect("Failed to publish data object");
+        assert_eq!(d.public_options_iter().prev_sig(), None);
+
+        // Expiry must follow issued time
+        let opts = PrimaryOptions{ issued: default_issued(), expiry: default_issued() };
+        assert_eq!(svc.publish_primary_buff(opts).err(), Some(Error::InvalidOption));
+
+        // Times may be provided by an application clock
+        let clock = DateTime::from_secs(10_000);
+        let (_n, p) = svc.publish_primary_buff(PrimaryOptions::from_clock(&clock)).expect("Failed to publish primary page");
+        let o = p.public_options_iter();
+        assert_eq!(o.issued(), Some(clock));
+        assert_eq!(o.expiry(), Some(clock + DEFAULT_PAGE_EXPIRY));
+
+        let (_n, d) = svc.publish_data_buff(DataOptions::<&[u8]>::from_clock(&clock)).expect("Failed to publish data object");
+        assert_eq!(d.public_options_iter().issued(), Some(clock));
+
+        let opts = SecondaryOptions::from_clock(&clock);
+        assert_eq!((opts.issued, opts.expiry), (Some(clock), Some(clock + DEFAULT_PAGE_EXPIRY)));
+    }
+
+    #[test]
+    fn test_publish_data_stream() {
+        let mut svc = init_service();
+        let keys = svc.keys();
+
+        // Produce body in small chunks
+        let body: Vec<u8> = (0..100u8).collect();
+        let mut chunks = body.chunks(7);
+        let producer = |buff: &mut [u8]| {
+            let c = chunks.next().unwrap_or(&[]);
+            buff[..c.len()].copy_from_slice(c);
+            Ok(c.len())
+        };
+
+        let (_n, d) = svc.publish_data_stream(Default::default(), body.len(), producer, vec![0u8; 1024])
+            .expect("Failed to publish data object");
+        assert_eq!(d.header().index(), 1);
+        assert_eq!(svc.last_sig, Some(d.signature()));
+
+        // Parse and decrypt object to check body
+        let mut c = Container::parse(d.raw().to_vec(), &keys).expect("Failed to parse data object");
+        c.decrypt(keys.sec_key.as_ref().unwrap()).expect("Failed to decrypt data object");
+        assert_eq!(c.body_raw(), &body[..]);
+
+        // Short streams are rejected
+        let producer = |_buff: &mut [u8]| Ok(0);
+        assert_eq!(
+            svc.publish_data_stream(Default::default(), 10, producer, vec![0u8; 1024]).map(|_| ()),
+            Err(Error::InvalidPageLength)
+        );
+    }
+
+    /// Signer holding the service key outside of the service, counting signing operations
+    struct ExternalSigner {
+        key: PrivateKey,
+        count: usize,
+    }
+
+    impl Signer for ExternalSigner {
+        type Error = Error;
+
+        fn sign(&mut self, id: &Id, data: &[u8]) -> Result<Signature, Error> {
+            self.count += 1;
+            self.key.sign(id, data)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[async_trait::async_trait]
+    impl AsyncSigner for ExternalSigner {
+        type Error = Error;
+
+        async fn sign(&mut self, id: &Id, data: &[u8]) -> Result<Signature, Error> {
+            Signer::sign(self, id, data)
+        }
+    }
+
+    #[test]
+    fn test_publish_replica() {
+        let (mut svc, mut peer) = crate::test_utils::service_pair();
+        let qos = ReplicaQos{ uptime: 3600, capacity: 1024, latency: 50 };
+
+        // Replica pages are published by the replicating peer for the replicated service
+        let (_n, p) = peer.publish_replica(&svc.id(), &qos, Default::default(), vec![0u8; 1024])
+            .expect("Failed to publish replica page");
+
+        let r = svc.validate_replica(&p).expect("Failed to validate replica page");
+        assert_eq!(r.service_id, svc.id());
+        assert_eq!(r.peer_id, peer.id());
+        assert_eq!(r.qos, qos);
+
+        // Replicas for other services and other secondary pages are rejected
+        assert_eq!(peer.validate_replica(&p).err(), Some(Error::UnexpectedServiceId));
+
+        let (_n, s) = peer.publish_secondary(&svc.id(), Default::default(), vec![0u8; 1024]).unwrap();
+        assert_eq!(svc.validate_replica(&s).err(), Some(Error::UnexpectedPageKind));
+
+        // As are expired replication agreements
+        let opts = SecondaryOptions{
+            issued: Some(DateTime::from_secs(1_000)),
+            expiry: Some(DateTime::from_secs(2_000)),
+            ..Default::default()
+        };
+        let (_n, p) = peer.publish_replica(&svc.id(), &qos, opts, vec![0u8; 1024]).unwrap();
+        assert_eq!(svc.validate_replica(&p).err(), Some(Error::PageExpired));
+
+        // Expiry is checked against the provided clock
+        let policy = crate::wire::ExpiryPolicy::default();
+        assert!(svc.validate_replica_with(&p, &policy, &DateTime::from_secs(1_500)).is_ok());
+        assert_eq!(svc.validate_replica_with(&p, &policy, &DateTime::from_secs(2_001)).err(), Some(Error::PageExpired));
+    }
+
+    #[test]
+    fn test_publish_external_signer() {
+        let mut svc = init_service();
+        let mut signer = ExternalSigner{ key: svc.private_key().unwrap(), count: 0 };
+        svc.set_private_key(None);
+
+        // Publishing without a private key or signer fails
+        assert_eq!(svc.publish_primary_buff(Default::default()).map(|_| ()), Err(Error::NoPrivateKey));
+
+        let (_n, p) = svc.publish_primary_signed(Default::default(), &mut signer, vec![0u8; 1024]).unwrap();
+        let (_n, d) = svc.publish_data_signed(DataOptions::<&[u8]>::default(), &mut signer, vec![0u8; 1024]).unwrap();
+        assert_eq!(signer.count, 2);
+
+        // Objects verify against the service keys and are chained as usual
+        let keys = svc.keys();
+        Container::parse(p.raw().to_vec(), &keys).unwrap();
+        Container::parse(d.raw().to_vec(), &keys).unwrap();
+        assert_eq!(d.public_options_iter().prev_sig(), Some(p.signature()));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn test_publish_async_signer() {
+        use std::{future::Future, sync::Arc, task::{Context, Poll, Wake}};
+
+        // Signers complete immediately so a single poll is sufficient
+        struct Noop;
+        impl Wake for Noop {
+            fn wake(self: Arc<Self>) {}
+        }
+        fn block_on<F: Future>(f: F) -> F::Output {
+            let waker = Arc::new(Noop).into();
+            match Box::pin(f).as_mut().poll(&mut Context::from_waker(&waker)) {
+                Poll::Ready(v) => v,
+                Poll::Pending => panic!("Signer future pending"),
+            }
+        }
+
+        let mut svc = init_service();
+        let mut signer = ExternalSigner{ key: svc.private_key().unwrap(), count: 0 };
+        svc.set_private_key(None);
+
+        let (_n, p) = block_on(svc.publish_primary_async(Default::default(), &mut signer, vec![0u8; 1024])).unwrap();
+        let (_n, d) = block_on(svc.publish_data_async(DataOptions::<&[u8]>::default(), &mut signer, vec![0u8; 1024])).unwrap();
+
+        let keys = svc.keys();
+        Container::parse(p.raw().to_vec(), &keys).unwrap();
+        Container::parse(d.raw().to_vec(), &keys).unwrap();
+        assert_eq!(d.public_options_iter().prev_sig(), Some(p.signature()));
+    }
+}
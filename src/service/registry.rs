@@ -1,21 +1,33 @@
 
+use core::convert::TryFrom;
 use core::ops::Add;
 
 use crate::base::PageBody;
-use crate::options::Options;
+use crate::options::{Coordinates, GeoHash, Options};
 
 use crate::error::Error;
 use crate::prelude::{Header};
-use crate::types::{Id, Kind, PageKind, Flags, Queryable, DateTime, Signature, MutableData};
+use crate::options::Filters;
+use crate::keys::Keys;
+use crate::types::{Id, Kind, PageKind, Flags, Queryable, DateTime, Signature, SecretKey, MutableData, ImmutableData};
 use crate::wire::{Builder, Container};
-use crate::crypto::{Crypto, Hash as _};
+use crate::crypto::{Crypto, Hash as _, PubKey as _};
 
-use super::Service;
+use super::{NsConfig, Service};
 
 pub trait Registry {
     /// Generate ID for registry lookup
     fn resolve(&self, q: impl Queryable) -> Result<Id, Error>;
 
+    /// Read the name service configuration from the registry primary page options
+    fn ns_config(&self) -> Result<NsConfig, Error>;
+
+    /// Resolve the link from a tertiary page published by this registry,
+    /// decrypting the page body for private namespaces.
+    ///
+    /// Note this does not verify the page signature, pages should be validated on receipt.
+    fn resolve_link<T: ImmutableData>(&self, page: &Container<T>) -> Result<TertiaryLink, Error>;
+
     /// Generates a tertiary page for the provided service ID and options
     fn publish_tertiary<Q: Queryable, T: MutableData> (
         &mut self,
@@ -25,6 +37,57 @@ pub trait Registry {
         buff: T
     ) -> Result<(usize, Container<T>), Error>;
 
+    /// Generates a tertiary page linking the provided query to a service ID
+    fn publish_service_link<Q: Queryable, T: MutableData>(
+        &mut self,
+        target_id: Id,
+        q: Q,
+        opts: TertiaryOptions,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        self.publish_tertiary(TertiaryLink::Service(target_id), opts, q, buff)
+    }
+
+    /// Generates a tertiary page linking the provided query to a block (data object) signature
+    fn publish_block_link<Q: Queryable, T: MutableData>(
+        &mut self,
+        sig: Signature,
+        q: Q,
+        opts: TertiaryOptions,
+        buff: T,
+    ) -> Result<(usize, Container<T>), Error> {
+        self.publish_tertiary(TertiaryLink::Block(sig), opts, q, buff)
+    }
+
+    /// Verify a tertiary page published by this registry matches the provided query,
+    /// returning the resolved link.
+    ///
+    /// As with [`Registry::resolve_link`] this does not verify the page signature.
+    fn verify_link<Q: Queryable, T: ImmutableData>(&self, page: &Container<T>, q: Q) -> Result<TertiaryLink, Error> {
+        // Check the page ID matches the TID for the query
+        if page.id() != self.resolve(q)? {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        self.resolve_link(page)
+    }
+
+    /// Resolve a service ID from a tertiary page published by this registry for the provided query
+    fn resolve_service<Q: Queryable, T: ImmutableData>(&self, page: &Container<T>, q: Q) -> Result<Id, Error> {
+        match self.verify_link(page, q)? {
+            TertiaryLink::Service(id) => Ok(id),
+            TertiaryLink::Block(_) => Err(Error::UnexpectedPageKind),
+        }
+    }
+
+    /// Resolve a block signature from a tertiary page published by this registry for the provided query
+    fn resolve_block<Q: Queryable, T: ImmutableData>(&self, page: &Container<T>, q: Q) -> Result<Signature, Error> {
+        match self.verify_link(page, q)? {
+            TertiaryLink::Block(sig) => Ok(sig),
+            TertiaryLink::Service(_) => Err(Error::UnexpectedPageKind),
+        }
+    }
+
     /// Generates a tertiary page for the provided service ID and options
     fn publish_tertiary_buff<const N: usize, Q: Queryable> (
         &mut self,
@@ -43,6 +106,17 @@ pub enum TertiaryLink {
     Block(Signature),
 }
 
+impl TertiaryLink {
+    /// Decode a tertiary link from a (decrypted) page body
+    pub fn decode(kind: Kind, body: &[u8]) -> Result<Self, Error> {
+        match PageKind::try_from(kind.index()) {
+            Ok(PageKind::ServiceLink) if kind.is_page() => Ok(Self::Service(Id::try_from(body)?)),
+            Ok(PageKind::BlockLink) if kind.is_page() => Ok(Self::Block(Signature::try_from(body)?)),
+            _ => Err(Error::InvalidPageKind),
+        }
+    }
+}
+
 impl From<Id> for TertiaryLink {
     fn from(id: Id) -> Self {
         Self::Service(id)
@@ -78,6 +152,105 @@ impl Default for TertiaryOptions {
     }
 }
 
+/// Resolver computes tertiary IDs for queries against a name service and verifies
+/// returned tertiary pages, for consumers holding only the name service keys.
+///
+/// For private name services the keys must include the namespace secret key.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Resolver {
+    id: Id,
+    keys: Keys,
+}
+
+impl Resolver {
+    /// Create a resolver for the name service with the provided keys
+    pub fn new(keys: Keys) -> Result<Self, Error> {
+        let id = match (&keys.pub_key, keys.id()) {
+            (Some(_), Some(id)) => id,
+            _ => return Err(Error::NoPublicKey),
+        };
+
+        Ok(Self { id, keys })
+    }
+
+    /// Fetch the name service ID
+    pub fn id(&self) -> Id {
+        self.id.clone()
+    }
+
+    /// Compute the tertiary ID for the provided query
+    pub fn tid(&self, q: impl Queryable) -> Result<Id, Error> {
+        match Crypto::hash_tid(self.id.clone(), &self.keys, q) {
+            Ok(tid) => Ok(Id::from(tid.as_bytes())),
+            Err(_) => Err(Error::CryptoError),
+        }
+    }
+
+    /// Compute the tertiary IDs for geohash cells covering the bounding box between the
+    /// south-west and north-east corners, see [`GeoHash::covering`](crate::options::GeoHash::covering)
+    #[cfg(feature = "alloc")]
+    pub fn tids_within(&self, sw: &Coordinates, ne: &Coordinates, precision: u8, max: usize) -> Result<alloc::vec::Vec<Id>, Error> {
+        GeoHash::covering(sw, ne, precision, max)?
+            .into_iter()
+            .map(|g| self.tid(g))
+            .collect()
+    }
+
+    /// Verify a tertiary page matches the provided query and is signed by the name service,
+    /// returning the resolved link
+    pub fn verify<T: ImmutableData>(&self, page: &Container<T>, q: impl Queryable) -> Result<TertiaryLink, Error> {
+        // Check the page ID matches the TID for the query
+        if page.id() != self.tid(q)? {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        // Check the page is signed by the name service
+        let pub_key = self.keys.pub_key.as_ref().ok_or(Error::NoPublicKey)?;
+        match Crypto::pk_verify(pub_key, &page.signature(), page.signed()) {
+            Ok(true) => (),
+            _ => return Err(Error::InvalidSignature),
+        }
+
+        decode_link(&self.id, self.keys.sec_key.as_ref(), page)
+    }
+}
+
+/// Decode the link from a tertiary page published by the provided name service,
+/// decrypting the page body for private namespaces
+fn decode_link<T: ImmutableData>(ns_id: &Id, sk: Option<&SecretKey>, page: &Container<T>) -> Result<TertiaryLink, Error> {
+    let header = page.header();
+    if !header.flags().contains(Flags::TERTIARY) {
+        return Err(Error::UnexpectedPageType);
+    }
+
+    // Check the page was published by this registry
+    match page.public_options_iter().peer_id() {
+        Some(peer_id) if &peer_id == ns_id => (),
+        Some(_) => return Err(Error::UnexpectedPeerId),
+        None => return Err(Error::NoPeerId),
+    }
+
+    // Public pages can be decoded directly
+    if !page.encrypted() {
+        return TertiaryLink::decode(header.kind(), page.body_raw());
+    }
+
+    // Private pages require the namespace secret key
+    let sk = match sk {
+        Some(sk) => sk,
+        None => return Err(Error::NoSecretKey),
+    };
+
+    let mut buff = [0u8; 256];
+    if page.cyphertext().len() > buff.len() {
+        return Err(Error::BufferLength);
+    }
+
+    let (body, _private_opts) = page.decrypt_to(sk, &mut buff)?;
+
+    TertiaryLink::decode(header.kind(), body)
+}
+
 impl <B: PageBody> Registry for Service<B> {
     /// Resolve an ID for a given hash
     fn resolve(&self, q: impl Queryable) -> Result<Id, Error>{
@@ -88,6 +261,14 @@ impl <B: PageBody> Registry for Service<B> {
         }
     }
 
+    fn ns_config(&self) -> Result<NsConfig, Error> {
+        NsConfig::from_service(self)
+    }
+
+    fn resolve_link<T: ImmutableData>(&self, page: &Container<T>) -> Result<TertiaryLink, Error> {
+        decode_link(&self.id(), self.secret_key.as_ref(), page)
+    }
+
     fn publish_tertiary<Q: Queryable, T: MutableData>(
         &mut self,
         link: TertiaryLink,
@@ -123,7 +304,7 @@ impl <B: PageBody> Registry for Service<B> {
 
         // TODO: should service link be in private options..?
         let b = Builder::new(buff)
-            .header(&header)
+            .header(&header)?
             .id(&tid)
             .body(body)?
             .private_options(&[])?;
@@ -149,7 +330,7 @@ impl <B: PageBody> Registry for Service<B> {
 mod test {
     use crate::base::Empty;
     use crate::{prelude::*, service::Publisher};
-    use crate::options::{Options, Filters};
+    use crate::options::{Options, Filters, DEFAULT_GEOHASH_PRECISION};
 
     use super::*;
 
@@ -169,20 +350,67 @@ mod test {
         let tid_name = Registry::resolve(&r, &Options::name(opt_name)).unwrap();
         assert_eq!(&p1.id(), &tid_name);
 
+        // Lookups are case insensitive
+        let tid_upper = Registry::resolve(&r, &Options::name("SomeThing")).unwrap();
+        assert_eq!(&tid_upper, &tid_name);
+
         // Check link to registry
         let opts: Vec<_> = p1.public_options_iter().collect();
         let pid = Filters::peer_id(&opts.iter()).unwrap();
         assert_eq!(pid, r.id());
 
         // Check link to service
-        #[cfg(todo)]
-        {
-        let pi = match p1.info() {
-            PageInfo::Tertiary(t) => Some(t),
-            _ => None,
-        }.unwrap();
-        assert_eq!(pi.target_id, c.id());
-    }
+        assert_eq!(r.resolve_link(&p1), Ok(TertiaryLink::Service(c.id())));
+
+        // Private links are opaque without the namespace secret key
+        match (r.encrypted(), p1.info()) {
+            (false, Ok(PageInfo::ServiceLink(l))) => assert_eq!(l.target_id, c.id()),
+            (true, Ok(PageInfo::Private(p))) => assert_ne!(p.body, c.id().as_ref()),
+            i => panic!("Unexpected page info: {:?}", i),
+        }
+
+        if r.encrypted() {
+            let mut o = r.clone();
+            o.set_secret_key(None);
+            assert_eq!(o.resolve_link(&p1), Err(Error::NoSecretKey));
+        }
+
+        // Links must be resolved by the publishing registry
+        assert_eq!(c.resolve_link(&p1), Err(Error::UnexpectedPeerId));
+
+        // Links are verified against the query used to publish them
+        let (_n, p2) = r.publish_service_link(c.id(), &Options::name("other"), TertiaryOptions::default(), vec![0u8; 512]).unwrap();
+        assert_eq!(r.resolve_service(&p2, &Options::name("other")), Ok(c.id()));
+        assert_eq!(r.resolve_service(&p2, &Options::name(opt_name)), Err(Error::UnexpectedServiceId));
+        assert_eq!(r.resolve_block(&p2, &Options::name("other")), Err(Error::UnexpectedPageKind));
+
+        let sig = Signature::from([0xab; 64]);
+        let (_n, p3) = r.publish_block_link(sig.clone(), &Options::name("block"), TertiaryOptions::default(), vec![0u8; 512]).unwrap();
+        assert!(p3.header().flags().contains(Flags::TERTIARY));
+        assert_eq!(r.resolve_block(&p3, &Options::name("block")), Ok(sig));
+
+        // Resolvers verify pages using only the name service keys
+        let mut keys = Keys::new(r.public_key());
+        keys.sec_key = r.secret_key();
+        let resolver = Resolver::new(keys).unwrap();
+        assert_eq!(resolver.tid(&Options::name(opt_name)), Ok(tid_name));
+        assert_eq!(resolver.verify(&p1, &Options::name(opt_name)), Ok(TertiaryLink::Service(c.id())));
+        assert_eq!(resolver.verify(&p1, &Options::name("other")), Err(Error::UnexpectedServiceId));
+
+        // Location links are found by querying cells covering an area
+        let loc = Coordinates{ lat: 51.5007, lng: -0.1246, alt: 0.0 };
+        let (_n, p4) = r.publish_service_link(c.id(), &Options::Coord(loc.clone()), TertiaryOptions::default(), vec![0u8; 512]).unwrap();
+        let (sw, ne) = (Coordinates{ lat: 51.48, lng: -0.15, alt: 0.0 }, Coordinates{ lat: 51.52, lng: -0.10, alt: 0.0 });
+        let tids = resolver.tids_within(&sw, &ne, DEFAULT_GEOHASH_PRECISION, 16).unwrap();
+        assert!(tids.contains(&p4.id()));
+        assert_eq!(resolver.verify(&p4, loc), Ok(TertiaryLink::Service(c.id())));
+
+        // Pages signed by other services are rejected
+        let mut forged = p1.raw().to_vec();
+        let n = forged.len();
+        forged[n - 1] ^= 0xff;
+        let (forged, _n) = Container::from(forged);
+        assert_eq!(resolver.verify(&forged, &Options::name(opt_name)), Err(Error::InvalidSignature));
     }
 
     #[test]
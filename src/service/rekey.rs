@@ -0,0 +1,237 @@
+//! Re-encryption helpers for secret key rotation.
+//!
+//! When a service rotates its secret key, existing encrypted pages may be re-encrypted by the
+//! owner with [`Service::re_encrypt_page`]. This produces a new page with the same kind, flags and
+//! public options, encrypted under the current secret key and signed by the service, carrying an
+//! [`Options::ReEncrypted`] reference to the signature of the original page. Re-encrypted objects
+//! are published at the next service version (or data index) and chained from the last published
+//! signature, so they supersede rather than fork the original.
+//!
+//! Replicas holding the original page may then link the two with [`Service::validate_re_encrypted`].
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::base::{Header, PageBody};
+use crate::crypto::{Crypto, PubKey as _};
+use crate::error::Error;
+use crate::options::Options;
+use crate::types::*;
+use crate::wire::{Builder, Container};
+
+use super::Service;
+
+impl <B: PageBody> Service<B> {
+    /// Re-encrypt an object published by this service from a previous secret key to the
+    /// current service secret key, signing the re-encrypted object into the provided buffer.
+    ///
+    /// The re-encrypted object is published at the next page version (or data index) of the service,
+    /// so only the most recently published object may be re-encrypted. This returns
+    /// [`Error::InvalidServiceVersion`] for earlier objects and [`Error::NotEncrypted`] where the
+    /// provided object is not encrypted.
+    #[cfg(feature = "alloc")]
+    pub fn re_encrypt_page<T: ImmutableData, U: MutableData>(
+        &mut self,
+        object: &Container<T>,
+        previous_key: &SecretKey,
+        buff: U,
+    ) -> Result<(usize, Container<U>), Error> {
+        let (private_key, secret_key) = match (&self.private_key, &self.secret_key) {
+            (Some(p), Some(s)) => (p, s),
+            (None, _) => return Err(Error::NoPrivateKey),
+            (_, None) => return Err(Error::NoSecretKey),
+        };
+
+        if object.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        if !object.encrypted() {
+            return Err(Error::NotEncrypted);
+        }
+
+        // Re-encrypting earlier objects would supersede the latest with stale content
+        if self.last_sig.as_ref() != Some(&object.signature()) {
+            return Err(Error::InvalidServiceVersion);
+        }
+
+        // Decrypt original body and private options
+        let mut plaintext = vec![0u8; object.cyphertext().len()];
+        let (body, private_options) = object.decrypt_to(previous_key, &mut plaintext)
+            .map_err(|_| Error::SecretKeyMismatch)?;
+
+        // Rebuild object at the next index, re-encrypting under the current key
+        let is_page = object.header().kind().is_page();
+        let index = match is_page {
+            true => self.version.wrapping_add(1),
+            false => self.data_index.wrapping_add(1),
+        };
+        let header = Header{ index, ..Header::from(&object.header()) };
+        let b = Builder::new(buff)
+            .header(&header)?
+            .id(&self.id)
+            .with_body(|d| {
+                if d.len() < body.len() {
+                    return Err(Error::BufferLength);
+                }
+                d[..body.len()].copy_from_slice(body);
+                Ok(body.len())
+            })?
+            .private_options_raw(private_options)?
+            .encrypt(secret_key)?;
+
+        // Copy public options, chaining from the last signature and referencing the original object
+        let public_options: Vec<_> = object.public_options_iter()
+            .filter(|o| !matches!(o, Options::ReEncrypted(_) | Options::PrevSig(_)))
+            .chain(self.last_sig.as_ref().map(Options::prev_sig))
+            .chain(core::iter::once(Options::re_encrypted(object.signature())))
+            .collect();
+        let b = b.public_options(public_options.iter())?;
+
+        let c = b.sign_pk(private_key)?;
+
+        // Update index and last signature
+        match is_page {
+            true => self.version = index,
+            false => self.data_index = index,
+        }
+        self.last_sig = Some(c.signature());
+
+        Ok((c.len(), c))
+    }
+
+    /// Validate a re-encrypted object against the original object, checking both objects are
+    /// signed by this service, the re-encrypted object references the original signature and
+    /// supersedes its index, and other header fields and (non-signature) public options are unchanged.
+    pub fn validate_re_encrypted<T: ImmutableData, U: ImmutableData>(
+        &self,
+        original: &Container<T>,
+        updated: &Container<U>,
+    ) -> Result<(), Error> {
+        let verify = |signature: &Signature, data: &[u8]| match Crypto::pk_verify(&self.public_key, signature, data) {
+            Ok(true) => Ok(()),
+            _ => Err(Error::InvalidSignature),
+        };
+        verify(&original.signature(), original.signed())?;
+        verify(&updated.signature(), updated.signed())?;
+
+        if original.id() != self.id || updated.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        // Check the re-encrypted object references the original
+        let reference = updated.public_options_iter().find_map(|o| match o {
+            Options::ReEncrypted(s) => Some(s),
+            _ => None,
+        });
+        if reference != Some(original.signature()) {
+            return Err(Error::ReEncryptionMismatch);
+        }
+
+        // Check header fields are unchanged
+        let (a, b) = (original.header(), updated.header());
+        if a.application_id() != b.application_id() || a.kind() != b.kind()
+                || a.flags() != b.flags() || b.index() <= a.index()
+                || a.data_len() != b.data_len() || a.private_options_len() != b.private_options_len() {
+            return Err(Error::ReEncryptionMismatch);
+        }
+
+        // And public options (excluding re-encryption and chain references)
+        let filter = |o: &Options| !matches!(o, Options::ReEncrypted(_) | Options::PrevSig(_));
+        if !crate::options::unordered_eq(original.public_options_iter().filter(filter), updated.public_options_iter().filter(filter)) {
+            return Err(Error::ReEncryptionMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::SecKey as _;
+    use crate::service::{Publisher, PrimaryOptions, Subscriber, ServiceBuilder};
+
+    #[test]
+    fn re_encrypt_rotated_page() {
+        let mut s = ServiceBuilder::<Vec<u8>>::generic()
+            .public_options(vec![Options::name("rotating")])
+            .body(vec![0xaa; 16])
+            .encrypt()
+            .build()
+            .unwrap();
+        let old_key = s.secret_key().unwrap();
+
+        let (_n, p) = s.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        let mut replica = Service::<Vec<u8>>::load(&p).unwrap();
+
+        // Rotate secret key and re-encrypt the existing page
+        let new_key = Crypto::new_sk().unwrap();
+        s.set_secret_key(Some(new_key.clone()));
+
+        assert_eq!(s.re_encrypt_page(&p, &new_key, vec![0u8; 1024]).err(), Some(Error::SecretKeyMismatch));
+        let (_n, r) = s.re_encrypt_page(&p, &old_key, vec![0u8; 1024]).unwrap();
+
+        // Re-encrypted pages decrypt under the new key only
+        let mut a = vec![0u8; 1024];
+        let mut b = vec![0u8; 1024];
+        assert_eq!(r.decrypt_to(&new_key, &mut a).unwrap(), p.decrypt_to(&old_key, &mut b).unwrap());
+        assert!(r.decrypt_to(&old_key, &mut a).is_err());
+
+        // Replicas link the re-encrypted page to the original
+        assert_eq!(replica.validate_re_encrypted(&p, &r), Ok(()));
+
+        // Re-encrypted pages supersede the original without forking
+        assert_eq!(r.header().index(), p.header().index() + 1);
+        assert_eq!(r.public_options_iter().prev_sig(), Some(p.signature()));
+        assert_eq!(replica.apply_primary(&r), Ok(true));
+
+        // Unrelated pages are rejected
+        let (_n, p2) = s.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        assert_eq!(replica.validate_re_encrypted(&p2, &r), Err(Error::ReEncryptionMismatch));
+
+        // Only the latest page may be re-encrypted
+        assert_eq!(s.re_encrypt_page(&p, &old_key, vec![0u8; 1024]).err(), Some(Error::InvalidServiceVersion));
+        assert_eq!(s.re_encrypt_page(&r, &new_key, vec![0u8; 1024]).err(), Some(Error::InvalidServiceVersion));
+
+        // Unencrypted objects are rejected
+        let mut c = ServiceBuilder::<Vec<u8>>::generic().secret_key(old_key.clone()).build().unwrap();
+        let (_n, p3) = c.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        assert_eq!(c.re_encrypt_page(&p3, &old_key, vec![0u8; 1024]).err(), Some(Error::NotEncrypted));
+    }
+
+    #[test]
+    fn re_encrypt_invalid_objects() {
+        let init_service = || ServiceBuilder::<Vec<u8>>::generic().encrypt().build().unwrap();
+
+        let mut s = init_service();
+        let old_key = s.secret_key().unwrap();
+        let (_n, p) = s.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        let replica = Service::<Vec<u8>>::load(&p).unwrap();
+
+        // Objects published by other services are rejected
+        let mut other = init_service();
+        let (_n, o) = other.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        assert_eq!(s.re_encrypt_page(&o, &old_key, vec![0u8; 1024]).err(), Some(Error::UnexpectedServiceId));
+        assert_eq!(replica.validate_re_encrypted(&p, &o), Err(Error::InvalidSignature));
+
+        // Re-encryption requires the service private key
+        let mut r = Service::<Vec<u8>>::load(&p).unwrap();
+        assert_eq!(r.re_encrypt_page(&p, &old_key, vec![0u8; 1024]).err(), Some(Error::NoPrivateKey));
+
+        // Data objects are re-encrypted at the next data index
+        let (_n, d) = s.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+        s.set_secret_key(Some(Crypto::new_sk().unwrap()));
+
+        let (_n, e) = s.re_encrypt_page(&d, &old_key, vec![0u8; 1024]).unwrap();
+        assert_eq!(e.header().index(), d.header().index() + 1);
+        assert_eq!(replica.validate_re_encrypted(&d, &e), Ok(()));
+
+        // Tampered objects are rejected
+        let mut t = e.raw().to_vec();
+        let n = t.len();
+        t[n - 1] ^= 0xff;
+        let (t, _) = Container::from(t);
+        assert_eq!(replica.validate_re_encrypted(&d, &t), Err(Error::InvalidSignature));
+    }
+}
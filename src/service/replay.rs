@@ -0,0 +1,153 @@
+//! Replay and rollback protection for subscribers, rejecting replayed or stale objects.
+//!
+//! A [`ReplayState`] tracks the latest page version and data index applied for a service,
+//! along with a window of the `N` most recent object signatures. Objects matching a signature
+//! in the window return [`Error::ReplayedObject`], while objects older than the latest applied
+//! version or index return [`Error::IndexRollback`].
+//!
+//! Data indices restart when a service is updated, so data objects are keyed by `(version, index)`
+//! with data attributed to the latest applied page version. Data applied prior to an update is
+//! then only rejected while its signature remains in the window.
+
+use crate::error::Error;
+use crate::types::*;
+use crate::wire::Container;
+
+/// Compact per-service replay state, with a window of up to `N` recent signatures
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ReplayState<const N: usize> {
+    /// Latest applied primary page version
+    pub version: Option<u16>,
+    /// Latest applied data object, as the page version it was applied under and its data index
+    pub data_index: Option<(u16, u16)>,
+    /// Recently applied object signatures, oldest first
+    window: heapless::Vec<Signature, N>,
+}
+
+impl <const N: usize> Default for ReplayState<N> {
+    fn default() -> Self {
+        Self { version: None, data_index: None, window: heapless::Vec::new() }
+    }
+}
+
+impl <const N: usize> ReplayState<N> {
+    /// Create an empty replay state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether an object has been recently applied
+    pub fn contains(&self, sig: &Signature) -> bool {
+        self.window.iter().any(|s| s == sig)
+    }
+
+    /// Check an object is neither replayed nor older than the latest applied object
+    pub fn check<T: ImmutableData>(&self, object: &Container<T>) -> Result<(), Error> {
+        let header = object.header();
+
+        if self.contains(&object.signature()) {
+            return Err(Error::ReplayedObject);
+        }
+
+        let latest = match header.kind().is_page() {
+            true => self.version,
+            false => self.data_index.filter(|(v, _)| *v == self.data_version()).map(|(_, i)| i),
+        };
+        if matches!(latest, Some(l) if header.index() <= l) {
+            return Err(Error::IndexRollback);
+        }
+
+        Ok(())
+    }
+
+    /// Record an applied object, without checking for replays
+    pub fn record<T: ImmutableData>(&mut self, object: &Container<T>) {
+        let header = object.header();
+
+        if header.kind().is_page() {
+            self.version = Some(header.index());
+        } else {
+            self.data_index = Some((self.data_version(), header.index()));
+        }
+
+        if N == 0 {
+            return;
+        }
+        if self.window.is_full() {
+            self.window.remove(0);
+        }
+        let _ = self.window.push(object.signature());
+    }
+
+    /// Page version data objects are attributed to (zero prior to any page being applied)
+    fn data_version(&self) -> u16 {
+        self.version.unwrap_or(0)
+    }
+
+    /// Check and record an object
+    pub fn apply<T: ImmutableData>(&mut self, object: &Container<T>) -> Result<(), Error> {
+        self.check(object)?;
+        self.record(object);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{DataOptions, Publisher, Service, Subscriber};
+    use crate::test_utils;
+
+    #[test]
+    fn replay_rollback_protection() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let p1 = test_utils::primary_page(&mut s).to_owned();
+
+        let mut objects = vec![];
+        for i in 0..3u8 {
+            let body = [i; 4];
+            let opts = DataOptions{ body: Some(&body[..]), ..Default::default() };
+            let (_n, d) = s.publish_data(opts, vec![0u8; 1024]).unwrap();
+            objects.push(d.to_owned());
+        }
+
+        let mut replica = Service::<Vec<u8>>::load(&p1).unwrap();
+        let mut state = ReplayState::<4>::new();
+
+        replica.validate_fresh(&p1, &mut state).unwrap();
+        replica.validate_fresh(&objects[0], &mut state).unwrap();
+        replica.validate_fresh(&objects[2], &mut state).unwrap();
+
+        // Replayed and stale objects are rejected
+        assert_eq!(replica.validate_fresh(&objects[2], &mut state), Err(Error::ReplayedObject));
+        assert_eq!(replica.validate_fresh(&objects[1], &mut state), Err(Error::IndexRollback));
+        assert_eq!(replica.validate_fresh(&p1, &mut state), Err(Error::ReplayedObject));
+
+        // Service updates restart data indices
+        s.update(|_b, o, _p| o.push(crate::options::Options::name("updated")) ).unwrap();
+        let p2 = test_utils::primary_page(&mut s).to_owned();
+        let mut updated = vec![];
+        for i in 0..2u8 {
+            let opts = DataOptions{ body: Some(&[0xf0 | i; 4][..]), ..Default::default() };
+            let (_n, d) = s.publish_data(opts, vec![0u8; 1024]).unwrap();
+            updated.push(d.to_owned());
+        }
+        assert_eq!(updated[0].header().index(), 1);
+
+        replica.apply_primary(&p2).unwrap();
+        replica.validate_fresh(&p2, &mut state).unwrap();
+        assert_eq!(state.version, Some(p2.header().index()));
+
+        // Data is keyed by page version, with recent data from earlier versions still rejected as replays
+        replica.validate_fresh(&updated[1], &mut state).unwrap();
+        assert_eq!(state.data_index, Some((p2.header().index(), 2)));
+        assert_eq!(replica.validate_fresh(&updated[0], &mut state), Err(Error::IndexRollback));
+        assert_eq!(replica.validate_fresh(&objects[2], &mut state), Err(Error::ReplayedObject));
+
+        // Older pages fall outside the window but are rejected as rollbacks
+        let mut state = ReplayState::<0>::new();
+        state.apply(&p2).unwrap();
+        assert_eq!(state.check(&p1), Err(Error::IndexRollback));
+    }
+}
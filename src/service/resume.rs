@@ -0,0 +1,198 @@
+//! Subscription resumption, allowing subscribers to re-subscribe following a disconnect
+//! without re-fetching the complete data object history of a service.
+//!
+//! Subscribers produce a [`ResumeToken`] from their [`History`] of applied objects with
+//! [`Service::resume_token`], carried in `Subscribe` requests using [`SubscribeFilter::with_resume`].
+//! Publishers check tokens against their own history with [`Service::resume_filter`], pushing only
+//! objects newer than the token where this matches, and falling back to a full resync otherwise
+//! (for example, where the referenced object has been evicted from the publisher history).
+//!
+//! Where the subscriber holds the service secret key tokens are MACed (keyed Blake2b), so publishers
+//! of encrypted services only resume subscriptions for peers able to decrypt service data. Tokens
+//! with missing or invalid MACs are rejected rather than resynced.
+
+use blake2::{Blake2bMac, digest::{Mac, consts::U32}};
+
+use crate::base::PageBody;
+use crate::error::Error;
+use crate::net::SubscribeFilter;
+use crate::options::ResumeToken;
+use crate::types::*;
+
+use super::{History, Retention, Service};
+
+impl <B: PageBody> Service<B> {
+    /// Create a resumption token from the latest object in the provided history of applied objects,
+    /// returning `None` where no objects have been applied
+    pub fn resume_token<const N: usize, R: Retention>(&self, history: &History<N, R>) -> Option<ResumeToken> {
+        let latest = history.latest()?;
+
+        let mut t = ResumeToken { index: latest.index, signature: latest.signature.clone(), mac: None };
+        t.mac = self.resume_token_mac(&t);
+
+        Some(t)
+    }
+
+    /// Compute the MAC for a resumption token, where the service secret key is available
+    fn resume_token_mac(&self, t: &ResumeToken) -> Option<CryptoHash> {
+        let mac = resume_mac(&self.id, self.secret_key.as_ref()?, t).ok()?;
+
+        Some(CryptoHash::from(mac.finalize().into_bytes().as_ref()))
+    }
+
+    /// Check the MAC for a resumption token (in constant time), for services with a secret key
+    fn check_resume_mac(&self, token: &ResumeToken) -> Result<(), Error> {
+        let sk = match &self.secret_key {
+            Some(sk) => sk,
+            None => return Ok(()),
+        };
+
+        let valid = match &token.mac {
+            Some(m) => resume_mac(&self.id, sk, token)?.verify_slice(m.as_ref()).is_ok(),
+            None => false,
+        };
+
+        if !valid {
+            debug!("Missing or invalid resumption token MAC");
+            return Err(Error::InvalidResumeToken);
+        }
+
+        Ok(())
+    }
+
+    /// Check a resumption token against the provided history of published objects.
+    ///
+    /// Returns [`Error::InvalidResumeToken`] where the MAC is missing or invalid (for services with
+    /// a secret key) or the token signature does not match the published object, and
+    /// [`Error::NotFound`] where the object is no longer held in the history.
+    pub fn check_resume<const N: usize, R: Retention>(&self, token: &ResumeToken, history: &History<N, R>) -> Result<(), Error> {
+        self.check_resume_mac(token)?;
+
+        match history.get(token.index) {
+            Some(e) if e.signature == token.signature => Ok(()),
+            Some(_) => Err(Error::InvalidResumeToken),
+            None => Err(Error::NotFound),
+        }
+    }
+
+    /// Resolve a subscription filter for pushing data, retaining the resumption token where this
+    /// is valid (so only newer objects are pushed) and removing it otherwise (for a full resync).
+    ///
+    /// Returns [`Error::InvalidResumeToken`] where the token MAC is missing or invalid.
+    pub fn resume_filter<const N: usize, R: Retention>(&self, filter: &SubscribeFilter, history: &History<N, R>) -> Result<SubscribeFilter, Error> {
+        let mut f = filter.clone();
+
+        if let Some(t) = &filter.resume {
+            if let Err(e) = self.check_resume(t, history) {
+                // Reject unauthenticated tokens
+                if self.check_resume_mac(t).is_err() {
+                    return Err(e);
+                }
+
+                debug!("Resumption from index {} rejected ({:?}), resyncing", t.index, e);
+                f.resume = None;
+            }
+        }
+
+        Ok(f)
+    }
+}
+
+/// Build the keyed MAC for a resumption token
+fn resume_mac(id: &Id, secret_key: &SecretKey, t: &ResumeToken) -> Result<Blake2bMac<U32>, Error> {
+    let mut m = Blake2bMac::<U32>::new_with_salt_and_personal(secret_key.as_ref(), &[], &DSF_RESUME_MAC_CTX)
+        .map_err(|_| Error::CryptoError)?;
+
+    m.update(id.as_ref());
+    m.update(&t.index.to_be_bytes());
+    m.update(t.signature.as_ref());
+
+    Ok(m)
+}
+
+/// Blake2b resumption token MAC context, randomly generated
+const DSF_RESUME_MAC_CTX: [u8; 8] = [116, 204, 33, 143, 80, 121, 123, 138];
+
+#[cfg(test)]
+mod test {
+    use encdec::{Encode, Decode};
+
+    use super::*;
+    use crate::service::{DataOptions, Publisher, ServiceBuilder};
+    use crate::test_utils;
+
+    #[test]
+    fn resume_subscription() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let _p = test_utils::primary_page(&mut s);
+
+        // Publisher and subscriber track published / applied objects
+        let mut published = History::<8>::default();
+        let mut applied = History::<8>::default();
+
+        let mut objects = vec![];
+        for i in 0..4 {
+            let (_n, d) = s.publish_data(DataOptions::<&[u8]>::default(), vec![0u8; 1024]).unwrap();
+            published.record(&d).unwrap();
+            if i < 2 {
+                applied.record(&d).unwrap();
+            }
+            objects.push(d);
+        }
+
+        // Subscriber resumes from the last applied object
+        assert_eq!(s.resume_token(&History::<8>::default()), None);
+        let t = s.resume_token(&applied).unwrap();
+        assert_eq!(t.index, 2);
+
+        // Encoded filters carry the token
+        let f = SubscribeFilter::default().with_resume(t.clone());
+        assert!(!f.is_empty());
+        let mut buff = [0u8; 256];
+        let n = f.encode(&mut buff).unwrap();
+        assert_eq!(SubscribeFilter::decode(&buff[..n]).unwrap().0, f);
+
+        // Publisher pushes only newer objects
+        let f = s.resume_filter(&f, &published).unwrap();
+        assert_eq!(f.resume, Some(t.clone()));
+        let pushed: Vec<_> = objects.iter().filter(|o| f.matches(o)).map(|o| o.header().index()).collect();
+        assert_eq!(pushed, vec![3, 4]);
+
+        // Mismatched tokens are rejected and fall back to a full resync
+        let mut bad = ResumeToken { signature: objects[0].signature(), ..t.clone() };
+        bad.mac = s.resume_token_mac(&bad);
+        assert_eq!(s.check_resume(&bad, &published), Err(Error::InvalidResumeToken));
+        let f = s.resume_filter(&SubscribeFilter::default().with_resume(bad), &published).unwrap();
+        assert!(objects.iter().all(|o| f.matches(o)));
+
+        // Tokens with invalid MACs are rejected without resyncing
+        let forged = ResumeToken { signature: objects[0].signature(), ..t.clone() };
+        assert_eq!(s.resume_filter(&SubscribeFilter::default().with_resume(forged), &published), Err(Error::InvalidResumeToken));
+
+        // As are tokens for evicted objects
+        let mut short = History::<1>::default();
+        short.record(&objects[3]).unwrap();
+        assert_eq!(s.check_resume(&t, &short), Err(Error::NotFound));
+    }
+
+    #[test]
+    fn resume_token_mac() {
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().encrypt().build().unwrap();
+        let (_n, _p) = s.publish_primary_buff(Default::default()).unwrap();
+        let (_n, d) = s.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+
+        let mut h = History::<4>::default();
+        h.record(&d).unwrap();
+
+        // Tokens are MACed with the service secret key
+        let t = s.resume_token(&h).unwrap();
+        assert!(t.mac.is_some());
+        assert_eq!(s.check_resume(&t, &h), Ok(()));
+
+        // Missing or invalid MACs are rejected
+        let unsigned = ResumeToken { mac: None, ..t.clone() };
+        assert_eq!(s.check_resume(&unsigned, &h), Err(Error::InvalidResumeToken));
+        let forged = ResumeToken { mac: Some(CryptoHash::from([0u8; HASH_LEN])), ..t };
+        assert_eq!(s.check_resume(&forged, &h), Err(Error::InvalidResumeToken));
+    }
+}
@@ -0,0 +1,171 @@
+//! Secret key rotation, allowing services to replace the service secret key without creating
+//! a new service identity.
+//!
+//! [`Service::rotate_secret_key`] generates a new secret key and publishes a primary page encrypted
+//! with this, carrying an [`Options::KeyTransition`] with the new key sealed using the previous key.
+//! Subscribers holding the previous key unseal and apply the new key with [`Service::apply_key_transition`],
+//! which is called when applying encrypted primary pages with [`Subscriber::apply_primary`](super::Subscriber::apply_primary).
+//!
+//! Transition options are retained in subsequent primary pages, however subscribers missing more than
+//! one rotation will require the current key to be distributed out-of-band.
+//! Existing encrypted objects may be re-encrypted under the new key with [`Service::re_encrypt_page`].
+//!
+//! Note this does not support rotation of the service signing key, as service IDs are bound to the
+//! service public key.
+
+use crate::base::PageBody;
+use crate::crypto::{Crypto, PubKey as _, SecKey as _};
+use crate::error::Error;
+use crate::options::{KeyTransition, Options};
+use crate::types::*;
+use crate::wire::Container;
+
+use super::{PrimaryOptions, Publisher, Service};
+
+impl <B: PageBody> Service<B> {
+    /// Rotate the service secret key, publishing a primary page encrypted with the new key and
+    /// carrying a key transition option into the provided buffer.
+    ///
+    /// The service secret key is only updated once the page is successfully encoded.
+    pub fn rotate_secret_key<T: MutableData>(&mut self, options: PrimaryOptions, buff: T) -> Result<(usize, Container<T>), Error> {
+        let previous_key = match &self.secret_key {
+            Some(k) => k.clone(),
+            None => return Err(Error::NoSecretKey),
+        };
+
+        let new_key = Crypto::new_sk().map_err(|_| Error::CryptoError)?;
+        let transition = seal_key(&self.id, &previous_key, &new_key)?;
+
+        // Replace any existing transition option and switch to the new key
+        let public_options = self.public_options.clone();
+        self.public_options.retain(|o| !matches!(o, Options::KeyTransition(_)));
+        self.public_options.push(Options::key_transition(transition));
+        self.secret_key = Some(new_key);
+
+        match self.publish_primary(options, buff) {
+            Ok(v) => Ok(v),
+            Err(e) => {
+                self.public_options = public_options;
+                self.secret_key = Some(previous_key);
+                Err(e)
+            }
+        }
+    }
+
+    /// Validate and apply a key transition from a primary page published by this service,
+    /// unsealing the new secret key with the current secret key.
+    ///
+    /// Returns `true` where the secret key has been updated, and `false` where the page contains
+    /// no transition or the transition has already been applied.
+    pub fn apply_key_transition<T: ImmutableData>(&mut self, page: &Container<T>) -> Result<bool, Error> {
+        let transition = match page.public_options_iter().find_map(|o| match o {
+            Options::KeyTransition(t) => Some(t),
+            _ => None,
+        }) {
+            Some(t) => t,
+            None => return Ok(false),
+        };
+
+        let current_key = match &self.secret_key {
+            Some(k) => k,
+            None => return Err(Error::NoSecretKey),
+        };
+
+        // Transitions must be signed by the service
+        if page.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+        match Crypto::pk_verify(&self.public_key, &page.signature(), page.signed()) {
+            Ok(true) => (),
+            _ => return Err(Error::InvalidSignature),
+        }
+
+        // Transitions sealed with another key have either been applied already,
+        // or follow a missed rotation (in which case decryption fails as usual)
+        let new_key = match unseal_key(&self.id, current_key, &transition) {
+            Ok(k) if &k != current_key => k,
+            _ => return Ok(false),
+        };
+
+        // Check the new key matches the page
+        if page.encrypted() {
+            let mut c = page.to_owned();
+            c.decrypt(&new_key).map_err(|_| Error::SecretKeyMismatch)?;
+        }
+
+        self.secret_key = Some(new_key);
+
+        Ok(true)
+    }
+}
+
+/// Seal a new secret key with the previous secret key, binding this to the service ID
+fn seal_key(id: &Id, previous_key: &SecretKey, new_key: &SecretKey) -> Result<KeyTransition, Error> {
+    let mut sealed_key = [0u8; SECRET_KEY_LEN];
+    sealed_key.copy_from_slice(new_key);
+
+    let meta = Crypto::sk_encrypt(previous_key, Some(id), &mut sealed_key)
+        .map_err(|_| Error::CryptoError)?;
+
+    Ok(KeyTransition { sealed_key, meta })
+}
+
+/// Unseal a new secret key using the previous secret key
+fn unseal_key(id: &Id, previous_key: &SecretKey, t: &KeyTransition) -> Result<SecretKey, Error> {
+    let mut key = t.sealed_key;
+
+    Crypto::sk_decrypt(previous_key, &t.meta, Some(id), &mut key)
+        .map_err(|_| Error::SecretKeyMismatch)?;
+
+    Ok(SecretKey::from(key))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{ServiceBuilder, Subscriber};
+
+    #[test]
+    fn rotate_secret_key() {
+        let mut s = ServiceBuilder::<Vec<u8>>::generic()
+            .body(vec![0xaa; 16])
+            .encrypt()
+            .build()
+            .unwrap();
+        let old_key = s.secret_key().unwrap();
+
+        let (_n, p) = s.publish_primary_buff(PrimaryOptions::default()).unwrap();
+
+        let mut sub = Service::<Vec<u8>>::load(&p).unwrap();
+        sub.set_secret_key(Some(old_key.clone()));
+
+        // Rotation publishes a page encrypted with the new key
+        let (_n, r) = s.rotate_secret_key(PrimaryOptions::default(), vec![0u8; 1024]).unwrap();
+        let new_key = s.secret_key().unwrap();
+        assert!(new_key != old_key);
+        assert!(r.to_owned().decrypt(&old_key).is_err());
+
+        // Subscribers holding the previous key follow the rotation
+        assert_eq!(sub.apply_primary(&r), Ok(true));
+        assert_eq!(sub.secret_key(), Some(new_key.clone()));
+        assert_eq!(sub.apply_key_transition(&r), Ok(false));
+
+        // Retained transitions are ignored once applied
+        let (_n, p2) = s.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        assert_eq!(sub.apply_primary(&p2), Ok(true));
+        assert_eq!(sub.secret_key(), Some(new_key));
+
+        // Transitions from other services are rejected
+        let mut other = ServiceBuilder::<Vec<u8>>::generic().encrypt().build().unwrap();
+        other.set_secret_key(Some(old_key.clone()));
+        let (_n, o) = other.rotate_secret_key(PrimaryOptions::default(), vec![0u8; 1024]).unwrap();
+
+        let mut sub = Service::<Vec<u8>>::load(&p).unwrap();
+        sub.set_secret_key(Some(old_key));
+        assert_eq!(sub.apply_key_transition(&o), Err(Error::UnexpectedServiceId));
+
+        // Rotation requires a secret key
+        let mut plain = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        assert_eq!(plain.rotate_secret_key(PrimaryOptions::default(), vec![0u8; 1024]).err(), Some(Error::NoSecretKey));
+    }
+}
@@ -0,0 +1,107 @@
+//! Signing state persistence, allowing publishers to export and restore the state required
+//! to continue a service's signature chain (for example, across restarts).
+//!
+//! The [`SigningState`] captures the page version, data index, and last signature of a service,
+//! exported with [`Service::history`] and restored with [`Service::restore_history`]. Restoring
+//! stale state would cause the service to re-use versions or indices (forking the chain),
+//! so states older than the current service state are rejected.
+
+use crate::base::PageBody;
+use crate::error::Error;
+use crate::types::*;
+
+use super::Service;
+
+/// Persistable signing state for a service
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct SigningState {
+    /// Service ID
+    pub id: Id,
+    /// Last published page version
+    pub version: u16,
+    /// Last published data index
+    pub data_index: u16,
+    /// Signature of the last published object, if any
+    pub last_sig: Option<Signature>,
+}
+
+impl <B: PageBody> Service<B> {
+    /// Export the signing state of the service for persistence
+    pub fn history(&self) -> SigningState {
+        SigningState {
+            id: self.id.clone(),
+            version: self.version,
+            data_index: self.data_index,
+            last_sig: self.last_sig.clone(),
+        }
+    }
+
+    /// Restore a previously exported signing state.
+    ///
+    /// Returns [`Error::UnexpectedServiceId`] where the state belongs to another service, and
+    /// [`Error::InvalidServiceVersion`] where the state is older than the current service state.
+    /// Data indices restart with each version, so states are ordered by `(version, data_index)`.
+    pub fn restore_history(&mut self, state: SigningState) -> Result<(), Error> {
+        if state.id != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        if (state.version, state.data_index) < (self.version, self.data_index) {
+            debug!("Rejecting stale signing state (version {} index {}, current version {} index {})",
+                state.version, state.data_index, self.version, self.data_index);
+            return Err(Error::InvalidServiceVersion);
+        }
+
+        self.version = state.version;
+        self.data_index = state.data_index;
+        self.last_sig = state.last_sig;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::Filters;
+    use crate::service::{DataOptions, Publisher};
+    use crate::test_utils;
+
+    #[test]
+    fn export_restore_signing_state() {
+        let mut svc = test_utils::service(test_utils::SERVICE_SEED);
+        let _p = test_utils::primary_page(&mut svc);
+
+        let opts = DataOptions{ body: Some(&[0xaa; 4][..]), ..Default::default() };
+        let (_n, d1) = svc.publish_data(opts, vec![0u8; 1024]).unwrap();
+
+        let state = svc.history();
+        assert_eq!(state.data_index, 1);
+        assert_eq!(state.last_sig, Some(d1.signature()));
+
+        // A freshly loaded service continues the chain from the restored state
+        let mut restored = test_utils::service(test_utils::SERVICE_SEED);
+        restored.restore_history(state.clone()).unwrap();
+        assert_eq!(restored.history(), state);
+
+        let opts = DataOptions{ body: Some(&[0xbb; 4][..]), ..Default::default() };
+        let (_n, d2) = restored.publish_data(opts, vec![0u8; 1024]).unwrap();
+        assert_eq!(d2.header().index(), 2);
+        assert_eq!(d2.public_options_iter().prev_sig(), Some(d1.signature()));
+
+        // Stale state and state for other services are rejected
+        assert_eq!(restored.restore_history(state.clone()), Err(Error::InvalidServiceVersion));
+
+        // Newer versions restart the data index, while older versions are rejected regardless of index
+        let older = SigningState{ version: state.version - 1, data_index: 10, ..state.clone() };
+        assert_eq!(restored.restore_history(older), Err(Error::InvalidServiceVersion));
+
+        let newer = SigningState{ version: state.version + 1, data_index: 0, ..state.clone() };
+        assert_eq!(restored.restore_history(newer.clone()), Ok(()));
+        assert_eq!(restored.history(), newer);
+
+        let mut other = test_utils::service(test_utils::PEER_SEED);
+        assert_eq!(other.restore_history(state), Err(Error::UnexpectedServiceId));
+    }
+}
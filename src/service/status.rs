@@ -0,0 +1,274 @@
+//! Service status objects summarise publisher health (uptime, battery, signal strength, queue depth)
+//! for fleet monitoring, published as [`DataKind::Status`] data objects with [`Service::publish_status`].
+//!
+//! Status bodies are compactly encoded as a field presence byte followed by each present field
+//! (in network byte order), so constrained publishers only pay for the fields they report.
+//! Subscribers may request only status objects using [`SubscribeFilter::status`](crate::net::SubscribeFilter::status).
+
+use byteorder::{ByteOrder, NetworkEndian};
+use encdec::{Encode, Decode};
+
+use crate::base::{DataBody, PageBody};
+use crate::error::Error;
+use crate::types::*;
+use crate::wire::Container;
+
+use super::{DataOptions, Publisher, Service};
+
+const FIELD_UPTIME: u8 = 1 << 0;
+const FIELD_BATTERY: u8 = 1 << 1;
+const FIELD_RSSI: u8 = 1 << 2;
+const FIELD_QUEUE_DEPTH: u8 = 1 << 3;
+
+const FIELDS_ALL: u8 = FIELD_UPTIME | FIELD_BATTERY | FIELD_RSSI | FIELD_QUEUE_DEPTH;
+
+/// Maximum encoded status length
+pub const SERVICE_STATUS_MAX_LEN: usize = 1 + 4 + 1 + 1 + 2;
+
+/// Publisher health summary, fields are only encoded where set
+#[derive(Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ServiceStatus {
+    /// Publisher uptime in seconds
+    pub uptime: Option<u32>,
+    /// Battery level in percent
+    pub battery: Option<u8>,
+    /// Received signal strength in dBm
+    pub rssi: Option<i8>,
+    /// Outgoing queue depth in objects
+    pub queue_depth: Option<u16>,
+}
+
+impl ServiceStatus {
+    /// Set publisher uptime
+    pub fn with_uptime(mut self, uptime: core::time::Duration) -> Self {
+        self.uptime = Some(uptime.as_secs().min(u32::MAX as u64) as u32);
+        self
+    }
+
+    /// Set battery level (clamped to 100%)
+    pub fn with_battery(mut self, percent: u8) -> Self {
+        self.battery = Some(percent.min(100));
+        self
+    }
+
+    /// Set received signal strength
+    pub fn with_rssi(mut self, dbm: i8) -> Self {
+        self.rssi = Some(dbm);
+        self
+    }
+
+    /// Set outgoing queue depth
+    pub fn with_queue_depth(mut self, depth: u16) -> Self {
+        self.queue_depth = Some(depth);
+        self
+    }
+
+    /// Fetch publisher uptime
+    pub fn uptime(&self) -> Option<core::time::Duration> {
+        self.uptime.map(|s| core::time::Duration::from_secs(s as u64))
+    }
+
+    /// Load a status from a published status data object,
+    /// encrypted objects must be decrypted prior to loading ([`Error::NotDecrypted`])
+    pub fn from_object<T: ImmutableData>(object: &Container<T>) -> Result<Self, Error> {
+        if object.header().kind() != Kind::from(DataKind::Status) {
+            return Err(Error::UnexpectedPageKind);
+        }
+        if object.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
+
+        let (s, n) = ServiceStatus::decode(object.body_raw())?;
+        if n != object.body_raw().len() {
+            return Err(Error::InvalidPageLength);
+        }
+
+        Ok(s)
+    }
+
+    fn fields(&self) -> u8 {
+        let mut f = 0;
+        if self.uptime.is_some() { f |= FIELD_UPTIME }
+        if self.battery.is_some() { f |= FIELD_BATTERY }
+        if self.rssi.is_some() { f |= FIELD_RSSI }
+        if self.queue_depth.is_some() { f |= FIELD_QUEUE_DEPTH }
+        f
+    }
+}
+
+impl Encode for ServiceStatus {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        Ok(1 + self.uptime.map(|_| 4).unwrap_or(0)
+            + self.battery.map(|_| 1).unwrap_or(0)
+            + self.rssi.map(|_| 1).unwrap_or(0)
+            + self.queue_depth.map(|_| 2).unwrap_or(0))
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        if buff.len() < self.encode_len()? {
+            return Err(Error::BufferLength);
+        }
+
+        buff[0] = self.fields();
+        let mut n = 1;
+
+        if let Some(v) = self.uptime {
+            NetworkEndian::write_u32(&mut buff[n..], v);
+            n += 4;
+        }
+        if let Some(v) = self.battery {
+            buff[n] = v;
+            n += 1;
+        }
+        if let Some(v) = self.rssi {
+            buff[n] = v as u8;
+            n += 1;
+        }
+        if let Some(v) = self.queue_depth {
+            NetworkEndian::write_u16(&mut buff[n..], v);
+            n += 2;
+        }
+
+        Ok(n)
+    }
+}
+
+impl <'a> Decode<'a> for ServiceStatus {
+    type Output = ServiceStatus;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let fields = *buff.first().ok_or(Error::InvalidPageLength)?;
+        if fields & !FIELDS_ALL != 0 {
+            return Err(Error::InvalidFlags);
+        }
+
+        let mut s = ServiceStatus::default();
+        let mut n = 1;
+
+        let mut take = |len: usize| -> Result<&'a [u8], Error> {
+            let d = buff.get(n..n + len).ok_or(Error::InvalidPageLength)?;
+            n += len;
+            Ok(d)
+        };
+
+        if fields & FIELD_UPTIME != 0 {
+            s.uptime = Some(NetworkEndian::read_u32(take(4)?));
+        }
+        if fields & FIELD_BATTERY != 0 {
+            s.battery = Some(take(1)?[0]);
+        }
+        if fields & FIELD_RSSI != 0 {
+            s.rssi = Some(take(1)?[0] as i8);
+        }
+        if fields & FIELD_QUEUE_DEPTH != 0 {
+            s.queue_depth = Some(NetworkEndian::read_u16(take(2)?));
+        }
+
+        Ok((s, n))
+    }
+}
+
+impl DataBody for ServiceStatus {}
+
+impl <B: PageBody> Service<B> {
+    /// Publish a status summary for this service as a [`DataKind::Status`] data object
+    pub fn publish_status<T: MutableData>(&mut self, status: &ServiceStatus, buff: T) -> Result<(usize, Container<T>), Error> {
+        let opts = DataOptions {
+            data_kind: DataKind::Status as u16,
+            body: Some(status.clone()),
+            ..Default::default()
+        };
+
+        self.publish_data(opts, buff)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::SubscribeFilter;
+    use crate::service::ServiceBuilder;
+
+    fn init_service() -> Service {
+        ServiceBuilder::generic()
+            .build()
+            .expect("Failed to create service")
+    }
+
+    #[test]
+    fn publish_service_status() {
+        let mut s = init_service();
+
+        let status = ServiceStatus::default()
+            .with_uptime(core::time::Duration::from_secs(3600))
+            .with_battery(87)
+            .with_rssi(-72);
+        assert_eq!(status.encode_len(), Ok(7));
+
+        // Status objects are published and loaded with matching fields
+        let (_n, c) = s.publish_status(&status, vec![0u8; 1024]).unwrap();
+        let c = Container::parse(c.raw().to_vec(), &s.keys()).unwrap();
+        let decoded = ServiceStatus::from_object(&c).unwrap();
+        assert_eq!(decoded, status);
+        assert_eq!(decoded.uptime(), Some(core::time::Duration::from_secs(3600)));
+
+        // Subscribers may filter for status objects
+        let (_n, d) = s.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+        let filter = SubscribeFilter::status();
+        assert!(filter.matches(&c));
+        assert!(!filter.matches(&d));
+        assert_eq!(ServiceStatus::from_object(&d), Err(Error::UnexpectedPageKind));
+
+        // Status bodies with trailing data are rejected
+        let opts = DataOptions{ data_kind: DataKind::Status as u16, body: Some(&[0u8, 0xaa][..]), ..Default::default() };
+        let (_n, t) = s.publish_data(opts, vec![0u8; 1024]).unwrap();
+        assert_eq!(ServiceStatus::from_object(&t), Err(Error::InvalidPageLength));
+    }
+
+    #[test]
+    fn encode_decode_status() {
+        // Empty statuses encode only the field presence byte
+        let mut buff = [0u8; SERVICE_STATUS_MAX_LEN];
+        assert_eq!(ServiceStatus::default().encode(&mut buff), Ok(1));
+        assert_eq!(ServiceStatus::decode(&buff[..1]), Ok((ServiceStatus::default(), 1)));
+
+        // Fields are clamped to their encoded ranges
+        let status = ServiceStatus::default()
+            .with_uptime(core::time::Duration::from_secs(u64::MAX))
+            .with_battery(150)
+            .with_rssi(-128)
+            .with_queue_depth(u16::MAX);
+        assert_eq!((status.uptime, status.battery), (Some(u32::MAX), Some(100)));
+
+        let n = status.encode(&mut buff).unwrap();
+        assert_eq!(n, SERVICE_STATUS_MAX_LEN);
+        assert_eq!(ServiceStatus::decode(&buff[..n]), Ok((status.clone(), n)));
+
+        // Short buffers are rejected on encode
+        assert_eq!(status.encode(&mut buff[..n - 1]), Err(Error::BufferLength));
+
+        // Empty, truncated or unknown fields are rejected on decode
+        assert_eq!(ServiceStatus::decode(&[]), Err(Error::InvalidPageLength));
+        assert_eq!(ServiceStatus::decode(&buff[..n - 1]), Err(Error::InvalidPageLength));
+        buff[0] |= 0x80;
+        assert_eq!(ServiceStatus::decode(&buff[..n]), Err(Error::InvalidFlags));
+    }
+
+    #[test]
+    fn encrypted_status() {
+        let mut s: Service = ServiceBuilder::generic().encrypt().build().unwrap();
+        let status = ServiceStatus::default().with_queue_depth(12);
+
+        // Encrypted status objects must be decrypted before loading
+        let (_n, mut c) = s.publish_status(&status, vec![0u8; 1024]).unwrap();
+        assert_eq!(ServiceStatus::from_object(&c), Err(Error::NotDecrypted));
+
+        c.decrypt(&s.secret_key().unwrap()).unwrap();
+        assert_eq!(ServiceStatus::from_object(&c), Ok(status));
+    }
+}
@@ -7,27 +7,73 @@ use alloc::{vec::Vec};
 
 use crate::{
     base::PageBody,
-    crypto::{Crypto, PubKey as _, SecKey as _, Hash as _},
     error::Error,
-    page::{PageInfo},
+    keys::KeySource,
+    page::{PageInfo, Replica},
     prelude::{MaybeEncrypted},
-    service::Service,
+    service::{verify_block_object, ReplayState, Service},
     types::*,
-    wire::Container,
+    wire::{Container, ExpiryPolicy},
 };
 
 pub trait Subscriber<B: PageBody> {
     /// Create a service instance (or replica) from a given primary service page
     fn load<T: ImmutableData>(page: &Container<T>) -> Result<Service<B>, Error>;
 
+    /// Create a service instance from an encrypted (or private) primary page,
+    /// decrypting the page using the secret key from the provided key source
+    fn load_private<T: ImmutableData, K: KeySource>(page: &Container<T>, key_source: &K) -> Result<Service<B>, Error>;
+
     /// Apply an updated primary page to an existing service instance
     fn apply_primary<T: ImmutableData>(&mut self, primary: &Container<T>) -> Result<bool, Error>;
 
     /// Validate a given secondary (or tertiary) page published by this service
     fn validate_page<T: ImmutableData>(&mut self, page: &Container<T>) -> Result<(), Error>;
 
-    /// Validate data published by this service
-    fn validate_block<T: ImmutableData>(&mut self, _block: &Container<T>) -> Result<(), Error> { todo!() }
+    /// Validate a given page published by this service, rejecting pages expired under the provided policy
+    fn validate_page_with<T: ImmutableData, C: Clock>(&mut self, page: &Container<T>, policy: &ExpiryPolicy, clock: &C) -> Result<(), Error> {
+        self.validate_page(page)?;
+        policy.check(page, clock)
+    }
+
+    /// Validate a primary page or data object published by this service, rejecting
+    /// replayed or stale objects and recording the object in the provided replay state
+    fn validate_fresh<T: ImmutableData, const N: usize>(&mut self, object: &Container<T>, state: &mut ReplayState<N>) -> Result<(), Error> {
+        if object.header().flags().intersects(Flags::SECONDARY | Flags::TERTIARY) {
+            return Err(Error::ExpectedPrimaryPage);
+        }
+
+        self.validate_page(object)?;
+        state.apply(object)
+    }
+
+    /// Validate a content-addressed block published by this service, checking the block body
+    /// matches the attached block hash (encrypted blocks must be decrypted prior to validation)
+    fn validate_block<T: ImmutableData>(&mut self, block: &Container<T>) -> Result<(), Error> {
+        self.validate_page(block)?;
+        verify_block_object(block).map(|_| ())
+    }
+
+    /// Validate a replica page for this service (published by a replicating peer),
+    /// returning the replica information.
+    ///
+    /// Replicas are checked for expiry under the default [`ExpiryPolicy`] using the system clock.
+    /// Without `std` no time is available so replicas are rejected, use
+    /// [`Subscriber::validate_replica_with`] to provide a clock.
+    fn validate_replica<T: ImmutableData>(&mut self, page: &Container<T>) -> Result<Replica, Error> {
+        #[cfg(feature = "std")]
+        return self.validate_replica_with(page, &ExpiryPolicy::default(), &SystemClock);
+
+        #[cfg(not(feature = "std"))]
+        {
+            let _ = page;
+            debug!("No time available to check replica expiry");
+            return Err(Error::PageExpired);
+        }
+    }
+
+    /// Validate a replica page for this service, rejecting replicas expired under the provided policy
+    fn validate_replica_with<T: ImmutableData, C: Clock>(&mut self, page: &Container<T>, policy: &ExpiryPolicy, clock: &C) -> Result<Replica, Error>;
 }
 
 impl <'a, B: PageBody + DecodeOwned<Output=B>> Subscriber<B> for Service<B> {
@@ -84,12 +130,46 @@ impl <'a, B: PageBody + DecodeOwned<Output=B>> Subscriber<B> for Service<B> {
             secret_key: None,
 
             last_sig: Some(page.signature()),
+
+            interceptors: Default::default(),
         })
     }
 
+    fn load_private<T: ImmutableData, K: KeySource>(page: &Container<T>, key_source: &K) -> Result<Service<B>, Error> {
+        // Fetch secret key for the service
+        let sec_key = match key_source.keys(&page.id()).and_then(|k| k.sec_key) {
+            Some(sk) => sk,
+            None => return Err(Error::NoSecretKey),
+        };
+
+        // Decrypt page, this checks the secret key matches the object
+        let mut c = page.to_owned();
+        if c.encrypted() {
+            c.decrypt(&sec_key)?;
+        }
+
+        // Load service and attach secret key for further updates
+        let mut s = Self::load(&c)?;
+        s.secret_key = Some(sec_key);
+
+        Ok(s)
+    }
+
     /// Apply an upgrade to an existing service.
     /// This consumes a new page and updates the service instance
     fn apply_primary<T: ImmutableData>(&mut self, update: &Container<T>) -> Result<bool, Error> {
+        // Apply secret key transitions where the previous key is known
+        if update.encrypted() && self.secret_key.is_some() {
+            self.apply_key_transition(update)?;
+        }
+
+        // Decrypt updates where the secret key is known
+        if let (true, Some(sk)) = (update.encrypted(), &self.secret_key) {
+            let mut c = update.to_owned();
+            c.decrypt(sk)?;
+            return self.apply_primary(&c);
+        }
+
         let header = update.header();
 
         let flags = header.flags();
@@ -118,7 +198,11 @@ impl <'a, B: PageBody + DecodeOwned<Output=B>> Subscriber<B> for Service<B> {
         // Skip index checks for zero index (reset service)
         if header.index() != 0 {
             if header.index() == self.version {
-                return Ok(false);
+                // Matching index with a differing signature indicates the publisher has forked
+                return match &self.last_sig {
+                    Some(s) if s != &update.signature() => Err(Error::ForkDetected),
+                    _ => Ok(false),
+                };
             }
             if header.index() <= self.version {
                 return Err(Error::InvalidServiceVersion);
@@ -130,10 +214,27 @@ impl <'a, B: PageBody + DecodeOwned<Output=B>> Subscriber<B> for Service<B> {
         self.body = body;
         self.public_options = public_options;
         self.private_options = private_options;
+        self.last_sig = Some(update.signature());
 
         Ok(true)
     }
 
+    fn validate_replica_with<T: ImmutableData, C: Clock>(&mut self, page: &Container<T>, policy: &ExpiryPolicy, clock: &C) -> Result<Replica, Error> {
+        if page.id() != self.id {
+            return Err(Error::UnexpectedServiceId);
+        }
+        if page.header().application_id() != self.application_id {
+            return Err(Error::UnexpectedApplicationId);
+        }
+
+        let replica = Replica::parse(page)?;
+
+        // Check the replication agreement has not expired
+        policy.check(page, clock)?;
+
+        Ok(replica)
+    }
+
     fn validate_page<T: ImmutableData>(&mut self, page: &Container<T>) -> Result<(), Error> {
         let header = page.header();
 
@@ -187,7 +288,7 @@ impl <B: PageBody> Service<B> {
         };
 
         // Check public key and ID match
-        if self.id.as_bytes() != Crypto::hash(&public_key).unwrap().as_bytes() {
+        if self.id != Id::from_public_key(&public_key) {
             return Err(Error::KeyIdMismatch);
         }
 
@@ -199,7 +300,8 @@ impl <B: PageBody> Service<B> {
         Ok(())
     }
 
-    /// Validate a secondary page
+    /// Validate a secondary page, either published by this service
+    /// or published for this service by a delegated peer
     pub(crate) fn validate_secondary<T: ImmutableData>(&mut self, secondary: &Container<T>) -> Result<(), Error> {
         let header = secondary.header();
 
@@ -210,16 +312,31 @@ impl <B: PageBody> Service<B> {
             return Err(Error::ExpectedSecondaryPage);
         }
 
+        if header.application_id() != self.application_id {
+            return Err(Error::UnexpectedApplicationId);
+        }
+
         let publisher_id = match secondary.info()?.peer_id() {
             Some(p) => p,
             None => return Err(Error::NoPeerId),
         };
-        if publisher_id != self.id {
-            return Err(Error::UnexpectedPeerId);
+
+        // Secondary pages must be signed by the publishing peer
+        if !secondary.verified {
+            error!("Secondary page from {} has not been verified", publisher_id);
+            return Err(Error::InvalidSignature);
         }
 
-        if header.application_id() != self.application_id {
-            return Err(Error::UnexpectedApplicationId);
+        if publisher_id == self.id {
+            return Ok(());
+        }
+
+        // Pages published for this service must come from a delegated peer
+        if secondary.id() != self.id {
+            return Err(Error::UnexpectedPeerId);
+        }
+        if !self.is_delegate(&publisher_id) {
+            return Err(Error::UnauthorizedPublisher);
         }
 
         Ok(())
@@ -240,6 +357,11 @@ impl <B: PageBody> Service<B> {
             return Err(Error::UnexpectedApplicationId);
         }
 
+        // Multi-writer services require a quorum of writer counter-signatures
+        if self.writer_quorum() > 0 {
+            self.validate_counter_signatures(data)?;
+        }
+
         Ok(())
     }
 }
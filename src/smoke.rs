@@ -0,0 +1,124 @@
+//! Smoke checks for the fixed-buffer publish / parse APIs used on constrained targets.
+//!
+//! [`wire_fixed`] round-trips an object through the wire builder using only fixed buffers,
+//! the service level checks require `alloc` (services and decoded options are `Vec` based).
+
+use crate::base::Header;
+use crate::crypto::{Crypto, PubKey as _};
+use crate::error::Error;
+use crate::keys::Keys;
+use crate::types::*;
+use crate::wire::{Builder, Container, MIN_OBJECT_LEN};
+
+#[cfg(feature = "alloc")]
+use crate::{
+    base::{Empty, PageBody},
+    net::{Message, Request, RequestBody},
+    service::{DataOptions, Net, Publisher, Service, ServiceBuilder},
+};
+
+/// Fixed buffer length for smoke checks
+pub(crate) const SMOKE_BUFF_LEN: usize = 512;
+
+const _: () = assert!(SMOKE_BUFF_LEN >= MIN_OBJECT_LEN);
+
+/// Body for fixed-buffer smoke objects
+const SMOKE_BODY: &[u8] = b"smoke";
+
+/// Encode a signed object into a fixed buffer using the wire builder and parse it back in place
+pub(crate) fn wire_fixed() -> Result<(), Error> {
+    let (pub_key, pri_key) = Crypto::new_pk().map_err(|_| Error::CryptoError)?;
+    let id = Id::from_public_key(&pub_key);
+    let keys = Keys::new(pub_key);
+
+    let header = Header {
+        kind: PageKind::Generic.into(),
+        ..Default::default()
+    };
+
+    let c = Builder::new([0u8; SMOKE_BUFF_LEN])
+        .id(&id)
+        .header(&header)?
+        .body(SMOKE_BODY)?
+        .private_options(&[])?
+        .public()
+        .sign_pk(&pri_key)?;
+
+    let mut buff = [0u8; SMOKE_BUFF_LEN];
+    let n = c.len();
+    buff[..n].copy_from_slice(c.raw());
+
+    let parsed = Container::parse(&mut buff[..n], &keys)?;
+    if parsed.raw() != c.raw() || !parsed.verified || parsed.body_raw() != SMOKE_BODY {
+        return Err(Error::InvalidSignature);
+    }
+
+    Ok(())
+}
+
+/// Publish primary and data pages into fixed buffers and parse them back in place
+#[cfg(feature = "alloc")]
+pub(crate) fn publish_parse<B: PageBody>(s: &mut Service<B>) -> Result<(), Error> {
+    let keys = s.keys();
+
+    let (_n, p) = Publisher::<SMOKE_BUFF_LEN>::publish_primary_buff(s, Default::default())?;
+    let (_n, d) = Publisher::<SMOKE_BUFF_LEN>::publish_data_buff::<&[u8]>(s, DataOptions::default())?;
+
+    for c in [&p, &d] {
+        let mut buff = [0u8; SMOKE_BUFF_LEN];
+        let n = c.len();
+        buff[..n].copy_from_slice(c.raw());
+
+        let parsed = Container::parse(&mut buff[..n], &keys)?;
+        if parsed.raw() != c.raw() || !parsed.verified {
+            return Err(Error::InvalidSignature);
+        }
+    }
+
+    Ok(())
+}
+
+/// Encode a request into a fixed buffer and parse it back in place
+#[cfg(feature = "alloc")]
+pub(crate) fn encode_parse_request<B: PageBody>(source: &Service<B>, target: &Service<B>) -> Result<(), Error> {
+    let req = Request::new(source.id(), 1, RequestBody::Hello, Flags::empty());
+    let c = source.encode_request_buff::<SMOKE_BUFF_LEN>(&req, &target.keys())?;
+
+    let mut buff = [0u8; SMOKE_BUFF_LEN];
+    let n = c.len();
+    buff[..n].copy_from_slice(c.raw());
+
+    match Message::parse(&mut buff[..n], &source.keys())? {
+        (Message::Request(r), _) if r == req => Ok(()),
+        _ => Err(Error::InvalidMessageType),
+    }
+}
+
+/// Run smoke checks, using freshly generated services where `alloc` is available
+pub(crate) fn run() -> Result<(), Error> {
+    wire_fixed()?;
+
+    #[cfg(feature = "alloc")]
+    {
+        let mut source = ServiceBuilder::<Empty>::generic().build()?;
+        let target = ServiceBuilder::<Empty>::generic().build()?;
+
+        publish_parse(&mut source)?;
+        encode_parse_request(&source, &target)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn smoke_fixed_buffers() {
+        assert_eq!(super::run(), Ok(()));
+    }
+
+    #[test]
+    fn smoke_wire_fixed() {
+        assert_eq!(super::wire_fixed(), Ok(()));
+    }
+}
@@ -0,0 +1,111 @@
+//! Tenant scoping for multi-tenant daemons, segregating objects by a tenant ID
+//! carried in the [`Options::Tenant`] public option.
+//!
+//! A [`TenantScope`] interceptor attaches the tenant option to outgoing objects and rejects
+//! incoming objects for other tenants at the parse and convert layers, while
+//! [`KeySource::scoped`](crate::keys::KeySource::scoped) restricts key lookups to tenant services.
+
+use alloc::vec::Vec;
+
+use crate::base::Header;
+use crate::error::Error;
+use crate::interceptor::Interceptor;
+use crate::options::{Options, Filters};
+use crate::types::*;
+use crate::wire::Container;
+
+/// Interceptor scoping objects to a single tenant
+#[derive(Clone, Debug, PartialEq)]
+pub struct TenantScope {
+    tenant: Id,
+    allow_untenanted: bool,
+}
+
+impl TenantScope {
+    /// Create a new tenant scope, rejecting objects without a matching tenant option
+    pub fn new(tenant: Id) -> Self {
+        Self { tenant, allow_untenanted: false }
+    }
+
+    /// Accept incoming objects without a tenant option (for example, from legacy peers)
+    pub fn allow_untenanted(mut self) -> Self {
+        self.allow_untenanted = true;
+        self
+    }
+
+    /// Fetch the scoped tenant ID
+    pub fn tenant(&self) -> &Id {
+        &self.tenant
+    }
+
+    /// Check a (possibly absent) tenant option against the scope
+    fn check(&self, tenant: Option<Id>) -> Result<(), Error> {
+        match tenant {
+            Some(t) if t == self.tenant => Ok(()),
+            None if self.allow_untenanted => Ok(()),
+            Some(t) => {
+                debug!("Rejecting object for tenant {} (expected {})", t, self.tenant);
+                Err(Error::TenantMismatch)
+            },
+            None => Err(Error::TenantMismatch),
+        }
+    }
+}
+
+impl Interceptor for TenantScope {
+    fn pre_sign(&self, _id: &Id, _header: &Header, public_options: &mut Vec<Options>) -> Result<(), Error> {
+        public_options.push(Options::tenant(self.tenant.clone()));
+        Ok(())
+    }
+
+    fn post_parse(&self, container: &Container<&[u8]>) -> Result<(), Error> {
+        self.check(container.public_options_iter().tenant())
+    }
+
+    fn pre_convert(&self, _header: &Header, public_options: &mut Vec<Options>) -> Result<(), Error> {
+        self.check(Filters::tenant(&public_options.iter()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interceptor::Interceptors;
+    use crate::keys::KeySource;
+    use crate::net::{Message, Request, RequestBody};
+    use crate::service::{Net, Publisher};
+    use crate::test_utils::{self, TestKeySource};
+
+    #[test]
+    fn tenant_scoped_objects() {
+        let (a, b) = (Id::from([0xaa; ID_LEN]), Id::from([0xbb; ID_LEN]));
+        let (mut source, target) = test_utils::service_pair();
+
+        let req = Request::new(source.id(), test_utils::FIXTURE_REQUEST_ID, RequestBody::Hello, Flags::empty());
+        let untenanted = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+
+        source.add_interceptor(TenantScope::new(a.clone()));
+        let tenanted = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+        assert_eq!(tenanted.public_options_iter().tenant(), Some(a.clone()));
+
+        // Objects are accepted only by the matching tenant scope
+        let parse = |c: &Container<Vec<u8>>, scope: TenantScope| {
+            Message::parse_with(c.raw().to_vec(), &source.keys(), &Interceptors::new().with(scope)).map(|_| ())
+        };
+        assert_eq!(parse(&tenanted, TenantScope::new(a.clone())), Ok(()));
+        assert_eq!(parse(&tenanted, TenantScope::new(b.clone())), Err(Error::TenantMismatch));
+
+        // Untenanted objects are rejected unless explicitly allowed
+        assert_eq!(parse(&untenanted, TenantScope::new(a.clone())), Err(Error::TenantMismatch));
+        assert_eq!(parse(&untenanted, TenantScope::new(a.clone()).allow_untenanted()), Ok(()));
+
+        // Key lookups are scoped to tenant services
+        let (_n, p) = source.publish_primary_buff(Default::default()).unwrap();
+        assert_eq!(p.public_options_iter().tenant(), Some(a));
+
+        let (_n, d) = source.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+        let ks = TestKeySource::default();
+        assert!(Container::parse(d.raw().to_vec(), &ks.scoped(|id| id == &source.id())).is_ok());
+        assert!(Container::parse(d.raw().to_vec(), &ks.scoped(|id| id == &target.id())).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Shared test fixtures, enabled via the `test_utils` feature.
+//!
+//! This provides deterministic keys, services, pages and messages so the crate's own tests
+//! and downstream implementations can test against identical objects.
+
+use alloc::vec::Vec;
+
+use crate::keys::{KeySource, Keys};
+use crate::net::{Request, RequestBody};
+use crate::service::{Net, PrimaryOptions, Publisher, Service, ServiceBuilder};
+use crate::types::*;
+use crate::wire::{Container, offsets};
+
+/// Seed for the canonical fixture service
+pub const SERVICE_SEED: u8 = 0x01;
+
+/// Seed for the canonical fixture peer
+pub const PEER_SEED: u8 = 0x02;
+
+/// Fixed issue time for fixture pages (seconds since the epoch)
+pub const FIXTURE_ISSUED: u64 = 1_600_000_000;
+
+/// Fixed expiry time for fixture pages (seconds since the epoch)
+pub const FIXTURE_EXPIRY: u64 = FIXTURE_ISSUED + 24 * 60 * 60;
+
+/// Fixed request ID for fixture messages
+pub const FIXTURE_REQUEST_ID: RequestId = 120;
+
+/// Generate a deterministic key set from the provided seed
+pub fn keys(seed: u8) -> Keys {
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+
+    // Our private keys contain both the public and private components
+    let mut pri_key = PrivateKey::default();
+    pri_key[..32].copy_from_slice(secret.as_bytes());
+    pri_key[32..].copy_from_slice(public.as_bytes());
+
+    Keys {
+        pub_key: Some(PublicKey::from(public.to_bytes())),
+        pri_key: Some(pri_key),
+        sec_key: Some(SecretKey::from([!seed; 32])),
+        sym_keys: None,
+    }
+}
+
+/// Compute the service ID for a given seed
+pub fn id(seed: u8) -> Id {
+    keys(seed).id().unwrap()
+}
+
+/// Build a deterministic generic service from the provided seed
+pub fn service(seed: u8) -> Service {
+    ServiceBuilder::generic().keys(keys(seed)).build().unwrap()
+}
+
+/// Build the canonical fixture (service, peer) pair
+pub fn service_pair() -> (Service, Service) {
+    (service(SERVICE_SEED), service(PEER_SEED))
+}
+
+/// Primary page options with fixed issue and expiry times
+pub fn primary_options() -> PrimaryOptions {
+    PrimaryOptions {
+        issued: Some(DateTime::from_secs(FIXTURE_ISSUED)),
+        expiry: Some(DateTime::from_secs(FIXTURE_EXPIRY)),
+    }
+}
+
+/// Publish a deterministic primary page for the provided service
+pub fn primary_page(service: &mut Service) -> Container {
+    let (_n, c) = service.publish_primary(primary_options(), vec![0u8; 1024]).unwrap();
+    c.to_owned()
+}
+
+/// Encode a deterministic hello request from `source` to `target`
+pub fn hello_request(source: &Service, target: &Service) -> Container {
+    let req = Request::new(source.id(), FIXTURE_REQUEST_ID, RequestBody::Hello, Flags::ADDRESS_REQUEST);
+    let c = source.encode_request(&req, &target.keys(), vec![0u8; 1024]).unwrap();
+    c.to_owned()
+}
+
+/// Generate malformed variants of an encoded object, each paired with a description.
+/// All of these are expected to fail parsing.
+pub fn malformed(valid: &[u8]) -> Vec<(&'static str, Vec<u8>)> {
+    let mut m = vec![];
+
+    // Truncated header
+    m.push(("truncated header", valid[..offsets::ID / 2].to_vec()));
+
+    // Truncated signature
+    m.push(("truncated signature", valid[..valid.len() - 1].to_vec()));
+
+    // Corrupted signature
+    let mut d = valid.to_vec();
+    let n = d.len();
+    d[n - 1] ^= 0xFF;
+    m.push(("corrupt signature", d));
+
+    // Corrupted ID
+    let mut d = valid.to_vec();
+    d[offsets::ID] ^= 0xFF;
+    m.push(("corrupt id", d));
+
+    // Data length exceeding the buffer
+    let mut d = valid.to_vec();
+    d[offsets::DATA_LEN..offsets::DATA_LEN + 2].copy_from_slice(&[0xFF, 0xFF]);
+    m.push(("oversized data length", d));
+
+    m
+}
+
+/// Canonical [`KeySource`] containing the fixture service and peer keys
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestKeySource {
+    entries: Vec<(Id, Keys)>,
+}
+
+impl Default for TestKeySource {
+    fn default() -> Self {
+        Self::new()
+            .with(id(SERVICE_SEED), keys(SERVICE_SEED))
+            .with(id(PEER_SEED), keys(PEER_SEED))
+    }
+}
+
+impl TestKeySource {
+    /// Create an empty test key source
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    /// Add keys for the specified ID
+    pub fn with(mut self, id: Id, keys: Keys) -> Self {
+        self.entries.push((id, keys));
+        self
+    }
+}
+
+impl KeySource for TestKeySource {
+    fn keys(&self, id: &Id) -> Option<Keys> {
+        self.entries.iter().find(|(i, _)| i == id).map(|(_, k)| k.clone())
+    }
+
+    fn update<F: FnMut(&mut Keys)>(&mut self, id: &Id, mut f: F) -> bool {
+        match self.entries.iter_mut().find(|(i, _)| i == id) {
+            Some((_, k)) => {
+                f(k);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::Message;
+
+    #[test]
+    fn fixtures_are_deterministic() {
+        assert_eq!(keys(SERVICE_SEED), keys(SERVICE_SEED));
+        assert_ne!(keys(SERVICE_SEED), keys(PEER_SEED));
+
+        let (mut a, b) = service_pair();
+        assert_eq!(a.id(), id(SERVICE_SEED));
+        assert_eq!(b.id(), id(PEER_SEED));
+
+        let p1 = primary_page(&mut service(SERVICE_SEED));
+        let p2 = primary_page(&mut a);
+        assert_eq!(p1.raw(), p2.raw());
+    }
+
+    #[test]
+    fn malformed_pages_fail_parsing() {
+        let page = primary_page(&mut service(SERVICE_SEED));
+        let ks = TestKeySource::default();
+
+        Container::parse(page.raw().to_vec(), &ks).expect("valid page failed to parse");
+
+        for (desc, d) in malformed(page.raw()) {
+            assert!(Container::parse(d, &ks).is_err(), "malformed page parsed ({})", desc);
+        }
+    }
+
+    #[test]
+    fn malformed_messages_fail_parsing() {
+        let (a, b) = service_pair();
+        let req = hello_request(&a, &b);
+        let ks = TestKeySource::default();
+
+        Message::parse(req.raw().to_vec(), &ks).expect("valid message failed to parse");
+
+        for (desc, d) in malformed(req.raw()) {
+            assert!(Message::parse(d, &ks).is_err(), "malformed message parsed ({})", desc);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Algorithm suites describe the algorithms and optional protocol features supported by a peer,
+//! allowing peers built with differing features to negotiate a common set for encoding.
+
+use crate::error::Error;
+
+bitflags! {
+    /// Algorithm and feature suite, advertised in peer pages and Hello requests.
+    ///
+    /// Unrecognised bits (from peers supporting newer features) are ignored on decode.
+    #[derive(Default)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+    pub struct AlgorithmSuite: u32 {
+        /// Ed25519 object signing
+        const SIGN_ED25519      = (1 << 0);
+        /// SHA512/256 hashing (IDs and TIDs)
+        const HASH_SHA512_256   = (1 << 1);
+        /// Symmetric (AEAD) message mode following key exchange
+        const SYMMETRIC_MESSAGES = (1 << 2);
+        /// Secret key encrypted pages and data objects
+        const ENCRYPTED_PAGES   = (1 << 3);
+        /// CBOR envelopes for external interop
+        const CBOR_ENVELOPE     = (1 << 4);
+        /// Object compression
+        const COMPRESSION       = (1 << 5);
+
+        /// Base suite, required by all peers
+        const BASE = Self::SIGN_ED25519.bits | Self::HASH_SHA512_256.bits;
+    }
+}
+
+impl AlgorithmSuite {
+    /// Fetch the suite supported by this build
+    pub fn local() -> Self {
+        #[allow(unused_mut)]
+        let mut s = Self::BASE | Self::SYMMETRIC_MESSAGES | Self::ENCRYPTED_PAGES;
+
+        #[cfg(feature = "cbor")]
+        {
+            s |= Self::CBOR_ENVELOPE;
+        }
+
+        s
+    }
+
+    /// Compute the common suite for communication with a peer advertising `remote`,
+    /// peers not advertising a suite are assumed to support only [`AlgorithmSuite::BASE`].
+    ///
+    /// Returns [`Error::UnsupportedSignatureMode`] if no common base suite is available.
+    pub fn negotiate(&self, remote: Option<AlgorithmSuite>) -> Result<AlgorithmSuite, Error> {
+        let common = *self & remote.unwrap_or(Self::BASE);
+
+        if !common.contains(Self::BASE) {
+            return Err(Error::UnsupportedSignatureMode);
+        }
+
+        Ok(common)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn negotiate_suites() {
+        let local = AlgorithmSuite::BASE | AlgorithmSuite::SYMMETRIC_MESSAGES | AlgorithmSuite::COMPRESSION;
+
+        // Legacy peers fall back to the base suite
+        assert_eq!(local.negotiate(None), Ok(AlgorithmSuite::BASE));
+
+        // Otherwise the intersection is used
+        let remote = AlgorithmSuite::BASE | AlgorithmSuite::COMPRESSION | AlgorithmSuite::ENCRYPTED_PAGES;
+        assert_eq!(local.negotiate(Some(remote)), Ok(AlgorithmSuite::BASE | AlgorithmSuite::COMPRESSION));
+
+        // Peers must share a base suite
+        let remote = AlgorithmSuite::SIGN_ED25519 | AlgorithmSuite::SYMMETRIC_MESSAGES;
+        assert_eq!(local.negotiate(Some(remote)), Err(Error::UnsupportedSignatureMode));
+
+        // Unknown bits are ignored
+        assert_eq!(AlgorithmSuite::from_bits_truncate(0xffff_0000 | AlgorithmSuite::BASE.bits()), AlgorithmSuite::BASE);
+    }
+}
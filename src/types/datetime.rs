@@ -24,6 +24,40 @@ impl DateTime {
     }
 }
 
+/// Clock trait, providing the current time for time-dependent logic
+/// (default issued and expiry times, expiry evaluation).
+///
+/// Applications without `std` may implement this over an RTC or synchronised counter.
+pub trait Clock {
+    /// Fetch the current time
+    fn now(&self) -> DateTime;
+}
+
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> DateTime {
+        (*self).now()
+    }
+}
+
+/// Fixed time clock, for example where time is obtained from an external source
+impl Clock for DateTime {
+    fn now(&self) -> DateTime {
+        *self
+    }
+}
+
+/// System clock (via [`std::time::SystemTime`])
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        DateTime::now()
+    }
+}
+
 impl Add<core::time::Duration> for DateTime {
     type Output = DateTime;
 
@@ -0,0 +1,104 @@
+//! XOR distance metric and bucket utilities for Kademlia-style routing over [`Id`]s.
+//!
+//! Distances are computed in constant time. Bucket indices, prefix matching, and distance
+//! comparisons exit early on the first differing byte, so are not constant time; these
+//! operate on (public) node and service IDs and should not be used with secret values.
+
+use core::cmp::Ordering;
+
+use super::{Array, Id, ID_LEN};
+
+/// Number of bits in an ID (and the number of routing buckets)
+pub const ID_BITS: usize = ID_LEN * 8;
+
+/// XOR distance between two IDs, ordered by magnitude (big-endian)
+pub type Distance = Array<DistanceTy, ID_LEN>;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DistanceTy {}
+
+impl Distance {
+    /// Fetch the number of leading zero bits in the distance
+    pub fn leading_zeros(&self) -> usize {
+        match self.0.iter().position(|b| *b != 0) {
+            Some(i) => i * 8 + self.0[i].leading_zeros() as usize,
+            None => ID_BITS,
+        }
+    }
+}
+
+impl Id {
+    /// Compute the XOR distance to another ID
+    pub fn distance(&self, other: &Id) -> Distance {
+        let mut d = Distance::default();
+        for i in 0..ID_LEN {
+            d[i] = self[i] ^ other[i];
+        }
+        d
+    }
+
+    /// Compute the bucket index of another ID relative to this one, being the index of the
+    /// highest differing bit such that bucket `i` contains IDs at distances in `[2^i, 2^(i+1))`.
+    ///
+    /// Returns `None` where the IDs are equal.
+    pub fn bucket_index(&self, other: &Id) -> Option<usize> {
+        match self.distance(other).leading_zeros() {
+            ID_BITS => None,
+            n => Some(ID_BITS - 1 - n),
+        }
+    }
+
+    /// Compare the distances of `a` and `b` from this ID, ordering closer IDs first
+    pub fn cmp_distance(&self, a: &Id, b: &Id) -> Ordering {
+        self.distance(a).cmp(&self.distance(b))
+    }
+
+    /// Sort IDs by distance from this ID, closest first
+    pub fn sort_by_distance(&self, ids: &mut [Id]) {
+        ids.sort_by(|a, b| self.cmp_distance(a, b))
+    }
+
+    /// Check whether this ID shares the first `bits` bits with the provided prefix
+    pub fn within(&self, prefix: &Id, bits: usize) -> bool {
+        self.distance(prefix).leading_zeros() >= bits.min(ID_BITS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn id(b: &[(usize, u8)]) -> Id {
+        let mut id = Id::default();
+        for (i, v) in b {
+            id[*i] = *v;
+        }
+        id
+    }
+
+    #[test]
+    fn xor_distance() {
+        let (a, b, c) = (id(&[]), id(&[(0, 0x80)]), id(&[(ID_LEN - 1, 0x01)]));
+
+        assert_eq!(a.distance(&b), b.distance(&a));
+        assert_eq!(a.distance(&a).leading_zeros(), ID_BITS);
+        assert_eq!(a.distance(&b).leading_zeros(), 0);
+
+        assert_eq!(a.bucket_index(&a), None);
+        assert_eq!(a.bucket_index(&b), Some(ID_BITS - 1));
+        assert_eq!(a.bucket_index(&c), Some(0));
+
+        assert_eq!(a.cmp_distance(&c, &b), Ordering::Less);
+        let mut ids = [b.clone(), c.clone(), a.clone()];
+        a.sort_by_distance(&mut ids);
+        assert_eq!(ids, [a.clone(), c.clone(), b.clone()]);
+
+        let d = id(&[(0, 0xf0), (1, 0x01)]);
+        assert!(d.within(&id(&[(0, 0xf0)]), 12));
+        assert!(d.within(&id(&[(0, 0xf0)]), 15));
+        assert!(!d.within(&id(&[(0, 0xf0)]), 16));
+        assert!(d.within(&a, 0));
+        assert!(d.within(&d, ID_BITS + 1));
+    }
+}
@@ -1,3 +1,7 @@
+use crate::error::Error;
+
+use super::{Kind, SECRET_KEY_TAG_LEN};
+
 bitflags! {
     /// Page and Message Flags.
     #[derive(Default)]
@@ -34,5 +38,106 @@ bitflags! {
 
         /// (subscribe request) prioritise latency, eliding message containers (and thus p2p encryption)
         const QOS_PRIO_LATENCY = (1 << 9);
+
+        /// Signal body and private options are encrypted independently (with separate tags),
+        /// allowing either section to be decrypted alone
+        const SPLIT_ENCRYPTION = (1 << 10);
+
+        /// Signal the object body is compressed (prior to encryption)
+        const COMPRESSED = (1 << 11);
+    }
+}
+
+impl Flags {
+    /// Flags for primary pages
+    pub const fn primary_page() -> Self {
+        Self::empty()
+    }
+
+    /// Flags for secondary pages
+    pub const fn secondary_page() -> Self {
+        Self::SECONDARY
+    }
+
+    /// Flags for tertiary pages
+    pub const fn tertiary_page() -> Self {
+        Self::TERTIARY
+    }
+
+    /// Flags for public key mode requests
+    pub const fn request() -> Self {
+        Self::empty()
+    }
+
+    /// Flags for symmetric mode (encrypted) requests, available following key exchange
+    pub const fn symmetric_request() -> Self {
+        Self::from_bits_truncate(Self::SYMMETRIC_MODE.bits | Self::ENCRYPTED.bits)
+    }
+
+    /// Length of the secret key tag(s) following the private options section
+    pub fn tag_len(&self) -> usize {
+        match (self.contains(Self::ENCRYPTED), self.contains(Self::SYMMETRIC_MODE), self.contains(Self::SPLIT_ENCRYPTION)) {
+            (true, false, false) => SECRET_KEY_TAG_LEN,
+            (true, false, true) => 2 * SECRET_KEY_TAG_LEN,
+            _ => 0,
+        }
+    }
+
+    /// Validate flags for an object of the provided kind,
+    /// returning [`Error::InvalidFlags`] for incompatible combinations
+    pub fn validate(&self, kind: Kind) -> Result<(), Error> {
+        let paging = *self & (Self::SECONDARY | Self::TERTIARY);
+        let symmetric = *self & Self::SYMMETRIC_MODE;
+
+        // Objects can not be both secondary and tertiary
+        if paging == Self::SECONDARY | Self::TERTIARY {
+            return Err(Error::InvalidFlags);
+        }
+
+        // Page flags are only valid for pages
+        if !kind.is_page() && !paging.is_empty() {
+            return Err(Error::InvalidFlags);
+        }
+
+        // Symmetric mode is only valid for messages
+        if !kind.is_message() && !symmetric.is_empty() {
+            return Err(Error::InvalidFlags);
+        }
+
+        // Split encryption is only valid for secret key encrypted objects
+        if self.contains(Self::SPLIT_ENCRYPTION) && (!self.contains(Self::ENCRYPTED) || !symmetric.is_empty()) {
+            return Err(Error::InvalidFlags);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::*;
+
+    #[test]
+    fn validate_flags() {
+        let page: Kind = PageKind::Generic.into();
+        let data: Kind = DataKind::Generic.into();
+        let req = Kind::from(RequestKind::Hello);
+
+        assert_eq!(Flags::primary_page().validate(page), Ok(()));
+        assert_eq!(Flags::secondary_page().validate(page), Ok(()));
+        assert_eq!(Flags::tertiary_page().validate(page), Ok(()));
+        assert_eq!(Flags::request().validate(req), Ok(()));
+        assert_eq!(Flags::symmetric_request().validate(req), Ok(()));
+
+        assert_eq!((Flags::SECONDARY | Flags::TERTIARY).validate(page), Err(Error::InvalidFlags));
+        assert_eq!(Flags::SYMMETRIC_MODE.validate(page), Err(Error::InvalidFlags));
+        assert_eq!(Flags::symmetric_request().validate(data), Err(Error::InvalidFlags));
+        assert_eq!(Flags::SECONDARY.validate(req), Err(Error::InvalidFlags));
+        assert_eq!(Flags::TERTIARY.validate(data), Err(Error::InvalidFlags));
+
+        assert_eq!((Flags::ENCRYPTED | Flags::SPLIT_ENCRYPTION).validate(data), Ok(()));
+        assert_eq!(Flags::SPLIT_ENCRYPTION.validate(data), Err(Error::InvalidFlags));
+        assert_eq!((Flags::symmetric_request() | Flags::SPLIT_ENCRYPTION).validate(req), Err(Error::InvalidFlags));
     }
 }
@@ -82,6 +82,82 @@ impl Kind {
 pub enum KindError {
     InvalidKind(Kind),
     Unrecognized(Kind),
+    /// Kind belongs to a different application
+    UnexpectedApplication(u16),
+    /// Kind index exceeds the 13-bit index field
+    IndexOutOfRange(u16),
+}
+
+/// Checked conversions between application-defined kind enumerations and object [`Kind`]s.
+///
+/// This checks the application flag, application ID and base kind, rejecting kinds from other
+/// applications. For `u16` enums (such as those deriving `num_enum` `IntoPrimitive` and
+/// `TryFromPrimitive`) this can be implemented with [`impl_kind_convert`](crate::impl_kind_convert).
+pub trait KindConvert: Sized {
+    /// Application ID for these kinds
+    const APPLICATION_ID: u16;
+
+    /// Base object kind for these kinds
+    const BASE: BaseKind;
+
+    /// Fetch the kind index for an application kind
+    fn kind_index(&self) -> u16;
+
+    /// Resolve an application kind from an index
+    fn from_kind_index(index: u16) -> Option<Self>;
+
+    /// Convert an application kind to an object [`Kind`],
+    /// returning [`KindError::IndexOutOfRange`] for indices exceeding the 13-bit index field
+    fn to_kind(&self) -> Result<Kind, KindError> {
+        let index = self.kind_index();
+
+        Kind::new().with_base(Self::BASE).with_app(true).with_index_checked(index)
+            .map_err(|_| KindError::IndexOutOfRange(index))
+    }
+
+    /// Convert an object [`Kind`] with the provided application ID to an application kind
+    fn try_from_kind(application_id: u16, kind: Kind) -> Result<Self, KindError> {
+        if application_id != Self::APPLICATION_ID {
+            return Err(KindError::UnexpectedApplication(application_id));
+        }
+        if !kind.app() || kind.base() != Self::BASE {
+            return Err(KindError::InvalidKind(kind));
+        }
+
+        Self::from_kind_index(kind.index()).ok_or(KindError::Unrecognized(kind))
+    }
+}
+
+/// Implement [`KindConvert`] for a `Copy` application kind enumeration
+/// implementing `Into<u16>` and `TryFrom<u16>`.
+///
+/// ```
+/// use dsf_core::{impl_kind_convert, types::BaseKind};
+///
+/// #[derive(Copy, Clone, num_enum::IntoPrimitive, num_enum::TryFromPrimitive)]
+/// #[repr(u16)]
+/// enum AppKind {
+///     Reading = 0x01,
+/// }
+///
+/// impl_kind_convert!(AppKind, 0x1234, BaseKind::Block);
+/// ```
+#[macro_export]
+macro_rules! impl_kind_convert {
+    ($t:ty, $app_id:expr, $base:expr) => {
+        impl $crate::types::KindConvert for $t {
+            const APPLICATION_ID: u16 = $app_id;
+            const BASE: $crate::types::BaseKind = $base;
+
+            fn kind_index(&self) -> u16 {
+                u16::from(*self)
+            }
+
+            fn from_kind_index(index: u16) -> Option<Self> {
+                <$t as core::convert::TryFrom<u16>>::try_from(index).ok()
+            }
+        }
+    };
 }
 
 /// PageKind describes DSF-specific page kinds for encoding and decoding
@@ -155,6 +231,7 @@ pub enum RequestKind {
     Unregister      = 0x000a,
     Discover        = 0x000b,
     Locate          = 0x000c,
+    PeerExchange    = 0x000d,
 }
 
 impl From<RequestKind> for Kind {
@@ -187,6 +264,10 @@ pub enum ResponseKind {
     NodesFound      = 0x0002,
     ValuesFound     = 0x0003,
     PullData        = 0x0004,
+    PeerExchange    = 0x0005,
+    Registered      = 0x0006,
+    Discovered      = 0x0007,
+    Located         = 0x0008,
 }
 
 impl From<ResponseKind> for Kind {
@@ -216,6 +297,12 @@ impl TryFrom<Kind> for ResponseKind {
 
 pub enum DataKind {
     Generic = 0x0000,
+    /// Chain digest over previously published data objects
+    Digest  = 0x0001,
+    /// Service status summary
+    Status  = 0x0002,
+    /// Content-addressed block, identified by the hash of the object body
+    Block   = 0x0003,
 }
 
 impl From<DataKind> for Kind {
@@ -304,6 +391,9 @@ mod tests {
             (ResponseKind::NodesFound, Kind::from_bytes([0b0000_0010, 0b1100_0000])),
             (ResponseKind::ValuesFound, Kind::from_bytes([0b0000_0011, 0b1100_0000])),
             (ResponseKind::PullData, Kind::from_bytes([0b0000_0100, 0b1100_0000])),
+            (ResponseKind::Registered, Kind::from_bytes([0b0000_0110, 0b1100_0000])),
+            (ResponseKind::Discovered, Kind::from_bytes([0b0000_0111, 0b1100_0000])),
+            (ResponseKind::Located, Kind::from_bytes([0b0000_1000, 0b1100_0000])),
         ];
 
         for (t, v) in tests {
@@ -319,9 +409,51 @@ mod tests {
         }
     }
 
+    #[derive(Copy, Clone, PartialEq, Debug, IntoPrimitive, TryFromPrimitive)]
+    #[repr(u16)]
+    enum AppKind {
+        A = 0x0001,
+        B = 0x0002,
+        Large = 0x2000,
+    }
+
+    crate::impl_kind_convert!(AppKind, 0x1234, BaseKind::Block);
+
+    #[test]
+    fn test_kind_convert() {
+        for t in [AppKind::A, AppKind::B] {
+            let k = t.to_kind().unwrap();
+            assert!(k.is_data());
+            assert!(k.is_application());
+            assert_eq!(AppKind::try_from_kind(0x1234, k), Ok(t));
+        }
+
+        let k = AppKind::A.to_kind().unwrap();
+
+        // Reject indices exceeding the kind index field
+        assert_eq!(AppKind::Large.to_kind(), Err(KindError::IndexOutOfRange(0x2000)));
+
+        // Reject kinds from other applications
+        assert_eq!(AppKind::try_from_kind(0x4321, k), Err(KindError::UnexpectedApplication(0x4321)));
+
+        // Reject non-application and mismatched base kinds
+        assert_eq!(AppKind::try_from_kind(0x1234, Kind::data(1)), Err(KindError::InvalidKind(Kind::data(1))));
+        let p = Kind::page(1).with_app(true);
+        assert_eq!(AppKind::try_from_kind(0x1234, p), Err(KindError::InvalidKind(p)));
+
+        // Reject unrecognised indices
+        let u = Kind::data(3).with_app(true);
+        assert_eq!(AppKind::try_from_kind(0x1234, u), Err(KindError::Unrecognized(u)));
+    }
+
     #[test]
     fn test_data_kinds() {
-        let tests = vec![(DataKind::Generic, Kind::from_bytes([0b0000_0000, 0b0100_0000]))];
+        let tests = vec![
+            (DataKind::Generic, Kind::from_bytes([0b0000_0000, 0b0100_0000])),
+            (DataKind::Digest, Kind::from_bytes([0b0000_0001, 0b0100_0000])),
+            (DataKind::Status, Kind::from_bytes([0b0000_0010, 0b0100_0000])),
+            (DataKind::Block, Kind::from_bytes([0b0000_0011, 0b0100_0000])),
+        ];
 
         for (t, v) in tests {
             println!("data t: {:02x?}, v: {:#b}", t, u16::from(v));
@@ -5,6 +5,8 @@
 
 use core::marker::PhantomData;
 
+use crate::error::Error;
+
 #[cfg(feature = "std")]
 pub use chrono::Duration;
 
@@ -20,12 +22,28 @@ pub use self::kinds::*;
 pub mod flags;
 pub use self::flags::*;
 
+pub mod algorithms;
+pub use self::algorithms::AlgorithmSuite;
+
+pub mod transports;
+pub use self::transports::Transports;
+
+#[cfg(feature = "alloc")]
+pub mod query;
+#[cfg(feature = "alloc")]
+pub use self::query::And;
+
 pub mod datetime;
-pub use self::datetime::DateTime;
+pub use self::datetime::{DateTime, Clock};
+#[cfg(feature = "std")]
+pub use self::datetime::SystemClock;
 
 pub mod address;
 pub use self::address::{Address, AddressV4, AddressV6, Ip};
 
+pub mod distance;
+pub use self::distance::{Distance, ID_BITS};
+
 
 /// ImmutableData trait wraps AsRef<[u8]>
 pub trait ImmutableData: AsRef<[u8]> + crate::Debug {}
@@ -42,6 +60,13 @@ impl<T: AsMut<[u8]> + ImmutableData> MutableData for T {}
 /// Queryable trait for name resolution services
 pub trait Queryable: core::fmt::Debug {
     fn hash<H: CryptoHasher>(&self, h: &mut H) -> bool;
+
+    /// Collect hashing inputs for each component of a (possibly composite) query,
+    /// used to canonicalise composite queries (see [`query`])
+    #[cfg(feature = "alloc")]
+    fn collect(&self, parts: &mut alloc::vec::Vec<alloc::vec::Vec<u8>>) -> bool {
+        query::collect_one(self, parts)
+    }
 }
 
 pub trait CryptoHasher {
@@ -63,6 +88,28 @@ impl From<CryptoHash> for Id {
     }
 }
 
+impl Id {
+    /// Derive the service (or peer) ID for a public key.
+    ///
+    /// This is the canonical ID derivation and should be used in place of hashing public keys directly.
+    pub fn from_public_key(pub_key: &PublicKey) -> Id {
+        // SHA512/256 is the only (and base) hash suite so derivation is infallible
+        Self::from_public_key_with(AlgorithmSuite::BASE, pub_key).unwrap()
+    }
+
+    /// Derive the ID for a public key using the hash algorithm from the provided suite,
+    /// returning [`Error::UnsupportedSignatureMode`] where the suite has no supported hash algorithm.
+    pub fn from_public_key_with(suite: AlgorithmSuite, pub_key: &PublicKey) -> Result<Id, Error> {
+        use crate::crypto::{Crypto, Hash as _};
+
+        if !suite.contains(AlgorithmSuite::HASH_SHA512_256) {
+            return Err(Error::UnsupportedSignatureMode);
+        }
+
+        Crypto::hash(pub_key).map(Id::from).map_err(|_| Error::CryptoError)
+    }
+}
+
 
 impl Queryable for Id {
     fn hash<H: CryptoHasher>(&self, state: &mut H) -> bool {
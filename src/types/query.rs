@@ -0,0 +1,108 @@
+//! Composite query combinators, supporting lookups over multiple fields (eg. kind + name).
+//!
+//! Composite queries are hashed in a canonical order independent of the order (or nesting)
+//! in which components are provided, so that equivalent lookups generate stable TIDs
+//! across implementations. Each component is hashed to bytes, components are sorted
+//! lexicographically, then the component count and each length-prefixed component
+//! (as little-endian u16s) are written to the hasher.
+
+use alloc::vec::Vec;
+
+use super::{CryptoHasher, Queryable};
+
+/// Collector for component hashing inputs
+#[derive(Default)]
+struct Collector(Vec<u8>);
+
+impl CryptoHasher for Collector {
+    fn update(&mut self, buff: &[u8]) {
+        self.0.extend_from_slice(buff);
+    }
+}
+
+/// Collect hashing inputs for a single (non-composite) queryable component
+pub(crate) fn collect_one<Q: Queryable + ?Sized>(q: &Q, parts: &mut Vec<Vec<u8>>) -> bool {
+    let mut c = Collector::default();
+    if !q.hash(&mut c) {
+        return false;
+    }
+    parts.push(c.0);
+    true
+}
+
+/// Hash collected components in canonical order
+fn hash_parts<H: CryptoHasher>(mut parts: Vec<Vec<u8>>, h: &mut H) -> bool {
+    parts.sort();
+
+    h.update(&(parts.len() as u16).to_le_bytes());
+    for p in &parts {
+        h.update(&(p.len() as u16).to_le_bytes());
+        h.update(p);
+    }
+
+    true
+}
+
+/// Composite query matching both components
+#[derive(Clone, Debug, PartialEq)]
+pub struct And<A, B>(pub A, pub B);
+
+macro_rules! impl_composite {
+    ($t:ty, $($n:ident: $i:tt),+) => {
+        impl <$($n: Queryable),+> Queryable for $t {
+            fn hash<H: CryptoHasher>(&self, h: &mut H) -> bool {
+                let mut parts = Vec::new();
+                if !self.collect(&mut parts) {
+                    return false;
+                }
+                hash_parts(parts, h)
+            }
+
+            fn collect(&self, parts: &mut Vec<Vec<u8>>) -> bool {
+                $( self.$i.collect(parts) )&&+
+            }
+        }
+    };
+}
+
+impl_composite!(And<A, B>, A: 0, B: 1);
+impl_composite!((A, B), A: 0, B: 1);
+impl_composite!((A, B, C), A: 0, B: 1, C: 2);
+impl_composite!((A, B, C, D), A: 0, B: 1, C: 2, D: 3);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{Crypto, Hash as _};
+    use crate::types::CryptoHash;
+    use crate::options::Options;
+    use crate::test_utils;
+
+    fn tid(q: impl Queryable) -> CryptoHash {
+        let s = test_utils::service(test_utils::SERVICE_SEED);
+        Crypto::hash_tid(s.id(), &s.keys(), q).unwrap()
+    }
+
+    #[test]
+    fn composite_tids() {
+        let (kind, name, room) = (Options::kind("sensor"), Options::name("temp"), Options::Room("1a".into()));
+
+        // Component order and nesting are canonicalised
+        let a = tid((&kind, &name));
+        assert_eq!(a, tid((&name, &kind)));
+        assert_eq!(a, tid(And(&name, &kind)));
+
+        let b = tid((&kind, &name, &room));
+        assert_eq!(b, tid(And(&room, And(&name, &kind))));
+        assert_eq!(b, tid(((&room, &kind), &name)));
+
+        // Composite queries differ from their components
+        assert_ne!(a, b);
+        assert_ne!(a, tid(&name));
+        assert_ne!(tid((&name, &name)), tid(&name));
+
+        // Composites containing non-queryable components can not be hashed
+        let mut parts = Vec::new();
+        assert!(!(&name, &Options::pub_key(Default::default())).collect(&mut parts));
+    }
+}
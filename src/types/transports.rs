@@ -0,0 +1,33 @@
+//! Transport capabilities describe the transports over which a peer may be reached, advertised
+//! with MTU and message size constraints so peers can select compatible transports during setup.
+
+bitflags! {
+    /// Transports supported by a peer, advertised in peer pages and Hello exchanges.
+    ///
+    /// Unrecognised bits (from peers supporting newer transports) are ignored on decode.
+    #[derive(Default)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+    pub struct Transports: u16 {
+        /// UDP datagrams
+        const UDP           = (1 << 0);
+        /// TCP streams
+        const TCP           = (1 << 1);
+        /// Bluetooth Low Energy
+        const BLE           = (1 << 2);
+        /// LoRa radio
+        const LORA          = (1 << 3);
+        /// Peer is only reachable via relays
+        const RELAY_ONLY    = (1 << 4);
+    }
+}
+
+impl Transports {
+    /// Fetch the transports common to this and a remote peer, ignoring the relay-only flag.
+    ///
+    /// Peers not advertising transports are assumed to support only UDP.
+    pub fn common(&self, remote: Option<Transports>) -> Transports {
+        let remote = remote.unwrap_or(Transports::UDP);
+        (*self & remote) - Transports::RELAY_ONLY
+    }
+}
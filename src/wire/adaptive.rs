@@ -0,0 +1,84 @@
+//! Adaptive buffer sizing for encoding objects where the encoded length is not known in advance,
+//! retrying encoding with larger buffers on [`Error::BufferLength`] rather than requiring callers
+//! to guess fixed buffer sizes.
+
+use alloc::vec::Vec;
+
+use crate::error::Error;
+
+use super::{Container, MIN_OBJECT_LEN};
+
+/// Initial buffer length for adaptive encoding (where no larger size hint is provided)
+pub const ENCODE_ALLOC_MIN: usize = 512;
+
+/// Maximum buffer length for adaptive encoding
+pub const ENCODE_ALLOC_MAX: usize = 256 * 1024;
+
+/// Encode an object using an allocated buffer, starting from the provided length hint
+/// and doubling the buffer on [`Error::BufferLength`] up to [`ENCODE_ALLOC_MAX`].
+///
+/// The returned container buffer is truncated to the encoded length.
+/// Note the encode function may be called multiple times, so should not have side effects on failure.
+pub fn encode_with_alloc<F>(hint: usize, mut f: F) -> Result<Container<Vec<u8>>, Error>
+where
+    F: FnMut(Vec<u8>) -> Result<Container<Vec<u8>>, Error>,
+{
+    let mut len = hint.max(MIN_OBJECT_LEN).max(ENCODE_ALLOC_MIN);
+
+    loop {
+        match f(alloc::vec![0u8; len]) {
+            Ok(mut c) => {
+                c.buff.truncate(c.len);
+                return Ok(c);
+            },
+            Err(Error::BufferLength) if len < ENCODE_ALLOC_MAX => {
+                debug!("Encode failed with {} byte buffer, retrying", len);
+                len = (len * 2).min(ENCODE_ALLOC_MAX);
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::{Request, RequestBody};
+    use crate::service::{DataOptions, Net, PrimaryOptions, Publisher};
+    use crate::test_utils::{self, TestKeySource};
+    use crate::types::Flags;
+
+    #[test]
+    fn encode_adaptive_buffers() {
+        let (mut s, p) = test_utils::service_pair();
+
+        // Undersized buffers return errors without updating service state
+        let body = [0xaau8; 4096];
+        let opts = DataOptions{ body: Some(&body[..]), ..Default::default() };
+        assert_eq!(s.publish_primary(PrimaryOptions::default(), vec![0u8; 128]).err(), Some(Error::BufferLength));
+        assert_eq!(s.publish_data(opts.clone(), vec![0u8; 4096]).err(), Some(Error::BufferLength));
+
+        // Allocated buffers are sized to fit
+        let (n, primary) = s.publish_primary_alloc(PrimaryOptions::default()).unwrap();
+        assert_eq!((n, primary.raw().len()), (primary.len(), primary.len()));
+        assert_eq!(primary.header().index(), 1);
+
+        let (n, d) = s.publish_data_alloc(opts).unwrap();
+        assert!(n > body.len());
+        assert_eq!(d.header().index(), 1);
+        Container::parse(d.raw().to_vec(), &TestKeySource::default()).unwrap();
+
+        // Retrying where the initial size hint is insufficient
+        let mut attempts = vec![];
+        let c = encode_with_alloc(0, |b| {
+            attempts.push(b.len());
+            s.publish_data(DataOptions{ body: Some(&body[..]), ..Default::default() }, b).map(|(_n, c)| c)
+        }).unwrap();
+        assert_eq!(attempts, vec![512, 1024, 2048, 4096, 8192]);
+        assert_eq!(c.header().index(), 2);
+
+        let req = Request::new(s.id(), 1, RequestBody::Hello, Flags::empty()).with_public_key(s.public_key());
+        let c = s.encode_request_alloc(&req, &p.keys()).unwrap();
+        assert_eq!(c.raw().len(), c.len());
+    }
+}
@@ -0,0 +1,235 @@
+//! Audit provides bulk re-verification of stored objects for storage integrity checks,
+//! re-computing signature checks and region consistency with per-object results.
+
+use alloc::vec::Vec;
+
+use crate::crypto::{Crypto, PubKey as _};
+use crate::error::Error;
+use crate::keys::KeySource;
+use crate::options::OptionLimits;
+use crate::types::*;
+
+use super::{Container, SigningOptions, MIN_OBJECT_LEN, check_limits, offsets};
+
+/// Audit result for a single object
+#[derive(Clone, Debug, PartialEq)]
+pub enum AuditResult {
+    /// Object regions are consistent and signature is valid
+    Valid,
+    /// No public key available to verify the object
+    MissingKey,
+    /// Object signature is invalid
+    InvalidSignature,
+    /// Object is malformed or can not be verified
+    Malformed(Error),
+}
+
+/// Audit entry for an object
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEntry {
+    /// Position of the object in the audited store
+    pub position: usize,
+    /// Object ID (where the header could be parsed)
+    pub id: Option<Id>,
+    /// Object index (where the header could be parsed)
+    pub index: Option<u16>,
+    /// Audit result
+    pub result: AuditResult,
+}
+
+/// Audit report over a set of objects
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct AuditReport {
+    /// Per-object audit entries
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditReport {
+    /// Count valid objects
+    pub fn valid(&self) -> usize {
+        self.count(|r| r == &AuditResult::Valid)
+    }
+
+    /// Count objects that could not be verified due to missing keys
+    pub fn missing_keys(&self) -> usize {
+        self.count(|r| r == &AuditResult::MissingKey)
+    }
+
+    /// Count invalid (or malformed) objects
+    pub fn invalid(&self) -> usize {
+        self.count(|r| matches!(r, AuditResult::InvalidSignature | AuditResult::Malformed(_)))
+    }
+
+    /// Check no invalid objects were found, objects with missing keys are tolerated
+    pub fn is_ok(&self) -> bool {
+        self.invalid() == 0
+    }
+
+    /// Iterate over entries for invalid objects
+    pub fn failures(&self) -> impl Iterator<Item=&AuditEntry> {
+        self.entries.iter().filter(|e| matches!(e.result, AuditResult::InvalidSignature | AuditResult::Malformed(_)))
+    }
+
+    fn count<F: Fn(&AuditResult) -> bool>(&self, f: F) -> usize {
+        self.entries.iter().filter(|e| f(&e.result)).count()
+    }
+}
+
+/// Audit a set of stored objects, re-verifying signatures and region consistency
+pub fn audit<I, T, K>(objects: I, key_source: &K) -> AuditReport
+where
+    I: IntoIterator<Item=T>,
+    T: ImmutableData,
+    K: KeySource,
+{
+    audit_with(objects, key_source, |_e| ())
+}
+
+/// Audit a set of stored objects, calling `progress` with each completed entry
+pub fn audit_with<I, T, K, P>(objects: I, key_source: &K, mut progress: P) -> AuditReport
+where
+    I: IntoIterator<Item=T>,
+    T: ImmutableData,
+    K: KeySource,
+    P: FnMut(&AuditEntry),
+{
+    let mut report = AuditReport::default();
+
+    for (position, o) in objects.into_iter().enumerate() {
+        let data = o.as_ref();
+
+        let (id, index, result) = match header_fields(data) {
+            Some((id, index)) => (Some(id), Some(index), audit_object(data, key_source)),
+            None => (None, None, AuditResult::Malformed(Error::BufferLength)),
+        };
+
+        let entry = AuditEntry{ position, id, index, result };
+        progress(&entry);
+
+        report.entries.push(entry);
+    }
+
+    report
+}
+
+/// Fetch object ID and index where the fixed header is available
+fn header_fields(data: &[u8]) -> Option<(Id, u16)> {
+    if data.len() < offsets::BODY {
+        return None;
+    }
+
+    let (c, _n) = Container::from(data);
+    Some((c.id(), c.header().index()))
+}
+
+/// Audit a single object
+fn audit_object<K: KeySource>(data: &[u8], key_source: &K) -> AuditResult {
+    // Check regions are consistent with the stored object
+    if data.len() < MIN_OBJECT_LEN {
+        return AuditResult::Malformed(Error::BufferLength);
+    }
+
+    let (c, n) = Container::from(data);
+    if n != data.len() {
+        return AuditResult::Malformed(Error::BufferLength);
+    }
+
+    if let Err(e) = check_limits(&c, &OptionLimits::default()) {
+        return AuditResult::Malformed(e);
+    }
+
+    // Symmetric mode objects are messages and can not be audited
+    let flags = c.header().flags();
+    if flags.contains(Flags::SYMMETRIC_MODE) {
+        return AuditResult::Malformed(Error::UnsupportedSignatureMode);
+    }
+
+    // Resolve signing ID
+    let opts = match SigningOptions::extract(&c, &OptionLimits::default()) {
+        Ok(v) => v,
+        Err(e) => return AuditResult::Malformed(e),
+    };
+    let is_primary = !flags.contains(Flags::SECONDARY) && !flags.contains(Flags::TERTIARY);
+
+    let signing_id = match (is_primary, opts.peer_id) {
+        (true, _) => c.id(),
+        (false, Some(peer_id)) => peer_id,
+        _ => return AuditResult::Malformed(Error::NoPeerId),
+    };
+
+    // Use prepared keys where available
+    if let Some(v) = key_source.verifier(&signing_id) {
+        return match Crypto::pk_verify_prepared(&v, &c.signature(), c.signed()) {
+            Ok(true) => AuditResult::Valid,
+            _ => AuditResult::InvalidSignature,
+        };
+    }
+
+    // Otherwise stored keys, falling back to self-signed keys for primary objects
+    let pub_key = match (key_source.keys(&signing_id).and_then(|k| k.pub_key), opts.pub_key) {
+        (Some(pk), _) => pk,
+        (None, Some(pk)) if is_primary => pk,
+        _ => return AuditResult::MissingKey,
+    };
+
+    // Check public key matches the signing ID
+    if signing_id != Id::from_public_key(&pub_key) {
+        return AuditResult::Malformed(Error::KeyIdMismatch);
+    }
+
+    match Crypto::pk_verify(&pub_key, &c.signature(), c.signed()) {
+        Ok(true) => AuditResult::Valid,
+        _ => AuditResult::InvalidSignature,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{Publisher, SecondaryOptions};
+    use crate::test_utils::{self, TestKeySource};
+
+    #[test]
+    fn audit_store() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let mut p = test_utils::service(0x03);
+
+        let primary = test_utils::primary_page(&mut s);
+        let (_n, secondary) = p.publish_secondary(&s.id(), SecondaryOptions::default(), vec![0u8; 1024]).unwrap();
+
+        let mut corrupt = primary.raw().to_vec();
+        let n = corrupt.len();
+        corrupt[n - 1] ^= 0xff;
+
+        let store = vec![
+            primary.raw().to_vec(),
+            secondary.raw().to_vec(),
+            corrupt,
+            primary.raw()[..primary.len() - 1].to_vec(),
+            vec![0u8; 8],
+        ];
+
+        // Secondary pages from unknown peers are tolerated
+        let mut progress = vec![];
+        let r = audit_with(&store, &TestKeySource::default(), |e| progress.push(e.position));
+
+        assert_eq!(progress, vec![0, 1, 2, 3, 4]);
+        assert_eq!(r.entries.iter().map(|e| e.result.clone()).collect::<Vec<_>>(), vec![
+            AuditResult::Valid,
+            AuditResult::MissingKey,
+            AuditResult::InvalidSignature,
+            AuditResult::Malformed(Error::BufferLength),
+            AuditResult::Malformed(Error::BufferLength),
+        ]);
+        assert_eq!(r.entries[1].id, Some(s.id()));
+        assert_eq!(r.entries[4].id, None);
+        assert_eq!((r.valid(), r.missing_keys(), r.invalid()), (1, 1, 3));
+        assert!(!r.is_ok());
+
+        // And verified once keys are available
+        let ks = TestKeySource::default().with(p.id(), p.keys());
+        let r = audit(&store[..2], &ks);
+        assert_eq!(r.valid(), 2);
+        assert!(r.is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+use core::convert::TryFrom;
 use core::marker::PhantomData;
 use core::fmt::Debug;
 
@@ -6,14 +7,18 @@ use encdec::{Encode, Decode, EncodeExt, DecodeExt};
 use pretty_hex::*;
 
 use crate::base::{Header};
-use crate::crypto::{Crypto, PubKey as _, SecKey as _, Hash as _};
+use crate::crypto::{Crypto, Signer, PubKey as _, SecKey as _, Hash as _};
+#[cfg(feature = "async")]
+use crate::crypto::AsyncSigner;
 use crate::error::Error;
-use crate::options::{Options};
+use crate::keys::OptionKeySource;
+use crate::options::{Options, OptionKind, SealedOption, StandardOptions};
 use crate::types::*;
 
+use super::compress::Compressor;
 use super::container::Container;
 use super::header::WireHeader;
-use super::{offsets, HEADER_LEN};
+use super::{offsets, HEADER_LEN, MIN_OBJECT_LEN};
 
 /// Init state, no data set
 pub struct Init;
@@ -34,6 +39,20 @@ pub struct SetPublicOptions;
 /// Sign state, has PublicOptions and previous (SetPublicOptions)
 pub struct Sign;
 
+/// Public option region reserved with [`Builder::reserve_option`], to be patched prior to signing
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OptionSlot {
+    offset: usize,
+    len: usize,
+}
+
+impl OptionSlot {
+    /// Fetch the reserved (encoded) option length
+    pub fn encoded_len(&self) -> usize {
+        self.len
+    }
+}
+
 /// Internal trait to support encoding of optionally encrypted objects in a generic buffer
 pub trait EncodeEncrypted {
     fn encode<B: MutableData>(
@@ -64,7 +83,7 @@ impl<S, T: MutableData> Builder<S, T> {
     pub fn id(mut self, id: &Id) -> Self {
         let d = self.buf.as_mut();
 
-        d[HEADER_LEN..HEADER_LEN + ID_LEN].clone_from_slice(id);
+        d[offsets::ID..offsets::BODY].clone_from_slice(id);
 
         self
     }
@@ -81,7 +100,12 @@ impl<S, T: MutableData> Builder<S, T> {
 
     /// Fetch the header bytes (including ID)
     pub fn header_raw(&self) -> &[u8] {
-        &self.buf.as_ref()[..HEADER_LEN+ID_LEN]
+        &self.buf.as_ref()[..offsets::BODY]
+    }
+
+    /// Fetch the number of bytes available for the remainder of the object
+    pub fn remaining(&self) -> usize {
+        self.buf.as_ref().len().saturating_sub(self.n)
     }
 }
 
@@ -98,16 +122,25 @@ impl<T: MutableData> Builder<Init, T> {
     }
 
     /// Set the object header.
-    /// Note that length fields will be overwritten by actual lengths
-    pub fn header(mut self, header: &Header) -> Self {
+    /// Note that length fields will be overwritten by actual lengths,
+    /// returns [`Error::InvalidFlags`] for flags incompatible with the object kind
+    pub fn header(mut self, header: &Header) -> Result<Self, Error> {
+        span!(Header);
         trace!("Set header: {:02?}", header);
-        
+
+        header.flags().validate(header.kind())?;
+
+        // Check the buffer can hold at least a minimal object
+        if self.buf.as_ref().len() < MIN_OBJECT_LEN {
+            return Err(Error::BufferLength);
+        }
+
         self.header_mut().encode(header);
         self.header_mut().set_data_len(0);
         self.header_mut().set_private_options_len(0);
         self.header_mut().set_public_options_len(0);
 
-        self
+        Ok(self)
     }
 
     /// Add body data, mutating the state of the builder
@@ -115,6 +148,7 @@ impl<T: MutableData> Builder<Init, T> {
         mut self,
         body: B,
     ) -> Result<Builder<SetPrivateOptions, T>, <B as Encode>::Error> {
+        span!(Body);
         let b = self.buf.as_mut();
 
         self.n = offsets::BODY;
@@ -136,6 +170,7 @@ impl<T: MutableData> Builder<Init, T> {
     }
 
     pub fn with_body(mut self, f: impl Fn(&mut [u8]) -> Result<usize, Error>) -> Result<Builder<SetPrivateOptions, T>, Error> {
+        span!(Body);
         let b = self.buf.as_mut();
         self.n = offsets::BODY;
 
@@ -157,6 +192,66 @@ impl<T: MutableData> Builder<Init, T> {
         })
     }
 
+    /// Write an encoded body compressed with the provided [`Compressor`],
+    /// setting [`Flags::COMPRESSED`] in the object header
+    pub fn compressed_body<C: Compressor>(self, compressor: &C, data: &[u8]) -> Result<Builder<SetPrivateOptions, T>, Error> {
+        let mut b = self.with_body(|buff| compressor.compress(data, buff))?;
+
+        trace!("Compressed {} byte body to {} bytes", data.len(), b.n - offsets::BODY);
+
+        let flags = b.header_ref().flags();
+        b.header_mut().set_flags(flags | Flags::COMPRESSED);
+
+        Ok(b)
+    }
+
+    /// Write a body of `len` bytes incrementally using the provided producer.
+    ///
+    /// The producer is called with the remaining body space and returns the number of bytes written
+    /// (as with `Read::read`), allowing large bodies to be written directly into the object buffer
+    /// without first being buffered in full.
+    pub fn with_body_stream(mut self, len: usize, mut producer: impl FnMut(&mut [u8]) -> Result<usize, Error>) -> Result<Builder<SetPrivateOptions, T>, Error> {
+        span!(Body);
+        let b = self.buf.as_mut();
+        self.n = offsets::BODY;
+
+        // Check the body will fit in the buffer
+        if b.len() < offsets::BODY + len {
+            return Err(Error::BufferLength);
+        }
+
+        // Write body chunks until complete
+        let body = &mut b[offsets::BODY..][..len];
+        let mut i = 0;
+        while i < len {
+            let n = producer(&mut body[i..])?;
+            if n == 0 {
+                error!("Body stream ended after {} of {} bytes", i, len);
+                return Err(Error::InvalidPageLength);
+            }
+
+            trace!("Add {} byte body chunk at offset {}", n, i);
+            i += n;
+        }
+
+        self.n += len;
+        self.header_mut().set_data_len(len);
+
+        Ok(Builder {
+            buf: self.buf,
+            n: self.n,
+            c: 0,
+            encrypted: false,
+            _s: PhantomData,
+        })
+    }
+
+    /// Write a body of `len` bytes incrementally from the provided reader
+    #[cfg(feature = "std")]
+    pub fn with_body_reader(self, len: usize, mut reader: impl std::io::Read) -> Result<Builder<SetPrivateOptions, T>, Error> {
+        self.with_body_stream(len, |buff| reader.read(buff).map_err(Error::from))
+    }
+
     pub fn no_body(
         self,
     ) -> Builder<SetPrivateOptions, T> {
@@ -188,6 +283,7 @@ impl<T: MutableData> Builder<SetPrivateOptions, T> {
         mut self,
         options: C,
     ) -> Result<Builder<Encrypt, T>, Error> {
+        span!(PrivateOptions);
         let b = self.buf.as_mut();
 
         let n = Options::encode_iter(options.into_iter(), &mut b[self.n..])?;
@@ -219,6 +315,10 @@ impl<T: MutableData> Builder<SetPrivateOptions, T> {
         let b = self.buf.as_mut();
         let o = options.as_ref();
 
+        if b.len() < self.n + o.len() {
+            return Err(Error::BufferLength);
+        }
+
         b[self.n..][..o.len()].copy_from_slice(o);
         self.n += o.len();
 
@@ -244,28 +344,59 @@ impl<T: MutableData> Builder<Encrypt, T> {
         secret_key: &SecretKey,
     ) -> Result<Builder<SetPublicOptions, T>, Error> {
         // TODO: skip if body + private options are empty...
+        span!(Encrypt);
 
         debug!("SK body encrypt with key: {}", secret_key);
 
+        // Mark object as encrypted so the tag(s) are accounted for on parsing
+        let flags = self.header_ref().flags();
+        self.header_mut().set_flags(flags | Flags::ENCRYPTED);
+
         // Calculate area to be encrypted
-        let o = HEADER_LEN + ID_LEN;
+        let o = offsets::BODY;
         let l = self.header_ref().data_len()
                 + self.header_ref().private_options_len();
 
+        let split = self.header_ref().flags().contains(Flags::SPLIT_ENCRYPTION);
+        let d = self.header_ref().data_len();
+
         let b = self.buf.as_mut();
 
+        // Check the tag(s) will fit in the buffer
+        let tag_len = SECRET_KEY_TAG_LEN * if split { 2 } else { 1 };
+        if b.len() < self.n + tag_len {
+            return Err(Error::BufferLength);
+        }
+
         let block = &mut b[o..o+l];
         trace!("Encrypting block: {:?}", block.hex_dump());
 
-        // Perform encryption
-        let tag = Crypto::sk_encrypt(secret_key, None, block).unwrap();
-
-        trace!("Encrypted block: {:?}", block.hex_dump());
-        trace!("Encryption tag: {:?}", tag.hex_dump());
-
-        // Attach tag to object
-        b[self.n..][..SECRET_KEY_TAG_LEN].copy_from_slice(&tag);
-        self.n += SECRET_KEY_TAG_LEN;
+        let encrypt_failed = |e| {
+            error!("Failed to encrypt object: {:?}", e);
+            Error::CryptoError
+        };
+
+        // Perform encryption, with separate tags for body and private options where split
+        let tags = match split {
+            true => {
+                let (body, opts) = block.split_at_mut(d);
+                [
+                    Some(Crypto::sk_encrypt(secret_key, None, body).map_err(encrypt_failed)?),
+                    Some(Crypto::sk_encrypt(secret_key, None, opts).map_err(encrypt_failed)?),
+                ]
+            },
+            false => [Some(Crypto::sk_encrypt(secret_key, None, block).map_err(encrypt_failed)?), None],
+        };
+
+        trace!("Encrypted block: {:?}", (&b[o..o+l]).hex_dump());
+
+        // Attach tag(s) to object
+        for tag in tags.iter().flatten() {
+            trace!("Encryption tag: {:?}", tag.hex_dump());
+
+            b[self.n..][..SECRET_KEY_TAG_LEN].copy_from_slice(tag);
+            self.n += SECRET_KEY_TAG_LEN;
+        }
 
         trace!("Encrypted {} bytes at offset {}, new index: {}", l, o, self.n);
 
@@ -279,25 +410,53 @@ impl<T: MutableData> Builder<Encrypt, T> {
     }
 
     /// Re-encode private data and options, using existing encryption tag
-    /// This must be done in one pass as the entire data/options block is encrypted
+    /// This must be done in one pass as the entire data/options block is encrypted.
+    /// Returns [`Error::BufferLength`] where the tag(s) are shorter than required by the object flags.
     pub fn re_encrypt<C: ImmutableData>(
         mut self,
         secret_key: &SecretKey,
         tag: C
     ) -> Result<Builder<SetPublicOptions, T>, Error> {
+        span!(Encrypt);
+
+        // Mark object as encrypted so the tag(s) are accounted for on parsing
+        let flags = self.header_ref().flags();
+        self.header_mut().set_flags(flags | Flags::ENCRYPTED);
+
         // Calculate area to be encrypted
-        let o = HEADER_LEN + ID_LEN;
+        let o = offsets::BODY;
         let l = self.header_ref().data_len()
                 + self.header_ref().private_options_len();
 
+        let tag_len = self.header_ref().flags().tag_len();
+        let d = self.header_ref().data_len();
+
         let b = self.buf.as_mut();
 
-        // Perform encryption
-        Crypto::sk_reencrypt(secret_key, tag.as_ref(), None, &mut b[o..o+l]).unwrap();
+        // Check the provided tag(s) match the object flags and will fit in the buffer
+        let t = tag.as_ref();
+        if t.len() < tag_len || b.len() < self.n + tag_len {
+            error!("Invalid re-encryption tag length: {} (expected {})", t.len(), tag_len);
+            return Err(Error::BufferLength);
+        }
+
+        let encrypt_failed = |e| {
+            error!("Failed to re-encrypt object: {:?}", e);
+            Error::CryptoError
+        };
+
+        // Perform encryption, with separate tags for body and private options where split
+        if tag_len > SECRET_KEY_TAG_LEN {
+            let (body, opts) = b[o..o+l].split_at_mut(d);
+            Crypto::sk_reencrypt(secret_key, &t[..SECRET_KEY_TAG_LEN], None, body).map_err(encrypt_failed)?;
+            Crypto::sk_reencrypt(secret_key, &t[SECRET_KEY_TAG_LEN..tag_len], None, opts).map_err(encrypt_failed)?;
+        } else {
+            Crypto::sk_reencrypt(secret_key, &t[..tag_len], None, &mut b[o..o+l]).map_err(encrypt_failed)?;
+        }
 
         // Attach tag to object
-        b[self.n..][..SECRET_KEY_TAG_LEN].copy_from_slice(&tag.as_ref());
-        self.n += SECRET_KEY_TAG_LEN;
+        b[self.n..][..tag_len].copy_from_slice(&t[..tag_len]);
+        self.n += tag_len;
 
         trace!("Re-encrypted {} bytes at offset {} with tag: {:02x?}, new index: {}", l, o, tag.as_ref(), self.n);
 
@@ -317,15 +476,17 @@ impl<T: MutableData> Builder<Encrypt, T> {
         tag: C
     ) -> Result<Builder<SetPublicOptions, T>, Error> {
         // Calculate area to be encrypted
-        let o = HEADER_LEN + ID_LEN
+        let o = offsets::BODY
                 + self.header_ref().data_len()
                 + self.header_ref().private_options_len();
 
+        let tag_len = self.header_ref().flags().tag_len();
+
         let b = self.buf.as_mut();
 
         // Attach tag to object
-        b[o..][..SECRET_KEY_TAG_LEN].copy_from_slice(tag.as_ref());
-        self.n = o + SECRET_KEY_TAG_LEN;
+        b[o..][..tag_len].copy_from_slice(&tag.as_ref()[..tag_len]);
+        self.n = o + tag_len;
 
         trace!("Added tag: {:02x?}, new index: {}", tag.as_ref(), self.n);
 
@@ -361,6 +522,7 @@ impl<T: MutableData> Builder<SetPublicOptions, T> {
         mut self,
         options: C,
     ) -> Result<Builder<SetPublicOptions, T>, Error> {
+        span!(PublicOptions);
         let b = self.buf.as_mut();
 
         let n = Options::encode_iter(options.into_iter(), &mut b[self.n..])?;
@@ -375,8 +537,20 @@ impl<T: MutableData> Builder<SetPublicOptions, T> {
         Ok(self)
     }
 
+    /// Validate and encode standard public options (public key, previous signature, issued and expiry times)
+    pub fn standard_options(mut self, options: &StandardOptions) -> Result<Builder<SetPublicOptions, T>, Error> {
+        options.validate()?;
+
+        for o in options.options() {
+            self.public_option(&o)?;
+        }
+
+        Ok(self)
+    }
+
     /// Add a single public option
     pub fn public_option(&mut self, option: &Options) -> Result<(), Error> {
+        span!(PublicOptions);
         let b = self.buf.as_mut();
 
         let n = option.encode(&mut b[self.n..])?;
@@ -392,10 +566,65 @@ impl<T: MutableData> Builder<SetPublicOptions, T> {
         Ok(())
     }
 
+    /// Add a public option sealed under the subkey for the option kind, allowing the option
+    /// to be disclosed separately from the object body (see [`SealedOption`])
+    pub fn sealed_option<K: OptionKeySource>(&mut self, option: &Options, keys: &K) -> Result<(), Error> {
+        let key = keys.option_key(OptionKind::from(option)).ok_or(Error::NoSecretKey)?;
+        let sealed = SealedOption::seal(option, &key)?;
+
+        self.public_option(&Options::Sealed(sealed))
+    }
+
+    /// Fetch the encoded payload (body, private options, and encryption tags), which is
+    /// fixed (and encrypted where required) once public options may be added
+    pub fn payload(&self) -> &[u8] {
+        &self.buf.as_ref()[offsets::BODY..self.n - self.c]
+    }
+
+    /// Add a public option computed from the encoded payload, for example a content hash
+    /// over the encrypted body
+    pub fn public_option_with<F>(&mut self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&[u8]) -> Result<Options, Error>,
+    {
+        let option = f(self.payload())?;
+        self.public_option(&option)
+    }
+
+    /// Reserve space for a public option using the provided placeholder, to be replaced
+    /// with [`Builder::patch_option`] once the option value is known (for example, where
+    /// the value depends on the length of the encoded object)
+    pub fn reserve_option(&mut self, placeholder: &Options) -> Result<OptionSlot, Error> {
+        let offset = self.n;
+        self.public_option(placeholder)?;
+
+        Ok(OptionSlot{ offset, len: self.n - offset })
+    }
+
+    /// Replace a reserved public option, returning [`Error::InvalidOptionLength`]
+    /// where the option does not match the reserved length
+    pub fn patch_option(&mut self, slot: &OptionSlot, option: &Options) -> Result<(), Error> {
+        if option.encode_len()? != slot.len {
+            return Err(Error::InvalidOptionLength);
+        }
+
+        let b = self.buf.as_mut();
+        option.encode(&mut b[slot.offset..][..slot.len])?;
+
+        trace!("Patch public option: {:?} at offset {}", option, slot.offset);
+
+        Ok(())
+    }
+
     // Sign the builder object, returning a new signed container
     pub fn sign_pk(mut self, signing_key: &PrivateKey) -> Result<Container<T>, Error> {
+        span!(Sign);
         let b = self.buf.as_mut();
 
+        if b.len() < self.n + SIGNATURE_LEN {
+            return Err(Error::BufferLength);
+        }
+
         // Generate signature
         let sig = Crypto::pk_sign(signing_key, &b[..self.n]).unwrap();
 
@@ -417,12 +646,17 @@ impl<T: MutableData> Builder<SetPublicOptions, T> {
     }
 
     pub fn encrypt_sk(mut self, secret_key: &SecretKey) -> Result<Container<T>, Error> {
+        span!(Sign);
 
         debug!("SK Sign/Encrypt (AEAD) with key: {} ({} bytes)", secret_key, self.n);
 
         let buf = self.buf.as_mut();
 
-        let (header, body) = buf[..self.n].split_at_mut(HEADER_LEN+ID_LEN);
+        if buf.len() < self.n + SIGNATURE_LEN {
+            return Err(Error::BufferLength);
+        }
+
+        let (header, body) = buf[..self.n].split_at_mut(offsets::BODY);
         let tag = Crypto::sk_encrypt(secret_key, Some(header), body).unwrap();
 
         debug!("MAC: {}", tag);
@@ -439,9 +673,51 @@ impl<T: MutableData> Builder<SetPublicOptions, T> {
     }
 
     // Provide an existing signature to the builder object
+    /// Sign the builder object using the provided [`Signer`], allowing signing keys to be held
+    /// externally (for example, in an HSM, secure element, or remote signer)
+    pub fn sign_with<S: Signer>(self, signer: &mut S) -> Result<Container<T>, Error> {
+        span!(Sign);
+        let id = self.signing_id()?;
+
+        let sig = signer.sign(&id, &self.buf.as_ref()[..self.n]).map_err(|e| {
+            error!("Signer error: {:?}", e);
+            Error::CryptoError
+        })?;
+
+        self.sign_raw(&sig)
+    }
+
+    /// Sign the builder object using the provided [`AsyncSigner`]
+    #[cfg(feature = "async")]
+    pub async fn sign_with_async<S: AsyncSigner>(self, signer: &mut S) -> Result<Container<T>, Error> {
+        let id = self.signing_id()?;
+
+        let sig = signer.sign(&id, &self.buf.as_ref()[..self.n]).await.map_err(|e| {
+            error!("Signer error: {:?}", e);
+            Error::CryptoError
+        })?;
+
+        self.sign_raw(&sig)
+    }
+
+    /// Fetch the object ID for signing, checking the buffer has space for the signature
+    fn signing_id(&self) -> Result<Id, Error> {
+        let b = self.buf.as_ref();
+
+        if b.len() < self.n + SIGNATURE_LEN {
+            return Err(Error::BufferLength);
+        }
+
+        Id::try_from(&b[offsets::ID..][..ID_LEN])
+    }
+
     pub fn sign_raw(mut self, sig: &Signature) -> Result<Container<T>, Error> {
         let b = self.buf.as_mut();
 
+        if b.len() < self.n + SIGNATURE_LEN {
+            return Err(Error::BufferLength);
+        }
+
         (&mut b[self.n..self.n + SIGNATURE_LEN]).copy_from_slice(&sig);
         self.n += SIGNATURE_LEN;
 
@@ -0,0 +1,193 @@
+//! Compression provides a pluggable compression layer for object bodies.
+//!
+//! Bodies are compressed prior to encryption and signing, with [`Flags::COMPRESSED`](crate::types::Flags::COMPRESSED)
+//! set in the object header so receivers know to decompress the body once decrypted.
+//! Backends implement [`Compressor`], with `deflate` available via the `deflate` feature.
+//! [`Container::body`](super::Container::body) decompresses transparently using deflate where
+//! enabled, with [`Container::body_with`](super::Container::body_with) and
+//! [`Container::decrypt_body_with`](super::Container::decrypt_body_with) accepting other backends.
+
+use crate::error::Error;
+
+/// Compressor trait for body compression backends
+pub trait Compressor {
+    /// Compress `data` into the provided buffer, returning the compressed length
+    fn compress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error>;
+
+    /// Decompress `data` into the provided buffer, returning the decompressed length
+    fn decompress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error>;
+}
+
+impl<C: Compressor> Compressor for &C {
+    fn compress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+        (*self).compress(data, buff)
+    }
+
+    fn decompress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+        (*self).decompress(data, buff)
+    }
+}
+
+/// Raw DEFLATE compression backend (via `miniz_oxide`)
+#[cfg(feature = "deflate")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deflate {
+    /// Compression level (0-10)
+    pub level: u8,
+}
+
+#[cfg(feature = "deflate")]
+impl Default for Deflate {
+    fn default() -> Self {
+        Self { level: 6 }
+    }
+}
+
+#[cfg(feature = "deflate")]
+impl Compressor for Deflate {
+    fn compress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+        let c = miniz_oxide::deflate::compress_to_vec(data, self.level);
+        if c.len() > buff.len() {
+            return Err(Error::BufferLength);
+        }
+
+        buff[..c.len()].copy_from_slice(&c);
+
+        Ok(c.len())
+    }
+
+    fn decompress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+        miniz_oxide::inflate::decompress_slice_iter_to_slice(buff, core::iter::once(data), false, true)
+            .map_err(|e| {
+                error!("Failed to decompress body: {:?}", e);
+                Error::CompressionFailed
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Minimal run-length compressor for testing
+    struct Rle;
+
+    impl Compressor for Rle {
+        fn compress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+            let mut n = 0;
+            for c in data.chunk_by(|a, b| a == b) {
+                for r in c.chunks(u8::MAX as usize) {
+                    if n + 2 > buff.len() {
+                        return Err(Error::BufferLength);
+                    }
+                    buff[n] = r.len() as u8;
+                    buff[n + 1] = r[0];
+                    n += 2;
+                }
+            }
+            Ok(n)
+        }
+
+        fn decompress(&self, data: &[u8], buff: &mut [u8]) -> Result<usize, Error> {
+            let mut n = 0;
+            for r in data.chunks(2) {
+                let (len, v) = match r {
+                    [len, v] => (*len as usize, *v),
+                    _ => return Err(Error::CompressionFailed),
+                };
+                if n + len > buff.len() {
+                    return Err(Error::BufferLength);
+                }
+                buff[n..][..len].fill(v);
+                n += len;
+            }
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn compress_decompress_rle() {
+        let data = [0u8; 100];
+        let mut c = [0u8; 16];
+        let mut d = [0u8; 128];
+
+        let n = Rle.compress(&data, &mut c).unwrap();
+        assert_eq!(n, 2);
+
+        let m = Rle.decompress(&c[..n], &mut d).unwrap();
+        assert_eq!(&d[..m], &data);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn publish_compressed_data() {
+        use crate::prelude::*;
+        use crate::service::{DataOptions, Publisher};
+        use crate::types::Flags;
+
+        let mut svc = ServiceBuilder::<Vec<u8>>::generic().encrypt().build().unwrap();
+        let keys = svc.keys();
+
+        let body = vec![0xaau8; 200];
+        let opts = DataOptions{ body: Some(body.clone()), ..Default::default() };
+
+        let (_n, d) = svc.publish_data_compressed(opts, &Rle, vec![0u8; 1024])
+            .expect("Failed to publish data object");
+        assert!(d.header().flags().contains(Flags::COMPRESSED));
+
+        // Compressed bodies are only available once decrypted
+        let mut c = Container::parse(d.raw().to_vec(), &keys).expect("Failed to parse data object");
+        let mut buff = [0u8; 256];
+        assert_eq!(c.decompress_body(&Rle, &mut buff).err(), Some(Error::NotDecrypted));
+        assert_eq!(c.body_with::<Vec<u8>, _>(&Rle), Err(Error::NotDecrypted));
+
+        // Or decrypted and decompressed into a buffer
+        let sk = keys.sec_key.as_ref().unwrap();
+        assert_eq!(c.decrypt_body_with(sk, &Rle, &mut buff).unwrap(), &body[..]);
+
+        c.decrypt(keys.sec_key.as_ref().unwrap()).expect("Failed to decrypt data object");
+        assert!(c.compressed());
+        assert!(c.body_raw().len() < body.len());
+        assert_eq!(c.decompress_body(&Rle, &mut buff).unwrap(), &body[..]);
+        assert_eq!(c.body_with::<Vec<u8>, _>(&Rle), Ok(body.clone()));
+
+        // Without a default compressor bodies cannot be decoded directly
+        #[cfg(not(feature = "deflate"))]
+        assert_eq!(c.body::<Vec<u8>>(), Err(Error::CompressionFailed));
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn compress_decompress_deflate() {
+        let data = b"{\"temp\": 21.5, \"temp\": 21.5, \"temp\": 21.5, \"temp\": 21.5}";
+        let mut c = [0u8; 128];
+        let mut d = [0u8; 128];
+
+        let n = Deflate::default().compress(data, &mut c).unwrap();
+        assert!(n < data.len());
+
+        let m = Deflate::default().decompress(&c[..n], &mut d).unwrap();
+        assert_eq!(&d[..m], &data[..]);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn publish_deflate_data() {
+        use crate::prelude::*;
+        use crate::service::{DataOptions, Publisher};
+
+        let mut svc = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let keys = svc.keys();
+
+        let body = vec![0xaau8; 200];
+        let opts = DataOptions{ body: Some(body.clone()), ..Default::default() };
+
+        let (_n, d) = svc.publish_data_compressed(opts, &Deflate::default(), vec![0u8; 1024])
+            .expect("Failed to publish data object");
+
+        // Deflate compressed bodies are decompressed transparently
+        let c = Container::parse(d.raw().to_vec(), &keys).expect("Failed to parse data object");
+        assert!(c.compressed());
+        assert_eq!(c.body::<Vec<u8>>(), Ok(body));
+    }
+}
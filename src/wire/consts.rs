@@ -0,0 +1,56 @@
+//! Fixed protocol constants for object encoding, with compile-time assertions
+//! over the object layout so changes to field lengths or offsets fail to build.
+//!
+//! Objects are encoded as:
+//!
+//! ```text
+//! | header (16) | id (32) | body | private options | [tag (40)] | public options | signature (64) |
+//! ```
+//!
+//! Objects with the `SPLIT_ENCRYPTION` flag carry separate body and private options tags (80).
+
+use crate::types::{HASH_LEN, ID_LEN, SECRET_KEY_TAG_LEN, SIGNATURE_LEN};
+
+/// Header object length
+pub const HEADER_LEN: usize = 16;
+
+/// Option header length (u16 kind, u16 length)
+pub const OPTION_HEADER_LEN: usize = 4;
+
+/// Minimum encoded object length (header, ID and signature)
+pub const MIN_OBJECT_LEN: usize = offsets::BODY + SIGNATURE_LEN;
+
+/// Offsets for fixed fields in the protocol header
+pub mod offsets {
+    use super::{HEADER_LEN, ID_LEN};
+
+    pub const PROTO_VERSION: usize = 0;
+    pub const APPLICATION_ID: usize = 2;
+    pub const OBJECT_KIND: usize = 4;
+    pub const FLAGS: usize = 6;
+    pub const INDEX: usize = 8;
+    pub const DATA_LEN: usize = 10;
+    pub const PRIVATE_OPTIONS_LEN: usize = 12;
+    pub const PUBLIC_OPTIONS_LEN: usize = 14;
+    pub const ID: usize = HEADER_LEN;
+    pub const BODY: usize = HEADER_LEN + ID_LEN;
+}
+
+// Header fields are contiguous u16s filling the fixed header
+const _: () = assert!(offsets::APPLICATION_ID == offsets::PROTO_VERSION + 2);
+const _: () = assert!(offsets::OBJECT_KIND == offsets::APPLICATION_ID + 2);
+const _: () = assert!(offsets::FLAGS == offsets::OBJECT_KIND + 2);
+const _: () = assert!(offsets::INDEX == offsets::FLAGS + 2);
+const _: () = assert!(offsets::DATA_LEN == offsets::INDEX + 2);
+const _: () = assert!(offsets::PRIVATE_OPTIONS_LEN == offsets::DATA_LEN + 2);
+const _: () = assert!(offsets::PUBLIC_OPTIONS_LEN == offsets::PRIVATE_OPTIONS_LEN + 2);
+const _: () = assert!(offsets::PUBLIC_OPTIONS_LEN + 2 == HEADER_LEN);
+
+// Object IDs are derived from public key hashes
+const _: () = assert!(HASH_LEN == ID_LEN);
+
+// Symmetric mode AEAD tags are carried in the signature field
+const _: () = assert!(SECRET_KEY_TAG_LEN <= SIGNATURE_LEN);
+
+// Section lengths are encoded as u16s
+const _: () = assert!(MIN_OBJECT_LEN < u16::MAX as usize);
@@ -4,18 +4,23 @@ use core::convert::TryFrom;
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
+use encdec::decode::DecodeOwned;
+
 use crate::Debug;
-use crate::base::PageBody;
+use crate::base::{Header, PageBody};
 use crate::crypto::{Crypto, PubKey as _, SecKey as _, Hash as _};
 use crate::page::PageInfo;
 use crate::{types::*};
 
-use crate::options::{Options, OptionsIter, Filters};
+use crate::options::{Options, OptionsIter, Filters, unordered_eq};
+use crate::keys::OptionKeySource;
 use crate::error::Error;
 
 use super::builder::Init;
-use super::header::WireHeader;
-use super::{offsets, HEADER_LEN};
+use super::compress::Compressor;
+use super::header::{check, WireHeader};
+use super::{offsets, HEADER_LEN, MIN_OBJECT_LEN};
+use super::expiry::ExpiryPolicy;
 
 use super::Builder;
 
@@ -81,17 +86,24 @@ impl <T: ImmutableData> core::fmt::Debug for Container<T> {
 
 /// Decode a container from the provided buffer
 /// 
-/// NOTE THIS DOES NOT PERFORM ANY VALIDATION
+/// NOTE THIS ONLY CHECKS HEADER LENGTHS AGAINST THE BUFFER AND DOES NOT PERFORM ANY VALIDATION
 impl <'a> encdec::Decode<'a> for Container<&'a [u8]> {
     type Output = Container<&'a [u8]>;
     type Error = Error;
 
     fn decode(buff: &'a[u8]) -> Result<(Self::Output, usize), Self::Error> {
+        if buff.len() < MIN_OBJECT_LEN {
+            return Err(Error::BufferLength);
+        }
+
         // Read header
         let header = WireHeader::new(buff);
 
         // Computed encoded object length
         let len = header.encoded_len();
+        if len > buff.len() {
+            return Err(Error::BufferLength);
+        }
 
         // Build container
         let c = Container { buff: &buff[..len], len, verified: false, decrypted: false };
@@ -178,33 +190,38 @@ impl<'de: 'a, 'a> serde::Deserialize<'de> for Container {
     }
 }
 
+/// Create a container over the provided buffer, checking the header (see [`check`](super::header::check))
 impl <'a> TryFrom<&'a [u8]> for Container<&'a [u8]> {
-    // TODO: check basic container info
-    type Error = ();
+    type Error = Error;
 
     fn try_from(buff: &'a [u8]) -> Result<Self, Self::Error> {
-        let h = WireHeader::new(buff);
-        let len = h.encoded_len();
-        
-        let c = Container { buff: &buff[..len], len, verified: false, decrypted: false };
-
-        Ok(c)
+        let len = check(buff)?;
+        Ok(Container { buff: &buff[..len], len, verified: false, decrypted: false })
     }
 }
 
+/// Create a container over the provided buffer, checking the header (see [`check`](super::header::check))
 impl TryFrom<Vec<u8>> for Container<Vec<u8>> {
-    // TODO: check basic container info
-    type Error = ();
+    type Error = Error;
 
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        let c = Container::from(value);
-        Ok(c.0)
+    fn try_from(mut buff: Vec<u8>) -> Result<Self, Self::Error> {
+        let len = check(&buff)?;
+        buff.truncate(len);
+        Ok(Container { buff, len, verified: false, decrypted: false })
     }
 }
 
 impl<'a, T: ImmutableData> Container<T> {
-    /// Create a new container object, providing field accessors over the provided buffer
-    // TODO: this should validate the container on creation to avoid invalid containers ever existing
+    /// Create a new container object over the provided buffer, checking the header
+    /// (see [`check`](super::header::check)) so field accessors can not exceed the buffer
+    pub fn checked(buff: T) -> Result<(Self, usize), Error> {
+        let len = check(buff.as_ref())?;
+        Ok((Container { buff, len, verified: false, decrypted: false }, len))
+    }
+
+    /// Create a new container object, providing field accessors over the provided buffer.
+    ///
+    /// NOTE THIS DOES NOT PERFORM ANY VALIDATION, see [`Container::checked`] for untrusted buffers
     pub fn from(buff: T) -> (Self, usize) {
         let len = buff.as_ref().len();
         let c = Container { buff, len, verified: false, decrypted: false };
@@ -221,18 +238,41 @@ impl<'a, T: ImmutableData> Container<T> {
         }
     }
 
+    /// Fetch a borrowed container over the same data
+    pub fn borrowed(&self) -> Container<&[u8]> {
+        Container{
+            buff: self.raw(), len: self.len, decrypted: self.decrypted, verified: self.verified
+        }
+    }
+
+    /// Fetch a region of the container buffer, returning [`Error::BufferLength`]
+    /// where this exceeds the buffer
+    fn region(&self, offset: usize, len: usize) -> Result<&[u8], Error> {
+        let data = self.buff.as_ref();
+
+        offset.checked_add(len)
+            .and_then(|end| data.get(offset..end))
+            .ok_or(Error::BufferLength)
+    }
+
     /// Fetch wire header
     pub fn header(&self) -> WireHeader<&[u8]> {
-        WireHeader {
-            buff: &self.buff.as_ref()[..HEADER_LEN],
-        }
+        self.try_header().unwrap()
+    }
+
+    /// Fetch wire header, returning [`Error::BufferLength`] where the buffer is too short
+    pub fn try_header(&self) -> Result<WireHeader<&[u8]>, Error> {
+        self.region(0, HEADER_LEN).map(WireHeader::new)
     }
 
     /// Fetch object ID
     pub fn id_raw(&self) -> &[u8] {
-        let data = self.buff.as_ref();
+        self.try_id_raw().unwrap()
+    }
 
-        &data[offsets::ID..offsets::BODY]
+    /// Fetch object ID, returning [`Error::BufferLength`] where the buffer is too short
+    pub fn try_id_raw(&self) -> Result<&[u8], Error> {
+        self.region(offsets::ID, ID_LEN)
     }
 
     /// Fetch object ID
@@ -246,51 +286,111 @@ impl<'a, T: ImmutableData> Container<T> {
 
     /// Return the body of data
     pub fn body_raw(&self) -> &[u8] {
-        let data = self.buff.as_ref();
+        self.try_body_raw().unwrap()
+    }
 
-        let s = self.header().data_len();
-        &data[offsets::BODY..][..s]
+    /// Return the body of data, returning [`Error::BufferLength`] where header lengths exceed the buffer
+    pub fn try_body_raw(&self) -> Result<&[u8], Error> {
+        let h = self.try_header()?;
+        self.region(h.data_offset(), h.data_len())
+    }
+
+    /// Check whether the object body is compressed
+    pub fn compressed(&self) -> bool {
+        self.header().flags().contains(Flags::COMPRESSED)
+    }
+
+    /// Decompress the object body into the provided buffer using the provided [`Compressor`],
+    /// copying the body directly where the object is not compressed.
+    /// NOTE: ONLY VALID FOR DECRYPTED OBJECTS
+    pub fn decompress_body<'b, C: Compressor>(&self, compressor: &C, buff: &'b mut [u8]) -> Result<&'b [u8], Error> {
+        if self.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
+
+        decompress_to(self.compressed(), compressor, self.try_body_raw()?, buff)
     }
 
-    /// Fetch page body type as appropriate
-    pub fn body<B: PageBody>(&self) -> Result<B, Error> {
-        todo!()
+    /// Decode the object body as the provided body type.
+    /// Encrypted objects must be decrypted prior to decoding.
+    ///
+    /// Compressed bodies are decompressed using [`Deflate`](super::compress::Deflate) where the
+    /// `deflate` feature is enabled, see [`Container::body_with`] for other compressors.
+    pub fn body<B: PageBody + DecodeOwned<Output = B>>(&self) -> Result<B, Error> {
+        if self.compressed() {
+            #[cfg(feature = "deflate")]
+            return self.body_with(&super::compress::Deflate::default());
+
+            #[cfg(not(feature = "deflate"))]
+            {
+                debug!("No default compressor, body_with or decompress_body required");
+                return Err(Error::CompressionFailed);
+            }
+        }
+
+        if self.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
+
+        decode_body(self.try_body_raw()?)
+    }
+
+    /// Decode the object body as the provided body type, decompressing
+    /// compressed bodies with the provided [`Compressor`].
+    /// Encrypted objects must be decrypted prior to decoding.
+    #[cfg(feature = "alloc")]
+    pub fn body_with<B: PageBody + DecodeOwned<Output = B>, C: Compressor>(&self, compressor: &C) -> Result<B, Error> {
+        if !self.compressed() {
+            return self.body();
+        }
+
+        let mut buff = vec![0u8; super::limits::DEFAULT_MAX_BODY_LEN];
+        let body = self.decompress_body(compressor, &mut buff)?;
+
+        decode_body(body)
     }
 
     /// Return the private options section data, note this may be encrypted
     pub fn private_options_raw(&self) -> &[u8] {
-        let data = self.buff.as_ref();
+        self.try_private_options_raw().unwrap()
+    }
+
+    /// Return the private options section data, returning [`Error::BufferLength`] where header lengths exceed the buffer
+    pub fn try_private_options_raw(&self) -> Result<&[u8], Error> {
+        let h = self.try_header()?;
+        self.region(h.private_options_offset(), h.private_options_len())
+    }
+
+    /// Decode the private options section as the provided type.
+    /// Encrypted objects must be decrypted prior to decoding.
+    pub fn private_options<P: DecodeOwned<Output = P>>(&self) -> Result<P, Error> {
+        if self.encrypted() {
+            return Err(Error::NotDecrypted);
+        }
 
-        let n = offsets::BODY + self.header().data_len();
-        let s = self.header().private_options_len();
-        &data[n..n + s]
+        let (options, _n) = P::decode(self.try_private_options_raw()?).map_err(|e| {
+            debug!("Failed to decode private options: {:?}", e);
+            Error::EncodeFailed
+        })?;
+
+        Ok(options)
     }
 
     /// Iterate over private options
     /// NOTE: ONLY VALID FOR DECRYPTED OBJECTS
     pub fn private_options_iter(&self) -> impl Iterator<Item = Options> + Clone + '_ {
-        let data = self.buff.as_ref();
-        let n = offsets::BODY + self.header().data_len();
-        let s = self.header().private_options_len();
-
-        OptionsIter::new(&data[n..n + s])
+        OptionsIter::new(self.private_options_raw())
     }
 
     /// Return public options section data
     pub fn public_options_raw(&self) -> &[u8] {
-        let h = self.header();
-        let data = self.buff.as_ref();
-
-        let tag_len = if h.flags().contains(Flags::ENCRYPTED) && !h.flags().contains(Flags::SYMMETRIC_MODE) {
-            SECRET_KEY_TAG_LEN
-        } else {
-            0
-        };
+        self.try_public_options_raw().unwrap()
+    }
 
-        let n = offsets::BODY + h.data_len()
-                + h.private_options_len() + tag_len;
-        let s = h.public_options_len();
-        &data[n..n + s]
+    /// Return public options section data, returning [`Error::BufferLength`] where header lengths exceed the buffer
+    pub fn try_public_options_raw(&self) -> Result<&[u8], Error> {
+        let h = self.try_header()?;
+        self.region(h.public_options_offset(), h.public_options_len())
     }
 
     /// Ciphertext for encrypted objects (body + private options fields)
@@ -302,58 +402,78 @@ impl<'a, T: ImmutableData> Container<T> {
         &data[offsets::BODY..][..s]
     }
 
-    /// Tag for secret key encryption
+    /// Tag(s) for secret key encryption
     pub fn tag_raw(&self) -> Option<&[u8]> {
-        let h = self.header();
-        let data = self.buff.as_ref();
-
-        if !h.flags().contains(Flags::ENCRYPTED) || h.flags().contains(Flags::SYMMETRIC_MODE) {
-            return None;
-        }
+        self.try_tag_raw().unwrap()
+    }
 
-        let n = HEADER_LEN + ID_LEN + h.data_len()
-                + h.private_options_len();
+    /// Tag(s) for secret key encryption, returning [`Error::BufferLength`] where header lengths exceed the buffer
+    pub fn try_tag_raw(&self) -> Result<Option<&[u8]>, Error> {
+        let h = self.try_header()?;
 
-        Some(&data[n..n + SECRET_KEY_TAG_LEN])
+        match h.flags().tag_len() {
+            0 => Ok(None),
+            s => self.region(h.tag_offset(), s).map(Some),
+        }
     }
 
+    /// Tag for secret key encryption, covering the body and private options
+    /// (or only the body for [`Flags::SPLIT_ENCRYPTION`] objects)
     pub fn tag(&self) -> Option<SecretMeta> {
-        self.tag_raw().map(|d| SecretMeta::try_from(d).ok() ).flatten()
+        self.tag_raw().map(|d| SecretMeta::try_from(&d[..SECRET_KEY_TAG_LEN]).ok() ).flatten()
     }
 
-    /// Return the public options section data
-    pub fn public_options_iter(&self) -> OptionsIter<&[u8]> {
-        let data = self.buff.as_ref();
-        let header = self.header();
-
-        let tag_len = if header.flags().contains(Flags::ENCRYPTED) && !header.flags().contains(Flags::SYMMETRIC_MODE) {
-            SECRET_KEY_TAG_LEN
-        } else {
-            0
-        };
+    /// Tag for secret key encryption of private options,
+    /// only present for [`Flags::SPLIT_ENCRYPTION`] objects
+    pub fn private_options_tag(&self) -> Option<SecretMeta> {
+        if !self.header().flags().contains(Flags::SPLIT_ENCRYPTION) {
+            return None;
+        }
 
-        let n = HEADER_LEN + ID_LEN + header.data_len() + header.private_options_len() + tag_len;
-        let s = header.public_options_len();
+        self.tag_raw().map(|d| SecretMeta::try_from(&d[SECRET_KEY_TAG_LEN..]).ok() ).flatten()
+    }
 
-        OptionsIter::new(&data[n..n + s])
+    /// Return the public options section data
+    pub fn public_options_iter(&self) -> OptionsIter<&[u8]> {
+        OptionsIter::new(self.public_options_raw())
     }
 
     /// Return the signed portion of the message for signing or verification
     pub fn signed(&self) -> &[u8] {
-        let data = self.buff.as_ref();
-        let n = self.len();
+        self.try_signed().unwrap()
+    }
 
-        &data[..n - SIGNATURE_LEN]
+    /// Return the signed portion of the message, returning [`Error::BufferLength`] where header lengths exceed the buffer
+    pub fn try_signed(&self) -> Result<&[u8], Error> {
+        let h = self.try_header()?;
+        self.region(0, h.signature_offset())
+    }
+
+    /// Digest covered by writer counter-signatures (see [`Options::CounterSig`]).
+    ///
+    /// This covers the header (excluding the public options length), ID, body, private options and tag(s),
+    /// allowing counter-signatures to be attached as public options without invalidating one another.
+    ///
+    /// Returns [`Error::BufferLength`] where header lengths exceed the buffer.
+    pub fn counter_sign_digest(&self) -> Result<CryptoHash, Error> {
+        let n = self.try_header()?.public_options_offset();
+
+        let head = self.region(0, offsets::PUBLIC_OPTIONS_LEN)?;
+        let signed = self.region(offsets::ID, n - offsets::ID)?;
+
+        Crypto::hash_iter(core::iter::once(head).chain(core::iter::once(signed)))
+            .map_err(|_| Error::CryptoError)
     }
 
     /// Return the signature portion of the message for verification
     pub fn signature_raw(&self) -> &[u8] {
-        let data = self.buff.as_ref();
-
-        let n = self.len() - SIGNATURE_LEN;
-        let s = SIGNATURE_LEN;
+        self.try_signature_raw().unwrap()
+    }
 
-        &data[n..n + s]
+    /// Return the signature portion of the message, returning [`Error::BufferLength`] where header lengths exceed the buffer
+    pub fn try_signature_raw(&self) -> Result<&[u8], Error> {
+        let h = self.try_header()?;
+        self.region(h.signature_offset(), SIGNATURE_LEN)
     }
 
     /// Fetch the message signature
@@ -365,19 +485,12 @@ impl<'a, T: ImmutableData> Container<T> {
     /// Return the total length of the object (from the header)
     pub fn len(&self) -> usize {
         let header = self.header();
-        let flags = header.flags();
-
-        let tag_len = if flags.contains(Flags::ENCRYPTED) && !flags.contains(Flags::SYMMETRIC_MODE) {
-            SECRET_KEY_TAG_LEN
-        } else {
-            0
-        };
 
         HEADER_LEN
             + ID_LEN
             + header.data_len()
             + header.private_options_len()
-            + tag_len
+            + header.flags().tag_len()
             + header.public_options_len()
             + SIGNATURE_LEN
     }
@@ -397,18 +510,29 @@ impl<'a, T: ImmutableData> Container<T> {
 
     /// Fetch the raw data using internal header length
     pub fn raw(&self) -> &[u8] {
-        let data = self.buff.as_ref();
-        let len = self.len();
+        self.try_raw().unwrap()
+    }
 
-        &data[0..len]
+    /// Fetch the raw data using internal header length, returning [`Error::BufferLength`] where this exceeds the buffer
+    pub fn try_raw(&self) -> Result<&[u8], Error> {
+        let h = self.try_header()?;
+        self.region(0, h.encoded_len())
     }
 
     /// Fetch page info from a container (filters body and options as required)
-    pub fn info(&self) -> Result<PageInfo, Error> {
+    pub fn info(&self) -> Result<PageInfo<'_>, Error> {
         let (kind, flags) = (self.header().kind(), self.header().flags());
 
-        let info = if kind.is_page() && !flags.contains(Flags::SECONDARY) && !flags.contains(Flags::TERTIARY) {
-            // Handle primary page parsing
+        let is_primary = !flags.contains(Flags::SECONDARY) && !flags.contains(Flags::TERTIARY);
+        let is_private = kind.is_page() && kind.index() == PageKind::Private as u16;
+
+        let info = if is_private && !(is_primary && self.public_options_iter().pub_key().is_some()) {
+            // Private pages are passed through without parsing, validation is limited to signatures
+            PageInfo::private(self.body_raw())
+
+        } else if kind.is_page() && is_primary {
+            // Handle primary page parsing, including primary pages for private services
+            // which carry a public key with an encrypted body
 
             // Fetch public key from options
             let public_key: PublicKey = match self.public_options_iter().pub_key() {
@@ -417,8 +541,7 @@ impl<'a, T: ImmutableData> Container<T> {
             };
 
             // Check public key and ID match
-            let hash: Id = Id::from(Crypto::hash(&public_key).unwrap().as_bytes());
-            if &hash != &self.id() {
+            if Id::from_public_key(&public_key) != self.id() {
                 return Err(Error::KeyIdMismatch);
             }
 
@@ -451,6 +574,12 @@ impl<'a, T: ImmutableData> Container<T> {
                 None => Err(Error::NoPeerId),
             }?;
 
+            // Encrypted tertiary pages (for private namespaces) are opaque without the
+            // namespace secret key, see [`crate::service::Registry::resolve_link`]
+            if self.encrypted() {
+                return Ok(PageInfo::private(self.body_raw()));
+            }
+
             match PageKind::try_from(kind.index()) {
                 Ok(PageKind::ServiceLink) => {
                     let target_id = Id::try_from(self.body_raw())?;
@@ -473,30 +602,68 @@ impl<'a, T: ImmutableData> Container<T> {
         Ok(info)
     }
 
-    /// Check whether an object has expired
+    /// Check whether an object has expired using the default [`ExpiryPolicy`] and system time
     #[cfg(feature = "std")]
     pub fn expired(&self) -> bool {
-        use core::ops::Add;
-
-        // Convert issued and expiry times
-        let (issued, expiry): (Option<std::time::SystemTime>, Option<std::time::SystemTime>) = (
-            self.public_options_iter().issued().map(|v| v.into()),
-            self.public_options_iter().expiry().map(|v| v.into()),
-        );
-
-        // Compute validity
-        match (issued, expiry) {
-            // For fixed expiry, use this
-            (_, Some(expiry)) => std::time::SystemTime::now() > expiry,
-            // For no expiry, use 1h
-            (Some(issued), None) => {
-                std::time::SystemTime::now() > issued.add(std::time::Duration::from_secs(3600))
-            }
-            // Otherwise default non-expiring..?
-            // TODO: should we even allow services _without_ valid time records?
-            // how to approach this for non-timesync'd devices?
-            _ => false,
+        self.expired_with(&ExpiryPolicy::default(), &SystemClock)
+    }
+
+    /// Check whether an object has expired using the provided policy and clock
+    pub fn expired_with<C: Clock>(&self, policy: &ExpiryPolicy, clock: &C) -> bool {
+        policy.expired(self, clock)
+    }
+
+    /// Convert the object kind to an application-defined kind, checking the application ID
+    pub fn app_kind<K: KindConvert>(&self) -> Result<K, KindError> {
+        let h = self.header();
+        K::try_from_kind(h.application_id(), h.kind())
+    }
+
+    /// Compare containers for semantic equivalence, independent of option ordering.
+    ///
+    /// This compares header fields, ID, body, and public options, as well as private options
+    /// where both objects are decrypted (or the raw ciphertext and tag where both are encrypted).
+    /// Signatures are only compared where the signed data is identical, as reordering options
+    /// necessarily results in differing signatures.
+    pub fn semantic_eq<U: ImmutableData>(&self, other: &Container<U>) -> bool {
+        let (a, b) = (self.header(), other.header());
+
+        // Compare header fields (excluding option lengths)
+        if a.protocol_version() != b.protocol_version()
+                || a.application_id() != b.application_id()
+                || a.kind() != b.kind()
+                || a.flags() != b.flags()
+                || a.index() != b.index() {
+            return false;
         }
+
+        // Compare ID and body
+        if self.id_raw() != other.id_raw() || self.body_raw() != other.body_raw() {
+            return false;
+        }
+
+        // Compare private options
+        match (self.encrypted(), other.encrypted()) {
+            (false, false) => if !unordered_eq(self.private_options_iter(), other.private_options_iter()) {
+                return false;
+            },
+            (true, true) => if self.private_options_raw() != other.private_options_raw() || self.tag_raw() != other.tag_raw() {
+                return false;
+            },
+            _ => return false,
+        }
+
+        // Compare public options
+        if !unordered_eq(self.public_options_iter(), other.public_options_iter()) {
+            return false;
+        }
+
+        // Compare signatures where signed data is identical
+        if self.signed() == other.signed() && self.signature_raw() != other.signature_raw() {
+            return false;
+        }
+
+        true
     }
 }
 
@@ -506,6 +673,74 @@ impl<'a, T: MutableData> Container<T> {
         Builder::new(buff)
     }
 
+    /// Assemble a container from previously encoded parts (for example, where the body and options
+    /// are stored separately), copying raw regions into the provided buffer without re-encoding.
+    ///
+    /// Header lengths are computed from the provided regions, and tag(s) must match the header flags.
+    /// Cleartext options sections are checked to decode in full, however the signature is not
+    /// verified, so assembled containers should be parsed or verified prior to use.
+    #[allow(clippy::too_many_arguments)]
+    pub fn assemble(
+        mut buff: T,
+        header: &Header,
+        id: &Id,
+        body_raw: &[u8],
+        private_raw: &[u8],
+        tag: Option<&[u8]>,
+        public_raw: &[u8],
+        sig: &Signature,
+    ) -> Result<Container<T>, Error> {
+        let flags = header.flags();
+        flags.validate(header.kind())?;
+
+        // Check tag(s) match flags
+        let tag = tag.unwrap_or(&[]);
+        if tag.len() != flags.tag_len() {
+            debug!("Tag length mismatch (expected: {} actual: {})", flags.tag_len(), tag.len());
+            return Err(Error::InvalidFlags);
+        }
+
+        // Check section lengths fit the header fields
+        if body_raw.len() > u16::MAX as usize
+                || private_raw.len() > u16::MAX as usize
+                || public_raw.len() > u16::MAX as usize {
+            return Err(Error::InvalidPageLength);
+        }
+
+        // Check cleartext options decode in full
+        let encrypted = flags.contains(Flags::ENCRYPTED);
+        if !encrypted {
+            check_options_raw(private_raw)?;
+        }
+        if !(encrypted && flags.contains(Flags::SYMMETRIC_MODE)) {
+            check_options_raw(public_raw)?;
+        }
+
+        let len = HEADER_LEN + ID_LEN + body_raw.len() + private_raw.len()
+            + tag.len() + public_raw.len() + SIGNATURE_LEN;
+
+        let b = buff.as_mut();
+        if b.len() < len {
+            return Err(Error::BufferLength);
+        }
+
+        // Write header
+        let mut h = WireHeader::new(&mut b[..HEADER_LEN]);
+        h.encode(header);
+        h.set_data_len(body_raw.len());
+        h.set_private_options_len(private_raw.len());
+        h.set_public_options_len(public_raw.len());
+
+        // And object regions
+        let mut n = offsets::ID;
+        for r in [id.as_ref(), body_raw, private_raw, tag, public_raw, sig.as_ref()] {
+            b[n..][..r.len()].copy_from_slice(r);
+            n += r.len();
+        }
+
+        Ok(Container { buff, len, decrypted: false, verified: false })
+    }
+
     pub fn cyphertext_mut(&mut self) -> &mut [u8] {
         let s = self.header().data_len() + self.header().private_options_len();
         let data = self.buff.as_mut();
@@ -516,6 +751,7 @@ impl<'a, T: MutableData> Container<T> {
     /// Decrypt private fields within an object (in place)
     pub fn decrypt(&mut self, sk: &SecretKey) -> Result<(), Error> {
         // TODO: skip if body + private options are empty...
+        span!(Decrypt);
         debug!("SK Decrypt body with key: {}", sk);
 
         // Check we're encrypted
@@ -533,6 +769,22 @@ impl<'a, T: MutableData> Container<T> {
             },
         };
 
+        // Split encryption objects decrypt body and private options separately
+        if let Some(opts_tag) = self.private_options_tag() {
+            let n = self.header().data_len();
+            let (body, opts) = self.cyphertext_mut().split_at_mut(n);
+
+            if Crypto::sk_decrypt(sk, &tag, None, body).is_err()
+                    || Crypto::sk_decrypt(sk, &opts_tag, None, opts).is_err() {
+                debug!("Signature verification failed");
+                return Err(Error::InvalidSignature);
+            }
+
+            self.decrypted = true;
+
+            return Ok(());
+        }
+
         // Perform decryption
         let c = self.cyphertext_mut();
         if let Err(_) = Crypto::sk_decrypt(sk, &tag, None, c) {
@@ -547,7 +799,8 @@ impl<'a, T: MutableData> Container<T> {
 
     /// Decrypt a symmetric mode AEAD message
     pub fn sk_decrypt(&mut self, secret_key: &SecretKey) -> Result<(), Error> {
-        
+        span!(Decrypt);
+
         let sig_index = {
             let h = self.header();
             offsets::BODY + h.data_len() + h.private_options_len() + h.public_options_len()
@@ -559,9 +812,9 @@ impl<'a, T: MutableData> Container<T> {
 
         let buff = self.buff.as_mut();
 
-        let (header, body) = buff[..sig_index].split_at_mut(HEADER_LEN+ID_LEN);
+        let (header, body) = buff[..sig_index].split_at_mut(offsets::BODY);
 
-        if let Err(e) = Crypto::sk_decrypt(secret_key, &sig[..40], Some(header), body) {
+        if let Err(e) = Crypto::sk_decrypt(secret_key, &sig[..SECRET_KEY_TAG_LEN], Some(header), body) {
             warn!("Failed AEAD decryption: {:?}", e);
             return Err(Error::CryptoError)
         }
@@ -577,6 +830,8 @@ impl<'a, T: ImmutableData> Container<T> {
 
     // Decrypt data and private options into the provided buffer
     pub fn decrypt_to<'b>(&self, sk: &SecretKey, buff: &'b mut [u8]) -> Result<(&'b [u8], &'b [u8]), Error> {
+        span!(Decrypt);
+
         // Check we're encrypted
         if !self.header().flags().contains(Flags::ENCRYPTED) || self.decrypted {
             return Err(Error::InvalidSignature)
@@ -592,18 +847,114 @@ impl<'a, T: ImmutableData> Container<T> {
         let c = self.cyphertext();
         buff[..c.len()].copy_from_slice(c);
 
+        match self.private_options_tag() {
+            Some(opts_tag) => {
+                let (body, opts) = buff[..c.len()].split_at_mut(self.header().data_len());
+
+                Crypto::sk_decrypt(sk, &tag, None, body)
+                    .map_err(|_e| Error::InvalidSignature)?;
+                Crypto::sk_decrypt(sk, &opts_tag, None, opts)
+                    .map_err(|_e| Error::InvalidSignature)?;
+            },
+            None => {
+                Crypto::sk_decrypt(sk, &tag, None, &mut buff[..c.len()])
+                    .map_err(|_e| Error::InvalidSignature)?;
+            }
+        }
+
+        let (body, opts) = buff[..c.len()].split_at(self.header().data_len());
+
+        Ok((body, opts))
+    }
+
+    /// Decrypt only the object body into the provided buffer.
+    ///
+    /// For [`Flags::SPLIT_ENCRYPTION`] objects this skips decryption of private options,
+    /// otherwise the full cyphertext must be decrypted.
+    pub fn decrypt_body_to<'b>(&self, sk: &SecretKey, buff: &'b mut [u8]) -> Result<&'b [u8], Error> {
+        if !self.header().flags().contains(Flags::SPLIT_ENCRYPTION) {
+            return self.decrypt_to(sk, buff).map(|(body, _opts)| body);
+        }
+
+        span!(Decrypt);
+
+        let tag = match (self.encrypted(), self.tag()) {
+            (true, Some(t)) => t,
+            _ => return Err(Error::InvalidSignature),
+        };
+
+        let c = self.body_raw();
+        buff[..c.len()].copy_from_slice(c);
+
+        Crypto::sk_decrypt(sk, &tag, None, &mut buff[..c.len()])
+            .map_err(|_e| Error::InvalidSignature)?;
+
+        Ok(&buff[..c.len()])
+    }
+
+    /// Decrypt only the object body into the provided buffer, decompressing
+    /// compressed bodies with the provided [`Compressor`].
+    #[cfg(feature = "alloc")]
+    pub fn decrypt_body_with<'b, C: Compressor>(&self, sk: &SecretKey, compressor: &C, buff: &'b mut [u8]) -> Result<&'b [u8], Error> {
+        let mut b = vec![0u8; self.cyphertext().len()];
+        let body = self.decrypt_body_to(sk, &mut b)?;
+
+        decompress_to(self.compressed(), compressor, body, buff)
+    }
+
+    /// Decrypt only the private options into the provided buffer.
+    ///
+    /// For [`Flags::SPLIT_ENCRYPTION`] objects this skips decryption of the body,
+    /// otherwise the full cyphertext must be decrypted.
+    pub fn decrypt_private_options_to<'b>(&self, sk: &SecretKey, buff: &'b mut [u8]) -> Result<&'b [u8], Error> {
+        if !self.header().flags().contains(Flags::SPLIT_ENCRYPTION) {
+            return self.decrypt_to(sk, buff).map(|(_body, opts)| opts);
+        }
+
+        span!(Decrypt);
+
+        let tag = match (self.encrypted(), self.private_options_tag()) {
+            (true, Some(t)) => t,
+            _ => return Err(Error::InvalidSignature),
+        };
+
+        let c = self.private_options_raw();
+        buff[..c.len()].copy_from_slice(c);
+
         Crypto::sk_decrypt(sk, &tag, None, &mut buff[..c.len()])
             .map_err(|_e| Error::InvalidSignature)?;
 
-        Ok((
-            &buff[..self.header().data_len()],
-            &buff[self.header().private_options_offset()..][..self.header().private_options_len()],
-        ))
+        Ok(&buff[..c.len()])
     }
 
+    /// Open sealed public options for which subkeys are available, skipping other
+    /// sealed options, see [`SealedOption`](crate::options::SealedOption)
+    #[cfg(feature = "alloc")]
+    pub fn open_sealed<K: OptionKeySource>(&self, keys: &K) -> Result<Vec<Options>, Error> {
+        let mut opened = Vec::new();
+
+        for o in self.public_options_iter() {
+            if let Options::Sealed(s) = o {
+                if let Some(k) = keys.option_key(s.kind) {
+                    opened.push(s.open(&k)?);
+                }
+            }
+        }
+
+        Ok(opened)
+    }
 }
 
 
+/// Check a raw options section decodes in full
+fn check_options_raw(mut d: &[u8]) -> Result<(), Error> {
+    while !d.is_empty() {
+        let (_o, n) = <Options as encdec::Decode>::decode(d)?;
+        d = &d[n..];
+    }
+    Ok(())
+}
+
 impl<'a, T: ImmutableData> AsRef<[u8]> for  Container<T> {
     fn as_ref(&self) -> &[u8] {
         let n = self.len;
@@ -630,4 +981,27 @@ impl <T: MutableData> core::ops::DerefMut for Container<T> {
 
         &mut data[..len]
     }
-}
\ No newline at end of file
+}
+/// Decompress (or copy where not compressed) a body into the provided buffer
+fn decompress_to<'b, C: Compressor>(compressed: bool, compressor: &C, body: &[u8], buff: &'b mut [u8]) -> Result<&'b [u8], Error> {
+    let n = match compressed {
+        true => compressor.decompress(body, buff)?,
+        false if body.len() > buff.len() => return Err(Error::BufferLength),
+        false => {
+            buff[..body.len()].copy_from_slice(body);
+            body.len()
+        }
+    };
+
+    Ok(&buff[..n])
+}
+
+/// Decode a (decrypted and decompressed) body
+fn decode_body<B: PageBody + DecodeOwned<Output = B>>(body: &[u8]) -> Result<B, Error> {
+    let (body, _n) = B::decode(body).map_err(|e| {
+        debug!("Failed to decode body: {:?}", e);
+        Error::EncodeFailed
+    })?;
+
+    Ok(body)
+}
@@ -0,0 +1,201 @@
+//! Diff provides structured comparison of page versions for CLIs, UIs and debugging,
+//! reporting option, body, version and issued / expiry changes between two pages of a service.
+
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::options::{Options, OptionsIter, Filters};
+use crate::types::*;
+
+use super::Container;
+
+/// Change in an optional field between page versions
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldChange<T> {
+    /// Value in the previous page
+    pub from: Option<T>,
+    /// Value in the updated page
+    pub to: Option<T>,
+}
+
+/// Options added and removed between page versions
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct OptionsDiff {
+    /// Options present only in the updated page
+    pub added: Vec<Options>,
+    /// Options present only in the previous page
+    pub removed: Vec<Options>,
+}
+
+impl OptionsDiff {
+    /// Compute the (unordered) difference between two option sets, excluding options
+    /// reported separately or expected to change with each version (issued, expiry and previous signature)
+    fn new(from: impl Iterator<Item=Options>, to: impl Iterator<Item=Options>) -> Self {
+        let filter = |o: &Options| !matches!(o, Options::Issued(_) | Options::Expiry(_) | Options::PrevSig(_));
+
+        let mut removed: Vec<_> = from.filter(filter).collect();
+        let mut added = Vec::new();
+
+        for o in to.filter(filter) {
+            match removed.iter().position(|r| r == &o) {
+                Some(i) => { removed.remove(i); },
+                None => added.push(o),
+            }
+        }
+
+        Self { added, removed }
+    }
+
+    /// Check whether any options have changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Structured difference between two versions of a page
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageDiff {
+    /// Service ID
+    pub id: Id,
+    /// Previous page version (index)
+    pub from_version: u16,
+    /// Updated page version (index)
+    pub to_version: u16,
+    /// Whether the page body has changed, `None` where this can not be determined
+    /// (encrypted pages without a secret key)
+    pub body_changed: Option<bool>,
+    /// Public option changes
+    pub public_options: OptionsDiff,
+    /// Private option changes, `None` for encrypted pages without a secret key
+    pub private_options: Option<OptionsDiff>,
+    /// Issued time change
+    pub issued: Option<FieldChange<DateTime>>,
+    /// Expiry time change
+    pub expiry: Option<FieldChange<DateTime>>,
+}
+
+impl PageDiff {
+    /// Version delta between pages
+    pub fn version_delta(&self) -> i32 {
+        self.to_version as i32 - self.from_version as i32
+    }
+
+    /// Check whether pages differ in anything other than version
+    pub fn is_empty(&self) -> bool {
+        self.body_changed != Some(true)
+            && self.public_options.is_empty()
+            && self.private_options.as_ref().map(|o| o.is_empty()).unwrap_or(true)
+            && self.issued.is_none()
+            && self.expiry.is_none()
+    }
+}
+
+/// Decrypted (or cleartext) body and private options for diffing
+fn plaintext<T: ImmutableData>(c: &Container<T>, sk: Option<&SecretKey>) -> Result<Option<(Vec<u8>, Vec<u8>)>, Error> {
+    if !c.encrypted() {
+        return Ok(Some((c.body_raw().to_vec(), c.private_options_raw().to_vec())));
+    }
+
+    let sk = match sk {
+        Some(sk) => sk,
+        None => return Ok(None),
+    };
+
+    let mut buff = alloc::vec![0u8; c.cyphertext().len()];
+    let (body, opts) = c.decrypt_to(sk, &mut buff)?;
+
+    Ok(Some((body.to_vec(), opts.to_vec())))
+}
+
+fn field_change<T: PartialEq>(from: Option<T>, to: Option<T>) -> Option<FieldChange<T>> {
+    match from == to {
+        true => None,
+        false => Some(FieldChange{ from, to }),
+    }
+}
+
+impl <T: ImmutableData> Container<T> {
+    /// Compute the difference from this page to an updated page of the same service.
+    ///
+    /// Encrypted pages are decrypted for comparison where a secret key is provided,
+    /// otherwise body and private option changes are reported as unknown.
+    pub fn diff<U: ImmutableData>(&self, updated: &Container<U>, sk: Option<&SecretKey>) -> Result<PageDiff, Error> {
+        if self.id() != updated.id() {
+            return Err(Error::UnexpectedServiceId);
+        }
+
+        let (a, b) = (self.header(), updated.header());
+        if !a.kind().is_page() || a.kind() != b.kind() {
+            return Err(Error::UnexpectedPageKind);
+        }
+
+        let (from, to) = (plaintext(self, sk)?, plaintext(updated, sk)?);
+
+        let (body_changed, private_options) = match (&from, &to) {
+            (Some(f), Some(t)) => (
+                Some(f.0 != t.0),
+                Some(OptionsDiff::new(OptionsIter::new(&f.1[..]), OptionsIter::new(&t.1[..]))),
+            ),
+            _ => (None, None),
+        };
+
+        let (from_public, to_public) = (self.public_options_iter(), updated.public_options_iter());
+
+        Ok(PageDiff {
+            id: self.id(),
+            from_version: a.index(),
+            to_version: b.index(),
+            body_changed,
+            issued: field_change(from_public.issued(), to_public.issued()),
+            expiry: field_change(from_public.expiry(), to_public.expiry()),
+            public_options: OptionsDiff::new(from_public, to_public),
+            private_options,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{Publisher, PrimaryOptions, ServiceBuilder};
+    use crate::test_utils;
+
+    #[test]
+    fn diff_page_versions() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let a = test_utils::primary_page(&mut s);
+
+        // Pages with updated options report changes
+        s.update(|_body, public_options, _private_options| {
+            public_options.push(Options::name("updated"));
+        }).unwrap();
+        let (_n, b) = s.publish_primary_buff(PrimaryOptions::default()).unwrap();
+
+        let d = a.diff(&b, None).unwrap();
+        assert_eq!(d.id, s.id());
+        assert_eq!((d.from_version, d.to_version), (a.header().index(), b.header().index()));
+        assert_eq!(d.version_delta(), 2);
+        assert_eq!(d.body_changed, Some(false));
+        assert_eq!(d.public_options.added, vec![Options::name("updated")]);
+        assert!(d.public_options.removed.is_empty());
+        assert_eq!(d.private_options, Some(OptionsDiff::default()));
+
+        // Pages from other services are rejected
+        let mut other = test_utils::service(test_utils::PEER_SEED);
+        let c = test_utils::primary_page(&mut other);
+        assert_eq!(a.diff(&c, None), Err(Error::UnexpectedServiceId));
+
+        // Encrypted pages are compared where a secret key is provided
+        let mut e = ServiceBuilder::<Vec<u8>>::generic().encrypt().body(vec![0xaa; 8]).build().unwrap();
+        let sk = e.secret_key().unwrap();
+        let (_n, e1) = e.publish_primary_buff(PrimaryOptions::default()).unwrap();
+        let (_n, e2) = e.publish_primary_buff(PrimaryOptions::default()).unwrap();
+
+        let d = e1.diff(&e2, None).unwrap();
+        assert_eq!((d.body_changed, d.private_options), (None, None));
+
+        let d = e1.diff(&e2, Some(&sk)).unwrap();
+        assert_eq!(d.body_changed, Some(false));
+        assert!(d.is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+//! CBOR envelopes for interop with non-DSF systems (MQTT bridges, HTTP gateways, etc.),
+//! wrapping raw container bytes with extracted summary fields.
+//!
+//! Envelopes are encoded as a definite-length CBOR map with text keys:
+//! - `id`: service ID (bytes)
+//! - `kind`: raw object kind (uint)
+//! - `index`: object index (uint)
+//! - `issued`: issued time in seconds since the epoch (uint, optional)
+//! - `object`: the unmodified encoded (and signed) container (bytes)
+//!
+//! Envelopes are encoded and decoded with `ciborium`. Summary fields are checked against the
+//! contained object on decode, however this does not verify object signatures, see [`Envelope::parse`].
+
+use core::convert::TryFrom;
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ciborium::value::{Integer, Value};
+use encdec::{Encode, Decode};
+
+use crate::error::Error;
+use crate::keys::KeySource;
+use crate::options::Filters;
+use crate::types::*;
+
+use super::{Container, MIN_OBJECT_LEN};
+
+const KEY_ID: &str = "id";
+const KEY_KIND: &str = "kind";
+const KEY_INDEX: &str = "index";
+const KEY_ISSUED: &str = "issued";
+const KEY_OBJECT: &str = "object";
+
+/// CBOR envelope over an encoded container
+#[derive(Clone, PartialEq, Debug)]
+pub struct Envelope<'a> {
+    /// Service ID
+    pub id: Id,
+    /// Object kind
+    pub kind: Kind,
+    /// Object index
+    pub index: u16,
+    /// Object issued time (from public options)
+    pub issued: Option<DateTime>,
+    /// Raw encoded object (borrowed when created from a container, owned when decoded)
+    pub object: Cow<'a, [u8]>,
+}
+
+impl <'a> Envelope<'a> {
+    /// Create an envelope for the provided container
+    pub fn new<T: ImmutableData>(container: &'a Container<T>) -> Self {
+        let header = container.header();
+
+        Self {
+            id: container.id(),
+            kind: header.kind(),
+            index: header.index(),
+            issued: container.public_options_iter().issued(),
+            object: Cow::Borrowed(container.raw()),
+        }
+    }
+
+    /// Fetch the (unverified) container from the envelope,
+    /// checking the summary fields match the contained object
+    pub fn container(&self) -> Result<Container<&[u8]>, Error> {
+        if self.object.len() < MIN_OBJECT_LEN {
+            return Err(Error::BufferLength);
+        }
+
+        let (c, n) = Container::from(&self.object[..]);
+        if n != self.object.len() {
+            return Err(Error::BufferLength);
+        }
+
+        let header = c.header();
+        if c.id() != self.id || header.kind() != self.kind || header.index() != self.index
+                || c.public_options_iter().issued() != self.issued {
+            return Err(Error::EnvelopeMismatch);
+        }
+
+        Ok(c)
+    }
+
+    /// Parse and verify the contained object
+    pub fn parse<K: KeySource>(&self, key_source: &K) -> Result<Container, Error> {
+        let _ = self.container()?;
+        Container::parse(self.object.to_vec(), key_source)
+    }
+
+    /// Build the CBOR map for the envelope
+    fn value(&self) -> Value {
+        let mut entries = vec![
+            (text(KEY_ID), Value::Bytes(self.id.to_vec())),
+            (text(KEY_KIND), Value::Integer(u16::from(self.kind).into())),
+            (text(KEY_INDEX), Value::Integer(self.index.into())),
+        ];
+        if let Some(issued) = &self.issued {
+            entries.push((text(KEY_ISSUED), Value::Integer(issued.as_secs().into())));
+        }
+        entries.push((text(KEY_OBJECT), Value::Bytes(self.object.to_vec())));
+
+        Value::Map(entries)
+    }
+}
+
+impl <'a> Encode for Envelope<'a> {
+    type Error = Error;
+
+    fn encode_len(&self) -> Result<usize, Self::Error> {
+        let mut buff = Vec::new();
+        ciborium::ser::into_writer(&self.value(), &mut buff).map_err(|e| {
+            error!("Failed to encode envelope: {:?}", e);
+            Error::EncodeFailed
+        })?;
+
+        Ok(buff.len())
+    }
+
+    fn encode(&self, buff: &mut [u8]) -> Result<usize, Self::Error> {
+        let len = buff.len();
+        let mut w = buff;
+
+        ciborium::ser::into_writer(&self.value(), &mut w).map_err(|e| match e {
+            ciborium::ser::Error::Io(_) => Error::BufferLength,
+            _ => {
+                error!("Failed to encode envelope: {:?}", e);
+                Error::EncodeFailed
+            },
+        })?;
+
+        Ok(len - w.len())
+    }
+}
+
+impl <'a> Decode<'a> for Envelope<'a> {
+    type Output = Envelope<'a>;
+    type Error = Error;
+
+    fn decode(buff: &'a [u8]) -> Result<(Self::Output, usize), Self::Error> {
+        let mut r = buff;
+
+        let entries = match ciborium::de::from_reader(&mut r) {
+            Ok(Value::Map(entries)) => entries,
+            Ok(_) => return Err(Error::InvalidEnvelope),
+            Err(e) => {
+                debug!("Failed to decode envelope: {:?}", e);
+                return Err(Error::InvalidEnvelope);
+            },
+        };
+
+        let (mut id, mut kind, mut index, mut issued, mut object) = (None, None, None, None, None);
+
+        for (key, value) in entries {
+            let key = match key {
+                Value::Text(key) => key,
+                _ => return Err(Error::InvalidEnvelope),
+            };
+
+            match (key.as_str(), value) {
+                (KEY_ID, Value::Bytes(v)) => id = Some(Id::try_from(&v[..])
+                    .map_err(|_| Error::InvalidEnvelope)?),
+                (KEY_KIND, Value::Integer(v)) => kind = Some(Kind::from(decode_u16(v)?)),
+                (KEY_INDEX, Value::Integer(v)) => index = Some(decode_u16(v)?),
+                (KEY_ISSUED, Value::Integer(v)) => issued = Some(DateTime::from_secs(
+                    u64::try_from(v).map_err(|_| Error::InvalidEnvelope)?)),
+                (KEY_OBJECT, Value::Bytes(v)) => object = Some(v),
+                // Known keys with unexpected types are invalid
+                (KEY_ID, _) | (KEY_KIND, _) | (KEY_INDEX, _) | (KEY_ISSUED, _) | (KEY_OBJECT, _) => return Err(Error::InvalidEnvelope),
+                // Unrecognised keys are ignored
+                _ => (),
+            }
+        }
+
+        let e = match (id, kind, index, object) {
+            (Some(id), Some(kind), Some(index), Some(object)) => Envelope{ id, kind, index, issued, object: Cow::Owned(object) },
+            _ => return Err(Error::InvalidEnvelope),
+        };
+
+        // Ensure summary fields match the contained object
+        e.container()?;
+
+        Ok((e, buff.len() - r.len()))
+    }
+}
+
+/// Build a CBOR text value
+fn text(s: &str) -> Value {
+    Value::Text(String::from(s))
+}
+
+/// Decode a CBOR integer, checking this fits in a u16
+fn decode_u16(v: Integer) -> Result<u16, Error> {
+    u16::try_from(v).map_err(|_| Error::InvalidEnvelope)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{DataOptions, Publisher};
+    use crate::test_utils;
+
+    #[test]
+    fn encode_decode_envelope() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let _p = test_utils::primary_page(&mut s);
+
+        let (_n, page) = s.publish_data(DataOptions::<&[u8]>::default(), vec![0u8; 1024]).unwrap();
+
+        let e = Envelope::new(&page);
+        assert!(e.issued.is_some());
+
+        let mut buff = [0u8; 1024];
+        let n = e.encode(&mut buff).unwrap();
+        assert_eq!(n, e.encode_len().unwrap());
+
+        // Envelopes round-trip with the object bytes untouched
+        let (d, m) = Envelope::decode(&buff[..n]).unwrap();
+        assert_eq!(m, n);
+        assert_eq!(d, e);
+        assert_eq!(&d.object[..], page.raw());
+
+        let c = d.parse(&test_utils::TestKeySource::default()).unwrap();
+        assert!(c.verified);
+        assert_eq!(c.raw(), page.raw());
+
+        // Mismatched summary fields are rejected
+        let mut e = Envelope::new(&page);
+        e.index += 1;
+        let n = e.encode(&mut buff).unwrap();
+        assert_eq!(Envelope::decode(&buff[..n]).map(|(e, _n)| e), Err(Error::EnvelopeMismatch));
+    }
+}
@@ -0,0 +1,151 @@
+//! Expiry policies, used to determine whether received objects have expired.
+//!
+//! Objects with an `Expiry` option expire at the specified time, objects with only an
+//! `Issued` option expire once they exceed the maximum age for their kind, and objects
+//! without time options are handled as configured by the [`ExpiryPolicy`].
+//! Current time is provided by a [`Clock`], allowing no_std users to supply external time sources.
+
+use core::time::Duration;
+
+use crate::error::Error;
+use crate::options::Filters;
+use crate::types::*;
+
+use super::Container;
+
+/// Default maximum age for objects with no expiry option
+pub const DEFAULT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Policy used to determine whether an object has expired.
+///
+/// Maximum ages apply to objects with an `Issued` but no `Expiry` option,
+/// with `None` allowing such objects to persist indefinitely.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ExpiryPolicy {
+    /// Maximum age of primary pages
+    pub max_primary_age: Option<Duration>,
+    /// Maximum age of secondary and tertiary pages
+    pub max_secondary_age: Option<Duration>,
+    /// Maximum age of data objects
+    pub max_data_age: Option<Duration>,
+    /// Maximum age of other objects (for example, messages)
+    pub max_other_age: Option<Duration>,
+    /// Treat objects with neither `Issued` nor `Expiry` options as expired
+    pub require_time: bool,
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            max_primary_age: Some(DEFAULT_MAX_AGE),
+            max_secondary_age: Some(DEFAULT_MAX_AGE),
+            max_data_age: Some(DEFAULT_MAX_AGE),
+            max_other_age: Some(DEFAULT_MAX_AGE),
+            require_time: false,
+        }
+    }
+}
+
+impl ExpiryPolicy {
+    /// Create a policy using the same maximum age for all object kinds
+    pub fn max_age(max_age: Option<Duration>) -> Self {
+        Self {
+            max_primary_age: max_age,
+            max_secondary_age: max_age,
+            max_data_age: max_age,
+            max_other_age: max_age,
+            require_time: false,
+        }
+    }
+
+    /// Require objects to include time options
+    pub fn with_require_time(mut self, require_time: bool) -> Self {
+        self.require_time = require_time;
+        self
+    }
+
+    /// Fetch the maximum age for an object with the provided kind and flags
+    pub fn max_age_for(&self, kind: Kind, flags: Flags) -> Option<Duration> {
+        if kind.is_page() && flags.intersects(Flags::SECONDARY | Flags::TERTIARY) {
+            self.max_secondary_age
+        } else if kind.is_page() {
+            self.max_primary_age
+        } else if kind.is_data() {
+            self.max_data_age
+        } else {
+            self.max_other_age
+        }
+    }
+
+    /// Check whether an object has expired at the time provided by `clock`
+    pub fn expired<T: ImmutableData, C: Clock>(&self, c: &Container<T>, clock: &C) -> bool {
+        let header = c.header();
+        let now = clock.now().as_secs();
+
+        let (issued, expiry) = (c.public_options_iter().issued(), c.public_options_iter().expiry());
+
+        match (issued, expiry) {
+            // Fixed expiry takes precedence
+            (_, Some(expiry)) => now > expiry.as_secs(),
+            // Otherwise apply the maximum age for the object kind
+            (Some(issued), None) => match self.max_age_for(header.kind(), header.flags()) {
+                Some(max_age) => now > (issued + max_age).as_secs(),
+                None => false,
+            },
+            // Objects without time options are only expired where required
+            (None, None) => self.require_time,
+        }
+    }
+
+    /// Check an object has not expired, returning [`Error::PageExpired`] if it has
+    pub fn check<T: ImmutableData, C: Clock>(&self, c: &Container<T>, clock: &C) -> Result<(), Error> {
+        match self.expired(c, clock) {
+            true => Err(Error::PageExpired),
+            false => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::Options;
+    use crate::service::{DataOptions, Publisher, PrimaryOptions};
+    use crate::test_utils;
+
+    #[test]
+    fn expiry_policies() {
+        let mut svc = test_utils::service(test_utils::SERVICE_SEED);
+        let issued = DateTime::from_secs(10_000);
+
+        // Primary page with an explicit expiry
+        let opts = PrimaryOptions{ issued: Some(issued), expiry: Some(issued + Duration::from_secs(60)), ..Default::default() };
+        let (_n, p) = svc.publish_primary(opts, vec![0u8; 1024]).unwrap();
+
+        let policy = ExpiryPolicy::max_age(None);
+        assert!(!policy.expired(&p, &DateTime::from_secs(10_060)));
+        assert!(policy.expired(&p, &DateTime::from_secs(10_061)));
+
+        // Data object with only an issued time uses the per-kind maximum age
+        let opts = DataOptions{ issued: Some(issued), body: Some(&[0xaa; 4][..]), ..Default::default() };
+        let (_n, d) = svc.publish_data(opts, vec![0u8; 1024]).unwrap();
+
+        let default = ExpiryPolicy::default();
+        assert!(!default.expired(&d, &(issued + DEFAULT_MAX_AGE)));
+        assert!(default.expired(&d, &DateTime::from_secs(issued.as_secs() + DEFAULT_MAX_AGE.as_secs() + 1)));
+
+        let policy = ExpiryPolicy{ max_data_age: Some(Duration::from_secs(10)), ..Default::default() };
+        assert_eq!(policy.check(&d, &DateTime::from_secs(10_011)), Err(Error::PageExpired));
+        assert_eq!(policy.check(&p, &DateTime::from_secs(10_011)), Ok(()));
+        assert!(!ExpiryPolicy::max_age(None).expired(&d, &DateTime::from_secs(u32::MAX as u64)));
+
+        // Objects without time options are only expired where required
+        let opts = DataOptions{ issued: None, body: Some(&[0xbb; 4][..]), ..Default::default() };
+        let (_n, u) = svc.publish_data(opts, vec![0u8; 1024]).unwrap();
+        assert!(u.public_options_iter().find(|o| matches!(o, Options::Issued(_))).is_none());
+
+        assert!(!default.expired(&u, &issued));
+        assert!(default.with_require_time(true).expired(&u, &issued));
+    }
+}
@@ -0,0 +1,408 @@
+//! Fragment provides splitting of encoded objects into MTU-sized fragments, and reassembly
+//! and verification of received fragments, for links unable to carry whole objects.
+//!
+//! Each fragment is prefixed with a [`FRAGMENT_HEADER_LEN`] byte header containing a group
+//! (derived from the object signature), the fragment index and count, and the total object length.
+//! Fragments may be received in any order, with reassembled objects parsed (and thus verified)
+//! using the provided key source.
+//!
+//! Fragments are unauthenticated until reassembled, so the [`Reassembler`] keys pending objects by
+//! sending peer and group, and bounds the number and total length of pending objects, evicting
+//! the oldest pending objects where limits are exceeded.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::error::Error;
+use crate::keys::KeySource;
+use crate::types::*;
+
+use super::container::Container;
+
+/// Length of the header prefixed to each fragment
+pub const FRAGMENT_HEADER_LEN: usize = 6;
+
+/// Fragment of an encoded object
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Fragment<'a> {
+    /// Fragment group, shared by fragments of the same object
+    pub group: u16,
+    /// Index of this fragment
+    pub index: u8,
+    /// Total number of fragments
+    pub count: u8,
+    /// Total length of the fragmented object
+    pub total_len: u16,
+    /// Fragment payload
+    pub data: &'a [u8],
+}
+
+impl<'a> Fragment<'a> {
+    /// Decode a fragment from the provided buffer
+    pub fn decode(buff: &'a [u8]) -> Result<Self, Error> {
+        if buff.len() < FRAGMENT_HEADER_LEN {
+            return Err(Error::BufferLength);
+        }
+
+        let f = Self {
+            group: NetworkEndian::read_u16(&buff[0..]),
+            index: buff[2],
+            count: buff[3],
+            total_len: NetworkEndian::read_u16(&buff[4..]),
+            data: &buff[FRAGMENT_HEADER_LEN..],
+        };
+
+        // Check fragment fields are consistent
+        let (offset, len) = chunk(f.total_len as usize, f.count, f.index)?;
+        if f.data.len() != len {
+            debug!("Fragment {} (offset {}) length {} does not match expected length {}", f.index, offset, f.data.len(), len);
+            return Err(Error::InvalidFragment);
+        }
+
+        Ok(f)
+    }
+
+    /// Encode a fragment into the provided buffer, returning the encoded length
+    pub fn encode(&self, buff: &mut [u8]) -> Result<usize, Error> {
+        let n = FRAGMENT_HEADER_LEN + self.data.len();
+        if buff.len() < n {
+            return Err(Error::BufferLength);
+        }
+
+        NetworkEndian::write_u16(&mut buff[0..], self.group);
+        buff[2] = self.index;
+        buff[3] = self.count;
+        NetworkEndian::write_u16(&mut buff[4..], self.total_len);
+        buff[FRAGMENT_HEADER_LEN..n].copy_from_slice(self.data);
+
+        Ok(n)
+    }
+
+    /// Fetch the encoded length of the fragment
+    pub fn encode_len(&self) -> usize {
+        FRAGMENT_HEADER_LEN + self.data.len()
+    }
+}
+
+/// Compute the offset and length of a fragment payload.
+///
+/// Objects are split into `count` equal chunks (with a shorter final chunk)
+/// so offsets may be computed from any fragment without a per-fragment offset.
+fn chunk(total_len: usize, count: u8, index: u8) -> Result<(usize, usize), Error> {
+    if count == 0 || index >= count || total_len < count as usize {
+        return Err(Error::InvalidFragment);
+    }
+
+    let size = total_len.div_ceil(count as usize);
+    let offset = index as usize * size;
+    if offset >= total_len {
+        return Err(Error::InvalidFragment);
+    }
+
+    Ok((offset, size.min(total_len - offset)))
+}
+
+/// Fragmenter splits an encoded object into fragments of at most `mtu` bytes
+#[derive(Clone, Debug)]
+pub struct Fragmenter<'a> {
+    data: &'a [u8],
+    group: u16,
+    count: u8,
+    index: u8,
+}
+
+impl<'a> Fragmenter<'a> {
+    /// Create a fragmenter for the provided (signed) container, with fragments
+    /// (including fragment headers) of at most `mtu` bytes
+    pub fn new<T: ImmutableData>(c: &'a Container<T>, mtu: usize) -> Result<Self, Error> {
+        let data = c.try_raw()?;
+        let sig = c.try_signature_raw()?;
+
+        let max = mtu.saturating_sub(FRAGMENT_HEADER_LEN);
+        if max == 0 || data.len() > u16::MAX as usize {
+            return Err(Error::BufferLength);
+        }
+
+        // Fragment count must fit in the fragment header
+        let count = data.len().div_ceil(max);
+        if count > u8::MAX as usize {
+            error!("Object length {} exceeds maximum fragments for MTU {}", data.len(), mtu);
+            return Err(Error::BufferLength);
+        }
+
+        Ok(Self {
+            data,
+            group: NetworkEndian::read_u16(sig),
+            count: count as u8,
+            index: 0,
+        })
+    }
+}
+
+impl<'a> Iterator for Fragmenter<'a> {
+    type Item = Fragment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.count {
+            return None;
+        }
+
+        let (offset, len) = chunk(self.data.len(), self.count, self.index).ok()?;
+
+        let f = Fragment {
+            group: self.group,
+            index: self.index,
+            count: self.count,
+            total_len: self.data.len() as u16,
+            data: &self.data[offset..][..len],
+        };
+
+        self.index += 1;
+
+        Some(f)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = (self.count - self.index) as usize;
+        (n, Some(n))
+    }
+}
+
+impl<'a> ExactSizeIterator for Fragmenter<'a> {}
+
+/// Default maximum number of objects pending reassembly
+pub const DEFAULT_MAX_PENDING: usize = 8;
+
+/// Default maximum total length (in bytes) of objects pending reassembly
+pub const DEFAULT_MAX_PENDING_LEN: usize = 64 * 1024;
+
+/// Partially received object
+#[derive(Clone, Debug)]
+struct Partial {
+    count: u8,
+    data: Vec<u8>,
+    received: Vec<bool>,
+    seq: u64,
+}
+
+/// Reassembler collects received fragments, returning verified objects once complete.
+///
+/// Pending objects are keyed by the sending peer `P` (for example, a peer address) and fragment
+/// group, so fragments from different peers do not collide.
+#[derive(Clone, Debug)]
+pub struct Reassembler<P: Ord> {
+    pending: BTreeMap<(P, u16), Partial>,
+    max_pending: usize,
+    max_len: usize,
+    len: usize,
+    seq: u64,
+}
+
+impl <P: Ord + Clone> Default for Reassembler<P> {
+    fn default() -> Self {
+        Self::with_limits(DEFAULT_MAX_PENDING, DEFAULT_MAX_PENDING_LEN)
+    }
+}
+
+impl <P: Ord + Clone> Reassembler<P> {
+    /// Create a new (empty) reassembler with default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new (empty) reassembler, bounding the number of pending objects
+    /// and total length (in bytes) of pending objects
+    pub fn with_limits(max_pending: usize, max_len: usize) -> Self {
+        Self { pending: BTreeMap::new(), max_pending, max_len, len: 0, seq: 0 }
+    }
+
+    /// Add an encoded fragment from the provided peer, returning the parsed and verified container
+    /// once all fragments have been received
+    pub fn push<K: KeySource>(&mut self, peer: P, fragment: &[u8], key_source: &K) -> Result<Option<Container<Vec<u8>>>, Error> {
+        match self.push_raw(peer, fragment)? {
+            Some(data) => Container::parse(data, key_source).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Add an encoded fragment from the provided peer, returning the (unverified) object data
+    /// once all fragments have been received
+    pub fn push_raw(&mut self, peer: P, fragment: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        let f = Fragment::decode(fragment)?;
+        let (offset, len) = chunk(f.total_len as usize, f.count, f.index)?;
+        let key = (peer, f.group);
+
+        // Objects must fit within the pending limit
+        let total_len = f.total_len as usize;
+        if total_len > self.max_len || self.max_pending == 0 {
+            debug!("Fragmented object length {} exceeds reassembly limit {}", total_len, self.max_len);
+            return Err(Error::ObjectTooLarge);
+        }
+
+        // Evict the oldest pending objects to make room for new objects
+        if !self.pending.contains_key(&key) {
+            while self.pending.len() >= self.max_pending || self.len + total_len > self.max_len {
+                self.evict_oldest();
+            }
+
+            self.seq += 1;
+            self.len += total_len;
+            self.pending.insert(key.clone(), Partial {
+                count: f.count,
+                data: vec![0u8; total_len],
+                received: vec![false; f.count as usize],
+                seq: self.seq,
+            });
+        }
+
+        let p = match self.pending.get_mut(&key) {
+            Some(p) => p,
+            None => return Err(Error::InvalidFragment),
+        };
+
+        // Fragments must match the object being reassembled
+        if p.count != f.count || p.data.len() != total_len {
+            debug!("Fragment {} does not match pending object for group {}", f.index, f.group);
+            return Err(Error::InvalidFragment);
+        }
+
+        p.data[offset..][..len].copy_from_slice(f.data);
+        p.received[f.index as usize] = true;
+
+        trace!("Received fragment {} of {} for group {}", f.index + 1, f.count, f.group);
+
+        if !p.received.iter().all(|r| *r) {
+            return Ok(None);
+        }
+
+        Ok(self.take(&key))
+    }
+
+    /// Fetch the number of objects pending reassembly
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Fetch the total length (in bytes) of objects pending reassembly
+    pub fn pending_len(&self) -> usize {
+        self.len
+    }
+
+    /// Drop a pending object, for example following a reassembly timeout
+    pub fn remove(&mut self, peer: P, group: u16) {
+        self.take(&(peer, group));
+    }
+
+    fn take(&mut self, key: &(P, u16)) -> Option<Vec<u8>> {
+        let p = self.pending.remove(key)?;
+        self.len -= p.data.len();
+        Some(p.data)
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.pending.iter().min_by_key(|(_k, p)| p.seq).map(|(k, _p)| k.clone());
+        if let Some(k) = oldest {
+            debug!("Evicting pending object for group {}", k.1);
+            self.take(&k);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::net::{Message, Request, RequestBody};
+    use crate::service::{DataOptions, Net, Publisher};
+    use crate::types::Flags;
+    use crate::test_utils;
+
+    #[test]
+    fn fragment_reassemble_message() {
+        let mut svc = test_utils::service(test_utils::SERVICE_SEED);
+        let keys = svc.keys();
+
+        // Build a PushData request larger than the MTU
+        let pages: Vec<_> = (0..3).map(|_| {
+            let body: &[u8] = &[0xab; 64];
+            let opts = DataOptions{ body: Some(body), ..Default::default() };
+            let (_n, c) = svc.publish_data(opts, vec![0u8; 1024]).unwrap();
+            c
+        }).collect();
+
+        let req = Request::new(svc.id(), 1, RequestBody::PushData(svc.id(), pages), Flags::empty());
+        let c = svc.encode_request(&req, &keys, vec![0u8; 2048]).unwrap();
+
+        // Fragment message
+        let fragmenter = Fragmenter::new(&c, 64).unwrap();
+        let n = fragmenter.len();
+        assert!(n > 1);
+
+        let fragments: Vec<Vec<u8>> = fragmenter.map(|f| {
+            assert!(f.encode_len() <= 64);
+            let mut b = vec![0u8; f.encode_len()];
+            f.encode(&mut b).unwrap();
+            b
+        }).collect();
+        assert_eq!(fragments.len(), n);
+
+        // Reassemble out of order
+        let mut r = Reassembler::new();
+        for f in fragments.iter().skip(1) {
+            assert_eq!(Message::parse_fragment(&mut r, 1, f, &keys).unwrap(), None);
+        }
+        assert_eq!(r.pending(), 1);
+
+        // Fragments from other peers do not complete the object
+        assert_eq!(Message::parse_fragment(&mut r, 2, &fragments[0], &keys).unwrap(), None);
+        assert_eq!(r.pending(), 2);
+        r.remove(2, Fragment::decode(&fragments[0]).unwrap().group);
+
+        let (m, _n) = Message::parse_fragment(&mut r, 1, &fragments[0], &keys).unwrap().expect("Reassembly failed");
+        assert_eq!(r.pending(), 0);
+        assert_eq!(r.pending_len(), 0);
+        assert_eq!(m, Message::request(req));
+
+        // Containers may also be reassembled directly
+        let o = fragments.iter().rev().find_map(|f| r.push(1, f, &keys).unwrap()).unwrap();
+        assert_eq!(o.raw(), c.raw());
+
+        // Corrupted fragments fail verification
+        let mut corrupt = fragments.clone();
+        corrupt[1][FRAGMENT_HEADER_LEN] ^= 0xff;
+        let res = corrupt.iter().map(|f| r.push(1, f, &keys)).last().unwrap();
+        assert!(res.is_err());
+
+        // Fragments with mismatched lengths are rejected
+        assert_eq!(r.push_raw(1, &fragments[0][..FRAGMENT_HEADER_LEN + 1]), Err(Error::InvalidFragment));
+    }
+
+    #[test]
+    fn reassembly_limits() {
+        let fragment = |group: u16, total_len: u16| {
+            let data = vec![0xab; total_len as usize / 2];
+            let f = Fragment{ group, index: 0, count: 2, total_len, data: &data };
+            let mut b = vec![0u8; f.encode_len()];
+            f.encode(&mut b).unwrap();
+            b
+        };
+
+        // Pending object count is bounded, evicting the oldest objects
+        let mut r = Reassembler::<u32>::with_limits(2, 1024);
+        for g in 0..3 {
+            assert_eq!(r.push_raw(1, &fragment(g, 64)), Ok(None));
+        }
+        assert_eq!(r.pending(), 2);
+        assert_eq!(r.pending_len(), 128);
+
+        // As is the total pending length
+        assert_eq!(r.push_raw(1, &fragment(10, 1000)), Ok(None));
+        assert_eq!(r.pending(), 1);
+        assert_eq!(r.pending_len(), 1000);
+
+        // Objects exceeding the limit are rejected without allocation
+        assert_eq!(r.push_raw(1, &fragment(11, 2048)), Err(Error::ObjectTooLarge));
+        assert_eq!(r.pending(), 1);
+    }
+}
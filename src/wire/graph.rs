@@ -0,0 +1,206 @@
+//! Object graph links sets of containers by dependency (service -> pages -> data chain -> links),
+//! providing shared lookups for store compaction and UI views.
+//!
+//! Objects are classified using [`Container::info`]:
+//! - primary pages and data objects are grouped by service ID, with data objects chained by previous signature
+//! - secondary pages are grouped by the ID of the service they are published for
+//! - tertiary pages link to a target service ID, or to a data object by signature
+//!
+//! Note the graph does not verify objects, lookups requiring verification check [`Container::verified`].
+
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::options::Filters;
+use crate::page::PageInfo;
+use crate::types::*;
+
+use super::Container;
+
+/// Object classification within an [`ObjectGraph`]
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    /// Primary service page
+    Primary,
+    /// Secondary page published by a peer
+    Secondary{ peer_id: Id },
+    /// Tertiary page linking to a service
+    ServiceLink{ target_id: Id, peer_id: Id },
+    /// Tertiary page linking to a data object
+    BlockLink{ block_sig: Signature, peer_id: Id },
+    /// Data object
+    Data,
+    /// Private or encrypted page, contents are opaque
+    Opaque,
+}
+
+/// Object within an [`ObjectGraph`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node<T: ImmutableData = Vec<u8>> {
+    /// Object kind
+    pub kind: NodeKind,
+    /// Object container
+    pub object: Container<T>,
+}
+
+/// Dependency graph over a set of objects
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectGraph<T: ImmutableData = Vec<u8>> {
+    nodes: Vec<Node<T>>,
+}
+
+impl <T: ImmutableData> Default for ObjectGraph<T> {
+    fn default() -> Self {
+        Self { nodes: Vec::new() }
+    }
+}
+
+impl <T: ImmutableData> ObjectGraph<T> {
+    /// Build a graph from the provided objects, returning an error for objects that can not be classified
+    pub fn new(objects: impl IntoIterator<Item = Container<T>>) -> Result<Self, Error> {
+        let mut g = Self::default();
+        for o in objects {
+            g.insert(o)?;
+        }
+        Ok(g)
+    }
+
+    /// Insert an object into the graph
+    pub fn insert(&mut self, object: Container<T>) -> Result<(), Error> {
+        let kind = match object.info()? {
+            PageInfo::Primary(_) => NodeKind::Primary,
+            PageInfo::Secondary(s) => NodeKind::Secondary{ peer_id: s.peer_id },
+            PageInfo::ServiceLink(l) => NodeKind::ServiceLink{ target_id: l.target_id, peer_id: l.peer_id },
+            PageInfo::BlockLink(l) => NodeKind::BlockLink{ block_sig: l.block_sig, peer_id: l.peer_id },
+            PageInfo::Data(_) => NodeKind::Data,
+            PageInfo::Private(_) => NodeKind::Opaque,
+        };
+
+        self.nodes.push(Node{ kind, object });
+
+        Ok(())
+    }
+
+    /// Iterate over objects in the graph
+    pub fn iter(&self) -> impl Iterator<Item = &Node<T>> {
+        self.nodes.iter()
+    }
+
+    /// Fetch the number of objects in the graph
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check whether the graph is empty
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Fetch the object with the provided signature
+    pub fn get(&self, sig: &Signature) -> Option<&Node<T>> {
+        self.nodes.iter().find(|n| &n.object.signature() == sig)
+    }
+
+    /// Iterate over objects of the provided kind for a service
+    fn service(&self, id: Id, f: impl Fn(&NodeKind) -> bool + 'static) -> impl Iterator<Item = &Container<T>> {
+        self.nodes.iter().filter(move |n| f(&n.kind) && n.object.id() == id).map(|n| &n.object)
+    }
+
+    /// Fetch the latest (highest index) verified primary page for a service
+    pub fn latest_primary(&self, id: &Id) -> Option<&Container<T>> {
+        self.service(id.clone(), |k| k == &NodeKind::Primary)
+            .filter(|c| c.verified)
+            .max_by_key(|c| c.header().index())
+    }
+
+    /// Iterate over secondary pages published for a service
+    pub fn secondaries<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = &'a Container<T>> + 'a {
+        self.service(id.clone(), |k| matches!(k, NodeKind::Secondary{ .. }))
+    }
+
+    /// Fetch the data objects for a service, ordered by index
+    pub fn data(&self, id: &Id) -> Vec<&Container<T>> {
+        let mut d: Vec<_> = self.service(id.clone(), |k| k == &NodeKind::Data).collect();
+        d.sort_by_key(|c| c.header().index());
+        d
+    }
+
+    /// Iterate over tertiary pages linking to a service,
+    /// either directly or via a block link to one of the service objects
+    pub fn links_to<'a>(&'a self, id: &'a Id) -> impl Iterator<Item = &'a Container<T>> + 'a {
+        self.nodes.iter().filter(move |n| match &n.kind {
+            NodeKind::ServiceLink{ target_id, .. } => target_id == id,
+            NodeKind::BlockLink{ block_sig, .. } => self.get(block_sig).map(|t| &t.object.id() == id).unwrap_or(false),
+            _ => false,
+        }).map(|n| &n.object)
+    }
+
+    /// Fetch orphaned objects, these being secondary pages and data objects with no
+    /// primary page for the service, and data objects with a previous signature
+    /// not held in the graph (for example, following compaction of earlier objects)
+    pub fn orphans(&self) -> Vec<&Container<T>> {
+        self.nodes.iter().filter(|n| {
+            let has_primary = || self.service(n.object.id(), |k| k == &NodeKind::Primary).next().is_some();
+
+            match &n.kind {
+                NodeKind::Secondary{ .. } => !has_primary(),
+                NodeKind::Data => {
+                    let linked = match n.object.public_options_iter().prev_sig() {
+                        Some(s) => self.get(&s).is_some(),
+                        None => true,
+                    };
+                    !has_primary() || !linked
+                },
+                _ => false,
+            }
+        }).map(|n| &n.object).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::service::{DataOptions, Publisher, Registry, SecondaryOptions, Service, ServiceBuilder, TertiaryOptions};
+    use crate::options::Options;
+    use crate::test_utils;
+
+    #[test]
+    fn object_graph() {
+        let mut s = test_utils::service(test_utils::SERVICE_SEED);
+        let mut p = test_utils::service(0x03);
+        let mut r: Service = ServiceBuilder::ns("test.com").build().unwrap();
+
+        let primary = test_utils::primary_page(&mut s);
+        let data: Vec<_> = (0..3).map(|_| s.publish_data(DataOptions::<&[u8]>::default(), vec![0u8; 1024]).unwrap().1).collect();
+        let (_n, secondary) = p.publish_secondary(&s.id(), SecondaryOptions::default(), vec![0u8; 1024]).unwrap();
+
+        let (_n, r_primary) = r.publish_primary(Default::default(), vec![0u8; 1024]).unwrap();
+        let (_n, link) = r.publish_tertiary(s.id().into(), TertiaryOptions::default(), &Options::name("something"), vec![0u8; 1024]).unwrap();
+        let (_n, block) = r.publish_tertiary(data[1].signature().into(), TertiaryOptions::default(), &Options::name("block"), vec![0u8; 1024]).unwrap();
+
+        let objects = [&primary, &data[2], &data[0], &data[1], &secondary, &r_primary, &link, &block];
+        let g = ObjectGraph::new(objects.iter().map(|c| (*c).clone())).unwrap();
+        assert_eq!(g.len(), objects.len());
+
+        // Objects are linked to services
+        assert_eq!(g.latest_primary(&s.id()), Some(&primary));
+        assert_eq!(g.secondaries(&s.id()).collect::<Vec<_>>(), vec![&secondary]);
+        assert_eq!(g.data(&s.id()), data.iter().collect::<Vec<_>>());
+        assert_eq!(g.links_to(&s.id()).collect::<Vec<_>>(), vec![&link, &block]);
+        assert_eq!(g.links_to(&r.id()).count(), 0);
+        assert_eq!(g.orphans(), Vec::<&Container>::new());
+
+        // Only verified primary pages are returned
+        let mut unverified = primary.clone();
+        unverified.verified = false;
+        let g = ObjectGraph::new([unverified]).unwrap();
+        assert_eq!(g.latest_primary(&s.id()), None);
+
+        // Objects missing primary pages or chain links are orphaned
+        let g = ObjectGraph::new([secondary.clone(), data[0].clone(), primary.clone(), data[2].clone()]).unwrap();
+        assert_eq!(g.orphans(), vec![&data[2]]);
+
+        let g = ObjectGraph::new([secondary.clone(), data[0].clone()]).unwrap();
+        assert_eq!(g.orphans(), vec![&secondary, &data[0]]);
+    }
+}
@@ -1,8 +1,12 @@
+use core::convert::TryFrom;
+
 use byteorder::{ByteOrder, NetworkEndian};
 
 use crate::base::{Header};
+use crate::error::Error;
 use crate::types::{Flags, ImmutableData, Kind, MutableData, ID_LEN, SIGNATURE_LEN};
-use super::{offsets, SECRET_KEY_TAG_LEN, HEADER_LEN};
+use super::{offsets, HEADER_LEN, MIN_OBJECT_LEN};
+use super::version::Version;
 
 /// Header generic over arbitrary storage for wire encoding
 // TODO: decide what to do with the high / low level impls
@@ -77,13 +81,7 @@ impl<T: ImmutableData> WireHeader<T> {
     }
 
     pub fn public_options_offset(&self) -> usize {
-        let mut o = self.private_options_offset() + self.private_options_len();
-
-        if self.flags().contains(Flags::ENCRYPTED) {
-            o += SECRET_KEY_TAG_LEN
-        };
-
-        o
+        self.tag_offset() + self.flags().tag_len()
     }
 
     pub fn signature_offset(&self) -> usize {
@@ -91,24 +89,70 @@ impl<T: ImmutableData> WireHeader<T> {
     }
 
     pub fn encoded_len(&self) -> usize {
-        let flags = self.flags();
-
-        let tag_len = if flags.contains(Flags::ENCRYPTED) && !flags.contains(Flags::SYMMETRIC_MODE) {
-            SECRET_KEY_TAG_LEN
-        } else {
-            0
-        };
-
         HEADER_LEN
             + ID_LEN
             + self.data_len()
             + self.private_options_len()
-            + tag_len
+            + self.flags().tag_len()
             + self.public_options_len()
             + SIGNATURE_LEN
     }
 }
 
+/// Peek at the application ID, object kind, flags and encoded length of an object from
+/// the fixed header, without constructing or validating a [`Container`](super::Container).
+///
+/// This supports classifying objects (for example, in application dispatch tables) prior to
+/// full parsing, and the returned values must not be trusted until the object is verified.
+pub fn peek(header: &[u8]) -> Result<(u16, Kind, Flags, usize), Error> {
+    if header.len() < HEADER_LEN {
+        return Err(Error::BufferLength);
+    }
+
+    let h = WireHeader::new(&header[..HEADER_LEN]);
+
+    Ok((h.application_id(), h.kind(), h.flags(), h.encoded_len()))
+}
+
+/// Check the fixed header of an encoded object against the provided buffer, returning the encoded object length.
+///
+/// Returns [`Error::BufferLength`] where the buffer is shorter than the fixed header, ID and signature
+/// or the object length computed from the header length fields, [`Error::UnsupportedVersion`] for
+/// unsupported protocol versions, and [`Error::InvalidFlags`] for flags incompatible with the object kind.
+/// Objects passing these checks may be accessed without exceeding the buffer, however signatures
+/// and options are not checked.
+pub fn check(buff: &[u8]) -> Result<usize, Error> {
+    if buff.len() < MIN_OBJECT_LEN {
+        return Err(Error::BufferLength);
+    }
+
+    let h = WireHeader::new(&buff[..HEADER_LEN]);
+
+    let version = Version::try_from(h.protocol_version()).map_err(|e| {
+        debug!("Unsupported protocol version: {}", h.protocol_version());
+        e
+    })?;
+
+    // Dispatch to per-version layout checks
+    match version {
+        Version::V0 => check_v0(buff, &h),
+    }
+}
+
+/// Check the fixed header of a [`Version::V0`] object
+fn check_v0(buff: &[u8], h: &WireHeader<&[u8]>) -> Result<usize, Error> {
+    h.flags().validate(h.kind())?;
+
+    // Header lengths (including the signature) must fit the buffer
+    let len = h.encoded_len();
+    if len > buff.len() {
+        debug!("Encoded length {} exceeds buffer length {}", len, buff.len());
+        return Err(Error::BufferLength);
+    }
+
+    Ok(len)
+}
+
 impl<T: ImmutableData> core::fmt::Debug for WireHeader<T> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         f.debug_struct("WireHeader")
@@ -191,7 +235,7 @@ impl<T: MutableData> WireHeader<T> {
 mod tests {
 
     use super::*;
-    use crate::wire::HEADER_LEN;
+    use crate::wire::{HEADER_LEN, PROTOCOL_VERSION};
 
     use crate::base::{Header};
     use crate::types::PageKind;
@@ -213,4 +257,51 @@ mod tests {
         // Check original / decoded match
         assert_eq!(h, h2);
     }
+
+    #[test]
+    fn test_peek_header() {
+        let mut s = crate::test_utils::service(crate::test_utils::SERVICE_SEED);
+        let p = crate::test_utils::primary_page(&mut s);
+
+        let (app_id, kind, flags, len) = peek(&p.raw()[..HEADER_LEN]).unwrap();
+        assert_eq!(app_id, p.header().application_id());
+        assert_eq!(kind, p.header().kind());
+        assert_eq!(flags, p.header().flags());
+        assert_eq!(len, p.len());
+
+        assert_eq!(peek(&p.raw()[..HEADER_LEN - 1]), Err(Error::BufferLength));
+    }
+
+    #[test]
+    fn test_check_header() {
+        let mut s = crate::test_utils::service(crate::test_utils::SERVICE_SEED);
+        let p = crate::test_utils::primary_page(&mut s);
+
+        assert_eq!(check(p.raw()), Ok(p.len()));
+
+        // Truncated objects are rejected
+        assert_eq!(check(&p.raw()[..p.len() - 1]), Err(Error::BufferLength));
+        assert_eq!(check(&p.raw()[..MIN_OBJECT_LEN - 1]), Err(Error::BufferLength));
+
+        // As are oversized length fields
+        let mut b = p.raw().to_vec();
+        WireHeader::new(&mut b[..HEADER_LEN]).set_data_len(u16::MAX as usize);
+        assert_eq!(check(&b), Err(Error::BufferLength));
+
+        // Unsupported versions
+        let mut b = p.raw().to_vec();
+        WireHeader::new(&mut b[..HEADER_LEN]).set_protocol_version(PROTOCOL_VERSION + 1);
+        assert_eq!(check(&b), Err(Error::UnsupportedVersion));
+
+        // And flags incompatible with the object kind
+        let mut b = p.raw().to_vec();
+        WireHeader::new(&mut b[..HEADER_LEN]).set_flags(Flags::SECONDARY | Flags::TERTIARY);
+        assert_eq!(check(&b), Err(Error::InvalidFlags));
+
+        // Containers are checked on conversion
+        use core::convert::TryFrom;
+        use crate::wire::Container;
+        assert_eq!(Container::try_from(p.raw()).map(|c| c.len()), Ok(p.len()));
+        assert_eq!(Container::try_from(b).err(), Some(Error::InvalidFlags));
+    }
 }
@@ -0,0 +1,82 @@
+//! Parsing limits, applied at object and message parsing entry points to bound
+//! resource use when handling untrusted input.
+
+use crate::error::Error;
+use crate::options::OptionLimits;
+
+/// Default maximum encoded object length
+pub const DEFAULT_MAX_OBJECT_LEN: usize = 10 * 1024;
+
+/// Default maximum body length of a single object
+pub const DEFAULT_MAX_BODY_LEN: usize = 8 * 1024;
+
+/// Default maximum number of pages per message
+pub const DEFAULT_MAX_PAGES: usize = 32;
+
+/// Limits applied when parsing objects and messages.
+///
+/// Admission control (for example, rate limiting senders) may be applied prior to verification
+/// using [`Interceptor::pre_verify`](crate::interceptor::Interceptor::pre_verify).
+///
+/// Applications may configure these per-deployment and pass them to
+/// [`Container::parse_with_limits`](super::Container::parse_with_limits),
+/// [`Container::decode_pages_with_limits`](super::Container::decode_pages_with_limits)
+/// or [`Message::parse_with_limits`](crate::net::Message::parse_with_limits).
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Limits {
+    /// Maximum encoded length (in bytes) of a single object
+    pub max_object_len: usize,
+    /// Maximum body length (in bytes) of a single object
+    pub max_body_len: usize,
+    /// Maximum number of pages contained in a single message
+    pub max_pages: usize,
+    /// Limits applied to each options section
+    pub options: OptionLimits,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_object_len: DEFAULT_MAX_OBJECT_LEN,
+            max_body_len: DEFAULT_MAX_BODY_LEN,
+            max_pages: DEFAULT_MAX_PAGES,
+            options: OptionLimits::default(),
+        }
+    }
+}
+
+impl From<OptionLimits> for Limits {
+    fn from(options: OptionLimits) -> Self {
+        Self {
+            options,
+            ..Default::default()
+        }
+    }
+}
+
+impl Limits {
+    /// Check an encoded object length against the configured limit
+    pub fn check_object_len(&self, len: usize) -> Result<(), Error> {
+        match len > self.max_object_len {
+            true => Err(Error::ObjectTooLarge),
+            false => Ok(()),
+        }
+    }
+
+    /// Check an object body length against the configured limit
+    pub fn check_body_len(&self, len: usize) -> Result<(), Error> {
+        match len > self.max_body_len {
+            true => Err(Error::ObjectTooLarge),
+            false => Ok(()),
+        }
+    }
+
+    /// Check a page count against the configured limit
+    pub fn check_pages(&self, count: usize) -> Result<(), Error> {
+        match count > self.max_pages {
+            true => Err(Error::TooManyPages),
+            false => Ok(()),
+        }
+    }
+}
@@ -2,6 +2,7 @@
 //! to support wire encoding and decoding.
 
 
+use core::convert::TryFrom;
 use core::ops::{DerefMut};
 
 
@@ -12,48 +13,107 @@ use encdec::{Encode, Decode, EncodeExt, DecodeExt};
 use pretty_hex::*;
 
 use crate::base::{MaybeEncrypted};
-use crate::crypto::{Crypto, PubKey as _, SecKey as _, Hash as _};
+use crate::crypto::{Crypto, PreparedKey, PubKey as _, SecKey as _};
 use crate::error::Error;
-use crate::options::{Options};
+use crate::keys::{KeySource, Keys};
+use crate::options::{Options, OptionsIter, OptionLimits, OptionError, OptionViolation, DelegationScope};
 use crate::types::*;
 
 /// Header provides a low-cost header abstraction for encoding/decoding
 pub mod header;
+pub use header::{peek, check};
 
 /// Builder provides methods to construct a container using a mutable buffer and base types
 pub mod builder;
-pub use builder::Builder;
+pub use builder::{Builder, OptionSlot};
 
 /// Container provides methods to access underlying wire object fields
 pub mod container;
 pub use container::Container;
 
-use crate::keys::{KeySource, Keys};
+/// Compress provides pluggable body compression
+pub mod compress;
+pub use compress::Compressor;
+
+/// Fragment provides splitting and reassembly of objects larger than the link MTU
+#[cfg(feature = "alloc")]
+pub mod fragment;
+#[cfg(feature = "alloc")]
+pub use fragment::{Fragment, Fragmenter, Reassembler, FRAGMENT_HEADER_LEN};
 
+/// Envelope provides CBOR wrapping of encoded containers for interop with non-DSF systems
+#[cfg(feature = "cbor")]
+pub mod envelope;
+#[cfg(feature = "cbor")]
+pub use envelope::Envelope;
 
+/// Audit provides bulk re-verification of stored objects
+#[cfg(feature = "alloc")]
+pub mod audit;
+#[cfg(feature = "alloc")]
+pub use audit::{audit, audit_with, AuditReport, AuditEntry, AuditResult};
 
-/// Header object length
-pub const HEADER_LEN: usize = 16;
+/// Diff provides structured comparison of page versions
+#[cfg(feature = "alloc")]
+pub mod diff;
+#[cfg(feature = "alloc")]
+pub use diff::{PageDiff, OptionsDiff, FieldChange};
 
-/// Offsets for fixed fields in the protocol header
-mod offsets {
-    pub const PROTO_VERSION: usize = 0;
-    pub const APPLICATION_ID: usize = 2;
-    pub const OBJECT_KIND: usize = 4;
-    pub const FLAGS: usize = 6;
-    pub const INDEX: usize = 8;
-    pub const DATA_LEN: usize = 10;
-    pub const PRIVATE_OPTIONS_LEN: usize = 12;
-    pub const PUBLIC_OPTIONS_LEN: usize = 14;
-    pub const ID: usize = 16;
-    pub const BODY: usize = 48;
-}
+/// Graph links objects by dependency for store compaction and views
+#[cfg(feature = "alloc")]
+pub mod graph;
+#[cfg(feature = "alloc")]
+pub use graph::{ObjectGraph, Node, NodeKind};
+
+/// Adaptive provides encoding with allocated buffers sized to fit the encoded object
+#[cfg(feature = "alloc")]
+pub mod adaptive;
+#[cfg(feature = "alloc")]
+pub use adaptive::encode_with_alloc;
+
+/// Prefix provides routing information from truncated or partial objects
+pub mod prefix;
+pub use prefix::ObjectPrefix;
+
+/// Pages provides borrowed page lists for zero-copy parsing of page-carrying messages
+pub mod pages;
+pub use pages::PageList;
+
+/// Round-trip property tests across object classes
+#[cfg(all(test, feature = "std"))]
+mod roundtrip;
+
+/// Observe provides collection of unknown or unsupported protocol features for telemetry
+pub mod observe;
+pub use observe::{Observations, Observation, ObservationKind};
+
+/// Expiry defines configurable object expiry policies and clock sources
+pub mod expiry;
+pub use expiry::ExpiryPolicy;
+
+/// Version defines supported protocol versions and version negotiation
+pub mod version;
+pub use version::{Version, PROTOCOL_VERSION};
+
+/// Test vectors provides canonical encodings for validating other implementations
+#[cfg(all(feature = "std", any(test, feature = "testvectors")))]
+pub mod testvectors;
+
+/// Limits defines parsing limits to bound resource use for untrusted input
+pub mod limits;
+pub use limits::Limits;
+
+/// Consts defines fixed protocol lengths and offsets
+pub mod consts;
+pub use consts::{HEADER_LEN, MIN_OBJECT_LEN};
+pub(crate) use consts::offsets;
 
 
 /// Helper for validating signatures in symmetric or asymmetric modes
 fn validate<T: MutableData>(
     signing_id: &Id,
     keys: &Keys,
+    verifier: Option<&PreparedKey>,
     container: &mut Container<T>
 ) -> Result<bool, Error> {
     let header = container.header();
@@ -89,6 +149,13 @@ fn validate<T: MutableData>(
     // Otherwise use public key
     } else {
         debug!("Using asymmetric mode");
+
+        // Use pre-parsed key where available, these are checked against the ID on creation
+        if let Some(v) = verifier {
+            let signature = Signature::try_from(container.try_signature_raw()?)?;
+            return Crypto::pk_verify_prepared(v, &signature, container.try_signed()?)
+                .map_err(|_e| Error::CryptoError);
+        }
         
         // Check for matching public key
         let pub_key = match &keys.pub_key {
@@ -97,31 +164,176 @@ fn validate<T: MutableData>(
         };
         
         // Check ID matches public key
-        let h = Crypto::hash(pub_key).unwrap();
-        if signing_id.as_bytes() != h.as_bytes() {
-            error!("Public key mismatch for object from {:?} ({})", signing_id, h);
+        let h = Id::from_public_key(pub_key);
+        if signing_id != &h {
+            error!("Public key mismatch for object from {:?} ({:?})", signing_id, h);
             return Err(Error::KeyIdMismatch);
         }
 
         // Validate signature
-        Crypto::pk_verify(pub_key, &container.signature(), container.signed()).map_err(|_e| Error::CryptoError)?
+        let signature = Signature::try_from(container.try_signature_raw()?)?;
+        Crypto::pk_verify(pub_key, &signature, container.try_signed()?).map_err(|_e| Error::CryptoError)?
     };
 
     Ok(valid)
 }
 
+/// Helper for validating objects signed by a delegate of the service, using the
+/// [`Options::Delegation`] carried in the object public options.
+///
+/// Returns `Ok(false)` where the object carries no delegation. Delegation expiry is checked
+/// against the provided time, with delegations rejected where no time is available.
+fn validate_delegated<T: MutableData>(
+    id: &Id,
+    keys: &Keys,
+    now: Option<DateTime>,
+    container: &mut Container<T>,
+) -> Result<bool, Error> {
+    let delegation = OptionsIter::new(container.try_public_options_raw()?).find_map(|o| match o {
+        Options::Delegation(d) => Some(d),
+        _ => None,
+    });
+    let delegation = match delegation {
+        Some(d) => d,
+        None => return Ok(false),
+    };
+
+    let pub_key = match &keys.pub_key {
+        Some(pk) => pk,
+        None => return Err(Error::NoPublicKey),
+    };
+
+    // Check the delegation is issued by this service
+    if &delegation.issuer != id {
+        return Err(Error::InvalidDelegation);
+    }
+    crate::crypto::verify_delegation(&delegation, pub_key)?;
+
+    // Check the object kind is in scope
+    let kind = container.header().kind();
+    let scope = match (kind.is_page(), kind.is_data()) {
+        (true, _) => DelegationScope::PAGES,
+        (_, true) => DelegationScope::DATA,
+        _ => DelegationScope::empty(),
+    };
+    if scope.is_empty() || !delegation.scope.contains(scope) {
+        return Err(Error::InvalidDelegation);
+    }
+
+    // Check the delegation has not expired
+    match now {
+        Some(t) if t.as_secs() <= delegation.expiry.as_secs() => (),
+        Some(_) => return Err(Error::DelegationExpired),
+        None => {
+            debug!("No time available to check delegation expiry");
+            return Err(Error::DelegationExpired);
+        },
+    }
+
+    // Validate the object signature using the delegate key
+    let delegate_id = Id::from_public_key(&delegation.delegate);
+    validate(&delegate_id, &Keys::new(delegation.delegate), None, container)
+}
+
+/// Options used to locate signing keys, extracted in a single pass over public options
+#[derive(Debug, Default)]
+struct SigningOptions {
+    peer_id: Option<Id>,
+    pub_key: Option<PublicKey>,
+    delegated: bool,
+}
+
+impl SigningOptions {
+    /// Extract signing options from public options, with sections exceeding the provided limits
+    /// rejected (rather than truncated) so signing options can not be hidden past the option limit
+    fn extract<T: ImmutableData>(container: &Container<T>, limits: &OptionLimits) -> Result<Self, Error> {
+        let mut s = Self::default();
+
+        let raw = container.try_public_options_raw()?;
+        limits.check(raw)?;
+
+        for o in OptionsIter::with_limits(raw, limits) {
+            match o {
+                Options::PeerId(v) if s.peer_id.is_none() => s.peer_id = Some(v),
+                Options::PubKey(v) if s.pub_key.is_none() => s.pub_key = Some(v),
+                Options::Delegation(_) => s.delegated = true,
+                _ => (),
+            }
+        }
+
+        Ok(s)
+    }
+}
+
+/// Check cleartext options sections against the provided limits
+fn check_limits<T: ImmutableData>(container: &Container<T>, limits: &OptionLimits) -> Result<(), Error> {
+    container.validate_options(limits).map_err(|e| {
+        debug!("Options validation failed: {:?}", e);
+        e.into()
+    })
+}
+
+impl<T: ImmutableData> Container<T> {
+    /// Validate cleartext options sections against the provided limits and strictness,
+    /// returning a structured [`OptionError`] identifying the offending option on failure.
+    ///
+    /// Encrypted sections are skipped.
+    pub fn validate_options(&self, limits: &OptionLimits) -> Result<(), OptionError> {
+        // Symmetric mode encrypts both options sections
+        if self.encrypted() && self.header().flags().contains(Flags::SYMMETRIC_MODE) {
+            return Ok(());
+        }
+
+        // Sections exceeding the container are reported as truncated
+        let truncated = |_| OptionError{ violation: OptionViolation::Truncated, kind: None, offset: 0 };
+
+        if !self.encrypted() {
+            limits.validate(self.try_private_options_raw().map_err(truncated)?)?;
+        }
+        limits.validate(self.try_public_options_raw().map_err(truncated)?)?;
+
+        Ok(())
+    }
+}
+
 impl<'a, T: MutableData> Container<T> {
     /// Parses a data array into a base object using the pub_key and sec_key functions to locate keys for validation and decryption
     pub fn parse<K>(data: T, key_source: &K) -> Result<Container<T>, Error>
     where
         K: KeySource,
     {
-        // Build container over buffer
-        let (mut container, _n) = Container::from(data);
+        Self::parse_with_limits(data, key_source, &Limits::default())
+    }
 
-        trace!("Parsing object: {:02x?}", container.hex_dump());
+    /// Parses a data array into a base object, enforcing the provided object and options limits
+    pub fn parse_with_limits<K>(data: T, key_source: &K, limits: &Limits) -> Result<Container<T>, Error>
+    where
+        K: KeySource,
+    {
+        Self::parse_inner(data, key_source, limits, false)
+    }
+
+    /// Parse an object, skipping signature validation where this has already been performed
+    /// (for example, via batch verification in [`Container::decode_pages`])
+    fn parse_inner<K>(data: T, key_source: &K, limits: &Limits, pre_verified: bool) -> Result<Container<T>, Error>
+    where
+        K: KeySource,
+    {
+        // Build container over buffer, checking the header and lengths
+        let (mut container, n) = Container::checked(data)?;
 
         let (id, flags, kind, index) = {
+            span!(ParseHeader);
+
+            // Check object and body limits
+            limits.check_object_len(n)?;
+            limits.check_body_len(container.header().data_len())?;
+
+            trace!("Parsing object: {:02x?}", container.hex_dump());
+
+            // Check options sections against limits prior to any further processing
+            check_limits(&container, &limits.options)?;
+
             let header = container.header();
             trace!("Parsed header: {:02x?}", header);
 
@@ -131,96 +343,92 @@ impl<'a, T: MutableData> Container<T> {
         debug!("Parse container: {:?}", container);
 
         // Fetch signature for page
-        let mut verified = false;
+        let mut verified = pre_verified;
         let signature: Signature = container.signature();
 
         // Validate primary types immediately if pubkey is known
         let is_primary = !flags.contains(Flags::SECONDARY) && !flags.contains(Flags::TERTIARY);
 
-        match (is_primary, key_source.keys(&id)) {
-            (true, Some(keys)) if keys.pub_key.is_some() => {
-                let pub_key = keys.pub_key.as_ref().unwrap();
+        if is_primary && !verified {
+            match key_source.keys(&id) {
+                Some(keys) if keys.pub_key.is_some() => {
+                    trace!("Early signature validate: {:02x?} using key: {:?}", signature.as_ref(), keys.pub_key);
+
+                    // Perform verification
+                    verified = {
+                        span!(Verify);
+                        validate(&id, &keys, key_source.verifier(&id).as_ref(), &mut container)?
+                    };
+
+                    // Fall back to delegated signing where the service key does not match
+                    if !verified {
+                        verified = validate_delegated(&id, &keys, key_source.now(), &mut container)?;
+                    }
+
+                    // Stop processing if signature is invalid
+                    if !verified {
+                        info!("Invalid signature with known pubkey");
+                        return Err(Error::InvalidSignature);
+                    }
+                },
+                _ => {
+                    trace!("Skipping early signature validation, no keys loaded");
+                },
+            }
+        }
 
-                trace!("Early signature validate: {:02x?} using key: {:?}", signature.as_ref(), pub_key);
+        // Late validation for self-signed objects from unknown sources
+        if !verified {
+            trace!("Fetching public options");
 
-                // Perform verification
-                verified = validate(&id, &keys, &mut container)?;
+            // Fetch signing options in a single pass
+            let opts = {
+                span!(ParseOptions);
+                SigningOptions::extract(&container, &limits.options)?
+            };
 
-                // Stop processing if signature is invalid
-                if !verified {
-                    info!("Invalid signature with known pubkey");
-                    return Err(Error::InvalidSignature);
-                }
-            },
-            _ => {
-                trace!("Skipping early signature validation, no keys loaded");
-            },
-        }
+            // Look for signing ID
+            let signing_id: Id = match (is_primary, &opts.peer_id) {
+                (true, _) => id.clone(),
+                (false, Some(peer_id)) => peer_id.clone(),
+                _ => return Err(Error::NoPeerId),
+            };
 
-        trace!("Fetching public options");
+            trace!("Signing options: {:?} signing_id: {:?}", opts, signing_id);
 
-        // Fetch public options
-        let mut peer_id = None;
-        let mut pub_key = None;
-        let mut parent = None;
+            // Fetch public key, primary objects have already been looked up by ID
+            let stored = match is_primary {
+                true => None,
+                false => key_source.keys(&signing_id),
+            };
 
-        for o in container.public_options_iter() {
-            match o {
-                Options::PeerId(v) => {
-                    peer_id = Some(v.clone());
-                },
-                Options::PubKey(v) => {
-                    pub_key = Some(v.clone());
-                },
-                Options::PrevSig(v) => {
-                    parent = Some(v.clone());
-                },
-                _ => (),
-            }
-        }
+            let (keys, verifier) = match (stored, &opts.pub_key) {
+                (Some(keys), _) if keys.pub_key.is_some() => (keys, key_source.verifier(&signing_id)),
+                (_, Some(key)) => (Keys::new(key.clone()), None),
+                _ => {
+                    error!("No signature or key for object {:?} from {:?} (signing id: {:?})", index, id, signing_id);
+                    return Err(Error::NoSignature);
+                }
+            };
 
-        // Look for signing ID
-        let signing_id: Id = match (!is_primary, &peer_id) {
-            (false, _) => Ok(container.id()),
-            (true, Some(id)) => Ok(id.clone()),
-            _ => Err(Error::NoPeerId),
-        }?;
-
-        trace!("Peer id: {:?} pub_key: {:?} parent: {:?} signing_id: {:?}",
-            peer_id, pub_key, parent, signing_id);
-
-        // Fetch public key
-        let keys: Option<Keys> = match (key_source.keys(&signing_id), &pub_key) {
-            (Some(keys), _) if keys.pub_key.is_some() => Some(keys),
-            (_, Some(key)) => Some(Keys::new(key.clone())),
-            _ => {
-                warn!(
-                    "Missing public key for message: {:?} signing id: {:?}",
-                    index, signing_id
-                );
-                None
-            }
-        };
+            trace!("Late signature validate (keys: {:?})", keys);
 
-        trace!("Re-validating object (keys: {:?})", keys);
+            // Check signature
+            verified = {
+                span!(Verify);
+                validate(&signing_id, &keys, verifier.as_ref(), &mut container)?
+            };
 
-        // Late validation for self-signed objects from unknown sources
-        match (verified, keys) {
-            (false, Some(keys)) => {
-                // Check signature
-                verified = validate(&signing_id, &keys,  &mut container)?;
-
-                // Stop processing on verification failure
-                if !verified {
-                    info!("Invalid signature for self-signed object from {:?}", id);
-                    return Err(Error::InvalidSignature);
-                }
-            }
-            (false, None) => {
-                error!("No signature or key for object from {:?}", id);
-                return Err(Error::NoSignature);
+            // Stop processing on verification failure
+            if !verified {
+                info!("Invalid signature for self-signed object from {:?}", id);
+                return Err(Error::InvalidSignature);
             }
-            _ => (),
+        }
+
+        // Re-check options limits for objects decrypted during validation
+        if flags.contains(Flags::SYMMETRIC_MODE) {
+            check_limits(&container, &limits.options)?;
         }
 
         trace!("Parse OK! (verified: {:?})", verified);
@@ -232,6 +440,70 @@ impl<'a, T: MutableData> Container<T> {
     }
 }
 
+/// Batch verify asymmetric signatures over a list of objects, returning whether each object
+/// was verified. Objects without a known signing key, using symmetric or delegated signing,
+/// or with a custom verifier are excluded from the batch, and no objects are verified where
+/// the batch fails (these must then be verified individually to identify the invalid object).
+fn verify_batch<K: KeySource>(objects: &[Vec<u8>], key_source: &K, limits: &OptionLimits) -> Result<Vec<bool>, Error> {
+    let mut items: Vec<(PublicKey, Signature, usize, usize)> = vec![];
+    let mut last_key: Option<(Id, PublicKey)> = None;
+
+    for (i, o) in objects.iter().enumerate() {
+        let (c, _n) = Container::checked(&o[..])?;
+        let flags = c.header().flags();
+
+        if flags.contains(Flags::SYMMETRIC_MODE) {
+            continue;
+        }
+
+        // Resolve the signing key as in `parse`, from the key source or object options
+        let opts = SigningOptions::extract(&c, limits)?;
+        let is_primary = !flags.contains(Flags::SECONDARY) && !flags.contains(Flags::TERTIARY);
+        let signing_id = match (is_primary, &opts.peer_id) {
+            (true, _) => c.id(),
+            (false, Some(peer_id)) => peer_id.clone(),
+            _ => continue,
+        };
+
+        if opts.delegated || key_source.verifier(&signing_id).is_some() {
+            continue;
+        }
+
+        let cached = last_key.as_ref().filter(|(id, _)| id == &signing_id).map(|(_, k)| k.clone());
+        let stored = key_source.keys(&signing_id).and_then(|k| k.pub_key).or(cached);
+
+        if let (true, Some(pub_key)) = (is_primary, &opts.pub_key) {
+            last_key = Some((c.id(), pub_key.clone()));
+        }
+
+        let pub_key = match stored.or(opts.pub_key) {
+            Some(k) if Id::from_public_key(&k) == signing_id => k,
+            _ => continue,
+        };
+
+        items.push((pub_key, c.signature(), i, c.try_signed()?.len()));
+    }
+
+    let mut verified = vec![false; objects.len()];
+    if items.len() < 2 {
+        return Ok(verified);
+    }
+
+    let batch: Vec<_> = items.iter()
+        .map(|(pub_key, sig, i, n)| (pub_key, sig, &objects[*i][..*n]))
+        .collect();
+
+    if let Ok(true) = Crypto::pk_verify_batch(&batch) {
+        for (_, _, i, _) in items.iter() {
+            verified[*i] = true;
+        }
+    } else {
+        debug!("Batch verification failed, falling back to individual verification");
+    }
+
+    Ok(verified)
+}
+
 /// Helper to decrypt optionally encrypted fields
 pub(crate) fn decrypt(sk: &SecretKey, body: &mut MaybeEncrypted, private_opts: &mut MaybeEncrypted<Vec<Options>>, tag: Option<&SecretMeta>) -> Result<(), Error> {
     
@@ -310,24 +582,46 @@ impl Container {
     where
         V: KeySource,
     {
-        let mut pages = vec![];
+        Self::decode_pages_with_limits(buff, key_source, &Limits::default())
+    }
+
+    /// Decode a list of pages, enforcing the provided page count and per-object limits
+    pub fn decode_pages_with_limits<V>(buff: &[u8], key_source: &V, limits: &Limits) -> Result<Vec<Container>, Error>
+    where
+        V: KeySource,
+    {
+        // Split objects, checking headers and lengths
+        let mut objects = vec![];
         let mut i = 0;
+
+        while i < buff.len() {
+            limits.check_pages(objects.len() + 1)?;
+
+            let (_c, n) = Container::checked(&buff[i..])?;
+            objects.push(buff[i..][..n].to_vec());
+
+            i += n;
+        }
+
+        // Batch verify signatures where signing keys are available,
+        // objects not verified by the batch are individually verified on parsing
+        let pre_verified = verify_batch(&objects, key_source, &limits.options)?;
+
+        let mut pages = vec![];
     
         // Last key used to cache the previous primary key to decode secondary pages published by a service in a single message.
         let mut last_key: Option<(Id, Keys)> = None;
     
-        while i < buff.len() {
+        for (i, (o, pre_verified)) in objects.drain(..).zip(pre_verified).enumerate() {
             // TODO: validate signatures against existing services!
-            let c = match Container::parse((&buff[i..]).to_vec(), &key_source.cached(last_key.clone())){
+            let c = match Container::parse_inner(o, &key_source.with_fallback(last_key.clone()), limits, pre_verified) {
                 Ok(v) => v,
                 Err(e) => {
-                    debug!("Error parsing base message: {:?}", e);
+                    debug!("Error parsing page {}: {:?}", i, e);
                     return Err(e);
                 }
             };
     
-            i += c.len();
-    
             // Cache key for next run
             if let Some(key) = c.info()?.pub_key() {
                 last_key = Some((c.id().clone(), Keys::new(key)));
@@ -349,6 +643,8 @@ mod test {
     use super::*;
 
     use crate::{crypto, keys::NullKeySource, prelude::{Header, Body}};
+    use crate::options::{Delegation, Filters, OptionKind, OptionsIter, OptionValidation};
+    use crate::page::PageInfo;
 
     fn setup() -> (Id, Keys) {
         #[cfg(feature="simplelog")]
@@ -357,9 +653,7 @@ mod test {
         let (pub_key, pri_key) =
             Crypto::new_pk().expect("Error generating new public/private key pair");
 
-        let id = Id::from(Crypto::hash(&pub_key)
-            .expect("Error generating new ID")
-            .as_bytes());
+        let id = Id::from_public_key(&pub_key);
 
         let sec_key = Crypto::new_sk().expect("Error generating new secret key");
         (
@@ -389,7 +683,7 @@ mod test {
         // Encode using builder
         let c = Builder::new(vec![0u8; 1024])
             .id(&id)
-            .header(&header)
+            .header(&header).unwrap()
             .body(data).unwrap()
             .private_options(&[]).unwrap()
             .public()
@@ -406,6 +700,158 @@ mod test {
         assert_eq!(c, d);
     }
 
+    #[test]
+    fn private_page_passthrough() {
+        let (id, mut keys) = setup();
+        keys.sec_key = None;
+
+        let body = vec![0xde, 0xad, 0xbe, 0xef];
+
+        for flags in [Flags::empty(), Flags::SECONDARY, Flags::TERTIARY] {
+            let header = Header {
+                kind: PageKind::Private.into(),
+                flags,
+                ..Default::default()
+            };
+
+            let encoded = Builder::new(vec![0u8; 1024])
+                .id(&id)
+                .header(&header).unwrap()
+                .body(body.clone()).unwrap()
+                .private_options(&[]).unwrap()
+                .public()
+                .public_options(&[Options::peer_id(id.clone())]).unwrap()
+                .sign_pk(keys.pri_key.as_ref().unwrap())
+                .expect("Error encoding page");
+
+            // Private pages are validated by signature only
+            let decoded = Container::parse(encoded.raw().to_vec(), &keys).expect("Error parsing private page");
+            assert_eq!(decoded.info(), Ok(PageInfo::private(&body)));
+            assert_eq!(decoded.raw(), encoded.raw());
+        }
+    }
+
+    #[test]
+    fn parse_enforces_option_limits() {
+        let (id, mut keys) = setup();
+        keys.sec_key = None;
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            index: 12,
+            ..Default::default()
+        };
+
+        let opts: Vec<_> = (0..8).map(|i| Options::limit(i)).collect();
+        let encoded = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(vec![1, 2, 3]).unwrap()
+            .private_options(&[]).unwrap()
+            .public()
+            .public_options(&opts).unwrap()
+            .sign_pk(keys.pri_key.as_ref().unwrap())
+            .expect("Error encoding page");
+
+        // Default limits accept the object
+        Container::parse(encoded.raw().to_vec(), &keys).expect("Error parsing page");
+
+        // Reduced limits do not
+        let limits = OptionLimits{ max_count: 4, ..Default::default() };
+        assert_eq!(Container::parse_with_limits(encoded.raw().to_vec(), &keys, &limits.into()), Err(Error::TooManyOptions));
+
+        let limits = OptionLimits{ max_len: 16, ..Default::default() };
+        assert_eq!(Container::parse_with_limits(encoded.raw().to_vec(), &keys, &limits.into()), Err(Error::OptionsTooLong));
+
+        // And iterators stop at the option limit
+        let limits = OptionLimits{ max_count: 4, ..Default::default() };
+        assert_eq!(OptionsIter::with_limits(encoded.public_options_raw(), &limits).count(), 4);
+
+        // While signing options are rejected rather than truncated
+        assert_eq!(SigningOptions::extract(&encoded, &limits).err(), Some(Error::TooManyOptions));
+        assert!(SigningOptions::extract(&encoded, &OptionLimits::default()).is_ok());
+
+        // Strict validation rejects duplicate single-valued options
+        let limits = OptionLimits{ validation: OptionValidation::NoDuplicates, ..Default::default() };
+        let e = encoded.validate_options(&limits).unwrap_err();
+        assert_eq!(Container::parse_with_limits(encoded.raw().to_vec(), &keys, &limits.into()), Err(Error::InvalidOptions(e)));
+        assert_eq!(encoded.validate_options(&limits).map_err(|e| e.option_kind()), Err(Some(OptionKind::Limit)));
+    }
+
+    #[test]
+    fn semantic_eq_ignores_option_order() {
+        let (id, mut keys) = setup();
+        keys.sec_key = None;
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            index: 12,
+            ..Default::default()
+        };
+
+        let encode = |private: &[Options], public: &[Options]| {
+            Builder::new(vec![0u8; 1024])
+                .id(&id)
+                .header(&header).unwrap()
+                .body(vec![1, 2, 3]).unwrap()
+                .private_options(private).unwrap()
+                .public()
+                .public_options(public).unwrap()
+                .sign_pk(keys.pri_key.as_ref().unwrap())
+                .expect("Error encoding page")
+        };
+
+        let (n, k) = (Options::name("name"), Options::kind("kind"));
+
+        let a = encode(&[n.clone(), k.clone()], &[n.clone(), k.clone()]);
+        let b = encode(&[k.clone(), n.clone()], &[k.clone(), n.clone()]);
+        let c = encode(&[n.clone()], &[n.clone(), k.clone()]);
+        let d = encode(&[n.clone(), k.clone()], &[n.clone(), n.clone()]);
+
+        // Reordered options are semantically equal but not byte-equal
+        assert_ne!(a, b);
+        assert!(a.semantic_eq(&a));
+        assert!(a.semantic_eq(&b));
+
+        // Differing options are not
+        assert!(!a.semantic_eq(&c));
+        assert!(!a.semantic_eq(&d));
+
+        // Nor are matching objects with differing signatures
+        let mut e = a.clone();
+        let n = e.len;
+        e.buff[n - 1] ^= 0xFF;
+        assert!(!a.semantic_eq(&e));
+    }
+
+    #[test]
+    fn assemble_from_parts() {
+        use crate::service::Publisher;
+
+        let mut s = crate::service::ServiceBuilder::<Vec<u8>>::generic()
+            .public_options(vec![Options::name("parts")])
+            .body(vec![0xaa; 12])
+            .encrypt()
+            .build()
+            .unwrap();
+        let (_n, p) = s.publish_primary(Default::default(), vec![0u8; 1024]).unwrap();
+        let header = Header::from(&p.header());
+
+        let assemble = |tag: Option<&[u8]>, public: &[u8], buff: Vec<u8>| {
+            Container::assemble(buff, &header, &p.id(), p.body_raw(), p.private_options_raw(), tag, public, &p.signature())
+        };
+
+        // Reassembled objects match the original and parse
+        let c = assemble(p.tag_raw(), p.public_options_raw(), vec![0u8; 1024]).unwrap();
+        assert_eq!(c.raw(), p.raw());
+        Container::parse(c.raw().to_vec(), &s.keys()).expect("Error parsing assembled object");
+
+        // Parts inconsistent with the header are rejected
+        assert_eq!(assemble(None, p.public_options_raw(), vec![0u8; 1024]).err(), Some(Error::InvalidFlags));
+        assert_eq!(assemble(p.tag_raw(), &p.public_options_raw()[1..], vec![0u8; 1024]).err(), Some(Error::InvalidOptionLength));
+        assert_eq!(assemble(p.tag_raw(), p.public_options_raw(), vec![0u8; p.len() - 1]).err(), Some(Error::BufferLength));
+    }
+
     #[bench]
     fn bench_encode_primary(b: &mut Bencher) {
         let (id, mut keys) = setup();
@@ -423,7 +869,7 @@ mod test {
             // Encode using builder
             let _c = Builder::new([0u8; 1024])
                 .id(&id)
-                .header(&header)
+                .header(&header).unwrap()
                 .body(&data).unwrap()
                 .private_options(&[]).unwrap()
                 .public()
@@ -432,6 +878,230 @@ mod test {
         });
     }
 
+    /// Encode a store-style set of pages (primary and secondary) for parsing benchmarks,
+    /// returning the encoded pages with a key source for the publishing services
+    fn store_pages() -> (Vec<u8>, usize, crate::test_utils::TestKeySource) {
+        use crate::service::{Publisher, SecondaryOptions, ServiceBuilder};
+
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let mut p = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+
+        let (_n, c) = s.publish_primary(Default::default(), vec![0u8; 1024]).unwrap();
+        let mut pages = vec![c];
+        for _i in 0..4 {
+            let (_n, c) = p.publish_secondary(&s.id(), SecondaryOptions::default(), vec![0u8; 1024]).unwrap();
+            pages.push(c);
+        }
+
+        let mut buff = vec![0u8; 4096];
+        let n = Container::encode_pages(&pages, &mut buff).unwrap();
+        buff.truncate(n);
+
+        let ks = crate::test_utils::TestKeySource::new()
+            .with(s.id(), s.keys())
+            .with(p.id(), p.keys());
+
+        (buff, pages.len(), ks)
+    }
+
+    #[bench]
+    fn bench_parse_primary_known(b: &mut Bencher) {
+        let (id, mut keys) = setup();
+        keys.sec_key = None;
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            ..Default::default()
+        };
+
+        let p = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(vec![1, 2, 3, 4, 5, 6, 7]).unwrap()
+            .private_options(&[]).unwrap()
+            .public()
+            .sign_pk(keys.pri_key.as_ref().unwrap())
+            .expect("Error encoding page");
+
+        b.iter(|| {
+            let _c = Container::parse(p.raw().to_vec(), &keys).expect("Error parsing page");
+        });
+    }
+
+    #[bench]
+    fn bench_decode_store_pages(b: &mut Bencher) {
+        let (buff, n, ks) = store_pages();
+
+        b.iter(|| {
+            let pages = Container::decode_pages(&buff, &ks).expect("Error decoding pages");
+            assert_eq!(pages.len(), n);
+        });
+    }
+
+    #[test]
+    fn decode_pages_enforces_limits() {
+        let (buff, n, ks) = store_pages();
+
+        // Default limits accept the pages
+        assert_eq!(Container::decode_pages(&buff, &ks).unwrap().len(), n);
+
+        // Empty lists decode to no pages, truncated trailing pages are rejected
+        assert_eq!(Container::decode_pages(&[], &ks).map(|p| p.len()), Ok(0));
+        assert!(Container::decode_pages(&buff[..buff.len() - 1], &ks).is_err());
+
+        // Limits apply to the page count, so exactly `n` pages are accepted
+        let limits = Limits{ max_pages: n, ..Default::default() };
+        assert_eq!(Container::decode_pages_with_limits(&buff, &ks, &limits).map(|p| p.len()), Ok(n));
+
+        // Reduced page count or object length limits do not
+        let limits = Limits{ max_pages: n - 1, ..Default::default() };
+        assert_eq!(Container::decode_pages_with_limits(&buff, &ks, &limits).map(|p| p.len()), Err(Error::TooManyPages));
+
+        let limits = Limits{ max_object_len: MIN_OBJECT_LEN, ..Default::default() };
+        assert_eq!(Container::decode_pages_with_limits(&buff, &ks, &limits).map(|p| p.len()), Err(Error::ObjectTooLarge));
+
+        // Or body length limits
+        use crate::service::{DataOptions, Publisher, ServiceBuilder};
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let opts = DataOptions{ body: Some(&[0xaa; 16][..]), ..Default::default() };
+        let (_n, d) = s.publish_data(opts, vec![0u8; 1024]).unwrap();
+
+        let limits = Limits{ max_body_len: 8, ..Default::default() };
+        assert_eq!(Container::parse_with_limits(d.raw().to_vec(), &s.keys(), &limits).err(), Some(Error::ObjectTooLarge));
+
+        let limits = Limits{ max_body_len: 16, ..Default::default() };
+        assert!(Container::parse_with_limits(d.raw().to_vec(), &s.keys(), &limits).is_ok());
+    }
+
+    #[test]
+    fn decode_pages_batch_verify() {
+        let (mut buff, n, ks) = store_pages();
+
+        let pages = Container::decode_pages(&buff, &ks).unwrap();
+        assert_eq!(pages.len(), n);
+        assert!(pages.iter().all(|p| p.verified));
+
+        // Batch verification matches individual verification
+        let pub_key = |c: &Container| ks.keys(&c.id()).and_then(|k| k.pub_key).unwrap();
+        let (pk_s, pk_p) = (pub_key(&pages[0]), pub_key(&pages[1]));
+        let items: Vec<_> = pages.iter().enumerate()
+            .map(|(i, c)| (if i == 0 { &pk_s } else { &pk_p }, c.signature(), c.signed()))
+            .collect();
+        let batch: Vec<_> = items.iter().map(|(k, s, d)| (*k, s, *d)).collect();
+        assert_eq!(Crypto::pk_verify_batch(&batch), Ok(true));
+
+        let mut invalid = batch.clone();
+        invalid[0].0 = &pk_p;
+        assert_eq!(Crypto::pk_verify_batch(&invalid), Ok(false));
+
+        // A passing batch verifies all batched objects
+        let mut objects: Vec<_> = pages.iter().map(|p| p.raw().to_vec()).collect();
+        assert_eq!(verify_batch(&objects, &ks, &OptionLimits::default()), Ok(vec![true; n]));
+
+        // Invalid signatures fall back to individual verification, rejecting the invalid object
+        let offset = pages[..3].iter().map(|p| p.len()).sum::<usize>() - SIGNATURE_LEN / 2;
+        buff[offset] ^= 0x01;
+        objects[2][pages[2].len() - SIGNATURE_LEN / 2] ^= 0x01;
+        assert_eq!(verify_batch(&objects, &ks, &OptionLimits::default()), Ok(vec![false; n]));
+        assert_eq!(Container::parse(objects[2].clone(), &ks).err(), Some(Error::InvalidSignature));
+        assert_eq!(Container::decode_pages(&buff, &ks).map(|p| p.len()), Err(Error::InvalidSignature));
+    }
+
+    #[test]
+    fn parse_corrupt_objects() {
+        use crate::net::{Message, Request, RequestBody};
+        use crate::service::{Net, Publisher, ServiceBuilder};
+
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let (_n, p) = s.publish_primary(Default::default(), vec![0u8; 1024]).unwrap();
+        let req = Request::new(s.id(), 1, RequestBody::Hello, Flags::empty());
+        let r = s.encode_request_alloc(&req, &s.keys()).unwrap();
+
+        for o in [p.raw(), r.raw()] {
+            // Accessors over truncated buffers return errors
+            let (c, _) = Container::from(&o[..o.len() - 1]);
+            assert_eq!(c.try_signature_raw(), Err(Error::BufferLength));
+            assert_eq!(c.try_raw(), Err(Error::BufferLength));
+            assert!(Container::decode(&o[..o.len() - 1]).is_err());
+
+            // Truncated objects are rejected
+            for n in 0..o.len() {
+                assert!(Container::parse(o[..n].to_vec(), &s.keys()).is_err());
+                assert!(Message::parse(o[..n].to_vec(), &s.keys()).is_err());
+            }
+
+            // Corrupt headers do not cause panics
+            for i in 0..HEADER_LEN {
+                for v in [0x00, 0x01, 0x7f, 0xff] {
+                    let mut b = o.to_vec();
+                    b[i] = v;
+
+                    let _ = Container::parse(b.clone(), &s.keys());
+                    let _ = Message::parse(b, &s.keys());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_delegated_objects() {
+        use crate::crypto::{sign_delegation, verify_delegation};
+        use crate::service::{DataOptions, Publisher, Service, ServiceBuilder, Subscriber};
+
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let (_n, p) = s.publish_primary(Default::default(), vec![0u8; 1024]).unwrap();
+        let (delegate, mut delegate_key) = Crypto::new_pk().unwrap();
+
+        let expiry = DateTime::from_secs(DateTime::now().as_secs() + 3600);
+        let delegation = |scope, expiry| sign_delegation(&s.id(), &s.private_key().unwrap(), delegate.clone(), scope, expiry).unwrap();
+
+        let d = delegation(DelegationScope::DATA, expiry);
+        assert_eq!(verify_delegation(&d, &s.public_key()), Ok(()));
+        assert_eq!(verify_delegation(&d, &delegate), Err(Error::KeyIdMismatch));
+
+        // Delegates publish data using a replica of the service
+        let mut replica = Service::<Vec<u8>>::load(&p).unwrap();
+        let mut publish = |opts: &[Options]| {
+            let opts = DataOptions::<&[u8]> { public_options: opts, ..Default::default() };
+            let (_n, c) = replica.publish_data_signed(opts, &mut delegate_key, vec![0u8; 1024]).unwrap();
+            Container::parse(c.raw().to_vec(), &s.keys())
+        };
+
+        let c = publish(&[Options::delegation(d.clone())]).unwrap();
+        assert!(c.verified);
+
+        // Expiry is checked using the key source clock, failing closed where no time is available
+        struct NoClock(Keys);
+
+        impl KeySource for NoClock {
+            fn keys(&self, id: &Id) -> Option<Keys> {
+                self.0.keys(id)
+            }
+
+            fn now(&self) -> Option<DateTime> {
+                None
+            }
+        }
+
+        let late = DateTime::from_secs(expiry.as_secs() + 1);
+        assert!(Container::parse(c.raw().to_vec(), &s.keys().with_clock(expiry)).is_ok());
+        assert_eq!(Container::parse(c.raw().to_vec(), &s.keys().with_clock(late)).err(), Some(Error::DelegationExpired));
+        assert_eq!(Container::parse(c.raw().to_vec(), &NoClock(s.keys())).err(), Some(Error::DelegationExpired));
+
+        // Objects signed without a delegation are rejected
+        assert_eq!(publish(&[]).err(), Some(Error::InvalidSignature));
+
+        // As are delegations out of scope, expired, or with invalid signatures
+        let pages = delegation(DelegationScope::PAGES, expiry);
+        assert_eq!(publish(&[Options::delegation(pages)]).err(), Some(Error::InvalidDelegation));
+
+        let expired = delegation(DelegationScope::DATA, DateTime::from_secs(1_000));
+        assert_eq!(publish(&[Options::delegation(expired)]).err(), Some(Error::DelegationExpired));
+
+        let forged = Delegation { scope: DelegationScope::all(), ..d };
+        assert_eq!(publish(&[Options::delegation(forged)]).err(), Some(Error::InvalidDelegation));
+    }
+
     #[test]
     fn encode_decode_secondary_page() {
         let (id, mut keys) = setup();
@@ -446,7 +1116,7 @@ mod test {
 
         let encoded = Builder::new(vec![0u8; 1024])
             .id(&id)
-            .header(&header)
+            .header(&header).unwrap()
             .body(Body::Cleartext(data)).unwrap()
             .private_options(&[]).unwrap()
             .public()
@@ -485,7 +1155,7 @@ mod test {
 
         let encoded = Builder::new(vec![0u8; 1024])
             .id(&id)
-            .header(&header)
+            .header(&header).unwrap()
             .body(Body::Cleartext(data)).unwrap()
             .private_options(&[]).unwrap()
             .public()
@@ -515,7 +1185,7 @@ mod test {
 
         let encoded = Builder::new(vec![0u8; 1024])
             .id(&id)
-            .header(&header)
+            .header(&header).unwrap()
             .body(Body::Cleartext(data.clone())).unwrap()
             .private_options(&[]).unwrap()
             .encrypt(keys.sec_key.as_ref().unwrap()).unwrap()
@@ -537,6 +1207,141 @@ mod test {
         assert_eq!(decoded.body_raw(), &data);
     }
 
+    #[test]
+    fn encode_payload_dependent_options() {
+        use crate::crypto::Hash as _;
+
+        let (id, keys) = setup();
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            flags: Flags::ENCRYPTED,
+            ..Default::default()
+        };
+
+        let mut b = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(Body::Cleartext(vec![1, 2, 3, 4])).unwrap()
+            .private_options(&[]).unwrap()
+            .encrypt(keys.sec_key.as_ref().unwrap()).unwrap();
+
+        // Hash over the encrypted payload (standing in for a content hash option)
+        let slot = b.reserve_option(&Options::limit(0)).unwrap();
+        b.public_option_with(|p| Ok(Options::peer_id(Crypto::hash(p).unwrap().into()))).unwrap();
+        let payload = b.payload().to_vec();
+
+        // Patched once the object length is known
+        let n = b.as_ref().len() + SIGNATURE_LEN;
+        assert_eq!(b.patch_option(&slot, &Options::name("too long")), Err(Error::InvalidOptionLength));
+        b.patch_option(&slot, &Options::limit(n as u32)).unwrap();
+
+        let encoded = b.sign_pk(keys.pri_key.as_ref().unwrap()).unwrap();
+        let decoded = Container::parse(encoded.raw().to_vec(), &keys).expect("Error decoding page");
+
+        let opts: Vec<_> = decoded.public_options_iter().collect();
+        assert_eq!(opts, vec![Options::limit(n as u32), Options::peer_id(Crypto::hash(&payload).unwrap().into())]);
+        assert_eq!(decoded.len(), n);
+        assert_eq!(&decoded.raw()[offsets::BODY..][..payload.len()], &payload[..]);
+    }
+
+    #[test]
+    fn decode_typed_body() {
+        let (id, keys) = setup();
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            flags: Flags::ENCRYPTED,
+            ..Default::default()
+        };
+        let data = vec![1, 2, 3, 4, 5, 6, 7];
+        let private_options = [Options::name("private")];
+
+        let encoded = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(&data[..]).unwrap()
+            .private_options(&private_options).unwrap()
+            .encrypt(keys.sec_key.as_ref().unwrap()).unwrap()
+            .public_options(&[]).unwrap()
+            .sign_pk(keys.pri_key.as_ref().unwrap())
+            .expect("Error encoding page");
+
+        let mut decoded = Container::parse(encoded.raw().to_vec(), &keys).expect("Error decoding page");
+
+        // Typed accessors require decryption
+        assert_eq!(decoded.body::<Vec<u8>>(), Err(Error::NotDecrypted));
+        assert_eq!(decoded.private_options::<Options>(), Err(Error::NotDecrypted));
+
+        decoded.decrypt(keys.sec_key.as_ref().unwrap()).unwrap();
+        assert_eq!(decoded.body::<Vec<u8>>(), Ok(data));
+        assert_eq!(decoded.private_options::<Options>(), Ok(private_options[0].clone()));
+    }
+
+    #[test]
+    fn encode_decode_split_encrypted_page() {
+        let (id, keys) = setup();
+        let sk = keys.sec_key.as_ref().unwrap();
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            flags: Flags::ENCRYPTED | Flags::SPLIT_ENCRYPTION,
+            ..Default::default()
+        };
+        let data = vec![1, 2, 3, 4, 5, 6, 7];
+        let private_options = [Options::name("private-name")];
+
+        let encoded = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(Body::Cleartext(data.clone())).unwrap()
+            .private_options(&private_options).unwrap()
+            .encrypt(sk).unwrap()
+            .public_options(&[
+                Options::peer_id(id.clone()),
+            ]).unwrap()
+            .sign_pk(keys.pri_key.as_ref().unwrap())
+            .expect("Error encoding page");
+
+        let decoded = Container::parse(encoded.raw().to_vec(), &keys).expect("Error decoding page");
+        assert_eq!(encoded, decoded);
+        assert_eq!(decoded.tag_raw().map(|t| t.len()), Some(2 * SECRET_KEY_TAG_LEN));
+        assert_eq!(decoded.public_options_iter().peer_id(), Some(id.clone()));
+
+        // Body and private options can be decrypted independently
+        let mut buff = [0u8; 256];
+        assert_eq!(decoded.decrypt_body_to(sk, &mut buff).unwrap(), &data[..]);
+
+        let opts = decoded.decrypt_private_options_to(sk, &mut buff).unwrap();
+        assert_eq!(OptionsIter::new(opts).collect::<Vec<_>>(), private_options.to_vec());
+
+        // Or together
+        let (body, _opts) = decoded.decrypt_to(sk, &mut buff).unwrap();
+        assert_eq!(body, &data[..]);
+
+        // Re-encryption requires both tags
+        let tag = decoded.tag_raw().unwrap().to_vec();
+        let re_encrypt = |tag: &[u8]| Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(Body::Cleartext(data.clone())).unwrap()
+            .private_options(&private_options).unwrap()
+            .re_encrypt(sk, tag.to_vec());
+
+        assert_eq!(re_encrypt(&tag[..SECRET_KEY_TAG_LEN]).err(), Some(Error::BufferLength));
+
+        let re_encrypted = re_encrypt(&tag).unwrap()
+            .public_options(&[Options::peer_id(id.clone())]).unwrap()
+            .sign_pk(keys.pri_key.as_ref().unwrap())
+            .expect("Error re-encrypting page");
+        assert_eq!(re_encrypted.cyphertext(), decoded.cyphertext());
+
+        let mut decoded = decoded;
+        decoded.decrypt(sk).unwrap();
+        assert_eq!(decoded.body_raw(), &data);
+        assert_eq!(decoded.private_options_iter().collect::<Vec<_>>(), private_options.to_vec());
+    }
+
     #[test]
     fn encode_decode_encrypted_message() {
         let (id, keys) = setup();
@@ -556,7 +1361,7 @@ mod test {
 
         let encoded = Builder::new(vec![0u8; 1024])
             .id(&id)
-            .header(&header)
+            .header(&header).unwrap()
             .body(Body::Cleartext(data.clone())).unwrap()
             .private_options(&[]).unwrap()
             .public()
@@ -593,7 +1398,7 @@ mod test {
             // Encode using builder
             let _c = Builder::new([0u8; 1024])
                 .id(&id)
-                .header(&header)
+                .header(&header).unwrap()
                 .body(&data).unwrap()
                 .private_options(&[]).unwrap()
                 .encrypt(keys.sec_key.as_ref().unwrap()).unwrap()
@@ -621,7 +1426,7 @@ mod test {
             // Encode using builder
             let _c = Builder::new([0u8; 1024])
                 .id(&id)
-                .header(&header)
+                .header(&header).unwrap()
                 .body(&data).unwrap()
                 .private_options(&[]).unwrap()
                 .public()
@@ -656,7 +1461,7 @@ mod test {
             // Encode using builder
             let _c = Builder::new([0u8; 1024])
                 .id(&id)
-                .header(&header)
+                .header(&header).unwrap()
                 .body(&data).unwrap()
                 .private_options(&[]).unwrap()
                 .public()
@@ -0,0 +1,282 @@
+//! Collection of unknown or unsupported protocol features observed when parsing objects,
+//! allowing deployments to measure how often they encounter newer-protocol peers.
+//!
+//! Observations are collected from parsed objects with [`Container::observations`] and
+//! may be aggregated across objects with [`Observations::merge`].
+
+use core::convert::TryFrom;
+
+use crate::options::OptionRefIter;
+use crate::types::*;
+
+use super::Container;
+use super::version::Version;
+
+pub use super::version::PROTOCOL_VERSION;
+
+/// Maximum number of distinct observations recorded
+pub const MAX_OBSERVATIONS: usize = 16;
+
+/// Categories of unknown or unsupported protocol features
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum ObservationKind {
+    /// Unsupported protocol version (code is the version)
+    ProtocolVersion,
+    /// Unknown (non-application) object kind (code is the raw kind)
+    ObjectKind,
+    /// Unknown header flags (code is the unknown flag bits)
+    Flags,
+    /// Unknown option kind (code is the raw option kind)
+    Option,
+}
+
+/// Observation of an unknown or unsupported protocol feature
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Observation {
+    /// Observation category
+    pub kind: ObservationKind,
+    /// Observed code (version, kind, flags, or option kind)
+    pub code: u16,
+    /// Number of times observed
+    pub count: u32,
+}
+
+/// Fixed-capacity collection of observations, aggregated by kind and code
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Observations {
+    entries: heapless::Vec<Observation, MAX_OBSERVATIONS>,
+    dropped: u32,
+}
+
+impl Observations {
+    /// Create an empty observation collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an observation, incrementing the count for existing entries.
+    ///
+    /// Observations beyond [`MAX_OBSERVATIONS`] distinct entries are counted as dropped.
+    pub fn record(&mut self, kind: ObservationKind, code: u16) {
+        self.record_n(kind, code, 1)
+    }
+
+    fn record_n(&mut self, kind: ObservationKind, code: u16, count: u32) {
+        match self.entries.iter_mut().find(|o| o.kind == kind && o.code == code) {
+            Some(o) => o.count = o.count.saturating_add(count),
+            None => if self.entries.push(Observation{ kind, code, count }).is_err() {
+                self.dropped = self.dropped.saturating_add(count);
+            },
+        }
+    }
+
+    /// Merge observations from another collection
+    pub fn merge(&mut self, other: &Observations) {
+        for o in other.iter() {
+            self.record_n(o.kind, o.code, o.count);
+        }
+        self.dropped = self.dropped.saturating_add(other.dropped);
+    }
+
+    /// Iterate over recorded observations
+    pub fn iter(&self) -> impl Iterator<Item=&Observation> {
+        self.entries.iter()
+    }
+
+    /// Fetch the count for a given observation
+    pub fn count(&self, kind: ObservationKind, code: u16) -> u32 {
+        self.entries.iter().find(|o| o.kind == kind && o.code == code).map(|o| o.count).unwrap_or(0)
+    }
+
+    /// Fetch the number of observations dropped due to capacity
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Check whether any observations have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.dropped == 0
+    }
+}
+
+impl<T: ImmutableData> Container<T> {
+    /// Collect observations of unknown or unsupported protocol features in this object,
+    /// including the protocol version, object kind, flags, and cleartext option kinds.
+    ///
+    /// Option sections with lengths exceeding the object buffer are not observed.
+    pub fn observations(&self) -> Observations {
+        let mut obs = Observations::new();
+        let header = self.header();
+
+        if !Version::is_supported(header.protocol_version()) {
+            obs.record(ObservationKind::ProtocolVersion, header.protocol_version());
+        }
+
+        // Data kinds are application defined so are not checked
+        let kind = header.kind();
+        let known = match kind {
+            k if k.is_application() || k.is_data() => true,
+            k if k.is_page() => PageKind::try_from(k).is_ok(),
+            k if k.is_request() => RequestKind::try_from(k).is_ok(),
+            k => ResponseKind::try_from(k).is_ok(),
+        };
+        if !known {
+            obs.record(ObservationKind::ObjectKind, u16::from(kind));
+        }
+
+        let unknown_flags = header.flags().bits() & !Flags::all().bits();
+        if unknown_flags != 0 {
+            obs.record(ObservationKind::Flags, unknown_flags);
+        }
+
+        // Encrypted private options are opaque, and sections exceeding the buffer are skipped
+        let private = match self.encrypted() {
+            true => &[][..],
+            false => self.try_private_options_raw().unwrap_or(&[]),
+        };
+        let public = match self.encrypted() && header.flags().contains(Flags::SYMMETRIC_MODE) {
+            true => &[][..],
+            false => self.try_public_options_raw().unwrap_or(&[]),
+        };
+
+        for o in OptionRefIter::new(private).chain(OptionRefIter::new(public)) {
+            if o.kind().is_none() {
+                obs.record(ObservationKind::Option, o.raw_kind());
+            }
+        }
+
+        obs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::base::Header;
+    use crate::crypto::{Crypto, PubKey as _};
+    use crate::options::Options;
+    use crate::wire::{header::WireHeader, offsets, Builder, HEADER_LEN};
+
+    /// Encode a signed generic object with the provided public options
+    fn object(public_options: &[Options]) -> Container {
+        let (pub_key, pri_key) =
+            Crypto::new_pk().expect("Error generating new public/private key pair");
+        let id = Id::from_public_key(&pub_key);
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            ..Default::default()
+        };
+
+        Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(vec![1, 2, 3, 4]).unwrap()
+            .private_options(&[]).unwrap()
+            .public()
+            .public_options(public_options).unwrap()
+            .sign_pk(&pri_key)
+            .expect("Error encoding object")
+    }
+
+    #[test]
+    fn observe_unknown_features() {
+        // Known objects produce no observations
+        let d = object(&[Options::name("test")]);
+        assert!(d.observations().is_empty());
+
+        // Unknown options are counted by kind
+        let mut unknown = [0u8; 8];
+        unknown[..2].copy_from_slice(&0x0fffu16.to_be_bytes());
+        unknown[3] = 4;
+
+        let mut b = d.raw().to_vec();
+        let o = d.header().public_options_offset();
+        b.splice(o..o, unknown.iter().cloned());
+        b.splice(o..o, unknown.iter().cloned());
+
+        let n = d.header().public_options_len() + 2 * unknown.len();
+        WireHeader::new(&mut b[..HEADER_LEN]).set_public_options_len(n);
+
+        let obs = Container::from(b.clone()).0.observations();
+        assert_eq!(obs.count(ObservationKind::Option, 0x0fff), 2);
+        assert_eq!(obs.count(ObservationKind::Option, 0x0ffe), 0);
+        assert_eq!(obs.iter().count(), 1);
+
+        // Along with unknown versions, kinds and flags
+        b[offsets::PROTO_VERSION..][..2].copy_from_slice(&1u16.to_be_bytes());
+        b[offsets::FLAGS..][..2].copy_from_slice(&(1u16 << 15).to_be_bytes());
+        b[offsets::OBJECT_KIND..][..2].copy_from_slice(&u16::from(Kind::request(0x0100)).to_be_bytes());
+
+        let obs = Container::from(b).0.observations();
+        assert_eq!(obs.count(ObservationKind::ProtocolVersion, 1), 1);
+        assert_eq!(obs.count(ObservationKind::Flags, 1 << 15), 1);
+        assert_eq!(obs.count(ObservationKind::ObjectKind, u16::from(Kind::request(0x0100))), 1);
+
+        // Observations aggregate across objects
+        let mut all = Observations::new();
+        all.merge(&obs);
+        all.merge(&obs);
+        assert_eq!(all.count(ObservationKind::Option, 0x0fff), 4);
+        assert_eq!(all.iter().count(), 4);
+    }
+
+    #[test]
+    fn observe_invalid_option_lengths() {
+        let mut unknown = [0u8; 8];
+        unknown[..2].copy_from_slice(&0x0fffu16.to_be_bytes());
+        unknown[3] = 4;
+
+        // Objects with unknown options in both sections
+        let d = object(&[]);
+        let mut b = d.raw().to_vec();
+        let o = d.header().public_options_offset();
+        b.splice(o..o, unknown.iter().cloned());
+        let o = d.header().private_options_offset();
+        b.splice(o..o, unknown.iter().cloned());
+
+        let mut h = WireHeader::new(&mut b[..HEADER_LEN]);
+        h.set_private_options_len(unknown.len());
+        h.set_public_options_len(unknown.len());
+
+        let obs = Container::from(b.clone()).0.observations();
+        assert_eq!(obs.count(ObservationKind::Option, 0x0fff), 2);
+
+        // Oversized public options lengths skip the public section
+        WireHeader::new(&mut b[..HEADER_LEN]).set_public_options_len(1024);
+        let obs = Container::from(b.clone()).0.observations();
+        assert_eq!(obs.count(ObservationKind::Option, 0x0fff), 1);
+
+        // Truncated buffers skip both sections without panicking
+        let n = offsets::BODY + d.header().data_len() + 4;
+        let obs = Container::from(&b[..n]).0.observations();
+        assert_eq!(obs.count(ObservationKind::Option, 0x0fff), 0);
+    }
+
+    #[test]
+    fn observations_capacity() {
+        assert!(Observations::new().is_empty());
+
+        // Distinct observations beyond capacity are dropped, existing entries are still counted
+        let mut obs = Observations::new();
+        for i in 0..MAX_OBSERVATIONS as u16 + 2 {
+            obs.record(ObservationKind::Option, i);
+        }
+        obs.record(ObservationKind::Option, 0);
+
+        assert_eq!(obs.iter().count(), MAX_OBSERVATIONS);
+        assert_eq!(obs.count(ObservationKind::Option, 0), 2);
+        assert_eq!(obs.count(ObservationKind::Option, MAX_OBSERVATIONS as u16), 0);
+        assert_eq!(obs.dropped(), 2);
+
+        // Dropped counts carry through merges
+        let mut all = Observations::new();
+        all.merge(&obs);
+        assert_eq!(all.dropped(), 2);
+        assert!(!all.is_empty());
+    }
+}
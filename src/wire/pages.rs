@@ -0,0 +1,145 @@
+//! Borrowed page lists, verifying pages in place within a message buffer so that page-carrying
+//! messages may be parsed without copying each page into an owned container.
+
+use encdec::Decode;
+
+use crate::error::Error;
+use crate::keys::{KeySource, Keys};
+use crate::types::*;
+
+use super::{Container, Limits};
+
+/// List of verified pages borrowed from a message buffer
+#[derive(Clone, Debug, PartialEq)]
+pub struct PageList<'a> {
+    buff: &'a [u8],
+    count: usize,
+}
+
+impl <'a> PageList<'a> {
+    /// Verify a list of encoded pages in place, enforcing the provided page count and per-object limits.
+    ///
+    /// Pages are parsed and verified as with [`Container::decode_pages_with_limits`],
+    /// the buffer is mutable as verification may decrypt objects in place.
+    pub fn parse<K: KeySource>(buff: &'a mut [u8], key_source: &K, limits: &Limits) -> Result<Self, Error> {
+        let mut count = 0;
+        let mut i = 0;
+
+        // Cache the previous primary key to verify secondary pages published by a service in a single message
+        let mut last_key: Option<(Id, Keys)> = None;
+
+        while i < buff.len() {
+            limits.check_pages(count + 1)?;
+
+            let c = match Container::parse_with_limits(&mut buff[i..], &key_source.with_fallback(last_key.clone()), limits) {
+                Ok(v) => v,
+                Err(e) => {
+                    debug!("Error parsing page {}: {:?}", count, e);
+                    return Err(e);
+                }
+            };
+
+            i += c.len();
+            count += 1;
+
+            if let Some(key) = c.info()?.pub_key() {
+                last_key = Some((c.id(), Keys::new(key)));
+            }
+        }
+
+        Ok(Self { buff, count })
+    }
+
+    /// Fetch the number of pages in the list
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Check whether the list is empty
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Fetch the encoded pages
+    pub fn raw(&self) -> &'a [u8] {
+        self.buff
+    }
+
+    /// Iterate over borrowed (verified) pages
+    pub fn iter(&self) -> impl Iterator<Item = Container<&'a [u8]>> + Clone {
+        let mut buff = self.buff;
+
+        core::iter::from_fn(move || {
+            if buff.is_empty() {
+                return None;
+            }
+
+            // Pages have been verified on parse so decoding is infallible
+            let (mut c, n) = Container::decode(buff).ok()?;
+            c.verified = true;
+            buff = &buff[n..];
+
+            Some(c)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keys::NullKeySource;
+    use crate::service::{Publisher, ServiceBuilder};
+
+    #[test]
+    fn parse_page_list() {
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let (_n, p) = s.publish_primary_buff(Default::default()).unwrap();
+        let (_n, d) = s.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+
+        let mut buff = [p.raw(), d.raw()].concat();
+        let keys = s.keys();
+
+        // Pages are verified and borrowed from the buffer
+        let l = PageList::parse(&mut buff, &keys, &Limits::default()).unwrap();
+        assert_eq!(l.len(), 2);
+        let pages: Vec<_> = l.iter().collect();
+        assert_eq!(pages, vec![p.borrowed(), d.borrowed()]);
+        assert!(pages.iter().all(|c| c.verified));
+
+        // Empty buffers produce empty lists
+        let l = PageList::parse(&mut [], &keys, &Limits::default()).unwrap();
+        assert!(l.is_empty());
+        assert_eq!(l.iter().count(), 0);
+
+        // Page counts are limited
+        let mut buff = [p.raw(), d.raw()].concat();
+        let limits = Limits { max_pages: 1, ..Default::default() };
+        assert_eq!(PageList::parse(&mut buff, &keys, &limits), Err(Error::TooManyPages));
+
+        // Invalid or truncated pages are rejected
+        let mut buff = [p.raw(), d.raw()].concat();
+        let n = buff.len();
+        buff[n - 1] ^= 0xff;
+        assert!(PageList::parse(&mut buff, &keys, &Limits::default()).is_err());
+
+        let mut buff = [p.raw(), d.raw()].concat();
+        let n = buff.len();
+        assert!(PageList::parse(&mut buff[..n - 1], &keys, &Limits::default()).is_err());
+    }
+
+    #[test]
+    fn parse_page_list_unknown_keys() {
+        let mut s = ServiceBuilder::<Vec<u8>>::generic().build().unwrap();
+        let (_n, p) = s.publish_primary_buff(Default::default()).unwrap();
+        let (_n, d) = s.publish_data_buff::<&[u8]>(Default::default()).unwrap();
+
+        // Pages following a primary page are verified using its public key
+        let mut buff = [p.raw(), d.raw()].concat();
+        let l = PageList::parse(&mut buff, &NullKeySource, &Limits::default()).unwrap();
+        assert_eq!(l.len(), 2);
+
+        // Which is otherwise unavailable for data objects
+        let mut buff = d.raw().to_vec();
+        assert!(PageList::parse(&mut buff, &NullKeySource, &Limits::default()).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+//! Prefix parsing for truncated or partial objects, extracting routing information (header and ID)
+//! from whatever bytes are available so relays can still route or respond to damaged objects.
+//!
+//! Prefixes are not verified and must not be trusted beyond routing and error reporting.
+
+use core::convert::TryFrom;
+
+use crate::base::Header;
+use crate::error::Error;
+use crate::types::*;
+
+use super::{Container, header::WireHeader, offsets, HEADER_LEN};
+
+/// Routing information extracted from a (possibly truncated) object
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectPrefix {
+    /// Object header
+    pub header: Header,
+    /// Object ID, where available
+    pub id: Option<Id>,
+    /// Encoded object length (from header)
+    pub encoded_len: usize,
+    /// Available object bytes
+    pub available: usize,
+}
+
+impl ObjectPrefix {
+    /// Check whether the full object is available
+    pub fn is_complete(&self) -> bool {
+        self.available >= self.encoded_len
+    }
+
+    /// Number of bytes missing from the object
+    pub fn missing(&self) -> usize {
+        self.encoded_len.saturating_sub(self.available)
+    }
+}
+
+impl <'a> Container<&'a [u8]> {
+    /// Parse the header and ID from a (possibly truncated) object, reading only the fixed
+    /// fields available in the buffer. Returns [`Error::Incomplete`] where the header itself is truncated.
+    pub fn parse_prefix(data: &'a [u8]) -> Result<ObjectPrefix, Error> {
+        if data.len() < HEADER_LEN {
+            debug!("Incomplete object header ({} of {} bytes)", data.len(), HEADER_LEN);
+            return Err(Error::Incomplete);
+        }
+
+        let h = WireHeader::new(&data[..HEADER_LEN]);
+        let encoded_len = h.encoded_len();
+
+        let id = data.get(offsets::ID..offsets::BODY)
+            .and_then(|d| Id::try_from(d).ok());
+
+        Ok(ObjectPrefix {
+            header: Header::from(&h),
+            id,
+            encoded_len,
+            available: data.len().min(encoded_len),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crypto::{Crypto, PubKey as _};
+    use crate::wire::Builder;
+
+    #[test]
+    fn parse_truncated_prefix() {
+        let (pub_key, pri_key) =
+            Crypto::new_pk().expect("Error generating new public/private key pair");
+        let id = Id::from_public_key(&pub_key);
+
+        let header = Header {
+            kind: PageKind::Generic.into(),
+            application_id: 10,
+            index: 12,
+            ..Default::default()
+        };
+
+        let p = Builder::new(vec![0u8; 1024])
+            .id(&id)
+            .header(&header).unwrap()
+            .body(vec![1, 2, 3, 4, 5, 6, 7]).unwrap()
+            .private_options(&[]).unwrap()
+            .public()
+            .sign_pk(&pri_key)
+            .expect("Error encoding page");
+        let raw = p.raw();
+
+        // Complete objects report all fields
+        let c = Container::parse_prefix(raw).unwrap();
+        assert_eq!(c.header, Header::from(&p.header()));
+        assert_eq!(c.id, Some(id));
+        assert_eq!(c.encoded_len, p.len());
+        assert!(c.is_complete());
+        assert_eq!(c.missing(), 0);
+
+        // Trailing data is not counted as part of the object
+        let mut extended = raw.to_vec();
+        extended.extend_from_slice(&[0xaa; 8]);
+        let e = Container::parse_prefix(&extended).unwrap();
+        assert_eq!((e.available, e.missing()), (p.len(), 0));
+
+        // Truncated objects report available fields and missing bytes
+        let t = Container::parse_prefix(&raw[..offsets::BODY + 4]).unwrap();
+        assert_eq!((&t.header, &t.id), (&c.header, &c.id));
+        assert_eq!(t.missing(), p.len() - offsets::BODY - 4);
+
+        let t = Container::parse_prefix(&raw[..offsets::BODY]).unwrap();
+        assert_eq!(t.id, c.id);
+
+        let t = Container::parse_prefix(&raw[..HEADER_LEN + 1]).unwrap();
+        assert_eq!((&t.id, t.is_complete()), (&None, false));
+        assert_eq!(t.missing(), p.len() - HEADER_LEN - 1);
+
+        // Truncated headers are rejected
+        assert_eq!(Container::parse_prefix(&raw[..HEADER_LEN - 1]), Err(Error::Incomplete));
+        assert_eq!(Container::parse_prefix(&[]), Err(Error::Incomplete));
+    }
+}
@@ -0,0 +1,232 @@
+//! Property tests for encode / parse round-trips across object classes, encoding randomly
+//! generated pages, data objects and messages and checking these re-parse to matching objects.
+
+use proptest::prelude::*;
+
+use crate::base::Header;
+use crate::crypto::{Crypto, SecKey as _};
+use crate::keys::Keys;
+use crate::net::{Message, Request, RequestBody, Response, ResponseBody, Status, request::is_discover_filter};
+use crate::options::{Options, OptionsIter};
+use crate::service::Net;
+use crate::test_utils;
+use crate::types::*;
+
+use super::{peek, Builder, Container};
+
+/// Object classes for round-trip testing
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Class {
+    Primary,
+    Secondary,
+    Tertiary,
+    Data,
+}
+
+fn id() -> impl Strategy<Value = Id> {
+    any::<[u8; ID_LEN]>().prop_map(Id::from)
+}
+
+fn text() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9 _.-]{0,24}"
+}
+
+fn option() -> impl Strategy<Value = Options> {
+    prop_oneof![
+        text().prop_map(|v| Options::name(&v)),
+        text().prop_map(|v| Options::kind(&v)),
+        ("[a-z_]{1,16}", text()).prop_map(|(k, v)| Options::meta(&k, &v)),
+        text().prop_map(|v| Options::content_type(&v)),
+        any::<u32>().prop_map(|t| Options::issued(DateTime::from_secs(t as u64))),
+        any::<u32>().prop_map(|t| Options::expiry(DateTime::from_secs(t as u64))),
+        any::<u32>().prop_map(Options::limit),
+        any::<u16>().prop_map(Options::data_kind),
+        any::<u16>().prop_map(Options::min_index),
+        id().prop_map(Options::delegate),
+        id().prop_map(Options::tenant),
+    ]
+}
+
+fn options() -> impl Strategy<Value = Vec<Options>> {
+    prop::collection::vec(option(), 0..6)
+}
+
+/// Options for discovery requests, excluding those extracted into common message fields
+fn discover_options() -> impl Strategy<Value = Vec<Options>> {
+    prop::collection::vec(option(), 0..6)
+        .prop_map(|o| o.into_iter().filter(is_discover_filter).collect())
+}
+
+fn class() -> impl Strategy<Value = Class> {
+    prop_oneof![Just(Class::Primary), Just(Class::Secondary), Just(Class::Tertiary), Just(Class::Data)]
+}
+
+fn request_body() -> impl Strategy<Value = RequestBody> {
+    prop_oneof![
+        Just(RequestBody::Hello),
+        Just(RequestBody::Ping),
+        id().prop_map(RequestBody::FindNode),
+        id().prop_map(RequestBody::FindValue),
+        id().prop_map(RequestBody::Locate),
+        id().prop_map(RequestBody::Subscribe),
+        id().prop_map(RequestBody::Unsubscribe),
+        id().prop_map(RequestBody::Query),
+        id().prop_map(RequestBody::Unregister),
+        (prop::collection::vec(any::<u8>(), 0..32), discover_options()).prop_map(|(b, o)| RequestBody::Discover(b, o)),
+    ]
+}
+
+fn response_body() -> impl Strategy<Value = ResponseBody> {
+    prop_oneof![
+        Just(ResponseBody::Status(Status::Ok)),
+        Just(ResponseBody::Status(Status::InvalidRequest)),
+        Just(ResponseBody::Status(Status::Failed)),
+        any::<u32>().prop_map(|v| ResponseBody::Status(Status::from(v))),
+        Just(ResponseBody::NoResult),
+    ]
+}
+
+/// Encode and sign an object of the provided class
+#[allow(clippy::too_many_arguments)]
+fn encode(
+    class: Class,
+    id: &Id,
+    application_id: u16,
+    kind_index: u16,
+    index: u16,
+    body: &[u8],
+    private_options: &[Options],
+    public_options: &[Options],
+    keys: &Keys,
+    signing_id: &Id,
+) -> Result<Container<Vec<u8>>, crate::error::Error> {
+    let (kind, flags) = match class {
+        Class::Primary => (Kind::page(kind_index), Flags::empty()),
+        Class::Secondary => (Kind::page(kind_index), Flags::SECONDARY),
+        Class::Tertiary => (Kind::page(kind_index), Flags::TERTIARY),
+        Class::Data => (Kind::data(kind_index), Flags::empty()),
+    };
+    let header = Header { application_id, kind, flags, index, ..Default::default() };
+
+    // Objects signed by other services reference the signing ID
+    let mut public_options = public_options.to_vec();
+    if class == Class::Secondary || class == Class::Tertiary {
+        public_options.insert(0, Options::peer_id(signing_id.clone()));
+    }
+
+    let b = Builder::new(vec![0u8; 4096])
+        .header(&header)?
+        .id(id)
+        .body(body)?
+        .private_options(private_options)?;
+
+    let b = match &keys.sec_key {
+        Some(sk) => b.encrypt(sk)?,
+        None => b.public(),
+    };
+
+    b.public_options(&public_options)?.sign_pk(keys.pri_key.as_ref().unwrap())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn object_round_trip(
+        class in class(),
+        target in id(),
+        application_id in any::<u16>(),
+        kind_index in 0u16..0x1fff,
+        index in any::<u16>(),
+        body in prop::collection::vec(any::<u8>(), 0..256),
+        private_options in options(),
+        public_options in options(),
+        encrypted in any::<bool>(),
+    ) {
+        let mut keys = test_utils::keys(test_utils::SERVICE_SEED);
+        keys.sec_key = match encrypted {
+            true => Some(Crypto::new_sk().unwrap()),
+            false => None,
+        };
+        let signing_id = keys.id().unwrap();
+
+        // Primary and data objects are published by the signing service
+        let id = match class {
+            Class::Primary | Class::Data => signing_id.clone(),
+            _ => target,
+        };
+
+        let c = encode(class, &id, application_id, kind_index, index, &body, &private_options, &public_options, &keys, &signing_id)
+            .expect("Error encoding object");
+
+        // Encoded lengths are consistent
+        let h = c.header();
+        prop_assert_eq!(c.len(), c.raw().len());
+        prop_assert_eq!(h.encoded_len(), c.len());
+        prop_assert_eq!(peek(c.raw()), Ok((application_id, h.kind(), h.flags(), c.len())));
+
+        // Parsed objects match encoded objects
+        let d = Container::parse(c.raw().to_vec(), &keys).expect("Error parsing object");
+        prop_assert_eq!(d.raw(), c.raw());
+        prop_assert_eq!(d.id(), id);
+        prop_assert_eq!(d.encrypted(), encrypted);
+
+        let h = d.header();
+        prop_assert_eq!((h.application_id(), h.index(), h.kind().index()), (application_id, index, kind_index));
+        prop_assert_eq!(h.kind().is_data(), class == Class::Data);
+
+        let mut expected_public = public_options.clone();
+        if class == Class::Secondary || class == Class::Tertiary {
+            expected_public.insert(0, Options::peer_id(signing_id));
+        }
+        prop_assert_eq!(d.public_options_iter().collect::<Vec<_>>(), expected_public);
+
+        // Body and private options match following decryption
+        let mut buff = vec![0u8; 4096];
+        let (b, o) = match &keys.sec_key {
+            Some(sk) => d.decrypt_to(sk, &mut buff).expect("Error decrypting object"),
+            None => (d.body_raw(), d.private_options_raw()),
+        };
+        prop_assert_eq!(b, &body[..]);
+        prop_assert_eq!(OptionsIter::new(o).collect::<Vec<_>>(), private_options);
+    }
+
+    #[test]
+    fn request_round_trip(
+        request_id in any::<u16>(),
+        body in request_body(),
+        address_request in any::<bool>(),
+    ) {
+        let (source, target) = test_utils::service_pair();
+
+        let flags = match address_request {
+            true => Flags::ADDRESS_REQUEST,
+            false => Flags::empty(),
+        };
+        let req = Request::new(source.id(), request_id, body, flags).with_public_key(source.public_key());
+
+        let c = source.encode_request(&req, &target.keys(), vec![0u8; 4096]).expect("Error encoding request");
+        prop_assert_eq!(c.header().encoded_len(), c.raw().len());
+
+        let (m, n) = Message::parse(c.raw().to_vec(), &source.keys()).expect("Error parsing request");
+        prop_assert_eq!(n, c.len());
+        prop_assert_eq!(m, Message::request(req));
+    }
+
+    #[test]
+    fn response_round_trip(
+        request_id in any::<u16>(),
+        body in response_body(),
+    ) {
+        let (source, target) = test_utils::service_pair();
+
+        let resp = Response::new(source.id(), request_id, body, Flags::empty()).with_public_key(source.public_key());
+
+        let c = source.encode_response(&resp, &target.keys(), vec![0u8; 4096]).expect("Error encoding response");
+        prop_assert_eq!(c.header().encoded_len(), c.raw().len());
+
+        let (m, n) = Message::parse(c.raw().to_vec(), &source.keys()).expect("Error parsing response");
+        prop_assert_eq!(n, c.len());
+        prop_assert_eq!(m, Message::response(resp));
+    }
+}
@@ -0,0 +1,263 @@
+//! Deterministic test vectors, providing canonical byte-exact encodings of each object type
+//! for validating other (C, embedded) implementations, enabled via the `testvectors` feature.
+//!
+//! Vectors are generated from the [`test_utils`](crate::test_utils) fixture keys with fixed
+//! times, and a deterministic RNG (see [`with_deterministic_rng`]) reseeded per vector so
+//! encrypted variants use fixed nonces.
+//!
+//! The deterministic RNG is scoped to the generating thread, so key and nonce generation
+//! on other threads is unaffected.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::crypto::with_deterministic_rng;
+use crate::error::Error;
+use crate::keys::Keys;
+use crate::net::{Message, Request, RequestBody, Response, ResponseBody, Status, StatusDetail, SubscribeFilter};
+use crate::options::Options;
+use crate::service::{DataOptions, Net, Publisher, Registry, SecondaryOptions, Service, ServiceBuilder, TertiaryOptions};
+use crate::test_utils::{self, TestKeySource, FIXTURE_EXPIRY, FIXTURE_ISSUED, FIXTURE_REQUEST_ID, PEER_SEED, SERVICE_SEED};
+use crate::types::*;
+
+use super::Container;
+
+/// Base seed for the deterministic RNG, offset by the vector index
+pub const TEST_VECTOR_SEED: u64 = 0x6473_662d_7465_7374;
+
+/// Buffer length used when encoding vectors
+const VECTOR_BUFF_LEN: usize = 2048;
+
+/// Canonical encoding of an object
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestVector {
+    /// Vector name, for example `primary-encrypted` or `request-hello-symmetric`
+    pub name: String,
+    /// Encoded object
+    pub data: Vec<u8>,
+}
+
+impl TestVector {
+    /// Parse (and verify) the encoded object using the fixture keys
+    pub fn parse(&self) -> Result<Container<Vec<u8>>, Error> {
+        let (c, _n) = Container::checked(self.data.clone())?;
+        let header = c.header();
+
+        // Symmetric mode messages are keyed by the receiving peer
+        if header.flags().contains(Flags::SYMMETRIC_MODE) {
+            let keys = match header.kind().is_request() {
+                true => symmetric_keys(PEER_SEED, SERVICE_SEED),
+                false => symmetric_keys(SERVICE_SEED, PEER_SEED),
+            };
+            return Container::parse(self.data.clone(), &keys);
+        }
+
+        Container::parse(self.data.clone(), &TestKeySource::default())
+    }
+
+    /// Parse (and verify) the encoded object as a message using the fixture keys
+    pub fn parse_message(&self) -> Result<Message, Error> {
+        let c = self.parse()?;
+        Message::convert(c, &TestKeySource::default())
+    }
+}
+
+/// Display as `name: hex` for export
+impl fmt::Display for TestVector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.name)?;
+        for b in &self.data {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Derive fixture symmetric keys for the local service and remote peer seeds
+fn symmetric_keys(local: u8, remote: u8) -> Keys {
+    let remote = test_utils::keys(remote).pub_key.unwrap();
+    test_utils::keys(local).derive_peer(remote).unwrap()
+}
+
+/// Vector generator, reseeding the deterministic RNG per vector
+struct Generator {
+    vectors: Vec<TestVector>,
+}
+
+impl Generator {
+    fn push<F>(&mut self, name: &str, f: F) -> Result<(), Error>
+    where
+        F: FnOnce() -> Result<Vec<u8>, Error>,
+    {
+        let data = with_deterministic_rng(TEST_VECTOR_SEED + self.vectors.len() as u64, f)?;
+
+        self.vectors.push(TestVector{ name: name.into(), data });
+
+        Ok(())
+    }
+}
+
+/// Generate canonical test vectors for each object type
+pub fn generate() -> Result<Vec<TestVector>, Error> {
+    let mut g = Generator{ vectors: Vec::new() };
+    generate_into(&mut g)?;
+
+    Ok(g.vectors)
+}
+
+fn generate_into(g: &mut Generator) -> Result<(), Error> {
+    let issued = DateTime::from_secs(FIXTURE_ISSUED);
+    let expiry = DateTime::from_secs(FIXTURE_EXPIRY);
+
+    let (mut s, mut p) = test_utils::service_pair();
+    let mut es: Service = ServiceBuilder::generic().encrypt().keys(test_utils::keys(SERVICE_SEED)).build()?;
+
+    let primary = test_utils::primary_page(&mut s);
+    let body: &[u8] = &[0x01, 0x02, 0x03, 0x04];
+
+    // Pages and data objects
+    g.push("primary", || Ok(primary.raw().to_vec()))?;
+    g.push("primary-encrypted", || {
+        let (_n, c) = es.publish_primary(test_utils::primary_options(), vec![0u8; VECTOR_BUFF_LEN])?;
+        Ok(c.raw().to_vec())
+    })?;
+    g.push("secondary", || {
+        let opts = SecondaryOptions{ issued: Some(issued), expiry: Some(expiry), body: Some(body), ..Default::default() };
+        let (_n, c) = p.publish_secondary(&s.id(), opts, vec![0u8; VECTOR_BUFF_LEN])?;
+        Ok(c.raw().to_vec())
+    })?;
+    g.push("tertiary", || {
+        let opts = TertiaryOptions{ index: 0, issued, expiry };
+        let (_n, c) = s.publish_service_link(p.id(), &Options::name("test"), opts, vec![0u8; VECTOR_BUFF_LEN])?;
+        Ok(c.raw().to_vec())
+    })?;
+
+    let data_opts = DataOptions{ issued: Some(issued), body: Some(body), ..Default::default() };
+    let (_n, data) = s.publish_data(data_opts.clone(), vec![0u8; VECTOR_BUFF_LEN])?;
+    g.push("data", || Ok(data.raw().to_vec()))?;
+    g.push("data-encrypted", || {
+        let (_n, c) = es.publish_data(data_opts, vec![0u8; VECTOR_BUFF_LEN])?;
+        Ok(c.raw().to_vec())
+    })?;
+
+    let (primary, data) = (primary.to_owned(), data.to_owned());
+    let addr = Address::new(Ip::V4([192, 168, 1, 2]), 10100);
+    let entry = s.peer_entry(vec![addr], issued)?;
+
+    // Requests (service to peer)
+    let requests = [
+        ("hello", RequestBody::Hello),
+        ("ping", RequestBody::Ping),
+        ("find-node", RequestBody::FindNode(p.id())),
+        ("find-value", RequestBody::FindValue(p.id())),
+        ("store", RequestBody::Store(s.id(), vec![primary.clone()])),
+        ("locate", RequestBody::Locate(p.id())),
+        ("subscribe", RequestBody::Subscribe(p.id())),
+        ("subscribe-filtered", RequestBody::SubscribeFiltered(p.id(), SubscribeFilter::default().with_data_kind(1).with_min_index(10))),
+        ("unsubscribe", RequestBody::Unsubscribe(p.id())),
+        ("query", RequestBody::Query(p.id())),
+        ("push-data", RequestBody::PushData(s.id(), vec![data.clone()])),
+        ("register", RequestBody::Register(s.id(), vec![primary.clone()])),
+        ("unregister", RequestBody::Unregister(s.id())),
+        ("discover", RequestBody::Discover(vec![], vec![Options::kind("sensor")])),
+        ("peer-exchange", RequestBody::PeerExchange(vec![entry.clone()])),
+    ];
+
+    let sym_keys = symmetric_keys(SERVICE_SEED, PEER_SEED);
+    for (name, body) in requests.iter() {
+        let req = Request::new(s.id(), FIXTURE_REQUEST_ID, body.clone(), Flags::empty());
+        g.push(&alloc::format!("request-{}", name), || {
+            Ok(s.encode_request(&req, &p.keys(), vec![0u8; VECTOR_BUFF_LEN])?.raw().to_vec())
+        })?;
+
+        let req = Request::new(s.id(), FIXTURE_REQUEST_ID, body.clone(), Flags::SYMMETRIC_MODE | Flags::ENCRYPTED);
+        g.push(&alloc::format!("request-{}-symmetric", name), || {
+            Ok(s.encode_request(&req, &sym_keys, vec![0u8; VECTOR_BUFF_LEN])?.raw().to_vec())
+        })?;
+    }
+
+    // Responses (peer to service)
+    let responses = [
+        ("status", ResponseBody::Status(Status::Ok)),
+        ("status-detail", ResponseBody::StatusDetail(Status::Failed, StatusDetail::default().with_retry_after(30))),
+        ("nodes-found", ResponseBody::NodesFound(s.id(), vec![(p.id(), addr, p.public_key())])),
+        ("values-found", ResponseBody::ValuesFound(s.id(), vec![primary.clone()])),
+        ("no-result", ResponseBody::NoResult),
+        ("pull-data", ResponseBody::PullData(s.id(), vec![data.clone()])),
+        ("peer-exchange", ResponseBody::PeerExchange(vec![entry])),
+        ("registered", ResponseBody::Registered(s.id(), vec![primary.signature()])),
+        ("discovered", ResponseBody::Discovered(vec![(s.id(), vec![Options::kind("sensor")])])),
+        ("located", ResponseBody::Located(s.id(), vec![primary.clone()])),
+    ];
+
+    let sym_keys = symmetric_keys(PEER_SEED, SERVICE_SEED);
+    for (name, body) in responses.iter() {
+        let resp = Response::new(p.id(), FIXTURE_REQUEST_ID, body.clone(), Flags::empty());
+        g.push(&alloc::format!("response-{}", name), || {
+            Ok(p.encode_response(&resp, &s.keys(), vec![0u8; VECTOR_BUFF_LEN])?.raw().to_vec())
+        })?;
+
+        let resp = Response::new(p.id(), FIXTURE_REQUEST_ID, body.clone(), Flags::SYMMETRIC_MODE | Flags::ENCRYPTED);
+        g.push(&alloc::format!("response-{}-symmetric", name), || {
+            Ok(p.encode_response(&resp, &sym_keys, vec![0u8; VECTOR_BUFF_LEN])?.raw().to_vec())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Verify the provided vectors against freshly generated encodings,
+/// returning the names of any mismatched (or unknown) vectors followed by
+/// the names of generated vectors missing from the input
+pub fn verify(vectors: &[TestVector]) -> Result<Vec<String>, Error> {
+    let expected = generate()?;
+
+    let mismatched = vectors.iter()
+        .filter(|v| !expected.iter().any(|e| e.name == v.name && e.data == v.data))
+        .map(|v| v.name.clone());
+
+    let missing = expected.iter()
+        .filter(|e| !vectors.iter().any(|v| v.name == e.name))
+        .map(|e| e.name.clone());
+
+    Ok(mismatched.chain(missing).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generate_verify_vectors() {
+        let vectors = generate().expect("Failed to generate test vectors");
+
+        // Each vector parses and verifies with the fixture keys
+        for v in vectors.iter() {
+            let c = v.parse().unwrap_or_else(|e| panic!("Failed to parse vector {}: {:?}", v.name, e));
+            let kind = c.header().kind();
+            if kind.is_request() || kind.is_response() {
+                v.parse_message().unwrap_or_else(|e| panic!("Failed to convert vector {}: {:?}", v.name, e));
+            }
+        }
+
+        // All vectors (including encrypted and symmetric variants) are byte-exact across runs
+        assert!(vectors.iter().any(|v| v.name == "primary-encrypted"));
+        assert!(vectors.iter().any(|v| v.name.ends_with("-symmetric")));
+        assert_eq!(generate(), Ok(vectors.clone()));
+        assert_eq!(verify(&vectors), Ok(vec![]));
+
+        // Modified vectors are reported
+        let mut m = vectors.clone();
+        let n = m[0].data.len();
+        m[0].data[n - 1] ^= 0xff;
+        assert_eq!(verify(&m), Ok(vec![String::from("primary")]));
+
+        // As are missing vectors
+        let missing: Vec<_> = vectors.iter().skip(1).map(|v| v.name.clone()).collect();
+        assert_eq!(verify(&vectors[..1]), Ok(missing));
+        assert_eq!(verify(&vectors[1..]), Ok(vec![String::from("primary")]));
+
+        assert!(vectors[0].to_string().starts_with("primary: "));
+    }
+}
@@ -0,0 +1,78 @@
+//! Protocol versions supported by this implementation, with per-version decoding.
+//!
+//! Objects carry a protocol version in the fixed header, which is checked when objects
+//! are parsed (see [`check`](super::header::check)). Header checks are dispatched by [`Version`]
+//! so later layouts may be supported alongside earlier encodings, with peers selecting a
+//! common version via [`negotiate`].
+
+use core::convert::TryFrom;
+
+use crate::error::Error;
+
+/// Protocol version produced by this implementation
+pub const PROTOCOL_VERSION: u16 = Version::V0 as u16;
+
+/// Protocol versions accepted by this implementation, in order of preference
+pub const SUPPORTED_VERSIONS: &[Version] = &[Version::V0];
+
+/// Protocol version registry
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[repr(u16)]
+pub enum Version {
+    /// Initial protocol version, 16 byte header with u16 section lengths
+    V0 = 0,
+}
+
+impl Version {
+    /// Current (default) protocol version
+    pub const CURRENT: Version = Version::V0;
+
+    /// Check whether a raw protocol version is supported
+    pub fn is_supported(v: u16) -> bool {
+        Version::try_from(v).is_ok()
+    }
+}
+
+impl TryFrom<u16> for Version {
+    type Error = Error;
+
+    fn try_from(v: u16) -> Result<Self, Self::Error> {
+        SUPPORTED_VERSIONS.iter()
+            .find(|s| **s as u16 == v)
+            .copied()
+            .ok_or(Error::UnsupportedVersion)
+    }
+}
+
+impl From<Version> for u16 {
+    fn from(v: Version) -> u16 {
+        v as u16
+    }
+}
+
+/// Select the preferred protocol version supported by both peers from the
+/// raw versions advertised by the remote peer
+pub fn negotiate(remote: &[u16]) -> Result<Version, Error> {
+    SUPPORTED_VERSIONS.iter()
+        .find(|v| remote.contains(&(**v as u16)))
+        .copied()
+        .ok_or(Error::UnsupportedVersion)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn version_registry() {
+        assert_eq!(Version::try_from(PROTOCOL_VERSION), Ok(Version::CURRENT));
+        assert_eq!(Version::try_from(PROTOCOL_VERSION + 1), Err(Error::UnsupportedVersion));
+        assert!(Version::is_supported(0));
+
+        assert_eq!(negotiate(&[3, 2, 0]), Ok(Version::V0));
+        assert_eq!(negotiate(&[3, 2]), Err(Error::UnsupportedVersion));
+        assert_eq!(negotiate(&[]), Err(Error::UnsupportedVersion));
+    }
+}